@@ -0,0 +1,128 @@
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use pixl_core::PixelBook;
+use pixl_format::convert::{self, DEFAULT_FRAME_DELAY_MS};
+use pixl_format::{Limits, PxlReader, PxlWriter};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+#[derive(Args)]
+pub struct ConvertArgs {
+    /// Input files: a single .pxl book, or GIF/PNG images that become frames in the order given
+    #[arg(required = true)]
+    pub inputs: Vec<PathBuf>,
+
+    /// Output file; its extension (.pxl, .png or .gif) selects the target format.
+    /// Multi-frame books written as PNG produce numbered files (name_000.png, ...)
+    #[arg(short, long)]
+    pub output: PathBuf,
+
+    /// Only write this frame (0-based) when converting to PNG
+    #[arg(short, long)]
+    pub frame: Option<usize>,
+
+    /// Delay between frames in milliseconds when converting to GIF
+    #[arg(long, default_value_t = DEFAULT_FRAME_DELAY_MS)]
+    pub delay: u32,
+}
+
+pub fn run(args: ConvertArgs) -> Result<()> {
+    match extension(&args.output).as_str() {
+        "pxl" => images_to_book(&args),
+        "png" => book_to_png(&args),
+        "gif" => book_to_gif(&args),
+        other => bail!("Unsupported output format '{}' (expected pxl, png or gif)", other),
+    }
+}
+
+fn images_to_book(args: &ConvertArgs) -> Result<()> {
+    let mut images = Vec::new();
+    let limits = Limits::default();
+
+    for input in &args.inputs {
+        let reader = BufReader::new(File::open(input)
+            .with_context(|| format!("Failed to open {}", input.display()))?);
+
+        match extension(input).as_str() {
+            "gif" => images.extend(convert::read_gif(reader, &limits)
+                .with_context(|| format!("Failed to decode {}", input.display()))?),
+            "png" => images.push(convert::read_image(reader, &limits)
+                .with_context(|| format!("Failed to decode {}", input.display()))?),
+            other => bail!("Cannot import '{}' files into a pixel book", other),
+        }
+    }
+
+    let filename = args.output.file_name().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let book = convert::book_from_images(&filename, &images)?;
+    let file = File::create(&args.output)
+        .with_context(|| format!("Failed to create {}", args.output.display()))?;
+    PxlWriter::write_book(BufWriter::new(file), &book)?;
+
+    println!(
+        "Wrote {} ({}x{}, {} frames)",
+        args.output.display(), book.width, book.height, book.frames.len()
+    );
+    Ok(())
+}
+
+fn book_to_png(args: &ConvertArgs) -> Result<()> {
+    let book = load_single_book(&args.inputs)?;
+
+    let frames: Vec<_> = match args.frame {
+        Some(index) => {
+            if index >= book.frames.len() {
+                bail!("Frame {} out of range (book has {} frames)", index, book.frames.len());
+            }
+            vec![(&book.frames[index], args.output.clone())]
+        }
+        None if book.frames.len() == 1 => vec![(&book.frames[0], args.output.clone())],
+        None => book.frames.iter()
+            .map(|frame| (frame, numbered_path(&args.output, frame.index)))
+            .collect(),
+    };
+
+    for (frame, path) in frames {
+        let file = File::create(&path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        convert::write_png(frame, book.width, book.height, BufWriter::new(file))?;
+        println!("Wrote frame {} to {}", frame.index, path.display());
+    }
+
+    Ok(())
+}
+
+fn book_to_gif(args: &ConvertArgs) -> Result<()> {
+    let book = load_single_book(&args.inputs)?;
+    let file = File::create(&args.output)
+        .with_context(|| format!("Failed to create {}", args.output.display()))?;
+    convert::write_gif(&book, BufWriter::new(file), args.delay)?;
+
+    println!("Wrote {} frames to {}", book.frames.len(), args.output.display());
+    Ok(())
+}
+
+fn load_single_book(inputs: &[PathBuf]) -> Result<PixelBook> {
+    let [input] = inputs else {
+        bail!("Exactly one .pxl input is required when exporting images");
+    };
+    if extension(input) != "pxl" {
+        bail!("{} is not a .pxl pixel book", input.display());
+    }
+
+    let reader = PxlReader::open(input)
+        .with_context(|| format!("Failed to open {}", input.display()))?;
+    Ok(reader.read_book(&input.display().to_string())?)
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+// sprite.png -> sprite_003.png
+fn numbered_path(path: &Path, index: usize) -> PathBuf {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    path.with_file_name(format!("{}_{:03}.png", stem, index))
+}
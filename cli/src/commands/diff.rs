@@ -0,0 +1,137 @@
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use image::{Rgba, RgbaImage};
+use pixl_core::{diff_frames, Frame, FrameDiff, Pixel};
+use pixl_format::PxlReader;
+use std::path::PathBuf;
+
+// Highlight colors for the visual diff panel
+const CHANGED_COLOR: Rgba<u8> = Rgba([255, 0, 80, 255]);
+const BACKGROUND_COLOR: Rgba<u8> = Rgba([32, 32, 32, 255]);
+
+#[derive(Args)]
+pub struct DiffArgs {
+    /// Original pixel book
+    pub before: PathBuf,
+
+    /// Pixel book to compare against the original
+    pub after: PathBuf,
+
+    /// Write a PNG with before / after / highlighted-changes panels for every differing frame
+    #[arg(short, long, value_name = "PNG")]
+    pub image: Option<PathBuf>,
+
+    /// Integer upscale factor for the diff image
+    #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..=32))]
+    pub scale: u32,
+}
+
+/// Prints a per-frame report and returns whether the books differ, so the
+/// caller can exit non-zero the same way `diff(1)` does.
+pub fn run(args: DiffArgs) -> Result<bool> {
+    let before = PxlReader::open(&args.before)
+        .with_context(|| format!("Failed to open {}", args.before.display()))?
+        .read_book(&args.before.display().to_string())?;
+    let after = PxlReader::open(&args.after)
+        .with_context(|| format!("Failed to open {}", args.after.display()))?
+        .read_book(&args.after.display().to_string())?;
+
+    if (before.width, before.height) != (after.width, after.height) {
+        bail!(
+            "Cannot compare books of different sizes: {}x{} vs {}x{}",
+            before.width, before.height, after.width, after.height
+        );
+    }
+
+    let (width, height) = (before.width, before.height);
+    let total_pixels = width as usize * height as usize;
+    let mut differing = Vec::new();
+
+    for (a, b) in before.frames.iter().zip(&after.frames) {
+        let diff = diff_frames(a, b, width, height);
+        if diff.is_empty() {
+            continue;
+        }
+
+        let bounds = diff.bounds.as_ref().unwrap();
+        println!(
+            "frame {}: {} of {} pixels changed, bounds x={} y={} {}x{}",
+            a.index, diff.changed_count(), total_pixels,
+            bounds.x, bounds.y, bounds.width, bounds.height
+        );
+        differing.push((a, b, diff));
+    }
+
+    let common = before.frames.len().min(after.frames.len());
+    for frame in &before.frames[common..] {
+        println!("frame {}: only in {}", frame.index, args.before.display());
+    }
+    for frame in &after.frames[common..] {
+        println!("frame {}: only in {}", frame.index, args.after.display());
+    }
+
+    let changed = !differing.is_empty() || before.frames.len() != after.frames.len();
+    if !changed {
+        println!("Books are identical ({} frames, {}x{})", common, width, height);
+    }
+
+    if let Some(path) = &args.image {
+        if differing.is_empty() {
+            println!("No differing frames, skipping diff image");
+        } else {
+            let image = render_diff_image(&differing, width, height, args.scale);
+            image.save(path)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!("Wrote diff image to {}", path.display());
+        }
+    }
+
+    Ok(changed)
+}
+
+// One row per differing frame: before, after, then the after frame dimmed
+// with every changed pixel painted in the highlight color.
+fn render_diff_image(differing: &[(&Frame, &Frame, FrameDiff)], width: u16, height: u16, scale: u32) -> RgbaImage {
+    let (w, h) = (width as u32, height as u32);
+    let mut image = RgbaImage::from_pixel(w * 3 * scale, h * differing.len() as u32 * scale, BACKGROUND_COLOR);
+
+    for (row, (before, after, diff)) in differing.iter().enumerate() {
+        let top = row as u32 * h;
+        for y in 0..height {
+            for x in 0..width {
+                let a = before.get_pixel(x, y, width).unwrap_or_else(Pixel::transparent);
+                let b = after.get_pixel(x, y, width).unwrap_or_else(Pixel::transparent);
+                let (px, py) = (x as u32, top + y as u32);
+
+                fill_cell(&mut image, px, py, scale, to_rgba(a));
+                fill_cell(&mut image, px + w, py, scale, to_rgba(b));
+                fill_cell(&mut image, px + w * 2, py, scale, dim(b));
+            }
+        }
+        for change in &diff.changes {
+            fill_cell(&mut image, change.x as u32 + w * 2, top + change.y as u32, scale, CHANGED_COLOR);
+        }
+    }
+
+    image
+}
+
+fn fill_cell(image: &mut RgbaImage, x: u32, y: u32, scale: u32, color: Rgba<u8>) {
+    for dy in 0..scale {
+        for dx in 0..scale {
+            image.put_pixel(x * scale + dx, y * scale + dy, color);
+        }
+    }
+}
+
+fn to_rgba(pixel: Pixel) -> Rgba<u8> {
+    Rgba([pixel.r, pixel.g, pixel.b, pixel.a])
+}
+
+// Fade unchanged content towards the background so the highlights stand out
+fn dim(pixel: Pixel) -> Rgba<u8> {
+    let alpha = pixel.a as u32;
+    let blend = |c: u8, bg: u8| ((c as u32 * alpha + bg as u32 * (255 - alpha)) / 255 / 3 + bg as u32 * 2 / 3) as u8;
+    let Rgba([br, bg, bb, _]) = BACKGROUND_COLOR;
+    Rgba([blend(pixel.r, br), blend(pixel.g, bg), blend(pixel.b, bb), 255])
+}
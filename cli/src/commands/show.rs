@@ -0,0 +1,58 @@
+use crate::terminal;
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use pixl_format::PxlReader;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Args)]
+pub struct ShowArgs {
+    /// Pixel book to display
+    pub file: PathBuf,
+
+    /// Frame to display (0-based)
+    #[arg(short, long, default_value_t = 0)]
+    pub frame: usize,
+
+    /// Play every frame as a looping animation until interrupted
+    #[arg(short, long)]
+    pub play: bool,
+
+    /// Playback speed in frames per second
+    #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u32).range(1..=60))]
+    pub fps: u32,
+}
+
+pub fn run(args: ShowArgs) -> Result<()> {
+    let mut reader = PxlReader::open(&args.file)
+        .with_context(|| format!("Failed to open {}", args.file.display()))?;
+    let (width, height, frame_count) = (reader.width(), reader.height(), reader.frame_count());
+    let name = args.file.display().to_string();
+
+    if args.play {
+        let frames = reader.frames().collect::<Result<Vec<_>, _>>()?;
+        let delay = Duration::from_millis(1000 / args.fps as u64);
+        let mut stdout = std::io::stdout().lock();
+
+        write!(stdout, "{}", terminal::CLEAR_SCREEN)?;
+        for frame in frames.iter().cycle() {
+            write!(stdout, "{}", terminal::CURSOR_HOME)?;
+            write!(stdout, "{}", terminal::render_frame(frame, width, height))?;
+            writeln!(stdout, "{} - frame {}/{} ({}x{})", name, frame.index + 1, frame_count, width, height)?;
+            stdout.flush()?;
+            std::thread::sleep(delay);
+        }
+        return Ok(());
+    }
+
+    if args.frame >= frame_count {
+        bail!("Frame {} out of range ({} has {} frames)", args.frame, name, frame_count);
+    }
+
+    let frame = reader.read_frame(args.frame)?;
+    print!("{}", terminal::render_frame(&frame, width, height));
+    println!("{} - frame {}/{} ({}x{})", name, args.frame + 1, frame_count, width, height);
+
+    Ok(())
+}
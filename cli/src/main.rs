@@ -0,0 +1,37 @@
+use clap::{Parser, Subcommand};
+
+mod commands;
+mod terminal;
+
+/// Command-line tools for working with PIXL pixel books
+#[derive(Parser)]
+#[command(name = "pixl", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Render a pixel book in the terminal using truecolor half-block characters
+    Show(commands::show::ShowArgs),
+    /// Compare two pixel books frame by frame (exits with status 1 when they differ)
+    Diff(commands::diff::DiffArgs),
+    /// Convert between pixel books and PNG sequences or GIFs
+    Convert(commands::convert::ConvertArgs),
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Show(args) => commands::show::run(args),
+        Command::Convert(args) => commands::convert::run(args),
+        Command::Diff(args) => {
+            if commands::diff::run(args)? {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+    }
+}
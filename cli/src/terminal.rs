@@ -0,0 +1,88 @@
+// ANSI truecolor rendering of frames using the upper half block character.
+// Each character cell shows two pixels: the foreground colors the top pixel
+// and the background colors the one below it.
+use pixl_core::{Frame, Pixel};
+
+pub const CLEAR_SCREEN: &str = "\x1b[2J";
+pub const CURSOR_HOME: &str = "\x1b[H";
+const RESET: &str = "\x1b[0m";
+const UPPER_HALF_BLOCK: char = '▀';
+
+// Same checkerboard colors the viewer uses behind transparent pixels
+const CHECKER_LIGHT: (u8, u8, u8) = (240, 240, 240);
+const CHECKER_DARK: (u8, u8, u8) = (200, 200, 200);
+const CHECKER_SIZE: u16 = 4;
+
+pub fn render_frame(frame: &Frame, width: u16, height: u16) -> String {
+    let mut output = String::new();
+
+    for y in (0..height).step_by(2) {
+        for x in 0..width {
+            let (tr, tg, tb) = composite(frame.get_pixel(x, y, width), x, y);
+            output.push_str(&format!("\x1b[38;2;{};{};{}m", tr, tg, tb));
+
+            if y + 1 < height {
+                let (br, bg, bb) = composite(frame.get_pixel(x, y + 1, width), x, y + 1);
+                output.push_str(&format!("\x1b[48;2;{};{};{}m", br, bg, bb));
+            } else {
+                // Odd height: leave the lower half as the terminal background
+                output.push_str("\x1b[49m");
+            }
+
+            output.push(UPPER_HALF_BLOCK);
+        }
+        output.push_str(RESET);
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Blend a pixel over the checkerboard so transparency stays visible
+fn composite(pixel: Option<Pixel>, x: u16, y: u16) -> (u8, u8, u8) {
+    let background = if ((x / CHECKER_SIZE) + (y / CHECKER_SIZE)).is_multiple_of(2) {
+        CHECKER_LIGHT
+    } else {
+        CHECKER_DARK
+    };
+
+    let Some(pixel) = pixel else {
+        return background;
+    };
+
+    let alpha = pixel.a as u16;
+    let blend = |fg: u8, bg: u8| ((fg as u16 * alpha + bg as u16 * (255 - alpha)) / 255) as u8;
+
+    (
+        blend(pixel.r, background.0),
+        blend(pixel.g, background.1),
+        blend(pixel.b, background.2),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_two_pixel_rows_per_line() {
+        let mut frame = Frame::new(0, 2, 3);
+        frame.set_pixel(0, 0, 2, Pixel::new(255, 0, 0, 255));
+        frame.set_pixel(0, 1, 2, Pixel::new(0, 0, 255, 255));
+
+        let output = render_frame(&frame, 2, 3);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].matches(UPPER_HALF_BLOCK).count(), 2);
+        assert!(lines[0].starts_with("\x1b[38;2;255;0;0m\x1b[48;2;0;0;255m"));
+        // Last line of an odd-height frame uses the default background
+        assert!(lines[1].contains("\x1b[49m"));
+    }
+
+    #[test]
+    fn test_transparent_pixels_show_checkerboard() {
+        assert_eq!(composite(Some(Pixel::transparent()), 0, 0), CHECKER_LIGHT);
+        assert_eq!(composite(Some(Pixel::transparent()), 4, 0), CHECKER_DARK);
+        assert_eq!(composite(Some(Pixel::new(10, 20, 30, 255)), 4, 0), (10, 20, 30));
+    }
+}
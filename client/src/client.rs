@@ -0,0 +1,803 @@
+use crate::error::{ClientError, Result};
+use crate::events::EventStream;
+use crate::stream::BookStream;
+use pixl_core::{filename_segment, ActiveSelection, BatchRequest, BatchResult, BookDetails, BookFilter, BookList, ColorGrid, CreatePixelBookRequest, CreateSnapshotRequest, DiffQuery, DrawingContext, EncodedFrame, FrameComparison, FrameDuration, FrameEncoding, FrameStats, OperationLogEntry, FrameRange, Palette, PixelBookEvent, ImportUrlRequest, LockRequest, LockResponse, MigrateRequest, MigrateResult, PixelBook, PixelBookInfo, PixelColor, QuantizeRequest, QuantizeResult, Rect, ResizeRequest, RenameBookRequest, ScanReport, ServerStatus, SetPermissionsRequest, SetSymmetryRequest, SetTilesRequest, SnapshotInfo, Symmetry, TemplateInfo, CreateFromTemplateRequest, TilePlacement, Tilemap, TilemapRequest, TilemapSummary, Tileset, TilesetInfo, TrashEntry, UpdateDetailsRequest, UpdatePixelBookRequest, OperationResult, ValidationReport};
+use chrono::{DateTime, SecondsFormat, Utc};
+use pixl_format::embedded::{CodeLanguage, ColorFormat, EmbeddedOptions};
+use reqwest::{Client, RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+struct TrashResponse {
+    books: Vec<TrashEntry>,
+}
+
+#[derive(Deserialize)]
+struct OperationLogResponse {
+    entries: Vec<OperationLogEntry>,
+}
+
+#[derive(Deserialize)]
+struct EventHistoryResponse {
+    events: Vec<PixelBookEvent>,
+}
+
+#[derive(Deserialize)]
+struct TemplatesResponse {
+    templates: Vec<TemplateInfo>,
+}
+
+#[derive(Deserialize)]
+struct TilemapsResponse {
+    tilemaps: Vec<TilemapSummary>,
+}
+
+#[derive(Deserialize)]
+struct SnapshotsResponse {
+    snapshots: Vec<SnapshotInfo>,
+}
+
+#[derive(Deserialize)]
+struct RestoreSnapshotResponse {
+    snapshot: SnapshotInfo,
+}
+
+#[derive(Deserialize)]
+struct MaintenanceStatus {
+    last_scan: Option<ScanReport>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PathBody {
+    path: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateBookResponse {
+    pub success: bool,
+    pub filename: String,
+    pub path: String,
+}
+
+/// A book's size after a resize or crop
+#[derive(Debug, Clone, Deserialize)]
+pub struct CanvasResponse {
+    pub success: bool,
+    pub filename: String,
+    pub width: u16,
+    pub height: u16,
+    pub frames: usize,
+    pub revision: u64,
+}
+
+/// A book's size after a trim, with the offset of the kept area in the old canvas
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrimResponse {
+    pub success: bool,
+    pub filename: String,
+    pub width: u16,
+    pub height: u16,
+    pub frames: usize,
+    pub revision: u64,
+    pub x: u16,
+    pub y: u16,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PermissionsResponse {
+    pub success: bool,
+    pub filename: String,
+    pub read_only: bool,
+    pub has_owner: bool,
+}
+
+/// Header the server checks against a book's owner key
+pub const OWNER_KEY_HEADER: &str = "X-Pixl-Owner-Key";
+
+/// Response header with a book's total frame count
+pub const FRAME_COUNT_HEADER: &str = "X-Pixl-Frame-Count";
+
+/// Header carrying the token of a lock this client holds
+pub const LOCK_TOKEN_HEADER: &str = "X-Pixl-Lock-Token";
+
+/// Header naming the session whose drawing context requests use
+pub const SESSION_HEADER: &str = "X-Pixl-Session";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateBookResponse {
+    pub success: bool,
+    pub operations_applied: usize,
+    pub filename: String,
+    /// The book's revision after the update
+    #[serde(default)]
+    pub revision: u64,
+    /// What became of each operation, in request order
+    #[serde(default)]
+    pub results: Vec<OperationResult>,
+}
+
+/// Result of `undo` or `redo`
+#[derive(Debug, Clone, Deserialize)]
+pub struct HistoryResponse {
+    pub success: bool,
+    pub filename: String,
+    /// Frames whose pixels changed
+    pub frames: Vec<usize>,
+    pub pixels_changed: usize,
+    pub undo_remaining: usize,
+    pub redo_remaining: usize,
+}
+
+/// Typed client for the PIXL server REST API
+#[derive(Clone)]
+pub struct PixlClient {
+    client: Client,
+    base_url: String,
+    owner_key: Option<String>,
+    lock_token: Option<String>,
+    session: Option<String>,
+}
+
+impl PixlClient {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            client: Client::builder()
+                .user_agent(concat!("pixl-client/", env!("CARGO_PKG_VERSION")))
+                .build()
+                .expect("default HTTP client"),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            owner_key: None,
+            lock_token: None,
+            session: None,
+        }
+    }
+
+    /// Sends `key` with every modifying request, for books protected by an owner key
+    pub fn with_owner_key(mut self, key: impl Into<String>) -> Self {
+        self.owner_key = Some(key.into());
+        self
+    }
+
+    /// Sends `token` with every modifying request, so writes pass a lock from [`lock_book`](Self::lock_book)
+    pub fn with_lock_token(mut self, token: impl Into<String>) -> Self {
+        self.lock_token = Some(token.into());
+        self
+    }
+
+    /// Draws in the named session, with its own drawing context, rather than
+    /// the one shared by clients that name none
+    pub fn with_session(mut self, session: impl Into<String>) -> Self {
+        self.session = Some(session.into());
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub async fn health_check(&self) -> Result<bool> {
+        let response = self.client.get(self.url("/")).send().await?;
+        Ok(response.status().is_success())
+    }
+
+    /// Uptime, workspace size and connected clients of the server
+    pub async fn status(&self) -> Result<ServerStatus> {
+        let response = check(self.client.get(self.url("/status")).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    pub async fn get_path(&self) -> Result<String> {
+        let response = check(self.client.get(self.url("/path")).send().await?).await?;
+        Ok(response.json::<PathBody>().await?.path)
+    }
+
+    pub async fn set_path(&self, path: &str) -> Result<String> {
+        let body = PathBody { path: path.to_string() };
+        let response = check(self.client.put(self.url("/path")).json(&body).send().await?).await?;
+        Ok(response.json::<PathBody>().await?.path)
+    }
+
+    pub async fn list_books(&self) -> Result<Vec<PixelBookInfo>> {
+        Ok(self.search_books(&BookFilter::default()).await?.books)
+    }
+
+    /// One page of the books matching `filter`, with the number of matches
+    pub async fn search_books(&self, filter: &BookFilter) -> Result<BookList> {
+        let response = check(self.client.get(self.url("/books")).query(filter).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    pub async fn get_book(&self, filename: &str) -> Result<PixelBook> {
+        let url = self.url(&format!("/books/{}", filename_segment(filename)));
+        let response = check(self.client.get(url).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Only the frames in `range`, plus the book's total frame count
+    pub async fn get_book_frames(&self, filename: &str, range: &FrameRange) -> Result<(PixelBook, usize)> {
+        let url = self.url(&format!("/books/{}", filename_segment(filename)));
+        let response = check(self.client.get(url).query(&[("frames", range.to_string())]).send().await?).await?;
+        let frame_count = response.headers().get(FRAME_COUNT_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok());
+        let book: PixelBook = response.json().await?;
+        let frame_count = frame_count.unwrap_or(book.frames.len());
+        Ok((book, frame_count))
+    }
+
+    /// Streams the book frame by frame; `range` limits which frames are sent
+    pub async fn stream_book(&self, filename: &str, range: Option<&FrameRange>) -> Result<BookStream> {
+        let url = self.url(&format!("/books/{}/stream", filename_segment(filename)));
+        let mut builder = self.client.get(url);
+        if let Some(range) = range {
+            builder = builder.query(&[("frames", range.to_string())]);
+        }
+        Ok(BookStream::new(check(builder.send().await?).await?))
+    }
+
+    /// Frame `index` as rows of hex colors, shrunk to fit in `max_size` cells when given
+    /// Color histogram, visible pixel count and content bounds of frame
+    /// `index`, keeping the `top` most used colors when given
+    pub async fn frame_stats(&self, filename: &str, index: usize, top: Option<usize>) -> Result<FrameStats> {
+        let url = self.url(&format!("/books/{}/frames/{}/stats", filename_segment(filename), index));
+        let mut builder = self.client.get(url);
+        if let Some(top) = top {
+            builder = builder.query(&[("top", top)]);
+        }
+        Ok(check(builder.send().await?).await?.json().await?)
+    }
+
+    pub async fn frame_grid(&self, filename: &str, index: usize, max_size: Option<u16>) -> Result<ColorGrid> {
+        let url = self.url(&format!("/books/{}/frames/{}/grid", filename_segment(filename), index));
+        let mut builder = self.client.get(url);
+        if let Some(max_size) = max_size {
+            builder = builder.query(&[("max_size", max_size)]);
+        }
+        Ok(check(builder.send().await?).await?.json().await?)
+    }
+
+    /// The color of one pixel
+    pub async fn get_pixel(&self, filename: &str, index: usize, x: u16, y: u16) -> Result<PixelColor> {
+        let url = self.url(&format!("/books/{}/frames/{}/pixels/{}/{}", filename_segment(filename), index, x, y));
+        Ok(check(self.client.get(url).send().await?).await?.json().await?)
+    }
+
+    /// One frame as run-length text or base64 RGBA
+    pub async fn get_frame(&self, filename: &str, index: usize, encoding: FrameEncoding) -> Result<EncodedFrame> {
+        let url = self.url(&format!("/books/{}/frames/{}", filename_segment(filename), index));
+        let builder = self.client.get(url).query(&[("encoding", encoding)]);
+        Ok(check(builder.send().await?).await?.json().await?)
+    }
+
+    /// Pixels that differ between two frames, of the live book or its snapshots
+    pub async fn diff(&self, filename: &str, query: &DiffQuery) -> Result<FrameComparison> {
+        let url = self.url(&format!("/books/{}/diff", filename_segment(filename)));
+        Ok(check(self.client.get(url).query(query).send().await?).await?.json().await?)
+    }
+
+    pub async fn create_book(&self, request: &CreatePixelBookRequest) -> Result<CreateBookResponse> {
+        let builder = self.authorized(self.client.post(self.url("/books")));
+        let response = check(builder.json(request).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Moves a book to `new_filename`, keeping its undo history and operation log
+    pub async fn rename_book(&self, filename: &str, new_filename: &str) -> Result<()> {
+        let url = self.url(&format!("/books/{}/rename", filename_segment(filename)));
+        let body = RenameBookRequest { new_filename: new_filename.to_string() };
+        check(self.authorized(self.client.post(url)).json(&body).send().await?).await?;
+        Ok(())
+    }
+
+    /// Duplicates a book as `new_filename`
+    pub async fn copy_book(&self, filename: &str, new_filename: &str) -> Result<()> {
+        let url = self.url(&format!("/books/{}/copy", filename_segment(filename)));
+        let body = RenameBookRequest { new_filename: new_filename.to_string() };
+        check(self.client.post(url).json(&body).send().await?).await?;
+        Ok(())
+    }
+
+    /// Every frame as `frame_NNN.png` entries of a ZIP archive
+    pub async fn export_zip(&self, filename: &str) -> Result<Vec<u8>> {
+        let url = self.url(&format!("/books/{}/export.zip", filename_segment(filename)));
+        let response = check(self.client.get(url).send().await?).await?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// ZIP with a sprite sheet PNG and its Aseprite JSON; `columns` frames per row
+    pub async fn export_aseprite(&self, filename: &str, columns: Option<u32>) -> Result<Vec<u8>> {
+        let url = self.url(&format!("/books/{}/export/aseprite", filename_segment(filename)));
+        let mut builder = self.client.get(url);
+        if let Some(columns) = columns {
+            builder = builder.query(&[("columns", columns)]);
+        }
+        Ok(check(builder.send().await?).await?.bytes().await?.to_vec())
+    }
+
+    /// The Aseprite JSON of [`PixlClient::export_aseprite`] on its own
+    pub async fn export_aseprite_json(&self, filename: &str, columns: Option<u32>) -> Result<serde_json::Value> {
+        let url = self.url(&format!("/books/{}/export/aseprite-json", filename_segment(filename)));
+        let mut builder = self.client.get(url);
+        if let Some(columns) = columns {
+            builder = builder.query(&[("columns", columns)]);
+        }
+        Ok(check(builder.send().await?).await?.json().await?)
+    }
+
+    /// The sprite sheet PNG described by [`PixlClient::export_aseprite_json`]
+    pub async fn export_aseprite_png(&self, filename: &str, columns: Option<u32>) -> Result<Vec<u8>> {
+        let url = self.url(&format!("/books/{}/export/aseprite-png", filename_segment(filename)));
+        let mut builder = self.client.get(url);
+        if let Some(columns) = columns {
+            builder = builder.query(&[("columns", columns)]);
+        }
+        Ok(check(builder.send().await?).await?.bytes().await?.to_vec())
+    }
+
+    /// Frame `frame` as a PNG
+    pub async fn export_png(&self, filename: &str, frame: usize) -> Result<Vec<u8>> {
+        let url = self.url(&format!("/books/{}/export/png", filename_segment(filename)));
+        let response = check(self.client.get(url).query(&[("frame", frame)]).send().await?).await?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// Frame `frame` enlarged `scale` times (8 by default) over `background`:
+    /// `checker` (the default), `none` or a `#rrggbb` color
+    pub async fn preview(&self, filename: &str, frame: usize, scale: Option<u32>, background: Option<&str>) -> Result<Vec<u8>> {
+        let url = self.url(&format!("/books/{}/preview", filename_segment(filename)));
+        let mut builder = self.client.get(url).query(&[("frame", frame)]);
+        if let Some(scale) = scale {
+            builder = builder.query(&[("scale", scale)]);
+        }
+        if let Some(background) = background {
+            builder = builder.query(&[("background", background)]);
+        }
+        Ok(check(builder.send().await?).await?.bytes().await?.to_vec())
+    }
+
+    /// Frame 0 shrunk to fit in `size` x `size` (64 by default) as a PNG
+    pub async fn thumbnail(&self, filename: &str, size: Option<u16>) -> Result<Vec<u8>> {
+        let url = self.url(&format!("/books/{}/thumbnail", filename_segment(filename)));
+        let mut builder = self.client.get(url);
+        if let Some(size) = size {
+            builder = builder.query(&[("size", size)]);
+        }
+        Ok(check(builder.send().await?).await?.bytes().await?.to_vec())
+    }
+
+    /// Every frame as a looping GIF, `delay_ms` apart (100 by default)
+    pub async fn export_gif(&self, filename: &str, delay_ms: Option<u32>) -> Result<Vec<u8>> {
+        let url = self.url(&format!("/books/{}/export/gif", filename_segment(filename)));
+        let mut builder = self.client.get(url);
+        if let Some(delay_ms) = delay_ms {
+            builder = builder.query(&[("delay_ms", delay_ms)]);
+        }
+        Ok(check(builder.send().await?).await?.bytes().await?.to_vec())
+    }
+
+    /// Source arrays or raw framebuffer bytes for microcontroller displays
+    pub async fn export_embedded(&self, filename: &str, options: &EmbeddedOptions) -> Result<Vec<u8>> {
+        let url = self.url(&format!("/books/{}/export/embedded", filename_segment(filename)));
+        let response = check(self.client.get(url).query(options).send().await?).await?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// A C header or Rust module with frame `frame` (every frame when `None`)
+    /// as `const` arrays in `format`
+    pub async fn export_code(&self, filename: &str, lang: CodeLanguage, frame: Option<usize>, format: ColorFormat) -> Result<String> {
+        let url = self.url(&format!("/books/{}/export/code", filename_segment(filename)));
+        let mut builder = self.client.get(url).query(&[("lang", lang)]).query(&[("format", format)]);
+        if let Some(frame) = frame {
+            builder = builder.query(&[("frame", frame)]);
+        }
+        Ok(check(builder.send().await?).await?.text().await?)
+    }
+
+    /// Raw framebuffer bytes of frame `frame` (every frame when `None`) in `format`
+    pub async fn export_raw(&self, filename: &str, frame: Option<usize>, format: ColorFormat) -> Result<Vec<u8>> {
+        let url = self.url(&format!("/books/{}/export/raw", filename_segment(filename)));
+        let mut builder = self.client.get(url).query(&[("format", format)]);
+        if let Some(frame) = frame {
+            builder = builder.query(&[("frame", frame)]);
+        }
+        Ok(check(builder.send().await?).await?.bytes().await?.to_vec())
+    }
+
+    /// Has the server download a PNG or GIF and save it as a book
+    pub async fn import_url(&self, request: &ImportUrlRequest) -> Result<CreateBookResponse> {
+        let builder = self.authorized(self.client.post(self.url("/books/import-url")));
+        let response = check(builder.json(request).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Built-in templates plus any in the server's templates directory
+    pub async fn list_templates(&self) -> Result<Vec<TemplateInfo>> {
+        let response = check(self.client.get(self.url("/templates")).send().await?).await?;
+        Ok(response.json::<TemplatesResponse>().await?.templates)
+    }
+
+    /// Creates a book with the contents of a template
+    pub async fn create_from_template(&self, request: &CreateFromTemplateRequest) -> Result<CreateBookResponse> {
+        let builder = self.authorized(self.client.post(self.url("/books/from-template")));
+        let response = check(builder.json(request).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    pub async fn update_book(&self, filename: &str, request: &UpdatePixelBookRequest) -> Result<UpdateBookResponse> {
+        let url = self.url(&format!("/books/{}", filename_segment(filename)));
+        let response = check(self.authorized(self.client.put(url)).json(request).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Like [`PixlClient::update_book`], but operations that fail are
+    /// skipped instead of failing the whole request. Check
+    /// [`UpdateBookResponse::results`] for the ones that did not apply.
+    pub async fn update_book_continuing(&self, filename: &str, request: &UpdatePixelBookRequest) -> Result<UpdateBookResponse> {
+        let url = self.url(&format!("/books/{}", filename_segment(filename)));
+        let builder = self.authorized(self.client.put(url)).query(&[("continue_on_error", "true")]);
+        let response = check(builder.json(request).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Like [`PixlClient::update_book`], but only if the book is still at
+    /// `revision` (see [`PixelBook::revision`]). Otherwise the server answers
+    /// 412 Precondition Failed and nothing is applied.
+    pub async fn update_book_at(&self, filename: &str, revision: u64, request: &UpdatePixelBookRequest) -> Result<UpdateBookResponse> {
+        let url = self.url(&format!("/books/{}", filename_segment(filename)));
+        let builder = self.authorized(self.client.put(url))
+            .header(reqwest::header::IF_MATCH, format!("\"{}\"", revision));
+        let response = check(builder.json(request).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Checks operations against a book without applying them, reporting
+    /// each one that would fail
+    pub async fn validate_operations(&self, filename: &str, request: &UpdatePixelBookRequest) -> Result<ValidationReport> {
+        let url = self.url(&format!("/books/{}", filename_segment(filename)));
+        let builder = self.authorized(self.client.put(url)).query(&[("dry_run", "true")]);
+        let response = check(builder.json(request).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Applies operations to several books at once. Either every book is
+    /// saved or none is; on failure the server's per-book errors are in the
+    /// [`ClientError::Server`] message.
+    pub async fn batch(&self, request: &BatchRequest) -> Result<BatchResult> {
+        let url = self.url("/batch");
+        let response = check(self.authorized(self.client.post(url)).json(request).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Reverts the most recent batch of operations applied to a book
+    pub async fn undo(&self, filename: &str) -> Result<HistoryResponse> {
+        let url = self.url(&format!("/books/{}/undo", filename_segment(filename)));
+        let response = check(self.authorized(self.client.post(url)).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Reapplies the most recently undone batch
+    pub async fn redo(&self, filename: &str) -> Result<HistoryResponse> {
+        let url = self.url(&format!("/books/{}/redo", filename_segment(filename)));
+        let response = check(self.authorized(self.client.post(url)).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Operation batches applied to a book after `since`, or all of them, oldest first
+    pub async fn operation_log(&self, filename: &str, since: Option<DateTime<Utc>>) -> Result<Vec<OperationLogEntry>> {
+        let url = self.url(&format!("/books/{}/operations", filename_segment(filename)));
+        let mut builder = self.client.get(url);
+        if let Some(since) = since {
+            builder = builder.query(&[("since", since.to_rfc3339_opts(SecondsFormat::AutoSi, true))]);
+        }
+        Ok(check(builder.send().await?).await?.json::<OperationLogResponse>().await?.entries)
+    }
+
+    /// Events already sent for a book after `since`, or from the start,
+    /// oldest first; at most `limit` of them (100 by default)
+    pub async fn event_history(&self, filename: &str, since: Option<DateTime<Utc>>, limit: Option<usize>) -> Result<Vec<PixelBookEvent>> {
+        let url = self.url(&format!("/books/{}/events/history", filename_segment(filename)));
+        let mut builder = self.client.get(url);
+        if let Some(since) = since {
+            builder = builder.query(&[("since", since.to_rfc3339_opts(SecondsFormat::AutoSi, true))]);
+        }
+        if let Some(limit) = limit {
+            builder = builder.query(&[("limit", limit)]);
+        }
+        Ok(check(builder.send().await?).await?.json::<EventHistoryResponse>().await?.events)
+    }
+
+    pub async fn set_permissions(&self, filename: &str, request: &SetPermissionsRequest) -> Result<PermissionsResponse> {
+        let url = self.url(&format!("/books/{}/permissions", filename_segment(filename)));
+        let response = check(self.authorized(self.client.put(url)).json(request).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Title, author, tags and description of a book
+    pub async fn get_details(&self, filename: &str) -> Result<BookDetails> {
+        let url = self.url(&format!("/books/{}/metadata", filename_segment(filename)));
+        Ok(check(self.client.get(url).send().await?).await?.json().await?)
+    }
+
+    /// Changes the fields set in `request`, returning the book's new details
+    pub async fn update_details(&self, filename: &str, request: &UpdateDetailsRequest) -> Result<BookDetails> {
+        let url = self.url(&format!("/books/{}/metadata", filename_segment(filename)));
+        let response = check(self.authorized(self.client.patch(url)).json(request).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// The book's palette; a `404` error when it has none
+    pub async fn get_palette(&self, filename: &str) -> Result<Palette> {
+        let url = self.url(&format!("/books/{}/palette", filename_segment(filename)));
+        let response = check(self.client.get(url).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Replaces the book's palette, which drawing operations can then index into
+    pub async fn set_palette(&self, filename: &str, palette: &Palette) -> Result<Palette> {
+        let url = self.url(&format!("/books/{}/palette", filename_segment(filename)));
+        let response = check(self.authorized(self.client.put(url)).json(palette).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// The book's tile size, if it has been declared a tileset
+    pub async fn get_tileset(&self, filename: &str) -> Result<TilesetInfo> {
+        let url = self.url(&format!("/books/{}/tileset", filename_segment(filename)));
+        let response = check(self.client.get(url).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Declares the book a tileset cut into tiles of `tileset`'s size
+    pub async fn set_tileset(&self, filename: &str, tileset: &Tileset) -> Result<TilesetInfo> {
+        let url = self.url(&format!("/books/{}/tileset", filename_segment(filename)));
+        let response = check(self.authorized(self.client.put(url)).json(tileset).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// The symmetry and other settings this client's session draws on the book with
+    pub async fn drawing_context(&self, filename: &str) -> Result<DrawingContext> {
+        let url = self.url(&format!("/books/{}/context", filename_segment(filename)));
+        let response = check(self.authorized(self.client.get(url)).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Sets the symmetry later updates in this client's session use when
+    /// they do not give their own
+    pub async fn set_symmetry(&self, filename: &str, symmetry: Symmetry) -> Result<DrawingContext> {
+        let url = self.url(&format!("/books/{}/symmetry", filename_segment(filename)));
+        let response = check(self.authorized(self.client.put(url)).json(&SetSymmetryRequest { symmetry }).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Limits later updates in this client's session to `selection`
+    pub async fn set_selection(&self, filename: &str, selection: &ActiveSelection) -> Result<DrawingContext> {
+        let url = self.url(&format!("/books/{}/selection", filename_segment(filename)));
+        let response = check(self.authorized(self.client.put(url)).json(selection).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Lets later updates in this client's session draw anywhere again
+    pub async fn clear_selection(&self, filename: &str) -> Result<DrawingContext> {
+        let url = self.url(&format!("/books/{}/selection", filename_segment(filename)));
+        let response = check(self.authorized(self.client.delete(url)).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Sets how long frame `index` shows for, or restores the default rate with `None`
+    pub async fn set_frame_duration(&self, filename: &str, index: usize, duration_ms: Option<u32>) -> Result<FrameDuration> {
+        let url = self.url(&format!("/books/{}/frames/{}/duration", filename_segment(filename), index));
+        let response = check(self.authorized(self.client.put(url)).json(&FrameDuration { duration_ms }).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Reduces the book to a palette; the result carries the palette actually used
+    pub async fn quantize(&self, filename: &str, request: &QuantizeRequest) -> Result<QuantizeResult> {
+        let url = self.url(&format!("/books/{}/quantize", filename_segment(filename)));
+        let response = check(self.authorized(self.client.post(url)).json(request).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Changes the canvas size of every frame, keeping the `anchor` side in place
+    pub async fn resize_book(&self, filename: &str, request: &ResizeRequest) -> Result<CanvasResponse> {
+        let url = self.url(&format!("/books/{}/resize", filename_segment(filename)));
+        let response = check(self.authorized(self.client.post(url)).json(request).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Crops every frame to the part of `rect` inside the canvas
+    pub async fn crop_book(&self, filename: &str, rect: &Rect) -> Result<CanvasResponse> {
+        let url = self.url(&format!("/books/{}/crop", filename_segment(filename)));
+        let response = check(self.authorized(self.client.post(url)).json(rect).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Crops every frame to the bounds of the book's visible pixels
+    pub async fn trim_book(&self, filename: &str) -> Result<TrimResponse> {
+        let url = self.url(&format!("/books/{}/trim", filename_segment(filename)));
+        let response = check(self.authorized(self.client.post(url)).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Scales every frame up by a whole `factor`, or down when `down` is set
+    pub async fn scale_book(&self, filename: &str, factor: u16, down: bool) -> Result<CanvasResponse> {
+        let url = self.url(&format!("/books/{}/scale", filename_segment(filename)));
+        let builder = self.authorized(self.client.post(url)).query(&[("factor", factor)]).query(&[("down", down)]);
+        Ok(check(builder.send().await?).await?.json().await?)
+    }
+
+    /// Rewrites a book in another `.pxl` format version (the newest by default)
+    pub async fn migrate_book(&self, filename: &str, request: &MigrateRequest) -> Result<MigrateResult> {
+        let url = self.url(&format!("/books/{}/migrate", filename_segment(filename)));
+        let response = check(self.authorized(self.client.post(url)).json(request).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Takes an advisory lock on a book, or renews it when this client's lock
+    /// token is the current one. Other clients' writes get `423 Locked` until it expires.
+    pub async fn lock_book(&self, filename: &str, request: &LockRequest) -> Result<LockResponse> {
+        let url = self.url(&format!("/books/{}/lock", filename_segment(filename)));
+        let response = check(self.authorized(self.client.post(url)).json(request).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    pub async fn unlock_book(&self, filename: &str) -> Result<()> {
+        let url = self.url(&format!("/books/{}/unlock", filename_segment(filename)));
+        check(self.authorized(self.client.post(url)).send().await?).await?;
+        Ok(())
+    }
+
+    /// Moves a book to the server's trash; it can be restored until it expires
+    pub async fn delete_book(&self, filename: &str) -> Result<()> {
+        let url = self.url(&format!("/books/{}", filename_segment(filename)));
+        check(self.authorized(self.client.delete(url)).send().await?).await?;
+        Ok(())
+    }
+
+    pub async fn list_trash(&self) -> Result<Vec<TrashEntry>> {
+        let response = check(self.client.get(self.url("/trash")).send().await?).await?;
+        Ok(response.json::<TrashResponse>().await?.books)
+    }
+
+    pub async fn restore_book(&self, filename: &str) -> Result<()> {
+        let url = self.url(&format!("/trash/{}/restore", filename_segment(filename)));
+        check(self.client.post(url).send().await?).await?;
+        Ok(())
+    }
+
+    /// Autosave and named snapshots of a book, newest first
+    pub async fn list_snapshots(&self, filename: &str) -> Result<Vec<SnapshotInfo>> {
+        let url = self.url(&format!("/books/{}/snapshots", filename_segment(filename)));
+        let response = check(self.client.get(url).send().await?).await?;
+        Ok(response.json::<SnapshotsResponse>().await?.snapshots)
+    }
+
+    /// Saves the book's current state as a named snapshot that is never pruned
+    pub async fn create_snapshot(&self, filename: &str, name: &str) -> Result<SnapshotInfo> {
+        let url = self.url(&format!("/books/{}/snapshots", filename_segment(filename)));
+        let request = CreateSnapshotRequest { name: name.to_string() };
+        let response = check(self.authorized(self.client.post(url)).json(&request).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Replaces the book with snapshot `id`; its current state is snapshotted first
+    pub async fn restore_snapshot(&self, filename: &str, id: i64) -> Result<SnapshotInfo> {
+        let url = self.url(&format!("/books/{}/snapshots/{}/restore", filename_segment(filename), id));
+        let response = check(self.authorized(self.client.post(url)).send().await?).await?;
+        Ok(response.json::<RestoreSnapshotResponse>().await?.snapshot)
+    }
+
+    pub async fn list_tilemaps(&self) -> Result<Vec<TilemapSummary>> {
+        let response = check(self.client.get(self.url("/tilemaps")).send().await?).await?;
+        Ok(response.json::<TilemapsResponse>().await?.tilemaps)
+    }
+
+    pub async fn get_tilemap(&self, name: &str) -> Result<Tilemap> {
+        let response = check(self.client.get(self.url(&format!("/tilemaps/{}", name))).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Creates or replaces the tilemap called `name`
+    pub async fn put_tilemap(&self, name: &str, request: &TilemapRequest) -> Result<Tilemap> {
+        let url = self.url(&format!("/tilemaps/{}", name));
+        let response = check(self.client.put(url).json(request).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Places or clears some of the tilemap's cells
+    pub async fn set_tiles(&self, name: &str, cells: &[TilePlacement]) -> Result<Tilemap> {
+        let url = self.url(&format!("/tilemaps/{}/tiles", name));
+        let request = SetTilesRequest { cells: cells.to_vec() };
+        let response = check(self.client.patch(url).json(&request).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    pub async fn delete_tilemap(&self, name: &str) -> Result<()> {
+        check(self.client.delete(self.url(&format!("/tilemaps/{}", name))).send().await?).await?;
+        Ok(())
+    }
+
+    /// The tilemap composited from its tileset as a PNG
+    pub async fn export_tilemap_png(&self, name: &str) -> Result<Vec<u8>> {
+        let url = self.url(&format!("/tilemaps/{}/export/png", name));
+        Ok(check(self.client.get(url).send().await?).await?.bytes().await?.to_vec())
+    }
+
+    /// The tilemap as a Tiled JSON map
+    pub async fn export_tilemap_json(&self, name: &str) -> Result<serde_json::Value> {
+        let url = self.url(&format!("/tilemaps/{}/export/json", name));
+        Ok(check(self.client.get(url).send().await?).await?.json().await?)
+    }
+
+    /// Checks every book for damage right away
+    pub async fn scan_books(&self) -> Result<ScanReport> {
+        let response = check(self.client.post(self.url("/maintenance/scan")).send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// The most recent integrity scan, if one has run
+    pub async fn last_scan(&self) -> Result<Option<ScanReport>> {
+        let response = check(self.client.get(self.url("/maintenance/status")).send().await?).await?;
+        Ok(response.json::<MaintenanceStatus>().await?.last_scan)
+    }
+
+    /// Opens the SSE stream for a book. Returns once the server has
+    /// acknowledged the connection, so no later event can be missed.
+    pub async fn subscribe(&self, filename: &str) -> Result<EventStream> {
+        self.subscribe_to(filename, &[]).await
+    }
+
+    /// Like [`subscribe`](Self::subscribe), but only receives events whose type
+    /// is in `types` (e.g. `"book_saved"`). An empty slice receives every event.
+    pub async fn subscribe_to(&self, filename: &str, types: &[&str]) -> Result<EventStream> {
+        self.open_events(&format!("/books/{}/events", filename_segment(filename)), types).await
+    }
+
+    /// Events of every book in the workspace
+    pub async fn subscribe_workspace(&self, types: &[&str]) -> Result<EventStream> {
+        self.open_events("/events", types).await
+    }
+
+    async fn open_events(&self, path: &str, types: &[&str]) -> Result<EventStream> {
+        let url = self.url(path);
+        let mut request = self.client.get(url);
+        if !types.is_empty() {
+            request = request.query(&[("types", types.join(","))]);
+        }
+        let response = request
+            .header("Accept", "text/event-stream")
+            .header("Cache-Control", "no-cache")
+            .send()
+            .await?;
+
+        EventStream::connect(check(response).await?).await
+    }
+
+    fn authorized(&self, builder: RequestBuilder) -> RequestBuilder {
+        let builder = match &self.owner_key {
+            Some(key) => builder.header(OWNER_KEY_HEADER, key),
+            None => builder,
+        };
+        let builder = match &self.lock_token {
+            Some(token) => builder.header(LOCK_TOKEN_HEADER, token),
+            None => builder,
+        };
+        match &self.session {
+            Some(session) => builder.header(SESSION_HEADER, session),
+            None => builder,
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+}
+
+// Turns non-2xx responses into `ClientError::Server` carrying the body text
+async fn check(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let message = response.text().await.unwrap_or_default();
+    Err(ClientError::Server { status: status.as_u16(), message })
+}
@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Server returned {status}: {message}")]
+    Server { status: u16, message: String },
+
+    #[error("Invalid event payload: {0}")]
+    InvalidEvent(#[from] serde_json::Error),
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
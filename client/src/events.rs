@@ -0,0 +1,103 @@
+use crate::error::{ClientError, Result};
+use futures_util::{Stream, StreamExt};
+use pixl_core::PixelBookEvent;
+use reqwest::Response;
+use std::pin::Pin;
+
+type ByteStream = Pin<Box<dyn Stream<Item = reqwest::Result<bytes::Bytes>> + Send>>;
+
+/// A single `data:` payload from the SSE stream
+#[derive(Debug, Clone)]
+pub enum StreamMessage {
+    /// Connection and heartbeat notices, which carry only a `type` field
+    Control(String),
+    Event(PixelBookEvent),
+}
+
+/// Live SSE subscription to one book's events
+pub struct EventStream {
+    stream: ByteStream,
+    buffer: String,
+}
+
+impl EventStream {
+    pub(crate) async fn connect(response: Response) -> Result<Self> {
+        let mut events = Self {
+            stream: Box::pin(response.bytes_stream()),
+            buffer: String::new(),
+        };
+
+        // The server always greets with a `connected` notice first
+        match events.next_message().await? {
+            Some(StreamMessage::Control(kind)) if kind == "connected" => Ok(events),
+            _ => Err(ClientError::Server {
+                status: 200,
+                message: "Event stream did not start with a connected notice".to_string(),
+            }),
+        }
+    }
+
+    /// Next book event, skipping heartbeats. `None` once the server closes the stream.
+    pub async fn next_event(&mut self) -> Result<Option<PixelBookEvent>> {
+        loop {
+            match self.next_message().await? {
+                Some(StreamMessage::Event(event)) => return Ok(Some(event)),
+                Some(StreamMessage::Control(_)) => continue,
+                None => return Ok(None),
+            }
+        }
+    }
+
+    pub async fn next_message(&mut self) -> Result<Option<StreamMessage>> {
+        loop {
+            while let Some(pos) = self.buffer.find("\n\n") {
+                let block = self.buffer[..pos].to_string();
+                self.buffer.drain(..pos + 2);
+
+                if let Some(message) = parse_message(&block)? {
+                    return Ok(Some(message));
+                }
+            }
+
+            match self.stream.next().await {
+                Some(chunk) => self.buffer.push_str(&String::from_utf8_lossy(&chunk?)),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Parses one SSE block; blocks without a `data:` line yield `None`
+pub fn parse_message(block: &str) -> Result<Option<StreamMessage>> {
+    let Some(data) = block.lines().find_map(|line| line.strip_prefix("data:")) else {
+        return Ok(None);
+    };
+
+    let value: serde_json::Value = serde_json::from_str(data.trim())?;
+    if value.get("event_type").is_some() {
+        return Ok(Some(StreamMessage::Event(serde_json::from_value(value)?)));
+    }
+
+    let kind = value.get("type").and_then(|t| t.as_str()).unwrap_or_default();
+    Ok(Some(StreamMessage::Control(kind.to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pixl_core::EventType;
+
+    #[test]
+    fn test_parse_control_and_event_messages() {
+        let connected = r#"data: {"type":"connected","filename":"a.pxl","timestamp":"2025-01-01T00:00:00Z"}"#;
+        assert!(matches!(parse_message(connected).unwrap(), Some(StreamMessage::Control(kind)) if kind == "connected"));
+
+        let saved = r#"data: {"filename":"a.pxl","timestamp":"2025-01-01T00:00:00Z","event_type":{"type":"book_saved"}}"#;
+        match parse_message(saved).unwrap() {
+            Some(StreamMessage::Event(event)) => assert!(matches!(event.event_type, EventType::BookSaved)),
+            other => panic!("unexpected message: {:?}", other),
+        }
+
+        assert!(parse_message(": keep-alive").unwrap().is_none());
+    }
+}
@@ -0,0 +1,22 @@
+//! Async client for the PIXL server: typed REST calls via [`PixlClient`] and
+//! live book events via [`EventStream`].
+//!
+//! ```no_run
+//! # async fn example() -> pixl_client::Result<()> {
+//! let client = pixl_client::PixlClient::new("http://localhost:3000");
+//! for book in client.list_books().await? {
+//!     println!("{} ({} frames)", book.filename, book.frames);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod client;
+pub mod error;
+pub mod events;
+pub mod stream;
+
+pub use client::*;
+pub use error::*;
+pub use events::*;
+pub use stream::*;
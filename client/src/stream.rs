@@ -0,0 +1,43 @@
+use crate::error::Result;
+use futures_util::{Stream, StreamExt};
+use pixl_core::BookChunk;
+use reqwest::Response;
+use std::pin::Pin;
+
+type ByteStream = Pin<Box<dyn Stream<Item = reqwest::Result<bytes::Bytes>> + Send>>;
+
+/// A book arriving frame by frame from `GET /books/:filename/stream`
+pub struct BookStream {
+    stream: ByteStream,
+    buffer: Vec<u8>,
+}
+
+impl BookStream {
+    pub(crate) fn new(response: Response) -> Self {
+        Self {
+            stream: Box::pin(response.bytes_stream()),
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Next line of the stream: the `book` line first, then each frame.
+    /// `None` once every requested frame has arrived.
+    pub async fn next_chunk(&mut self) -> Result<Option<BookChunk>> {
+        loop {
+            if let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+                return Ok(Some(serde_json::from_slice(&line)?));
+            }
+
+            match self.stream.next().await {
+                Some(chunk) => self.buffer.extend_from_slice(&chunk?),
+                None if self.buffer.iter().all(u8::is_ascii_whitespace) => return Ok(None),
+                // A final line without its newline
+                None => return Ok(Some(serde_json::from_slice(&std::mem::take(&mut self.buffer))?)),
+            }
+        }
+    }
+}
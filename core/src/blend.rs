@@ -0,0 +1,51 @@
+use crate::operations::BlendMode;
+
+/// `src` combined with `dst` by `mode`, with straight (non-premultiplied)
+/// alpha. Every mode but `Replace` composites the blended color over `dst`
+/// by `src`'s alpha, so a transparent `src` leaves `dst` unchanged.
+pub fn blend(mode: BlendMode, dst: [u8; 4], src: [u8; 4]) -> [u8; 4] {
+    let mix: fn(f32, f32) -> f32 = match mode {
+        BlendMode::Replace => return src,
+        BlendMode::Alpha => |_, s| s,
+        BlendMode::Add => |d, s| (d + s).min(1.0),
+        BlendMode::Multiply => |d, s| d * s,
+    };
+
+    let src_alpha = src[3] as f32 / 255.0;
+    let dst_alpha = dst[3] as f32 / 255.0;
+    let alpha = src_alpha + dst_alpha * (1.0 - src_alpha);
+    if alpha <= 0.0 {
+        return [0, 0, 0, 0];
+    }
+
+    let channel = |i: usize| {
+        let (d, s) = (dst[i] as f32 / 255.0, src[i] as f32 / 255.0);
+        // Over a transparent pixel there is nothing to mix with
+        let mixed = (1.0 - dst_alpha) * s + dst_alpha * mix(d, s);
+        let value = (src_alpha * mixed + dst_alpha * (1.0 - src_alpha) * d) / alpha;
+        (value * 255.0).round().clamp(0.0, 255.0) as u8
+    };
+    [channel(0), channel(1), channel(2), (alpha * 255.0).round() as u8]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blend_modes() {
+        let red = [255, 0, 0, 255];
+        let half_blue = [0, 0, 255, 128];
+        assert_eq!(blend(BlendMode::Replace, red, half_blue), half_blue);
+        assert_eq!(blend(BlendMode::Alpha, red, [0, 0, 255, 255]), [0, 0, 255, 255]);
+        assert_eq!(blend(BlendMode::Alpha, red, [0, 0, 255, 0]), red);
+        assert_eq!(blend(BlendMode::Alpha, red, half_blue), [127, 0, 128, 255]);
+        // Over transparency the source keeps its color and alpha
+        assert_eq!(blend(BlendMode::Alpha, [0, 0, 0, 0], half_blue), half_blue);
+
+        assert_eq!(blend(BlendMode::Add, [200, 100, 0, 255], [100, 100, 100, 255]), [255, 200, 100, 255]);
+        assert_eq!(blend(BlendMode::Add, [200, 100, 0, 255], [100, 100, 100, 128]), [228, 150, 50, 255]);
+        assert_eq!(blend(BlendMode::Multiply, [200, 100, 50, 255], [128, 255, 0, 255]), [100, 100, 0, 255]);
+        assert_eq!(blend(BlendMode::Multiply, [0, 0, 0, 0], half_blue), half_blue);
+    }
+}
@@ -0,0 +1,38 @@
+use crate::operations::{Selection, Symmetry};
+use serde::{Deserialize, Serialize};
+
+/// Settings that carry over from one drawing request to the next. The
+/// server keeps one per book and session, so every client drawing in the
+/// same session shares them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DrawingContext {
+    /// Mirror mode for updates that do not ask for their own
+    #[serde(default)]
+    pub symmetry: Symmetry,
+    /// Pixels updates may change until the selection is cleared
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selection: Option<ActiveSelection>,
+}
+
+/// A selection kept between requests. A magic wand selection picks its
+/// pixels again at the start of every update.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ActiveSelection {
+    pub frame: usize,
+    pub selection: Selection,
+}
+
+impl DrawingContext {
+    /// True when nothing differs from a fresh context
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SetSymmetryRequest {
+    pub symmetry: Symmetry,
+}
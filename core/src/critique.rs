@@ -0,0 +1,225 @@
+use crate::operations::Rect;
+use crate::pixel_book::{Frame, Pixel, PixelBook};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Colors listed in [`RegionCritique::dominant_colors`]
+pub const DOMINANT_COLOR_COUNT: usize = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ColorUsage {
+    pub color: [u8; 4],
+    pub count: usize,
+    /// Share of the region's visible pixels
+    pub share: f32,
+}
+
+/// Statistics describing one rectangle of a frame
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RegionCritique {
+    pub frame: usize,
+    /// The region examined, clipped to the canvas
+    pub rect: Rect,
+    pub pixel_count: usize,
+    pub transparent_pixels: usize,
+    pub distinct_colors: usize,
+    /// Most used visible colors, most used first
+    pub dominant_colors: Vec<ColorUsage>,
+    /// Share of neighbouring pixel pairs (left-right and up-down) whose colors
+    /// differ: 0 for a flat fill, 1 for a checkerboard
+    pub edge_density: f32,
+    /// Share of pixels matching their reflection across the region's vertical
+    /// centre line; 1 when the left half mirrors the right
+    pub horizontal_symmetry: f32,
+    /// Share of pixels matching their reflection across the region's horizontal
+    /// centre line; 1 when the top half mirrors the bottom
+    pub vertical_symmetry: f32,
+}
+
+/// Examines `rect` of frame `frame`. Returns `None` for an unknown frame or a
+/// rectangle entirely outside the canvas.
+pub fn critique_region(book: &PixelBook, frame: usize, rect: &Rect) -> Option<RegionCritique> {
+    let pixels = book.frames.get(frame)?;
+    let right = (rect.x as u32 + rect.width as u32).min(book.width as u32);
+    let bottom = (rect.y as u32 + rect.height as u32).min(book.height as u32);
+    if rect.x as u32 >= right || rect.y as u32 >= bottom {
+        return None;
+    }
+    let rect = Rect { x: rect.x, y: rect.y, width: (right - rect.x as u32) as u16, height: (bottom - rect.y as u32) as u16 };
+    let (width, height) = (rect.width as usize, rect.height as usize);
+
+    // The region's colors, row by row
+    let colors: Vec<[u8; 4]> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let p = pixels.get_pixel(rect.x + x as u16, rect.y + y as u16, book.width).unwrap_or(Pixel::new(0, 0, 0, 0));
+            [p.r, p.g, p.b, p.a]
+        })
+        .collect();
+    let at = |x: usize, y: usize| colors[y * width + x];
+
+    let mut counts: HashMap<[u8; 4], usize> = HashMap::new();
+    for color in colors.iter().filter(|c| c[3] != 0) {
+        *counts.entry(*color).or_default() += 1;
+    }
+    let visible: usize = counts.values().sum();
+    let mut usage: Vec<ColorUsage> = counts.iter()
+        .map(|(&color, &count)| ColorUsage { color, count, share: count as f32 / visible as f32 })
+        .collect();
+    usage.sort_unstable_by(|a, b| b.count.cmp(&a.count).then(a.color.cmp(&b.color)));
+    let distinct_colors = usage.len();
+    usage.truncate(DOMINANT_COLOR_COUNT);
+
+    let mut pairs = 0;
+    let mut edges = 0;
+    for y in 0..height {
+        for x in 0..width {
+            for (nx, ny) in [(x + 1, y), (x, y + 1)] {
+                if nx < width && ny < height {
+                    pairs += 1;
+                    if at(x, y) != at(nx, ny) {
+                        edges += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    let share = |matches: usize| matches as f32 / colors.len() as f32;
+    let mirrored_x = (0..height).flat_map(|y| (0..width).map(move |x| (x, y)))
+        .filter(|&(x, y)| at(x, y) == at(width - 1 - x, y))
+        .count();
+    let mirrored_y = (0..height).flat_map(|y| (0..width).map(move |x| (x, y)))
+        .filter(|&(x, y)| at(x, y) == at(x, height - 1 - y))
+        .count();
+
+    Some(RegionCritique {
+        frame,
+        rect,
+        pixel_count: colors.len(),
+        transparent_pixels: colors.len() - visible,
+        distinct_colors,
+        dominant_colors: usage,
+        edge_density: if pairs == 0 { 0.0 } else { edges as f32 / pairs as f32 },
+        horizontal_symmetry: share(mirrored_x),
+        vertical_symmetry: share(mirrored_y),
+    })
+}
+
+/// Counts describing a whole frame, for checking edits and palette budgets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FrameStats {
+    pub frame: usize,
+    pub pixel_count: usize,
+    /// Pixels that are not fully transparent
+    pub visible_pixels: usize,
+    pub distinct_colors: usize,
+    /// Smallest rectangle holding every visible pixel; `None` for an empty frame
+    pub bounds: Option<Rect>,
+    /// Visible colors, most used first
+    pub histogram: Vec<ColorUsage>,
+}
+
+/// Smallest rectangle holding every pixel of a `width` x `height` frame that
+/// is not fully transparent; `None` for an empty frame
+pub fn visible_bounds(frame: &Frame, width: u16, height: u16) -> Option<Rect> {
+    let mut bounds: Option<(u16, u16, u16, u16)> = None;
+    for (i, pixel) in frame.pixels.chunks_exact(4).take(width as usize * height as usize).enumerate() {
+        if pixel[3] == 0 {
+            continue;
+        }
+        let (x, y) = ((i % width as usize) as u16, (i / width as usize) as u16);
+        bounds = Some(match bounds {
+            Some((left, top, right, bottom)) => (left.min(x), top.min(y), right.max(x), bottom.max(y)),
+            None => (x, y, x, y),
+        });
+    }
+    bounds.map(|(left, top, right, bottom)| Rect { x: left, y: top, width: right - left + 1, height: bottom - top + 1 })
+}
+
+/// Counts the colors of a `width` x `height` frame. The histogram keeps its
+/// `top` most used colors, or all of them when `None`.
+pub fn frame_stats(frame: &Frame, index: usize, width: u16, height: u16, top: Option<usize>) -> FrameStats {
+    let mut counts: HashMap<[u8; 4], usize> = HashMap::new();
+    for pixel in frame.pixels.chunks_exact(4).take(width as usize * height as usize).filter(|pixel| pixel[3] != 0) {
+        *counts.entry([pixel[0], pixel[1], pixel[2], pixel[3]]).or_default() += 1;
+    }
+
+    let visible: usize = counts.values().sum();
+    let mut histogram: Vec<ColorUsage> = counts.iter()
+        .map(|(&color, &count)| ColorUsage { color, count, share: count as f32 / visible as f32 })
+        .collect();
+    histogram.sort_unstable_by(|a, b| b.count.cmp(&a.count).then(a.color.cmp(&b.color)));
+    let distinct_colors = histogram.len();
+    if let Some(top) = top {
+        histogram.truncate(top);
+    }
+
+    FrameStats {
+        frame: index,
+        pixel_count: width as usize * height as usize,
+        visible_pixels: visible,
+        distinct_colors,
+        bounds: visible_bounds(frame, width, height),
+        histogram,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_critique_of_a_left_right_symmetric_region() {
+        // A 4x2 region: red on both outer columns of the top row
+        let mut book = PixelBook::new("c.pxl".to_string(), 6, 6, 1);
+        let red = Pixel::new(255, 0, 0, 255);
+        book.frames[0].set_pixel(1, 1, 6, red);
+        book.frames[0].set_pixel(4, 1, 6, red);
+
+        let critique = critique_region(&book, 0, &Rect { x: 1, y: 1, width: 4, height: 2 }).unwrap();
+        assert_eq!(critique.pixel_count, 8);
+        assert_eq!(critique.transparent_pixels, 6);
+        assert_eq!(critique.dominant_colors, vec![ColorUsage { color: [255, 0, 0, 255], count: 2, share: 1.0 }]);
+        assert_eq!(critique.horizontal_symmetry, 1.0);
+        assert_eq!(critique.vertical_symmetry, 0.5);
+        // 10 neighbouring pairs, 4 of them touching a red pixel
+        assert_eq!(critique.edge_density, 0.4);
+    }
+
+    #[test]
+    fn test_critique_clips_to_the_canvas() {
+        let book = PixelBook::new("c.pxl".to_string(), 4, 4, 1);
+        let critique = critique_region(&book, 0, &Rect { x: 2, y: 2, width: 10, height: 10 }).unwrap();
+        assert_eq!((critique.rect.width, critique.rect.height), (2, 2));
+        assert!(critique_region(&book, 0, &Rect { x: 4, y: 0, width: 1, height: 1 }).is_none());
+        assert!(critique_region(&book, 1, &Rect { x: 0, y: 0, width: 1, height: 1 }).is_none());
+    }
+
+    #[test]
+    fn test_frame_stats_count_colors_and_bounds() {
+        let mut frame = Frame::new(0, 5, 4);
+        let (red, blue) = (Pixel::new(255, 0, 0, 255), Pixel::new(0, 0, 255, 128));
+        for (x, y) in [(1, 1), (3, 1), (2, 2)] {
+            frame.set_pixel(x, y, 5, red);
+        }
+        frame.set_pixel(1, 2, 5, blue);
+
+        let stats = frame_stats(&frame, 3, 5, 4, None);
+        assert_eq!((stats.frame, stats.pixel_count, stats.visible_pixels, stats.distinct_colors), (3, 20, 4, 2));
+        let bounds = stats.bounds.unwrap();
+        assert_eq!((bounds.x, bounds.y, bounds.width, bounds.height), (1, 1, 3, 2));
+        assert_eq!(stats.histogram, vec![
+            ColorUsage { color: [255, 0, 0, 255], count: 3, share: 0.75 },
+            ColorUsage { color: [0, 0, 255, 128], count: 1, share: 0.25 },
+        ]);
+
+        let top = frame_stats(&frame, 3, 5, 4, Some(1));
+        assert_eq!((top.histogram.len(), top.distinct_colors), (1, 2));
+        let empty = frame_stats(&Frame::new(0, 2, 2), 0, 2, 2, None);
+        assert!(empty.bounds.is_none() && empty.histogram.is_empty());
+    }
+}
@@ -0,0 +1,272 @@
+use crate::operations::{FillRule, Point};
+
+/// Vertices of a polyline following the Bézier curve from `start` to `end`
+/// shaped by `controls`: one control point gives a quadratic curve, two a
+/// cubic one, and none a straight line. Samples are at most a pixel apart
+/// along the control polygon, so joining consecutive vertices with straight
+/// lines draws the curve without gaps.
+pub fn bezier_points(start: &Point, controls: &[Point], end: &Point) -> Vec<Point> {
+    let hull: Vec<(f32, f32)> = std::iter::once(start)
+        .chain(controls)
+        .chain(std::iter::once(end))
+        .map(|p| (p.x as f32, p.y as f32))
+        .collect();
+    let length: f32 = hull.windows(2)
+        .map(|pair| (pair[1].0 - pair[0].0).hypot(pair[1].1 - pair[0].1))
+        .sum();
+    let steps = (length.ceil() as usize).max(1);
+
+    let mut points: Vec<Point> = Vec::with_capacity(steps + 1);
+    for step in 0..=steps {
+        let (x, y) = de_casteljau(&hull, step as f32 / steps as f32);
+        let point = Point { x: x.round() as u16, y: y.round() as u16 };
+        if points.last().is_none_or(|last| (last.x, last.y) != (point.x, point.y)) {
+            points.push(point);
+        }
+    }
+    points
+}
+
+/// Pixels of the Bresenham line from `start` to `end`, both included
+pub fn line_pixels(start: &Point, end: &Point) -> Vec<(i32, i32)> {
+    let (mut x, mut y) = (start.x as i32, start.y as i32);
+    let (x1, y1) = (end.x as i32, end.y as i32);
+    let (dx, dy) = ((x1 - x).abs(), (y1 - y).abs());
+    let (sx, sy) = (if x < x1 { 1 } else { -1 }, if y < y1 { 1 } else { -1 });
+    let mut err = dx - dy;
+    let mut pixels = Vec::new();
+
+    loop {
+        pixels.push((x, y));
+        if x == x1 && y == y1 {
+            return pixels;
+        }
+        let e2 = 2 * err;
+        if e2 > -dy {
+            err -= dy;
+            x += sx;
+        }
+        if e2 < dx {
+            err += dx;
+            y += sy;
+        }
+    }
+}
+
+/// Pixels of the polyline through `vertices`, each listed once in drawing
+/// order. A single vertex gives that one pixel.
+pub fn path_pixels(vertices: &[Point]) -> Vec<(i32, i32)> {
+    let mut pixels: Vec<(i32, i32)> = vertices.first().map(|p| vec![(p.x as i32, p.y as i32)]).unwrap_or_default();
+    for pair in vertices.windows(2) {
+        for pixel in line_pixels(&pair[0], &pair[1]) {
+            if pixels.last() != Some(&pixel) {
+                pixels.push(pixel);
+            }
+        }
+    }
+    pixels
+}
+
+/// `pixels` (a connected path) without the corner pixel of each L-shaped
+/// step, which makes strokes look doubled where they turn
+pub fn pixel_perfect(pixels: &[(i32, i32)]) -> Vec<(i32, i32)> {
+    let mut kept: Vec<(i32, i32)> = Vec::with_capacity(pixels.len());
+    for (i, &(x, y)) in pixels.iter().enumerate() {
+        if let (Some(&(px, py)), Some(&(nx, ny))) = (kept.last(), pixels.get(i + 1)) {
+            let corner = (px == x || py == y) && (nx == x || ny == y) && px != nx && py != ny;
+            if corner {
+                continue;
+            }
+        }
+        kept.push((x, y));
+    }
+    kept
+}
+
+/// Pixels of the polyline through `vertices` drawn with Xiaolin Wu's
+/// algorithm, each with how much of it the line covers (0 to 1]
+pub fn antialiased_path(vertices: &[Point]) -> Vec<(i32, i32, f32)> {
+    let mut coverage: Vec<((i32, i32), f32)> = Vec::new();
+    let mut cover = |x: i32, y: i32, amount: f32| {
+        if amount <= 0.0 {
+            return;
+        }
+        // Segments meet at shared vertices; keep the strongest coverage rather than adding them up
+        match coverage.iter_mut().find(|(pixel, _)| *pixel == (x, y)) {
+            Some((_, existing)) => *existing = existing.max(amount),
+            None => coverage.push(((x, y), amount)),
+        }
+    };
+
+    if let [only] = vertices {
+        cover(only.x as i32, only.y as i32, 1.0);
+    }
+    for pair in vertices.windows(2) {
+        let (mut x0, mut y0, mut x1, mut y1) = (pair[0].x as f32, pair[0].y as f32, pair[1].x as f32, pair[1].y as f32);
+        let steep = (y1 - y0).abs() > (x1 - x0).abs();
+        if steep {
+            (x0, y0, x1, y1) = (y0, x0, y1, x1);
+        }
+        if x0 > x1 {
+            (x0, y0, x1, y1) = (x1, y1, x0, y0);
+        }
+        let gradient = if x1 == x0 { 0.0 } else { (y1 - y0) / (x1 - x0) };
+        for x in x0 as i32..=x1 as i32 {
+            let y = y0 + gradient * (x as f32 - x0);
+            let (row, fraction) = (y.floor(), y - y.floor());
+            for (row, amount) in [(row as i32, 1.0 - fraction), (row as i32 + 1, fraction)] {
+                if steep {
+                    cover(row, x, amount);
+                } else {
+                    cover(x, row, amount);
+                }
+            }
+        }
+    }
+    coverage.into_iter().map(|((x, y), amount)| (x, y, amount)).collect()
+}
+
+/// Pixels whose centres lie inside the polygon through `points` under
+/// `rule`, clipped to a `width` x `height` canvas. Scanlines run through
+/// pixel centres, so they never meet a vertex or run along a horizontal
+/// edge, and each crossing is counted exactly once.
+pub fn polygon_interior(points: &[Point], rule: FillRule, width: u16, height: u16) -> Vec<(u16, u16)> {
+    let mut interior = Vec::new();
+    let (Some(min_y), Some(max_y)) = (points.iter().map(|p| p.y).min(), points.iter().map(|p| p.y).max()) else {
+        return interior;
+    };
+    if points.len() < 3 || width == 0 {
+        return interior;
+    }
+
+    let mut crossings: Vec<(f32, i32)> = Vec::new();
+    for y in min_y..=max_y.min(height.saturating_sub(1)) {
+        let scan_y = y as f32 + 0.5;
+        crossings.clear();
+        for (a, b) in points.iter().zip(points.iter().cycle().skip(1)) {
+            let (ay, by) = (a.y as f32, b.y as f32);
+            if (ay < scan_y) == (by < scan_y) {
+                continue;
+            }
+            let x = a.x as f32 + (scan_y - ay) * (b.x as f32 - a.x as f32) / (by - ay);
+            crossings.push((x, if by > ay { 1 } else { -1 }));
+        }
+        crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        // Between two crossings the winding number is the sum of those to the left
+        let mut winding = 0;
+        for pair in crossings.windows(2) {
+            winding += pair[0].1;
+            let inside = match rule {
+                FillRule::EvenOdd => winding % 2 != 0,
+                FillRule::NonZero => winding != 0,
+            };
+            if !inside {
+                continue;
+            }
+            let start = (pair[0].0 - 0.5).ceil().max(0.0) as i32;
+            let end = ((pair[1].0 - 0.5).ceil() as i32 - 1).min(width as i32 - 1);
+            interior.extend((start..=end).map(|x| (x as u16, y)));
+        }
+    }
+    interior
+}
+
+// Point at `t` along the curve, found by repeatedly interpolating between
+// neighbouring hull points
+fn de_casteljau(hull: &[(f32, f32)], t: f32) -> (f32, f32) {
+    let mut points = hull.to_vec();
+    while points.len() > 1 {
+        points = points.windows(2)
+            .map(|pair| (pair[0].0 + (pair[1].0 - pair[0].0) * t, pair[0].1 + (pair[1].1 - pair[0].1) * t))
+            .collect();
+    }
+    points[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coords(points: &[Point]) -> Vec<(u16, u16)> {
+        points.iter().map(|p| (p.x, p.y)).collect()
+    }
+
+    fn point(x: u16, y: u16) -> Point {
+        Point { x, y }
+    }
+
+    #[test]
+    fn test_line_styles() {
+        // Joined segments share their vertices; Bresenham steps diagonally, so there is nothing to remove
+        let path = path_pixels(&[point(0, 0), point(2, 1), point(3, 3)]);
+        assert_eq!(path, [(0, 0), (1, 0), (2, 1), (2, 2), (3, 3)]);
+        // Paths that turn one axis at a time lose their corners
+        let stairs = [(0, 0), (1, 0), (1, 1), (2, 1), (2, 2)];
+        assert_eq!(pixel_perfect(&stairs), [(0, 0), (1, 1), (2, 2)]);
+        assert_eq!(pixel_perfect(&path), path);
+        assert_eq!(path_pixels(&[point(4, 4)]), [(4, 4)]);
+
+        // Axis-aligned and diagonal lines are fully covered; others share coverage between rows
+        let flat = antialiased_path(&[point(0, 0), point(3, 0)]);
+        assert_eq!(flat.len(), 4);
+        assert!(flat.iter().all(|&(_, y, amount)| y == 0 && amount == 1.0));
+        let shallow = antialiased_path(&[point(0, 0), point(4, 1)]);
+        let column: f32 = shallow.iter().filter(|p| p.0 == 2).map(|p| p.2).sum();
+        assert!((column - 1.0).abs() < 1e-5);
+        assert!(shallow.iter().any(|&(x, y, amount)| (x, y) == (2, 1) && amount == 0.5));
+    }
+
+    #[test]
+    fn test_polygon_interior_handles_concave_and_crossing_edges() {
+        // A square covers the pixels whose centres it contains
+        let square = [point(0, 0), point(4, 0), point(4, 4), point(0, 4)];
+        let inside = polygon_interior(&square, FillRule::EvenOdd, 10, 10);
+        assert_eq!(inside.len(), 16);
+        assert!(inside.iter().all(|&(x, y)| x < 4 && y < 4));
+
+        // A U shape leaves its notch empty, even where the scanline meets its vertices' rows
+        let u = [point(0, 0), point(2, 0), point(2, 4), point(4, 4), point(4, 0), point(6, 0), point(6, 6), point(0, 6)];
+        let inside = polygon_interior(&u, FillRule::EvenOdd, 10, 10);
+        assert!(!inside.contains(&(3, 2)));
+        assert!(inside.contains(&(1, 2)) && inside.contains(&(5, 2)) && inside.contains(&(3, 5)));
+        assert_eq!(inside.len(), 36 - 8);
+
+        // The middle of a five-pointed star is only inside under the non-zero rule
+        let star = [point(10, 0), point(16, 19), point(0, 7), point(20, 7), point(4, 19)];
+        assert!(!polygon_interior(&star, FillRule::EvenOdd, 20, 20).contains(&(10, 10)));
+        assert!(polygon_interior(&star, FillRule::NonZero, 20, 20).contains(&(10, 10)));
+        assert!(polygon_interior(&star, FillRule::EvenOdd, 20, 20).contains(&(10, 3)));
+
+        assert!(polygon_interior(&square[..2], FillRule::NonZero, 10, 10).is_empty());
+        assert!(polygon_interior(&square, FillRule::EvenOdd, 2, 2).iter().all(|&(x, y)| x < 2 && y < 2));
+    }
+
+    #[test]
+    fn test_bezier_points_follow_the_control_points() {
+        let (start, end) = (Point { x: 0, y: 10 }, Point { x: 20, y: 10 });
+
+        // No controls: the straight segment, one vertex per pixel
+        let straight = coords(&bezier_points(&start, &[], &end));
+        assert_eq!(straight.len(), 21);
+        assert!(straight.iter().all(|&(_, y)| y == 10));
+
+        // Quadratic: the peak is halfway to the control point
+        let quadratic = coords(&bezier_points(&start, &[Point { x: 10, y: 0 }], &end));
+        assert_eq!(quadratic.first(), Some(&(0, 10)));
+        assert_eq!(quadratic.last(), Some(&(20, 10)));
+        assert_eq!(quadratic.iter().map(|&(_, y)| y).min(), Some(5));
+
+        // Cubic: an S-curve crossing the baseline in the middle
+        let cubic = coords(&bezier_points(&start, &[Point { x: 7, y: 0 }, Point { x: 13, y: 20 }], &end));
+        assert!(cubic.iter().any(|&(x, y)| x < 10 && y < 10));
+        assert!(cubic.iter().any(|&(x, y)| x > 10 && y > 10));
+        assert!(cubic.contains(&(10, 10)));
+
+        // Consecutive vertices never repeat or leave gaps wider than a pixel
+        for pair in quadratic.windows(2).chain(cubic.windows(2)) {
+            assert_ne!(pair[0], pair[1]);
+            assert!(pair[0].0.abs_diff(pair[1].0) <= 1 && pair[0].1.abs_diff(pair[1].1) <= 1);
+        }
+    }
+}
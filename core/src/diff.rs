@@ -0,0 +1,128 @@
+use crate::operations::Rect;
+use crate::pixel_book::{Frame, Pixel};
+use serde::{Deserialize, Serialize};
+
+/// A single pixel whose color differs between two frames
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PixelChange {
+    pub x: u16,
+    pub y: u16,
+    pub before: Pixel,
+    pub after: Pixel,
+}
+
+/// Pixel-level difference between two frames of the same dimensions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FrameDiff {
+    pub changes: Vec<PixelChange>,
+    /// Smallest rectangle containing every changed pixel, `None` when identical
+    pub bounds: Option<Rect>,
+}
+
+impl FrameDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn changed_count(&self) -> usize {
+        self.changes.len()
+    }
+}
+
+/// Query of `GET /books/:filename/diff`. Each side is a frame of the live
+/// book, or of a snapshot when its snapshot id is given.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DiffQuery {
+    #[serde(default)]
+    pub frame_a: usize,
+    /// Defaults to `frame_a`, to compare one frame across snapshots
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame_b: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_a: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_b: Option<i64>,
+}
+
+/// Response of `GET /books/:filename/diff`: the two sides compared and the
+/// pixels that differ between them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FrameComparison {
+    pub frame_a: usize,
+    pub frame_b: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_a: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_b: Option<i64>,
+    /// Number of changed pixels
+    pub changed: usize,
+    #[serde(flatten)]
+    pub diff: FrameDiff,
+}
+
+/// Compares two frames pixel by pixel. Pixels missing from a short buffer are
+/// treated as transparent so truncated frames still produce a meaningful diff.
+pub fn diff_frames(before: &Frame, after: &Frame, width: u16, height: u16) -> FrameDiff {
+    let mut changes = Vec::new();
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (u16::MAX, u16::MAX, 0, 0);
+
+    for y in 0..height {
+        for x in 0..width {
+            let a = before.get_pixel(x, y, width).unwrap_or_else(Pixel::transparent);
+            let b = after.get_pixel(x, y, width).unwrap_or_else(Pixel::transparent);
+            if a != b {
+                changes.push(PixelChange { x, y, before: a, after: b });
+                min_x = min_x.min(x);
+                min_y = min_y.min(y);
+                max_x = max_x.max(x);
+                max_y = max_y.max(y);
+            }
+        }
+    }
+
+    let bounds = (!changes.is_empty()).then(|| Rect {
+        x: min_x,
+        y: min_y,
+        width: max_x - min_x + 1,
+        height: max_y - min_y + 1,
+    });
+
+    FrameDiff { changes, bounds }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_frames() {
+        let frame = Frame::new(0, 4, 4);
+        let diff = diff_frames(&frame, &frame.clone(), 4, 4);
+        assert!(diff.is_empty());
+        assert!(diff.bounds.is_none());
+    }
+
+    #[test]
+    fn test_changes_and_bounds() {
+        let before = Frame::new(0, 8, 8);
+        let mut after = before.clone();
+        after.set_pixel(1, 2, 8, Pixel::new(255, 0, 0, 255));
+        after.set_pixel(5, 6, 8, Pixel::new(0, 255, 0, 255));
+
+        let diff = diff_frames(&before, &after, 8, 8);
+        assert_eq!(diff.changed_count(), 2);
+        assert_eq!(diff.changes[0], PixelChange {
+            x: 1,
+            y: 2,
+            before: Pixel::transparent(),
+            after: Pixel::new(255, 0, 0, 255),
+        });
+
+        let bounds = diff.bounds.unwrap();
+        assert_eq!((bounds.x, bounds.y, bounds.width, bounds.height), (1, 2, 5, 5));
+    }
+}
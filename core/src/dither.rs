@@ -0,0 +1,190 @@
+use crate::operations::{DitherPattern, GradientDirection, Rect};
+
+/// 4x4 Bayer threshold matrix, indexed `[y % 4][x % 4]`
+pub(crate) const BAYER_4X4: [[u32; 4]; 4] = [
+    [0, 8, 2, 10],
+    [12, 4, 14, 6],
+    [3, 11, 1, 9],
+    [15, 7, 13, 5],
+];
+
+/// Every pixel of `rect` on a `width` x `height` canvas with its color in an
+/// ordered dither from `from` to `to` along `direction`. The first row or
+/// column of the rect is all `from` and the last all `to`, even where they
+/// fall off the canvas. Thresholds follow canvas coordinates, so neighbouring
+/// gradients line up.
+pub fn dither_gradient(rect: &Rect, from: [u8; 4], to: [u8; 4], direction: GradientDirection, width: u16, height: u16) -> Vec<(u16, u16, [u8; 4])> {
+    if rect.width == 0 || rect.height == 0 {
+        return Vec::new();
+    }
+    let extent = Extent {
+        left: rect.x,
+        top: rect.y,
+        width: (rect.width - 1) as f32,
+        height: (rect.height - 1) as f32,
+    };
+    ordered_dither(&rect.canvas_pixels(width, height), &extent, from, to, direction)
+}
+
+// The box a gradient runs across: its top-left pixel and the distance to its
+// last column and row
+struct Extent {
+    left: u16,
+    top: u16,
+    width: f32,
+    height: f32,
+}
+
+impl Extent {
+    // Distance of a pixel along the gradient, and the distance at which it is all `to`
+    fn progress(&self, direction: GradientDirection, x: u16, y: u16) -> (f32, f32) {
+        let (dx, dy) = ((x - self.left) as f32, (y - self.top) as f32);
+        match direction {
+            GradientDirection::Horizontal => (dx, self.width),
+            GradientDirection::Vertical => (dy, self.height),
+            GradientDirection::Radial => {
+                let (cx, cy) = (self.width / 2.0, self.height / 2.0);
+                ((dx - cx).hypot(dy - cy), cx.hypot(cy))
+            }
+        }
+    }
+}
+
+fn ordered_dither(region: &[(u16, u16)], extent: &Extent, from: [u8; 4], to: [u8; 4], direction: GradientDirection) -> Vec<(u16, u16, [u8; 4])> {
+    region.iter().map(|&(x, y)| {
+        let (position, steps) = extent.progress(direction, x, y);
+        // `to` once the position passes the threshold's midpoint: 16ths of the
+        // way along, compared in halves so linear gradients stay exact
+        let threshold = BAYER_4X4[y as usize % 4][x as usize % 4];
+        let color = if position * 32.0 > (threshold * 2 + 1) as f32 * steps { to } else { from };
+        (x, y, color)
+    }).collect()
+}
+
+/// Every pixel of `region` with its color in a dithered gradient from `from`
+/// to `to` along `direction`, spanning the region's bounding box. A radial
+/// gradient is `from` at the centre and `to` at the corners.
+pub fn gradient_fill(
+    region: &[(u16, u16)],
+    from: [u8; 4],
+    to: [u8; 4],
+    direction: GradientDirection,
+    dither: DitherPattern,
+) -> Vec<(u16, u16, [u8; 4])> {
+    let Some(&(first_x, first_y)) = region.first() else { return Vec::new() };
+    let (mut left, mut top, mut right, mut bottom) = (first_x, first_y, first_x, first_y);
+    for &(x, y) in region {
+        (left, top) = (left.min(x), top.min(y));
+        (right, bottom) = (right.max(x), bottom.max(y));
+    }
+    let extent = Extent { left, top, width: (right - left) as f32, height: (bottom - top) as f32 };
+
+    match dither {
+        DitherPattern::Ordered => ordered_dither(region, &extent, from, to, direction),
+        DitherPattern::ErrorDiffusion => {
+            // Floyd-Steinberg over the bounding box in reading order; error
+            // spilling onto pixels outside the region is dropped
+            let (columns, rows) = ((right - left) as usize + 1, (bottom - top) as usize + 1);
+            let mut inside = vec![false; columns * rows];
+            for &(x, y) in region {
+                inside[(y - top) as usize * columns + (x - left) as usize] = true;
+            }
+            let mut error = vec![0.0f32; columns * rows];
+            let mut pixels = Vec::with_capacity(region.len());
+            for row in 0..rows {
+                for column in 0..columns {
+                    let index = row * columns + column;
+                    if !inside[index] {
+                        continue;
+                    }
+                    let (x, y) = (left + column as u16, top + row as u16);
+                    let (position, steps) = extent.progress(direction, x, y);
+                    let target = if steps > 0.0 { position / steps } else { 0.0 };
+                    let value = target + error[index];
+                    let chosen = if value >= 0.5 { 1.0 } else { 0.0 };
+                    pixels.push((x, y, if chosen == 1.0 { to } else { from }));
+
+                    let spill = value - chosen;
+                    let mut spread = |c: isize, r: usize, weight: f32| {
+                        if c >= 0 && (c as usize) < columns && r < rows {
+                            error[r * columns + c as usize] += spill * weight / 16.0;
+                        }
+                    };
+                    let c = column as isize;
+                    spread(c + 1, row, 7.0);
+                    spread(c - 1, row + 1, 3.0);
+                    spread(c, row + 1, 5.0);
+                    spread(c + 1, row + 1, 1.0);
+                }
+            }
+            pixels
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dither_gradient_runs_from_one_color_to_the_other() {
+        let (black, white) = ([0, 0, 0, 255], [255, 255, 255, 255]);
+        let rect = Rect { x: 0, y: 0, width: 17, height: 4 };
+        let pixels = dither_gradient(&rect, black, white, GradientDirection::Horizontal, 64, 64);
+        assert_eq!(pixels.len(), 17 * 4);
+
+        let column = |x: u16| pixels.iter().filter(|p| p.0 == x).map(|p| p.2).collect::<Vec<_>>();
+        assert!(column(0).iter().all(|&c| c == black));
+        assert!(column(16).iter().all(|&c| c == white));
+        // The share of `to` grows along the gradient
+        let whites = |xs: std::ops::Range<u16>| pixels.iter().filter(|p| xs.contains(&p.0) && p.2 == white).count();
+        assert!(whites(1..5) < whites(6..10) && whites(6..10) < whites(12..16));
+
+        let pixels = dither_gradient(&rect, black, white, GradientDirection::Vertical, 64, 64);
+        assert!(pixels.iter().filter(|p| p.1 == 0).all(|p| p.2 == black));
+        assert!(pixels.iter().filter(|p| p.1 == 3).all(|p| p.2 == white));
+    }
+
+    #[test]
+    fn test_dither_gradient_only_covers_the_canvas() {
+        let (black, white) = ([0, 0, 0, 255], [255, 255, 255, 255]);
+        let huge = Rect { x: 2, y: 0, width: u16::MAX, height: u16::MAX };
+        let pixels = dither_gradient(&huge, black, white, GradientDirection::Horizontal, 8, 4);
+        assert_eq!(pixels.len(), 6 * 4);
+        assert!(pixels.iter().all(|p| p.0 >= 2 && p.0 < 8 && p.1 < 4));
+        // Progress is measured across the whole rect, so the visible corner stays near `from`
+        assert!(pixels.iter().all(|p| p.2 == black));
+
+        // A clipped gradient matches the same pixels of the unclipped one
+        let rect = Rect { x: 0, y: 0, width: 16, height: 2 };
+        let full = dither_gradient(&rect, black, white, GradientDirection::Horizontal, 16, 2);
+        let clipped = dither_gradient(&rect, black, white, GradientDirection::Horizontal, 10, 2);
+        assert_eq!(clipped[..], full.iter().copied().filter(|p| p.0 < 10).collect::<Vec<_>>()[..]);
+        assert!(dither_gradient(&Rect { x: 9, y: 0, width: 4, height: 4 }, black, white, GradientDirection::Radial, 8, 8).is_empty());
+    }
+
+    #[test]
+    fn test_gradient_fill_dithers_radially_and_by_error_diffusion() {
+        let (black, white) = ([0, 0, 0, 255], [255, 255, 255, 255]);
+        let region: Vec<(u16, u16)> = (10..27).flat_map(|y| (10..27).map(move |x| (x, y))).collect();
+        let color_at = |pixels: &[(u16, u16, [u8; 4])], x: u16, y: u16| pixels.iter().find(|p| (p.0, p.1) == (x, y)).unwrap().2;
+
+        let radial = gradient_fill(&region, black, white, GradientDirection::Radial, DitherPattern::Ordered);
+        assert_eq!(radial.len(), region.len());
+        assert_eq!(color_at(&radial, 18, 18), black);
+        for (x, y) in [(10, 10), (26, 10), (10, 26), (26, 26)] {
+            assert_eq!(color_at(&radial, x, y), white);
+        }
+
+        // Error diffusion keeps the ends solid and the share of `to` close to the gradient's
+        let diffused = gradient_fill(&region, black, white, GradientDirection::Horizontal, DitherPattern::ErrorDiffusion);
+        assert!((10..27).all(|y| color_at(&diffused, 10, y) == black && color_at(&diffused, 26, y) == white));
+        let whites = diffused.iter().filter(|p| p.2 == white).count();
+        assert!(whites.abs_diff(region.len() / 2) <= 17);
+
+        // Only the region is filled, whatever its shape
+        let ring: Vec<(u16, u16)> = region.iter().copied().filter(|&(x, y)| x == 10 || y == 10).collect();
+        assert_eq!(gradient_fill(&ring, black, white, GradientDirection::Vertical, DitherPattern::ErrorDiffusion).len(), ring.len());
+        assert!(gradient_fill(&[], black, white, GradientDirection::Radial, DitherPattern::Ordered).is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+use crate::operations::{DrawingOperation, Rect};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PixelBookEvent {
+    pub filename: String,
+    pub timestamp: DateTime<Utc>,
+    pub event_type: EventType,
+    /// The client behind the change, from its `User-Agent`, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(tag = "type")]
+pub enum EventType {
+    #[serde(rename = "drawing_operation")]
+    DrawingOperation { operation: DrawingOperation },
+    /// A burst of operations coalesced into one event; `region` bounds every
+    /// pixel they changed, across all frames, and is `None` when nothing changed
+    #[serde(rename = "operations_applied")]
+    OperationsApplied { count: usize, region: Option<Rect> },
+    #[serde(rename = "book_saved")]
+    BookSaved,
+    #[serde(rename = "book_loaded")]
+    BookLoaded,
+    #[serde(rename = "book_deleted")]
+    BookDeleted,
+    #[serde(rename = "book_restored")]
+    BookRestored,
+    /// Sent on the old name; the book now lives at `new_filename`
+    #[serde(rename = "book_renamed")]
+    BookRenamed { new_filename: String },
+    #[serde(rename = "frame_changed")]
+    FrameChanged { frame_index: usize },
+    #[serde(rename = "connected")]
+    Connected,
+    #[serde(rename = "heartbeat")]
+    Heartbeat,
+    /// An integrity scan found the book damaged
+    #[serde(rename = "integrity_problem")]
+    IntegrityProblem { problem: String },
+}
+
+impl EventType {
+    /// Every value of the serialized `type` tag
+    pub const NAMES: [&'static str; 11] = [
+        "drawing_operation", "operations_applied", "book_saved", "book_loaded", "book_deleted",
+        "book_restored", "book_renamed", "frame_changed", "connected", "heartbeat", "integrity_problem",
+    ];
+
+    /// The serialized `type` tag of this event
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventType::DrawingOperation { .. } => "drawing_operation",
+            EventType::OperationsApplied { .. } => "operations_applied",
+            EventType::BookSaved => "book_saved",
+            EventType::BookLoaded => "book_loaded",
+            EventType::BookDeleted => "book_deleted",
+            EventType::BookRestored => "book_restored",
+            EventType::BookRenamed { .. } => "book_renamed",
+            EventType::FrameChanged { .. } => "frame_changed",
+            EventType::Connected => "connected",
+            EventType::Heartbeat => "heartbeat",
+            EventType::IntegrityProblem { .. } => "integrity_problem",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operations::Point;
+
+    #[test]
+    fn test_event_round_trip_keeps_operation_details() {
+        let event = PixelBookEvent {
+            filename: "test.pxl".to_string(),
+            timestamp: Utc::now(),
+            event_type: EventType::DrawingOperation {
+                operation: DrawingOperation::CopyRegion {
+                    src_frame: 0,
+                    src_rect: Rect { x: 1, y: 2, width: 3, height: 4 },
+                    dst_frame: 1,
+                    dst_point: Point { x: 5, y: 6 },
+                },
+            },
+            origin: None,
+        };
+
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains(r#""type":"drawing_operation""#));
+        assert!(json.contains(r#""type":"copy_region""#));
+
+        let parsed: PixelBookEvent = serde_json::from_str(&json).unwrap();
+        match parsed.event_type {
+            EventType::DrawingOperation { operation: DrawingOperation::CopyRegion { dst_frame, .. } } => {
+                assert_eq!(dst_frame, 1);
+            }
+            other => panic!("Unexpected event type: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_names_match_serialized_tags() {
+        let events = [
+            EventType::OperationsApplied { count: 1, region: None },
+            EventType::BookSaved,
+            EventType::BookRenamed { new_filename: "b.pxl".to_string() },
+            EventType::FrameChanged { frame_index: 2 },
+            EventType::Heartbeat,
+            EventType::IntegrityProblem { problem: "truncated".to_string() },
+        ];
+        for event_type in events {
+            let json = serde_json::to_value(&event_type).unwrap();
+            assert_eq!(json["type"], event_type.name());
+            assert!(EventType::NAMES.contains(&event_type.name()));
+        }
+    }
+}
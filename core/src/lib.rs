@@ -0,0 +1,41 @@
+//! Shared data model for PIXL: pixel books, drawing operations, and the
+//! event types exchanged between the server, viewer, and MCP bridge, along
+//! with the rasterizers the server draws with and the viewer previews with.
+
+pub mod pixel_book;
+pub mod metadata;
+pub mod operations;
+pub mod events;
+pub mod diff;
+pub mod quantize;
+pub mod minimap;
+pub mod critique;
+pub mod dither;
+pub mod curves;
+pub mod transform;
+pub mod palette;
+pub mod library;
+pub mod blend;
+pub mod noise;
+pub mod raster;
+pub mod tilemap;
+pub mod context;
+
+pub use pixel_book::*;
+pub use metadata::*;
+pub use operations::*;
+pub use events::*;
+pub use diff::*;
+pub use quantize::*;
+pub use minimap::*;
+pub use critique::*;
+pub use dither::*;
+pub use curves::*;
+pub use transform::*;
+pub use palette::*;
+pub use library::*;
+pub use blend::*;
+pub use noise::*;
+pub use raster::*;
+pub use tilemap::*;
+pub use context::*;
@@ -0,0 +1,171 @@
+use crate::pixel_book::PixelBookInfo;
+use serde::{Deserialize, Serialize};
+
+/// What `GET /books` sorts by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum BookSort {
+    #[default]
+    Name,
+    Modified,
+    Created,
+    Size,
+    Frames,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Query of `GET /books`. Every filter is optional; books are sorted by name
+/// unless `sort` says otherwise.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BookFilter {
+    /// Case-insensitive part of the filename or title
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Only books with this tag
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_width: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_height: Option<u16>,
+    /// Only books with more than this many frames
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frames_gt: Option<usize>,
+    #[serde(default)]
+    pub sort: BookSort,
+    #[serde(default)]
+    pub order: SortOrder,
+    /// Most books to return, after skipping `offset`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: usize,
+    /// Include books in sub-folders, named by their relative path
+    #[serde(default)]
+    pub recursive: bool,
+}
+
+/// Response of `GET /books`: one page of books and how many matched in all
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BookList {
+    pub books: Vec<PixelBookInfo>,
+    #[serde(default)]
+    pub total: usize,
+}
+
+impl BookFilter {
+    pub fn matches(&self, book: &PixelBookInfo) -> bool {
+        let name_matches = self.name.as_ref().is_none_or(|name| {
+            let name = name.to_lowercase();
+            book.filename.to_lowercase().contains(&name)
+                || book.details.title.as_ref().is_some_and(|title| title.to_lowercase().contains(&name))
+        });
+        let tag_matches = self.tag.as_ref().is_none_or(|tag| book.details.tags.contains(&tag.trim().to_lowercase()));
+
+        name_matches
+            && tag_matches
+            && self.min_width.is_none_or(|width| book.width >= width)
+            && self.min_height.is_none_or(|height| book.height >= height)
+            && self.frames_gt.is_none_or(|frames| book.frames > frames)
+    }
+
+    /// The requested page of matching books in order, with the number of matches
+    pub fn apply(&self, books: Vec<PixelBookInfo>) -> BookList {
+        let mut books: Vec<PixelBookInfo> = books.into_iter().filter(|book| self.matches(book)).collect();
+        books.sort_by(|a, b| {
+            let ordering = match self.sort {
+                BookSort::Name => a.filename.cmp(&b.filename),
+                BookSort::Modified => a.modified.cmp(&b.modified),
+                BookSort::Created => a.created.cmp(&b.created),
+                BookSort::Size => a.size.cmp(&b.size),
+                BookSort::Frames => a.frames.cmp(&b.frames),
+            };
+            // Ties fall back to the filename so pages are stable
+            let ordering = ordering.then_with(|| a.filename.cmp(&b.filename));
+            match self.order {
+                SortOrder::Asc => ordering,
+                SortOrder::Desc => ordering.reverse(),
+            }
+        });
+
+        let total = books.len();
+        let books = books.into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect();
+        BookList { books, total }
+    }
+}
+
+/// A book filename as a single URL path segment. Books in sub-folders keep
+/// their `/` separators encoded, so `sprites/hero.pxl` becomes
+/// `sprites%2Fhero.pxl`; plain names pass through unchanged.
+pub fn filename_segment(filename: &str) -> String {
+    let mut segment = String::with_capacity(filename.len());
+    for byte in filename.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            segment.push(byte as char);
+        } else {
+            segment.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    segment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::BookDetails;
+
+    fn book(filename: &str, width: u16, frames: usize, minutes: i64) -> PixelBookInfo {
+        let time = chrono::DateTime::UNIX_EPOCH + chrono::Duration::minutes(minutes);
+        PixelBookInfo {
+            filename: filename.to_string(),
+            size: 0,
+            created: time,
+            modified: time,
+            frames,
+            width,
+            height: width,
+            version: 1,
+            lock: None,
+            details: BookDetails::default(),
+        }
+    }
+
+    #[test]
+    fn test_filter_sorts_and_paginates() {
+        let books = || vec![book("b.pxl", 16, 1, 3), book("a.pxl", 32, 4, 1), book("c.pxl", 64, 8, 2)];
+        let names = |list: BookList| list.books.iter().map(|b| b.filename.clone()).collect::<Vec<_>>();
+
+        assert_eq!(names(BookFilter::default().apply(books())), vec!["a.pxl", "b.pxl", "c.pxl"]);
+
+        let newest = BookFilter { sort: BookSort::Modified, order: SortOrder::Desc, ..Default::default() };
+        assert_eq!(names(newest.apply(books())), vec!["b.pxl", "c.pxl", "a.pxl"]);
+
+        let big = BookFilter { min_width: Some(32), frames_gt: Some(1), limit: Some(1), offset: 1, ..Default::default() };
+        let page = big.apply(books());
+        assert_eq!(page.total, 2);
+        assert_eq!(names(page), vec!["c.pxl"]);
+
+        let named = BookFilter { name: Some("B.PXL".to_string()), ..Default::default() };
+        assert_eq!(names(named.apply(books())), vec!["b.pxl"]);
+    }
+
+    #[test]
+    fn test_filename_segment_encodes_folders() {
+        assert_eq!(filename_segment("hero.pxl"), "hero.pxl");
+        assert_eq!(filename_segment("sprites/hero 2.pxl"), "sprites%2Fhero%202.pxl");
+    }
+}
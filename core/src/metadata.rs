@@ -0,0 +1,254 @@
+use crate::palette::Palette;
+use crate::tilemap::Tileset;
+use serde::{Deserialize, Serialize};
+
+/// Book-level settings stored next to the pixel data. Only `.pxl` format v2
+/// and later can hold metadata, and only v3 a palette; books with default
+/// metadata are still written as v1.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BookMetadata {
+    #[serde(default)]
+    pub permissions: Permissions,
+    /// Title, author, tags and description, stored as top-level JSON keys
+    #[serde(flatten)]
+    pub details: BookDetails,
+    /// Colors drawing operations can refer to by index. Kept in its own
+    /// binary block in `.pxl` files rather than the JSON metadata.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub palette: Option<Palette>,
+    /// Tile size when the book is a tileset for tilemaps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tileset: Option<Tileset>,
+}
+
+impl BookMetadata {
+    /// True when there is nothing worth storing
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+pub const MAX_TITLE_LEN: usize = 200;
+pub const MAX_AUTHOR_LEN: usize = 200;
+pub const MAX_DESCRIPTION_LEN: usize = 4000;
+pub const MAX_TAGS: usize = 32;
+pub const MAX_TAG_LEN: usize = 50;
+pub const MAX_FRAME_TAGS: usize = 64;
+
+/// Order a [`FrameTag`]'s frames play in, named as in Aseprite
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum TagDirection {
+    #[default]
+    Forward,
+    Reverse,
+    Pingpong,
+}
+
+/// A named run of frames, such as one animation of a character sheet.
+/// Exported as Aseprite `frameTags`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FrameTag {
+    pub name: String,
+    /// First frame, 0-based
+    pub from: usize,
+    /// Last frame, inclusive
+    pub to: usize,
+    #[serde(default)]
+    pub direction: TagDirection,
+}
+
+/// Descriptive fields that make a library of books searchable
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BookDetails {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Lowercase and unique
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Named frame ranges, with unique names
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub frame_tags: Vec<FrameTag>,
+}
+
+impl BookDetails {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// An error naming the first frame tag that reaches past `frame_count` frames
+    pub fn check_frame_tags(&self, frame_count: usize) -> Result<(), String> {
+        match self.frame_tags.iter().find(|tag| tag.to >= frame_count) {
+            Some(tag) => Err(format!("Frame tag '{}' ends at frame {}; the book has {} frames", tag.name, tag.to, frame_count)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Body of `PATCH /books/:filename/metadata`. Omitted fields are left as
+/// they are; an empty string or list clears a field.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct UpdateDetailsRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame_tags: Option<Vec<FrameTag>>,
+}
+
+/// Trimmed `value`, `None` when blank, or an error past `max_len` characters
+fn text_field(name: &str, value: &str, max_len: usize) -> Result<Option<String>, String> {
+    let value = value.trim();
+    if value.chars().count() > max_len {
+        return Err(format!("{} is longer than {} characters", name, max_len));
+    }
+    Ok((!value.is_empty()).then(|| value.to_string()))
+}
+
+impl UpdateDetailsRequest {
+    /// The details after this update, or why it is invalid
+    pub fn apply(&self, details: &BookDetails) -> Result<BookDetails, String> {
+        let mut updated = details.clone();
+        if let Some(title) = &self.title {
+            updated.title = text_field("Title", title, MAX_TITLE_LEN)?;
+        }
+        if let Some(author) = &self.author {
+            updated.author = text_field("Author", author, MAX_AUTHOR_LEN)?;
+        }
+        if let Some(description) = &self.description {
+            updated.description = text_field("Description", description, MAX_DESCRIPTION_LEN)?;
+        }
+        if let Some(tags) = &self.tags {
+            updated.tags.clear();
+            for tag in tags {
+                let Some(tag) = text_field("Each tag", &tag.to_lowercase(), MAX_TAG_LEN)? else { continue };
+                if !updated.tags.contains(&tag) {
+                    updated.tags.push(tag);
+                }
+            }
+            if updated.tags.len() > MAX_TAGS {
+                return Err(format!("A book can have at most {} tags", MAX_TAGS));
+            }
+        }
+        if let Some(frame_tags) = &self.frame_tags {
+            if frame_tags.len() > MAX_FRAME_TAGS {
+                return Err(format!("A book can have at most {} frame tags", MAX_FRAME_TAGS));
+            }
+            updated.frame_tags.clear();
+            for tag in frame_tags {
+                let Some(name) = text_field("Each frame tag name", &tag.name, MAX_TAG_LEN)? else {
+                    return Err("Frame tag names cannot be empty".to_string());
+                };
+                if tag.from > tag.to {
+                    return Err(format!("Frame tag '{}' starts after it ends", name));
+                }
+                if updated.frame_tags.iter().any(|existing| existing.name == name) {
+                    return Err(format!("Frame tag '{}' is defined twice", name));
+                }
+                updated.frame_tags.push(FrameTag { name, ..tag.clone() });
+            }
+        }
+        Ok(updated)
+    }
+}
+
+/// Write protection for a book, enforced by the server
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Permissions {
+    /// Reject every modification until cleared
+    #[serde(default)]
+    pub read_only: bool,
+    /// SHA-256 hex digest of the key required to modify the book, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner_key_hash: Option<String>,
+}
+
+impl Permissions {
+    pub fn is_protected(&self) -> bool {
+        self.read_only || self.owner_key_hash.is_some()
+    }
+}
+
+/// Body of `PUT /books/:filename/permissions`. The key is sent in plain text
+/// and only its hash is stored; `None` removes the owner.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SetPermissionsRequest {
+    #[serde(default)]
+    pub read_only: bool,
+    #[serde(default)]
+    pub owner_key: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_details_keeps_omitted_fields_and_clears_empty_ones() {
+        let details = BookDetails {
+            title: Some("Slime".to_string()),
+            author: Some("Ada".to_string()),
+            ..Default::default()
+        };
+        let update = UpdateDetailsRequest {
+            author: Some("  ".to_string()),
+            tags: Some(vec!["Enemy".to_string(), "enemy ".to_string(), "".to_string(), "forest".to_string()]),
+            ..Default::default()
+        };
+
+        let updated = update.apply(&details).unwrap();
+        assert_eq!(updated.title.as_deref(), Some("Slime"));
+        assert_eq!(updated.author, None);
+        assert_eq!(updated.tags, vec!["enemy", "forest"]);
+
+        let too_long = UpdateDetailsRequest { title: Some("x".repeat(MAX_TITLE_LEN + 1)), ..Default::default() };
+        assert!(too_long.apply(&details).is_err());
+    }
+
+    #[test]
+    fn test_frame_tags_are_validated() {
+        let tag = |name: &str, from, to| FrameTag { name: name.to_string(), from, to, direction: TagDirection::Forward };
+        let update = |tags| UpdateDetailsRequest { frame_tags: Some(tags), ..Default::default() };
+
+        let details = update(vec![tag(" walk ", 0, 3), tag("jump", 4, 4)]).apply(&BookDetails::default()).unwrap();
+        assert_eq!(details.frame_tags[0].name, "walk");
+        assert!(details.check_frame_tags(5).is_ok());
+        assert!(details.check_frame_tags(4).is_err());
+
+        assert!(update(vec![tag("walk", 3, 1)]).apply(&details).is_err());
+        assert!(update(vec![tag("walk", 0, 1), tag("walk", 2, 3)]).apply(&details).is_err());
+        assert!(update(vec![tag(" ", 0, 1)]).apply(&details).is_err());
+        assert!(update(Vec::new()).apply(&details).unwrap().frame_tags.is_empty());
+
+        let json: FrameTag = serde_json::from_str(r#"{"name":"idle","from":0,"to":1,"direction":"pingpong"}"#).unwrap();
+        assert_eq!(json.direction, TagDirection::Pingpong);
+    }
+
+    #[test]
+    fn test_details_are_top_level_metadata_keys() {
+        let metadata = BookMetadata {
+            details: BookDetails { title: Some("Slime".to_string()), ..Default::default() },
+            ..Default::default()
+        };
+        let json = serde_json::to_value(&metadata).unwrap();
+        assert_eq!(json["title"], "Slime");
+        assert_eq!(serde_json::from_value::<BookMetadata>(json).unwrap(), metadata);
+        assert!(BookMetadata::default().is_empty());
+    }
+}
@@ -0,0 +1,246 @@
+use crate::pixel_book::{Frame, Pixel, PixelBook};
+use serde::{Deserialize, Serialize};
+
+/// Symbols given to colors in ASCII minimaps, in order of first appearance
+const SYMBOLS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+/// Symbol for colors beyond the first [`SYMBOLS`]`.len()`
+const OVERFLOW_SYMBOL: char = '?';
+/// Symbol for fully transparent pixels
+const TRANSPARENT_SYMBOL: char = '.';
+/// Cell of a [`ColorGrid`] for fully transparent pixels
+pub const GRID_TRANSPARENT: &str = ".";
+
+/// Size of a `width` x `height` image shrunk to fit in `max_size` x `max_size`,
+/// keeping its aspect ratio; images that already fit keep their size
+pub fn thumbnail_size(width: u16, height: u16, max_size: u16) -> (u16, u16) {
+    let max_size = max_size.max(1);
+    let longest = width.max(height).max(1);
+    if longest <= max_size {
+        return (width, height);
+    }
+    let scale = |side: u16| ((side as u32 * max_size as u32 / longest as u32) as u16).max(1);
+    (scale(width), scale(height))
+}
+
+/// `frame` shrunk with nearest-neighbour sampling to fit in `max_size` x
+/// `max_size`, along with its new size
+pub fn thumbnail(frame: &Frame, width: u16, height: u16, max_size: u16) -> (Frame, u16, u16) {
+    let (thumb_width, thumb_height) = thumbnail_size(width, height, max_size);
+    let mut thumb = Frame::new(frame.index, thumb_width, thumb_height);
+    for y in 0..thumb_height {
+        for x in 0..thumb_width {
+            let source_x = (x as u32 * width as u32 / thumb_width as u32) as u16;
+            let source_y = (y as u32 * height as u32 / thumb_height as u32) as u16;
+            if let Some(pixel) = frame.get_pixel(source_x, source_y, width) {
+                thumb.set_pixel(x, y, thumb_width, pixel);
+            }
+        }
+    }
+    (thumb, thumb_width, thumb_height)
+}
+
+/// Text overviews of every frame of a book, sharing one color legend
+#[derive(Debug, Clone)]
+pub struct AsciiMinimaps {
+    /// One map per frame, a line per row
+    pub frames: Vec<String>,
+    /// The color each symbol stands for
+    pub legend: Vec<(char, [u8; 4])>,
+}
+
+/// Draws each frame, shrunk to fit in `max_size` x `max_size`, as text with
+/// one symbol per color and `.` for transparency
+pub fn ascii_minimaps(book: &PixelBook, max_size: u16) -> AsciiMinimaps {
+    let mut legend: Vec<(char, [u8; 4])> = Vec::new();
+    let frames = book.frames.iter()
+        .map(|frame| {
+            let (thumb, width, height) = thumbnail(frame, book.width, book.height, max_size);
+            if width == 0 || height == 0 {
+                return String::new();
+            }
+            let mut map = String::with_capacity((width as usize + 1) * height as usize);
+            for row in thumb.pixels.chunks_exact(width as usize * 4) {
+                for p in row.chunks_exact(4) {
+                    let color = [p[0], p[1], p[2], p[3]];
+                    if color[3] == 0 {
+                        map.push(TRANSPARENT_SYMBOL);
+                        continue;
+                    }
+                    let symbol = match legend.iter().find(|(_, known)| *known == color) {
+                        Some((symbol, _)) => *symbol,
+                        None if legend.len() < SYMBOLS.len() => {
+                            let symbol = SYMBOLS[legend.len()] as char;
+                            legend.push((symbol, color));
+                            symbol
+                        }
+                        None => OVERFLOW_SYMBOL,
+                    };
+                    map.push(symbol);
+                }
+                map.push('\n');
+            }
+            map
+        })
+        .collect();
+
+    AsciiMinimaps { frames, legend }
+}
+
+/// A frame as rows of hex colors, served by `GET /books/:filename/frames/:i/grid`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ColorGrid {
+    pub frame: usize,
+    /// Size of the grid, smaller than the book when downsampled
+    pub width: u16,
+    pub height: u16,
+    /// Cell used for fully transparent pixels
+    pub transparent: String,
+    /// `#rrggbb` for opaque pixels, `#rrggbbaa` for partly transparent ones
+    pub rows: Vec<Vec<String>>,
+}
+
+/// One pixel's color, served by `GET /books/:filename/frames/:i/pixels/:x/:y`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PixelColor {
+    pub frame: usize,
+    pub x: u16,
+    pub y: u16,
+    pub color: [u8; 4],
+    /// As a [`ColorGrid`] cell
+    pub hex: String,
+}
+
+/// How `GET /books/:filename/frames/:i` encodes the frame's pixels
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum FrameEncoding {
+    /// Text rows of [`ColorGrid`] cells, with runs written as `count*cell`
+    #[default]
+    #[serde(rename = "rle")]
+    Rle,
+    /// The raw RGBA bytes, row by row, in standard base64
+    #[serde(rename = "base64")]
+    Base64,
+}
+
+/// A whole frame in a compact encoding
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct EncodedFrame {
+    pub frame: usize,
+    pub width: u16,
+    pub height: u16,
+    pub encoding: FrameEncoding,
+    pub data: String,
+    /// The frame's own playback duration, if it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u32>,
+}
+
+/// `pixel` as a [`ColorGrid`] cell
+pub fn hex_color(pixel: Pixel) -> String {
+    match pixel.a {
+        255 => format!("#{:02x}{:02x}{:02x}", pixel.r, pixel.g, pixel.b),
+        0 => GRID_TRANSPARENT.to_string(),
+        a => format!("#{:02x}{:02x}{:02x}{:02x}", pixel.r, pixel.g, pixel.b, a),
+    }
+}
+
+/// `frame` as run-length text: one line per row of space-separated
+/// [`ColorGrid`] cells, where `count*cell` stands for a run of one color
+pub fn run_length_text(frame: &Frame, width: u16, height: u16) -> String {
+    let mut text = String::new();
+    for y in 0..height {
+        let mut runs: Vec<(String, usize)> = Vec::new();
+        for x in 0..width {
+            let cell = hex_color(frame.get_pixel(x, y, width).unwrap_or_else(Pixel::transparent));
+            match runs.last_mut() {
+                Some((last, count)) if *last == cell => *count += 1,
+                _ => runs.push((cell, 1)),
+            }
+        }
+        let row: Vec<String> = runs.into_iter()
+            .map(|(cell, count)| if count == 1 { cell } else { format!("{}*{}", count, cell) })
+            .collect();
+        text.push_str(&row.join(" "));
+        text.push('\n');
+    }
+    text
+}
+
+/// `frame` as a [`ColorGrid`], shrunk with nearest-neighbour sampling to fit
+/// in `max_size` x `max_size` when given
+pub fn color_grid(frame: &Frame, index: usize, width: u16, height: u16, max_size: Option<u16>) -> ColorGrid {
+    let (frame, width, height) = match max_size {
+        Some(max_size) => thumbnail(frame, width, height, max_size),
+        None => (frame.clone(), width, height),
+    };
+    let rows = (0..height)
+        .map(|y| {
+            (0..width)
+                .map(|x| hex_color(frame.get_pixel(x, y, width).unwrap_or_else(Pixel::transparent)))
+                .collect()
+        })
+        .collect();
+
+    ColorGrid { frame: index, width, height, transparent: GRID_TRANSPARENT.to_string(), rows }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thumbnail_size_keeps_aspect_ratio() {
+        assert_eq!(thumbnail_size(8, 4, 16), (8, 4));
+        assert_eq!(thumbnail_size(64, 32, 16), (16, 8));
+        assert_eq!(thumbnail_size(200, 1, 10), (10, 1));
+    }
+
+    #[test]
+    fn test_color_grid_uses_hex_and_a_transparency_token() {
+        let mut book = PixelBook::new("grid.pxl".to_string(), 4, 2, 1);
+        book.frames[0].set_pixel(0, 0, 4, Pixel::new(255, 0, 0, 255));
+        book.frames[0].set_pixel(3, 1, 4, Pixel::new(0, 16, 255, 128));
+
+        let grid = color_grid(&book.frames[0], 0, 4, 2, None);
+        assert_eq!(grid.rows, vec![
+            vec!["#ff0000", ".", ".", "."],
+            vec![".", ".", ".", "#0010ff80"],
+        ]);
+
+        let grid = color_grid(&book.frames[0], 0, 4, 2, Some(2));
+        assert_eq!((grid.width, grid.height), (2, 1));
+        assert_eq!(grid.rows, vec![vec!["#ff0000", "."]]);
+    }
+
+    #[test]
+    fn test_run_length_text_collapses_runs() {
+        let mut book = PixelBook::new("runs.pxl".to_string(), 5, 2, 1);
+        for x in 0..3 {
+            book.frames[0].set_pixel(x, 0, 5, Pixel::new(255, 0, 0, 255));
+        }
+        book.frames[0].set_pixel(4, 1, 5, Pixel::new(0, 16, 255, 128));
+
+        assert_eq!(run_length_text(&book.frames[0], 5, 2), "3*#ff0000 2*.\n4*. #0010ff80\n");
+    }
+
+    #[test]
+    fn test_ascii_minimaps_share_a_legend() {
+        let mut book = PixelBook::new("map.pxl".to_string(), 4, 2, 2);
+        book.frames[0].set_pixel(0, 0, 4, Pixel::new(255, 0, 0, 255));
+        book.frames[1].set_pixel(3, 1, 4, Pixel::new(0, 0, 255, 255));
+        book.frames[1].set_pixel(0, 0, 4, Pixel::new(255, 0, 0, 255));
+
+        let minimaps = ascii_minimaps(&book, 8);
+        assert_eq!(minimaps.frames[0], "A...\n....\n");
+        assert_eq!(minimaps.frames[1], "A...\n...B\n");
+        assert_eq!(minimaps.legend, vec![('A', [255, 0, 0, 255]), ('B', [0, 0, 255, 255])]);
+
+        // Shrinking samples the top-left pixel of each block
+        let minimaps = ascii_minimaps(&book, 2);
+        assert_eq!(minimaps.frames[0], "A.\n");
+    }
+}
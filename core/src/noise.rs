@@ -0,0 +1,52 @@
+/// Pixels of `region` picked at random to cover about `density` (0 to 1) of
+/// it, each with a random color from `colors`. Every pixel is decided by
+/// `seed` and its canvas coordinates alone, so the same seed repeats the same
+/// speckles and neighbouring fills line up.
+pub fn noise_fill(region: &[(u16, u16)], colors: &[[u8; 4]], density: f32, seed: u64) -> Vec<(u16, u16, [u8; 4])> {
+    if colors.is_empty() {
+        return Vec::new();
+    }
+    region.iter()
+        .filter_map(|&(x, y)| {
+            let hash = splitmix64(seed ^ ((x as u64) << 32 | y as u64));
+            // The top 24 bits decide whether the pixel is painted, the rest pick its color
+            let roll = (hash >> 40) as f32 / (1u32 << 24) as f32;
+            (roll < density).then(|| (x, y, colors[(hash % colors.len() as u64) as usize]))
+        })
+        .collect()
+}
+
+// SplitMix64's output function: spreads nearby inputs over the whole range
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noise_fill_is_reproducible_and_follows_density() {
+        let region: Vec<(u16, u16)> = (0..64).flat_map(|y| (0..64).map(move |x| (x, y))).collect();
+        let (green, brown) = ([40, 160, 40, 255], [120, 80, 40, 255]);
+
+        let speckles = noise_fill(&region, &[green, brown], 0.25, 7);
+        assert_eq!(speckles, noise_fill(&region, &[green, brown], 0.25, 7));
+        assert_ne!(speckles, noise_fill(&region, &[green, brown], 0.25, 8));
+        // Roughly a quarter of the pixels, using both colors about equally
+        assert!((900..1150).contains(&speckles.len()), "{}", speckles.len());
+        let greens = speckles.iter().filter(|p| p.2 == green).count();
+        assert!(greens * 3 > speckles.len() && greens * 3 < speckles.len() * 2);
+
+        // Pixels are decided by position, not by where they fall in the region
+        let half = &region[..region.len() / 2];
+        assert_eq!(noise_fill(half, &[green, brown], 0.25, 7), speckles[..speckles.iter().filter(|p| p.1 < 32).count()]);
+
+        assert!(noise_fill(&region, &[green], 0.0, 7).is_empty());
+        assert_eq!(noise_fill(&region, &[green], 1.0, 7).len(), region.len());
+        assert!(noise_fill(&region, &[], 1.0, 7).is_empty());
+    }
+}
@@ -0,0 +1,658 @@
+use crate::palette::ColorRef;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(tag = "type")]
+pub enum DrawingOperation {
+    #[serde(rename = "draw_pixel")]
+    DrawPixel {
+        frame: usize,
+        x: u16,
+        y: u16,
+        color: ColorRef,
+        /// How the color combines with the pixels already there
+        #[serde(default)]
+        blend_mode: BlendMode,
+    },
+    #[serde(rename = "set_color")]
+    SetColor {
+        color: ColorRef,
+    },
+    #[serde(rename = "draw_line")]
+    DrawLine {
+        frame: usize,
+        start: Point,
+        end: Point,
+        line_type: LineType,
+        color: ColorRef,
+        /// Bézier control points for curved lines: one for a quadratic curve,
+        /// two for a cubic one. Ignored by straight lines.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        control_points: Vec<Point>,
+        #[serde(default)]
+        style: LineStyle,
+        /// How the color combines with the pixels already there
+        #[serde(default)]
+        blend_mode: BlendMode,
+    },
+    #[serde(rename = "draw_shape")]
+    DrawShape {
+        frame: usize,
+        shape: ShapeType,
+        position: Point,
+        size: Size,
+        filled: bool,
+        color: ColorRef,
+        /// How the color combines with the pixels already there
+        #[serde(default)]
+        blend_mode: BlendMode,
+    },
+    /// A circle given by its centre pixel and radius, covering
+    /// `2 * radius + 1` pixels across
+    #[serde(rename = "draw_circle")]
+    DrawCircle {
+        frame: usize,
+        center: Point,
+        radius: u16,
+        filled: bool,
+        color: ColorRef,
+        /// How the color combines with the pixels already there
+        #[serde(default)]
+        blend_mode: BlendMode,
+    },
+    #[serde(rename = "draw_polygon")]
+    DrawPolygon {
+        frame: usize,
+        points: Vec<Point>,
+        filled: bool,
+        color: ColorRef,
+        /// Which parts of a self-intersecting polygon count as inside
+        #[serde(default)]
+        fill_rule: FillRule,
+        /// How the color combines with the pixels already there
+        #[serde(default)]
+        blend_mode: BlendMode,
+    },
+    #[serde(rename = "fill_area")]
+    FillArea {
+        frame: usize,
+        x: u16,
+        y: u16,
+        color: ColorRef,
+        /// Replace only the area connected to (x, y); when false, every pixel
+        /// of the frame with the same color is replaced
+        #[serde(default = "default_contiguous")]
+        contiguous: bool,
+        /// Hard boundary for the fill: pixels outside it are never replaced,
+        /// even when they match and connect to (x, y)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bounds: Option<Rect>,
+        /// How the color combines with the pixels already there
+        #[serde(default)]
+        blend_mode: BlendMode,
+    },
+    #[serde(rename = "copy_region")]
+    CopyRegion {
+        src_frame: usize,
+        src_rect: Rect,
+        dst_frame: usize,
+        dst_point: Point,
+    },
+    /// Like `copy_region`, but the source rectangle is left transparent
+    #[serde(rename = "cut_region")]
+    CutRegion {
+        src_frame: usize,
+        src_rect: Rect,
+        dst_frame: usize,
+        dst_point: Point,
+    },
+    /// Fills `rect` with an ordered (Bayer) dither from `from` at one edge to
+    /// `to` at the opposite edge
+    #[serde(rename = "dither_gradient")]
+    DitherGradient {
+        frame: usize,
+        rect: Rect,
+        from: ColorRef,
+        to: ColorRef,
+        #[serde(default)]
+        direction: GradientDirection,
+        /// How the color combines with the pixels already there
+        #[serde(default)]
+        blend_mode: BlendMode,
+    },
+    /// Fills `rect`, or the contiguous region of `seed`'s color (kept inside
+    /// `rect` when both are given), with a two-color dithered gradient
+    /// spanning the filled pixels
+    #[serde(rename = "gradient_fill")]
+    GradientFill {
+        frame: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rect: Option<Rect>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        seed: Option<Point>,
+        from: ColorRef,
+        to: ColorRef,
+        #[serde(default)]
+        direction: GradientDirection,
+        #[serde(default)]
+        dither: DitherPattern,
+        /// How the color combines with the pixels already there
+        #[serde(default)]
+        blend_mode: BlendMode,
+    },
+    /// Speckles `rect` with colors picked at random from `colors`, covering
+    /// about `density` (0 to 1) of its pixels and leaving the rest alone.
+    /// The same `seed` always gives the same speckles; the server picks one
+    /// when it is omitted.
+    #[serde(rename = "noise_fill")]
+    NoiseFill {
+        frame: usize,
+        rect: Rect,
+        colors: Vec<ColorRef>,
+        density: f32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        seed: Option<u64>,
+        /// How the color combines with the pixels already there
+        #[serde(default)]
+        blend_mode: BlendMode,
+    },
+    /// Exchanges colors `a` and `b` in one frame, or in every frame when
+    /// `frame` is omitted and no selection is active (only the selected
+    /// frame otherwise). Symmetry does not apply.
+    #[serde(rename = "swap_colors")]
+    SwapColors {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        frame: Option<usize>,
+        a: ColorRef,
+        b: ColorRef,
+    },
+    /// Makes one pixel fully transparent
+    #[serde(rename = "erase_pixel")]
+    ErasePixel {
+        frame: usize,
+        x: u16,
+        y: u16,
+    },
+    /// Makes every pixel of `rect` fully transparent
+    #[serde(rename = "erase_area")]
+    EraseArea {
+        frame: usize,
+        rect: Rect,
+    },
+    /// Makes a whole frame fully transparent. Symmetry does not apply.
+    #[serde(rename = "clear_frame")]
+    ClearFrame {
+        frame: usize,
+    },
+    /// Mirrors a whole frame along `axis`. Symmetry does not apply.
+    #[serde(rename = "flip_frame")]
+    FlipFrame {
+        frame: usize,
+        axis: FlipAxis,
+    },
+    /// Rotates a whole frame clockwise by 90, 180, or 270 degrees. Quarter
+    /// turns need a square canvas. Symmetry does not apply.
+    #[serde(rename = "rotate_frame")]
+    RotateFrame {
+        frame: usize,
+        degrees: u16,
+    },
+    /// Moves a whole frame by (`dx`, `dy`); pixels pushed off an edge wrap
+    /// around to the opposite one when `wrap` is set. Symmetry does not apply.
+    #[serde(rename = "shift_frame")]
+    ShiftFrame {
+        frame: usize,
+        dx: i32,
+        dy: i32,
+        #[serde(default)]
+        wrap: bool,
+    },
+    /// Pastes a small bitmap with its top-left corner at `position`. Parts
+    /// falling off the canvas are dropped.
+    #[serde(rename = "draw_stamp")]
+    DrawStamp {
+        frame: usize,
+        position: Point,
+        source: StampSource,
+        /// Mirror the stamp before pasting it
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        flip: Option<FlipAxis>,
+        /// Leave the canvas alone under fully transparent stamp pixels
+        /// instead of erasing it
+        #[serde(default = "default_skip_transparent")]
+        skip_transparent: bool,
+        /// How the color combines with the pixels already there
+        #[serde(default)]
+        blend_mode: BlendMode,
+    },
+    /// Limits the rest of the request's operations to the selected pixels
+    /// of `frame`; other frames are unaffected. Replaces any earlier selection.
+    #[serde(rename = "set_selection")]
+    SetSelection {
+        frame: usize,
+        selection: Selection,
+    },
+    /// Lets later operations change any pixel again
+    #[serde(rename = "clear_selection")]
+    ClearSelection,
+}
+
+impl DrawingOperation {
+    /// The serialized `type` tag
+    pub fn name(&self) -> &'static str {
+        match self {
+            DrawingOperation::DrawPixel { .. } => "draw_pixel",
+            DrawingOperation::SetColor { .. } => "set_color",
+            DrawingOperation::DrawLine { .. } => "draw_line",
+            DrawingOperation::DrawShape { .. } => "draw_shape",
+            DrawingOperation::DrawCircle { .. } => "draw_circle",
+            DrawingOperation::DrawPolygon { .. } => "draw_polygon",
+            DrawingOperation::FillArea { .. } => "fill_area",
+            DrawingOperation::CopyRegion { .. } => "copy_region",
+            DrawingOperation::CutRegion { .. } => "cut_region",
+            DrawingOperation::DitherGradient { .. } => "dither_gradient",
+            DrawingOperation::GradientFill { .. } => "gradient_fill",
+            DrawingOperation::NoiseFill { .. } => "noise_fill",
+            DrawingOperation::SwapColors { .. } => "swap_colors",
+            DrawingOperation::ErasePixel { .. } => "erase_pixel",
+            DrawingOperation::EraseArea { .. } => "erase_area",
+            DrawingOperation::ClearFrame { .. } => "clear_frame",
+            DrawingOperation::FlipFrame { .. } => "flip_frame",
+            DrawingOperation::RotateFrame { .. } => "rotate_frame",
+            DrawingOperation::ShiftFrame { .. } => "shift_frame",
+            DrawingOperation::DrawStamp { .. } => "draw_stamp",
+            DrawingOperation::SetSelection { .. } => "set_selection",
+            DrawingOperation::ClearSelection => "clear_selection",
+        }
+    }
+
+    /// The frame the operation changes; `None` when it changes none or all of them
+    pub fn frame(&self) -> Option<usize> {
+        match self {
+            DrawingOperation::DrawPixel { frame, .. }
+            | DrawingOperation::DrawLine { frame, .. }
+            | DrawingOperation::DrawShape { frame, .. }
+            | DrawingOperation::DrawCircle { frame, .. }
+            | DrawingOperation::DrawPolygon { frame, .. }
+            | DrawingOperation::FillArea { frame, .. }
+            | DrawingOperation::DitherGradient { frame, .. }
+            | DrawingOperation::GradientFill { frame, .. }
+            | DrawingOperation::NoiseFill { frame, .. }
+            | DrawingOperation::ErasePixel { frame, .. }
+            | DrawingOperation::EraseArea { frame, .. }
+            | DrawingOperation::ClearFrame { frame }
+            | DrawingOperation::FlipFrame { frame, .. }
+            | DrawingOperation::RotateFrame { frame, .. }
+            | DrawingOperation::ShiftFrame { frame, .. }
+            | DrawingOperation::DrawStamp { frame, .. } => Some(*frame),
+            DrawingOperation::CopyRegion { dst_frame, .. } => Some(*dst_frame),
+            // Both frames change; the destination is the one drawn into
+            DrawingOperation::CutRegion { dst_frame, .. } => Some(*dst_frame),
+            DrawingOperation::SwapColors { frame, .. } => *frame,
+            DrawingOperation::SetColor { .. }
+            | DrawingOperation::SetSelection { .. }
+            | DrawingOperation::ClearSelection => None,
+        }
+    }
+
+    /// How the operation's colors combine with existing pixels; `Replace`
+    /// for operations without a blend mode
+    pub fn blend_mode(&self) -> BlendMode {
+        match self {
+            DrawingOperation::DrawPixel { blend_mode, .. }
+            | DrawingOperation::DrawLine { blend_mode, .. }
+            | DrawingOperation::DrawShape { blend_mode, .. }
+            | DrawingOperation::DrawCircle { blend_mode, .. }
+            | DrawingOperation::DrawPolygon { blend_mode, .. }
+            | DrawingOperation::FillArea { blend_mode, .. }
+            | DrawingOperation::DitherGradient { blend_mode, .. }
+            | DrawingOperation::GradientFill { blend_mode, .. }
+            | DrawingOperation::NoiseFill { blend_mode, .. }
+            | DrawingOperation::DrawStamp { blend_mode, .. } => *blend_mode,
+            _ => BlendMode::Replace,
+        }
+    }
+
+    /// Name and frame, e.g. `draw_line f2`
+    pub fn summary(&self) -> String {
+        match self.frame() {
+            Some(frame) => format!("{} f{}", self.name(), frame),
+            None => self.name().to_string(),
+        }
+    }
+}
+
+fn default_contiguous() -> bool {
+    true
+}
+
+fn default_skip_transparent() -> bool {
+    true
+}
+
+/// The pixels a `set_selection` lets later operations change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(tag = "kind")]
+pub enum Selection {
+    #[serde(rename = "rect")]
+    Rect {
+        rect: Rect,
+    },
+    /// The polygon's outline and everything inside it
+    #[serde(rename = "polygon")]
+    Polygon {
+        points: Vec<Point>,
+    },
+    /// The pixels a fill from (`x`, `y`) would replace
+    #[serde(rename = "magic_wand")]
+    MagicWand {
+        x: u16,
+        y: u16,
+        #[serde(default = "default_contiguous")]
+        contiguous: bool,
+    },
+}
+
+/// Where the pixels of a `draw_stamp` come from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(tag = "kind")]
+pub enum StampSource {
+    /// `width` x `height` RGBA bytes, row by row, in standard base64
+    #[serde(rename = "inline")]
+    Inline {
+        width: u16,
+        height: u16,
+        data: String,
+    },
+    /// A rectangle of a frame (the whole frame when `rect` is omitted), from
+    /// the book being drawn on or from `book`
+    #[serde(rename = "frame")]
+    Frame {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        book: Option<String>,
+        frame: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rect: Option<Rect>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Point {
+    pub x: u16,
+    pub y: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Size {
+    pub width: u16,
+    pub height: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Rect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Rect {
+    pub fn contains(&self, x: u16, y: u16) -> bool {
+        x >= self.x && y >= self.y
+            && (x as u32) < self.x as u32 + self.width as u32
+            && (y as u32) < self.y as u32 + self.height as u32
+    }
+
+    /// Every pixel of the rect on a `width` x `height` canvas, row by row
+    pub fn canvas_pixels(&self, width: u16, height: u16) -> Vec<(u16, u16)> {
+        let right = self.x.saturating_add(self.width).min(width);
+        let bottom = self.y.saturating_add(self.height).min(height);
+        (self.y..bottom).flat_map(|y| (self.x..right).map(move |x| (x, y))).collect()
+    }
+
+    /// Smallest rectangle covering both `self` and `other`
+    pub fn union(&self, other: &Rect) -> Rect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = (self.x as u32 + self.width as u32).max(other.x as u32 + other.width as u32);
+        let bottom = (self.y as u32 + self.height as u32).max(other.y as u32 + other.height as u32);
+        Rect {
+            x,
+            y,
+            width: (right - x as u32).min(u16::MAX as u32) as u16,
+            height: (bottom - y as u32).min(u16::MAX as u32) as u16,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum LineType {
+    #[serde(rename = "straight")]
+    Straight,
+    #[serde(rename = "curved")]
+    Curved,
+}
+
+/// How a drawing operation's colors combine with the pixels already there
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum BlendMode {
+    /// Overwrite the pixel, alpha included
+    #[default]
+    #[serde(rename = "replace")]
+    Replace,
+    /// Composite over the pixel according to the color's alpha
+    #[serde(rename = "alpha")]
+    Alpha,
+    /// Add the color to the pixel, clamped, weighted by the color's alpha
+    #[serde(rename = "add")]
+    Add,
+    /// Multiply the pixel by the color, weighted by the color's alpha
+    #[serde(rename = "multiply")]
+    Multiply,
+}
+
+/// How a line is rasterized
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum LineStyle {
+    /// Plain Bresenham: solid pixels, sometimes doubled where a curve turns
+    #[default]
+    #[serde(rename = "aliased")]
+    Aliased,
+    /// Solid pixels with the doubled corner of every L-shaped step removed,
+    /// leaving a one-pixel-wide stroke
+    #[serde(rename = "pixel_perfect")]
+    PixelPerfect,
+    /// Pixels the line only partly covers get a matching share of the
+    /// color's alpha, blended over what is already there
+    #[serde(rename = "anti_aliased")]
+    AntiAliased,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum ShapeType {
+    #[serde(rename = "rectangle")]
+    Rectangle,
+    #[serde(rename = "circle")]
+    Circle,
+    #[serde(rename = "oval")]
+    Oval,
+    #[serde(rename = "triangle")]
+    Triangle,
+}
+
+/// How a filled polygon decides which pixels are inside. The two only
+/// differ where edges cross or wind around a region more than once.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum FillRule {
+    /// Inside when a ray from the pixel crosses an odd number of edges, so
+    /// the middle of a star is left empty
+    #[default]
+    #[serde(rename = "even_odd")]
+    EvenOdd,
+    /// Inside when the edges wind around the pixel at all, so a star is solid
+    #[serde(rename = "non_zero")]
+    NonZero,
+}
+
+/// Axis a gradient runs along
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum GradientDirection {
+    /// Left edge to right edge
+    #[default]
+    #[serde(rename = "horizontal")]
+    Horizontal,
+    /// Top edge to bottom edge
+    #[serde(rename = "vertical")]
+    Vertical,
+    /// Centre out to the corners
+    #[serde(rename = "radial")]
+    Radial,
+}
+
+/// How a gradient, or a quantized image, is dithered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum DitherPattern {
+    /// 4x4 Bayer matrix: a regular cross-hatch that tiles cleanly
+    #[default]
+    #[serde(rename = "ordered")]
+    Ordered,
+    /// Floyd-Steinberg error diffusion: an irregular, grainier texture
+    #[serde(rename = "error_diffusion")]
+    ErrorDiffusion,
+}
+
+/// Axis a frame is flipped along
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum FlipAxis {
+    /// Left and right swap (a mirror image)
+    #[serde(rename = "horizontal")]
+    Horizontal,
+    /// Top and bottom swap
+    #[serde(rename = "vertical")]
+    Vertical,
+}
+
+/// Mirror mode applied while drawing. Every pixel written by an operation is
+/// also written at its reflection about the canvas centre line(s).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum Symmetry {
+    #[default]
+    #[serde(rename = "none")]
+    None,
+    /// Left half mirrors onto the right half (reflection about the vertical axis)
+    #[serde(rename = "horizontal")]
+    Horizontal,
+    /// Top half mirrors onto the bottom half (reflection about the horizontal axis)
+    #[serde(rename = "vertical")]
+    Vertical,
+    /// Both axes: every pixel is written to all four quadrants
+    #[serde(rename = "quad")]
+    Quad,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct UpdatePixelBookRequest {
+    pub operations: Vec<DrawingOperation>,
+    /// Mirror mode for these operations; the drawing context's when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symmetry: Option<Symmetry>,
+}
+
+/// What became of one operation of `PUT /books/:filename`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum OperationStatus {
+    Applied,
+    /// Not attempted, or undone, because another operation failed
+    Skipped,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct OperationResult {
+    pub index: usize,
+    pub status: OperationStatus,
+    /// Why the operation failed, when `status` is `error`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Outcome of a dry run of `PUT /books/:filename`: what would fail, without saving anything
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ValidationReport {
+    pub valid: bool,
+    pub operations: usize,
+    pub errors: Vec<OperationError>,
+}
+
+/// An operation that could not be applied
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct OperationError {
+    /// Position of the operation in the request
+    pub index: usize,
+    /// Short description of the operation, as in the operation log
+    pub operation: String,
+    pub error: String,
+}
+
+/// Body of `POST /batch`: updates to several books that are saved together or not at all
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BatchRequest {
+    pub books: Vec<BatchBookUpdate>,
+}
+
+/// The operations a batch applies to one book
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BatchBookUpdate {
+    pub filename: String,
+    pub operations: Vec<DrawingOperation>,
+    /// Mirror mode for these operations; the drawing context's when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symmetry: Option<Symmetry>,
+    /// Revision the operations were made against; the batch fails if the book has moved on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<u64>,
+}
+
+/// Outcome of `POST /batch`. When `success` is false nothing was saved and
+/// each failing book carries an `error`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BatchResult {
+    pub success: bool,
+    pub books: Vec<BatchBookResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BatchBookResult {
+    pub filename: String,
+    pub operations_applied: usize,
+    /// Revision the book was saved at, when the batch succeeded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+
+/// Most colors a palette can hold, so every index fits in a byte
+pub const MAX_PALETTE_COLORS: usize = 256;
+
+/// Longest palette name in bytes
+pub const MAX_PALETTE_NAME_LEN: usize = 255;
+
+/// A named set of colors that drawing operations can refer to by index.
+/// Body and response of `GET/PUT /books/:filename/palette`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Palette {
+    pub name: String,
+    pub colors: Vec<[u8; 4]>,
+}
+
+impl Palette {
+    /// Checks the palette fits in a `.pxl` file
+    pub fn validate(&self) -> Result<(), String> {
+        if self.colors.len() > MAX_PALETTE_COLORS {
+            return Err(format!("Palettes hold at most {} colors, got {}", MAX_PALETTE_COLORS, self.colors.len()));
+        }
+        if self.name.len() > MAX_PALETTE_NAME_LEN {
+            return Err(format!("Palette names are at most {} bytes", MAX_PALETTE_NAME_LEN));
+        }
+        Ok(())
+    }
+
+    pub fn color(&self, index: u8) -> Option<[u8; 4]> {
+        self.colors.get(index as usize).copied()
+    }
+}
+
+/// A color in a drawing operation: RGBA like `[255, 0, 0, 255]`, or an
+/// index into the book's palette like `3`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+pub enum ColorRef {
+    Rgba([u8; 4]),
+    Index(u8),
+}
+
+impl ColorRef {
+    /// The RGBA value; `None` for an index the palette does not have
+    pub fn resolve(self, palette: Option<&Palette>) -> Option<[u8; 4]> {
+        match self {
+            ColorRef::Rgba(color) => Some(color),
+            ColorRef::Index(index) => palette?.color(index),
+        }
+    }
+}
+
+impl From<[u8; 4]> for ColorRef {
+    fn from(color: [u8; 4]) -> Self {
+        ColorRef::Rgba(color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_refs_parse_and_resolve() {
+        let palette = Palette { name: "duo".to_string(), colors: vec![[0, 0, 0, 255], [255, 255, 255, 255]] };
+
+        let rgba: ColorRef = serde_json::from_str("[255, 0, 0, 255]").unwrap();
+        assert_eq!(rgba, ColorRef::Rgba([255, 0, 0, 255]));
+        assert_eq!(rgba.resolve(None), Some([255, 0, 0, 255]));
+
+        let index: ColorRef = serde_json::from_str("1").unwrap();
+        assert_eq!(index, ColorRef::Index(1));
+        assert_eq!(index.resolve(Some(&palette)), Some([255, 255, 255, 255]));
+        assert_eq!(ColorRef::Index(2).resolve(Some(&palette)), None);
+        assert_eq!(index.resolve(None), None);
+        assert_eq!(serde_json::to_string(&index).unwrap(), "1");
+
+        assert!(palette.validate().is_ok());
+        let oversized = Palette { colors: vec![[0; 4]; MAX_PALETTE_COLORS + 1], ..palette };
+        assert!(oversized.validate().is_err());
+    }
+}
@@ -0,0 +1,459 @@
+use crate::metadata::{BookDetails, BookMetadata};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Pixel {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Pixel {
+    pub fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+    
+    pub fn transparent() -> Self {
+        Self { r: 0, g: 0, b: 0, a: 0 }
+    }
+    
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() >= 4 {
+            Some(Self::new(bytes[0], bytes[1], bytes[2], bytes[3]))
+        } else {
+            None
+        }
+    }
+    
+    /// Packs the color channels as 0x00RRGGBB, the layout framebuffer displays expect
+    pub fn to_rgba32(&self) -> u32 {
+        ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
+    }
+    
+    /// True for any pixel that isn't fully opaque
+    pub fn is_transparent(&self) -> bool {
+        self.a < 255
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Frame {
+    pub index: usize,
+    pub pixels: Vec<u8>, // RGBA bytes: [r, g, b, a, r, g, b, a, ...]
+    /// How long the frame shows during playback; `None` uses the player's
+    /// default frame rate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u32>,
+}
+
+impl Frame {
+    pub fn new(index: usize, width: u16, height: u16) -> Self {
+        let pixel_count = (width as usize) * (height as usize) * 4; // RGBA
+        let pixels = vec![0u8; pixel_count]; // Transparent pixels
+        Self { index, pixels, duration_ms: None }
+    }
+    
+    pub fn get_pixel(&self, x: u16, y: u16, width: u16) -> Option<Pixel> {
+        let pixel_idx = (y as usize * width as usize + x as usize) * 4;
+        if pixel_idx + 3 < self.pixels.len() {
+            Some(Pixel::new(
+                self.pixels[pixel_idx],
+                self.pixels[pixel_idx + 1],
+                self.pixels[pixel_idx + 2],
+                self.pixels[pixel_idx + 3],
+            ))
+        } else {
+            None
+        }
+    }
+    
+    pub fn set_pixel(&mut self, x: u16, y: u16, width: u16, pixel: Pixel) -> bool {
+        let pixel_idx = (y as usize * width as usize + x as usize) * 4;
+        if pixel_idx + 3 < self.pixels.len() {
+            self.pixels[pixel_idx] = pixel.r;
+            self.pixels[pixel_idx + 1] = pixel.g;
+            self.pixels[pixel_idx + 2] = pixel.b;
+            self.pixels[pixel_idx + 3] = pixel.a;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PixelBook {
+    pub filename: String,
+    pub width: u16,
+    pub height: u16,
+    pub frames: Vec<Frame>,
+    #[serde(default)]
+    pub metadata: BookMetadata,
+    /// Counts the server's saves of this book, starting from 1. Sent back in
+    /// `If-Match` so an update only applies to the revision that was read.
+    /// Tracked by the server, not stored in `.pxl` files.
+    #[serde(default)]
+    pub revision: u64,
+}
+
+impl PixelBook {
+    pub fn new(filename: String, width: u16, height: u16, frame_count: usize) -> Self {
+        let frames = (0..frame_count)
+            .map(|i| Frame::new(i, width, height))
+            .collect();
+        
+        Self {
+            filename,
+            width,
+            height,
+            frames,
+            metadata: BookMetadata::default(),
+            revision: 0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct PixelBookInfo {
+    pub filename: String,
+    pub size: u64,
+    pub created: chrono::DateTime<chrono::Utc>,
+    pub modified: chrono::DateTime<chrono::Utc>,
+    pub frames: usize,
+    #[serde(default)]
+    pub width: u16,
+    #[serde(default)]
+    pub height: u16,
+    /// `.pxl` format version the file is stored in
+    #[serde(default)]
+    pub version: u16,
+    /// Advisory lock currently held on the book, if any
+    #[serde(default)]
+    pub lock: Option<BookLock>,
+    /// Title, author, tags and description from the book's metadata
+    #[serde(default, skip_serializing_if = "BookDetails::is_empty")]
+    pub details: BookDetails,
+}
+
+/// An advisory lock on a book. Until it expires, writes are only accepted
+/// from the client presenting the lock's token.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BookLock {
+    /// Who holds the lock, e.g. `viewer` or an agent name; shown to other clients
+    pub holder: String,
+    pub acquired_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Body of `POST /books/:filename/lock`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct LockRequest {
+    pub holder: String,
+    /// Lease length; the server default when omitted
+    #[serde(default)]
+    pub ttl_seconds: Option<u64>,
+}
+
+/// A granted or renewed lock. `token` must accompany writes and the unlock.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct LockResponse {
+    pub filename: String,
+    pub token: String,
+    pub lock: BookLock,
+}
+
+/// A deleted book waiting in the trash until `expires_at`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TrashEntry {
+    pub filename: String,
+    pub size: u64,
+    pub deleted_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A point-in-time copy of a book, kept by the autosave task or taken on request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SnapshotInfo {
+    pub filename: String,
+    /// Creation time in milliseconds since the epoch; used to restore it
+    pub id: i64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub size: u64,
+    /// Set on snapshots taken on request, which are kept until deleted by hand
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// Body of `POST /books/:filename/snapshots`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CreateSnapshotRequest {
+    pub name: String,
+}
+
+/// One applied batch of operations, as kept in a book's operation log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct OperationLogEntry {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// User agent of the client that sent the batch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+    /// `X-Request-Id` the client sent with the batch, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// [`DrawingOperation::summary`](crate::DrawingOperation::summary) of each operation, in order
+    pub operations: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CreatePixelBookRequest {
+    pub filename: String,
+    pub width: u16,
+    pub height: u16,
+    pub frames: usize,
+}
+
+/// Body of `POST /books/:filename/rename` and `POST /books/:filename/copy`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RenameBookRequest {
+    /// Name of the new book; must not exist yet
+    pub new_filename: String,
+}
+
+/// Body of `POST /books/:filename/migrate`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct MigrateRequest {
+    /// Target format version; the newest supported version when omitted
+    #[serde(default)]
+    pub version: Option<u16>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct MigrateResult {
+    pub filename: String,
+    pub from_version: u16,
+    pub to_version: u16,
+}
+
+impl MigrateResult {
+    pub fn changed(&self) -> bool {
+        self.from_version != self.to_version
+    }
+}
+
+/// Longest duration a single frame may be given, in milliseconds
+pub const MAX_FRAME_DURATION_MS: u32 = 60_000;
+
+/// Body and response of `PUT /books/:filename/frames/:index/duration`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct FrameDuration {
+    /// Milliseconds the frame shows for; `None` falls back to the player's frame rate
+    #[serde(default)]
+    pub duration_ms: Option<u32>,
+}
+
+impl FrameDuration {
+    pub fn validate(&self) -> Result<(), String> {
+        match self.duration_ms {
+            Some(ms) if ms == 0 || ms > MAX_FRAME_DURATION_MS => Err(format!(
+                "duration_ms must be between 1 and {}, or null for the default",
+                MAX_FRAME_DURATION_MS
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Body of `POST /books/import-url`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ImportUrlRequest {
+    /// `http` or `https` URL of a PNG or GIF
+    pub url: String,
+    /// Book to create; derived from the last URL path segment when omitted
+    #[serde(default)]
+    pub filename: Option<String>,
+    /// Replace the book if it already exists instead of refusing the import
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// A starting point for new books, listed by `GET /templates`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TemplateInfo {
+    pub name: String,
+    pub description: String,
+    pub width: u16,
+    pub height: u16,
+    pub frames: usize,
+    /// Shipped with the server rather than read from the templates directory
+    pub builtin: bool,
+}
+
+/// Body of `POST /books/from-template`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CreateFromTemplateRequest {
+    pub template: String,
+    pub filename: String,
+}
+
+/// Body of `GET /status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ServerStatus {
+    /// Version of the server crate
+    pub version: String,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub uptime_seconds: u64,
+    /// Directory books are currently read from and written to
+    pub path: String,
+    pub book_count: usize,
+    /// Combined size of every book in `path`, in bytes
+    pub disk_usage: u64,
+    /// Open SSE connections, per-book and workspace streams together
+    pub active_clients: usize,
+}
+
+/// A book an integrity scan could not fully read
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct IntegrityIssue {
+    pub filename: String,
+    pub problem: String,
+}
+
+/// Outcome of one integrity scan over the workspace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ScanReport {
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub finished_at: chrono::DateTime<chrono::Utc>,
+    pub books_scanned: usize,
+    pub issues: Vec<IntegrityIssue>,
+}
+
+/// A half-open range of frame indices, written `start..end`, `start..`
+/// (through the last frame) or a single index `n`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameRange {
+    pub start: usize,
+    pub end: Option<usize>,
+}
+
+impl FrameRange {
+    /// The range holding only frame `index`; `None` for `usize::MAX`,
+    /// which no range can end after
+    pub fn single(index: usize) -> Option<Self> {
+        index.checked_add(1).map(|end| Self { start: index, end: Some(end) })
+    }
+
+    /// The indices this range selects in a book of `frame_count` frames;
+    /// `None` when it selects nothing
+    pub fn resolve(&self, frame_count: usize) -> Option<std::ops::Range<usize>> {
+        let end = self.end.unwrap_or(frame_count).min(frame_count);
+        (self.start < end).then_some(self.start..end)
+    }
+}
+
+impl std::str::FromStr for FrameRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |n: &str| n.trim().parse::<usize>().map_err(|_| format!("invalid frame range: {}", s));
+        match s.split_once("..") {
+            Some((start, "")) => Ok(Self { start: parse(start)?, end: None }),
+            Some((start, end)) => Ok(Self { start: parse(start)?, end: Some(parse(end)?) }),
+            None => Self::single(parse(s)?).ok_or_else(|| format!("invalid frame range: {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for FrameRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.end {
+            Some(end) => write!(f, "{}..{}", self.start, end),
+            None => write!(f, "{}..", self.start),
+        }
+    }
+}
+
+/// One line of the `GET /books/:filename/stream` NDJSON body: a `book` line
+/// with everything but the pixels, then a `frame` line per frame
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(tag = "type")]
+pub enum BookChunk {
+    #[serde(rename = "book")]
+    Book {
+        filename: String,
+        width: u16,
+        height: u16,
+        /// Frames in the whole book, not just the streamed range
+        frame_count: usize,
+        metadata: Box<BookMetadata>,
+    },
+    #[serde(rename = "frame")]
+    Frame(Frame),
+    /// The book could not be read past this point
+    #[serde(rename = "error")]
+    Error { message: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_set_and_get_pixel() {
+        let mut frame = Frame::new(0, 4, 4);
+        assert!(frame.set_pixel(2, 3, 4, Pixel::new(10, 20, 30, 255)));
+        assert_eq!(frame.get_pixel(2, 3, 4), Some(Pixel::new(10, 20, 30, 255)));
+        assert!(!frame.set_pixel(0, 4, 4, Pixel::new(1, 1, 1, 1)));
+        assert_eq!(frame.get_pixel(0, 4, 4), None);
+    }
+
+    #[test]
+    fn test_pixel_helpers() {
+        let pixel = Pixel::from_bytes(&[0x12, 0x34, 0x56, 0x80]).unwrap();
+        assert_eq!(pixel.to_rgba32(), 0x123456);
+        assert!(pixel.is_transparent());
+        assert!(!Pixel::new(0, 0, 0, 255).is_transparent());
+        assert!(Pixel::from_bytes(&[1, 2, 3]).is_none());
+    }
+
+    #[test]
+    fn test_frame_range_parsing() {
+        let range: FrameRange = "2..5".parse().unwrap();
+        assert_eq!(range.resolve(10), Some(2..5));
+        assert_eq!(range.resolve(4), Some(2..4));
+        assert_eq!(range.to_string(), "2..5");
+
+        let open: FrameRange = "3..".parse().unwrap();
+        assert_eq!(open.resolve(10), Some(3..10));
+        assert_eq!(open.to_string(), "3..");
+
+        assert_eq!("7".parse::<FrameRange>().unwrap().resolve(10), Some(7..8));
+        assert_eq!("7".parse::<FrameRange>().unwrap().resolve(5), None);
+        assert!("a..b".parse::<FrameRange>().is_err());
+        assert!(usize::MAX.to_string().parse::<FrameRange>().is_err());
+        assert_eq!(FrameRange::single(usize::MAX), None);
+    }
+}
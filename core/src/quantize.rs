@@ -0,0 +1,290 @@
+use crate::dither::BAYER_4X4;
+use crate::operations::DitherPattern;
+use crate::pixel_book::PixelBook;
+use serde::{Deserialize, Serialize};
+
+/// Iterations of k-means refinement after the median cut seed
+const KMEANS_ITERATIONS: usize = 10;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum QuantizeMethod {
+    #[default]
+    #[serde(rename = "median_cut")]
+    MedianCut,
+    /// Median cut refined with k-means; slower but closer to the original colors
+    #[serde(rename = "kmeans")]
+    KMeans,
+}
+
+/// Body of `POST /books/:filename/quantize`. Give either `colors` or `palette`,
+/// or neither to map onto the book's own palette.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct QuantizeRequest {
+    /// Reduce to at most this many colors
+    #[serde(default)]
+    pub colors: Option<usize>,
+    /// Map every pixel to the nearest of these colors instead
+    #[serde(default)]
+    pub palette: Option<Vec<[u8; 4]>>,
+    #[serde(default)]
+    pub method: QuantizeMethod,
+    /// Dither between palette colors instead of snapping each pixel to the nearest
+    #[serde(default)]
+    pub dither: Option<DitherPattern>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct QuantizeResult {
+    pub palette: Vec<[u8; 4]>,
+    pub pixels_changed: usize,
+}
+
+/// Every visible pixel of the book; fully transparent pixels are left out of
+/// quantization and never change
+fn visible_colors(book: &PixelBook) -> Vec<[u8; 4]> {
+    book.frames
+        .iter()
+        .flat_map(|frame| frame.pixels.chunks_exact(4))
+        .filter(|p| p[3] != 0)
+        .map(|p| [p[0], p[1], p[2], p[3]])
+        .collect()
+}
+
+fn distance(a: [u8; 4], b: [u8; 4]) -> u32 {
+    a.iter().zip(b.iter()).map(|(&a, &b)| (a as i32 - b as i32).pow(2) as u32).sum()
+}
+
+fn nearest(palette: &[[u8; 4]], color: [u8; 4]) -> usize {
+    (0..palette.len()).min_by_key(|&i| distance(palette[i], color)).unwrap_or(0)
+}
+
+fn average(colors: &[[u8; 4]]) -> [u8; 4] {
+    let mut sums = [0u64; 4];
+    for color in colors {
+        for channel in 0..4 {
+            sums[channel] += color[channel] as u64;
+        }
+    }
+    let n = colors.len().max(1) as u64;
+    sums.map(|sum| ((sum + n / 2) / n) as u8)
+}
+
+/// Palette of at most `count` colors: repeatedly splits the box with the
+/// widest channel range at its median
+pub fn median_cut(colors: &[[u8; 4]], count: usize) -> Vec<[u8; 4]> {
+    let mut unique = colors.to_vec();
+    unique.sort_unstable();
+    unique.dedup();
+    if unique.len() <= count {
+        return unique;
+    }
+
+    let mut boxes = vec![colors.to_vec()];
+    while boxes.len() < count {
+        let widest = boxes.iter().enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .map(|(i, b)| {
+                let (channel, range) = (0..4)
+                    .map(|c| {
+                        let min = b.iter().map(|p| p[c]).min().unwrap_or(0);
+                        let max = b.iter().map(|p| p[c]).max().unwrap_or(0);
+                        (c, max - min)
+                    })
+                    .max_by_key(|&(_, range)| range)
+                    .unwrap_or((0, 0));
+                (i, channel, range)
+            })
+            .max_by_key(|&(_, _, range)| range);
+
+        let Some((index, channel, range)) = widest else { break };
+        if range == 0 {
+            break;
+        }
+
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_unstable_by_key(|p| p[channel]);
+        let upper = colors.split_off(colors.len() / 2);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    boxes.iter().map(|b| average(b)).collect()
+}
+
+/// Lloyd's k-means starting from `palette`
+fn kmeans(colors: &[[u8; 4]], mut palette: Vec<[u8; 4]>) -> Vec<[u8; 4]> {
+    for _ in 0..KMEANS_ITERATIONS {
+        let mut clusters = vec![Vec::new(); palette.len()];
+        for &color in colors {
+            clusters[nearest(&palette, color)].push(color);
+        }
+
+        let next: Vec<[u8; 4]> = clusters.iter().zip(&palette)
+            .map(|(cluster, &old)| if cluster.is_empty() { old } else { average(cluster) })
+            .collect();
+        if next == palette {
+            break;
+        }
+        palette = next;
+    }
+    palette
+}
+
+/// Builds a palette of at most `count` colors from the book's visible pixels
+pub fn build_palette(book: &PixelBook, count: usize, method: QuantizeMethod) -> Vec<[u8; 4]> {
+    let colors = visible_colors(book);
+    let palette = median_cut(&colors, count.max(1));
+    match method {
+        QuantizeMethod::MedianCut => palette,
+        QuantizeMethod::KMeans => {
+            let mut palette = kmeans(&colors, palette);
+            palette.sort_unstable();
+            palette.dedup();
+            palette
+        }
+    }
+}
+
+/// Replaces every visible pixel with its nearest palette color
+pub fn apply_palette(book: &mut PixelBook, palette: &[[u8; 4]]) -> usize {
+    apply_palette_dithered(book, palette, None)
+}
+
+/// How far `color` lies from the nearest palette color towards the second
+/// nearest, from 0 to 1, along with both colors
+fn blend(palette: &[[u8; 4]], color: [u8; 4]) -> ([u8; 4], [u8; 4], f32) {
+    let first = palette[nearest(palette, color)];
+    let Some(second) = palette.iter().copied().filter(|&c| c != first).min_by_key(|&c| distance(c, color)) else {
+        return (first, first, 0.0);
+    };
+    let along: i32 = (0..4).map(|c| (color[c] as i32 - first[c] as i32) * (second[c] as i32 - first[c] as i32)).sum();
+    (first, second, (along as f32 / distance(first, second) as f32).clamp(0.0, 1.0))
+}
+
+fn to_color(channels: [f32; 3], alpha: u8) -> [u8; 4] {
+    let [r, g, b] = channels.map(|c| c.round().clamp(0.0, 255.0) as u8);
+    [r, g, b, alpha]
+}
+
+/// Replaces every visible pixel with a palette color, dithering between
+/// colors when `dither` is given. Ordered dithering mixes each pixel's two
+/// nearest colors; error diffusion carries only the color channels' error.
+/// Transparent pixels neither change nor take on diffused error.
+pub fn apply_palette_dithered(book: &mut PixelBook, palette: &[[u8; 4]], dither: Option<DitherPattern>) -> usize {
+    if palette.is_empty() {
+        return 0;
+    }
+
+    let (width, height) = (book.width as usize, book.height as usize);
+    let mut changed = 0;
+    for frame in &mut book.frames {
+        // Floyd-Steinberg error carried to pixels not yet visited
+        let mut error = vec![[0.0f32; 3]; width * height];
+        for index in 0..width * height {
+            let pixel = &mut frame.pixels[index * 4..index * 4 + 4];
+            let color = [pixel[0], pixel[1], pixel[2], pixel[3]];
+            if color[3] == 0 {
+                continue;
+            }
+            let (x, y) = (index % width, index / width);
+
+            let replacement = match dither {
+                None => palette[nearest(palette, color)],
+                Some(DitherPattern::Ordered) => {
+                    let (first, second, position) = blend(palette, color);
+                    let threshold = (BAYER_4X4[y % 4][x % 4] * 2 + 1) as f32 / 32.0;
+                    if position > threshold { second } else { first }
+                }
+                Some(DitherPattern::ErrorDiffusion) => {
+                    let wanted = [0, 1, 2].map(|c| color[c] as f32 + error[index][c]);
+                    let chosen = palette[nearest(palette, to_color(wanted, color[3]))];
+                    let spill = [0, 1, 2].map(|c| wanted[c] - chosen[c] as f32);
+                    let mut spread = |dx: isize, dy: usize, weight: f32| {
+                        let (nx, ny) = (x as isize + dx, y + dy);
+                        if nx >= 0 && (nx as usize) < width && ny < height {
+                            let target = &mut error[ny * width + nx as usize];
+                            for c in 0..3 {
+                                target[c] += spill[c] * weight / 16.0;
+                            }
+                        }
+                    };
+                    spread(1, 0, 7.0);
+                    spread(-1, 1, 3.0);
+                    spread(0, 1, 5.0);
+                    spread(1, 1, 1.0);
+                    chosen
+                }
+            };
+            if replacement != color {
+                pixel.copy_from_slice(&replacement);
+                changed += 1;
+            }
+        }
+    }
+    changed
+}
+
+/// Reduces the book to `count` colors, returning the palette used
+pub fn quantize_book(book: &mut PixelBook, count: usize, method: QuantizeMethod, dither: Option<DitherPattern>) -> QuantizeResult {
+    let palette = build_palette(book, count, method);
+    let pixels_changed = apply_palette_dithered(book, &palette, dither);
+    QuantizeResult { palette, pixels_changed }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pixel_book::Pixel;
+
+    fn gradient_book() -> PixelBook {
+        let mut book = PixelBook::new("gradient.pxl".to_string(), 8, 1, 1);
+        for x in 0..8 {
+            let shade = if x < 4 { x as u8 } else { 250 - x as u8 };
+            book.frames[0].set_pixel(x, 0, 8, Pixel::new(shade, 0, 0, 255));
+        }
+        book
+    }
+
+    #[test]
+    fn test_median_cut_reduces_to_requested_count() {
+        for method in [QuantizeMethod::MedianCut, QuantizeMethod::KMeans] {
+            let mut book = gradient_book();
+            let result = quantize_book(&mut book, 2, method, None);
+            assert_eq!(result.palette.len(), 2);
+            assert_eq!(visible_colors(&book).iter().collect::<std::collections::HashSet<_>>().len(), 2);
+            // The shade matching each cluster average is already in the palette
+            assert_eq!(result.pixels_changed, 6);
+        }
+    }
+
+    #[test]
+    fn test_apply_palette_skips_transparent_pixels() {
+        let mut book = PixelBook::new("mixed.pxl".to_string(), 2, 1, 1);
+        book.frames[0].set_pixel(0, 0, 2, Pixel::new(200, 10, 10, 255));
+
+        let changed = apply_palette(&mut book, &[[255, 0, 0, 255], [0, 0, 255, 255]]);
+        assert_eq!(changed, 1);
+        assert_eq!(book.frames[0].get_pixel(0, 0, 2), Some(Pixel::new(255, 0, 0, 255)));
+        assert_eq!(book.frames[0].get_pixel(1, 0, 2).unwrap().a, 0);
+    }
+
+    #[test]
+    fn test_dithered_palette_mixes_colors_for_in_between_shades() {
+        let (black, white) = ([0, 0, 0, 255], [255, 255, 255, 255]);
+        for pattern in [DitherPattern::Ordered, DitherPattern::ErrorDiffusion] {
+            let mut book = PixelBook::new("gray.pxl".to_string(), 8, 8, 1);
+            for pixel in book.frames[0].pixels.chunks_exact_mut(4) {
+                pixel.copy_from_slice(&[128, 128, 128, 255]);
+            }
+            book.frames[0].set_pixel(0, 0, 8, Pixel::transparent());
+
+            assert_eq!(apply_palette_dithered(&mut book, &[black, white], Some(pattern)), 63);
+            let whites = visible_colors(&book).iter().filter(|&&c| c == white).count();
+            assert!((28..=36).contains(&whites), "{:?} gave {} white pixels", pattern, whites);
+            assert_eq!(book.frames[0].get_pixel(0, 0, 8), Some(Pixel::transparent()));
+        }
+    }
+}
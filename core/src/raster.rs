@@ -0,0 +1,244 @@
+use crate::curves::line_pixels;
+use crate::operations::{Point, Rect, ShapeType, Size};
+use crate::pixel_book::Frame;
+
+/// Pixels of `shape` drawn in the box at `position` of `size`, as its outline
+/// or, when `filled`, its whole area, on a `width` x `height` canvas. Only
+/// pixels on the canvas are returned, so a shape far larger than the canvas
+/// costs no more than the canvas; outlines may repeat a pixel.
+pub fn shape_pixels(shape: &ShapeType, position: &Point, size: &Size, filled: bool, width: u16, height: u16) -> Vec<(i32, i32)> {
+    let (x, y) = (position.x as i32, position.y as i32);
+    let (w, h) = (size.width as i32, size.height as i32);
+    let pixels = match shape {
+        ShapeType::Rectangle => rectangle_pixels(x, y, w, h, filled, width, height),
+        ShapeType::Circle => return circle_pixels(x + w / 2, y + h / 2, w.min(h) / 2, filled, width, height),
+        ShapeType::Oval => oval_pixels(x + w / 2, y + h / 2, w / 2, h / 2, filled, width, height),
+        ShapeType::Triangle => triangle_pixels(x, y, w, h, filled, width, height),
+    };
+    on_canvas(pixels, width, height)
+}
+
+// The part of `start..=end` inside `0..limit`; empty when they don't overlap
+fn clamp_span(start: i32, end: i32, limit: u16) -> std::ops::RangeInclusive<i32> {
+    start.max(0)..=end.min(limit as i32 - 1)
+}
+
+fn on_canvas(mut pixels: Vec<(i32, i32)>, width: u16, height: u16) -> Vec<(i32, i32)> {
+    pixels.retain(|&(x, y)| x >= 0 && y >= 0 && x < width as i32 && y < height as i32);
+    pixels
+}
+
+fn rectangle_pixels(x1: i32, y1: i32, width: i32, height: i32, filled: bool, canvas_width: u16, canvas_height: u16) -> Vec<(i32, i32)> {
+    let x2 = x1 + (width - 1).max(0);
+    let y2 = y1 + (height - 1).max(0);
+    let (columns, rows) = (clamp_span(x1, x2, canvas_width), clamp_span(y1, y2, canvas_height));
+    if filled {
+        return rows.flat_map(|y| columns.clone().map(move |x| (x, y))).collect();
+    }
+
+    let mut pixels = Vec::new();
+    for x in columns {
+        pixels.push((x, y1));
+        if y2 != y1 {
+            pixels.push((x, y2));
+        }
+    }
+    for y in rows {
+        pixels.push((x1, y));
+        if x2 != x1 {
+            pixels.push((x2, y));
+        }
+    }
+    pixels
+}
+
+/// Pixels of the circle of `radius` around (cx, cy) that fall on a `width`
+/// x `height` canvas: a midpoint circle outline, or every pixel within the
+/// radius when `filled`
+pub fn circle_pixels(cx: i32, cy: i32, radius: i32, filled: bool, width: u16, height: u16) -> Vec<(i32, i32)> {
+    if filled {
+        let columns = clamp_span(cx - radius, cx + radius, width);
+        let within = |x: i32, y: i32| {
+            let (dx, dy, r) = ((x - cx) as i64, (y - cy) as i64, radius as i64);
+            dx * dx + dy * dy <= r * r
+        };
+        return clamp_span(cy - radius, cy + radius, height)
+            .flat_map(|y| columns.clone().map(move |x| (x, y)))
+            .filter(|&(x, y)| within(x, y))
+            .collect();
+    }
+
+    let mut pixels = Vec::new();
+    let (mut x, mut y) = (0, radius);
+    let mut d = 1 - radius;
+    while x <= y {
+        // Each step gives a point in all eight octants
+        pixels.extend([
+            (cx + x, cy + y), (cx + x, cy - y),
+            (cx - x, cy + y), (cx - x, cy - y),
+            (cx + y, cy + x), (cx + y, cy - x),
+            (cx - y, cy + x), (cx - y, cy - x),
+        ]);
+        if d < 0 {
+            d += 2 * x + 3;
+        } else {
+            d += 2 * (x - y) + 5;
+            y -= 1;
+        }
+        x += 1;
+    }
+    on_canvas(pixels, width, height)
+}
+
+fn oval_pixels(cx: i32, cy: i32, rx: i32, ry: i32, filled: bool, width: u16, height: u16) -> Vec<(i32, i32)> {
+    if filled {
+        let columns = clamp_span(cx - rx, cx + rx, width);
+        let (rx2, ry2) = (rx as i64 * rx as i64, ry as i64 * ry as i64);
+        let within = |x: i32, y: i32| {
+            let (dx, dy) = ((x - cx) as i64, (y - cy) as i64);
+            rx2 * dy * dy + ry2 * dx * dx <= rx2 * ry2
+        };
+        return clamp_span(cy - ry, cy + ry, height)
+            .flat_map(|y| columns.clone().map(move |x| (x, y)))
+            .filter(|&(x, y)| within(x, y))
+            .collect();
+    }
+
+    // Sampled along the parametric ellipse
+    let steps = ((rx + ry) * 2).max(20);
+    (0..steps)
+        .map(|i| {
+            let angle = 2.0 * std::f64::consts::PI * i as f64 / steps as f64;
+            (cx + (rx as f64 * angle.cos()) as i32, cy + (ry as f64 * angle.sin()) as i32)
+        })
+        .collect()
+}
+
+// Apex at the top centre, base along the bottom edge
+fn triangle_pixels(x: i32, y: i32, width: i32, height: i32, filled: bool, canvas_width: u16, canvas_height: u16) -> Vec<(i32, i32)> {
+    let (x1, y1) = (x + width / 2, y);
+    let (x2, y2) = (x, y + (height - 1).max(0));
+    let (x3, y3) = (x + (width - 1).max(0), y2);
+
+    if filled {
+        let mut pixels = Vec::new();
+        for row in clamp_span(y1, y2, canvas_height) {
+            let progress = if y2 == y1 { 0.0 } else { (row - y1) as f32 / (y2 - y1) as f32 };
+            let left = (x1 as f32 + progress * (x2 - x1) as f32) as i32;
+            let right = (x1 as f32 + progress * (x3 - x1) as f32) as i32;
+            pixels.extend(clamp_span(left.min(right), left.max(right), canvas_width).map(|column| (column, row)));
+        }
+        return pixels;
+    }
+
+    let corner = |x: i32, y: i32| Point { x: x as u16, y: y as u16 };
+    let (a, b, c) = (corner(x1, y1), corner(x2, y2), corner(x3, y3));
+    [(&a, &b), (&b, &c), (&c, &a)].into_iter()
+        .flat_map(|(start, end)| line_pixels(start, end))
+        .collect()
+}
+
+/// Pixels a fill from (x, y) replaces: those of its color 4-connected to it,
+/// or anywhere in the frame when not `contiguous`, inside `bounds`. Pixels
+/// `mask` (one flag per pixel, row by row) leaves unselected are walls the
+/// fill never crosses. Empty when (x, y) is off the canvas or outside either.
+#[allow(clippy::too_many_arguments)]
+pub fn flood_region(frame: &Frame, width: u16, height: u16, x: u16, y: u16, contiguous: bool, bounds: Option<&Rect>, mask: Option<&[bool]>) -> Vec<(u16, u16)> {
+    let allowed = |x: u16, y: u16| bounds.is_none_or(|b| b.contains(x, y))
+        && mask.is_none_or(|mask| mask.get(y as usize * width as usize + x as usize) == Some(&true));
+    if x >= width || y >= height || !allowed(x, y) {
+        return Vec::new();
+    }
+    let Some(target) = frame.get_pixel(x, y, width) else { return Vec::new() };
+    if !contiguous {
+        return (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| allowed(x, y) && frame.get_pixel(x, y, width) == Some(target))
+            .collect();
+    }
+
+    let mut seen = vec![false; width as usize * height as usize];
+    let mut stack = vec![(x, y)];
+    let mut region = Vec::new();
+    while let Some((x, y)) = stack.pop() {
+        let index = y as usize * width as usize + x as usize;
+        if seen[index] || !allowed(x, y) || frame.get_pixel(x, y, width) != Some(target) {
+            continue;
+        }
+        seen[index] = true;
+        region.push((x, y));
+
+        if x > 0 { stack.push((x - 1, y)); }
+        if y > 0 { stack.push((x, y - 1)); }
+        if x + 1 < width { stack.push((x + 1, y)); }
+        if y + 1 < height { stack.push((x, y + 1)); }
+    }
+    region
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pixel_book::Pixel;
+    use std::collections::HashSet;
+
+    fn distinct(pixels: Vec<(i32, i32)>) -> HashSet<(i32, i32)> {
+        pixels.into_iter().collect()
+    }
+
+    #[test]
+    fn test_shape_pixels() {
+        let position = Point { x: 1, y: 1 };
+        let size = Size { width: 3, height: 3 };
+        let outline = distinct(shape_pixels(&ShapeType::Rectangle, &position, &size, false, 10, 10));
+        assert_eq!(outline.len(), 8);
+        assert!(!outline.contains(&(2, 2)));
+        assert_eq!(distinct(shape_pixels(&ShapeType::Rectangle, &position, &size, true, 10, 10)).len(), 9);
+
+        let circle = distinct(circle_pixels(5, 5, 3, false, 10, 10));
+        let disc = distinct(circle_pixels(5, 5, 3, true, 10, 10));
+        assert!(circle.contains(&(5, 2)) && circle.contains(&(8, 5)));
+        assert!(!circle.contains(&(5, 5)) && disc.contains(&(5, 5)));
+
+        let triangle = distinct(shape_pixels(&ShapeType::Triangle, &Point { x: 0, y: 0 }, &Size { width: 5, height: 3 }, true, 10, 10));
+        assert!(triangle.contains(&(2, 0)) && triangle.contains(&(0, 2)) && triangle.contains(&(4, 2)));
+        assert!(!triangle.contains(&(0, 0)));
+    }
+
+    #[test]
+    fn test_shapes_far_larger_than_the_canvas_are_clipped() {
+        let huge = Size { width: u16::MAX, height: u16::MAX };
+        let origin = Point { x: 0, y: 0 };
+        for shape in [ShapeType::Rectangle, ShapeType::Circle, ShapeType::Oval, ShapeType::Triangle] {
+            for filled in [true, false] {
+                let pixels = shape_pixels(&shape, &origin, &huge, filled, 16, 16);
+                assert!(pixels.iter().all(|&(x, y)| (0..16).contains(&x) && (0..16).contains(&y)));
+            }
+        }
+        assert_eq!(shape_pixels(&ShapeType::Rectangle, &origin, &huge, true, 16, 16).len(), 256);
+
+        // Radii whose squares do not fit in an i32
+        let oval = distinct(shape_pixels(&ShapeType::Oval, &origin, &Size { width: 1000, height: 800 }, true, 1000, 800));
+        assert!(oval.contains(&(500, 400)) && oval.contains(&(0, 400)) && !oval.contains(&(0, 0)));
+        assert_eq!(circle_pixels(32000, 32000, 50000, true, 16, 16).len(), 256);
+        assert!(circle_pixels(8, 8, 40000, false, 16, 16).is_empty());
+    }
+
+    #[test]
+    fn test_flood_region() {
+        let mut frame = Frame::new(0, 4, 1);
+        frame.set_pixel(2, 0, 4, Pixel::new(255, 0, 0, 255));
+
+        assert_eq!(flood_region(&frame, 4, 1, 0, 0, true, None, None), vec![(0, 0), (1, 0)]);
+        assert_eq!(flood_region(&frame, 4, 1, 0, 0, false, None, None).len(), 3);
+        let bounds = Rect { x: 0, y: 0, width: 1, height: 1 };
+        assert_eq!(flood_region(&frame, 4, 1, 0, 0, true, Some(&bounds), None), vec![(0, 0)]);
+        assert!(flood_region(&frame, 4, 1, 9, 0, true, None, None).is_empty());
+
+        // Unselected pixels stop the fill even where the color carries on
+        let mask = [true, false, true, true];
+        assert_eq!(flood_region(&frame, 4, 1, 0, 0, true, None, Some(&mask)), vec![(0, 0)]);
+        assert_eq!(flood_region(&frame, 4, 1, 0, 0, false, None, Some(&mask)), vec![(0, 0), (3, 0)]);
+        assert!(flood_region(&frame, 4, 1, 1, 0, true, None, Some(&mask)).is_empty());
+    }
+}
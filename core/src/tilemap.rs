@@ -0,0 +1,284 @@
+use crate::pixel_book::{Frame, PixelBook};
+use serde::{Deserialize, Serialize};
+
+/// Widest and tallest a composited tilemap may be, in pixels
+pub const MAX_TILEMAP_SIZE: u32 = 4096;
+
+/// Longest tilemap name, in characters
+pub const MAX_TILEMAP_NAME_LEN: usize = 64;
+
+/// Declares a book a tileset: each frame is cut into tiles of this size,
+/// numbered left to right, then top to bottom. Tiles that would run past the
+/// right or bottom edge are left out. Body of `PUT /books/:filename/tileset`;
+/// stored in the book's metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Tileset {
+    pub tile_width: u16,
+    pub tile_height: u16,
+}
+
+impl Tileset {
+    /// Checks at least one tile fits in a `width` x `height` book
+    pub fn validate(&self, width: u16, height: u16) -> Result<(), String> {
+        if self.tile_width == 0 || self.tile_height == 0 {
+            return Err("Tile width and height must be greater than 0".to_string());
+        }
+        if self.tile_width > width || self.tile_height > height {
+            return Err(format!("{}x{} tiles do not fit in a {}x{} book", self.tile_width, self.tile_height, width, height));
+        }
+        Ok(())
+    }
+
+    /// Tiles across a book `width` pixels wide
+    pub fn columns(&self, width: u16) -> u16 {
+        width.checked_div(self.tile_width).unwrap_or(0)
+    }
+
+    /// Tiles down a book `height` pixels tall
+    pub fn rows(&self, height: u16) -> u16 {
+        height.checked_div(self.tile_height).unwrap_or(0)
+    }
+
+    pub fn tile_count(&self, width: u16, height: u16) -> u32 {
+        self.columns(width) as u32 * self.rows(height) as u32
+    }
+
+    /// Top-left pixel of tile `index` in a book `width` pixels wide
+    pub fn tile_origin(&self, index: u32, width: u16) -> (u32, u32) {
+        let columns = self.columns(width).max(1) as u32;
+        ((index % columns) * self.tile_width as u32, (index / columns) * self.tile_height as u32)
+    }
+
+    pub fn info(&self, filename: &str, width: u16, height: u16) -> TilesetInfo {
+        TilesetInfo {
+            filename: filename.to_string(),
+            tile_width: self.tile_width,
+            tile_height: self.tile_height,
+            columns: self.columns(width),
+            rows: self.rows(height),
+            tile_count: self.tile_count(width, height),
+        }
+    }
+}
+
+/// Response of `GET/PUT /books/:filename/tileset`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TilesetInfo {
+    pub filename: String,
+    pub tile_width: u16,
+    pub tile_height: u16,
+    pub columns: u16,
+    pub rows: u16,
+    pub tile_count: u32,
+}
+
+/// True for 1 to [`MAX_TILEMAP_NAME_LEN`] ASCII letters, digits, `-` and `_`
+pub fn valid_tilemap_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_TILEMAP_NAME_LEN
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// A grid of tiles taken from one frame of a tileset book
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Tilemap {
+    pub name: String,
+    /// Filename of the tileset book
+    pub tileset: String,
+    /// Frame of the tileset the tiles are cut from
+    #[serde(default)]
+    pub frame: usize,
+    pub columns: u16,
+    pub rows: u16,
+    /// Tile index of each cell, row by row; `null` leaves a cell empty
+    pub tiles: Vec<Option<u32>>,
+}
+
+/// Body of `PUT /tilemaps/:name`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TilemapRequest {
+    pub tileset: String,
+    #[serde(default)]
+    pub frame: usize,
+    pub columns: u16,
+    pub rows: u16,
+    /// Tile index of each cell, row by row; every cell starts empty when omitted
+    #[serde(default)]
+    pub tiles: Vec<Option<u32>>,
+}
+
+impl TilemapRequest {
+    /// The tilemap this request describes, before it is checked against its tileset
+    pub fn into_tilemap(self, name: &str) -> Tilemap {
+        let cells = self.columns as usize * self.rows as usize;
+        let tiles = if self.tiles.is_empty() { vec![None; cells] } else { self.tiles };
+        Tilemap { name: name.to_string(), tileset: self.tileset, frame: self.frame, columns: self.columns, rows: self.rows, tiles }
+    }
+}
+
+/// One cell of a `PATCH /tilemaps/:name/tiles` request
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TilePlacement {
+    pub x: u16,
+    pub y: u16,
+    /// `null` clears the cell
+    pub tile: Option<u32>,
+}
+
+/// Body of `PATCH /tilemaps/:name/tiles`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct SetTilesRequest {
+    pub cells: Vec<TilePlacement>,
+}
+
+/// One entry of `GET /tilemaps`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TilemapSummary {
+    pub name: String,
+    pub tileset: String,
+    pub columns: u16,
+    pub rows: u16,
+}
+
+impl Tilemap {
+    pub fn summary(&self) -> TilemapSummary {
+        TilemapSummary { name: self.name.clone(), tileset: self.tileset.clone(), columns: self.columns, rows: self.rows }
+    }
+
+    /// Size of the composited map in pixels
+    pub fn pixel_size(&self, tileset: &Tileset) -> (u32, u32) {
+        (self.columns as u32 * tileset.tile_width as u32, self.rows as u32 * tileset.tile_height as u32)
+    }
+
+    /// Checks the map's shape and that every tile exists in `book`, cut by `tileset`
+    pub fn validate(&self, tileset: &Tileset, book: &PixelBook) -> Result<(), String> {
+        if !valid_tilemap_name(&self.name) {
+            return Err(format!("Tilemap names are 1-{} letters, digits, '-' or '_'", MAX_TILEMAP_NAME_LEN));
+        }
+        if self.columns == 0 || self.rows == 0 {
+            return Err("Tilemaps need at least one column and one row".to_string());
+        }
+        let (width, height) = self.pixel_size(tileset);
+        if width > MAX_TILEMAP_SIZE || height > MAX_TILEMAP_SIZE {
+            return Err(format!("A {}x{} pixel map exceeds the {} pixel maximum", width, height, MAX_TILEMAP_SIZE));
+        }
+        if self.tiles.len() != self.columns as usize * self.rows as usize {
+            return Err(format!("{} tiles given for {}x{} cells", self.tiles.len(), self.columns, self.rows));
+        }
+        if self.frame >= book.frames.len() {
+            return Err(format!("Frame {} does not exist; {} has {} frames", self.frame, book.filename, book.frames.len()));
+        }
+        let tile_count = tileset.tile_count(book.width, book.height);
+        if let Some(tile) = self.tiles.iter().flatten().find(|&&tile| tile >= tile_count) {
+            return Err(format!("Tile {} does not exist; {} has {} tiles", tile, book.filename, tile_count));
+        }
+        Ok(())
+    }
+
+    /// Places each tile, failing on cells outside the map before changing any
+    pub fn set_tiles(&mut self, cells: &[TilePlacement]) -> Result<(), String> {
+        if let Some(cell) = cells.iter().find(|cell| cell.x >= self.columns || cell.y >= self.rows) {
+            return Err(format!("Cell ({}, {}) is outside the {}x{} map", cell.x, cell.y, self.columns, self.rows));
+        }
+        for cell in cells {
+            self.tiles[cell.y as usize * self.columns as usize + cell.x as usize] = cell.tile;
+        }
+        Ok(())
+    }
+
+    /// Draws every tile into one frame of [`Tilemap::pixel_size`]. Empty
+    /// cells, and tiles the tileset no longer has, stay transparent.
+    pub fn compose(&self, tileset: &Tileset, book: &PixelBook) -> Frame {
+        let (width, height) = self.pixel_size(tileset);
+        let mut frame = Frame { index: 0, pixels: vec![0; width as usize * height as usize * 4], duration_ms: None };
+        let Some(source) = book.frames.get(self.frame) else {
+            return frame;
+        };
+
+        let tile_count = tileset.tile_count(book.width, book.height);
+        let row_bytes = tileset.tile_width as usize * 4;
+        for (cell, tile) in self.tiles.iter().enumerate() {
+            let Some(tile) = tile.filter(|&tile| tile < tile_count) else {
+                continue;
+            };
+            let (src_x, src_y) = tileset.tile_origin(tile, book.width);
+            let dst_x = (cell % self.columns as usize) * tileset.tile_width as usize;
+            let dst_y = (cell / self.columns as usize) * tileset.tile_height as usize;
+            for row in 0..tileset.tile_height as usize {
+                let src = ((src_y as usize + row) * book.width as usize + src_x as usize) * 4;
+                let dst = ((dst_y + row) * width as usize + dst_x) * 4;
+                frame.pixels[dst..dst + row_bytes].copy_from_slice(&source.pixels[src..src + row_bytes]);
+            }
+        }
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pixel_book::Pixel;
+
+    // 4x2 book of two 2x2 tiles: red on the left, blue on the right
+    fn tileset_book() -> PixelBook {
+        let mut book = PixelBook::new("tiles.pxl".to_string(), 4, 2, 1);
+        for y in 0..2 {
+            for x in 0..4 {
+                let color = if x < 2 { Pixel::new(255, 0, 0, 255) } else { Pixel::new(0, 0, 255, 255) };
+                book.frames[0].set_pixel(x, y, 4, color);
+            }
+        }
+        book
+    }
+
+    #[test]
+    fn test_tileset_cuts_whole_tiles() {
+        let tileset = Tileset { tile_width: 3, tile_height: 2 };
+        assert_eq!((tileset.columns(8), tileset.rows(5), tileset.tile_count(8, 5)), (2, 2, 4));
+        assert_eq!(tileset.tile_origin(3, 8), (3, 2));
+        assert!(tileset.validate(8, 5).is_ok());
+        assert!(tileset.validate(2, 5).is_err());
+        assert!(Tileset { tile_width: 0, tile_height: 2 }.validate(8, 5).is_err());
+    }
+
+    #[test]
+    fn test_compose_places_tiles() {
+        let book = tileset_book();
+        let tileset = Tileset { tile_width: 2, tile_height: 2 };
+        let mut map = TilemapRequest { tileset: "tiles.pxl".to_string(), columns: 3, rows: 1, ..Default::default() }.into_tilemap("level-1");
+        assert_eq!(map.tiles, vec![None; 3]);
+        map.set_tiles(&[TilePlacement { x: 0, y: 0, tile: Some(1) }, TilePlacement { x: 2, y: 0, tile: Some(0) }]).unwrap();
+        assert!(map.validate(&tileset, &book).is_ok());
+
+        let frame = map.compose(&tileset, &book);
+        assert_eq!(map.pixel_size(&tileset), (6, 2));
+        assert_eq!(frame.get_pixel(1, 1, 6), Some(Pixel::new(0, 0, 255, 255)));
+        assert_eq!(frame.get_pixel(2, 0, 6), Some(Pixel::new(0, 0, 0, 0)));
+        assert_eq!(frame.get_pixel(5, 1, 6), Some(Pixel::new(255, 0, 0, 255)));
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_maps() {
+        let book = tileset_book();
+        let tileset = Tileset { tile_width: 2, tile_height: 2 };
+        let map = |name: &str, tiles: Vec<Option<u32>>| Tilemap { name: name.to_string(), tileset: "tiles.pxl".to_string(), frame: 0, columns: 2, rows: 1, tiles };
+
+        assert!(map("ok", vec![Some(0), None]).validate(&tileset, &book).is_ok());
+        assert!(map("bad name", vec![None, None]).validate(&tileset, &book).is_err());
+        assert!(map("short", vec![None]).validate(&tileset, &book).is_err());
+        assert!(map("missing-tile", vec![Some(2), None]).validate(&tileset, &book).is_err());
+        assert!(Tilemap { frame: 1, ..map("frame", vec![None, None]) }.validate(&tileset, &book).is_err());
+        assert!(Tilemap { columns: 2049, tiles: vec![None; 2049], ..map("huge", Vec::new()) }.validate(&tileset, &book).is_err());
+
+        let mut small = map("small", vec![None, None]);
+        assert!(small.set_tiles(&[TilePlacement { x: 0, y: 0, tile: Some(1) }, TilePlacement { x: 2, y: 0, tile: Some(0) }]).is_err());
+        assert_eq!(small.tiles, vec![None, None]);
+    }
+}
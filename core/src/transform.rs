@@ -0,0 +1,434 @@
+use crate::critique::visible_bounds;
+use crate::operations::{FlipAxis, Rect};
+use crate::pixel_book::PixelBook;
+use crate::tilemap::Tileset;
+use serde::{Deserialize, Serialize};
+
+/// Which part of the canvas stays put when a book is resized
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    #[default]
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// How far the old canvas moves inside the new one. Centering an odd
+    /// difference puts the extra column or row on the right or bottom.
+    pub fn offset(self, width: u16, height: u16, new_width: u16, new_height: u16) -> (i32, i32) {
+        let (dw, dh) = (new_width as i32 - width as i32, new_height as i32 - height as i32);
+        let (column, row) = match self {
+            Self::TopLeft => (0, 0),
+            Self::Top => (1, 0),
+            Self::TopRight => (2, 0),
+            Self::Left => (0, 1),
+            Self::Center => (1, 1),
+            Self::Right => (2, 1),
+            Self::BottomLeft => (0, 2),
+            Self::Bottom => (1, 2),
+            Self::BottomRight => (2, 2),
+        };
+        (dw * column / 2, dh * row / 2)
+    }
+}
+
+/// Body of `POST /books/:filename/resize`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ResizeRequest {
+    pub width: u16,
+    pub height: u16,
+    #[serde(default)]
+    pub anchor: Anchor,
+    /// Color of canvas added around the old one; transparent by default
+    #[serde(default)]
+    pub fill: [u8; 4],
+}
+
+/// RGBA pixels of a `width` x `height` frame placed on a `new_width` x
+/// `new_height` canvas by `anchor`. Pixels falling outside are dropped and
+/// new canvas is painted `fill`.
+pub fn resize_canvas_pixels(pixels: &[u8], width: u16, height: u16, new_width: u16, new_height: u16, anchor: Anchor, fill: [u8; 4]) -> Vec<u8> {
+    let (dx, dy) = anchor.offset(width, height, new_width, new_height);
+    let mut result = fill.repeat(new_width as usize * new_height as usize);
+    for y in 0..new_height as i32 {
+        let sy = y - dy;
+        if sy < 0 || sy >= height as i32 {
+            continue;
+        }
+        for x in 0..new_width as i32 {
+            let sx = x - dx;
+            if sx < 0 || sx >= width as i32 {
+                continue;
+            }
+            let from = (sy as usize * width as usize + sx as usize) * 4;
+            let to = (y as usize * new_width as usize + x as usize) * 4;
+            result[to..to + 4].copy_from_slice(&pixels[from..from + 4]);
+        }
+    }
+    result
+}
+
+/// Resizes every frame's canvas. A tileset whose tiles no longer fit is dropped.
+pub fn resize_book(book: &mut PixelBook, request: &ResizeRequest) {
+    for frame in &mut book.frames {
+        frame.pixels = resize_canvas_pixels(&frame.pixels, book.width, book.height, request.width, request.height, request.anchor, request.fill);
+    }
+    book.width = request.width;
+    book.height = request.height;
+    book.metadata.tileset = book.metadata.tileset.filter(|tileset| tileset.validate(book.width, book.height).is_ok());
+}
+
+/// Largest factor a book may be scaled up or down by
+pub const MAX_SCALE_FACTOR: u16 = 16;
+
+/// RGBA pixels of a `width` x `height` frame resampled to `new_width` x
+/// `new_height` with nearest-neighbour sampling
+pub fn scale_pixels(pixels: &[u8], width: u16, height: u16, new_width: u16, new_height: u16) -> Vec<u8> {
+    let mut result = Vec::with_capacity(new_width as usize * new_height as usize * 4);
+    for y in 0..new_height as usize {
+        let sy = y * height as usize / new_height as usize;
+        for x in 0..new_width as usize {
+            let from = (sy * width as usize + x * width as usize / new_width as usize) * 4;
+            result.extend_from_slice(&pixels[from..from + 4]);
+        }
+    }
+    result
+}
+
+// One side scaled by `factor`, or `None` when it overflows or, scaling
+// down, does not divide evenly
+fn scale_side(side: u16, factor: u16, down: bool) -> Option<u16> {
+    if down {
+        side.is_multiple_of(factor).then(|| side / factor)
+    } else {
+        side.checked_mul(factor)
+    }
+}
+
+/// The size a `width` x `height` canvas becomes when scaled by `factor`,
+/// without touching any pixels, so callers can check it before scaling
+pub fn scaled_size(width: u16, height: u16, factor: u16, down: bool) -> Result<(u16, u16), String> {
+    if !(1..=MAX_SCALE_FACTOR).contains(&factor) {
+        return Err(format!("Scale factor must be between 1 and {}", MAX_SCALE_FACTOR));
+    }
+    match (scale_side(width, factor, down), scale_side(height, factor, down)) {
+        (Some(new_width), Some(new_height)) => Ok((new_width, new_height)),
+        _ if down => Err(format!("A {}x{} canvas does not divide by {}", width, height, factor)),
+        _ => Err(format!("A {}x{} canvas is too large to scale by {}", width, height, factor)),
+    }
+}
+
+/// Scales every frame up by a whole `factor`, or down when `down` is set,
+/// which keeps the top-left pixel of each `factor` x `factor` block and needs
+/// the canvas to divide evenly. A tileset's tile size scales along with it.
+pub fn scale_book(book: &mut PixelBook, factor: u16, down: bool) -> Result<(), String> {
+    let (width, height) = scaled_size(book.width, book.height, factor, down)?;
+
+    for frame in &mut book.frames {
+        frame.pixels = scale_pixels(&frame.pixels, book.width, book.height, width, height);
+    }
+    book.width = width;
+    book.height = height;
+    book.metadata.tileset = book.metadata.tileset.and_then(|tileset| {
+        let (Some(tile_width), Some(tile_height)) = (scale_side(tileset.tile_width, factor, down), scale_side(tileset.tile_height, factor, down)) else {
+            return None;
+        };
+        Some(Tileset { tile_width, tile_height }).filter(|tileset| tileset.validate(width, height).is_ok())
+    });
+    Ok(())
+}
+
+/// Crops every frame to the smallest rectangle holding all of the book's
+/// visible pixels, returning that rectangle in the old canvas. Fails when
+/// every frame is empty.
+pub fn trim_book(book: &mut PixelBook) -> Result<Rect, String> {
+    let bounds = book.frames.iter()
+        .filter_map(|frame| visible_bounds(frame, book.width, book.height))
+        .map(|rect| (rect.x, rect.y, rect.x + rect.width, rect.y + rect.height))
+        .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+        .ok_or_else(|| format!("{} has no visible pixels to trim to", book.filename))?;
+    let rect = Rect { x: bounds.0, y: bounds.1, width: bounds.2 - bounds.0, height: bounds.3 - bounds.1 };
+    crop_book(book, &rect)?;
+    Ok(rect)
+}
+
+/// Crops every frame to the part of `rect` inside the canvas, failing when
+/// that is empty. A tileset whose tiles no longer fit is dropped.
+pub fn crop_book(book: &mut PixelBook, rect: &Rect) -> Result<(), String> {
+    if rect.width == 0 || rect.height == 0 || rect.x >= book.width || rect.y >= book.height {
+        return Err(format!("Crop rectangle must overlap the {}x{} canvas", book.width, book.height));
+    }
+
+    let (mut width, mut height) = (book.width, book.height);
+    for frame in &mut book.frames {
+        let (region_width, region_height, pixels) = region_pixels(&frame.pixels, book.width, book.height, rect);
+        frame.pixels = pixels;
+        (width, height) = (region_width, region_height);
+    }
+    book.width = width;
+    book.height = height;
+    book.metadata.tileset = book.metadata.tileset.filter(|tileset| tileset.validate(book.width, book.height).is_ok());
+    Ok(())
+}
+
+/// RGBA pixels of a `width` x `height` frame mirrored along `axis`
+pub fn flip_pixels(pixels: &[u8], width: u16, height: u16, axis: FlipAxis) -> Vec<u8> {
+    let (w, h) = (width as i32, height as i32);
+    remap(pixels, width, height, |x, y| match axis {
+        FlipAxis::Horizontal => (w - 1 - x, y),
+        FlipAxis::Vertical => (x, h - 1 - y),
+    })
+}
+
+/// RGBA pixels of a frame rotated clockwise by `turns` quarter turns. Odd
+/// turns swap the axes, so they expect a square frame.
+pub fn rotate_pixels(pixels: &[u8], width: u16, height: u16, turns: u8) -> Vec<u8> {
+    let (w, h) = (width as i32, height as i32);
+    remap(pixels, width, height, |x, y| match turns % 4 {
+        1 => (y, h - 1 - x),
+        2 => (w - 1 - x, h - 1 - y),
+        3 => (w - 1 - y, x),
+        _ => (x, y),
+    })
+}
+
+/// RGBA pixels of a frame moved by (`dx`, `dy`). Pixels pushed off one edge
+/// come back on the opposite edge when `wrap` is set and are dropped
+/// otherwise, leaving transparent pixels behind.
+pub fn shift_pixels(pixels: &[u8], width: u16, height: u16, dx: i32, dy: i32, wrap: bool) -> Vec<u8> {
+    let (w, h) = (width as i32, height as i32);
+    // Any offset past the frame size moves every pixel off it, and wrapping
+    // repeats every frame size, so reducing the offsets keeps `x - dx` in range
+    let (dx, dy) = if wrap {
+        (dx.rem_euclid(w.max(1)), dy.rem_euclid(h.max(1)))
+    } else {
+        (dx.clamp(-w, w), dy.clamp(-h, h))
+    };
+    remap(pixels, width, height, |x, y| {
+        let (sx, sy) = (x - dx, y - dy);
+        if wrap {
+            (sx.rem_euclid(w), sy.rem_euclid(h))
+        } else {
+            (sx, sy)
+        }
+    })
+}
+
+/// The part of `rect` inside a `width` x `height` frame, as its width,
+/// height and RGBA pixels row by row; empty when `rect` starts off the frame
+pub fn region_pixels(pixels: &[u8], width: u16, height: u16, rect: &Rect) -> (u16, u16, Vec<u8>) {
+    if rect.x >= width || rect.y >= height {
+        return (0, 0, Vec::new());
+    }
+    let region_width = rect.width.min(width.saturating_sub(rect.x));
+    let region_height = rect.height.min(height.saturating_sub(rect.y));
+    let row_bytes = region_width as usize * 4;
+    let mut region = Vec::with_capacity(row_bytes * region_height as usize);
+    for row in 0..region_height {
+        let start = ((rect.y + row) as usize * width as usize + rect.x as usize) * 4;
+        region.extend_from_slice(&pixels[start..start + row_bytes]);
+    }
+    (region_width, region_height, region)
+}
+
+/// Offsets and colors a `width` x `height` stamp paints, mirrored along
+/// `flip` first. Fully transparent pixels are left out when
+/// `skip_transparent` is set.
+pub fn stamp_pixels(
+    pixels: &[u8],
+    width: u16,
+    height: u16,
+    flip: Option<FlipAxis>,
+    skip_transparent: bool,
+) -> Vec<(u16, u16, [u8; 4])> {
+    let pixels = match flip {
+        Some(axis) => flip_pixels(pixels, width, height, axis),
+        None => pixels.to_vec(),
+    };
+    pixels.chunks_exact(4).enumerate()
+        .filter(|(_, rgba)| !skip_transparent || rgba[3] != 0)
+        .map(|(i, rgba)| {
+            let (x, y) = ((i % width as usize) as u16, (i / width as usize) as u16);
+            (x, y, [rgba[0], rgba[1], rgba[2], rgba[3]])
+        })
+        .collect()
+}
+
+// Builds a frame whose pixel at (x, y) is copied from `source(x, y)`;
+// sources off the frame give transparent pixels
+fn remap(pixels: &[u8], width: u16, height: u16, source: impl Fn(i32, i32) -> (i32, i32)) -> Vec<u8> {
+    let (w, h) = (width as i32, height as i32);
+    let mut result = vec![0; pixels.len()];
+    for y in 0..h {
+        for x in 0..w {
+            let (sx, sy) = source(x, y);
+            if sx < 0 || sy < 0 || sx >= w || sy >= h {
+                continue;
+            }
+            let from = (sy * w + sx) as usize * 4;
+            let to = (y * w + x) as usize * 4;
+            result[to..to + 4].copy_from_slice(&pixels[from..from + 4]);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pixel_book::Pixel;
+
+    // 3x2 frame whose pixels are numbered 1..=6 in the red channel, row by row
+    fn numbered() -> Vec<u8> {
+        (1..=6).flat_map(|n| [n, 0, 0, 255]).collect()
+    }
+
+    fn reds(pixels: &[u8]) -> Vec<u8> {
+        pixels.chunks(4).map(|p| p[0]).collect()
+    }
+
+    #[test]
+    fn test_flip_and_shift() {
+        let pixels = numbered();
+        assert_eq!(reds(&flip_pixels(&pixels, 3, 2, FlipAxis::Horizontal)), [3, 2, 1, 6, 5, 4]);
+        assert_eq!(reds(&flip_pixels(&pixels, 3, 2, FlipAxis::Vertical)), [4, 5, 6, 1, 2, 3]);
+
+        assert_eq!(reds(&shift_pixels(&pixels, 3, 2, 1, 0, true)), [3, 1, 2, 6, 4, 5]);
+        assert_eq!(reds(&shift_pixels(&pixels, 3, 2, -1, -1, true)), [5, 6, 4, 2, 3, 1]);
+        let shifted = shift_pixels(&pixels, 3, 2, 1, 1, false);
+        assert_eq!(reds(&shifted), [0, 0, 0, 0, 1, 2]);
+        assert_eq!(shifted[3], 0);
+    }
+
+    #[test]
+    fn test_shift_by_extreme_offsets() {
+        let pixels = numbered();
+        // i32::MIN is -2 mod 3 and 0 mod 2
+        assert_eq!(shift_pixels(&pixels, 3, 2, i32::MIN, i32::MIN, true), shift_pixels(&pixels, 3, 2, 1, 0, true));
+        assert_eq!(shift_pixels(&pixels, 3, 2, i32::MAX, 0, true), shift_pixels(&pixels, 3, 2, 1, 0, true));
+        for (dx, dy) in [(i32::MIN, 0), (0, i32::MIN), (i32::MAX, i32::MAX)] {
+            assert!(reds(&shift_pixels(&pixels, 3, 2, dx, dy, false)).iter().all(|&r| r == 0));
+        }
+    }
+
+    #[test]
+    fn test_region_and_stamp_pixels() {
+        let pixels = numbered();
+        let (width, height, region) = region_pixels(&pixels, 3, 2, &Rect { x: 1, y: 0, width: 5, height: 1 });
+        assert_eq!((width, height, reds(&region)), (2, 1, vec![2, 3]));
+        assert_eq!(region_pixels(&pixels, 3, 2, &Rect { x: 1000, y: 0, width: 5, height: 1 }), (0, 0, Vec::new()));
+        assert_eq!(region_pixels(&pixels, 3, 2, &Rect { x: 0, y: 2, width: 5, height: 1 }), (0, 0, Vec::new()));
+
+        let mut stamp = numbered();
+        stamp[3] = 0;
+        let painted = stamp_pixels(&stamp, 3, 2, Some(FlipAxis::Horizontal), true);
+        assert_eq!(painted.len(), 5);
+        assert_eq!(painted[0], (0, 0, [3, 0, 0, 255]));
+        assert!(!painted.iter().any(|&(x, y, _)| (x, y) == (2, 0)));
+        assert_eq!(stamp_pixels(&stamp, 3, 2, None, false).len(), 6);
+    }
+
+    #[test]
+    fn test_rotate_quarter_turns() {
+        // 2x2: 1 2 / 3 4
+        let pixels: Vec<u8> = (1..=4).flat_map(|n| [n, 0, 0, 255]).collect();
+        assert_eq!(reds(&rotate_pixels(&pixels, 2, 2, 1)), [3, 1, 4, 2]);
+        assert_eq!(reds(&rotate_pixels(&pixels, 2, 2, 2)), [4, 3, 2, 1]);
+        assert_eq!(reds(&rotate_pixels(&pixels, 2, 2, 3)), [2, 4, 1, 3]);
+        assert_eq!(reds(&rotate_pixels(&numbered(), 3, 2, 2)), [6, 5, 4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn test_resize_canvas_anchors() {
+        // Growing 3x2 to 5x3 centered adds a column each side and a row below
+        let grown = resize_canvas_pixels(&numbered(), 3, 2, 5, 3, Anchor::Center, [9, 9, 9, 9]);
+        let reds: Vec<u8> = grown.chunks(4).map(|p| p[0]).collect();
+        assert_eq!(reds, vec![9, 1, 2, 3, 9, 9, 4, 5, 6, 9, 9, 9, 9, 9, 9]);
+
+        let shrunk = resize_canvas_pixels(&numbered(), 3, 2, 2, 1, Anchor::BottomRight, [0; 4]);
+        assert_eq!(shrunk.chunks(4).map(|p| p[0]).collect::<Vec<_>>(), vec![5, 6]);
+        assert_eq!(Anchor::Center.offset(4, 4, 1, 1), (-1, -1));
+    }
+
+    #[test]
+    fn test_crop_book_clips_to_the_canvas() {
+        let mut book = PixelBook::new("c.pxl".to_string(), 3, 2, 2);
+        book.frames[1].pixels = numbered();
+        book.metadata.tileset = Some(Tileset { tile_width: 2, tile_height: 2 });
+
+        crop_book(&mut book, &Rect { x: 1, y: 1, width: 5, height: 5 }).unwrap();
+        assert_eq!((book.width, book.height), (2, 1));
+        assert_eq!(book.frames[1].pixels.chunks(4).map(|p| p[0]).collect::<Vec<_>>(), vec![5, 6]);
+        assert_eq!(book.frames[0].pixels.len(), 8);
+        assert!(book.metadata.tileset.is_none());
+
+        assert!(crop_book(&mut book, &Rect { x: 2, y: 0, width: 1, height: 1 }).is_err());
+        assert!(crop_book(&mut book, &Rect { x: 0, y: 0, width: 0, height: 1 }).is_err());
+    }
+
+    #[test]
+    fn test_resize_book_keeps_frames_consistent() {
+        let mut book = PixelBook::new("r.pxl".to_string(), 3, 2, 2);
+        book.frames[0].pixels = numbered();
+        let request = ResizeRequest { width: 4, height: 4, anchor: Anchor::TopLeft, fill: [0, 0, 0, 255] };
+        resize_book(&mut book, &request);
+        assert_eq!((book.width, book.height), (4, 4));
+        assert!(book.frames.iter().all(|frame| frame.pixels.len() == 4 * 4 * 4));
+        assert_eq!(&book.frames[0].pixels[4..8], &[2, 0, 0, 255]);
+        assert_eq!(&book.frames[1].pixels[12..16], &[0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_scale_book_up_and_down() {
+        let mut book = PixelBook::new("s.pxl".to_string(), 3, 2, 2);
+        book.frames[0].pixels = numbered();
+        book.metadata.tileset = Some(Tileset { tile_width: 1, tile_height: 2 });
+
+        scale_book(&mut book, 2, false).unwrap();
+        assert_eq!((book.width, book.height), (6, 4));
+        let reds: Vec<u8> = book.frames[0].pixels.chunks(4).map(|p| p[0]).collect();
+        assert_eq!(&reds[..12], &[1, 1, 2, 2, 3, 3, 1, 1, 2, 2, 3, 3]);
+        assert_eq!(reds[23], 6);
+        assert_eq!(book.frames[1].pixels.len(), 6 * 4 * 4);
+        assert_eq!(book.metadata.tileset, Some(Tileset { tile_width: 2, tile_height: 4 }));
+
+        scale_book(&mut book, 2, true).unwrap();
+        assert_eq!((book.width, book.height), (3, 2));
+        assert_eq!(book.frames[0].pixels, numbered());
+        assert_eq!(book.metadata.tileset, Some(Tileset { tile_width: 1, tile_height: 2 }));
+
+        assert!(scale_book(&mut book, 2, true).is_err());
+        assert!(scale_book(&mut book, 0, false).is_err());
+        assert!(scale_book(&mut PixelBook::new("big.pxl".to_string(), 5000, 1, 1), 16, false).is_err());
+
+        // Sizes are known up front, without allocating any frames
+        assert_eq!(scaled_size(4000, 4000, 16, false), Ok((64000, 64000)));
+        assert_eq!(scaled_size(6, 4, 2, true), Ok((3, 2)));
+        assert!(scaled_size(6, 4, 4, true).is_err());
+    }
+
+    #[test]
+    fn test_trim_book_uses_the_union_of_every_frame() {
+        let mut book = PixelBook::new("t.pxl".to_string(), 6, 5, 2);
+        book.frames[0].set_pixel(1, 3, 6, Pixel::new(255, 0, 0, 255));
+        book.frames[1].set_pixel(4, 1, 6, Pixel::new(0, 0, 255, 1));
+
+        let rect = trim_book(&mut book).unwrap();
+        assert_eq!((rect.x, rect.y, rect.width, rect.height), (1, 1, 4, 3));
+        assert_eq!((book.width, book.height), (4, 3));
+        assert_eq!(book.frames[0].get_pixel(0, 2, 4), Some(Pixel::new(255, 0, 0, 255)));
+        assert_eq!(book.frames[1].get_pixel(3, 0, 4), Some(Pixel::new(0, 0, 255, 1)));
+
+        assert!(trim_book(&mut PixelBook::new("empty.pxl".to_string(), 2, 2, 1)).is_err());
+    }
+}
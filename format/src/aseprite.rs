@@ -0,0 +1,132 @@
+//! Sprite sheets with Aseprite-compatible JSON metadata.
+//!
+//! The JSON follows Aseprite's `--format json-array` export (frame rects,
+//! per-frame durations, `meta.frameTags` from the book's frame tags), which
+//! most engine importers read.
+//! Only available with the `image` feature.
+
+use crate::convert::{frame_to_image, DEFAULT_FRAME_DELAY_MS};
+use image::{imageops, RgbaImage};
+use pixl_core::PixelBook;
+use serde_json::{json, Value};
+
+/// Columns used when none are requested: one row holding every frame
+pub fn default_columns(book: &PixelBook) -> u32 {
+    book.frames.len().max(1) as u32
+}
+
+/// Position of frame `index` in a sheet with `columns` columns
+fn cell(book: &PixelBook, index: usize, columns: u32) -> (u32, u32) {
+    let index = index as u32;
+    (
+        (index % columns) * book.width as u32,
+        (index / columns) * book.height as u32,
+    )
+}
+
+/// Lays the frames out left to right, top to bottom, `columns` to a row
+pub fn sprite_sheet(book: &PixelBook, columns: u32) -> RgbaImage {
+    let columns = columns.clamp(1, default_columns(book));
+    let rows = (book.frames.len() as u32).div_ceil(columns).max(1);
+    let mut sheet = RgbaImage::new(columns * book.width as u32, rows * book.height as u32);
+
+    for (index, frame) in book.frames.iter().enumerate() {
+        let (x, y) = cell(book, index, columns);
+        imageops::replace(&mut sheet, &frame_to_image(frame, book.width, book.height), x as i64, y as i64);
+    }
+    sheet
+}
+
+/// Aseprite JSON describing [`sprite_sheet`] output saved as `image_name`
+pub fn aseprite_json(book: &PixelBook, image_name: &str, columns: u32) -> Value {
+    let columns = columns.clamp(1, default_columns(book));
+    let rows = (book.frames.len() as u32).div_ceil(columns).max(1);
+    let (w, h) = (book.width as u32, book.height as u32);
+    let stem = book.filename.trim_end_matches(".pxl");
+
+    let frames: Vec<Value> = (0..book.frames.len())
+        .map(|index| {
+            let (x, y) = cell(book, index, columns);
+            json!({
+                "filename": format!("{} {}.aseprite", stem, index),
+                "frame": { "x": x, "y": y, "w": w, "h": h },
+                "rotated": false,
+                "trimmed": false,
+                "spriteSourceSize": { "x": 0, "y": 0, "w": w, "h": h },
+                "sourceSize": { "w": w, "h": h },
+                "duration": book.frames[index].duration_ms.unwrap_or(DEFAULT_FRAME_DELAY_MS)
+            })
+        })
+        .collect();
+
+    // Tags left pointing past the last frame, e.g. after frames were removed,
+    // are cut short or dropped
+    let last = book.frames.len().saturating_sub(1);
+    let frame_tags: Vec<Value> = book.metadata.details.frame_tags.iter()
+        .filter(|tag| tag.from <= last)
+        .map(|tag| json!({
+            "name": tag.name,
+            "from": tag.from,
+            "to": tag.to.min(last),
+            "direction": tag.direction
+        }))
+        .collect();
+
+    json!({
+        "frames": frames,
+        "meta": {
+            "app": "pixl",
+            "version": env!("CARGO_PKG_VERSION"),
+            "image": image_name,
+            "format": "RGBA8888",
+            "size": { "w": columns * w, "h": rows * h },
+            "scale": "1",
+            "frameTags": frame_tags,
+            "layers": [],
+            "slices": []
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pixl_core::{FrameTag, Pixel, TagDirection};
+
+    #[test]
+    fn test_sheet_layout_matches_json() {
+        let mut book = PixelBook::new("walk.pxl".to_string(), 2, 3, 3);
+        book.frames[2].set_pixel(1, 2, 2, Pixel::new(0, 255, 0, 255));
+
+        let sheet = sprite_sheet(&book, 2);
+        assert_eq!(sheet.dimensions(), (4, 6));
+        assert_eq!(sheet.get_pixel(1, 5).0, [0, 255, 0, 255]);
+
+        let json = aseprite_json(&book, "walk.png", 2);
+        assert_eq!(json["frames"][2]["frame"], json!({ "x": 0, "y": 3, "w": 2, "h": 3 }));
+        assert_eq!(json["frames"][0]["filename"], "walk 0.aseprite");
+        assert_eq!(json["meta"]["size"], json!({ "w": 4, "h": 6 }));
+        assert_eq!(json["meta"]["image"], "walk.png");
+        assert_eq!(json["meta"]["frameTags"], json!([]));
+    }
+
+    #[test]
+    fn test_frame_tags_and_durations() {
+        let mut book = PixelBook::new("hero.pxl".to_string(), 2, 2, 4);
+        book.frames[1].duration_ms = Some(250);
+        let tag = |name: &str, from, to, direction| FrameTag { name: name.to_string(), from, to, direction };
+        book.metadata.details.frame_tags = vec![
+            tag("idle", 0, 1, TagDirection::Pingpong),
+            tag("walk", 2, 6, TagDirection::Forward),
+            tag("gone", 4, 5, TagDirection::Reverse),
+        ];
+
+        let json = aseprite_json(&book, "hero.png", 4);
+        assert_eq!(json["frames"][0]["duration"], DEFAULT_FRAME_DELAY_MS);
+        assert_eq!(json["frames"][1]["duration"], 250);
+        assert_eq!(json["meta"]["frameTags"], json!([
+            { "name": "idle", "from": 0, "to": 1, "direction": "pingpong" },
+            { "name": "walk", "from": 2, "to": 3, "direction": "forward" }
+        ]));
+    }
+}
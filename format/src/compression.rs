@@ -0,0 +1,168 @@
+//! Run-length encoding of frame data, used by format v5.
+//!
+//! Frames are encoded a pixel (4 bytes) at a time as TGA-style packets: a
+//! count byte with the high bit set repeats the pixel that follows
+//! `(count & 0x7F) + 1` times, and a count byte without it is followed by
+//! `count + 1` literal pixels. Mostly transparent frames shrink to a few
+//! bytes per row.
+
+use std::borrow::Cow;
+
+// High bit of a packet's count byte, marking a run rather than literals
+const RUN_FLAG: u8 = 0x80;
+// Most pixels a single packet can hold
+const MAX_PACKET: usize = 128;
+
+/// How one frame's data is stored, recorded in v5 frame table entries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compression {
+    /// Raw RGBA bytes
+    #[default]
+    None,
+    /// Run-length encoded RGBA pixels
+    Rle,
+}
+
+impl Compression {
+    /// The value stored in the frame table, or `None` for an unknown scheme
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::None),
+            1 => Some(Self::Rle),
+            _ => None,
+        }
+    }
+
+    pub fn to_byte(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Rle => 1,
+        }
+    }
+}
+
+/// Encodes RGBA `pixels` whose length is a multiple of 4
+pub fn rle_encode(pixels: &[u8]) -> Vec<u8> {
+    let pixels: Vec<&[u8]> = pixels.chunks_exact(4).collect();
+    let mut encoded = Vec::new();
+    let mut start = 0;
+    while start < pixels.len() {
+        let run = pixels[start..].iter().take(MAX_PACKET).take_while(|pixel| **pixel == pixels[start]).count();
+        if run > 1 {
+            encoded.push(RUN_FLAG | (run - 1) as u8);
+            encoded.extend_from_slice(pixels[start]);
+            start += run;
+            continue;
+        }
+
+        // Literals last until the next pair of equal pixels starts a run
+        let mut end = start + 1;
+        while end < pixels.len() && end - start < MAX_PACKET && pixels.get(end + 1) != Some(&pixels[end]) {
+            end += 1;
+        }
+        encoded.push((end - start - 1) as u8);
+        encoded.extend(pixels[start..end].concat());
+        start = end;
+    }
+    encoded
+}
+
+/// Decodes [`rle_encode`] output, or `None` when `data` is malformed or does
+/// not decode to exactly `len` bytes
+pub fn rle_decode(data: &[u8], len: usize) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(len);
+    let mut rest = data;
+    while let Some((&count, tail)) = rest.split_first() {
+        let pixels = (count & !RUN_FLAG) as usize + 1;
+        if decoded.len() + pixels * 4 > len {
+            return None;
+        }
+
+        if count & RUN_FLAG != 0 {
+            let pixel = tail.get(..4)?;
+            for _ in 0..pixels {
+                decoded.extend_from_slice(pixel);
+            }
+            rest = &tail[4..];
+        } else {
+            decoded.extend_from_slice(tail.get(..pixels * 4)?);
+            rest = &tail[pixels * 4..];
+        }
+    }
+    (decoded.len() == len).then_some(decoded)
+}
+
+/// Run-length encodes `pixels` when that makes them smaller, otherwise
+/// leaves them raw
+pub fn compress(pixels: &[u8]) -> (Compression, Cow<'_, [u8]>) {
+    let encoded = rle_encode(pixels);
+    if encoded.len() < pixels.len() {
+        (Compression::Rle, Cow::Owned(encoded))
+    } else {
+        (Compression::None, Cow::Borrowed(pixels))
+    }
+}
+
+/// Restores `len` bytes of RGBA data stored with `compression`, or `None`
+/// when the data does not decode to that length
+pub fn decompress(compression: Compression, data: &[u8], len: usize) -> Option<Vec<u8>> {
+    match compression {
+        Compression::None => (data.len() == len).then(|| data.to_vec()),
+        Compression::Rle => rle_decode(data, len),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rle_round_trip() {
+        let mut pixels = vec![0u8; 300 * 4];
+        pixels[40..44].copy_from_slice(&[255, 0, 0, 255]);
+        pixels[44..48].copy_from_slice(&[0, 255, 0, 255]);
+        for (i, byte) in pixels[600..800].iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+
+        let encoded = rle_encode(&pixels);
+        assert!(encoded.len() < pixels.len() / 4);
+        assert_eq!(rle_decode(&encoded, pixels.len()).unwrap(), pixels);
+
+        for len in [0, 4, 8, 12, 129 * 4] {
+            let pixels: Vec<u8> = (0..len).map(|i| (i / 8) as u8).collect();
+            assert_eq!(rle_decode(&rle_encode(&pixels), len).unwrap(), pixels);
+        }
+    }
+
+    #[test]
+    fn test_transparent_frame_shrinks_to_packets() {
+        // 128 pixels per run packet of 5 bytes
+        let pixels = vec![0u8; 64 * 64 * 4];
+        assert_eq!(rle_encode(&pixels).len(), 64 * 64 / 128 * 5);
+
+        let (compression, data) = compress(&pixels);
+        assert_eq!(compression, Compression::Rle);
+        assert_eq!(decompress(compression, &data, pixels.len()).unwrap(), pixels);
+    }
+
+    #[test]
+    fn test_noisy_frames_stay_raw() {
+        let pixels: Vec<u8> = (0..64u8).collect();
+        let (compression, data) = compress(&pixels);
+        assert_eq!(compression, Compression::None);
+        assert_eq!(data, &pixels[..]);
+    }
+
+    #[test]
+    fn test_rejects_malformed_data() {
+        // Run of 2 pixels where 1 fits, truncated literal, short output
+        assert!(rle_decode(&[0x81, 0, 0, 0, 0], 4).is_none());
+        assert!(rle_decode(&[0x01, 1, 2, 3, 4], 8).is_none());
+        assert!(rle_decode(&[0x80, 0, 0, 0, 0], 8).is_none());
+        assert!(rle_decode(&[0x80, 0, 0], 4).is_none());
+        assert!(decompress(Compression::None, &[0; 4], 8).is_none());
+        assert_eq!(Compression::from_byte(2), None);
+        assert_eq!(Compression::from_byte(Compression::Rle.to_byte()), Some(Compression::Rle));
+    }
+}
@@ -0,0 +1,226 @@
+//! Conversion between pixel books and common image formats (PNG and GIF).
+//!
+//! Only available with the `image` feature. Lives here rather than in the CLI
+//! so the server and other tools produce identical output.
+
+use crate::error::{FormatError, Result};
+use crate::limits::Limits;
+use image::codecs::gif::{GifDecoder, GifEncoder, Repeat};
+use image::codecs::png::PngEncoder;
+use image::{AnimationDecoder, Delay, ImageDecoder, ImageEncoder, RgbaImage};
+use pixl_core::{Frame, PixelBook};
+use std::io::{BufRead, Seek, Write};
+
+/// Default delay between GIF frames when a book carries no timing of its own
+pub const DEFAULT_FRAME_DELAY_MS: u32 = 100;
+
+/// Copies a frame into an RGBA image, padding short pixel buffers with transparency
+pub fn frame_to_image(frame: &Frame, width: u16, height: u16) -> RgbaImage {
+    let mut pixels = frame.pixels.clone();
+    pixels.resize(width as usize * height as usize * 4, 0);
+    RgbaImage::from_raw(width as u32, height as u32, pixels)
+        .expect("buffer sized to match dimensions")
+}
+
+pub fn image_to_frame(index: usize, image: &RgbaImage) -> Frame {
+    Frame {
+        index,
+        pixels: image.as_raw().clone(),
+        duration_ms: None,
+    }
+}
+
+pub fn write_png<W: Write>(frame: &Frame, width: u16, height: u16, writer: W) -> Result<()> {
+    write_image_png(&frame_to_image(frame, width, height), writer)
+}
+
+/// Encodes any RGBA image, such as a sprite sheet, as PNG
+pub fn write_image_png<W: Write>(image: &RgbaImage, writer: W) -> Result<()> {
+    PngEncoder::new(writer).write_image(
+        image.as_raw(),
+        image.width(),
+        image.height(),
+        image::ExtendedColorType::Rgba8,
+    )?;
+    Ok(())
+}
+
+/// Encodes every frame of the book as a looping GIF, showing frames without a
+/// duration of their own for `delay_ms`. Fully transparent pixels map to the
+/// GIF transparent index; partial alpha is not representable.
+pub fn write_gif<W: Write>(book: &PixelBook, writer: W, delay_ms: u32) -> Result<()> {
+    let mut encoder = GifEncoder::new(writer);
+    encoder.set_repeat(Repeat::Infinite)?;
+
+    for frame in &book.frames {
+        let image = frame_to_image(frame, book.width, book.height);
+        let delay = Delay::from_numer_denom_ms(frame.duration_ms.unwrap_or(delay_ms), 1);
+        encoder.encode_frame(image::Frame::from_parts(image, 0, 0, delay))?;
+    }
+
+    Ok(())
+}
+
+/// Decodes a single still image, guessing the format from its contents and
+/// refusing images larger than `limits` allow
+pub fn read_image<R: BufRead + Seek>(reader: R, limits: &Limits) -> Result<RgbaImage> {
+    let mut reader = image::ImageReader::new(reader).with_guessed_format()?;
+    reader.limits(image_limits(limits));
+    Ok(reader.decode()?.to_rgba8())
+}
+
+/// Decodes every frame of a GIF, fully composited to the logical screen size.
+/// The screen size is checked against `limits` before any frame is decoded,
+/// and decoding stops as soon as the frame count or total size is exceeded.
+pub fn read_gif<R: BufRead + Seek>(reader: R, limits: &Limits) -> Result<Vec<RgbaImage>> {
+    let mut decoder = GifDecoder::new(reader)?;
+    let (width, height) = decoder.dimensions();
+    check_image_size(width, height, limits)?;
+    decoder.set_limits(image_limits(limits))?;
+
+    let frame_size = width as u64 * height as u64 * 4;
+    let mut images = Vec::new();
+    for frame in decoder.into_frames() {
+        if images.len() >= limits.max_frame_count as usize {
+            return Err(FormatError::LimitExceeded {
+                details: format!("more than {} frames", limits.max_frame_count),
+            });
+        }
+        if (images.len() as u64 + 1) * frame_size > limits.max_total_bytes {
+            return Err(FormatError::LimitExceeded {
+                details: format!("pixel data exceeds the {} byte maximum", limits.max_total_bytes),
+            });
+        }
+        images.push(frame?.into_buffer());
+    }
+    Ok(images)
+}
+
+fn check_image_size(width: u32, height: u32, limits: &Limits) -> Result<()> {
+    if width > limits.max_width as u32 || height > limits.max_height as u32 {
+        return Err(FormatError::LimitExceeded {
+            details: format!(
+                "{}x{} exceeds the {}x{} maximum",
+                width, height, limits.max_width, limits.max_height
+            ),
+        });
+    }
+    Ok(())
+}
+
+// The decoder-side equivalent of `limits`, so oversized images fail before
+// their pixel buffers are allocated
+fn image_limits(limits: &Limits) -> image::Limits {
+    let mut image_limits = image::Limits::default();
+    image_limits.max_image_width = Some(limits.max_width as u32);
+    image_limits.max_image_height = Some(limits.max_height as u32);
+    image_limits.max_alloc = Some(limits.max_total_bytes);
+    image_limits
+}
+
+/// Builds a book from a sequence of equally sized images, one frame each
+pub fn book_from_images(filename: &str, images: &[RgbaImage]) -> Result<PixelBook> {
+    let first = images.first().ok_or_else(|| FormatError::InvalidImage {
+        details: "At least one image is required".to_string(),
+    })?;
+    let (width, height) = first.dimensions();
+
+    if width == 0 || height == 0 || width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err(FormatError::InvalidImage {
+            details: format!("Unsupported image size {}x{}", width, height),
+        });
+    }
+
+    let mut frames = Vec::with_capacity(images.len());
+    for (index, image) in images.iter().enumerate() {
+        if image.dimensions() != (width, height) {
+            return Err(FormatError::InvalidImage {
+                details: format!(
+                    "Image {} is {}x{}, expected {}x{}",
+                    index, image.width(), image.height(), width, height
+                ),
+            });
+        }
+        frames.push(image_to_frame(index, image));
+    }
+
+    Ok(PixelBook {
+        filename: filename.to_string(),
+        width: width as u16,
+        height: height as u16,
+        frames,
+        metadata: Default::default(),
+        revision: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pixl_core::Pixel;
+    use std::io::Cursor;
+
+    fn sample_book() -> PixelBook {
+        let mut book = PixelBook::new("sample.pxl".to_string(), 3, 2, 2);
+        book.frames[0].set_pixel(0, 0, 3, Pixel::new(255, 0, 0, 255));
+        book.frames[1].set_pixel(2, 1, 3, Pixel::new(0, 0, 255, 255));
+        book
+    }
+
+    #[test]
+    fn test_png_round_trip() {
+        let book = sample_book();
+        let mut buffer = Vec::new();
+        write_png(&book.frames[0], 3, 2, &mut buffer).unwrap();
+
+        let image = read_image(Cursor::new(buffer), &Limits::default()).unwrap();
+        assert_eq!(image.dimensions(), (3, 2));
+        assert_eq!(image_to_frame(0, &image).pixels, book.frames[0].pixels);
+    }
+
+    #[test]
+    fn test_gif_round_trip_keeps_transparency() {
+        let book = sample_book();
+        let mut buffer = Vec::new();
+        write_gif(&book, &mut buffer, DEFAULT_FRAME_DELAY_MS).unwrap();
+
+        let images = read_gif(Cursor::new(buffer), &Limits::default()).unwrap();
+        let decoded = book_from_images("decoded.pxl", &images).unwrap();
+        assert_eq!(decoded.frames.len(), 2);
+        assert_eq!(decoded.frames[1].get_pixel(2, 1, 3), Some(Pixel::new(0, 0, 255, 255)));
+        assert_eq!(decoded.frames[1].get_pixel(0, 0, 3).unwrap().a, 0);
+    }
+
+    #[test]
+    fn test_read_gif_enforces_limits() {
+        let book = PixelBook::new("long.pxl".to_string(), 8, 4, 5);
+        let mut buffer = Vec::new();
+        write_gif(&book, &mut buffer, DEFAULT_FRAME_DELAY_MS).unwrap();
+
+        let narrow = Limits { max_width: 4, ..Limits::default() };
+        assert!(matches!(
+            read_gif(Cursor::new(&buffer), &narrow),
+            Err(FormatError::LimitExceeded { .. })
+        ));
+        let short = Limits { max_frame_count: 3, ..Limits::default() };
+        assert!(matches!(
+            read_gif(Cursor::new(&buffer), &short),
+            Err(FormatError::LimitExceeded { .. })
+        ));
+        let small = Limits { max_total_bytes: 8 * 4 * 4 * 2, ..Limits::default() };
+        assert!(matches!(
+            read_gif(Cursor::new(&buffer), &small),
+            Err(FormatError::LimitExceeded { .. })
+        ));
+        assert_eq!(read_gif(Cursor::new(&buffer), &Limits::default()).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_book_from_images_rejects_mismatched_sizes() {
+        let images = vec![RgbaImage::new(4, 4), RgbaImage::new(4, 5)];
+        assert!(matches!(
+            book_from_images("bad.pxl", &images),
+            Err(FormatError::InvalidImage { .. })
+        ));
+    }
+}
@@ -0,0 +1,340 @@
+//! Export of pixel books for microcontroller displays and retro consoles.
+//!
+//! Books become C or Rust source arrays, or a raw binary blob, in RGB565,
+//! RGB888, RGBA8888 or 8-bit indexed color. Frames are written one after
+//! another; alpha is discarded by every format except RGBA8888.
+//!
+//! Raw indexed binaries start with a 256-entry RGB565 palette (512 bytes,
+//! unused entries zeroed) followed by one index byte per pixel.
+
+use crate::error::{FormatError, Result};
+use pixl_core::{Frame, Pixel, PixelBook};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Most colors an indexed export can hold
+pub const MAX_PALETTE_COLORS: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EmbeddedOutput {
+    /// A C header with `stdint.h` arrays
+    #[default]
+    #[serde(rename = "c")]
+    C,
+    /// Rust `const` arrays
+    #[serde(rename = "rust")]
+    Rust,
+    /// Raw bytes with no framing
+    #[serde(rename = "binary")]
+    Binary,
+}
+
+/// The source outputs of [`EmbeddedOutput`], for callers that only want code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CodeLanguage {
+    #[default]
+    #[serde(rename = "c")]
+    C,
+    #[serde(rename = "rust")]
+    Rust,
+}
+
+impl From<CodeLanguage> for EmbeddedOutput {
+    fn from(language: CodeLanguage) -> Self {
+        match language {
+            CodeLanguage::C => Self::C,
+            CodeLanguage::Rust => Self::Rust,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ColorFormat {
+    #[default]
+    #[serde(rename = "rgb565")]
+    Rgb565,
+    #[serde(rename = "rgb888")]
+    Rgb888,
+    #[serde(rename = "rgba8888")]
+    Rgba8888,
+    /// One byte per pixel indexing an RGB565 palette
+    #[serde(rename = "indexed")]
+    Indexed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RowOrder {
+    #[default]
+    #[serde(rename = "top_down")]
+    TopDown,
+    /// Last row first, as expected by BMP-style framebuffers
+    #[serde(rename = "bottom_up")]
+    BottomUp,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmbeddedOptions {
+    #[serde(default)]
+    pub output: EmbeddedOutput,
+    #[serde(default)]
+    pub color: ColorFormat,
+    #[serde(default)]
+    pub row_order: RowOrder,
+    /// Byte order of RGB565 values in binary output; source arrays are unaffected
+    #[serde(default)]
+    pub big_endian: bool,
+    /// Export a single frame instead of all of them
+    #[serde(default)]
+    pub frame: Option<usize>,
+}
+
+/// Converts an RGB color to RGB565
+pub fn rgb565(r: u8, g: u8, b: u8) -> u16 {
+    ((r as u16 >> 3) << 11) | ((g as u16 >> 2) << 5) | (b as u16 >> 3)
+}
+
+// Pixel data ready to print: 8-bit or 16-bit samples per frame
+struct Encoded {
+    wide: bool,
+    palette: Option<Vec<u16>>,
+    frames: Vec<Vec<u16>>,
+}
+
+pub fn export_embedded(book: &PixelBook, options: &EmbeddedOptions) -> Result<Vec<u8>> {
+    let frames: Vec<&Frame> = match options.frame {
+        Some(index) => vec![book.frames.get(index).ok_or_else(|| FormatError::InvalidFrame {
+            index,
+            details: format!("book has {} frames", book.frames.len()),
+        })?],
+        None => book.frames.iter().collect(),
+    };
+
+    let encoded = encode(book, &frames, options)?;
+    match options.output {
+        EmbeddedOutput::Binary => Ok(to_binary(&encoded, options.big_endian)),
+        EmbeddedOutput::C => Ok(to_c(book, &encoded, options).into_bytes()),
+        EmbeddedOutput::Rust => Ok(to_rust(book, &encoded, options).into_bytes()),
+    }
+}
+
+fn encode(book: &PixelBook, frames: &[&Frame], options: &EmbeddedOptions) -> Result<Encoded> {
+    let rows: Vec<u16> = match options.row_order {
+        RowOrder::TopDown => (0..book.height).collect(),
+        RowOrder::BottomUp => (0..book.height).rev().collect(),
+    };
+
+    let mut palette: Vec<[u8; 4]> = Vec::new();
+    let mut encoded = Vec::with_capacity(frames.len());
+
+    for frame in frames {
+        let mut samples = Vec::new();
+        for &y in &rows {
+            for x in 0..book.width {
+                let p = frame.get_pixel(x, y, book.width).unwrap_or(Pixel::new(0, 0, 0, 0));
+                match options.color {
+                    ColorFormat::Rgb565 => samples.push(rgb565(p.r, p.g, p.b)),
+                    ColorFormat::Rgb888 => samples.extend([p.r, p.g, p.b].map(u16::from)),
+                    ColorFormat::Rgba8888 => samples.extend([p.r, p.g, p.b, p.a].map(u16::from)),
+                    ColorFormat::Indexed => {
+                        let color = [p.r, p.g, p.b, p.a];
+                        let index = match palette.iter().position(|c| *c == color) {
+                            Some(index) => index,
+                            None if palette.len() < MAX_PALETTE_COLORS => {
+                                palette.push(color);
+                                palette.len() - 1
+                            }
+                            None => return Err(FormatError::LimitExceeded {
+                                details: format!("indexed export supports at most {} colors", MAX_PALETTE_COLORS),
+                            }),
+                        };
+                        samples.push(index as u16);
+                    }
+                }
+            }
+        }
+        encoded.push(samples);
+    }
+
+    Ok(Encoded {
+        wide: options.color == ColorFormat::Rgb565,
+        palette: (options.color == ColorFormat::Indexed)
+            .then(|| palette.iter().map(|c| rgb565(c[0], c[1], c[2])).collect()),
+        frames: encoded,
+    })
+}
+
+fn to_binary(encoded: &Encoded, big_endian: bool) -> Vec<u8> {
+    let word = |value: u16| if big_endian { value.to_be_bytes() } else { value.to_le_bytes() };
+    let mut bytes = Vec::new();
+
+    if let Some(palette) = &encoded.palette {
+        for index in 0..MAX_PALETTE_COLORS {
+            bytes.extend(word(palette.get(index).copied().unwrap_or(0)));
+        }
+    }
+    for sample in encoded.frames.iter().flatten() {
+        if encoded.wide {
+            bytes.extend(word(*sample));
+        } else {
+            bytes.push(*sample as u8);
+        }
+    }
+    bytes
+}
+
+// Identifier from the book filename: `hero-idle.pxl` -> `hero_idle`
+fn identifier(filename: &str) -> String {
+    let stem = filename.trim_end_matches(".pxl");
+    let mut ident: String = stem.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    if !ident.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        ident.insert_str(0, "sprite_");
+    }
+    ident
+}
+
+fn describe(book: &PixelBook, encoded: &Encoded, options: &EmbeddedOptions) -> String {
+    let color = match options.color {
+        ColorFormat::Rgb565 => "RGB565",
+        ColorFormat::Rgb888 => "RGB888",
+        ColorFormat::Rgba8888 => "RGBA8888",
+        ColorFormat::Indexed => "8-bit indexed, RGB565 palette",
+    };
+    let rows = match options.row_order {
+        RowOrder::TopDown => "top-down",
+        RowOrder::BottomUp => "bottom-up",
+    };
+    format!(
+        "{}: {}x{}, {} frame(s), {}, {} rows",
+        book.filename, book.width, book.height, encoded.frames.len(), color, rows
+    )
+}
+
+// Comma separated values, `per_line` to a line, each line indented by `indent`
+fn values(samples: &[u16], wide: bool, per_line: usize, indent: &str) -> String {
+    samples
+        .chunks(per_line)
+        .map(|line| {
+            let items: Vec<String> = line.iter()
+                .map(|v| if wide { format!("0x{:04X}", v) } else { format!("0x{:02X}", v) })
+                .collect();
+            format!("{}{},", indent, items.join(", "))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn per_line(wide: bool) -> usize {
+    if wide { 12 } else { 16 }
+}
+
+fn to_c(book: &PixelBook, encoded: &Encoded, options: &EmbeddedOptions) -> String {
+    let ident = identifier(&book.filename);
+    let upper = ident.to_ascii_uppercase();
+    let element = if encoded.wide { "uint16_t" } else { "uint8_t" };
+    let len = encoded.frames.first().map_or(0, Vec::len);
+    let mut out = String::new();
+
+    let _ = writeln!(out, "// {}", describe(book, encoded, options));
+    let _ = writeln!(out, "#include <stdint.h>\n");
+    let _ = writeln!(out, "#define {}_WIDTH {}", upper, book.width);
+    let _ = writeln!(out, "#define {}_HEIGHT {}", upper, book.height);
+    let _ = writeln!(out, "#define {}_FRAMES {}\n", upper, encoded.frames.len());
+
+    if let Some(palette) = &encoded.palette {
+        let _ = writeln!(out, "const uint16_t {}_palette[{}] = {{", ident, palette.len());
+        let _ = writeln!(out, "{}", values(palette, true, per_line(true), "    "));
+        let _ = writeln!(out, "}};\n");
+    }
+
+    let _ = writeln!(out, "const {} {}[{}][{}] = {{", element, ident, encoded.frames.len(), len);
+    for frame in &encoded.frames {
+        let _ = writeln!(out, "    {{");
+        let _ = writeln!(out, "{}", values(frame, encoded.wide, per_line(encoded.wide), "        "));
+        let _ = writeln!(out, "    }},");
+    }
+    let _ = writeln!(out, "}};");
+    out
+}
+
+fn to_rust(book: &PixelBook, encoded: &Encoded, options: &EmbeddedOptions) -> String {
+    let upper = identifier(&book.filename).to_ascii_uppercase();
+    let element = if encoded.wide { "u16" } else { "u8" };
+    let len = encoded.frames.first().map_or(0, Vec::len);
+    let mut out = String::new();
+
+    let _ = writeln!(out, "// {}\n", describe(book, encoded, options));
+    let _ = writeln!(out, "pub const {}_WIDTH: usize = {};", upper, book.width);
+    let _ = writeln!(out, "pub const {}_HEIGHT: usize = {};", upper, book.height);
+    let _ = writeln!(out, "pub const {}_FRAMES: usize = {};\n", upper, encoded.frames.len());
+
+    if let Some(palette) = &encoded.palette {
+        let _ = writeln!(out, "pub const {}_PALETTE: [u16; {}] = [", upper, palette.len());
+        let _ = writeln!(out, "{}", values(palette, true, per_line(true), "    "));
+        let _ = writeln!(out, "];\n");
+    }
+
+    let _ = writeln!(out, "pub const {}: [[{}; {}]; {}] = [", upper, element, len, encoded.frames.len());
+    for frame in &encoded.frames {
+        let _ = writeln!(out, "    [");
+        let _ = writeln!(out, "{}", values(frame, encoded.wide, per_line(encoded.wide), "        "));
+        let _ = writeln!(out, "    ],");
+    }
+    let _ = writeln!(out, "];");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_book() -> PixelBook {
+        let mut book = PixelBook::new("hero-idle.pxl".to_string(), 2, 2, 2);
+        book.frames[0].set_pixel(0, 0, 2, Pixel::new(255, 0, 0, 255));
+        book.frames[1].set_pixel(1, 1, 2, Pixel::new(0, 0, 255, 255));
+        book
+    }
+
+    #[test]
+    fn test_rgb565_binary_row_order_and_endianness() {
+        let book = sample_book();
+        let options = EmbeddedOptions {
+            output: EmbeddedOutput::Binary,
+            frame: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(export_embedded(&book, &options).unwrap(), vec![0x00, 0xF8, 0, 0, 0, 0, 0, 0]);
+
+        let flipped = EmbeddedOptions { row_order: RowOrder::BottomUp, big_endian: true, ..options };
+        assert_eq!(export_embedded(&book, &flipped).unwrap(), vec![0, 0, 0, 0, 0xF8, 0x00, 0, 0]);
+    }
+
+    #[test]
+    fn test_indexed_binary_has_padded_palette() {
+        let book = sample_book();
+        let options = EmbeddedOptions {
+            output: EmbeddedOutput::Binary,
+            color: ColorFormat::Indexed,
+            ..Default::default()
+        };
+        let bytes = export_embedded(&book, &options).unwrap();
+        assert_eq!(bytes.len(), MAX_PALETTE_COLORS * 2 + 8);
+        // Palette in order of first appearance: red, transparent, blue
+        assert_eq!(&bytes[..6], &[0x00, 0xF8, 0x00, 0x00, 0x1F, 0x00]);
+        assert_eq!(&bytes[512..], &[0, 1, 1, 1, 1, 1, 1, 2]);
+    }
+
+    #[test]
+    fn test_source_arrays() {
+        let book = sample_book();
+        let c = String::from_utf8(export_embedded(&book, &EmbeddedOptions::default()).unwrap()).unwrap();
+        assert!(c.contains("#define HERO_IDLE_WIDTH 2"));
+        assert!(c.contains("const uint16_t hero_idle[2][4] = {"));
+        assert!(c.contains("0xF800, 0x0000, 0x0000, 0x0000,"));
+
+        let options = EmbeddedOptions { output: EmbeddedOutput::Rust, color: ColorFormat::Rgb888, ..Default::default() };
+        let rust = String::from_utf8(export_embedded(&book, &options).unwrap()).unwrap();
+        assert!(rust.contains("pub const HERO_IDLE: [[u8; 12]; 2] = ["));
+    }
+}
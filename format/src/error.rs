@@ -0,0 +1,34 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum FormatError {
+    #[error("Invalid magic number")]
+    InvalidMagic,
+
+    #[error("Unsupported version: {0}")]
+    UnsupportedVersion(u16),
+
+    #[error("Invalid header: {details}")]
+    InvalidHeader { details: String },
+
+    #[error("Invalid frame {index}: {details}")]
+    InvalidFrame { index: usize, details: String },
+
+    #[error("Invalid metadata: {details}")]
+    InvalidMetadata { details: String },
+
+    #[error("Limit exceeded: {details}")]
+    LimitExceeded { details: String },
+
+    #[error("Invalid image: {details}")]
+    InvalidImage { details: String },
+
+    #[cfg(feature = "image")]
+    #[error("Image error: {0}")]
+    Image(#[from] image::ImageError),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, FormatError>;
@@ -0,0 +1,183 @@
+use crate::compression::Compression;
+use crate::error::{FormatError, Result};
+use std::io::{Read, Write};
+
+pub const MAGIC_NUMBER: u32 = 0x504958; // "PIX"
+/// Original layout: header, frame table, pixel data
+pub const FORMAT_VERSION_V1: u16 = 1;
+/// Adds a JSON metadata block between the header and the frame table, whose
+/// length is stored in the formerly reserved header field
+pub const FORMAT_VERSION_V2: u16 = 2;
+/// The metadata block starts with the JSON length and may end with a binary
+/// palette
+pub const FORMAT_VERSION_V3: u16 = 3;
+/// Frame table entries grow a playback duration
+pub const FORMAT_VERSION_V4: u16 = 4;
+/// Frame table entries record how each frame's data is compressed
+pub const FORMAT_VERSION_V5: u16 = 5;
+/// Newest version this crate can write
+pub const FORMAT_VERSION: u16 = FORMAT_VERSION_V5;
+pub const SUPPORTED_VERSIONS: &[u16] = &[FORMAT_VERSION_V1, FORMAT_VERSION_V2, FORMAT_VERSION_V3, FORMAT_VERSION_V4, FORMAT_VERSION_V5];
+
+pub const HEADER_SIZE: usize = 16;
+/// Size of a frame table entry up to v3
+pub const FRAME_ENTRY_SIZE: usize = 8;
+/// Size of a frame table entry in v4
+pub const FRAME_ENTRY_SIZE_V4: usize = 12;
+/// Size of a frame table entry from v5 on
+pub const FRAME_ENTRY_SIZE_V5: usize = 16;
+
+/// The fixed-size header at the start of every `.pxl` file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PxlHeader {
+    pub version: u16,
+    pub width: u16,
+    pub height: u16,
+    pub frame_count: u16,
+    /// Length of the metadata block that follows the header (always 0 in v1),
+    /// including the palette in v3
+    pub metadata_len: u32,
+}
+
+impl PxlHeader {
+    /// A v1 header; use [`PxlHeader::with_metadata_len`] to describe a v2 file
+    pub fn new(width: u16, height: u16, frame_count: u16) -> Self {
+        Self { version: FORMAT_VERSION_V1, width, height, frame_count, metadata_len: 0 }
+    }
+
+    pub fn with_metadata_len(self, metadata_len: u32) -> Self {
+        Self { version: FORMAT_VERSION_V2, metadata_len, ..self }
+    }
+
+    /// Offset of the frame table, just past the header and any metadata
+    pub fn frame_table_offset(&self) -> u64 {
+        HEADER_SIZE as u64 + self.metadata_len as u64
+    }
+
+    /// Size in bytes of one frame table entry in this version
+    pub fn frame_entry_size(&self) -> usize {
+        match self.version {
+            FORMAT_VERSION_V5.. => FRAME_ENTRY_SIZE_V5,
+            FORMAT_VERSION_V4 => FRAME_ENTRY_SIZE_V4,
+            _ => FRAME_ENTRY_SIZE,
+        }
+    }
+
+    /// Offset just past the frame table, where frame data may start
+    pub fn frame_table_end(&self) -> u64 {
+        self.frame_table_offset() + self.frame_count as u64 * self.frame_entry_size() as u64
+    }
+
+    /// Size in bytes of a single frame's RGBA data
+    pub fn frame_size(&self) -> usize {
+        self.width as usize * self.height as usize * 4
+    }
+
+    pub fn parse(bytes: &[u8; HEADER_SIZE]) -> Result<Self> {
+        let magic = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        if magic != MAGIC_NUMBER {
+            return Err(FormatError::InvalidMagic);
+        }
+
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if !SUPPORTED_VERSIONS.contains(&version) {
+            return Err(FormatError::UnsupportedVersion(version));
+        }
+
+        let header = Self {
+            version,
+            width: u16::from_le_bytes([bytes[6], bytes[7]]),
+            height: u16::from_le_bytes([bytes[8], bytes[9]]),
+            frame_count: u16::from_le_bytes([bytes[10], bytes[11]]),
+            // Reserved in v1, where writers always left it zero
+            metadata_len: match version {
+                FORMAT_VERSION_V1 => 0,
+                _ => u32::from_le_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]),
+            },
+        };
+
+        if header.width == 0 || header.height == 0 || header.frame_count == 0 {
+            return Err(FormatError::InvalidHeader {
+                details: "Invalid dimensions or frame count".to_string(),
+            });
+        }
+
+        Ok(header)
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut bytes = [0u8; HEADER_SIZE];
+        reader.read_exact(&mut bytes)?;
+        Self::parse(&bytes)
+    }
+
+    pub fn to_bytes(&self) -> [u8; HEADER_SIZE] {
+        let mut bytes = [0u8; HEADER_SIZE];
+        bytes[0..4].copy_from_slice(&MAGIC_NUMBER.to_le_bytes());
+        bytes[4..6].copy_from_slice(&self.version.to_le_bytes());
+        bytes[6..8].copy_from_slice(&self.width.to_le_bytes());
+        bytes[8..10].copy_from_slice(&self.height.to_le_bytes());
+        bytes[10..12].copy_from_slice(&self.frame_count.to_le_bytes());
+        // Bytes 12..16 are reserved (zero) in v1
+        if self.version >= FORMAT_VERSION_V2 {
+            bytes[12..16].copy_from_slice(&self.metadata_len.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&self.to_bytes())?;
+        Ok(())
+    }
+}
+
+/// Location of one frame's pixel data within the file, from v4 on how long
+/// it shows, and from v5 on how its data is compressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameEntry {
+    pub offset: u32,
+    /// Size of the stored data, which is smaller than the frame when compressed
+    pub size: u32,
+    /// Playback duration in milliseconds; 0 (and always before v4) for the
+    /// player's default
+    pub duration_ms: u32,
+    /// Always [`Compression::None`] before v5
+    pub compression: Compression,
+}
+
+impl FrameEntry {
+    /// Parses an entry of [`FRAME_ENTRY_SIZE`], [`FRAME_ENTRY_SIZE_V4`] or
+    /// [`FRAME_ENTRY_SIZE_V5`] bytes
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let word = |at: usize| u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]);
+        let compression = match bytes.get(12) {
+            Some(&byte) if bytes.len() >= FRAME_ENTRY_SIZE_V5 => Compression::from_byte(byte).ok_or_else(|| FormatError::InvalidHeader {
+                details: format!("unknown frame compression {}", byte),
+            })?,
+            _ => Compression::None,
+        };
+        Ok(Self {
+            offset: word(0),
+            size: word(4),
+            duration_ms: if bytes.len() >= FRAME_ENTRY_SIZE_V4 { word(8) } else { 0 },
+            compression,
+        })
+    }
+
+    /// The v5 encoding; earlier versions use the first [`FRAME_ENTRY_SIZE`]
+    /// or [`FRAME_ENTRY_SIZE_V4`] bytes
+    pub fn to_bytes(&self) -> [u8; FRAME_ENTRY_SIZE_V5] {
+        let mut bytes = [0u8; FRAME_ENTRY_SIZE_V5];
+        bytes[0..4].copy_from_slice(&self.offset.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.size.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.duration_ms.to_le_bytes());
+        // Bytes 13..16 are reserved (zero)
+        bytes[12] = self.compression.to_byte();
+        bytes
+    }
+
+    /// The duration as stored on a [`pixl_core::Frame`]
+    pub fn duration(&self) -> Option<u32> {
+        (self.duration_ms != 0).then_some(self.duration_ms)
+    }
+}
@@ -0,0 +1,44 @@
+//! Reader and writer for the binary `.pxl` pixel book format.
+//!
+//! The layout is a 16 byte header, an optional metadata block (v2 and later,
+//! with a palette in v3), a table of `(offset, size)` entries (one per frame),
+//! and then the RGBA data for every frame, run-length encoded in v5 where
+//! that saves space (see [`compression`]). See
+//! `docs/specs/pixel-book-format.md` for the full specification.
+//!
+//! ```no_run
+//! use pixl_format::PxlReader;
+//!
+//! let mut reader = PxlReader::open("sprite.pxl")?;
+//! println!("{}x{}, {} frames", reader.width(), reader.height(), reader.frame_count());
+//! for frame in reader.frames() {
+//!     let frame = frame?;
+//!     println!("frame {}: {} bytes", frame.index, frame.pixels.len());
+//! }
+//! # Ok::<(), pixl_format::FormatError>(())
+//! ```
+//!
+//! Enable the `image` feature for PNG/GIF conversion in [`convert`] and
+//! Aseprite-style sprite sheets in [`aseprite`].
+//! [`embedded`] exports books as source arrays or raw framebuffer data.
+//! The `mmap` feature adds [`PxlReader::open_mmap`] for large files.
+
+pub mod compression;
+pub mod embedded;
+pub mod error;
+pub mod header;
+pub mod limits;
+mod palette;
+pub mod reader;
+pub mod writer;
+#[cfg(feature = "image")]
+pub mod aseprite;
+#[cfg(feature = "image")]
+pub mod convert;
+
+pub use compression::Compression;
+pub use error::*;
+pub use header::*;
+pub use limits::*;
+pub use reader::*;
+pub use writer::*;
@@ -0,0 +1,72 @@
+use crate::error::{FormatError, Result};
+use crate::header::PxlHeader;
+
+/// Upper bounds enforced while parsing `.pxl` data, so a corrupt or hostile
+/// header cannot make the reader allocate or seek without bound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub max_width: u16,
+    pub max_height: u16,
+    pub max_frame_count: u16,
+    /// Cap on the combined pixel data of every frame
+    pub max_total_bytes: u64,
+    /// Cap on the JSON metadata block (format v2)
+    pub max_metadata_bytes: u32,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_width: 4096,
+            max_height: 4096,
+            max_frame_count: 1000,
+            max_total_bytes: 512 * 1024 * 1024,
+            max_metadata_bytes: 1024 * 1024,
+        }
+    }
+}
+
+impl Limits {
+    /// Only the limits implied by the format itself
+    pub fn unlimited() -> Self {
+        Self {
+            max_width: u16::MAX,
+            max_height: u16::MAX,
+            max_frame_count: u16::MAX,
+            max_total_bytes: u64::MAX,
+            max_metadata_bytes: u32::MAX,
+        }
+    }
+
+    pub fn check_header(&self, header: &PxlHeader) -> Result<()> {
+        if header.width > self.max_width || header.height > self.max_height {
+            return Err(FormatError::LimitExceeded {
+                details: format!(
+                    "{}x{} exceeds the {}x{} maximum",
+                    header.width, header.height, self.max_width, self.max_height
+                ),
+            });
+        }
+
+        if header.frame_count > self.max_frame_count {
+            return Err(FormatError::LimitExceeded {
+                details: format!("{} frames exceeds the maximum of {}", header.frame_count, self.max_frame_count),
+            });
+        }
+
+        if header.metadata_len > self.max_metadata_bytes {
+            return Err(FormatError::LimitExceeded {
+                details: format!("{} byte metadata block exceeds the maximum of {}", header.metadata_len, self.max_metadata_bytes),
+            });
+        }
+
+        let frame_size = header.width as u64 * header.height as u64 * 4;
+        let total = frame_size.checked_mul(header.frame_count as u64);
+        match total {
+            Some(total) if total <= self.max_total_bytes => Ok(()),
+            _ => Err(FormatError::LimitExceeded {
+                details: format!("pixel data exceeds the {} byte maximum", self.max_total_bytes),
+            }),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! The v3 extension block between the header and the frame table: the JSON
+//! book metadata followed by an optional binary palette.
+
+use crate::error::{FormatError, Result};
+use pixl_core::{BookMetadata, Palette, MAX_PALETTE_COLORS};
+
+/// Encodes `metadata` as a v3 extension block
+pub(crate) fn encode_block(metadata: &BookMetadata) -> Result<Vec<u8>> {
+    let settings = BookMetadata { palette: None, ..metadata.clone() };
+    let json = if settings.is_empty() {
+        Vec::new()
+    } else {
+        serde_json::to_vec(&settings).map_err(|e| FormatError::InvalidMetadata { details: e.to_string() })?
+    };
+
+    let mut block = Vec::with_capacity(4 + json.len());
+    block.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    block.extend_from_slice(&json);
+
+    if let Some(palette) = &metadata.palette {
+        palette.validate().map_err(|details| FormatError::InvalidMetadata { details })?;
+        block.push(palette.name.len() as u8);
+        block.extend_from_slice(palette.name.as_bytes());
+        block.extend_from_slice(&(palette.colors.len() as u16).to_le_bytes());
+        for color in &palette.colors {
+            block.extend_from_slice(color);
+        }
+    }
+    Ok(block)
+}
+
+/// Decodes a v3 extension block, which must be used up exactly
+pub(crate) fn decode_block(bytes: &[u8]) -> Result<BookMetadata> {
+    let mut rest = bytes;
+    let json_len = u32::from_le_bytes(take(&mut rest, 4)?.try_into().unwrap()) as usize;
+    let json = take(&mut rest, json_len)?;
+    let mut metadata: BookMetadata = if json.is_empty() {
+        BookMetadata::default()
+    } else {
+        serde_json::from_slice(json).map_err(|e| FormatError::InvalidMetadata { details: e.to_string() })?
+    };
+
+    if !rest.is_empty() {
+        let name_len = take(&mut rest, 1)?[0] as usize;
+        let name = String::from_utf8(take(&mut rest, name_len)?.to_vec())
+            .map_err(|_| invalid("palette name is not UTF-8"))?;
+        let count = u16::from_le_bytes(take(&mut rest, 2)?.try_into().unwrap()) as usize;
+        if count > MAX_PALETTE_COLORS {
+            return Err(invalid(&format!("palette has {} colors, more than {}", count, MAX_PALETTE_COLORS)));
+        }
+        let colors = take(&mut rest, count * 4)?
+            .chunks_exact(4)
+            .map(|c| [c[0], c[1], c[2], c[3]])
+            .collect();
+        if !rest.is_empty() {
+            return Err(invalid("unexpected bytes after the palette"));
+        }
+        metadata.palette = Some(Palette { name, colors });
+    }
+    Ok(metadata)
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if bytes.len() < len {
+        return Err(invalid("metadata block is truncated"));
+    }
+    let (taken, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Ok(taken)
+}
+
+fn invalid(details: &str) -> FormatError {
+    FormatError::InvalidMetadata { details: details.to_string() }
+}
@@ -0,0 +1,414 @@
+use crate::compression::{self, Compression};
+use crate::error::{FormatError, Result};
+use crate::header::{FrameEntry, PxlHeader, FORMAT_VERSION_V3, HEADER_SIZE};
+use crate::limits::Limits;
+use crate::palette;
+use pixl_core::{BookMetadata, Frame, PixelBook};
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Reads `.pxl` data from any seekable source.
+///
+/// The header and frame table are parsed up front; frame pixel data is only
+/// read when requested, either individually with [`PxlReader::read_frame`] or
+/// sequentially with [`PxlReader::frames`].
+///
+/// Every size and offset in the file is checked against the stream length and
+/// the reader's [`Limits`] before anything is allocated, so malformed input
+/// produces an error rather than a panic or an oversized allocation.
+pub struct PxlReader<R> {
+    inner: R,
+    header: PxlHeader,
+    metadata: BookMetadata,
+    entries: Vec<FrameEntry>,
+}
+
+impl PxlReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_with_limits(path, Limits::default())
+    }
+
+    pub fn open_with_limits<P: AsRef<Path>>(path: P, limits: Limits) -> Result<Self> {
+        Self::with_limits(BufReader::new(File::open(path)?), limits)
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl PxlReader<Cursor<memmap2::Mmap>> {
+    /// Maps the file into memory instead of reading it through a buffer, so
+    /// large books are paged in by the OS and [`PxlReader::frame_bytes`] can
+    /// borrow frame data without copying.
+    ///
+    /// The mapping reflects later changes to the file. If another writer
+    /// truncates the file while the reader is alive, accessing the missing
+    /// pages terminates the process with `SIGBUS`, so callers must keep
+    /// writers away for as long as the reader is in use.
+    pub fn open_mmap<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_mmap_with_limits(path, Limits::default())
+    }
+
+    pub fn open_mmap_with_limits<P: AsRef<Path>>(path: P, limits: Limits) -> Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the mapping is read-only; the caller upholds the no-truncation
+        // requirement documented on `open_mmap`
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        Self::with_limits(Cursor::new(map), limits)
+    }
+}
+
+impl<T: AsRef<[u8]>> PxlReader<Cursor<T>> {
+    /// The RGBA bytes of frame `index`, borrowed from the in-memory or
+    /// memory-mapped source without copying unless the frame is compressed
+    pub fn frame_bytes(&self, index: usize) -> Result<Cow<'_, [u8]>> {
+        let entry = *self.entries.get(index).ok_or_else(|| FormatError::InvalidFrame {
+            index,
+            details: format!("book only has {} frames", self.entries.len()),
+        })?;
+
+        if entry.compression == Compression::None && entry.size as usize != self.header.frame_size() {
+            return Err(FormatError::InvalidFrame {
+                index,
+                details: "Invalid frame size".to_string(),
+            });
+        }
+
+        // Entries were checked against the source length when it was opened
+        let start = entry.offset as usize;
+        let data = &self.inner.get_ref().as_ref()[start..start + entry.size as usize];
+        match entry.compression {
+            Compression::None => Ok(Cow::Borrowed(data)),
+            compression => Ok(Cow::Owned(decompress(index, compression, data, self.header.frame_size())?)),
+        }
+    }
+}
+
+impl<R: Read + Seek> PxlReader<R> {
+    pub fn new(inner: R) -> Result<Self> {
+        Self::with_limits(inner, Limits::default())
+    }
+
+    pub fn with_limits(mut inner: R, limits: Limits) -> Result<Self> {
+        let stream_len = inner.seek(SeekFrom::End(0))?;
+        inner.seek(SeekFrom::Start(0))?;
+
+        if stream_len < HEADER_SIZE as u64 {
+            return Err(FormatError::InvalidHeader {
+                details: format!("file is {} bytes, shorter than the header", stream_len),
+            });
+        }
+
+        let header = PxlHeader::read_from(&mut inner)?;
+        limits.check_header(&header)?;
+
+        let table_end = header.frame_table_end();
+        if table_end > stream_len {
+            return Err(FormatError::InvalidHeader {
+                details: format!("frame table for {} frames extends past end of file", header.frame_count),
+            });
+        }
+
+        let metadata = read_metadata(&mut inner, &header)?;
+
+        let mut entries = Vec::with_capacity(header.frame_count as usize);
+        for index in 0..header.frame_count as usize {
+            let mut bytes = vec![0u8; header.frame_entry_size()];
+            inner.read_exact(&mut bytes)?;
+            let entry = FrameEntry::parse(&bytes)?;
+            validate_entry(index, &entry, &header, table_end, stream_len)?;
+            entries.push(entry);
+        }
+
+        Ok(Self { inner, header, metadata, entries })
+    }
+
+    pub fn header(&self) -> &PxlHeader {
+        &self.header
+    }
+
+    /// Book metadata; always the default for v1 files
+    pub fn metadata(&self) -> &BookMetadata {
+        &self.metadata
+    }
+
+    pub fn version(&self) -> u16 {
+        self.header.version
+    }
+
+    pub fn width(&self) -> u16 {
+        self.header.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.header.height
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.header.frame_count as usize
+    }
+
+    pub fn frame_entries(&self) -> &[FrameEntry] {
+        &self.entries
+    }
+
+    pub fn read_frame(&mut self, index: usize) -> Result<Frame> {
+        let entry = *self.entries.get(index).ok_or_else(|| FormatError::InvalidFrame {
+            index,
+            details: format!("book only has {} frames", self.entries.len()),
+        })?;
+
+        if entry.compression == Compression::None && entry.size as usize != self.header.frame_size() {
+            return Err(FormatError::InvalidFrame {
+                index,
+                details: "Invalid frame size".to_string(),
+            });
+        }
+
+        self.inner.seek(SeekFrom::Start(entry.offset as u64))?;
+
+        let mut data = vec![0u8; entry.size as usize];
+        self.inner.read_exact(&mut data)?;
+        let pixels = match entry.compression {
+            Compression::None => data,
+            compression => decompress(index, compression, &data, self.header.frame_size())?,
+        };
+
+        Ok(Frame { index, pixels, duration_ms: entry.duration() })
+    }
+
+    /// Iterate over every frame in file order
+    pub fn frames(&mut self) -> Frames<'_, R> {
+        Frames { reader: self, next: 0 }
+    }
+
+    pub fn read_book(mut self, filename: &str) -> Result<PixelBook> {
+        let frames = self.frames().collect::<Result<Vec<_>>>()?;
+
+        Ok(PixelBook {
+            filename: filename.to_string(),
+            width: self.header.width,
+            height: self.header.height,
+            frames,
+            metadata: self.metadata,
+            revision: 0,
+        })
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+pub struct Frames<'a, R> {
+    reader: &'a mut PxlReader<R>,
+    next: usize,
+}
+
+impl<R: Read + Seek> Iterator for Frames<'_, R> {
+    type Item = Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.reader.frame_count() {
+            return None;
+        }
+
+        let frame = self.reader.read_frame(self.next);
+        self.next += 1;
+        Some(frame)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.reader.frame_count().saturating_sub(self.next);
+        (remaining, Some(remaining))
+    }
+}
+
+// The metadata block sits directly after the header; its length has already
+// been checked against the limits and the stream length
+fn read_metadata<R: Read>(inner: &mut R, header: &PxlHeader) -> Result<BookMetadata> {
+    if header.metadata_len == 0 {
+        return Ok(BookMetadata::default());
+    }
+
+    let mut bytes = vec![0u8; header.metadata_len as usize];
+    inner.read_exact(&mut bytes)?;
+    if header.version >= FORMAT_VERSION_V3 {
+        return palette::decode_block(&bytes);
+    }
+    serde_json::from_slice(&bytes).map_err(|e| FormatError::InvalidMetadata {
+        details: e.to_string(),
+    })
+}
+
+// Restores a compressed frame, which must decode to exactly one frame
+fn decompress(index: usize, compression: Compression, data: &[u8], frame_size: usize) -> Result<Vec<u8>> {
+    compression::decompress(compression, data, frame_size).ok_or_else(|| FormatError::InvalidFrame {
+        index,
+        details: format!("{:?} data does not decode to {} bytes", compression, frame_size),
+    })
+}
+
+// Raw frame data must match the header's frame size, and all frame data must
+// sit wholly between the frame table and the end of the stream
+fn validate_entry(index: usize, entry: &FrameEntry, header: &PxlHeader, table_end: u64, stream_len: u64) -> Result<()> {
+    if entry.compression == Compression::None && entry.size as u64 != header.width as u64 * header.height as u64 * 4 {
+        return Err(FormatError::InvalidFrame {
+            index,
+            details: format!("size {} does not match {}x{} RGBA", entry.size, header.width, header.height),
+        });
+    }
+
+    let start = entry.offset as u64;
+    if start < table_end || start + entry.size as u64 > stream_len {
+        return Err(FormatError::InvalidFrame {
+            index,
+            details: format!("data at {}..{} lies outside the frame data area", start, start + entry.size as u64),
+        });
+    }
+
+    Ok(())
+}
+
+/// Read only the header of a `.pxl` file, e.g. to list books without loading pixels
+pub fn read_header<P: AsRef<Path>>(path: P) -> Result<PxlHeader> {
+    let mut file = File::open(path)?;
+    let header = PxlHeader::read_from(&mut file)?;
+    Limits::default().check_header(&header)?;
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::FORMAT_VERSION_V5;
+    use crate::writer::PxlWriter;
+    use pixl_core::{Pixel, PixelBook};
+
+    fn sample_book() -> PixelBook {
+        let mut book = PixelBook::new("sample.pxl".to_string(), 3, 2, 2);
+        book.frames[1].set_pixel(1, 1, 3, Pixel::new(9, 8, 7, 255));
+        book
+    }
+
+    fn sample_bytes() -> Vec<u8> {
+        PxlWriter::write_book(Vec::new(), &sample_book()).unwrap()
+    }
+
+    fn compressed_bytes() -> Vec<u8> {
+        PxlWriter::write_book_as(Vec::new(), &sample_book(), FORMAT_VERSION_V5).unwrap()
+    }
+
+    #[test]
+    fn test_rejects_truncated_files() {
+        for bytes in [sample_bytes(), compressed_bytes()] {
+            for len in 0..bytes.len() {
+                let result = PxlReader::new(Cursor::new(&bytes[..len]));
+                assert!(result.is_err(), "accepted file truncated to {} bytes", len);
+            }
+            assert!(PxlReader::new(Cursor::new(&bytes)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_rejects_bad_compressed_frames() {
+        let bytes = compressed_bytes();
+        let table = HEADER_SIZE + 4;
+        assert_eq!(bytes[table + 12], Compression::Rle.to_byte());
+
+        let mut unknown = bytes.clone();
+        unknown[table + 12] = 9;
+        assert!(matches!(PxlReader::new(Cursor::new(unknown)), Err(FormatError::InvalidHeader { .. })));
+
+        // Frame 0 is a single run of 6 pixels; claim 7
+        let mut overlong = bytes.clone();
+        let offset = u32::from_le_bytes(bytes[table..table + 4].try_into().unwrap()) as usize;
+        overlong[offset] = 0x86;
+        let mut reader = PxlReader::new(Cursor::new(overlong.as_slice())).unwrap();
+        assert!(matches!(reader.read_frame(0), Err(FormatError::InvalidFrame { index: 0, .. })));
+        assert!(reader.frame_bytes(0).is_err());
+        assert_eq!(reader.frame_bytes(1).unwrap().into_owned(), sample_book().frames[1].pixels);
+    }
+
+    #[test]
+    fn test_rejects_offsets_outside_file() {
+        let mut bytes = sample_bytes();
+        // Point frame 1 past the end of the file
+        bytes[24..28].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            PxlReader::new(Cursor::new(bytes)),
+            Err(FormatError::InvalidFrame { index: 1, .. })
+        ));
+
+        let mut bytes = sample_bytes();
+        // Point frame 0 back into the header
+        bytes[16..20].copy_from_slice(&0u32.to_le_bytes());
+        assert!(matches!(
+            PxlReader::new(Cursor::new(bytes)),
+            Err(FormatError::InvalidFrame { index: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_enforces_limits() {
+        let bytes = sample_bytes();
+        let limits = Limits { max_frame_count: 1, ..Limits::default() };
+        assert!(matches!(
+            PxlReader::with_limits(Cursor::new(&bytes), limits),
+            Err(FormatError::LimitExceeded { .. })
+        ));
+
+        let limits = Limits { max_total_bytes: 24, ..Limits::default() };
+        assert!(matches!(
+            PxlReader::with_limits(Cursor::new(&bytes), limits),
+            Err(FormatError::LimitExceeded { .. })
+        ));
+
+        // Header claims 65535x65535 with no data behind it
+        let mut huge = bytes.clone();
+        huge[6..10].copy_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF]);
+        assert!(PxlReader::with_limits(Cursor::new(huge), Limits::unlimited()).is_err());
+    }
+
+    #[test]
+    fn test_corrupted_bytes_never_panic() {
+        for bytes in [sample_bytes(), compressed_bytes()] {
+            for position in 0..bytes.len() {
+                for value in [0x00, 0x01, 0x7F, 0x80, 0xFF] {
+                    let mut corrupted = bytes.clone();
+                    corrupted[position] = value;
+                    if let Ok(mut reader) = PxlReader::new(Cursor::new(corrupted)) {
+                        for frame in reader.frames() {
+                            let _ = frame;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_frame_bytes_borrow_from_memory() {
+        let bytes = sample_bytes();
+        let mut reader = PxlReader::new(Cursor::new(bytes.as_slice())).unwrap();
+        let borrowed = reader.frame_bytes(1).unwrap().to_vec();
+        assert_eq!(borrowed, reader.read_frame(1).unwrap().pixels);
+        assert!(matches!(reader.frame_bytes(2), Err(FormatError::InvalidFrame { index: 2, .. })));
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_open_mmap_matches_buffered_reads() {
+        let path = std::env::temp_dir().join(format!("pixl-mmap-{}.pxl", std::process::id()));
+        std::fs::write(&path, sample_bytes()).unwrap();
+
+        let mapped = PxlReader::open_mmap(&path).unwrap().read_book("sample.pxl").unwrap();
+        let buffered = PxlReader::open(&path).unwrap().read_book("sample.pxl").unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(mapped.frames.len(), 2);
+        for (a, b) in mapped.frames.iter().zip(&buffered.frames) {
+            assert_eq!(a.pixels, b.pixels);
+        }
+    }
+}
@@ -0,0 +1,425 @@
+use crate::compression::{self, Compression};
+use crate::error::{FormatError, Result};
+use crate::header::{FrameEntry, PxlHeader, FORMAT_VERSION_V1, FORMAT_VERSION_V2, FORMAT_VERSION_V3, FORMAT_VERSION_V4, FORMAT_VERSION_V5};
+use crate::palette;
+use pixl_core::{BookMetadata, PixelBook};
+use std::borrow::Cow;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Writes `.pxl` data one frame at a time.
+///
+/// Every frame has the same size, so the header and frame table are written
+/// as soon as the writer is created and frames can then be streamed out
+/// without holding the whole book in memory.
+///
+/// Books with frame durations are written as format v4, books with a palette
+/// as v3 and books with other metadata as v2; everything else stays v1 so
+/// older readers can still open it. Frames are only compressed when a book is
+/// explicitly written as v5 with [`PxlWriter::write_book_as`].
+pub struct PxlWriter<W: Write> {
+    inner: W,
+    header: PxlHeader,
+    entries: Vec<FrameEntry>,
+    written: usize,
+}
+
+impl PxlWriter<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P, header: PxlHeader) -> Result<Self> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        Self::new(BufWriter::new(file), header)
+    }
+}
+
+impl<W: Write> PxlWriter<W> {
+    pub fn new(inner: W, header: PxlHeader) -> Result<Self> {
+        Self::with_metadata(inner, header, &BookMetadata::default())
+    }
+
+    /// Like [`PxlWriter::new`], upgrading the header to v2 when `metadata`
+    /// has anything to store
+    pub fn with_metadata(inner: W, header: PxlHeader, metadata: &BookMetadata) -> Result<Self> {
+        Self::with_version(inner, header, metadata, None)
+    }
+
+    /// Writes a specific format version, e.g. to migrate v1 files to v2.
+    /// `None` picks the oldest version able to hold `metadata`. Frames
+    /// streamed through [`PxlWriter::write_frame`] are stored uncompressed,
+    /// even in v5.
+    pub fn with_version(inner: W, header: PxlHeader, metadata: &BookMetadata, version: Option<u16>) -> Result<Self> {
+        Self::with_entries(inner, header, metadata, version, &[])
+    }
+
+    // Like `with_version`, taking each frame's stored size, duration and
+    // compression from `entries` (whose offsets are ignored); missing ones
+    // are raw frames with the default duration
+    fn with_entries(mut inner: W, header: PxlHeader, metadata: &BookMetadata, version: Option<u16>, entries: &[FrameEntry]) -> Result<Self> {
+        let frame_size = u32::try_from(header.frame_size()).map_err(|_| FormatError::InvalidHeader {
+            details: "Frame size exceeds 4GB".to_string(),
+        })?;
+        let raw = FrameEntry { offset: 0, size: frame_size, duration_ms: 0, compression: Compression::None };
+        let mut entries: Vec<FrameEntry> = (0..header.frame_count as usize)
+            .map(|index| entries.get(index).copied().unwrap_or(raw))
+            .collect();
+
+        let timed = entries.iter().any(|entry| entry.duration_ms != 0);
+        if timed && version.is_some_and(|version| version < FORMAT_VERSION_V4) {
+            return Err(FormatError::InvalidMetadata {
+                details: format!("format v{} cannot store frame durations", version.unwrap_or_default()),
+            });
+        }
+        let compressed = entries.iter().any(|entry| entry.compression != Compression::None);
+        if compressed && version.is_some_and(|version| version < FORMAT_VERSION_V5) {
+            return Err(FormatError::InvalidMetadata {
+                details: format!("format v{} cannot store compressed frames", version.unwrap_or_default()),
+            });
+        }
+        let version = version.unwrap_or(if compressed {
+            FORMAT_VERSION_V5
+        } else if timed {
+            FORMAT_VERSION_V4
+        } else if metadata.palette.is_some() {
+            FORMAT_VERSION_V3
+        } else if !metadata.is_empty() {
+            FORMAT_VERSION_V2
+        } else {
+            FORMAT_VERSION_V1
+        });
+        let (header, metadata_bytes) = match version {
+            FORMAT_VERSION_V1 if !metadata.is_empty() => {
+                return Err(FormatError::InvalidMetadata {
+                    details: "format v1 cannot store book metadata".to_string(),
+                });
+            }
+            FORMAT_VERSION_V1 => (PxlHeader { version, metadata_len: 0, ..header }, Vec::new()),
+            FORMAT_VERSION_V2 if metadata.palette.is_some() => {
+                return Err(FormatError::InvalidMetadata {
+                    details: "format v2 cannot store a palette".to_string(),
+                });
+            }
+            FORMAT_VERSION_V2 => {
+                let bytes = if metadata.is_empty() {
+                    Vec::new()
+                } else {
+                    serde_json::to_vec(metadata).map_err(|e| FormatError::InvalidMetadata { details: e.to_string() })?
+                };
+                (header.with_metadata_len(bytes.len() as u32), bytes)
+            }
+            FORMAT_VERSION_V3 | FORMAT_VERSION_V4 | FORMAT_VERSION_V5 => {
+                let bytes = palette::encode_block(metadata)?;
+                (PxlHeader { version, ..header.with_metadata_len(bytes.len() as u32) }, bytes)
+            }
+            other => return Err(FormatError::UnsupportedVersion(other)),
+        };
+
+        let table_end = header.frame_table_end();
+        let data_end = table_end + entries.iter().map(|entry| entry.size as u64).sum::<u64>();
+        if data_end > u32::MAX as u64 {
+            return Err(FormatError::InvalidHeader {
+                details: "Frame offsets exceed 4GB".to_string(),
+            });
+        }
+
+        header.write_to(&mut inner)?;
+        inner.write_all(&metadata_bytes)?;
+
+        let mut offset = table_end as u32;
+        for entry in &mut entries {
+            entry.offset = offset;
+            inner.write_all(&entry.to_bytes()[..header.frame_entry_size()])?;
+            offset += entry.size;
+        }
+
+        Ok(Self { inner, header, entries, written: 0 })
+    }
+
+    /// Write an entire book, returning the underlying writer once flushed
+    pub fn write_book(inner: W, book: &PixelBook) -> Result<W> {
+        Self::write_book_versioned(inner, book, None)
+    }
+
+    /// Like [`PxlWriter::write_book`], in a specific format version. From v5
+    /// on, frames that run-length encoding makes smaller are compressed.
+    pub fn write_book_as(inner: W, book: &PixelBook, version: u16) -> Result<W> {
+        Self::write_book_versioned(inner, book, Some(version))
+    }
+
+    fn write_book_versioned(inner: W, book: &PixelBook, version: Option<u16>) -> Result<W> {
+        let frame_count = u16::try_from(book.frames.len()).map_err(|_| FormatError::InvalidHeader {
+            details: format!("Too many frames: {}", book.frames.len()),
+        })?;
+
+        let header = PxlHeader::new(book.width, book.height, frame_count);
+        let compress = version.is_some_and(|version| version >= FORMAT_VERSION_V5);
+        let frames: Vec<(Compression, Cow<[u8]>)> = book.frames.iter()
+            .map(|frame| if compress { compression::compress(&frame.pixels) } else { (Compression::None, Cow::Borrowed(&frame.pixels[..])) })
+            .collect();
+        let entries: Vec<FrameEntry> = book.frames.iter().zip(&frames)
+            .map(|(frame, (compression, data))| FrameEntry {
+                offset: 0,
+                size: data.len() as u32,
+                duration_ms: frame.duration_ms.unwrap_or(0),
+                compression: *compression,
+            })
+            .collect();
+
+        let mut writer = Self::with_entries(inner, header, &book.metadata, version, &entries)?;
+        for (_, data) in &frames {
+            writer.write_data(data)?;
+        }
+        writer.finish()
+    }
+
+    pub fn header(&self) -> &PxlHeader {
+        &self.header
+    }
+
+    pub fn write_frame(&mut self, pixels: &[u8]) -> Result<()> {
+        if self.written >= self.header.frame_count as usize {
+            return Err(FormatError::InvalidFrame {
+                index: self.written,
+                details: "more frames written than declared in the header".to_string(),
+            });
+        }
+
+        if pixels.len() != self.header.frame_size() {
+            return Err(FormatError::InvalidFrame {
+                index: self.written,
+                details: format!("expected {} bytes, got {}", self.header.frame_size(), pixels.len()),
+            });
+        }
+
+        self.write_data(pixels)
+    }
+
+    // Writes the next frame's data as stored, matching its table entry
+    fn write_data(&mut self, data: &[u8]) -> Result<()> {
+        let entry = self.entries[self.written];
+        if data.len() != entry.size as usize {
+            return Err(FormatError::InvalidFrame {
+                index: self.written,
+                details: format!("expected {} stored bytes, got {}", entry.size, data.len()),
+            });
+        }
+
+        self.inner.write_all(data)?;
+        self.written += 1;
+        Ok(())
+    }
+
+    /// Flush the output, checking that every declared frame was written
+    pub fn finish(mut self) -> Result<W> {
+        if self.written != self.header.frame_count as usize {
+            return Err(FormatError::InvalidFrame {
+                index: self.written,
+                details: format!("only {} of {} frames written", self.written, self.header.frame_count),
+            });
+        }
+
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PxlReader, FORMAT_VERSION_V1, FORMAT_VERSION_V2, FORMAT_VERSION_V3, FORMAT_VERSION_V4, FORMAT_VERSION_V5, FRAME_ENTRY_SIZE, FRAME_ENTRY_SIZE_V4, FRAME_ENTRY_SIZE_V5, HEADER_SIZE, MAGIC_NUMBER};
+    use pixl_core::{Palette, Permissions};
+    use std::io::Cursor;
+
+    fn sample_book() -> PixelBook {
+        let mut book = PixelBook::new("sample.pxl".to_string(), 3, 2, 2);
+        book.frames[0].pixels[0..4].copy_from_slice(&[255, 0, 0, 255]);
+        book.frames[1].pixels[20..24].copy_from_slice(&[0, 0, 255, 128]);
+        book
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let book = sample_book();
+        let bytes = PxlWriter::write_book(Vec::new(), &book).unwrap();
+        assert_eq!(bytes.len(), HEADER_SIZE + 2 * FRAME_ENTRY_SIZE + 2 * 3 * 2 * 4);
+
+        let loaded = PxlReader::new(Cursor::new(bytes)).unwrap().read_book("sample.pxl").unwrap();
+        assert_eq!(loaded.width, 3);
+        assert_eq!(loaded.height, 2);
+        assert_eq!(loaded.frames.len(), 2);
+        assert_eq!(loaded.frames[0].pixels, book.frames[0].pixels);
+        assert_eq!(loaded.frames[1].pixels, book.frames[1].pixels);
+    }
+
+    #[test]
+    fn test_metadata_round_trip_uses_v2() {
+        let mut book = sample_book();
+        let bytes = PxlWriter::write_book(Vec::new(), &book).unwrap();
+        assert_eq!(u16::from_le_bytes([bytes[4], bytes[5]]), FORMAT_VERSION_V1);
+
+        book.metadata.permissions = Permissions {
+            read_only: true,
+            owner_key_hash: Some("abc123".to_string()),
+        };
+        let bytes = PxlWriter::write_book(Vec::new(), &book).unwrap();
+        assert_eq!(u16::from_le_bytes([bytes[4], bytes[5]]), FORMAT_VERSION_V2);
+
+        let reader = PxlReader::new(Cursor::new(bytes)).unwrap();
+        assert!(reader.header().metadata_len > 0);
+        assert_eq!(reader.metadata(), &book.metadata);
+
+        let loaded = reader.read_book("sample.pxl").unwrap();
+        assert_eq!(loaded.metadata, book.metadata);
+        assert_eq!(loaded.frames[1].pixels, book.frames[1].pixels);
+    }
+
+    #[test]
+    fn test_palette_round_trip_uses_v3() {
+        let mut book = sample_book();
+        book.metadata.palette = Some(Palette {
+            name: "dusk".to_string(),
+            colors: vec![[20, 12, 28, 255], [68, 36, 52, 255], [0, 0, 0, 0]],
+        });
+        let bytes = PxlWriter::write_book(Vec::new(), &book).unwrap();
+        assert_eq!(u16::from_le_bytes([bytes[4], bytes[5]]), FORMAT_VERSION_V3);
+
+        let loaded = PxlReader::new(Cursor::new(bytes)).unwrap().read_book("sample.pxl").unwrap();
+        assert_eq!(loaded.metadata, book.metadata);
+        assert_eq!(loaded.frames[1].pixels, book.frames[1].pixels);
+
+        // Other metadata shares the block with the palette
+        book.metadata.permissions.read_only = true;
+        let bytes = PxlWriter::write_book(Vec::new(), &book).unwrap();
+        let loaded = PxlReader::new(Cursor::new(bytes)).unwrap().read_book("sample.pxl").unwrap();
+        assert_eq!(loaded.metadata, book.metadata);
+
+        assert!(matches!(
+            PxlWriter::write_book_as(Vec::new(), &book, FORMAT_VERSION_V2),
+            Err(FormatError::InvalidMetadata { .. })
+        ));
+
+        // A v3 book without a palette or other metadata reads back empty
+        let bytes = PxlWriter::write_book_as(Vec::new(), &sample_book(), FORMAT_VERSION_V3).unwrap();
+        let reader = PxlReader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!((reader.version(), reader.header().metadata_len), (FORMAT_VERSION_V3, 4));
+        assert!(reader.metadata().is_empty());
+    }
+
+    #[test]
+    fn test_write_book_as_forces_version() {
+        let mut book = sample_book();
+        let bytes = PxlWriter::write_book_as(Vec::new(), &book, FORMAT_VERSION_V2).unwrap();
+        let reader = PxlReader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!((reader.version(), reader.header().metadata_len), (FORMAT_VERSION_V2, 0));
+        assert_eq!(reader.read_book("sample.pxl").unwrap().frames[1].pixels, book.frames[1].pixels);
+
+        book.metadata.permissions.read_only = true;
+        assert!(matches!(
+            PxlWriter::write_book_as(Vec::new(), &book, FORMAT_VERSION_V1),
+            Err(FormatError::InvalidMetadata { .. })
+        ));
+        assert!(matches!(
+            PxlWriter::write_book_as(Vec::new(), &book, 9),
+            Err(FormatError::UnsupportedVersion(9))
+        ));
+    }
+
+    #[test]
+    fn test_streaming_frames() {
+        let bytes = PxlWriter::write_book(Vec::new(), &sample_book()).unwrap();
+        let mut reader = PxlReader::new(Cursor::new(bytes)).unwrap();
+
+        let indices: Vec<usize> = reader.frames().map(|frame| frame.unwrap().index).collect();
+        assert_eq!(indices, vec![0, 1]);
+
+        // Random access after iteration still works
+        let frame = reader.read_frame(1).unwrap();
+        assert_eq!(&frame.pixels[20..24], &[0, 0, 255, 128]);
+        assert!(reader.read_frame(2).is_err());
+    }
+
+    #[test]
+    fn test_frame_durations_round_trip_as_v4() {
+        let mut book = sample_book();
+        book.frames[1].duration_ms = Some(250);
+        let bytes = PxlWriter::write_book(Vec::new(), &book).unwrap();
+        assert_eq!(u16::from_le_bytes([bytes[4], bytes[5]]), FORMAT_VERSION_V4);
+        assert_eq!(bytes.len(), HEADER_SIZE + 4 + 2 * FRAME_ENTRY_SIZE_V4 + 2 * 3 * 2 * 4);
+
+        let reader = PxlReader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(reader.frame_entries()[1].duration_ms, 250);
+        let loaded = reader.read_book("sample.pxl").unwrap();
+        assert_eq!(loaded.frames.iter().map(|f| f.duration_ms).collect::<Vec<_>>(), vec![None, Some(250)]);
+        assert_eq!(loaded.frames[1].pixels, book.frames[1].pixels);
+
+        // Older versions have nowhere to keep the durations
+        assert!(matches!(
+            PxlWriter::write_book_as(Vec::new(), &book, FORMAT_VERSION_V3),
+            Err(FormatError::InvalidMetadata { .. })
+        ));
+        book.frames[1].duration_ms = None;
+        let bytes = PxlWriter::write_book_as(Vec::new(), &book, FORMAT_VERSION_V4).unwrap();
+        let loaded = PxlReader::new(Cursor::new(bytes)).unwrap().read_book("sample.pxl").unwrap();
+        assert!(loaded.frames.iter().all(|f| f.duration_ms.is_none()));
+    }
+
+    #[test]
+    fn test_v5_compresses_frames_that_shrink() {
+        let mut book = PixelBook::new("big.pxl".to_string(), 64, 64, 3);
+        book.frames[0].pixels[0..4].copy_from_slice(&[255, 0, 0, 255]);
+        for (i, byte) in book.frames[2].pixels.iter_mut().enumerate() {
+            *byte = (i * 7 % 251) as u8;
+        }
+        book.frames[1].duration_ms = Some(80);
+
+        let raw = PxlWriter::write_book(Vec::new(), &book).unwrap();
+        let bytes = PxlWriter::write_book_as(Vec::new(), &book, FORMAT_VERSION_V5).unwrap();
+        assert!(bytes.len() < raw.len() * 2 / 3);
+
+        let reader = PxlReader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(reader.header().frame_table_end(), (HEADER_SIZE + 4 + 3 * FRAME_ENTRY_SIZE_V5) as u64);
+        let compressions: Vec<Compression> = reader.frame_entries().iter().map(|e| e.compression).collect();
+        assert_eq!(compressions, vec![Compression::Rle, Compression::Rle, Compression::None]);
+        assert_eq!(reader.frame_entries()[1].duration_ms, 80);
+
+        let loaded = reader.read_book("big.pxl").unwrap();
+        for (loaded, frame) in loaded.frames.iter().zip(&book.frames) {
+            assert_eq!(loaded.pixels, frame.pixels);
+            assert_eq!(loaded.duration_ms, frame.duration_ms);
+        }
+
+        // Streamed frames stay raw, and v5 books can move back to v4
+        let mut writer = PxlWriter::with_version(Vec::new(), PxlHeader::new(2, 1, 1), &BookMetadata::default(), Some(FORMAT_VERSION_V5)).unwrap();
+        writer.write_frame(&[0u8; 8]).unwrap();
+        let reader = PxlReader::new(Cursor::new(writer.finish().unwrap())).unwrap();
+        assert_eq!((reader.version(), reader.frame_entries()[0].compression), (FORMAT_VERSION_V5, Compression::None));
+        let bytes = PxlWriter::write_book_as(Vec::new(), &loaded, FORMAT_VERSION_V4).unwrap();
+        assert_eq!(bytes.len(), raw.len());
+    }
+
+    #[test]
+    fn test_rejects_bad_magic_and_version() {
+        let mut bytes = PxlWriter::write_book(Vec::new(), &sample_book()).unwrap();
+        bytes[0] = 0;
+        assert!(matches!(PxlReader::new(Cursor::new(bytes.clone())), Err(FormatError::InvalidMagic)));
+
+        bytes[0..4].copy_from_slice(&MAGIC_NUMBER.to_le_bytes());
+        bytes[4..6].copy_from_slice(&99u16.to_le_bytes());
+        assert!(matches!(PxlReader::new(Cursor::new(bytes)), Err(FormatError::UnsupportedVersion(99))));
+    }
+
+    #[test]
+    fn test_writer_checks_frame_count_and_size() {
+        let mut writer = PxlWriter::new(Vec::new(), PxlHeader::new(2, 2, 1)).unwrap();
+        assert!(writer.write_frame(&[0u8; 4]).is_err());
+        writer.write_frame(&[0u8; 16]).unwrap();
+        assert!(writer.write_frame(&[0u8; 16]).is_err());
+        assert!(writer.finish().is_ok());
+
+        let writer = PxlWriter::new(Vec::new(), PxlHeader::new(2, 2, 2)).unwrap();
+        assert!(writer.finish().is_err());
+    }
+}
@@ -0,0 +1,86 @@
+//! Test support for end-to-end tests that cross crate boundaries.
+//!
+//! [`TestServer`] runs the embeddable server on an ephemeral port against a
+//! temporary books directory and hands out a [`PixlClient`] pointed at it, so
+//! tests can drive the real HTTP/SSE surface and then inspect the `.pxl` files
+//! that land on disk.
+
+use pixl_client::{EventStream, PixlClient};
+use pixl_core::{PixelBookEvent, PixelBook};
+use pixl_format::PxlReader;
+use pixl_server::{RunningServer, ServerConfig};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// How long [`next_event`] waits before treating a missing event as a failure
+pub const EVENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct TestServer {
+    server: RunningServer,
+    books_dir: TempDir,
+    client: PixlClient,
+}
+
+impl TestServer {
+    pub async fn start() -> Self {
+        Self::start_with(|_| {}).await
+    }
+
+    /// Starts a server after letting `configure` adjust the default test config
+    pub async fn start_with(configure: impl FnOnce(&mut ServerConfig)) -> Self {
+        let books_dir = TempDir::new().expect("create books dir");
+        let mut config = ServerConfig {
+            bind: "127.0.0.1:0".to_string(),
+            base_path: books_dir.path().to_path_buf(),
+            ..ServerConfig::default()
+        };
+        configure(&mut config);
+
+        let server = pixl_server::spawn(config).await.expect("start server");
+        let client = PixlClient::new(server.url());
+
+        Self { server, books_dir, client }
+    }
+
+    pub fn client(&self) -> &PixlClient {
+        &self.client
+    }
+
+    pub fn books_dir(&self) -> &Path {
+        self.books_dir.path()
+    }
+
+    pub fn book_path(&self, filename: &str) -> PathBuf {
+        self.books_dir.path().join(filename)
+    }
+
+    /// Raw bytes of a book as written by the server
+    pub fn read_bytes(&self, filename: &str) -> Vec<u8> {
+        std::fs::read(self.book_path(filename)).expect("read book file")
+    }
+
+    /// Parses a book straight from disk, bypassing the server
+    pub fn read_book(&self, filename: &str) -> PixelBook {
+        PxlReader::open(self.book_path(filename))
+            .and_then(|reader| reader.read_book(filename))
+            .expect("parse book file")
+    }
+
+    pub async fn subscribe(&self, filename: &str) -> EventStream {
+        self.client.subscribe(filename).await.expect("subscribe to events")
+    }
+
+    pub async fn shutdown(self) {
+        self.server.shutdown().await.expect("shut down server");
+    }
+}
+
+/// Waits for the next book event, panicking if none arrives within [`EVENT_TIMEOUT`]
+pub async fn next_event(events: &mut EventStream) -> PixelBookEvent {
+    tokio::time::timeout(EVENT_TIMEOUT, events.next_event())
+        .await
+        .expect("timed out waiting for event")
+        .expect("read event")
+        .expect("event stream closed")
+}
@@ -0,0 +1,1332 @@
+use pixl_client::{ClientError, StreamMessage};
+use pixl_core::{ActiveSelection, Anchor, BlendMode, OperationStatus, Rect, ResizeRequest, BatchBookUpdate, Selection, FlipAxis, Point, StampSource, BatchRequest, BookChunk, BookFilter, BookSort, SortOrder, ColorRef, DiffQuery, FrameEncoding, CreateFromTemplateRequest, CreatePixelBookRequest, DrawingOperation, ImportUrlRequest, DitherPattern, EventType, FrameTag, LineStyle, LineType, LockRequest, MigrateRequest, Palette, Pixel, QuantizeRequest, SetPermissionsRequest, Symmetry, TagDirection, TilePlacement, TilemapRequest, Tileset, UpdateDetailsRequest, UpdatePixelBookRequest};
+use pixl_format::embedded::{CodeLanguage, ColorFormat, EmbeddedOptions, EmbeddedOutput};
+use pixl_integration::{next_event, TestServer};
+
+const RED: [u8; 4] = [255, 0, 0, 255];
+const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+fn create_request(filename: &str, width: u16, height: u16, frames: usize) -> CreatePixelBookRequest {
+    CreatePixelBookRequest { filename: filename.to_string(), width, height, frames }
+}
+
+#[tokio::test]
+async fn test_created_book_is_written_in_pxl_format() {
+    let server = TestServer::start().await;
+    let response = server.client().create_book(&create_request("sprite.pxl", 4, 3, 2)).await.unwrap();
+    assert!(response.success);
+
+    let bytes = server.read_bytes("sprite.pxl");
+    assert_eq!(u32::from_le_bytes(bytes[0..4].try_into().unwrap()), pixl_format::MAGIC_NUMBER);
+    assert_eq!(u16::from_le_bytes([bytes[4], bytes[5]]), pixl_format::FORMAT_VERSION_V1);
+    assert_eq!(u16::from_le_bytes([bytes[6], bytes[7]]), 4);
+    assert_eq!(u16::from_le_bytes([bytes[8], bytes[9]]), 3);
+    assert_eq!(u16::from_le_bytes([bytes[10], bytes[11]]), 2);
+    // Header, two frame table entries, then 4x3 RGBA per frame
+    assert_eq!(bytes.len(), 16 + 2 * 8 + 2 * 4 * 3 * 4);
+
+    let books = server.client().list_books().await.unwrap();
+    assert_eq!(books.len(), 1);
+    assert_eq!(books[0].frames, 2);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_operations_reach_disk_and_event_stream_in_order() {
+    // Per-operation events, as sent unless coalescing is turned on
+    let server = TestServer::start().await;
+    server.client().create_book(&create_request("walk.pxl", 8, 8, 1)).await.unwrap();
+    let mut events = server.subscribe("walk.pxl").await;
+
+    // Same request body the MCP draw_pixel/fill_area tools send
+    let request = UpdatePixelBookRequest {
+        operations: vec![
+            DrawingOperation::DrawPixel { frame: 0, x: 1, y: 2, color: RED.into(), blend_mode: BlendMode::Replace },
+            DrawingOperation::FillArea { frame: 0, x: 7, y: 7, color: BLUE.into(), contiguous: true, bounds: None, blend_mode: BlendMode::Replace },
+        ],
+        symmetry: None,
+    };
+    let response = server.client().update_book("walk.pxl", &request).await.unwrap();
+    assert_eq!(response.operations_applied, 2);
+
+    let first = next_event(&mut events).await;
+    assert!(matches!(first.event_type, EventType::DrawingOperation { operation: DrawingOperation::DrawPixel { x: 1, y: 2, .. } }));
+    assert!(first.origin.as_deref().is_some_and(|origin| origin.starts_with("pixl-client/")));
+    let second = next_event(&mut events).await;
+    assert!(matches!(second.event_type, EventType::DrawingOperation { operation: DrawingOperation::FillArea { .. } }));
+    let saved = next_event(&mut events).await;
+    assert!(matches!(saved.event_type, EventType::BookSaved));
+    assert_eq!(saved.filename, "walk.pxl");
+
+    let on_disk = server.read_book("walk.pxl");
+    assert_eq!(on_disk.frames[0].get_pixel(1, 2, 8), Some(Pixel::new(255, 0, 0, 255)));
+    assert_eq!(on_disk.frames[0].get_pixel(0, 0, 8), Some(Pixel::new(0, 0, 255, 255)));
+
+    // The batch is kept in the book's operation log
+    let log = server.client().operation_log("walk.pxl", None).await.unwrap();
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].operations, vec!["draw_pixel f0", "fill_area f0"]);
+    assert!(log[0].origin.as_deref().is_some_and(|origin| origin.starts_with("pixl-client/")));
+    assert!(server.client().operation_log("walk.pxl", Some(log[0].timestamp)).await.unwrap().is_empty());
+
+    drop(events);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_operation_bursts_are_coalesced_into_one_event() {
+    // A generous window so slow machines still see a single burst
+    let server = TestServer::start_with(|config| {
+        config.event_coalesce_window = Some(std::time::Duration::from_secs(1));
+    }).await;
+    server.client().create_book(&create_request("burst.pxl", 16, 16, 1)).await.unwrap();
+    let mut events = server.subscribe("burst.pxl").await;
+
+    for y in 2..5 {
+        let request = UpdatePixelBookRequest {
+            operations: (3..13).map(|x| DrawingOperation::DrawPixel { frame: 0, x, y, color: RED.into(), blend_mode: BlendMode::Replace }).collect(),
+            symmetry: None,
+        };
+        server.client().update_book("burst.pxl", &request).await.unwrap();
+    }
+
+    match next_event(&mut events).await.event_type {
+        EventType::OperationsApplied { count, region: Some(region) } => {
+            assert_eq!(count, 30);
+            assert_eq!((region.x, region.y, region.width, region.height), (3, 2, 10, 3));
+        }
+        other => panic!("Expected one coalesced event, got {:?}", other),
+    }
+    assert!(matches!(next_event(&mut events).await.event_type, EventType::BookSaved));
+
+    drop(events);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_subscriptions_can_filter_event_types() {
+    let server = TestServer::start().await;
+    server.client().create_book(&create_request("dash.pxl", 4, 4, 1)).await.unwrap();
+    let mut events = server.client().subscribe_to("dash.pxl", &["book_saved"]).await.unwrap();
+
+    let request = UpdatePixelBookRequest {
+        operations: vec![DrawingOperation::DrawPixel { frame: 0, x: 1, y: 1, color: RED.into(), blend_mode: BlendMode::Replace }],
+        symmetry: None,
+    };
+    server.client().update_book("dash.pxl", &request).await.unwrap();
+
+    // The drawing_operation event is filtered out
+    assert!(matches!(next_event(&mut events).await.event_type, EventType::BookSaved));
+
+    let error = server.client().subscribe_to("dash.pxl", &["pixel_party"]).await.err().unwrap();
+    assert!(matches!(error, ClientError::Server { status: 400, .. }));
+
+    drop(events);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_idle_streams_receive_heartbeats() {
+    let server = TestServer::start_with(|config| {
+        config.sse_heartbeat_interval = std::time::Duration::from_millis(100);
+    }).await;
+    server.client().create_book(&create_request("idle.pxl", 4, 4, 1)).await.unwrap();
+    let mut events = server.subscribe("idle.pxl").await;
+
+    let status = server.client().status().await.unwrap();
+    assert_eq!(status.active_clients, 1);
+    assert_eq!(status.book_count, 1);
+    assert_eq!(status.disk_usage, std::fs::metadata(server.book_path("idle.pxl")).unwrap().len());
+
+    for _ in 0..2 {
+        let message = tokio::time::timeout(std::time::Duration::from_secs(5), events.next_message())
+            .await
+            .expect("timed out waiting for heartbeat")
+            .unwrap();
+        assert!(matches!(message, Some(StreamMessage::Control(kind)) if kind == "heartbeat"));
+    }
+
+    drop(events);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_books_can_be_fetched_by_frame_range_and_streamed() {
+    let server = TestServer::start().await;
+    server.client().create_book(&create_request("long.pxl", 3, 3, 6)).await.unwrap();
+    let request = UpdatePixelBookRequest {
+        operations: vec![DrawingOperation::DrawPixel { frame: 4, x: 2, y: 2, color: BLUE.into(), blend_mode: BlendMode::Replace }],
+        symmetry: None,
+    };
+    server.client().update_book("long.pxl", &request).await.unwrap();
+
+    let (book, frame_count) = server.client().get_book_frames("long.pxl", &"3..5".parse().unwrap()).await.unwrap();
+    assert_eq!(frame_count, 6);
+    assert_eq!(book.frames.iter().map(|f| f.index).collect::<Vec<_>>(), vec![3, 4]);
+    assert_eq!(book.frames[1].get_pixel(2, 2, 3), Some(Pixel::new(0, 0, 255, 255)));
+
+    let mut stream = server.client().stream_book("long.pxl", None).await.unwrap();
+    match stream.next_chunk().await.unwrap() {
+        Some(BookChunk::Book { width, height, frame_count, .. }) => assert_eq!((width, height, frame_count), (3, 3, 6)),
+        other => panic!("Expected the book line first, got {:?}", other),
+    }
+    let mut indices = Vec::new();
+    while let Some(chunk) = stream.next_chunk().await.unwrap() {
+        match chunk {
+            BookChunk::Frame(frame) => indices.push(frame.index),
+            other => panic!("Unexpected chunk: {:?}", other),
+        }
+    }
+    assert_eq!(indices, (0..6).collect::<Vec<_>>());
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_book_symmetry_is_kept_by_the_server() {
+    let server = TestServer::start().await;
+    let client = server.client();
+    client.create_book(&create_request("face.pxl", 6, 4, 1)).await.unwrap();
+    let dot = |x, symmetry| UpdatePixelBookRequest {
+        operations: vec![DrawingOperation::DrawPixel { frame: 0, x, y: 1, color: RED.into(), blend_mode: BlendMode::Replace }],
+        symmetry,
+    };
+    let red = |x| server.read_book("face.pxl").frames[0].get_pixel(x, 1, 6) == Some(Pixel::new(255, 0, 0, 255));
+
+    assert_eq!(client.set_symmetry("face.pxl", Symmetry::Horizontal).await.unwrap().symmetry, Symmetry::Horizontal);
+    client.update_book("face.pxl", &dot(0, None)).await.unwrap();
+    assert!(red(0) && red(5));
+    // An update's own symmetry wins, even when it is none
+    client.update_book("face.pxl", &dot(1, Some(Symmetry::None))).await.unwrap();
+    assert!(red(1) && !red(4));
+
+    // Other sessions draw with their own context
+    let other = client.clone().with_session("agent-2");
+    assert_eq!(other.drawing_context("face.pxl").await.unwrap().symmetry, Symmetry::None);
+    other.update_book("face.pxl", &dot(2, None)).await.unwrap();
+    assert!(red(2) && !red(3));
+
+    // A second server on the same directory picks the setting up
+    let books_dir = server.books_dir().to_path_buf();
+    let restarted = TestServer::start_with(|config| config.base_path = books_dir).await;
+    assert_eq!(restarted.client().drawing_context("face.pxl").await.unwrap().symmetry, Symmetry::Horizontal);
+    assert!(matches!(restarted.client().set_symmetry("missing.pxl", Symmetry::Quad).await, Err(ClientError::Server { status: 404, .. })));
+
+    restarted.shutdown().await;
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_book_selection_is_kept_by_the_server() {
+    let server = TestServer::start().await;
+    let client = server.client();
+    client.create_book(&create_request("room.pxl", 6, 4, 2)).await.unwrap();
+    let update = |operation| UpdatePixelBookRequest { operations: vec![operation], symmetry: None };
+    let fill = |frame, x| DrawingOperation::FillArea { frame, x, y: x, color: RED.into(), contiguous: true, bounds: None, blend_mode: BlendMode::Replace };
+    let painted = |frame: usize| {
+        let book = server.read_book("room.pxl");
+        (0..4u16).flat_map(|y| (0..6u16).map(move |x| (x, y)))
+            .filter(|&(x, y)| book.frames[frame].get_pixel(x, y, 6).unwrap().a != 0)
+            .count()
+    };
+
+    let selection = ActiveSelection { frame: 0, selection: Selection::Rect { rect: Rect { x: 1, y: 1, width: 2, height: 2 } } };
+    assert_eq!(client.set_selection("room.pxl", &selection).await.unwrap().selection, Some(selection));
+    client.update_book("room.pxl", &update(fill(0, 1))).await.unwrap();
+    assert_eq!(painted(0), 4);
+
+    // Swapping colors without a frame stays on the selected one
+    client.update_book("room.pxl", &update(fill(1, 0))).await.unwrap();
+    assert_eq!(painted(1), 24);
+    let swap = DrawingOperation::SwapColors { frame: None, a: RED.into(), b: [0, 0, 0, 0].into() };
+    client.update_book("room.pxl", &update(swap)).await.unwrap();
+    assert_eq!((painted(0), painted(1)), (0, 24));
+
+    // Other sessions draw anywhere, and clearing the selection lifts the limit
+    client.clone().with_session("agent-2").update_book("room.pxl", &update(fill(0, 0))).await.unwrap();
+    assert_eq!(painted(0), 24);
+    client.update_book("room.pxl", &update(DrawingOperation::ClearFrame { frame: 0 })).await.unwrap();
+    assert_eq!(painted(0), 20);
+    assert_eq!(client.clear_selection("room.pxl").await.unwrap().selection, None);
+    client.update_book("room.pxl", &update(DrawingOperation::ClearFrame { frame: 0 })).await.unwrap();
+    assert_eq!(painted(0), 0);
+
+    let missing = ActiveSelection { frame: 2, selection: Selection::Rect { rect: Rect { x: 0, y: 0, width: 1, height: 1 } } };
+    assert!(matches!(client.set_selection("room.pxl", &missing).await, Err(ClientError::Server { status: 400, .. })));
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_viewer_reloads_saved_book_into_its_model() {
+    let server = TestServer::start().await;
+    server.client().create_book(&create_request("mirror.pxl", 6, 4, 1)).await.unwrap();
+    let mut events = server.subscribe("mirror.pxl").await;
+
+    let request = UpdatePixelBookRequest {
+        operations: vec![DrawingOperation::DrawPixel { frame: 0, x: 0, y: 1, color: RED.into(), blend_mode: BlendMode::Replace }],
+        symmetry: Some(Symmetry::Horizontal),
+    };
+    server.client().update_book("mirror.pxl", &request).await.unwrap();
+
+    // The viewer reloads through its own API client whenever a save lands
+    loop {
+        if matches!(next_event(&mut events).await.event_type, EventType::BookSaved) {
+            break;
+        }
+    }
+    let api = viewer::services::ApiClient::new(server.client().base_url().to_string());
+    let mut state = viewer::app::AppState::new();
+    state.set_book(api.get_book("mirror.pxl").await.unwrap());
+
+    let book = state.current_book.as_ref().unwrap();
+    assert_eq!(book.frames[0].get_pixel(0, 1, 6), Some(Pixel::new(255, 0, 0, 255)));
+    assert_eq!(book.frames[0].get_pixel(5, 1, 6), Some(Pixel::new(255, 0, 0, 255)));
+    assert_eq!(book.frames[0].pixels, server.read_book("mirror.pxl").frames[0].pixels);
+
+    drop(events);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_viewer_previews_operations_from_a_default_server() {
+    let server = TestServer::start().await;
+    server.client().create_book(&create_request("sketch.pxl", 6, 4, 1)).await.unwrap();
+    let api = viewer::services::ApiClient::new(server.client().base_url().to_string());
+    let saved = api.get_book("sketch.pxl").await.unwrap();
+    let mut events = server.subscribe("sketch.pxl").await;
+
+    let request = UpdatePixelBookRequest {
+        operations: vec![DrawingOperation::DrawLine {
+            frame: 0,
+            start: Point { x: 0, y: 2 },
+            end: Point { x: 3, y: 2 },
+            line_type: LineType::Straight,
+            color: RED.into(),
+            control_points: Vec::new(),
+            style: LineStyle::default(),
+            blend_mode: BlendMode::Replace,
+        }],
+        symmetry: None,
+    };
+    server.client().update_book("sketch.pxl", &request).await.unwrap();
+
+    // The viewer previews what each operation event draws until the save lands
+    let mut preview = viewer::rendering::PreviewOverlay::new();
+    loop {
+        match next_event(&mut events).await.event_type {
+            EventType::DrawingOperation { operation } => preview.add_operation(&operation, &saved),
+            EventType::BookSaved => break,
+            other => panic!("Expected operation events before the save, got {:?}", other),
+        }
+    }
+    let mut pixels: Vec<_> = preview.pixels_for_frame(0).collect();
+    pixels.sort();
+    assert_eq!(pixels, (0..4).map(|x| (x, 2, RED)).collect::<Vec<_>>());
+
+    drop(events);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_operation_batches_can_be_undone_and_redone() {
+    let server = TestServer::start().await;
+    server.client().create_book(&create_request("oops.pxl", 4, 4, 1)).await.unwrap();
+    let draw = |x, color: [u8; 4]| UpdatePixelBookRequest {
+        operations: vec![DrawingOperation::DrawPixel { frame: 0, x, y: 0, color: color.into(), blend_mode: BlendMode::Replace }],
+        symmetry: None,
+    };
+    server.client().update_book("oops.pxl", &draw(0, RED)).await.unwrap();
+    server.client().update_book("oops.pxl", &draw(1, BLUE)).await.unwrap();
+
+    let undone = server.client().undo("oops.pxl").await.unwrap();
+    assert_eq!((undone.frames, undone.pixels_changed), (vec![0], 1));
+    assert_eq!((undone.undo_remaining, undone.redo_remaining), (1, 1));
+    let book = server.read_book("oops.pxl");
+    assert_eq!(book.frames[0].get_pixel(1, 0, 4), Some(Pixel::transparent()));
+    assert_eq!(book.frames[0].get_pixel(0, 0, 4), Some(Pixel::new(255, 0, 0, 255)));
+
+    server.client().redo("oops.pxl").await.unwrap();
+    assert_eq!(server.read_book("oops.pxl").frames[0].get_pixel(1, 0, 4), Some(Pixel::new(0, 0, 255, 255)));
+
+    // Drawing after an undo leaves nothing to redo
+    server.client().undo("oops.pxl").await.unwrap();
+    server.client().update_book("oops.pxl", &draw(2, BLUE)).await.unwrap();
+    let nothing = server.client().redo("oops.pxl").await.unwrap_err();
+    assert!(matches!(nothing, ClientError::Server { status: 409, .. }));
+
+    let log = server.client().operation_log("oops.pxl", None).await.unwrap();
+    assert_eq!(log.iter().filter(|entry| entry.operations == vec!["undo"]).count(), 2);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_event_history_outlives_the_server() {
+    let server = TestServer::start().await;
+    let client = server.client();
+    client.create_book(&create_request("walk.pxl", 4, 4, 1)).await.unwrap();
+    let draw = UpdatePixelBookRequest {
+        operations: vec![DrawingOperation::DrawPixel { frame: 0, x: 1, y: 1, color: RED.into(), blend_mode: BlendMode::Replace }],
+        symmetry: None,
+    };
+    client.update_book("walk.pxl", &draw).await.unwrap();
+    client.rename_book("walk.pxl", "run.pxl").await.unwrap();
+
+    // A second server on the same directory sees what the first one sent
+    let books_dir = server.books_dir().to_path_buf();
+    let restarted = TestServer::start_with(|config| config.base_path = books_dir).await;
+    let history = restarted.client().event_history("run.pxl", None, None).await.unwrap();
+    let types: Vec<&str> = history.iter().map(|event| event.event_type.name()).collect();
+    assert_eq!(types, vec!["drawing_operation", "book_saved", "book_renamed", "book_saved"]);
+    assert_eq!(history[2].filename, "walk.pxl");
+
+    let page = restarted.client().event_history("run.pxl", Some(history[0].timestamp), Some(2)).await.unwrap();
+    assert_eq!(page.iter().map(|event| event.event_type.name()).collect::<Vec<_>>(), vec!["book_saved", "book_renamed"]);
+    assert!(matches!(restarted.client().event_history("run.pxl", None, Some(0)).await, Err(ClientError::Server { status: 400, .. })));
+    assert!(matches!(restarted.client().event_history("missing.pxl", None, None).await, Err(ClientError::Server { status: 404, .. })));
+
+    restarted.shutdown().await;
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_configured_path_survives_a_restart() {
+    let settings_dir = tempfile::TempDir::new().unwrap();
+    let chosen_dir = tempfile::TempDir::new().unwrap();
+    let settings_file = settings_dir.path().join("server.json");
+    let chosen = chosen_dir.path().to_string_lossy().to_string();
+
+    let server = TestServer::start_with(|config| config.settings_file = Some(settings_file.clone())).await;
+    server.client().set_path(&chosen).await.unwrap();
+    server.shutdown().await;
+
+    let server = TestServer::start_with(|config| config.settings_file = Some(settings_file.clone())).await;
+    assert_eq!(server.client().get_path().await.unwrap(), chosen);
+    server.shutdown().await;
+
+    // Without a settings file the configured directory is used as before
+    let server = TestServer::start().await;
+    assert_eq!(server.client().get_path().await.unwrap(), server.books_dir().to_string_lossy());
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_errors_surface_as_server_status() {
+    let server = TestServer::start().await;
+
+    let missing = server.client().get_book("missing.pxl").await;
+    assert!(matches!(missing, Err(ClientError::Server { status: 404, .. })));
+
+    let invalid = server.client().create_book(&create_request("bad.pxl", 0, 4, 1)).await;
+    assert!(matches!(invalid, Err(ClientError::Server { status: 400, .. })));
+
+    server.shutdown().await;
+}
+
+// Serves `body` to every request over plain HTTP, returning the URL
+async fn serve_image(body: Vec<u8>) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).await;
+            let head = format!("HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len());
+            let _ = stream.write_all(head.as_bytes()).await;
+            let _ = stream.write_all(&body).await;
+        }
+    });
+    format!("http://{}/art/hero.png", addr)
+}
+
+#[tokio::test]
+async fn test_import_refuses_to_replace_a_book_unless_asked() {
+    let server = TestServer::start().await;
+    let mut png = Vec::new();
+    pixl_format::convert::write_png(&pixl_core::Frame::new(0, 2, 2), 2, 2, &mut png).unwrap();
+    let url = serve_image(png).await;
+    server.client().create_book(&create_request("hero.pxl", 4, 4, 3)).await.unwrap();
+    let mut events = server.subscribe("hero.pxl").await;
+
+    let request = ImportUrlRequest { url, filename: None, overwrite: false };
+    let refused = server.client().import_url(&request).await;
+    assert!(matches!(refused, Err(ClientError::Server { status: 409, .. })));
+    assert_eq!(server.read_book("hero.pxl").frames.len(), 3);
+
+    let request = ImportUrlRequest { overwrite: true, ..request };
+    let response = server.client().import_url(&request).await.unwrap();
+    assert_eq!(response.filename, "hero.pxl");
+    let on_disk = server.read_book("hero.pxl");
+    assert_eq!((on_disk.width, on_disk.height, on_disk.frames.len()), (2, 2, 1));
+
+    let saved = next_event(&mut events).await;
+    assert!(matches!(saved.event_type, EventType::BookSaved));
+    assert_eq!(saved.filename, "hero.pxl");
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_permissions_protect_books_and_persist_on_disk() {
+    let server = TestServer::start().await;
+    let owner = server.client().clone().with_owner_key("owner-secret");
+    let intruder = server.client().clone().with_owner_key("guess");
+    owner.create_book(&create_request("hero.pxl", 4, 4, 1)).await.unwrap();
+
+    let lock = SetPermissionsRequest { read_only: false, owner_key: Some("owner-secret".to_string()) };
+    owner.set_permissions("hero.pxl", &lock).await.unwrap();
+
+    // The permissions live in the file's v2 metadata block
+    let on_disk = server.read_book("hero.pxl");
+    assert!(on_disk.metadata.permissions.owner_key_hash.is_some());
+    assert_eq!(u16::from_le_bytes([server.read_bytes("hero.pxl")[4], server.read_bytes("hero.pxl")[5]]), pixl_format::FORMAT_VERSION_V2);
+
+    let draw = UpdatePixelBookRequest {
+        operations: vec![DrawingOperation::DrawPixel { frame: 0, x: 0, y: 0, color: RED.into(), blend_mode: BlendMode::Replace }],
+        symmetry: None,
+    };
+    for client in [server.client(), &intruder] {
+        assert!(matches!(client.update_book("hero.pxl", &draw).await, Err(ClientError::Server { status: 403, .. })));
+    }
+    assert!(matches!(
+        intruder.create_book(&create_request("hero.pxl", 2, 2, 1)).await,
+        Err(ClientError::Server { status: 403, .. })
+    ));
+    owner.update_book("hero.pxl", &draw).await.unwrap();
+
+    // Read-only blocks even the owner until they lift it
+    let freeze = SetPermissionsRequest { read_only: true, owner_key: Some("owner-secret".to_string()) };
+    owner.set_permissions("hero.pxl", &freeze).await.unwrap();
+    assert!(owner.update_book("hero.pxl", &draw).await.is_err());
+    assert!(intruder.set_permissions("hero.pxl", &SetPermissionsRequest::default()).await.is_err());
+    owner.set_permissions("hero.pxl", &SetPermissionsRequest::default()).await.unwrap();
+    server.client().update_book("hero.pxl", &draw).await.unwrap();
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_operations_draw_with_palette_indices() {
+    let server = TestServer::start().await;
+    let client = server.client();
+    client.create_book(&create_request("tiles.pxl", 4, 4, 1)).await.unwrap();
+    assert!(matches!(client.get_palette("tiles.pxl").await, Err(ClientError::Server { status: 404, .. })));
+
+    let palette = Palette { name: "primaries".to_string(), colors: vec![RED, BLUE] };
+    client.set_palette("tiles.pxl", &palette).await.unwrap();
+    assert_eq!(client.get_palette("tiles.pxl").await.unwrap(), palette);
+
+    // The palette lives in the file's v3 metadata block
+    assert_eq!(server.read_book("tiles.pxl").metadata.palette.as_ref(), Some(&palette));
+    assert_eq!(u16::from_le_bytes([server.read_bytes("tiles.pxl")[4], server.read_bytes("tiles.pxl")[5]]), pixl_format::FORMAT_VERSION_V3);
+
+    let draw = |color| UpdatePixelBookRequest {
+        operations: vec![DrawingOperation::DrawPixel { frame: 0, x: 2, y: 1, color, blend_mode: BlendMode::Replace }],
+        symmetry: None,
+    };
+    client.update_book("tiles.pxl", &draw(ColorRef::Index(1))).await.unwrap();
+    assert_eq!(server.read_book("tiles.pxl").frames[0].get_pixel(2, 1, 4), Some(Pixel::new(0, 0, 255, 255)));
+    assert!(matches!(client.update_book("tiles.pxl", &draw(ColorRef::Index(2))).await, Err(ClientError::Server { status: 400, .. })));
+
+    let oversized = Palette { name: "too many".to_string(), colors: vec![RED; 257] };
+    assert!(matches!(client.set_palette("tiles.pxl", &oversized).await, Err(ClientError::Server { status: 400, .. })));
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_frame_durations_are_stored_in_the_file() {
+    let server = TestServer::start().await;
+    let client = server.client();
+    client.create_book(&create_request("walk.pxl", 4, 4, 2)).await.unwrap();
+
+    assert_eq!(client.set_frame_duration("walk.pxl", 1, Some(250)).await.unwrap().duration_ms, Some(250));
+    let book = server.read_book("walk.pxl");
+    assert_eq!(book.frames.iter().map(|frame| frame.duration_ms).collect::<Vec<_>>(), vec![None, Some(250)]);
+    assert_eq!(u16::from_le_bytes([server.read_bytes("walk.pxl")[4], server.read_bytes("walk.pxl")[5]]), pixl_format::FORMAT_VERSION_V4);
+    assert_eq!(client.get_frame("walk.pxl", 1, FrameEncoding::Rle).await.unwrap().duration_ms, Some(250));
+
+    assert!(matches!(client.set_frame_duration("walk.pxl", 1, Some(0)).await, Err(ClientError::Server { status: 400, .. })));
+    assert!(matches!(client.set_frame_duration("walk.pxl", 2, Some(100)).await, Err(ClientError::Server { status: 404, .. })));
+
+    client.set_frame_duration("walk.pxl", 1, None).await.unwrap();
+    assert_eq!(server.read_book("walk.pxl").frames[1].duration_ms, None);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_aseprite_json_export_carries_durations_and_frame_tags() {
+    let server = TestServer::start().await;
+    let client = server.client();
+    client.create_book(&create_request("hero.pxl", 4, 4, 4)).await.unwrap();
+    client.set_frame_duration("hero.pxl", 2, Some(300)).await.unwrap();
+
+    let tag = |name: &str, from, to| FrameTag { name: name.to_string(), from, to, direction: TagDirection::Pingpong };
+    let update = UpdateDetailsRequest { frame_tags: Some(vec![tag("idle", 0, 1), tag("walk", 2, 3)]), ..Default::default() };
+    assert_eq!(client.update_details("hero.pxl", &update).await.unwrap().frame_tags.len(), 2);
+    let past_end = UpdateDetailsRequest { frame_tags: Some(vec![tag("run", 2, 4)]), ..Default::default() };
+    assert!(matches!(client.update_details("hero.pxl", &past_end).await, Err(ClientError::Server { status: 400, .. })));
+
+    let json = client.export_aseprite_json("hero.pxl", Some(2)).await.unwrap();
+    assert_eq!(json["frames"][2]["duration"], 300);
+    assert_eq!(json["frames"][3]["frame"]["x"], 4);
+    assert_eq!(json["meta"]["image"], "hero.png");
+    assert_eq!(json["meta"]["frameTags"][1]["name"], "walk");
+    assert_eq!(json["meta"]["frameTags"][1]["direction"], "pingpong");
+
+    let png = client.export_aseprite_png("hero.pxl", Some(2)).await.unwrap();
+    assert!(png.starts_with(b"\x89PNG"));
+    assert_eq!(u32::from_be_bytes(png[16..20].try_into().unwrap()), json["meta"]["size"]["w"].as_u64().unwrap() as u32);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_resize_crop_and_scale_change_every_frame() {
+    let server = TestServer::start().await;
+    let client = server.client();
+    client.create_book(&create_request("hero.pxl", 4, 4, 2)).await.unwrap();
+    let draw = UpdatePixelBookRequest {
+        operations: vec![DrawingOperation::DrawPixel { frame: 1, x: 3, y: 3, color: RED.into(), blend_mode: BlendMode::Replace }],
+        symmetry: None,
+    };
+    let before = client.update_book("hero.pxl", &draw).await.unwrap();
+
+    let resize = ResizeRequest { width: 6, height: 5, anchor: Anchor::TopLeft, fill: BLUE };
+    let resized = client.resize_book("hero.pxl", &resize).await.unwrap();
+    assert_eq!((resized.width, resized.height, resized.frames), (6, 5, 2));
+    assert!(resized.revision > before.revision);
+    let book = server.read_book("hero.pxl");
+    assert_eq!(book.frames[1].get_pixel(3, 3, 6), Some(Pixel::new(255, 0, 0, 255)));
+    assert_eq!(book.frames[0].get_pixel(5, 4, 6), Some(Pixel::new(0, 0, 255, 255)));
+    assert_eq!(book.frames[0].get_pixel(0, 0, 6), Some(Pixel::new(0, 0, 0, 0)));
+
+    let cropped = client.crop_book("hero.pxl", &Rect { x: 3, y: 3, width: 10, height: 10 }).await.unwrap();
+    assert_eq!((cropped.width, cropped.height), (3, 2));
+    assert_eq!(server.read_book("hero.pxl").frames[1].get_pixel(0, 0, 3), Some(Pixel::new(255, 0, 0, 255)));
+
+    let scaled = client.scale_book("hero.pxl", 2, false).await.unwrap();
+    assert_eq!((scaled.width, scaled.height), (6, 4));
+    assert_eq!(server.read_book("hero.pxl").frames[1].get_pixel(1, 1, 6), Some(Pixel::new(255, 0, 0, 255)));
+    assert!(matches!(client.scale_book("hero.pxl", 4, true).await, Err(ClientError::Server { status: 400, .. })));
+    assert!(matches!(client.scale_book("hero.pxl", 17, false).await, Err(ClientError::Server { status: 400, .. })));
+    assert_eq!(client.scale_book("hero.pxl", 2, true).await.unwrap().width, 3);
+
+    // Rejected from its size alone, before any frame is scaled
+    client.create_book(&create_request("wide.pxl", 300, 300, 2)).await.unwrap();
+    let before = server.read_bytes("wide.pxl");
+    assert!(matches!(client.scale_book("wide.pxl", 16, false).await, Err(ClientError::Server { status: 400, .. })));
+    assert_eq!(server.read_bytes("wide.pxl"), before);
+
+    client.create_book(&create_request("dot.pxl", 8, 8, 2)).await.unwrap();
+    assert!(matches!(client.trim_book("dot.pxl").await, Err(ClientError::Server { status: 400, .. })));
+    let draw = UpdatePixelBookRequest {
+        operations: vec![
+            DrawingOperation::DrawPixel { frame: 0, x: 5, y: 2, color: RED.into(), blend_mode: BlendMode::Replace },
+            DrawingOperation::DrawPixel { frame: 1, x: 6, y: 4, color: RED.into(), blend_mode: BlendMode::Replace },
+        ],
+        symmetry: None,
+    };
+    client.update_book("dot.pxl", &draw).await.unwrap();
+    let trimmed = client.trim_book("dot.pxl").await.unwrap();
+    assert_eq!((trimmed.width, trimmed.height, trimmed.x, trimmed.y), (2, 3, 5, 2));
+    assert_eq!(server.read_book("dot.pxl").frames[1].get_pixel(1, 2, 2), Some(Pixel::new(255, 0, 0, 255)));
+    let again = client.trim_book("dot.pxl").await.unwrap();
+    assert_eq!((again.x, again.y, again.revision), (0, 0, trimmed.revision));
+
+    let outside = Rect { x: 3, y: 0, width: 1, height: 1 };
+    assert!(matches!(client.crop_book("hero.pxl", &outside).await, Err(ClientError::Server { status: 400, .. })));
+    let huge = ResizeRequest { width: 5000, height: 5, anchor: Anchor::Center, fill: [0; 4] };
+    assert!(matches!(client.resize_book("hero.pxl", &huge).await, Err(ClientError::Server { status: 400, .. })));
+    assert!(matches!(client.resize_book("missing.pxl", &resize).await, Err(ClientError::Server { status: 404, .. })));
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_thumbnails_follow_book_changes() {
+    let server = TestServer::start().await;
+    let client = server.client();
+    client.create_book(&create_request("hero.pxl", 128, 32, 1)).await.unwrap();
+
+    let size = |png: &[u8]| (u32::from_be_bytes(png[16..20].try_into().unwrap()), u32::from_be_bytes(png[20..24].try_into().unwrap()));
+    let before = client.thumbnail("hero.pxl", None).await.unwrap();
+    assert_eq!(size(&before), (64, 16));
+    assert_eq!(client.thumbnail("hero.pxl", None).await.unwrap(), before);
+    assert_eq!(size(&client.thumbnail("hero.pxl", Some(16)).await.unwrap()), (16, 4));
+
+    let draw = UpdatePixelBookRequest {
+        operations: vec![DrawingOperation::FillArea { frame: 0, x: 0, y: 0, color: RED.into(), contiguous: true, bounds: None, blend_mode: BlendMode::Replace }],
+        symmetry: None,
+    };
+    client.update_book("hero.pxl", &draw).await.unwrap();
+    assert_ne!(client.thumbnail("hero.pxl", None).await.unwrap(), before);
+
+    let preview = client.preview("hero.pxl", 0, Some(2), Some("#000000")).await.unwrap();
+    assert_eq!(size(&preview), (256, 64));
+    assert!(matches!(client.preview("hero.pxl", 1, None, None).await, Err(ClientError::Server { status: 400, .. })));
+    assert!(matches!(client.preview("hero.pxl", 0, None, Some("plaid")).await, Err(ClientError::Server { status: 400, .. })));
+
+    assert!(matches!(client.thumbnail("hero.pxl", Some(0)).await, Err(ClientError::Server { status: 400, .. })));
+    assert!(matches!(client.thumbnail("missing.pxl", None).await, Err(ClientError::Server { status: 404, .. })));
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_tilemaps_compose_tiles_from_a_tileset() {
+    let server = TestServer::start().await;
+    let client = server.client();
+    client.create_book(&create_request("tiles.pxl", 4, 2, 1)).await.unwrap();
+    let draw = UpdatePixelBookRequest {
+        operations: vec![DrawingOperation::DrawPixel { frame: 0, x: 3, y: 1, color: BLUE.into(), blend_mode: BlendMode::Replace }],
+        symmetry: None,
+    };
+    client.update_book("tiles.pxl", &draw).await.unwrap();
+
+    let request = TilemapRequest { tileset: "tiles.pxl".to_string(), columns: 3, rows: 2, ..Default::default() };
+    assert!(matches!(client.put_tilemap("level-1", &request).await, Err(ClientError::Server { status: 400, .. })));
+    assert!(matches!(client.get_tileset("tiles.pxl").await, Err(ClientError::Server { status: 404, .. })));
+    let oversized = Tileset { tile_width: 8, tile_height: 2 };
+    assert!(matches!(client.set_tileset("tiles.pxl", &oversized).await, Err(ClientError::Server { status: 400, .. })));
+
+    let info = client.set_tileset("tiles.pxl", &Tileset { tile_width: 2, tile_height: 2 }).await.unwrap();
+    assert_eq!((info.columns, info.rows, info.tile_count), (2, 1, 2));
+    assert_eq!(server.read_book("tiles.pxl").metadata.tileset, Some(Tileset { tile_width: 2, tile_height: 2 }));
+
+    assert_eq!(client.put_tilemap("level-1", &request).await.unwrap().tiles, vec![None; 6]);
+    let map = client.set_tiles("level-1", &[TilePlacement { x: 2, y: 1, tile: Some(1) }]).await.unwrap();
+    assert_eq!(map.tiles[5], Some(1));
+    let missing_tile = [TilePlacement { x: 0, y: 0, tile: Some(2) }];
+    assert!(matches!(client.set_tiles("level-1", &missing_tile).await, Err(ClientError::Server { status: 400, .. })));
+    assert_eq!(client.list_tilemaps().await.unwrap()[0].name, "level-1");
+
+    // 3x2 cells of 2x2 tiles
+    let png = client.export_tilemap_png("level-1").await.unwrap();
+    assert!(png.starts_with(b"\x89PNG"));
+    assert_eq!(u32::from_be_bytes(png[16..20].try_into().unwrap()), 6);
+    assert_eq!(u32::from_be_bytes(png[20..24].try_into().unwrap()), 4);
+
+    let tiled = client.export_tilemap_json("level-1").await.unwrap();
+    assert_eq!(tiled["layers"][0]["data"][5], 2);
+    assert_eq!(tiled["layers"][0]["data"][0], 0);
+    assert_eq!(tiled["tilesets"][0]["image"], "tiles.png");
+
+    client.delete_tilemap("level-1").await.unwrap();
+    assert!(matches!(client.get_tilemap("level-1").await, Err(ClientError::Server { status: 404, .. })));
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_migrate_round_trips_a_book_between_versions() {
+    let server = TestServer::start().await;
+    let client = server.client();
+    client.create_book(&create_request("hero.pxl", 4, 4, 2)).await.unwrap();
+    let original = server.read_bytes("hero.pxl");
+
+    let to = |version| MigrateRequest { version: Some(version) };
+    let result = client.migrate_book("hero.pxl", &to(pixl_format::FORMAT_VERSION_V2)).await.unwrap();
+    assert_eq!((result.from_version, result.to_version), (pixl_format::FORMAT_VERSION_V1, pixl_format::FORMAT_VERSION_V2));
+    assert_eq!(u16::from_le_bytes([server.read_bytes("hero.pxl")[4], server.read_bytes("hero.pxl")[5]]), pixl_format::FORMAT_VERSION_V2);
+
+    client.migrate_book("hero.pxl", &to(pixl_format::FORMAT_VERSION_V1)).await.unwrap();
+    assert_eq!(server.read_bytes("hero.pxl"), original);
+    assert!(!client.migrate_book("hero.pxl", &to(pixl_format::FORMAT_VERSION_V1)).await.unwrap().changed());
+
+    // The newest version by default; unknown versions are refused
+    let result = client.migrate_book("hero.pxl", &MigrateRequest::default()).await.unwrap();
+    assert_eq!(result.to_version, pixl_format::FORMAT_VERSION);
+    assert_eq!(server.read_book("hero.pxl").frames.len(), 2);
+    assert!(matches!(client.migrate_book("hero.pxl", &to(9)).await, Err(ClientError::Server { status: 400, .. })));
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_quantize_maps_pixels_onto_the_book_palette() {
+    let server = TestServer::start().await;
+    let client = server.client();
+    client.create_book(&create_request("import.pxl", 4, 4, 1)).await.unwrap();
+    let paint = UpdatePixelBookRequest {
+        operations: vec![DrawingOperation::FillArea { frame: 0, x: 0, y: 0, color: [120, 0, 140, 255].into(), contiguous: true, bounds: None, blend_mode: BlendMode::Replace }],
+        symmetry: None,
+    };
+    client.update_book("import.pxl", &paint).await.unwrap();
+
+    let request = QuantizeRequest { colors: None, palette: None, method: Default::default(), dither: Some(DitherPattern::Ordered) };
+    assert!(matches!(client.quantize("import.pxl", &request).await, Err(ClientError::Server { status: 400, .. })));
+
+    client.set_palette("import.pxl", &Palette { name: "primaries".to_string(), colors: vec![RED, BLUE] }).await.unwrap();
+    let result = client.quantize("import.pxl", &request).await.unwrap();
+    assert_eq!(result.palette, vec![RED, BLUE]);
+    assert_eq!(result.pixels_changed, 16);
+
+    // The purple in between dithers into a mix of both palette colors
+    let book = server.read_book("import.pxl");
+    let reds = book.frames[0].pixels.chunks_exact(4).filter(|p| *p == RED).count();
+    assert!(reds > 0 && reds < 16);
+
+    // Quantizing is logged and can be undone like any drawing
+    let log = client.operation_log("import.pxl", None).await.unwrap();
+    assert_eq!(log.last().unwrap().operations, vec!["quantize 2 colors".to_string()]);
+    client.undo("import.pxl").await.unwrap();
+    let book = server.read_book("import.pxl");
+    assert!(book.frames[0].pixels.chunks_exact(4).all(|p| p == [120, 0, 140, 255]));
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_pixels_and_frames_can_be_read_back() {
+    let server = TestServer::start().await;
+    let client = server.client();
+    client.create_book(&create_request("canvas.pxl", 4, 2, 1)).await.unwrap();
+    let draw = UpdatePixelBookRequest {
+        operations: vec![DrawingOperation::DrawPixel { frame: 0, x: 3, y: 1, color: RED.into(), blend_mode: BlendMode::Replace }],
+        symmetry: None,
+    };
+    client.update_book("canvas.pxl", &draw).await.unwrap();
+
+    let pixel = client.get_pixel("canvas.pxl", 0, 3, 1).await.unwrap();
+    assert_eq!((pixel.color, pixel.hex.as_str()), (RED, "#ff0000"));
+    assert!(matches!(client.get_pixel("canvas.pxl", 0, 4, 0).await, Err(ClientError::Server { status: 400, .. })));
+    assert!(matches!(client.get_pixel("canvas.pxl", 1, 0, 0).await, Err(ClientError::Server { status: 404, .. })));
+
+    let rle = client.get_frame("canvas.pxl", 0, FrameEncoding::Rle).await.unwrap();
+    assert_eq!(rle.data, "4*.\n3*. #ff0000\n");
+    let raw = client.get_frame("canvas.pxl", 0, FrameEncoding::Base64).await.unwrap();
+    assert_eq!(raw.encoding, FrameEncoding::Base64);
+    assert_eq!(raw.data.len(), (4 * 2 * 4usize).div_ceil(3) * 4);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_book_details_are_listed() {
+    let server = TestServer::start().await;
+    let client = server.client();
+    client.create_book(&create_request("slime.pxl", 4, 4, 1)).await.unwrap();
+    assert!(client.get_details("slime.pxl").await.unwrap().is_empty());
+
+    let update = UpdateDetailsRequest {
+        title: Some("Slime".to_string()),
+        tags: Some(vec!["Enemy".to_string(), "forest".to_string()]),
+        ..Default::default()
+    };
+    client.update_details("slime.pxl", &update).await.unwrap();
+    let details = client.update_details("slime.pxl", &UpdateDetailsRequest { author: Some("Ada".to_string()), ..Default::default() }).await.unwrap();
+    assert_eq!(details.title.as_deref(), Some("Slime"));
+    assert_eq!(details.author.as_deref(), Some("Ada"));
+    assert_eq!(details.tags, vec!["enemy", "forest"]);
+    assert_eq!(client.get_details("slime.pxl").await.unwrap(), details);
+
+    let books = client.list_books().await.unwrap();
+    assert_eq!(books[0].details, details);
+
+    client.create_book(&create_request("tree.pxl", 8, 4, 3)).await.unwrap();
+    let tagged = client.search_books(&BookFilter { tag: Some("enemy".to_string()), ..Default::default() }).await.unwrap();
+    assert_eq!((tagged.total, tagged.books[0].filename.as_str()), (1, "slime.pxl"));
+    let page = client.search_books(&BookFilter { sort: BookSort::Name, order: SortOrder::Desc, limit: Some(1), ..Default::default() }).await.unwrap();
+    assert_eq!((page.total, page.books.len(), page.books[0].filename.as_str()), (2, 1, "tree.pxl"));
+    let wide = client.search_books(&BookFilter { min_width: Some(8), frames_gt: Some(2), ..Default::default() }).await.unwrap();
+    assert_eq!((wide.books[0].width, wide.books[0].height), (8, 4));
+    assert_eq!(wide.total, 1);
+    assert_eq!(server.read_book("slime.pxl").metadata.details, details);
+
+    let too_many = UpdateDetailsRequest { tags: Some((0..40).map(|i| format!("tag{}", i)).collect()), ..Default::default() };
+    assert!(matches!(client.update_details("slime.pxl", &too_many).await, Err(ClientError::Server { status: 400, .. })));
+    assert!(matches!(client.get_details("missing.pxl").await, Err(ClientError::Server { status: 404, .. })));
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_books_can_live_in_sub_folders() {
+    let server = TestServer::start().await;
+    let client = server.client();
+    client.create_book(&create_request("title.pxl", 4, 4, 1)).await.unwrap();
+    client.create_book(&create_request("sprites/hero.pxl", 4, 4, 1)).await.unwrap();
+    assert!(server.book_path("sprites/hero.pxl").exists());
+
+    let top = client.list_books().await.unwrap();
+    assert_eq!(top.iter().map(|b| b.filename.as_str()).collect::<Vec<_>>(), vec!["title.pxl"]);
+    let all = client.search_books(&BookFilter { recursive: true, ..Default::default() }).await.unwrap();
+    assert_eq!(all.books.iter().map(|b| b.filename.as_str()).collect::<Vec<_>>(), vec!["sprites/hero.pxl", "title.pxl"]);
+
+    let update = UpdatePixelBookRequest {
+        operations: vec![DrawingOperation::DrawPixel { frame: 0, x: 1, y: 1, color: RED.into(), blend_mode: BlendMode::Replace }],
+        symmetry: None,
+    };
+    client.update_book("sprites/hero.pxl", &update).await.unwrap();
+    assert_eq!(client.get_pixel("sprites/hero.pxl", 0, 1, 1).await.unwrap().color, RED);
+
+    client.rename_book("sprites/hero.pxl", "characters/hero.pxl").await.unwrap();
+    client.delete_book("characters/hero.pxl").await.unwrap();
+    assert_eq!(client.list_trash().await.unwrap()[0].filename, "characters/hero.pxl");
+    client.restore_book("characters/hero.pxl").await.unwrap();
+    assert_eq!(server.read_book("characters/hero.pxl").frames[0].get_pixel(1, 1, 4), Some(Pixel::new(255, 0, 0, 255)));
+
+    for filename in ["../escape.pxl", "sprites/../../escape.pxl", ".trash/hero.pxl"] {
+        let rejected = client.create_book(&create_request(filename, 4, 4, 1)).await;
+        assert!(matches!(rejected, Err(ClientError::Server { status: 400, .. })), "{filename}");
+        assert!(matches!(client.get_book(filename).await, Err(ClientError::Server { status: 400, .. })), "{filename}");
+    }
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_stale_updates_are_rejected() {
+    let server = TestServer::start_with(|config| config.require_if_match = true).await;
+    let client = server.client();
+    client.create_book(&create_request("shared.pxl", 4, 4, 1)).await.unwrap();
+    let draw = |x| UpdatePixelBookRequest {
+        operations: vec![DrawingOperation::DrawPixel { frame: 0, x, y: 0, color: RED.into(), blend_mode: BlendMode::Replace }],
+        symmetry: None,
+    };
+
+    // Two agents read the same revision; only the first update lands
+    let read = client.get_book("shared.pxl").await.unwrap().revision;
+    let first = client.update_book_at("shared.pxl", read, &draw(0)).await.unwrap();
+    assert_eq!(first.revision, read + 1);
+    let stale = client.update_book_at("shared.pxl", read, &draw(1)).await;
+    assert!(matches!(stale, Err(ClientError::Server { status: 412, .. })));
+    assert!(matches!(client.update_book("shared.pxl", &draw(1)).await, Err(ClientError::Server { status: 428, .. })));
+    assert_eq!(client.get_pixel("shared.pxl", 0, 1, 0).await.unwrap().color, [0, 0, 0, 0]);
+
+    // A change made outside the server also moves the book on
+    let bytes = server.read_bytes("shared.pxl");
+    std::fs::write(server.book_path("shared.pxl"), &bytes[..bytes.len() - 1]).unwrap();
+    std::fs::write(server.book_path("shared.pxl"), &bytes).unwrap();
+    let current = client.get_book("shared.pxl").await.unwrap().revision;
+    assert!(current > first.revision);
+    client.update_book_at("shared.pxl", current, &draw(1)).await.unwrap();
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_stamps_can_come_from_other_books() {
+    let server = TestServer::start().await;
+    let client = server.client();
+    client.create_book(&create_request("tiles.pxl", 2, 2, 1)).await.unwrap();
+    client.create_book(&create_request("map.pxl", 6, 6, 1)).await.unwrap();
+    let tile = UpdatePixelBookRequest {
+        operations: vec![
+            DrawingOperation::DrawPixel { frame: 0, x: 0, y: 0, color: RED.into(), blend_mode: BlendMode::Replace },
+            DrawingOperation::DrawPixel { frame: 0, x: 1, y: 1, color: BLUE.into(), blend_mode: BlendMode::Replace },
+        ],
+        symmetry: None,
+    };
+    client.update_book("tiles.pxl", &tile).await.unwrap();
+
+    let stamp = |x, flip| DrawingOperation::DrawStamp {
+        frame: 0,
+        position: Point { x, y: 2 },
+        source: StampSource::Frame { book: Some("tiles.pxl".to_string()), frame: 0, rect: None },
+        flip,
+        skip_transparent: true,
+        blend_mode: BlendMode::Replace,
+    };
+    let request = UpdatePixelBookRequest { operations: vec![stamp(0, None), stamp(4, Some(FlipAxis::Horizontal))], symmetry: None };
+    client.update_book("map.pxl", &request).await.unwrap();
+    assert_eq!(client.get_pixel("map.pxl", 0, 0, 2).await.unwrap().color, RED);
+    assert_eq!(client.get_pixel("map.pxl", 0, 1, 3).await.unwrap().color, BLUE);
+    assert_eq!(client.get_pixel("map.pxl", 0, 5, 2).await.unwrap().color, RED);
+    assert_eq!(client.get_pixel("map.pxl", 0, 4, 3).await.unwrap().color, BLUE);
+
+    let missing = DrawingOperation::DrawStamp {
+        frame: 0,
+        position: Point { x: 0, y: 0 },
+        source: StampSource::Frame { book: Some("gone.pxl".to_string()), frame: 0, rect: None },
+        flip: None,
+        skip_transparent: true,
+        blend_mode: BlendMode::Replace,
+    };
+    let result = client.update_book("map.pxl", &UpdatePixelBookRequest { operations: vec![missing], symmetry: None }).await;
+    assert!(matches!(result, Err(ClientError::Server { status: 404, .. })));
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_dry_runs_report_failures_without_saving() {
+    let server = TestServer::start().await;
+    let client = server.client();
+    client.create_book(&create_request("plan.pxl", 4, 4, 1)).await.unwrap();
+    let request = UpdatePixelBookRequest {
+        operations: vec![
+            DrawingOperation::DrawPixel { frame: 0, x: 0, y: 0, color: RED.into(), blend_mode: BlendMode::Replace },
+            DrawingOperation::DrawPixel { frame: 0, x: 8, y: 0, color: RED.into(), blend_mode: BlendMode::Replace },
+            DrawingOperation::DrawPixel { frame: 2, x: 0, y: 0, color: BLUE.into(), blend_mode: BlendMode::Replace },
+        ],
+        symmetry: None,
+    };
+    let before = server.read_bytes("plan.pxl");
+
+    let report = client.validate_operations("plan.pxl", &request).await.unwrap();
+    assert!(!report.valid);
+    assert_eq!(report.errors.iter().map(|e| e.index).collect::<Vec<_>>(), vec![1, 2]);
+    assert_eq!(server.read_bytes("plan.pxl"), before);
+
+    let valid = UpdatePixelBookRequest { operations: request.operations[..1].to_vec(), symmetry: None };
+    assert!(client.validate_operations("plan.pxl", &valid).await.unwrap().valid);
+    assert_eq!(client.get_pixel("plan.pxl", 0, 0, 0).await.unwrap().color, [0, 0, 0, 0]);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_failing_operations_can_be_skipped() {
+    let server = TestServer::start().await;
+    let client = server.client();
+    client.create_book(&create_request("partial.pxl", 4, 4, 1)).await.unwrap();
+    let request = UpdatePixelBookRequest {
+        operations: vec![
+            DrawingOperation::DrawPixel { frame: 0, x: 0, y: 0, color: RED.into(), blend_mode: BlendMode::Replace },
+            DrawingOperation::DrawPixel { frame: 0, x: 8, y: 0, color: RED.into(), blend_mode: BlendMode::Replace },
+            DrawingOperation::DrawPixel { frame: 0, x: 1, y: 0, color: BLUE.into(), blend_mode: BlendMode::Replace },
+        ],
+        symmetry: None,
+    };
+
+    // By default the bad pixel stops everything
+    let failed = client.update_book("partial.pxl", &request).await;
+    let Err(ClientError::Server { status: 400, message }) = failed else { panic!("expected 400, got {:?}", failed) };
+    assert!(message.contains("\"status\":\"skipped\"") && message.contains("\"status\":\"error\""));
+    assert_eq!(client.get_pixel("partial.pxl", 0, 0, 0).await.unwrap().color, [0, 0, 0, 0]);
+
+    let response = client.update_book_continuing("partial.pxl", &request).await.unwrap();
+    assert_eq!(response.operations_applied, 2);
+    let statuses: Vec<_> = response.results.iter().map(|result| result.status).collect();
+    assert_eq!(statuses, vec![OperationStatus::Applied, OperationStatus::Error, OperationStatus::Applied]);
+    assert_eq!(client.get_pixel("partial.pxl", 0, 1, 0).await.unwrap().color, BLUE);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_batches_save_every_book_or_none() {
+    let server = TestServer::start().await;
+    let client = server.client();
+    client.create_book(&create_request("hero.pxl", 4, 4, 1)).await.unwrap();
+    client.create_book(&create_request("tiles/grass.pxl", 4, 4, 1)).await.unwrap();
+    let update = |filename: &str, x| BatchBookUpdate {
+        filename: filename.to_string(),
+        operations: vec![DrawingOperation::DrawPixel { frame: 0, x, y: 0, color: RED.into(), blend_mode: BlendMode::Replace }],
+        symmetry: None,
+        revision: None,
+    };
+
+    let result = client.batch(&BatchRequest { books: vec![update("hero.pxl", 0), update("tiles/grass.pxl", 1)] }).await.unwrap();
+    assert!(result.success);
+    assert!(result.books.iter().all(|book| book.operations_applied == 1 && book.revision.is_some()));
+    assert_eq!(client.get_pixel("tiles/grass.pxl", 0, 1, 0).await.unwrap().color, RED);
+
+    // One bad operation keeps the other book from being saved too
+    let before = server.read_bytes("hero.pxl");
+    let failed = client.batch(&BatchRequest { books: vec![update("hero.pxl", 2), update("tiles/grass.pxl", 9)] }).await;
+    let Err(ClientError::Server { status: 400, message }) = failed else { panic!("expected 400, got {:?}", failed) };
+    assert!(message.contains("\"success\":false"));
+    assert_eq!(message.matches("\"error\"").count(), 1);
+    assert_eq!(server.read_bytes("hero.pxl"), before);
+
+    let missing = client.batch(&BatchRequest { books: vec![update("hero.pxl", 2), update("gone.pxl", 0)] }).await;
+    assert!(matches!(missing, Err(ClientError::Server { status: 404, .. })));
+    let repeated = client.batch(&BatchRequest { books: vec![update("hero.pxl", 2), update("hero.pxl", 3)] }).await;
+    assert!(matches!(repeated, Err(ClientError::Server { status: 400, .. })));
+    assert_eq!(client.get_pixel("hero.pxl", 0, 2, 0).await.unwrap().color, [0, 0, 0, 0]);
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_batch_rollback_keeps_revisions() {
+    let server = TestServer::start().await;
+    let client = server.client();
+    client.create_book(&create_request("hero.pxl", 4, 4, 1)).await.unwrap();
+    client.create_book(&create_request("tiles/grass.pxl", 4, 4, 1)).await.unwrap();
+    let revision = client.get_book("hero.pxl").await.unwrap().revision;
+    let before = server.read_bytes("hero.pxl");
+
+    // grass.pxl cannot be written once its temporary file's name is taken by a directory
+    std::fs::create_dir(server.books_dir().join("tiles/.grass.pxl.tmp")).unwrap();
+    let draw = |filename: &str| BatchBookUpdate {
+        filename: filename.to_string(),
+        operations: vec![DrawingOperation::DrawPixel { frame: 0, x: 1, y: 1, color: RED.into(), blend_mode: BlendMode::Replace }],
+        symmetry: None,
+        revision: None,
+    };
+    let failed = client.batch(&BatchRequest { books: vec![draw("hero.pxl"), draw("tiles/grass.pxl")] }).await;
+    assert!(matches!(failed, Err(ClientError::Server { status: 500, .. })));
+
+    // hero.pxl was saved, then put back as it was, at the revision clients already hold
+    assert_eq!(server.read_bytes("hero.pxl"), before);
+    assert_eq!(client.get_book("hero.pxl").await.unwrap().revision, revision);
+    let request = UpdatePixelBookRequest { operations: vec![draw("hero.pxl").operations.remove(0)], symmetry: None };
+    assert!(client.update_book_at("hero.pxl", revision, &request).await.is_ok());
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_locked_books_reject_other_writers() {
+    let server = TestServer::start().await;
+    server.client().create_book(&create_request("scene.pxl", 4, 4, 1)).await.unwrap();
+
+    let lock = LockRequest { holder: "viewer".to_string(), ttl_seconds: Some(60) };
+    let granted = server.client().lock_book("scene.pxl", &lock).await.unwrap();
+    let holder = server.client().clone().with_lock_token(&granted.token);
+
+    let books = server.client().list_books().await.unwrap();
+    assert_eq!(books[0].lock.as_ref().map(|lock| lock.holder.as_str()), Some("viewer"));
+
+    let request = UpdatePixelBookRequest {
+        operations: vec![DrawingOperation::DrawPixel { frame: 0, x: 0, y: 0, color: RED.into(), blend_mode: BlendMode::Replace }],
+        symmetry: None,
+    };
+    let blocked = server.client().update_book("scene.pxl", &request).await;
+    assert!(matches!(blocked, Err(ClientError::Server { status: 423, .. })));
+    let stolen = server.client().lock_book("scene.pxl", &LockRequest { holder: "agent".to_string(), ttl_seconds: None }).await;
+    assert!(matches!(stolen, Err(ClientError::Server { status: 423, .. })));
+
+    holder.update_book("scene.pxl", &request).await.unwrap();
+    holder.unlock_book("scene.pxl").await.unwrap();
+    server.client().update_book("scene.pxl", &request).await.unwrap();
+    assert!(server.client().list_books().await.unwrap()[0].lock.is_none());
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_books_can_be_copied_and_renamed() {
+    let server = TestServer::start().await;
+    server.client().create_book(&create_request("wip.pxl", 4, 4, 1)).await.unwrap();
+    server.client().create_book(&create_request("taken.pxl", 4, 4, 1)).await.unwrap();
+    let request = UpdatePixelBookRequest {
+        operations: vec![DrawingOperation::DrawPixel { frame: 0, x: 0, y: 0, color: RED.into(), blend_mode: BlendMode::Replace }],
+        symmetry: None,
+    };
+    server.client().update_book("wip.pxl", &request).await.unwrap();
+    let mut events = server.subscribe("wip.pxl").await;
+
+    server.client().copy_book("wip.pxl", "wip-v1.pxl").await.unwrap();
+    assert_eq!(server.read_bytes("wip-v1.pxl"), server.read_bytes("wip.pxl"));
+
+    server.client().rename_book("wip.pxl", "hero.pxl").await.unwrap();
+    // Skip the events of the earlier update
+    loop {
+        if let EventType::BookRenamed { new_filename } = next_event(&mut events).await.event_type {
+            assert_eq!(new_filename, "hero.pxl");
+            break;
+        }
+    }
+    assert!(!server.book_path("wip.pxl").exists());
+    assert_eq!(server.client().operation_log("hero.pxl", None).await.unwrap().len(), 1);
+    // The undo history follows the book to its new name
+    server.client().undo("hero.pxl").await.unwrap();
+    assert_eq!(server.read_book("hero.pxl").frames[0].get_pixel(0, 0, 4), Some(Pixel::transparent()));
+
+    for result in [
+        server.client().rename_book("hero.pxl", "taken.pxl").await,
+        server.client().copy_book("hero.pxl", "taken.pxl").await,
+    ] {
+        assert!(matches!(result, Err(ClientError::Server { status: 409, .. })));
+    }
+    let missing = server.client().copy_book("wip.pxl", "again.pxl").await;
+    assert!(matches!(missing, Err(ClientError::Server { status: 404, .. })));
+
+    drop(events);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_deleted_books_go_to_trash_and_can_be_restored() {
+    let server = TestServer::start().await;
+    server.client().create_book(&create_request("draft.pxl", 4, 4, 1)).await.unwrap();
+    let original = server.read_bytes("draft.pxl");
+    let mut events = server.subscribe("draft.pxl").await;
+
+    server.client().delete_book("draft.pxl").await.unwrap();
+    assert!(matches!(next_event(&mut events).await.event_type, EventType::BookDeleted));
+    assert!(!server.book_path("draft.pxl").exists());
+    assert!(server.client().list_books().await.unwrap().is_empty());
+
+    let trash = server.client().list_trash().await.unwrap();
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0].filename, "draft.pxl");
+
+    server.client().restore_book("draft.pxl").await.unwrap();
+    assert!(matches!(next_event(&mut events).await.event_type, EventType::BookRestored));
+    assert_eq!(server.read_bytes("draft.pxl"), original);
+    assert!(server.client().list_trash().await.unwrap().is_empty());
+
+    drop(events);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_books_can_start_from_templates() {
+    let server = TestServer::start().await;
+    let templates = server.client().list_templates().await.unwrap();
+    let skeleton = templates.iter().find(|t| t.name == "character-skeleton").unwrap();
+    assert!(skeleton.builtin);
+
+    let request = CreateFromTemplateRequest { template: skeleton.name.clone(), filename: "hero.pxl".to_string() };
+    server.client().create_from_template(&request).await.unwrap();
+    let book = server.client().get_book("hero.pxl").await.unwrap();
+    assert_eq!((book.width, book.height, book.frames.len()), (skeleton.width, skeleton.height, skeleton.frames));
+    assert!(book.frames[0].pixels.chunks_exact(4).any(|p| p[3] != 0));
+
+    let unknown = CreateFromTemplateRequest { template: "dragon".to_string(), filename: "dragon.pxl".to_string() };
+    let missing = server.client().create_from_template(&unknown).await;
+    assert!(matches!(missing, Err(ClientError::Server { status: 404, .. })));
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_integrity_scan_reports_damaged_books() {
+    let server = TestServer::start_with(|config| config.integrity_scan_interval = None).await;
+    server.client().create_book(&create_request("fine.pxl", 4, 4, 1)).await.unwrap();
+    server.client().create_book(&create_request("torn.pxl", 4, 4, 3)).await.unwrap();
+    assert!(server.client().last_scan().await.unwrap().is_none());
+
+    let mut events = server.client().subscribe_workspace(&["integrity_problem"]).await.unwrap();
+    let bytes = server.read_bytes("torn.pxl");
+    std::fs::write(server.book_path("torn.pxl"), &bytes[..bytes.len() / 2]).unwrap();
+
+    let report = server.client().scan_books().await.unwrap();
+    assert_eq!(report.books_scanned, 2);
+    assert_eq!(report.issues.len(), 1);
+    assert_eq!(report.issues[0].filename, "torn.pxl");
+
+    let event = next_event(&mut events).await;
+    assert_eq!(event.filename, "torn.pxl");
+    assert!(matches!(event.event_type, EventType::IntegrityProblem { .. }));
+    assert_eq!(server.client().last_scan().await.unwrap().unwrap().finished_at, report.finished_at);
+
+    drop(events);
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_autosave_snapshots_can_be_restored() {
+    let server = TestServer::start_with(|config| {
+        config.snapshot_interval = Some(std::time::Duration::from_millis(50));
+    }).await;
+    server.client().create_book(&create_request("autosaved.pxl", 4, 4, 1)).await.unwrap();
+    let original = server.read_bytes("autosaved.pxl");
+
+    let mut snapshots = Vec::new();
+    for _ in 0..100 {
+        snapshots = server.client().list_snapshots("autosaved.pxl").await.unwrap();
+        if !snapshots.is_empty() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    let first = snapshots.last().expect("autosave never snapshotted the book").clone();
+
+    let request = UpdatePixelBookRequest {
+        operations: vec![DrawingOperation::DrawPixel { frame: 0, x: 0, y: 0, color: RED.into(), blend_mode: BlendMode::Replace }],
+        symmetry: None,
+    };
+    server.client().update_book("autosaved.pxl", &request).await.unwrap();
+    assert_ne!(server.read_bytes("autosaved.pxl"), original);
+
+    let restored = server.client().restore_snapshot("autosaved.pxl", first.id).await.unwrap();
+    assert_eq!(restored.id, first.id);
+    assert_eq!(server.read_bytes("autosaved.pxl"), original);
+
+    let missing = server.client().restore_snapshot("autosaved.pxl", 1).await.unwrap_err();
+    assert!(matches!(missing, ClientError::Server { status: 404, .. }));
+
+    let checkpoint = server.client().create_snapshot("autosaved.pxl", "restored").await.unwrap();
+    let listed = server.client().list_snapshots("autosaved.pxl").await.unwrap();
+    assert!(listed.iter().any(|s| s.id == checkpoint.id && s.name.as_deref() == Some("restored")));
+    let unnamed = server.client().create_snapshot("autosaved.pxl", "").await.unwrap_err();
+    assert!(matches!(unnamed, ClientError::Server { status: 400, .. }));
+
+    // Compare the checkpoint with the book after redrawing the pixel
+    server.client().update_book("autosaved.pxl", &request).await.unwrap();
+    let since = server.client().diff("autosaved.pxl", &DiffQuery { snapshot_a: Some(checkpoint.id), ..Default::default() }).await.unwrap();
+    assert_eq!(since.changed, 1);
+    assert_eq!((since.diff.changes[0].x, since.diff.changes[0].y), (0, 0));
+    assert_eq!(since.diff.changes[0].after, Pixel::new(255, 0, 0, 255));
+    let unknown = server.client().diff("autosaved.pxl", &DiffQuery { snapshot_b: Some(1), ..Default::default() }).await.unwrap_err();
+    assert!(matches!(unknown, ClientError::Server { status: 404, .. }));
+
+    server.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_exports_download_through_client() {
+    let server = TestServer::start().await;
+    server.client().create_book(&create_request("led.pxl", 2, 2, 2)).await.unwrap();
+    let request = UpdatePixelBookRequest {
+        operations: vec![DrawingOperation::DrawPixel { frame: 1, x: 0, y: 0, color: RED.into(), blend_mode: BlendMode::Replace }],
+        symmetry: None,
+    };
+    server.client().update_book("led.pxl", &request).await.unwrap();
+
+    let zip = server.client().export_zip("led.pxl").await.unwrap();
+    assert!(zip.starts_with(b"PK"));
+
+    let png = server.client().export_png("led.pxl", 1).await.unwrap();
+    assert!(png.starts_with(b"\x89PNG"));
+    let missing = server.client().export_png("led.pxl", 2).await.unwrap_err();
+    assert!(matches!(missing, ClientError::Server { status: 400, .. }));
+
+    let gif = server.client().export_gif("led.pxl", Some(50)).await.unwrap();
+    assert!(gif.starts_with(b"GIF8"));
+
+    let grid = server.client().frame_grid("led.pxl", 1, None).await.unwrap();
+    assert_eq!(grid.rows, vec![vec!["#ff0000", "."], vec![".", "."]]);
+    let missing = server.client().frame_grid("led.pxl", 2, None).await.unwrap_err();
+    assert!(matches!(missing, ClientError::Server { status: 404, .. }));
+
+    let stats = server.client().frame_stats("led.pxl", 1, None).await.unwrap();
+    assert_eq!((stats.visible_pixels, stats.distinct_colors), (1, 1));
+    assert_eq!(stats.histogram[0].color, RED);
+    assert_eq!(stats.bounds.map(|bounds| (bounds.x, bounds.y, bounds.width, bounds.height)), Some((0, 0, 1, 1)));
+    assert!(server.client().frame_stats("led.pxl", 0, Some(5)).await.unwrap().bounds.is_none());
+
+    // No frame range can end after the largest index, so it is refused rather than overflowing
+    let overflow = server.client().frame_stats("led.pxl", usize::MAX, None).await.unwrap_err();
+    assert!(matches!(overflow, ClientError::Server { status: 400, .. }));
+    let overflow = server.client().export_png("led.pxl", usize::MAX).await.unwrap_err();
+    assert!(matches!(overflow, ClientError::Server { status: 400, .. }));
+    let overflow = server.client().diff("led.pxl", &DiffQuery { frame_a: usize::MAX, ..Default::default() }).await.unwrap_err();
+    assert!(matches!(overflow, ClientError::Server { status: 400, .. }));
+
+    let options = EmbeddedOptions { output: EmbeddedOutput::Binary, frame: Some(1), ..Default::default() };
+    let raw = server.client().export_embedded("led.pxl", &options).await.unwrap();
+    assert_eq!(raw, vec![0x00, 0xF8, 0, 0, 0, 0, 0, 0]);
+
+    let options = EmbeddedOptions { frame: Some(7), ..Default::default() };
+    let missing = server.client().export_embedded("led.pxl", &options).await.unwrap_err();
+    assert!(matches!(missing, ClientError::Server { status: 400, .. }));
+
+    assert_eq!(server.client().export_raw("led.pxl", Some(1), ColorFormat::Rgb565).await.unwrap(), raw);
+    let rgba = server.client().export_raw("led.pxl", Some(1), ColorFormat::Rgba8888).await.unwrap();
+    assert_eq!(rgba[..8], [255, 0, 0, 255, 0, 0, 0, 0]);
+    assert_eq!(server.client().export_raw("led.pxl", None, ColorFormat::Rgba8888).await.unwrap().len(), 2 * 2 * 2 * 4);
+    let missing = server.client().export_raw("led.pxl", Some(2), ColorFormat::Indexed).await.unwrap_err();
+    assert!(matches!(missing, ClientError::Server { status: 400, .. }));
+
+    let rust = server.client().export_code("led.pxl", CodeLanguage::Rust, Some(1), ColorFormat::Rgb565).await.unwrap();
+    assert!(rust.contains("pub const LED_WIDTH: usize = 2;"));
+    assert!(rust.contains("pub const LED: [[u16; 4]; 1] = ["));
+    assert!(rust.contains("0xF800, 0x0000, 0x0000, 0x0000,"));
+    let c = server.client().export_code("led.pxl", CodeLanguage::C, None, ColorFormat::Rgba8888).await.unwrap();
+    assert!(c.contains("#define LED_HEIGHT 2"));
+    assert!(c.contains("const uint8_t led[2][16] = {"));
+
+    server.shutdown().await;
+}
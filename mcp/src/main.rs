@@ -1,7 +1,11 @@
-use poem_mcpserver::{content::Text, stdio::stdio, McpServer, Tools};
+use poem_mcpserver::{content::{Image, Text}, stdio::stdio, McpServer, Tools};
+use base64::Engine;
+use pixl_core::{
+    ActiveSelection, ascii_minimaps, critique_region, BatchBookUpdate, BatchRequest, BatchResult, filename_segment, thumbnail, CreateFromTemplateRequest, BlendMode, ColorRef, CreatePixelBookRequest, DitherPattern, DrawingOperation, EncodedFrame, FrameDuration, FrameEncoding, FrameStats, FillRule, FlipAxis, GradientDirection, LineStyle, LineType, Palette, PixelBook, PixelColor, Point,
+    Rect, RenameBookRequest, Selection, SetSymmetryRequest, ShapeType, Size, StampSource, Symmetry, UpdatePixelBookRequest, ValidationReport,
+};
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use schemars::JsonSchema;
+use serde::Serialize;
 
 
 
@@ -26,79 +30,64 @@ impl PixlMcpServer {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-pub struct Point {
-    pub x: u16,
-    pub y: u16,
-}
+/// Longest side of the frame thumbnails `get_book` can include
+const THUMBNAIL_SIZE: u16 = 16;
+/// Pixels per canvas pixel in `render_frame` images by default
+const RENDER_SCALE: u32 = 8;
+/// Histogram entries `frame_stats` asks for by default
+const STATS_TOP_COLORS: usize = 16;
+/// Longest side `preview_frame` draws one character per pixel by default
+const PREVIEW_SIZE: u16 = 64;
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-pub struct Size {
-    pub width: u16,
-    pub height: u16,
+#[derive(Clone, Copy)]
+enum Thumbnails {
+    Ascii,
+    Png,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-#[serde(rename_all = "snake_case")]
-pub enum LineType {
-    Straight,
-    Curved,
+/// A thumbnail of every frame of `book`, to follow its details
+fn frame_overview(book: &PixelBook, kind: Thumbnails) -> String {
+    let mut overview = String::new();
+    match kind {
+        Thumbnails::Ascii => {
+            let minimaps = ascii_minimaps(book, THUMBNAIL_SIZE);
+            overview.push_str(&ascii_legend(&minimaps.legend));
+            for (index, map) in minimaps.frames.iter().enumerate() {
+                overview.push_str(&format!("\n\nFrame {}:\n{}", index, map));
+            }
+        }
+        Thumbnails::Png => {
+            for (index, frame) in book.frames.iter().enumerate() {
+                let (thumb, width, height) = thumbnail(frame, book.width, book.height, THUMBNAIL_SIZE);
+                let mut png = Vec::new();
+                let line = match pixl_format::convert::write_png(&thumb, width, height, &mut png) {
+                    Ok(()) => format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(&png)),
+                    Err(e) => format!("failed to encode: {}", e),
+                };
+                overview.push_str(&format!("\n\nFrame {} ({}x{}): {}", index, width, height, line));
+            }
+        }
+    }
+    overview
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-#[serde(rename_all = "snake_case")]
-pub enum ShapeType {
-    Rectangle,
-    Circle,
-    Oval,
-    Triangle,
+/// The legend of an ASCII minimap, one symbol per line
+fn ascii_legend(legend: &[(char, [u8; 4])]) -> String {
+    let mut text = "\n\nLegend ('.' is transparent):".to_string();
+    for (symbol, [r, g, b, a]) in legend {
+        text.push_str(&format!("\n{} = rgba({}, {}, {}, {})", symbol, r, g, b, a));
+    }
+    text
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-#[serde(tag = "type")]
-pub enum DrawingOperation {
-    #[serde(rename = "draw_pixel")]
-    DrawPixel {
-        frame: usize,
-        x: u16,
-        y: u16,
-        color: [u8; 4],
-    },
-    #[serde(rename = "set_color")]
-    SetColor {
-        color: [u8; 4],
-    },
-    #[serde(rename = "draw_line")]
-    DrawLine {
-        frame: usize,
-        start: Point,
-        end: Point,
-        line_type: LineType,
-        color: [u8; 4],
-    },
-    #[serde(rename = "draw_shape")]
-    DrawShape {
-        frame: usize,
-        shape: ShapeType,
-        position: Point,
-        size: Size,
-        filled: bool,
-        color: [u8; 4],
-    },
-    #[serde(rename = "draw_polygon")]
-    DrawPolygon {
-        frame: usize,
-        points: Vec<Point>,
-        filled: bool,
-        color: [u8; 4],
-    },
-    #[serde(rename = "fill_area")]
-    FillArea {
-        frame: usize,
-        x: u16,
-        y: u16,
-        color: [u8; 4],
-    },
+/// Gradient direction from a tool argument; horizontal when omitted
+fn parse_gradient_direction(direction: Option<String>) -> Result<GradientDirection, String> {
+    match direction.map(|d| d.to_lowercase()).as_deref() {
+        None | Some("horizontal") => Ok(GradientDirection::Horizontal),
+        Some("vertical") => Ok(GradientDirection::Vertical),
+        Some("radial") => Ok(GradientDirection::Radial),
+        _ => Err("Invalid direction. Use 'horizontal', 'vertical', or 'radial'".to_string()),
+    }
 }
 
 #[derive(Serialize)]
@@ -106,19 +95,6 @@ struct SetPathRequest {
     path: String,
 }
 
-#[derive(Serialize)]
-struct CreatePixelBookRequest {
-    filename: String,
-    width: u16,
-    height: u16,
-    frames: usize,
-}
-
-#[derive(Serialize)]
-struct UpdatePixelBookRequest {
-    operations: Vec<DrawingOperation>,
-}
-
 /// This server provides comprehensive tools for creating and manipulating pixel art images.
 /// 
 /// The PIXL MCP Server acts as a bridge between AI models and the PIXL API, enabling
@@ -129,7 +105,7 @@ impl PixlMcpServer {
     /// Check if the PIXL server is running and healthy
     async fn health_check(&self) -> Text<String> {
         let message = match self.client
-            .get(&format!("{}/", self.server_url))
+            .get(format!("{}/", self.server_url))
             .send()
             .await 
         {
@@ -152,7 +128,7 @@ impl PixlMcpServer {
     /// Get the current file system path where pixel books are stored
     async fn get_path(&self) -> Text<String> {
         let message = match self.client
-            .get(&format!("{}/path", self.server_url))
+            .get(format!("{}/path", self.server_url))
             .send()
             .await 
         {
@@ -177,7 +153,7 @@ impl PixlMcpServer {
         let request = SetPathRequest { path: path.clone() };
         
         let message = match self.client
-            .put(&format!("{}/path", self.server_url))
+            .put(format!("{}/path", self.server_url))
             .json(&request)
             .send()
             .await 
@@ -198,10 +174,41 @@ impl PixlMcpServer {
         Text(message)
     }
 
-    /// List all available pixel books in the current directory
-    async fn list_books(&self) -> Text<String> {
+    /// List the pixel books in the current directory, sorted by name.
+    /// Optional filters: name (part of the filename or title), tag, min_width, min_height,
+    /// and frames_gt (more than this many frames). sort is "name", "modified", "created", "size",
+    /// or "frames", with order "asc" or "desc". limit and offset page through large libraries;
+    /// the response's total counts every match. recursive also lists books in sub-folders,
+    /// named by their relative path such as "sprites/hero.pxl".
+    #[allow(clippy::too_many_arguments)]
+    async fn list_books(
+        &self,
+        name: Option<String>,
+        tag: Option<String>,
+        min_width: Option<u16>,
+        min_height: Option<u16>,
+        frames_gt: Option<usize>,
+        sort: Option<String>,
+        order: Option<String>,
+        limit: Option<usize>,
+        offset: Option<usize>,
+        recursive: Option<bool>,
+    ) -> Text<String> {
+        let mut query: Vec<(&str, String)> = Vec::new();
+        query.extend(name.map(|name| ("name", name)));
+        query.extend(tag.map(|tag| ("tag", tag)));
+        query.extend(min_width.map(|width| ("min_width", width.to_string())));
+        query.extend(min_height.map(|height| ("min_height", height.to_string())));
+        query.extend(frames_gt.map(|frames| ("frames_gt", frames.to_string())));
+        query.extend(sort.map(|sort| ("sort", sort.to_lowercase())));
+        query.extend(order.map(|order| ("order", order.to_lowercase())));
+        query.extend(limit.map(|limit| ("limit", limit.to_string())));
+        query.extend(offset.map(|offset| ("offset", offset.to_string())));
+        query.extend(recursive.map(|recursive| ("recursive", recursive.to_string())));
+
         let message = match self.client
-            .get(&format!("{}/books", self.server_url))
+            .get(format!("{}/books", self.server_url))
+            .query(&query)
             .send()
             .await 
         {
@@ -213,7 +220,11 @@ impl PixlMcpServer {
                         Err(e) => format!("Failed to parse response: {}", e)
                     }
                 } else {
-                    format!("Failed to list books: {}", response.status())
+                    let status = response.status();
+                    match response.text().await {
+                        Ok(error_text) => format!("Failed to list books: {}", error_text),
+                        Err(_) => format!("Failed to list books: HTTP {}", status)
+                    }
                 }
             },
             Err(e) => format!("Failed to connect to PIXL server: {}", e)
@@ -237,7 +248,7 @@ impl PixlMcpServer {
         };
         
         let message = match self.client
-            .post(&format!("{}/books", self.server_url))
+            .post(format!("{}/books", self.server_url))
             .json(&request)
             .send()
             .await 
@@ -263,18 +274,110 @@ impl PixlMcpServer {
         Text(message)
     }
 
-    /// Get information about a specific pixel book
-    async fn get_book(&self, filename: String) -> Text<String> {
+    /// Duplicate a pixel book under a new filename, e.g. to keep a version of a sprite
+    /// before reworking it. Fails if new_filename already exists.
+    async fn copy_book(&self, filename: String, new_filename: String) -> Text<String> {
+        self.move_book(filename, new_filename, "copy").await
+    }
+
+    /// Rename a pixel book. Its undo history and operation log follow it to the new name.
+    /// Fails if new_filename already exists.
+    async fn rename_book(&self, filename: String, new_filename: String) -> Text<String> {
+        self.move_book(filename, new_filename, "rename").await
+    }
+
+    /// List templates that new books can start from, such as a character
+    /// skeleton or a tileset grid, with their dimensions and frame counts
+    async fn list_templates(&self) -> Text<String> {
+        let message = match self.client
+            .get(format!("{}/templates", self.server_url))
+            .send()
+            .await 
+        {
+            Ok(response) => {
+                if response.status().is_success() {
+                    match response.json::<serde_json::Value>().await {
+                        Ok(body) => format!("Available templates:\n{}", 
+                            serde_json::to_string_pretty(&body).unwrap_or_else(|_| "{}".to_string())),
+                        Err(e) => format!("Failed to parse response: {}", e)
+                    }
+                } else {
+                    format!("Failed to list templates: {}", response.status())
+                }
+            },
+            Err(e) => format!("Failed to connect to PIXL server: {}", e)
+        };
+        Text(message)
+    }
+
+    /// Create a new pixel book from a template (see list_templates) instead of a blank canvas
+    async fn create_from_template(
+        &self,
+        template: String,
+        filename: String,
+    ) -> Text<String> {
+        let request = CreateFromTemplateRequest {
+            template: template.clone(),
+            filename: filename.clone(),
+        };
+        
+        let message = match self.client
+            .post(format!("{}/books/from-template", self.server_url))
+            .json(&request)
+            .send()
+            .await 
+        {
+            Ok(response) => {
+                if response.status().is_success() {
+                    match response.json::<serde_json::Value>().await {
+                        Ok(body) => format!("Created pixel book '{}' from template '{}': {}", 
+                            filename, template,
+                            serde_json::to_string_pretty(&body).unwrap_or_else(|_| "{}".to_string())),
+                        Err(e) => format!("Created pixel book '{}' but failed to parse response: {}", filename, e)
+                    }
+                } else {
+                    let status = response.status();
+                    match response.text().await {
+                        Ok(error_text) => format!("Failed to create book from template: {}", error_text),
+                        Err(_) => format!("Failed to create book from template: HTTP {}", status)
+                    }
+                }
+            },
+            Err(e) => format!("Failed to connect to PIXL server: {}", e)
+        };
+        Text(message)
+    }
+
+    /// Get information about a specific pixel book.
+    /// Optional thumbnails ("ascii" or "png") add a small overview of every frame:
+    /// text maps with a color legend, or base64 PNG data URIs at most 16 pixels across.
+    async fn get_book(&self, filename: String, thumbnails: Option<String>) -> Text<String> {
+        let thumbnails = match thumbnails.map(|t| t.to_lowercase()).as_deref() {
+            None | Some("none") => None,
+            Some("ascii") => Some(Thumbnails::Ascii),
+            Some("png") => Some(Thumbnails::Png),
+            _ => return Text("Invalid thumbnails. Use 'none', 'ascii', or 'png'".to_string()),
+        };
+        
         let message = match self.client
-            .get(&format!("{}/books/{}", self.server_url, filename))
+            .get(format!("{}/books/{}", self.server_url, filename_segment(&filename)))
             .send()
             .await 
         {
             Ok(response) => {
                 if response.status().is_success() {
                     match response.json::<serde_json::Value>().await {
-                        Ok(body) => format!("Pixel book '{}' details:\n{}", 
-                            filename, serde_json::to_string_pretty(&body).unwrap_or_else(|_| "{}".to_string())),
+                        Ok(body) => {
+                            let mut message = format!("Pixel book '{}' details:\n{}", 
+                                filename, serde_json::to_string_pretty(&body).unwrap_or_else(|_| "{}".to_string()));
+                            if let Some(kind) = thumbnails {
+                                match serde_json::from_value::<PixelBook>(body) {
+                                    Ok(book) => message.push_str(&frame_overview(&book, kind)),
+                                    Err(e) => message.push_str(&format!("\n\nFailed to build thumbnails: {}", e)),
+                                }
+                            }
+                            message
+                        }
                         Err(e) => format!("Failed to parse response: {}", e)
                     }
                 } else {
@@ -290,7 +393,211 @@ impl PixlMcpServer {
         Text(message)
     }
 
-    /// Draw a single pixel at specified coordinates with a given color
+    /// Show one frame as text, one character per pixel with a legend mapping characters to colors
+    /// and '.' for transparent pixels. Row y of the text is row y of the frame. Frames wider or
+    /// taller than max_size (default 64) are shrunk to fit, so characters then cover several pixels.
+    async fn preview_frame(&self, filename: String, frame: usize, max_size: Option<u16>) -> Text<String> {
+        let book = match self.fetch_book(&filename, Some(&frame.to_string())).await {
+            Ok(book) => book,
+            Err(e) => return Text(e),
+        };
+        if book.frames.is_empty() {
+            return Text(format!("Frame {} does not exist in '{}'", frame, filename));
+        }
+
+        let minimaps = ascii_minimaps(&book, max_size.unwrap_or(PREVIEW_SIZE));
+        let map = &minimaps.frames[0];
+        let (columns, rows) = (map.lines().next().map_or(0, |line| line.len()), map.lines().count());
+        let mut message = format!("Frame {} of '{}' ({}x{}", frame, filename, book.width, book.height);
+        if (columns, rows) != (book.width as usize, book.height as usize) {
+            message.push_str(&format!(", shrunk to {}x{}", columns, rows));
+        }
+        message.push_str(&format!("):\n{}", map));
+        message.push_str(&ascii_legend(&minimaps.legend));
+        Text(message)
+    }
+
+    /// Read back the color of one pixel, to check what is actually on the canvas
+    async fn get_pixel(&self, filename: String, frame: usize, x: u16, y: u16) -> Text<String> {
+        let message = match self.client
+            .get(format!("{}/books/{}/frames/{}/pixels/{}/{}", self.server_url, filename_segment(&filename), frame, x, y))
+            .send()
+            .await
+        {
+            Ok(response) => {
+                if response.status().is_success() {
+                    match response.json::<PixelColor>().await {
+                        Ok(pixel) if pixel.color[3] == 0 => format!("Pixel ({}, {}) of frame {} is transparent", x, y, frame),
+                        Ok(pixel) => {
+                            let [r, g, b, a] = pixel.color;
+                            format!("Pixel ({}, {}) of frame {} is {} (rgba {}, {}, {}, {})", x, y, frame, pixel.hex, r, g, b, a)
+                        }
+                        Err(e) => format!("Failed to parse response: {}", e)
+                    }
+                } else {
+                    let status = response.status();
+                    match response.text().await {
+                        Ok(error_text) => format!("Failed to get pixel: {}", error_text),
+                        Err(_) => format!("Failed to get pixel: HTTP {}", status)
+                    }
+                }
+            },
+            Err(e) => format!("Failed to connect to PIXL server: {}", e)
+        };
+        Text(message)
+    }
+
+    /// Read back a whole frame. The default "rle" encoding gives one line per row of hex colors
+    /// ("." for transparent), with runs written as count*color, e.g. "3*#ff0000 5*.".
+    /// "base64" gives the raw RGBA bytes instead.
+    async fn get_frame(&self, filename: String, frame: usize, encoding: Option<String>) -> Text<String> {
+        let encoding = match encoding.map(|e| e.to_lowercase()).as_deref() {
+            None | Some("rle") => FrameEncoding::Rle,
+            Some("base64") => FrameEncoding::Base64,
+            _ => return Text("Invalid encoding. Use 'rle' or 'base64'".to_string()),
+        };
+
+        let message = match self.client
+            .get(format!("{}/books/{}/frames/{}", self.server_url, filename_segment(&filename), frame))
+            .query(&[("encoding", encoding)])
+            .send()
+            .await
+        {
+            Ok(response) => {
+                if response.status().is_success() {
+                    match response.json::<EncodedFrame>().await {
+                        Ok(encoded) => {
+                            let duration = encoded.duration_ms.map(|ms| format!(", {}ms", ms)).unwrap_or_default();
+                            format!("Frame {} of '{}' ({}x{}{}):\n{}", frame, filename, encoded.width, encoded.height, duration, encoded.data)
+                        }
+                        Err(e) => format!("Failed to parse response: {}", e)
+                    }
+                } else {
+                    let status = response.status();
+                    match response.text().await {
+                        Ok(error_text) => format!("Failed to get frame: {}", error_text),
+                        Err(_) => format!("Failed to get frame: HTTP {}", status)
+                    }
+                }
+            },
+            Err(e) => format!("Failed to connect to PIXL server: {}", e)
+        };
+        Text(message)
+    }
+
+    /// Inspect a rectangle of one frame: its dominant colors, edge density (how busy it is),
+    /// and how closely it matches its own left-right and top-bottom mirror images.
+    /// Useful for checking and fixing a specific area after drawing it.
+    async fn critique_region(
+        &self,
+        filename: String,
+        frame: usize,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+    ) -> Text<String> {
+        let book = match self.fetch_book(&filename, Some(&frame.to_string())).await {
+            Ok(book) => book,
+            Err(message) => return Text(message),
+        };
+        
+        // Only the requested frame was fetched
+        let rect = Rect { x, y, width, height };
+        let message = match critique_region(&book, 0, &rect) {
+            Some(mut critique) => {
+                critique.frame = frame;
+                format!("Critique of frame {} of '{}':\n{}", frame, filename,
+                    serde_json::to_string_pretty(&critique).unwrap_or_else(|_| "{}".to_string()))
+            }
+            None if book.frames.is_empty() => format!("Frame {} does not exist in '{}'", frame, filename),
+            None => format!("Region at ({}, {}) is outside the {}x{} canvas", x, y, book.width, book.height),
+        };
+        Text(message)
+    }
+
+    /// Count the colors of one frame: how many pixels are visible, the bounding box of the
+    /// content, the number of distinct colors, and a histogram of the most used ones (optional
+    /// top, default 16). Useful for verifying an edit and for staying within a color budget.
+    async fn frame_stats(&self, filename: String, frame: usize, top: Option<usize>) -> Text<String> {
+        let message = match self.client
+            .get(format!("{}/books/{}/frames/{}/stats", self.server_url, filename_segment(&filename), frame))
+            .query(&[("top", top.unwrap_or(STATS_TOP_COLORS))])
+            .send()
+            .await
+        {
+            Ok(response) => {
+                if response.status().is_success() {
+                    match response.json::<FrameStats>().await {
+                        Ok(stats) => format!("Statistics of frame {} of '{}':\n{}", frame, filename,
+                            serde_json::to_string_pretty(&stats).unwrap_or_else(|_| "{}".to_string())),
+                        Err(e) => format!("Failed to parse response: {}", e)
+                    }
+                } else {
+                    let status = response.status();
+                    match response.text().await {
+                        Ok(error_text) => format!("Failed to get frame statistics: {}", error_text),
+                        Err(_) => format!("Failed to get frame statistics: HTTP {}", status)
+                    }
+                }
+            },
+            Err(e) => format!("Failed to connect to PIXL server: {}", e)
+        };
+        Text(message)
+    }
+
+    /// Render one frame as a PNG image, to visually check what was just drawn.
+    /// Optional scale (1-32, default 8) draws each pixel as a scale x scale block so small sprites
+    /// are easy to see; large frames may need a smaller scale.
+    async fn render_frame(&self, filename: String, frame: usize, scale: Option<u32>) -> Result<Image<Vec<u8>>, String> {
+        let response = self.client
+            .get(format!("{}/books/{}/export/png", self.server_url, filename_segment(&filename)))
+            .query(&[("frame", frame as u32), ("scale", scale.unwrap_or(RENDER_SCALE))])
+            .send()
+            .await
+            .map_err(|e| format!("Failed to connect to PIXL server: {}", e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            return Err(match response.text().await {
+                Ok(error_text) => format!("Failed to render frame {} of '{}': {}", frame, filename, error_text),
+                Err(_) => format!("Failed to render frame {} of '{}': HTTP {}", frame, filename, status),
+            });
+        }
+        let bytes = response.bytes().await
+            .map_err(|e| format!("Failed to read PNG: {}", e))?;
+        Ok(Image::new(bytes.to_vec(), "image/png"))
+    }
+
+    /// Render every frame of a pixel book as a looping animated GIF and return it as an image,
+    /// to review an animation cycle after drawing it. Optional delay_ms sets the time between
+    /// frames (default 100); alternatively pass fps for a frame rate.
+    async fn preview_animation(&self, filename: String, delay_ms: Option<u32>, fps: Option<u32>) -> Result<Image<Vec<u8>>, String> {
+        let mut request = self.client.get(format!("{}/books/{}/export/gif", self.server_url, filename_segment(&filename)));
+        if let Some(delay_ms) = delay_ms {
+            request = request.query(&[("delay_ms", delay_ms)]);
+        }
+        if let Some(fps) = fps {
+            request = request.query(&[("fps", fps)]);
+        }
+        
+        let response = request.send().await
+            .map_err(|e| format!("Failed to connect to PIXL server: {}", e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            return Err(match response.text().await {
+                Ok(error_text) => format!("Failed to export '{}' as GIF: {}", filename, error_text),
+                Err(_) => format!("Failed to export '{}' as GIF: HTTP {}", filename, status),
+            });
+        }
+        let bytes = response.bytes().await
+            .map_err(|e| format!("Failed to read GIF: {}", e))?;
+        Ok(Image::new(bytes.to_vec(), "image/gif"))
+    }
+
+    /// Draw a single pixel at specified coordinates with a given color.
+    /// Optional blend_mode (replace, alpha, add, multiply) combines the color with the pixels already there.
+    /// Optional symmetry (none, horizontal, vertical, quad) mirrors the pixel about the canvas centre.
+    #[allow(clippy::too_many_arguments)]
     async fn draw_pixel(
         &self,
         filename: String,
@@ -301,15 +608,22 @@ impl PixlMcpServer {
         g: u8,
         b: u8,
         a: u8,
+        symmetry: Option<String>,
+        blend_mode: Option<String>,
     ) -> Text<String> {
+        let blend_mode = match parse_blend_mode(blend_mode) {
+            Ok(mode) => mode,
+            Err(e) => return Text(e),
+        };
         let operation = DrawingOperation::DrawPixel {
             frame,
             x,
             y,
-            color: [r, g, b, a],
+            color: [r, g, b, a].into(),
+            blend_mode,
         };
         
-        self.apply_operations(filename, vec![operation]).await
+        self.apply_with_symmetry(filename, vec![operation], symmetry).await
     }
 
     /// Set the current drawing color (for tools that use current color)
@@ -322,13 +636,20 @@ impl PixlMcpServer {
         a: u8,
     ) -> Text<String> {
         let operation = DrawingOperation::SetColor {
-            color: [r, g, b, a],
+            color: [r, g, b, a].into(),
         };
         
         self.apply_operations(filename, vec![operation]).await
     }
 
-    /// Draw a line between two points
+    /// Draw a line between two points.
+    /// Curved lines bend towards optional control points: control_x1/control_y1 for a quadratic curve,
+    /// plus control_x2/control_y2 for a cubic one.
+    /// Optional style: "aliased" (default), "pixel_perfect" to drop the doubled pixel at each stair step,
+    /// or "anti_aliased" to blend the edges into the pixels underneath.
+    /// Optional blend_mode (replace, alpha, add, multiply) combines the color with the pixels already there.
+    /// Optional symmetry (none, horizontal, vertical, quad) mirrors the line about the canvas centre.
+    #[allow(clippy::too_many_arguments)]
     async fn draw_line(
         &self,
         filename: String,
@@ -342,25 +663,57 @@ impl PixlMcpServer {
         g: u8,
         b: u8,
         a: u8,
+        symmetry: Option<String>,
+        control_x1: Option<u16>,
+        control_y1: Option<u16>,
+        control_x2: Option<u16>,
+        control_y2: Option<u16>,
+        style: Option<String>,
+        blend_mode: Option<String>,
     ) -> Text<String> {
+        let blend_mode = match parse_blend_mode(blend_mode) {
+            Ok(mode) => mode,
+            Err(e) => return Text(e),
+        };
         let line_type = match line_type.to_lowercase().as_str() {
             "straight" => LineType::Straight,
             "curved" => LineType::Curved,
             _ => return Text("Invalid line type. Use 'straight' or 'curved'".to_string()),
         };
+        let style = match style.map(|style| style.to_lowercase()).as_deref() {
+            None | Some("aliased") => LineStyle::Aliased,
+            Some("pixel_perfect") => LineStyle::PixelPerfect,
+            Some("anti_aliased") => LineStyle::AntiAliased,
+            _ => return Text("Invalid style. Use 'aliased', 'pixel_perfect' or 'anti_aliased'".to_string()),
+        };
+        
+        let mut control_points = Vec::new();
+        for (x, y) in [(control_x1, control_y1), (control_x2, control_y2)] {
+            match (x, y) {
+                (Some(x), Some(y)) => control_points.push(Point { x, y }),
+                (None, None) => {}
+                _ => return Text("Control points need both an x and a y coordinate".to_string()),
+            }
+        }
         
         let operation = DrawingOperation::DrawLine {
             frame,
             start: Point { x: start_x, y: start_y },
             end: Point { x: end_x, y: end_y },
             line_type,
-            color: [r, g, b, a],
+            color: [r, g, b, a].into(),
+            control_points,
+            style,
+            blend_mode,
         };
         
-        self.apply_operations(filename, vec![operation]).await
+        self.apply_with_symmetry(filename, vec![operation], symmetry).await
     }
 
-    /// Draw a shape (rectangle, circle, oval, or triangle)
+    /// Draw a shape (rectangle, circle, oval, or triangle).
+    /// Optional blend_mode (replace, alpha, add, multiply) combines the color with the pixels already there.
+    /// Optional symmetry (none, horizontal, vertical, quad) mirrors the shape about the canvas centre.
+    #[allow(clippy::too_many_arguments)]
     async fn draw_shape(
         &self,
         filename: String,
@@ -375,7 +728,13 @@ impl PixlMcpServer {
         g: u8,
         b: u8,
         a: u8,
+        symmetry: Option<String>,
+        blend_mode: Option<String>,
     ) -> Text<String> {
+        let blend_mode = match parse_blend_mode(blend_mode) {
+            Ok(mode) => mode,
+            Err(e) => return Text(e),
+        };
         let shape = match shape_type.to_lowercase().as_str() {
             "rectangle" => ShapeType::Rectangle,
             "circle" => ShapeType::Circle,
@@ -390,13 +749,55 @@ impl PixlMcpServer {
             position: Point { x, y },
             size: Size { width, height },
             filled,
-            color: [r, g, b, a],
+            color: [r, g, b, a].into(),
+            blend_mode,
         };
         
-        self.apply_operations(filename, vec![operation]).await
+        self.apply_with_symmetry(filename, vec![operation], symmetry).await
+    }
+
+    /// Draw a circle by its centre pixel and radius; it spans 2 * radius + 1 pixels across.
+    /// Prefer this over draw_shape for circles, which takes a bounding box instead.
+    /// Optional blend_mode (replace, alpha, add, multiply) combines the color with the pixels already there.
+    /// Optional symmetry (none, horizontal, vertical, quad) mirrors the circle about the canvas centre.
+    #[allow(clippy::too_many_arguments)]
+    async fn draw_circle(
+        &self,
+        filename: String,
+        frame: usize,
+        center_x: u16,
+        center_y: u16,
+        radius: u16,
+        filled: bool,
+        r: u8,
+        g: u8,
+        b: u8,
+        a: u8,
+        symmetry: Option<String>,
+        blend_mode: Option<String>,
+    ) -> Text<String> {
+        let blend_mode = match parse_blend_mode(blend_mode) {
+            Ok(mode) => mode,
+            Err(e) => return Text(e),
+        };
+        let operation = DrawingOperation::DrawCircle {
+            frame,
+            center: Point { x: center_x, y: center_y },
+            radius,
+            filled,
+            color: [r, g, b, a].into(),
+            blend_mode,
+        };
+        
+        self.apply_with_symmetry(filename, vec![operation], symmetry).await
     }
 
-    /// Draw a polygon from a list of points
+    /// Draw a polygon from a list of points.
+    /// Optional fill_rule decides what is inside a self-intersecting filled polygon: "even_odd" (default) leaves
+    /// the middle of a star empty, "non_zero" fills it.
+    /// Optional blend_mode (replace, alpha, add, multiply) combines the color with the pixels already there.
+    /// Optional symmetry (none, horizontal, vertical, quad) mirrors the polygon about the canvas centre.
+    #[allow(clippy::too_many_arguments)]
     async fn draw_polygon(
         &self,
         filename: String,
@@ -407,7 +808,14 @@ impl PixlMcpServer {
         g: u8,
         b: u8,
         a: u8,
+        fill_rule: Option<String>,
+        symmetry: Option<String>,
+        blend_mode: Option<String>,
     ) -> Text<String> {
+        let blend_mode = match parse_blend_mode(blend_mode) {
+            Ok(mode) => mode,
+            Err(e) => return Text(e),
+        };
         let points: Vec<Point> = match serde_json::from_str(&points_json) {
             Ok(points) => points,
             Err(e) => return Text(format!("Invalid points JSON: {}. Expected format: [{{\"x\": 10, \"y\": 20}}, ...]", e))
@@ -416,18 +824,30 @@ impl PixlMcpServer {
         if points.len() < 3 {
             return Text("Polygon must have at least 3 points".to_string());
         }
+        let fill_rule = match fill_rule.map(|rule| rule.to_lowercase()).as_deref() {
+            None | Some("even_odd") => FillRule::EvenOdd,
+            Some("non_zero") => FillRule::NonZero,
+            _ => return Text("Invalid fill_rule. Use 'even_odd' or 'non_zero'".to_string()),
+        };
         
         let operation = DrawingOperation::DrawPolygon {
             frame,
             points,
             filled,
-            color: [r, g, b, a],
+            color: [r, g, b, a].into(),
+            fill_rule,
+            blend_mode,
         };
         
-        self.apply_operations(filename, vec![operation]).await
+        self.apply_with_symmetry(filename, vec![operation], symmetry).await
     }
 
-    /// Fill an area starting from the specified point with the given color (flood fill)
+    /// Fill an area starting from the specified point with the given color (flood fill).
+    /// Set contiguous to false to replace every pixel of the clicked color in the frame instead
+    /// of only the connected area (default true).
+    /// Optional blend_mode (replace, alpha, add, multiply) combines the color with the pixels already there.
+    /// Optional symmetry (none, horizontal, vertical, quad) mirrors the filled region about the canvas centre.
+    #[allow(clippy::too_many_arguments)]
     async fn fill_area(
         &self,
         filename: String,
@@ -438,65 +858,857 @@ impl PixlMcpServer {
         g: u8,
         b: u8,
         a: u8,
+        contiguous: Option<bool>,
+        symmetry: Option<String>,
+        blend_mode: Option<String>,
     ) -> Text<String> {
+        let blend_mode = match parse_blend_mode(blend_mode) {
+            Ok(mode) => mode,
+            Err(e) => return Text(e),
+        };
         let operation = DrawingOperation::FillArea {
             frame,
             x,
             y,
-            color: [r, g, b, a],
+            color: [r, g, b, a].into(),
+            contiguous: contiguous.unwrap_or(true),
+            bounds: None,
+            blend_mode,
+        };
+        
+        self.apply_with_symmetry(filename, vec![operation], symmetry).await
+    }
+
+    /// Copy a rectangular region of one frame to a position in another (or the same) frame.
+    /// Useful for carrying unchanged parts of a sprite across animation frames exactly.
+    #[allow(clippy::too_many_arguments)]
+    async fn copy_region(
+        &self,
+        filename: String,
+        src_frame: usize,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        dst_frame: usize,
+        dst_x: u16,
+        dst_y: u16,
+    ) -> Text<String> {
+        let operation = DrawingOperation::CopyRegion {
+            src_frame,
+            src_rect: Rect { x, y, width, height },
+            dst_frame,
+            dst_point: Point { x: dst_x, y: dst_y },
         };
         
         self.apply_operations(filename, vec![operation]).await
     }
 
-    /// Apply multiple drawing operations in a single batch
-    async fn batch_operations(
+    /// Move a rectangular region of one frame to a position in another (or the same) frame,
+    /// leaving the source rectangle transparent.
+    #[allow(clippy::too_many_arguments)]
+    async fn cut_region(
         &self,
         filename: String,
-        operations_json: String,
+        src_frame: usize,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        dst_frame: usize,
+        dst_x: u16,
+        dst_y: u16,
     ) -> Text<String> {
-        let operations: Vec<DrawingOperation> = match serde_json::from_str(&operations_json) {
-            Ok(operations) => operations,
-            Err(e) => return Text(format!("Invalid operations JSON: {}", e))
+        let operation = DrawingOperation::CutRegion {
+            src_frame,
+            src_rect: Rect { x, y, width, height },
+            dst_frame,
+            dst_point: Point { x: dst_x, y: dst_y },
         };
         
-        self.apply_operations(filename, operations).await
+        self.apply_operations(filename, vec![operation]).await
     }
 
-    /// Helper method to apply operations to a pixel book
-    async fn apply_operations(
+    /// Fill a rectangle with an ordered (Bayer) dither shading from one color to another.
+    /// Direction is "horizontal" (left to right, the default), "vertical" (top to bottom), or "radial" (centre out).
+    /// Optional blend_mode (replace, alpha, add, multiply) combines the color with the pixels already there.
+    /// Optional symmetry (none, horizontal, vertical, quad) mirrors the fill about the canvas centre.
+    #[allow(clippy::too_many_arguments)]
+    async fn dither_gradient(
         &self,
         filename: String,
-        operations: Vec<DrawingOperation>,
+        frame: usize,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        from_r: u8,
+        from_g: u8,
+        from_b: u8,
+        from_a: u8,
+        to_r: u8,
+        to_g: u8,
+        to_b: u8,
+        to_a: u8,
+        direction: Option<String>,
+        symmetry: Option<String>,
+        blend_mode: Option<String>,
     ) -> Text<String> {
-        let request = UpdatePixelBookRequest { operations: operations.clone() };
+        let blend_mode = match parse_blend_mode(blend_mode) {
+            Ok(mode) => mode,
+            Err(e) => return Text(e),
+        };
+        let direction = match parse_gradient_direction(direction) {
+            Ok(direction) => direction,
+            Err(message) => return Text(message),
+        };
         
-        let message = match self.client
-            .put(&format!("{}/books/{}", self.server_url, filename))
-            .json(&request)
-            .send()
-            .await 
-        {
-            Ok(response) => {
-                if response.status().is_success() {
-                    match response.json::<serde_json::Value>().await {
-                        Ok(body) => format!("Applied {} operation(s) to '{}': {}", 
-                            operations.len(), filename,
-                            serde_json::to_string_pretty(&body).unwrap_or_else(|_| "{}".to_string())),
-                        Err(e) => format!("Applied {} operation(s) to '{}' but failed to parse response: {}", 
-                            operations.len(), filename, e)
-                    }
-                } else {
-                    let status = response.status();
-                    match response.text().await {
-                        Ok(error_text) => format!("Failed to apply operations to '{}': {}", filename, error_text),
-                        Err(_) => format!("Failed to apply operations to '{}': HTTP {}", filename, status)
-                    }
-                }
-            },
-            Err(e) => format!("Failed to connect to PIXL server: {}", e)
+        let operation = DrawingOperation::DitherGradient {
+            frame,
+            rect: Rect { x, y, width, height },
+            from: [from_r, from_g, from_b, from_a].into(),
+            to: [to_r, to_g, to_b, to_a].into(),
+            direction,
+            blend_mode,
         };
-        Text(message)
+        
+        self.apply_with_symmetry(filename, vec![operation], symmetry).await
+    }
+
+    /// Fill a rectangle, or the region of one color around a seed pixel, with a dithered two-color gradient.
+    /// Give x/y/width/height for a rectangle, seed_x/seed_y for a flood region, or both to keep the flood
+    /// inside the rectangle. Direction is "horizontal" (default), "vertical", or "radial" (centre out);
+    /// dither is "ordered" (Bayer, default) or "error_diffusion" (Floyd-Steinberg).
+    /// Optional blend_mode (replace, alpha, add, multiply) combines the color with the pixels already there.
+    /// Optional symmetry (none, horizontal, vertical, quad) mirrors the fill about the canvas centre.
+    #[allow(clippy::too_many_arguments)]
+    async fn gradient_fill(
+        &self,
+        filename: String,
+        frame: usize,
+        from_r: u8,
+        from_g: u8,
+        from_b: u8,
+        from_a: u8,
+        to_r: u8,
+        to_g: u8,
+        to_b: u8,
+        to_a: u8,
+        x: Option<u16>,
+        y: Option<u16>,
+        width: Option<u16>,
+        height: Option<u16>,
+        seed_x: Option<u16>,
+        seed_y: Option<u16>,
+        direction: Option<String>,
+        dither: Option<String>,
+        symmetry: Option<String>,
+        blend_mode: Option<String>,
+    ) -> Text<String> {
+        let blend_mode = match parse_blend_mode(blend_mode) {
+            Ok(mode) => mode,
+            Err(e) => return Text(e),
+        };
+        let direction = match parse_gradient_direction(direction) {
+            Ok(direction) => direction,
+            Err(message) => return Text(message),
+        };
+        let dither = match dither.map(|d| d.to_lowercase()).as_deref() {
+            None | Some("ordered") => DitherPattern::Ordered,
+            Some("error_diffusion") => DitherPattern::ErrorDiffusion,
+            _ => return Text("Invalid dither. Use 'ordered' or 'error_diffusion'".to_string()),
+        };
+        let rect = match (x, y, width, height) {
+            (Some(x), Some(y), Some(width), Some(height)) => Some(Rect { x, y, width, height }),
+            (None, None, None, None) => None,
+            _ => return Text("A rectangle needs all of x, y, width, and height".to_string()),
+        };
+        let seed = match (seed_x, seed_y) {
+            (Some(x), Some(y)) => Some(Point { x, y }),
+            (None, None) => None,
+            _ => return Text("A seed needs both seed_x and seed_y".to_string()),
+        };
+        if rect.is_none() && seed.is_none() {
+            return Text("Give a rectangle (x, y, width, height), a seed (seed_x, seed_y), or both".to_string());
+        }
+        
+        let operation = DrawingOperation::GradientFill {
+            frame,
+            rect,
+            seed,
+            from: [from_r, from_g, from_b, from_a].into(),
+            to: [to_r, to_g, to_b, to_a].into(),
+            direction,
+            dither,
+            blend_mode,
+        };
+        
+        self.apply_with_symmetry(filename, vec![operation], symmetry).await
+    }
+
+    /// Speckle a rectangle with random pixels from a list of colors, for grass, stone, or dirt textures.
+    /// colors_json is a JSON array of RGBA arrays or palette indices, e.g. [[60, 140, 60, 255], [40, 110, 40, 255]].
+    /// density (0 to 1) is the share of pixels painted; the rest are left alone. Pass the same seed to
+    /// repeat a pattern; without one the server picks a seed and records it in the book's operation log.
+    /// Optional blend_mode (replace, alpha, add, multiply) combines the color with the pixels already there.
+    /// Optional symmetry (none, horizontal, vertical, quad) mirrors the fill about the canvas centre.
+    #[allow(clippy::too_many_arguments)]
+    async fn noise_fill(
+        &self,
+        filename: String,
+        frame: usize,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        colors_json: String,
+        density: f32,
+        seed: Option<u64>,
+        symmetry: Option<String>,
+        blend_mode: Option<String>,
+    ) -> Text<String> {
+        let blend_mode = match parse_blend_mode(blend_mode) {
+            Ok(mode) => mode,
+            Err(e) => return Text(e),
+        };
+        let colors: Vec<ColorRef> = match serde_json::from_str(&colors_json) {
+            Ok(colors) => colors,
+            Err(e) => return Text(format!("Invalid colors JSON: {}. Expected format: [[r, g, b, a], ...]", e)),
+        };
+        if colors.is_empty() {
+            return Text("Give at least one color".to_string());
+        }
+        if !(0.0..=1.0).contains(&density) {
+            return Text("Density must be between 0 and 1".to_string());
+        }
+
+        let operation = DrawingOperation::NoiseFill {
+            frame,
+            rect: Rect { x, y, width, height },
+            colors,
+            density,
+            seed,
+            blend_mode,
+        };
+
+        self.apply_with_symmetry(filename, vec![operation], symmetry).await
+    }
+
+    /// Exchange two colors: every pixel of color a becomes color b and vice versa, in one frame
+    /// or in every frame when frame is omitted. Handy for trying alternate palette assignments.
+    #[allow(clippy::too_many_arguments)]
+    async fn swap_colors(
+        &self,
+        filename: String,
+        frame: Option<usize>,
+        a_r: u8,
+        a_g: u8,
+        a_b: u8,
+        a_a: u8,
+        b_r: u8,
+        b_g: u8,
+        b_b: u8,
+        b_a: u8,
+    ) -> Text<String> {
+        let operation = DrawingOperation::SwapColors {
+            frame,
+            a: [a_r, a_g, a_b, a_a].into(),
+            b: [b_r, b_g, b_b, b_a].into(),
+        };
+        
+        self.apply_operations(filename, vec![operation]).await
+    }
+
+    /// Erase a single pixel, making it fully transparent.
+    /// Optional symmetry (none, horizontal, vertical, quad) mirrors the erase about the canvas centre.
+    async fn erase_pixel(
+        &self,
+        filename: String,
+        frame: usize,
+        x: u16,
+        y: u16,
+        symmetry: Option<String>,
+    ) -> Text<String> {
+        let operation = DrawingOperation::ErasePixel { frame, x, y };
+        
+        self.apply_with_symmetry(filename, vec![operation], symmetry).await
+    }
+
+    /// Erase a rectangle, making every pixel in it fully transparent. The rectangle is clipped to the canvas.
+    /// Optional symmetry (none, horizontal, vertical, quad) mirrors the erase about the canvas centre.
+    #[allow(clippy::too_many_arguments)]
+    async fn erase_area(
+        &self,
+        filename: String,
+        frame: usize,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        symmetry: Option<String>,
+    ) -> Text<String> {
+        let operation = DrawingOperation::EraseArea {
+            frame,
+            rect: Rect { x, y, width, height },
+        };
+        
+        self.apply_with_symmetry(filename, vec![operation], symmetry).await
+    }
+
+    /// Clear a whole frame, making every pixel fully transparent.
+    async fn clear_frame(
+        &self,
+        filename: String,
+        frame: usize,
+    ) -> Text<String> {
+        let operation = DrawingOperation::ClearFrame { frame };
+        
+        self.apply_operations(filename, vec![operation]).await
+    }
+
+    /// Flip a whole frame. Axis is "horizontal" (mirror left and right) or "vertical" (swap top and bottom).
+    async fn flip_frame(
+        &self,
+        filename: String,
+        frame: usize,
+        axis: String,
+    ) -> Text<String> {
+        let axis = match axis.to_lowercase().as_str() {
+            "horizontal" => FlipAxis::Horizontal,
+            "vertical" => FlipAxis::Vertical,
+            _ => return Text("Invalid axis. Use 'horizontal' or 'vertical'".to_string()),
+        };
+        
+        let operation = DrawingOperation::FlipFrame { frame, axis };
+        
+        self.apply_operations(filename, vec![operation]).await
+    }
+
+    /// Rotate a whole frame clockwise by 90, 180, or 270 degrees. 90 and 270 need a square canvas.
+    async fn rotate_frame(
+        &self,
+        filename: String,
+        frame: usize,
+        degrees: u16,
+    ) -> Text<String> {
+        let operation = DrawingOperation::RotateFrame { frame, degrees };
+        
+        self.apply_operations(filename, vec![operation]).await
+    }
+
+    /// Move a whole frame by dx, dy pixels (negative values move left or up).
+    /// With wrap, pixels pushed off one edge come back on the opposite edge; otherwise they are dropped.
+    async fn shift_frame(
+        &self,
+        filename: String,
+        frame: usize,
+        dx: i32,
+        dy: i32,
+        wrap: Option<bool>,
+    ) -> Text<String> {
+        let operation = DrawingOperation::ShiftFrame { frame, dx, dy, wrap: wrap.unwrap_or(false) };
+        
+        self.apply_operations(filename, vec![operation]).await
+    }
+
+    /// Paste a small bitmap with its top-left corner at x, y, for tile composition or reusing motifs.
+    /// source_json is either {"kind": "frame", "frame": 0, "book": "tiles.pxl", "rect": {"x": 0, "y": 0, "width": 8, "height": 8}}
+    /// (book and rect optional; book defaults to this one) or {"kind": "inline", "width": 2, "height": 2, "data": "<base64 RGBA>"}.
+    /// Optional flip is "horizontal" or "vertical". Transparent stamp pixels leave the canvas alone unless skip_transparent is false.
+    /// Optional blend_mode (replace, alpha, add, multiply) combines the color with the pixels already there.
+    #[allow(clippy::too_many_arguments)]
+    async fn draw_stamp(
+        &self,
+        filename: String,
+        frame: usize,
+        x: u16,
+        y: u16,
+        source_json: String,
+        flip: Option<String>,
+        skip_transparent: Option<bool>,
+        blend_mode: Option<String>,
+    ) -> Text<String> {
+        let blend_mode = match parse_blend_mode(blend_mode) {
+            Ok(mode) => mode,
+            Err(e) => return Text(e),
+        };
+        let source: StampSource = match serde_json::from_str(&source_json) {
+            Ok(source) => source,
+            Err(e) => return Text(format!("Invalid source JSON: {}", e))
+        };
+        let flip = match flip.map(|axis| axis.to_lowercase()).as_deref() {
+            None => None,
+            Some("horizontal") => Some(FlipAxis::Horizontal),
+            Some("vertical") => Some(FlipAxis::Vertical),
+            _ => return Text("Invalid flip. Use 'horizontal' or 'vertical'".to_string()),
+        };
+
+        let operation = DrawingOperation::DrawStamp {
+            frame,
+            position: Point { x, y },
+            source,
+            flip,
+            skip_transparent: skip_transparent.unwrap_or(true),
+            blend_mode,
+        };
+
+        self.apply_operations(filename, vec![operation]).await
+    }
+
+    /// Apply multiple drawing operations in a single batch.
+    /// Any color in an operation may be a palette index (e.g. "color": 3) instead of an [r, g, b, a] array.
+    /// Optional symmetry (none, horizontal, vertical, quad) mirrors every operation about the canvas centre.
+    /// By default one failing operation stops the whole batch; set continue_on_error to apply the rest anyway.
+    async fn batch_operations(
+        &self,
+        filename: String,
+        operations_json: String,
+        symmetry: Option<String>,
+        continue_on_error: Option<bool>,
+    ) -> Text<String> {
+        let operations: Vec<DrawingOperation> = match serde_json::from_str(&operations_json) {
+            Ok(operations) => operations,
+            Err(e) => return Text(format!("Invalid operations JSON: {}", e))
+        };
+        
+        match self.symmetry_for(symmetry) {
+            Ok(symmetry) => self.send_operations(filename, operations, symmetry, continue_on_error.unwrap_or(false)).await,
+            Err(message) => Text(message),
+        }
+    }
+
+    /// Set the symmetry (none, horizontal, vertical, quad) every later drawing tool uses on this book
+    /// when called without its own symmetry argument. The server keeps it, so it also applies to other
+    /// clients drawing on the book and lasts until changed.
+    async fn set_symmetry(&self, filename: String, symmetry: String) -> Text<String> {
+        let symmetry = match parse_symmetry(Some(symmetry)) {
+            Ok(symmetry) => symmetry,
+            Err(message) => return Text(message),
+        };
+
+        let message = match self.client
+            .put(format!("{}/books/{}/symmetry", self.server_url, filename_segment(&filename)))
+            .json(&SetSymmetryRequest { symmetry })
+            .send()
+            .await
+        {
+            Ok(response) if response.status().is_success() => if symmetry == Symmetry::None {
+                format!("Drawing on '{}' is no longer mirrored", filename)
+            } else {
+                format!("Drawing on '{}' is now mirrored with {} symmetry", filename, format!("{:?}", symmetry).to_lowercase())
+            },
+            Ok(response) => {
+                let status = response.status();
+                match response.text().await {
+                    Ok(error_text) => format!("Failed to set symmetry on '{}': {}", filename, error_text),
+                    Err(_) => format!("Failed to set symmetry on '{}': HTTP {}", filename, status)
+                }
+            },
+            Err(e) => format!("Failed to connect to PIXL server: {}", e)
+        };
+        Text(message)
+    }
+
+    /// Restrict every later drawing tool on this book to part of one frame, so fills and shapes cannot spill over.
+    /// selection_json is {"kind": "rect", "rect": {"x": 0, "y": 0, "width": 8, "height": 8}},
+    /// {"kind": "polygon", "points": [{"x": 0, "y": 0}, ...]}, or {"kind": "magic_wand", "x": 3, "y": 4} to select
+    /// the area a fill from that pixel would cover (add "contiguous": false for every pixel of that color).
+    /// The server keeps the selection, so it also applies to other clients drawing on the book.
+    async fn set_selection(&self, filename: String, frame: usize, selection_json: String) -> Text<String> {
+        let selection: Selection = match serde_json::from_str(&selection_json) {
+            Ok(selection) => selection,
+            Err(e) => return Text(format!("Invalid selection JSON: {}", e))
+        };
+
+        let message = match self.client
+            .put(format!("{}/books/{}/selection", self.server_url, filename_segment(&filename)))
+            .json(&ActiveSelection { frame, selection })
+            .send()
+            .await
+        {
+            Ok(response) if response.status().is_success() =>
+                format!("Drawing on '{}' is now limited to the selection on frame {}", filename, frame),
+            Ok(response) => {
+                let status = response.status();
+                match response.text().await {
+                    Ok(error_text) => format!("Failed to set selection on '{}': {}", filename, error_text),
+                    Err(_) => format!("Failed to set selection on '{}': HTTP {}", filename, status)
+                }
+            },
+            Err(e) => format!("Failed to connect to PIXL server: {}", e)
+        };
+        Text(message)
+    }
+
+    /// Let drawing tools change any pixel of the book again
+    async fn clear_selection(&self, filename: String) -> Text<String> {
+        let message = match self.client
+            .delete(format!("{}/books/{}/selection", self.server_url, filename_segment(&filename)))
+            .send()
+            .await
+        {
+            Ok(response) if response.status().is_success() => format!("Cleared the selection on '{}'", filename),
+            Ok(response) => {
+                let status = response.status();
+                match response.text().await {
+                    Ok(error_text) => format!("Failed to clear the selection on '{}': {}", filename, error_text),
+                    Err(_) => format!("Failed to clear the selection on '{}': HTTP {}", filename, status)
+                }
+            },
+            Err(e) => format!("Failed to connect to PIXL server: {}", e)
+        };
+        Text(message)
+    }
+
+    /// Check a batch of drawing operations against a pixel book without applying them.
+    /// Reports every operation that would fail (out of bounds, unknown frame, bad palette index) so a
+    /// large batch can be fixed before it is sent with batch_operations.
+    async fn validate_operations(
+        &self,
+        filename: String,
+        operations_json: String,
+        symmetry: Option<String>,
+    ) -> Text<String> {
+        let operations: Vec<DrawingOperation> = match serde_json::from_str(&operations_json) {
+            Ok(operations) => operations,
+            Err(e) => return Text(format!("Invalid operations JSON: {}", e))
+        };
+        let symmetry = match self.symmetry_for(symmetry) {
+            Ok(symmetry) => symmetry,
+            Err(message) => return Text(message),
+        };
+        let request = UpdatePixelBookRequest { operations, symmetry };
+
+        let message = match self.client
+            .put(format!("{}/books/{}", self.server_url, filename_segment(&filename)))
+            .query(&[("dry_run", "true")])
+            .json(&request)
+            .send()
+            .await
+        {
+            Ok(response) if response.status().is_success() => match response.json::<ValidationReport>().await {
+                Ok(report) if report.valid => format!("All {} operation(s) can be applied to '{}'", report.operations, filename),
+                Ok(report) => format!("{} of {} operation(s) would fail on '{}': {}",
+                    report.errors.len(), report.operations, filename,
+                    serde_json::to_string_pretty(&report.errors).unwrap_or_else(|_| "[]".to_string())),
+                Err(e) => format!("Failed to parse validation report: {}", e)
+            },
+            Ok(response) => {
+                let status = response.status();
+                match response.text().await {
+                    Ok(error_text) => format!("Failed to validate operations for '{}': {}", filename, error_text),
+                    Err(_) => format!("Failed to validate operations for '{}': HTTP {}", filename, status)
+                }
+            },
+            Err(e) => format!("Failed to connect to PIXL server: {}", e)
+        };
+        Text(message)
+    }
+
+    /// Apply operations to several pixel books at once; either every book is saved or none is.
+    /// books_json is a JSON array of {"filename": "...", "operations": [...], "symmetry": "none"} objects.
+    async fn batch_update(&self, books_json: String) -> Text<String> {
+        let books: Vec<BatchBookUpdate> = match serde_json::from_str(&books_json) {
+            Ok(books) => books,
+            Err(e) => return Text(format!("Invalid books JSON: {}", e))
+        };
+        let request = BatchRequest { books };
+
+        let message = match self.client
+            .post(format!("{}/batch", self.server_url))
+            .json(&request)
+            .send()
+            .await
+        {
+            Ok(response) => {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                // Failed batches still report per-book errors; other refusals are plain text
+                match serde_json::from_str::<BatchResult>(&body) {
+                    Ok(result) => {
+                        let summary = serde_json::to_string_pretty(&result.books).unwrap_or_else(|_| "[]".to_string());
+                        if result.success {
+                            format!("Updated {} book(s): {}", result.books.len(), summary)
+                        } else {
+                            format!("Batch failed and no book was changed: {}", summary)
+                        }
+                    }
+                    Err(_) if body.is_empty() => format!("Failed to apply batch: HTTP {}", status),
+                    Err(_) => format!("Failed to apply batch: {}", body)
+                }
+            },
+            Err(e) => format!("Failed to connect to PIXL server: {}", e)
+        };
+        Text(message)
+    }
+
+    /// Get the named palette stored with a pixel book, listing each color with its index
+    async fn get_palette(&self, filename: String) -> Text<String> {
+        let message = match self.client
+            .get(format!("{}/books/{}/palette", self.server_url, filename_segment(&filename)))
+            .send()
+            .await
+        {
+            Ok(response) => {
+                if response.status().is_success() {
+                    match response.json::<Palette>().await {
+                        Ok(palette) => {
+                            let colors: Vec<String> = palette.colors.iter().enumerate()
+                                .map(|(i, c)| format!("  {}: [{}, {}, {}, {}]", i, c[0], c[1], c[2], c[3]))
+                                .collect();
+                            format!("Palette '{}' of '{}' ({} colors):\n{}", palette.name, filename, colors.len(), colors.join("\n"))
+                        }
+                        Err(e) => format!("Failed to parse palette: {}", e)
+                    }
+                } else {
+                    let status = response.status();
+                    match response.text().await {
+                        Ok(error_text) => format!("Failed to get palette: {}", error_text),
+                        Err(_) => format!("Failed to get palette: HTTP {}", status)
+                    }
+                }
+            },
+            Err(e) => format!("Failed to connect to PIXL server: {}", e)
+        };
+        Text(message)
+    }
+
+    /// Store a named palette of up to 256 colors with a pixel book. colors_json is a JSON array
+    /// of [r, g, b, a] arrays; drawing operations can then use a color's index instead of its RGBA value.
+    async fn set_palette(&self, filename: String, name: String, colors_json: String) -> Text<String> {
+        let colors: Vec<[u8; 4]> = match serde_json::from_str(&colors_json) {
+            Ok(colors) => colors,
+            Err(e) => return Text(format!("Invalid colors JSON: {}", e))
+        };
+        let palette = Palette { name, colors };
+
+        let message = match self.client
+            .put(format!("{}/books/{}/palette", self.server_url, filename_segment(&filename)))
+            .json(&palette)
+            .send()
+            .await
+        {
+            Ok(response) => {
+                if response.status().is_success() {
+                    format!("Set palette '{}' ({} colors) on '{}'", palette.name, palette.colors.len(), filename)
+                } else {
+                    let status = response.status();
+                    match response.text().await {
+                        Ok(error_text) => format!("Failed to set palette: {}", error_text),
+                        Err(_) => format!("Failed to set palette: HTTP {}", status)
+                    }
+                }
+            },
+            Err(e) => format!("Failed to connect to PIXL server: {}", e)
+        };
+        Text(message)
+    }
+
+    /// Set how many milliseconds one frame of an animation shows for, so holds and quick
+    /// in-betweens can differ. Omit duration_ms to fall back to the playback frame rate.
+    async fn set_frame_duration(&self, filename: String, frame: usize, duration_ms: Option<u32>) -> Text<String> {
+        let message = match self.client
+            .put(format!("{}/books/{}/frames/{}/duration", self.server_url, filename_segment(&filename), frame))
+            .json(&FrameDuration { duration_ms })
+            .send()
+            .await
+        {
+            Ok(response) => {
+                if response.status().is_success() {
+                    match duration_ms {
+                        Some(ms) => format!("Frame {} of '{}' now shows for {}ms", frame, filename, ms),
+                        None => format!("Frame {} of '{}' now uses the default frame rate", frame, filename),
+                    }
+                } else {
+                    let status = response.status();
+                    match response.text().await {
+                        Ok(error_text) => format!("Failed to set frame duration: {}", error_text),
+                        Err(_) => format!("Failed to set frame duration: HTTP {}", status)
+                    }
+                }
+            },
+            Err(e) => format!("Failed to connect to PIXL server: {}", e)
+        };
+        Text(message)
+    }
+
+    /// Revert the most recent batch of drawing operations applied to a pixel book, one
+    /// tool call's worth at a time. Fails if the book was changed some other way since.
+    async fn undo(&self, filename: String) -> Text<String> {
+        self.step_history(filename, "undo").await
+    }
+
+    /// Reapply the most recently undone batch of drawing operations. Drawing anything
+    /// after an undo discards what could be redone.
+    async fn redo(&self, filename: String) -> Text<String> {
+        self.step_history(filename, "redo").await
+    }
+
+    /// Helper method to apply operations to a pixel book
+    async fn apply_operations(
+        &self,
+        filename: String,
+        operations: Vec<DrawingOperation>,
+    ) -> Text<String> {
+        self.send_operations(filename, operations, Some(Symmetry::None), false).await
+    }
+}
+
+impl PixlMcpServer {
+    /// Validate a symmetry argument and apply the operations with that mirror mode
+    async fn apply_with_symmetry(
+        &self,
+        filename: String,
+        operations: Vec<DrawingOperation>,
+        symmetry: Option<String>,
+    ) -> Text<String> {
+        match self.symmetry_for(symmetry) {
+            Ok(symmetry) => self.send_operations(filename, operations, symmetry, false).await,
+            Err(message) => Text(message),
+        }
+    }
+
+    /// The symmetry a tool argument asks for; without one the server uses
+    /// the book's, as set with `set_symmetry`
+    fn symmetry_for(&self, symmetry: Option<String>) -> Result<Option<Symmetry>, String> {
+        match symmetry {
+            Some(_) => parse_symmetry(symmetry).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Fetches a book, or only the frames in `frames` (such as "2" or "0..4")
+    async fn fetch_book(&self, filename: &str, frames: Option<&str>) -> Result<PixelBook, String> {
+        let mut request = self.client.get(format!("{}/books/{}", self.server_url, filename_segment(filename)));
+        if let Some(frames) = frames {
+            request = request.query(&[("frames", frames)]);
+        }
+        
+        let response = request.send().await
+            .map_err(|e| format!("Failed to connect to PIXL server: {}", e))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            return Err(match response.text().await {
+                Ok(error_text) => format!("Failed to get book '{}': {}", filename, error_text),
+                Err(_) => format!("Failed to get book '{}': HTTP {}", filename, status),
+            });
+        }
+        response.json::<PixelBook>().await
+            .map_err(|e| format!("Failed to parse response: {}", e))
+    }
+
+    /// Posts to the book's `copy` or `rename` endpoint
+    async fn move_book(&self, filename: String, new_filename: String, action: &str) -> Text<String> {
+        let request = RenameBookRequest { new_filename: new_filename.clone() };
+        let message = match self.client
+            .post(format!("{}/books/{}/{}", self.server_url, filename_segment(&filename), action))
+            .json(&request)
+            .send()
+            .await
+        {
+            Ok(response) => {
+                if response.status().is_success() {
+                    format!("Applied {} of '{}' to '{}'", action, filename, new_filename)
+                } else {
+                    let status = response.status();
+                    match response.text().await {
+                        Ok(error_text) => format!("Failed to {} '{}': {}", action, filename, error_text),
+                        Err(_) => format!("Failed to {} '{}': HTTP {}", action, filename, status)
+                    }
+                }
+            },
+            Err(e) => format!("Failed to connect to PIXL server: {}", e)
+        };
+        Text(message)
+    }
+
+    /// Posts to the book's `undo` or `redo` endpoint
+    async fn step_history(&self, filename: String, action: &str) -> Text<String> {
+        let message = match self.client
+            .post(format!("{}/books/{}/{}", self.server_url, filename_segment(&filename), action))
+            .send()
+            .await
+        {
+            Ok(response) => {
+                if response.status().is_success() {
+                    match response.json::<serde_json::Value>().await {
+                        Ok(body) => format!("Applied {} to '{}': {}", action, filename,
+                            serde_json::to_string_pretty(&body).unwrap_or_else(|_| "{}".to_string())),
+                        Err(e) => format!("Applied {} to '{}' but failed to parse response: {}", action, filename, e)
+                    }
+                } else {
+                    let status = response.status();
+                    match response.text().await {
+                        Ok(error_text) => format!("Failed to {} '{}': {}", action, filename, error_text),
+                        Err(_) => format!("Failed to {} '{}': HTTP {}", action, filename, status)
+                    }
+                }
+            },
+            Err(e) => format!("Failed to connect to PIXL server: {}", e)
+        };
+        Text(message)
+    }
+
+    async fn send_operations(
+        &self,
+        filename: String,
+        operations: Vec<DrawingOperation>,
+        symmetry: Option<Symmetry>,
+        continue_on_error: bool,
+    ) -> Text<String> {
+        let request = UpdatePixelBookRequest { operations: operations.clone(), symmetry };
+        let mut builder = self.client.put(format!("{}/books/{}", self.server_url, filename_segment(&filename)));
+        if continue_on_error {
+            builder = builder.query(&[("continue_on_error", "true")]);
+        }
+        
+        let message = match builder
+            .json(&request)
+            .send()
+            .await 
+        {
+            Ok(response) => {
+                if response.status().is_success() {
+                    match response.json::<serde_json::Value>().await {
+                        Ok(body) => format!("Applied {} of {} operation(s) to '{}': {}", 
+                            body["operations_applied"].as_u64().unwrap_or(0), operations.len(), filename,
+                            serde_json::to_string_pretty(&body).unwrap_or_else(|_| "{}".to_string())),
+                        Err(e) => format!("Applied {} operation(s) to '{}' but failed to parse response: {}", 
+                            operations.len(), filename, e)
+                    }
+                } else {
+                    let status = response.status();
+                    match response.text().await {
+                        Ok(error_text) => format!("Failed to apply operations to '{}': {}", filename, error_text),
+                        Err(_) => format!("Failed to apply operations to '{}': HTTP {}", filename, status)
+                    }
+                }
+            },
+            Err(e) => format!("Failed to connect to PIXL server: {}", e)
+        };
+        Text(message)
+    }
+}
+
+// Reads a blend_mode tool argument, defaulting to replace
+fn parse_blend_mode(blend_mode: Option<String>) -> Result<BlendMode, String> {
+    match blend_mode.map(|mode| mode.to_lowercase()).as_deref() {
+        None | Some("replace") => Ok(BlendMode::Replace),
+        Some("alpha") => Ok(BlendMode::Alpha),
+        Some("add") => Ok(BlendMode::Add),
+        Some("multiply") => Ok(BlendMode::Multiply),
+        _ => Err("Invalid blend_mode. Use 'replace', 'alpha', 'add', or 'multiply'".to_string()),
+    }
+}
+
+// Reads a symmetry tool argument, defaulting to none
+fn parse_symmetry(symmetry: Option<String>) -> Result<Symmetry, String> {
+    match symmetry.map(|s| s.to_lowercase()).as_deref() {
+        None | Some("none") => Ok(Symmetry::None),
+        Some("horizontal") => Ok(Symmetry::Horizontal),
+        Some("vertical") => Ok(Symmetry::Vertical),
+        Some("quad") => Ok(Symmetry::Quad),
+        _ => Err("Invalid symmetry. Use 'none', 'horizontal', 'vertical', or 'quad'".to_string()),
     }
 }
 
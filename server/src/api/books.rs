@@ -1,32 +1,128 @@
-use crate::models::{PixelBook, PixelBookInfo, CreatePixelBookRequest, UpdatePixelBookRequest, PixelError};
-use crate::services::{FileService, DrawingService, EventService};
-use crate::utils::validation;
-use poem::{handler, web::{Json, Path}, Result, Error, http::StatusCode};
+use crate::models::{ResizeRequest, color_grid, frame_stats, FrameStats, region_pixels, BatchBookResult, DrawingOperation, OperationStatus, Rect, StampSource, BatchBookUpdate, BatchRequest, BatchResult, PixelBook, diff_frames, hex_color, run_length_text, BookChunk, BookFilter, BookList, ColorGrid, DiffQuery, EncodedFrame, Frame, FrameComparison, FrameDuration, FrameEncoding, FrameRange, PixelColor, BookDetails, OperationLogEntry, Palette, PixelError, Tileset, TilesetInfo, CreatePixelBookRequest, ImportUrlRequest, MigrateRequest, QuantizeRequest, RenameBookRequest, UpdateDetailsRequest, UpdatePixelBookRequest, SetPermissionsRequest};
+use crate::api::context::{drawing_service, load_context};
+use crate::api::locks::check_lock;
+use crate::services::{filename_from_url, random_seed, FileService, EventService, ImportService, LockService, OperationLogService, SnapshotService, UndoService, UndoStep, REQUEST_ID_HEADER};
+use crate::utils::{permissions, validation};
+use base64::Engine;
+use poem::{handler, web::{Json, Path, Query}, Body, IntoResponse, Request, Response, Result, Error};
 use serde_json::json;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{mpsc, RwLock};
+use tokio_stream::wrappers::ReceiverStream;
 
-#[derive(serde::Serialize)]
-struct BooksResponse {
-    books: Vec<PixelBookInfo>,
+/// How `PUT /books/:filename` treats `If-Match`
+#[derive(Debug, Clone, Default)]
+pub struct RevisionSettings {
+    /// Refuse updates that do not name the revision they were made against
+    pub require_if_match: bool,
+}
+
+/// A book revision as an ETag
+pub fn etag(revision: u64) -> String {
+    format!("\"{}\"", revision)
+}
+
+// Refuses an update whose `If-Match` does not name the book's current
+// revision, or that has none when one is required
+fn check_revision(req: &Request, filename: &str, revision: u64, settings: &RevisionSettings) -> Result<()> {
+    let Some(if_match) = req.header(poem::http::header::IF_MATCH) else {
+        if settings.require_if_match {
+            return Err(Error::from_string(
+                format!("Updating {} requires an If-Match header with the ETag it was read at", filename),
+                poem::http::StatusCode::PRECONDITION_REQUIRED,
+            ));
+        }
+        return Ok(());
+    };
+
+    let current = etag(revision);
+    let matches = if_match.split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == current);
+    if !matches {
+        println!("⚔️ Rejected stale update of {}: {} is not {}", filename, if_match, current);
+        return Err(Error::from_response(Response::builder()
+            .status(poem::http::StatusCode::PRECONDITION_FAILED)
+            .header(poem::http::header::ETAG, current)
+            .body(format!("{} has changed since it was read; it is now at revision {}", filename, revision))));
+    }
+    Ok(())
+}
+
+fn owner_key(req: &Request) -> Option<&str> {
+    req.header(permissions::OWNER_KEY_HEADER)
+}
+
+fn permission_error(e: PixelError) -> Error {
+    Error::from_string(e.to_string(), poem::http::StatusCode::FORBIDDEN)
+}
+
+// A book's operation log line for `operations`, attributed to the sender of `req`
+fn log_entry(req: &Request, operations: Vec<String>) -> OperationLogEntry {
+    OperationLogEntry {
+        timestamp: chrono::Utc::now(),
+        origin: req.header(poem::http::header::USER_AGENT).map(str::to_string),
+        request_id: req.header(REQUEST_ID_HEADER).map(str::to_string),
+        operations,
+    }
 }
 
 #[handler]
 pub async fn list_books(
     file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
-) -> Result<Json<BooksResponse>> {
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    Query(filter): Query<BookFilter>,
+) -> Result<Json<BookList>> {
     let service = file_service.read().await;
-    let books = service.list_books()
+    let books = service.list_books(filter.recursive)
         .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    let mut list = filter.apply(books);
+    for book in &mut list.books {
+        book.lock = lock_service.current(&book.filename);
+    }
     
-    Ok(Json(BooksResponse { books }))
+    Ok(Json(list))
+}
+
+/// Response header with the book's total frame count, which a frame range may not show
+pub const FRAME_COUNT_HEADER: &str = "X-Pixl-Frame-Count";
+
+/// Frames sent ahead of a slow `GET /books/:filename/stream` client
+const STREAM_BUFFER_FRAMES: usize = 4;
+
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct BookQuery {
+    /// Frame range such as `0..10`, `5..` or `3`; every frame when omitted
+    #[serde(default)]
+    pub frames: Option<String>,
+}
+
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct UpdateQuery {
+    /// Check the operations against the book and report what would fail, without saving
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Apply the operations that succeed and skip the ones that fail, instead of applying none
+    #[serde(default)]
+    pub continue_on_error: bool,
+}
+
+impl BookQuery {
+    fn frame_range(&self) -> Result<FrameRange> {
+        match &self.frames {
+            Some(frames) => frames.parse()
+                .map_err(|e: String| Error::from_string(e, poem::http::StatusCode::BAD_REQUEST)),
+            None => Ok(FrameRange { start: 0, end: None }),
+        }
+    }
 }
 
 #[handler]
 pub async fn get_book(
     file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
     filename: Path<String>,
-) -> Result<Json<PixelBook>> {
+    Query(query): Query<BookQuery>,
+) -> Result<Response> {
     let service = file_service.read().await;
     
     if !validation::validate_filename(&filename) {
@@ -36,19 +132,268 @@ pub async fn get_book(
         ));
     }
     
-    let book = service.load_book(&filename)
+    let range = query.frame_range()?;
+    let (book, frame_count) = service.load_frames(&filename, &range)
         .map_err(|e| match e {
             crate::models::PixelError::FileNotFound { .. } => 
                 Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
             _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
         })?;
     
-    Ok(Json(book))
+    let revision = etag(book.revision);
+    Ok(Json(book)
+        .with_header(FRAME_COUNT_HEADER, frame_count.to_string())
+        .with_header(poem::http::header::ETAG, revision)
+        .into_response())
+}
+
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+pub struct GridQuery {
+    /// Downsample to fit in this many cells across and down
+    max_size: Option<u16>,
+}
+
+/// One frame as rows of hex color strings, which LLM clients read far more
+/// easily than packed RGBA bytes
+#[handler]
+pub async fn frame_grid(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    Path((filename, index)): Path<(String, usize)>,
+    Query(query): Query<GridQuery>,
+) -> Result<Json<ColorGrid>> {
+    let (width, height, frame) = load_frame(&file_service, &filename, index).await?;
+    Ok(Json(color_grid(&frame, index, width, height, query.max_size)))
+}
+
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+pub struct StatsQuery {
+    /// Keep only this many of the most used colors in the histogram
+    top: Option<usize>,
+}
+
+/// Color histogram, visible pixel count and content bounds of one frame
+#[handler]
+pub async fn get_frame_stats(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    Path((filename, index)): Path<(String, usize)>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<FrameStats>> {
+    let (width, height, frame) = load_frame(&file_service, &filename, index).await?;
+    Ok(Json(frame_stats(&frame, index, width, height, query.top)))
+}
+
+/// Loads frame `index` of a book along with the book's dimensions
+async fn load_frame(file_service: &RwLock<FileService>, filename: &str, index: usize) -> Result<(u16, u16, Frame)> {
+    if !validation::validate_filename(filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let range = FrameRange::single(index).ok_or_else(|| Error::from_string(
+        format!("Frame {} is out of range", index),
+        poem::http::StatusCode::BAD_REQUEST,
+    ))?;
+    let (book, frame_count) = file_service.read().await.load_frames(filename, &range)
+        .map_err(|e| match e {
+            PixelError::FileNotFound { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+    let (width, height) = (book.width, book.height);
+    let frame = book.frames.into_iter().next().ok_or_else(|| Error::from_string(
+        format!("Frame {} does not exist; {} has {} frames", index, filename, frame_count),
+        poem::http::StatusCode::NOT_FOUND,
+    ))?;
+    Ok((width, height, frame))
+}
+
+/// The color of one pixel, so clients can check what is on the canvas
+#[handler]
+pub async fn get_pixel(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    Path((filename, index, x, y)): Path<(String, usize, u16, u16)>,
+) -> Result<Json<PixelColor>> {
+    let (width, height, frame) = load_frame(&file_service, &filename, index).await?;
+    let pixel = frame.get_pixel(x, y, width).filter(|_| x < width && y < height).ok_or_else(|| Error::from_string(
+        PixelError::InvalidCoordinates { x, y, width, height }.to_string(),
+        poem::http::StatusCode::BAD_REQUEST,
+    ))?;
+
+    Ok(Json(PixelColor { frame: index, x, y, color: [pixel.r, pixel.g, pixel.b, pixel.a], hex: hex_color(pixel) }))
+}
+
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+pub struct FrameQuery {
+    #[serde(default)]
+    encoding: FrameEncoding,
+}
+
+/// One frame as run-length text or base64 RGBA, far smaller than the
+/// book's JSON
+#[handler]
+pub async fn get_frame(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    Path((filename, index)): Path<(String, usize)>,
+    Query(query): Query<FrameQuery>,
+) -> Result<Json<EncodedFrame>> {
+    let (width, height, frame) = load_frame(&file_service, &filename, index).await?;
+    let data = match query.encoding {
+        FrameEncoding::Rle => run_length_text(&frame, width, height),
+        FrameEncoding::Base64 => base64::engine::general_purpose::STANDARD.encode(&frame.pixels),
+    };
+
+    Ok(Json(EncodedFrame { frame: index, width, height, encoding: query.encoding, data, duration_ms: frame.duration_ms }))
+}
+
+/// Dimensions and one frame of the live book, or of one of its snapshots
+async fn diff_side(
+    file_service: &RwLock<FileService>,
+    snapshot_service: &SnapshotService,
+    filename: &str,
+    snapshot: Option<i64>,
+    index: usize,
+) -> Result<(u16, u16, Frame)> {
+    let not_found = |e: PixelError| match e {
+        PixelError::FileNotFound { .. } =>
+            Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+        _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    let range = FrameRange::single(index).ok_or_else(|| Error::from_string(
+        format!("Frame {} is out of range", index),
+        poem::http::StatusCode::BAD_REQUEST,
+    ))?;
+    let service = file_service.read().await;
+    let (book, frame_count) = match snapshot {
+        Some(id) => {
+            let book = snapshot_service.load_snapshot(service.get_path(), filename, id).map_err(not_found)?;
+            let frame_count = book.frames.len();
+            (book, frame_count)
+        }
+        None => service.load_frames(filename, &range).map_err(not_found)?,
+    };
+    let (width, height) = (book.width, book.height);
+    let frame = match snapshot {
+        Some(_) => book.frames.into_iter().nth(index),
+        None => book.frames.into_iter().next(),
+    };
+    let frame = frame.ok_or_else(|| Error::from_string(
+        format!("Frame {} does not exist; {} has {} frames", index, filename, frame_count),
+        poem::http::StatusCode::NOT_FOUND,
+    ))?;
+    Ok((width, height, frame))
+}
+
+/// Pixels that differ between two frames, of the live book or its snapshots
+#[handler]
+pub async fn diff_book(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    snapshot_service: poem::web::Data<&Arc<SnapshotService>>,
+    filename: Path<String>,
+    Query(query): Query<DiffQuery>,
+) -> Result<Json<FrameComparison>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let frame_b = query.frame_b.unwrap_or(query.frame_a);
+    let (width, height, a) = diff_side(&file_service, &snapshot_service, &filename, query.snapshot_a, query.frame_a).await?;
+    let (width_b, height_b, b) = diff_side(&file_service, &snapshot_service, &filename, query.snapshot_b, frame_b).await?;
+    if (width, height) != (width_b, height_b) {
+        return Err(Error::from_string(
+            format!("Cannot compare a {}x{} frame with a {}x{} one", width, height, width_b, height_b),
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let diff = diff_frames(&a, &b, width, height);
+    Ok(Json(FrameComparison {
+        frame_a: query.frame_a,
+        frame_b,
+        snapshot_a: query.snapshot_a,
+        snapshot_b: query.snapshot_b,
+        changed: diff.changed_count(),
+        diff,
+    }))
+}
+
+/// The book as NDJSON: a `book` line, then one `frame` line per frame as it
+/// is read, so clients can show the first frames before the rest arrive
+#[handler]
+pub async fn stream_book(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    filename: Path<String>,
+    Query(query): Query<BookQuery>,
+) -> Result<Response> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+    
+    let range = query.frame_range()?;
+    let mut reader = file_service.read().await.open_book(&filename)
+        .map_err(|e| match e {
+            PixelError::FileNotFound { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+    
+    let frame_count = reader.frame_count();
+    let header = BookChunk::Book {
+        filename: filename.to_string(),
+        width: reader.width(),
+        height: reader.height(),
+        frame_count,
+        metadata: Box::new(reader.metadata().clone()),
+    };
+    let indices = range.resolve(frame_count).unwrap_or(0..0);
+    
+    // Frames are read on a blocking thread, at most a few ahead of the client
+    let (tx, rx) = mpsc::channel::<std::io::Result<Vec<u8>>>(STREAM_BUFFER_FRAMES);
+    tokio::task::spawn_blocking(move || {
+        let send = |chunk: &BookChunk| {
+            let mut line = serde_json::to_vec(chunk).map_err(std::io::Error::other);
+            if let Ok(line) = &mut line {
+                line.push(b'\n');
+            }
+            tx.blocking_send(line).is_ok()
+        };
+        
+        if !send(&header) {
+            return;
+        }
+        for index in indices {
+            match reader.read_frame(index) {
+                Ok(frame) => {
+                    if !send(&BookChunk::Frame(frame)) {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    send(&BookChunk::Error { message: e.to_string() });
+                    return;
+                }
+            }
+        }
+    });
+    
+    Ok(Response::builder()
+        .content_type("application/x-ndjson")
+        .header(FRAME_COUNT_HEADER, frame_count.to_string())
+        .body(Body::from_bytes_stream(ReceiverStream::new(rx))))
 }
 
 #[handler]
 pub async fn create_book(
+    req: &Request,
     file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
     request: Json<CreatePixelBookRequest>,
 ) -> Result<Json<serde_json::Value>> {
     if !validation::validate_filename(&request.filename) {
@@ -72,9 +417,23 @@ pub async fn create_book(
         ));
     }
     
-    let service = file_service.read().await;
-    let book = service.create_book(&request.filename, request.width, request.height, request.frames)
+    let service = file_service.write().await;
+    
+    // Creating over an existing book replaces it, so it needs write access
+    let existing = service.load_metadata(&request.filename)
         .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    if let Some(metadata) = existing {
+        permissions::check_write_access(&request.filename, &metadata.permissions, owner_key(req))
+            .map_err(permission_error)?;
+    }
+    check_lock(&lock_service, &request.filename, req)?;
+    
+    let book = service.create_book(&request.filename, request.width, request.height, request.frames)
+        .map_err(|e| match e {
+            crate::models::PixelError::InvalidFormat { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::BAD_REQUEST),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
     
     let full_path = service.get_path().join(&request.filename);
     
@@ -86,12 +445,105 @@ pub async fn create_book(
 }
 
 #[handler]
+pub async fn import_url(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    import_service: poem::web::Data<&Arc<ImportService>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    request: Json<ImportUrlRequest>,
+) -> Result<Json<serde_json::Value>> {
+    let url = import_service.check_url(&request.url).map_err(import_error)?;
+    let filename = match &request.filename {
+        Some(filename) => filename.clone(),
+        None => filename_from_url(&url).ok_or_else(|| Error::from_string(
+            "Could not derive a filename from the URL; pass one explicitly",
+            poem::http::StatusCode::BAD_REQUEST,
+        ))?,
+    };
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+    
+    // Check access before downloading anything
+    {
+        let service = file_service.read().await;
+        check_import_target(&service, &filename, request.overwrite, req)?;
+    }
+    check_lock(&lock_service, &filename, req)?;
+    
+    println!("🌐 Importing {} from {}", filename, url);
+    let bytes = import_service.download(url.as_str()).await.map_err(import_error)?;
+    let decode_filename = filename.clone();
+    let book = tokio::task::spawn_blocking(move || ImportService::decode(&decode_filename, &bytes))
+        .await
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?
+        .map_err(import_error)?;
+    
+    let service = file_service.write().await;
+    // The book may have appeared while downloading
+    check_import_target(&service, &filename, request.overwrite, req)?;
+    service.import_book(&book).map_err(import_error)?;
+    let full_path = service.get_path().join(&filename);
+    drop(service);
+    
+    event_service.read().await.on_book_saved(&filename).await;
+    
+    Ok(Json(json!({
+        "success": true,
+        "filename": book.filename,
+        "path": full_path.to_string_lossy(),
+        "width": book.width,
+        "height": book.height,
+        "frames": book.frames.len()
+    })))
+}
+
+// An import only replaces an existing book when asked to, and the caller can write to it
+fn check_import_target(service: &FileService, filename: &str, overwrite: bool, req: &Request) -> Result<()> {
+    let existing = service.load_metadata(filename)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    if let Some(metadata) = existing {
+        if !overwrite {
+            return Err(Error::from_string(
+                format!("Book '{}' already exists; set overwrite to replace it", filename),
+                poem::http::StatusCode::CONFLICT,
+            ));
+        }
+        permissions::check_write_access(filename, &metadata.permissions, owner_key(req))
+            .map_err(permission_error)?;
+    }
+    Ok(())
+}
+
+fn import_error(e: PixelError) -> Error {
+    let status = match e {
+        PixelError::InvalidUrl { .. } | PixelError::InvalidFormat { .. } => poem::http::StatusCode::BAD_REQUEST,
+        PixelError::HostNotAllowed { .. } => poem::http::StatusCode::FORBIDDEN,
+        PixelError::DownloadTooLarge { .. } => poem::http::StatusCode::PAYLOAD_TOO_LARGE,
+        PixelError::DownloadFailed { .. } => poem::http::StatusCode::BAD_GATEWAY,
+        _ => poem::http::StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    println!("❌ Import failed: {}", e);
+    Error::from_string(e.to_string(), status)
+}
+
+#[handler]
+#[allow(clippy::too_many_arguments)]
 pub async fn update_book(
+    req: &Request,
     file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
     event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    undo_service: poem::web::Data<&Arc<UndoService>>,
+    revision_settings: poem::web::Data<&Arc<RevisionSettings>>,
     filename: Path<String>,
-    request: Json<UpdatePixelBookRequest>,
-) -> Result<Json<serde_json::Value>> {
+    Query(query): Query<UpdateQuery>,
+    mut request: Json<UpdatePixelBookRequest>,
+) -> Result<Response> {
     println!("🚨 UPDATE_BOOK called for: {} with {} operations", filename.as_str(), request.operations.len());
     
     if !validation::validate_filename(&filename) {
@@ -111,40 +563,1151 @@ pub async fn update_book(
             _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
         })?;
 
-    // Apply drawing operations
-    println!("🎨 Applying {} drawing operations...", request.operations.len());
-    let drawing_service = DrawingService::new();
-    drawing_service.apply_operations(&mut book, request.operations.clone())
+    permissions::check_write_access(&filename, &book.metadata.permissions, owner_key(req))
         .map_err(|e| {
-            println!("❌ Drawing operation failed: {}", e);
-            Error::from_string(e.to_string(), poem::http::StatusCode::BAD_REQUEST)
+            println!("🔒 Rejected update: {}", e);
+            permission_error(e)
         })?;
+    check_lock(&lock_service, &filename, req)?;
+    check_revision(req, &filename, book.revision, &revision_settings)?;
+    resolve_stamps(&service, &filename, &mut request.operations)?;
+    seed_noise(&mut request.operations);
+
+    let context = load_context(&service, &filename, req)?;
+    let drawing_service = drawing_service(&context, request.symmetry, &book)?;
+    if query.dry_run {
+        let report = drawing_service.validate_operations(&book, &request.operations);
+        println!("🧪 Dry run of {} operations: {} would fail", request.operations.len(), report.errors.len());
+        return Ok(Json(report).into_response());
+    }
+
+    // Apply drawing operations
+    println!("🎨 Applying {} drawing operations...", request.operations.len());
+    let before = book.frames.clone();
+    let results = drawing_service.apply_each(&mut book, &request.operations, query.continue_on_error);
+    let applied: Vec<_> = results.iter()
+        .filter(|result| result.status == OperationStatus::Applied)
+        .map(|result| request.operations[result.index].clone())
+        .collect();
+    if applied.len() < request.operations.len() {
+        println!("❌ {} of {} drawing operations failed", request.operations.len() - applied.len(), request.operations.len());
+        if applied.is_empty() {
+            return Err(Error::from_response(Json(json!({
+                "success": false,
+                "operations_applied": 0,
+                "filename": filename.to_string(),
+                "results": results
+            })).with_status(poem::http::StatusCode::BAD_REQUEST).into_response()));
+        }
+    }
 
     // Save the updated book
     println!("💾 Saving pixel book to disk...");
-    service.save_book(&book)
+    let revision = service.save_book(&book)
         .map_err(|e| {
             println!("❌ Save failed: {}", e);
             Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR)
         })?;
     println!("✅ Book saved successfully!");
 
-    // Emit events for each drawing operation
-    let event_svc = event_service.read().await;
-    for operation in &request.operations {
-        println!("🎨 Emitting drawing operation event for: {}", filename.as_str());
-        event_svc.on_drawing_operation(&filename, operation.clone()).await;
+    record_update(req, &service, &undo_service, &event_service, &filename, &before, &book, &applied).await;
+
+    Ok(Json(json!({
+        "success": true,
+        "operations_applied": applied.len(),
+        "filename": filename.to_string(),
+        "revision": revision,
+        "results": results
+    })).with_header(poem::http::header::ETAG, etag(revision)).into_response())
+}
+
+/// Applies operations to several books, saving every one of them or none
+#[handler]
+#[allow(clippy::too_many_arguments)]
+pub async fn batch_update(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    undo_service: poem::web::Data<&Arc<UndoService>>,
+    revision_settings: poem::web::Data<&Arc<RevisionSettings>>,
+    request: Json<BatchRequest>,
+) -> Result<Response> {
+    println!("📦 BATCH called for {} books", request.books.len());
+
+    if request.books.is_empty() {
+        return Err(Error::from_string("A batch needs at least one book", poem::http::StatusCode::BAD_REQUEST));
+    }
+    let mut seen = std::collections::HashSet::new();
+    if let Some(update) = request.books.iter().find(|update| !seen.insert(update.filename.as_str())) {
+        return Err(Error::from_string(
+            format!("{} appears more than once in the batch", update.filename),
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
     }
+
+    let service = file_service.write().await;
+
+    // Apply everything in memory first so a failure leaves every book untouched
+    let mut staged = Vec::new();
+    let mut results = Vec::new();
+    let mut failure = None;
+    for update in &request.books {
+        let (applied, error) = match stage_batch_update(req, &service, &lock_service, &revision_settings, update) {
+            Ok(books) => {
+                staged.push((update, books));
+                (update.operations.len(), None)
+            }
+            Err(e) => {
+                println!("❌ Batch update of {} failed: {}", update.filename, e);
+                failure.get_or_insert(e.status());
+                (0, Some(e.to_string()))
+            }
+        };
+        results.push(BatchBookResult { filename: update.filename.clone(), operations_applied: applied, revision: None, error });
+    }
+    if let Some(status) = failure {
+        return Ok(Json(BatchResult { success: false, books: results }).with_status(status).into_response());
+    }
+
+    println!("💾 Saving {} books...", staged.len());
+    // Books already saved are put back at their old revisions if a later one fails
+    let mut backups = Vec::new();
+    for index in 0..staged.len() {
+        let (_, (_, book, _)) = &staged[index];
+        let saved = service.backup_book(&book.filename).and_then(|backup| {
+            let revision = service.save_book(book)?;
+            backups.push(backup);
+            Ok(revision)
+        });
+        match saved {
+            Ok(revision) => results[index].revision = Some(revision),
+            Err(e) => {
+                println!("❌ Save failed, restoring the books already saved: {}", e);
+                for ((_, (original, _, _)), backup) in staged.iter().zip(&backups) {
+                    if let Err(e) = service.restore_backup(backup) {
+                        println!("⚠️ Could not restore {}: {}", original.filename, e);
+                    }
+                }
+                for result in &mut results {
+                    result.revision = None;
+                }
+                results[index].error = Some(e.to_string());
+                return Ok(Json(BatchResult { success: false, books: results })
+                    .with_status(poem::http::StatusCode::INTERNAL_SERVER_ERROR)
+                    .into_response());
+            }
+        }
+    }
+    println!("✅ Batch saved successfully!");
+
+    for (update, (original, book, operations)) in &staged {
+        record_update(req, &service, &undo_service, &event_service, &update.filename, &original.frames, book, operations).await;
+    }
+
+    Ok(Json(BatchResult { success: true, books: results }).into_response())
+}
+
+// Loads one book of a batch and applies its operations, returning the book
+// as it was and as it will be saved, and the operations with stamps resolved
+fn stage_batch_update(
+    req: &Request,
+    service: &FileService,
+    lock_service: &LockService,
+    settings: &RevisionSettings,
+    update: &BatchBookUpdate,
+) -> Result<(PixelBook, PixelBook, Vec<DrawingOperation>)> {
+    let filename = update.filename.as_str();
+    if !validation::validate_filename(filename) {
+        return Err(Error::from_string("Invalid filename", poem::http::StatusCode::BAD_REQUEST));
+    }
+
+    let original = service.load_book(filename)
+        .map_err(|e| match e {
+            PixelError::FileNotFound { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+    permissions::check_write_access(filename, &original.metadata.permissions, owner_key(req))
+        .map_err(permission_error)?;
+    check_lock(lock_service, filename, req)?;
+    match update.revision {
+        None if settings.require_if_match => return Err(Error::from_string(
+            format!("Updating {} requires the revision it was read at", filename),
+            poem::http::StatusCode::PRECONDITION_REQUIRED,
+        )),
+        Some(expected) if expected != original.revision => return Err(Error::from_string(
+            format!("{} has changed since it was read; it is now at revision {}", filename, original.revision),
+            poem::http::StatusCode::PRECONDITION_FAILED,
+        )),
+        _ => {}
+    }
+
+    let mut operations = update.operations.clone();
+    resolve_stamps(service, filename, &mut operations)?;
+    seed_noise(&mut operations);
+    let mut book = original.clone();
+    let context = load_context(service, filename, req)?;
+    drawing_service(&context, update.symmetry, &book)?
+        .apply_operations(&mut book, operations.clone())
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::BAD_REQUEST))?;
+    Ok((original, book, operations))
+}
+
+// Replaces stamps taken from other books with their pixels, so the drawing
+// service, undo history and viewers only ever see self-contained operations
+fn resolve_stamps(service: &FileService, filename: &str, operations: &mut [DrawingOperation]) -> Result<()> {
+    for operation in operations {
+        let DrawingOperation::DrawStamp { source, .. } = operation else { continue };
+        let StampSource::Frame { book: Some(other), frame, rect } = source.clone() else { continue };
+        if other == filename {
+            *source = StampSource::Frame { book: None, frame, rect };
+            continue;
+        }
+        if !validation::validate_filename(&other) {
+            return Err(Error::from_string(
+                format!("Invalid stamp filename: {}", other),
+                poem::http::StatusCode::BAD_REQUEST,
+            ));
+        }
+
+        let stamp_book = service.load_book(&other)
+            .map_err(|e| match e {
+                PixelError::FileNotFound { .. } =>
+                    Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+                _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+            })?;
+        let pixels = &stamp_book.frames.get(frame)
+            .ok_or_else(|| Error::from_string(
+                format!("{} has no frame {}", other, frame),
+                poem::http::StatusCode::BAD_REQUEST,
+            ))?
+            .pixels;
+        let whole = Rect { x: 0, y: 0, width: stamp_book.width, height: stamp_book.height };
+        let (width, height, data) = region_pixels(pixels, stamp_book.width, stamp_book.height, rect.as_ref().unwrap_or(&whole));
+        *source = StampSource::Inline { width, height, data: base64::engine::general_purpose::STANDARD.encode(data) };
+    }
+    Ok(())
+}
+
+// Picks a seed for noise fills without one, so the operation log and
+// viewers see the same speckles that were saved
+fn seed_noise(operations: &mut [DrawingOperation]) {
+    let base = random_seed();
+    for (index, operation) in operations.iter_mut().enumerate() {
+        if let DrawingOperation::NoiseFill { seed: seed @ None, .. } = operation {
+            // Fills in one request differ even if the clock hasn't moved
+            *seed = Some(base.wrapping_add(index as u64));
+        }
+    }
+}
+
+// Logs, records undo history for and announces operations that were just
+// saved to `book`, whose frames were `before` they were applied
+#[allow(clippy::too_many_arguments)]
+async fn record_update(
+    req: &Request,
+    service: &FileService,
+    undo_service: &UndoService,
+    event_service: &Arc<RwLock<EventService>>,
+    filename: &str,
+    before: &[Frame],
+    book: &PixelBook,
+    operations: &[crate::models::DrawingOperation],
+) {
+    let entry = log_entry(req, operations.iter().map(|op| op.summary()).collect());
+    if let Err(e) = OperationLogService::record(service.get_path(), filename, &entry) {
+        // The batch is already saved, so a missing log line is not worth failing over
+        println!("⚠️ Could not record operations for {}: {}", filename, e);
+    }
+
+    let region = record_undo_step(undo_service, filename, before, book);
+
+    // Emit the operations, coalesced with other recent ones when configured
+    let event_svc = event_service.read().await;
+    println!("🎨 Emitting drawing operation events for: {}", filename);
+    let origin = req.header(poem::http::header::USER_AGENT);
+    event_svc.on_operations_applied(filename, operations, region, origin).await;
     
     // Emit book saved event
-    println!("💾 Emitting book saved event for: {}", filename.as_str());
-    event_svc.on_book_saved(&filename).await;
+    println!("💾 Emitting book saved event for: {}", filename);
+    event_svc.on_book_saved(filename).await;
+}
+
+// Records how `book` changed from `before` as one undo step, returning the
+// bounds of the changed pixels across all frames
+fn record_undo_step(undo_service: &UndoService, filename: &str, before: &[Frame], book: &PixelBook) -> Option<Rect> {
+    let diffs: Vec<_> = before.iter().zip(&book.frames).enumerate()
+        .map(|(index, (before, after))| (index, diff_frames(before, after, book.width, book.height)))
+        .collect();
+    let region = diffs.iter()
+        .filter_map(|(_, diff)| diff.bounds.clone())
+        .reduce(|a, b| a.union(&b));
+    if let Some(step) = UndoStep::new(book.width, book.height, diffs) {
+        undo_service.record(filename, step);
+    }
+    region
+}
+
+#[handler]
+pub async fn undo_book(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    undo_service: poem::web::Data<&Arc<UndoService>>,
+    filename: Path<String>,
+) -> Result<Json<serde_json::Value>> {
+    step_history(req, &file_service, &lock_service, &event_service, &undo_service, &filename, false).await
+}
+
+#[handler]
+pub async fn redo_book(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    undo_service: poem::web::Data<&Arc<UndoService>>,
+    filename: Path<String>,
+) -> Result<Json<serde_json::Value>> {
+    step_history(req, &file_service, &lock_service, &event_service, &undo_service, &filename, true).await
+}
+
+// Undoes (or with `redo`, reapplies) the latest batch and saves the result
+async fn step_history(
+    req: &Request,
+    file_service: &Arc<RwLock<FileService>>,
+    lock_service: &LockService,
+    event_service: &Arc<RwLock<EventService>>,
+    undo_service: &UndoService,
+    filename: &str,
+    redo: bool,
+) -> Result<Json<serde_json::Value>> {
+    if !validation::validate_filename(filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let service = file_service.write().await;
+    let mut book = service.load_book(filename)
+        .map_err(|e| match e {
+            PixelError::FileNotFound { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+
+    permissions::check_write_access(filename, &book.metadata.permissions, owner_key(req))
+        .map_err(permission_error)?;
+    check_lock(lock_service, filename, req)?;
+
+    let action = if redo { "redo" } else { "undo" };
+    let step = if redo { undo_service.redo(&mut book) } else { undo_service.undo(&mut book) }
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::CONFLICT))?;
+    service.save_book(&book)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    println!("↩️ Applied {} to {} ({} pixels)", action, filename, step.pixel_count());
+
+    if let Err(e) = OperationLogService::record(service.get_path(), filename, &log_entry(req, vec![action.to_string()])) {
+        println!("⚠️ Could not record {} for {}: {}", action, filename, e);
+    }
+    event_service.read().await.on_book_saved(filename).await;
 
+    let (undo_remaining, redo_remaining) = undo_service.depths(filename);
     Ok(Json(json!({
         "success": true,
-        "operations_applied": request.operations.len(),
-        "filename": filename.to_string()
+        "filename": filename,
+        "frames": step.frames(),
+        "pixels_changed": step.pixel_count(),
+        "undo_remaining": undo_remaining,
+        "redo_remaining": redo_remaining
+    })))
+}
+
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+pub struct OperationLogQuery {
+    /// Only entries recorded after this RFC 3339 time
+    since: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[handler]
+pub async fn operation_log(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    filename: Path<String>,
+    Query(query): Query<OperationLogQuery>,
+) -> Result<Json<serde_json::Value>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+    
+    let service = file_service.read().await;
+    let entries = OperationLogService::entries(service.get_path(), &filename, query.since)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    // Deleted books keep their log, so only a book with neither is unknown
+    if entries.is_empty() && !service.get_path().join(filename.as_str()).exists() {
+        return Err(Error::from_string(
+            format!("File not found: {}", filename.as_str()),
+            poem::http::StatusCode::NOT_FOUND,
+        ));
+    }
+    
+    Ok(Json(json!({
+        "filename": filename.to_string(),
+        "entries": entries
     })))
 }
 
- 
\ No newline at end of file
+/// Most colors a quantized palette may hold
+const MAX_QUANTIZE_COLORS: usize = 256;
+
+#[handler]
+pub async fn quantize_book(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    undo_service: poem::web::Data<&Arc<UndoService>>,
+    filename: Path<String>,
+    request: Json<QuantizeRequest>,
+) -> Result<Json<serde_json::Value>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+    
+    let invalid = || Error::from_string(
+        format!("Provide either colors (1-{0}) or a palette of 1-{0} colors, or set a palette on the book", MAX_QUANTIZE_COLORS),
+        poem::http::StatusCode::BAD_REQUEST,
+    );
+    let valid = match (&request.colors, &request.palette) {
+        (Some(colors), None) => (1..=MAX_QUANTIZE_COLORS).contains(colors),
+        (None, Some(palette)) => !palette.is_empty() && palette.len() <= MAX_QUANTIZE_COLORS,
+        (None, None) => true,
+        _ => false,
+    };
+    if !valid {
+        return Err(invalid());
+    }
+    
+    let service = file_service.write().await;
+    let mut book = service.load_book(&filename)
+        .map_err(|e| match e {
+            PixelError::FileNotFound { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+    permissions::check_write_access(&filename, &book.metadata.permissions, owner_key(req))
+        .map_err(permission_error)?;
+    check_lock(&lock_service, &filename, req)?;
+    
+    // Without colors or a palette, imported art is brought onto the book's own palette
+    let palette = match (&request.colors, &request.palette) {
+        (None, None) => Some(book.metadata.palette.as_ref()
+            .filter(|palette| !palette.colors.is_empty())
+            .ok_or_else(invalid)?
+            .colors.clone()),
+        (_, palette) => palette.clone(),
+    };
+    let before = book.frames.clone();
+    let result = match palette {
+        Some(palette) => {
+            let pixels_changed = pixl_core::apply_palette_dithered(&mut book, &palette, request.dither);
+            pixl_core::QuantizeResult { palette, pixels_changed }
+        }
+        None => pixl_core::quantize_book(&mut book, request.colors.unwrap_or(MAX_QUANTIZE_COLORS), request.method, request.dither),
+    };
+    
+    service.save_book(&book)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    println!("🎨 Quantized {} to {} colors ({} pixels changed)", filename.as_str(), result.palette.len(), result.pixels_changed);
+    
+    record_undo_step(&undo_service, &filename, &before, &book);
+    let entry = log_entry(req, vec![format!("quantize {} colors", result.palette.len())]);
+    if let Err(e) = OperationLogService::record(service.get_path(), &filename, &entry) {
+        println!("⚠️ Could not record the quantize of {}: {}", filename.as_str(), e);
+    }
+    event_service.read().await.on_book_saved(&filename).await;
+    
+    Ok(Json(json!({
+        "success": true,
+        "filename": filename.to_string(),
+        "palette": result.palette,
+        "pixels_changed": result.pixels_changed
+    })))
+}
+
+#[handler]
+pub async fn resize_book(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    undo_service: poem::web::Data<&Arc<UndoService>>,
+    filename: Path<String>,
+    request: Json<ResizeRequest>,
+) -> Result<Json<serde_json::Value>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+    if !validation::validate_dimensions(request.width, request.height) {
+        return Err(Error::from_string(
+            "Width and height must be between 1 and 4096",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let service = file_service.write().await;
+    let mut book = load_for_transform(&service, &filename, req, &lock_service)?;
+    FileService::check_limits(request.width, request.height, book.frames.len())
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::BAD_REQUEST))?;
+
+    let (width, height) = (book.width, book.height);
+    pixl_core::resize_book(&mut book, &request);
+    let revision = save_transformed(&service, &book, &undo_service)?;
+    println!("📐 Resized {} from {}x{} to {}x{}", filename.as_str(), width, height, book.width, book.height);
+
+    event_service.read().await.on_book_saved(&filename).await;
+
+    Ok(Json(transform_response(&book, revision)))
+}
+
+#[handler]
+pub async fn crop_book(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    undo_service: poem::web::Data<&Arc<UndoService>>,
+    filename: Path<String>,
+    request: Json<Rect>,
+) -> Result<Json<serde_json::Value>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let service = file_service.write().await;
+    let mut book = load_for_transform(&service, &filename, req, &lock_service)?;
+
+    let (width, height) = (book.width, book.height);
+    pixl_core::crop_book(&mut book, &request)
+        .map_err(|e| Error::from_string(e, poem::http::StatusCode::BAD_REQUEST))?;
+    let revision = save_transformed(&service, &book, &undo_service)?;
+    println!("✂️ Cropped {} from {}x{} to {}x{}", filename.as_str(), width, height, book.width, book.height);
+
+    event_service.read().await.on_book_saved(&filename).await;
+
+    Ok(Json(transform_response(&book, revision)))
+}
+
+#[handler]
+pub async fn trim_book(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    undo_service: poem::web::Data<&Arc<UndoService>>,
+    filename: Path<String>,
+) -> Result<Json<serde_json::Value>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let service = file_service.write().await;
+    let mut book = load_for_transform(&service, &filename, req, &lock_service)?;
+
+    let (width, height) = (book.width, book.height);
+    let rect = pixl_core::trim_book(&mut book)
+        .map_err(|e| Error::from_string(e, poem::http::StatusCode::BAD_REQUEST))?;
+    // Nothing to trim leaves the book, and its undo history, untouched
+    let revision = if (book.width, book.height) == (width, height) {
+        service.revision(&filename)
+            .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?
+    } else {
+        let revision = save_transformed(&service, &book, &undo_service)?;
+        println!("✂️ Trimmed {} from {}x{} to {}x{}", filename.as_str(), width, height, book.width, book.height);
+        event_service.read().await.on_book_saved(&filename).await;
+        revision
+    };
+
+    let mut response = transform_response(&book, revision);
+    response["x"] = json!(rect.x);
+    response["y"] = json!(rect.y);
+    Ok(Json(response))
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ScaleQuery {
+    /// Whole factor to scale by, 1-16
+    pub factor: u16,
+    /// Scale down instead of up; the canvas must divide evenly by `factor`
+    #[serde(default)]
+    pub down: bool,
+}
+
+#[handler]
+pub async fn scale_book(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    undo_service: poem::web::Data<&Arc<UndoService>>,
+    filename: Path<String>,
+    Query(query): Query<ScaleQuery>,
+) -> Result<Json<serde_json::Value>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let service = file_service.write().await;
+    let mut book = load_for_transform(&service, &filename, req, &lock_service)?;
+
+    let (width, height) = (book.width, book.height);
+    // Checked before any pixels are allocated, as a large book scaled up
+    // could otherwise ask for gigabytes per frame
+    let (new_width, new_height) = pixl_core::scaled_size(width, height, query.factor, query.down)
+        .map_err(|e| Error::from_string(e, poem::http::StatusCode::BAD_REQUEST))?;
+    if !validation::validate_dimensions(new_width, new_height) {
+        return Err(Error::from_string(
+            format!("Scaling a {}x{} book by {} would exceed 4096 pixels", width, height, query.factor),
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+    FileService::check_limits(new_width, new_height, book.frames.len())
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::BAD_REQUEST))?;
+    pixl_core::scale_book(&mut book, query.factor, query.down)
+        .map_err(|e| Error::from_string(e, poem::http::StatusCode::BAD_REQUEST))?;
+
+    let revision = save_transformed(&service, &book, &undo_service)?;
+    println!("🔍 Scaled {} from {}x{} to {}x{}", filename.as_str(), width, height, book.width, book.height);
+
+    event_service.read().await.on_book_saved(&filename).await;
+
+    Ok(Json(transform_response(&book, revision)))
+}
+
+// Loads a book whose canvas is about to change, checking the caller may write it
+fn load_for_transform(service: &FileService, filename: &str, req: &Request, lock_service: &LockService) -> Result<PixelBook> {
+    let book = service.load_book(filename)
+        .map_err(|e| match e {
+            PixelError::FileNotFound { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+    permissions::check_write_access(filename, &book.metadata.permissions, owner_key(req))
+        .map_err(permission_error)?;
+    check_lock(lock_service, filename, req)?;
+    Ok(book)
+}
+
+// Undo steps are pixel diffs at the old size, so the book's history no longer applies
+fn save_transformed(service: &FileService, book: &PixelBook, undo_service: &UndoService) -> Result<u64> {
+    let revision = service.save_book(book)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    undo_service.forget(&book.filename);
+    Ok(revision)
+}
+
+fn transform_response(book: &PixelBook, revision: u64) -> serde_json::Value {
+    json!({
+        "success": true,
+        "filename": book.filename,
+        "width": book.width,
+        "height": book.height,
+        "frames": book.frames.len(),
+        "revision": revision
+    })
+}
+
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct MigrateQuery {
+    /// Target format version; takes precedence over `version` in the body
+    pub to_version: Option<u16>,
+}
+
+#[handler]
+pub async fn migrate_book(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    filename: Path<String>,
+    Query(query): Query<MigrateQuery>,
+    request: Option<Json<MigrateRequest>>,
+) -> Result<Json<serde_json::Value>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+    
+    let service = file_service.write().await;
+    let metadata = service.load_metadata(&filename)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?
+        .ok_or_else(|| Error::from_string(
+            format!("File not found: {}", filename.as_str()),
+            poem::http::StatusCode::NOT_FOUND,
+        ))?;
+    permissions::check_write_access(&filename, &metadata.permissions, owner_key(req))
+        .map_err(permission_error)?;
+    check_lock(&lock_service, &filename, req)?;
+    
+    let version = query.to_version.or_else(|| request.and_then(|Json(request)| request.version));
+    let result = service.migrate_book(&filename, version)
+        .map_err(|e| match e {
+            PixelError::InvalidFormat { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::BAD_REQUEST),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+    
+    if result.changed() {
+        println!("⬆️ Migrated {} from v{} to v{}", filename.as_str(), result.from_version, result.to_version);
+        event_service.read().await.on_book_saved(&filename).await;
+    }
+    
+    Ok(Json(json!({
+        "success": true,
+        "filename": result.filename,
+        "from_version": result.from_version,
+        "to_version": result.to_version,
+        "migrated": result.changed()
+    })))
+}
+
+#[handler]
+pub async fn delete_book(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    undo_service: poem::web::Data<&Arc<UndoService>>,
+    filename: Path<String>,
+) -> Result<Json<serde_json::Value>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let service = file_service.write().await;
+    let metadata = service.load_metadata(&filename)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?
+        .ok_or_else(|| Error::from_string(
+            format!("File not found: {}", filename.as_str()),
+            poem::http::StatusCode::NOT_FOUND,
+        ))?;
+
+    permissions::check_write_access(&filename, &metadata.permissions, owner_key(req))
+        .map_err(permission_error)?;
+    check_lock(&lock_service, &filename, req)?;
+
+    let entry = service.delete_book(&filename)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    println!("🗑️ Moved {} to trash (restorable until {})", filename.as_str(), entry.expires_at);
+    undo_service.forget(&filename);
+
+    event_service.read().await.on_book_deleted(&filename).await;
+
+    Ok(Json(json!({
+        "success": true,
+        "filename": entry.filename,
+        "expires_at": entry.expires_at
+    })))
+}
+
+// Both names must be valid book filenames
+fn validate_names(filename: &str, new_filename: &str) -> Result<()> {
+    if !validation::validate_filename(filename) || !validation::validate_filename(new_filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+    Ok(())
+}
+
+fn move_error(e: PixelError) -> Error {
+    match e {
+        PixelError::FileNotFound { .. } =>
+            Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+        PixelError::AlreadyExists { .. } =>
+            Error::from_string(e.to_string(), poem::http::StatusCode::CONFLICT),
+        _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[handler]
+pub async fn rename_book(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    undo_service: poem::web::Data<&Arc<UndoService>>,
+    filename: Path<String>,
+    request: Json<RenameBookRequest>,
+) -> Result<Json<serde_json::Value>> {
+    validate_names(&filename, &request.new_filename)?;
+
+    let service = file_service.write().await;
+    let metadata = service.load_metadata(&filename)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?
+        .ok_or_else(|| Error::from_string(
+            format!("File not found: {}", filename.as_str()),
+            poem::http::StatusCode::NOT_FOUND,
+        ))?;
+
+    permissions::check_write_access(&filename, &metadata.permissions, owner_key(req))
+        .map_err(permission_error)?;
+    check_lock(&lock_service, &filename, req)?;
+
+    service.rename_book(&filename, &request.new_filename).map_err(move_error)?;
+    println!("✏️ Renamed {} to {}", filename.as_str(), request.new_filename);
+    
+    undo_service.rename(&filename, &request.new_filename);
+    if let Err(e) = OperationLogService::rename(service.get_path(), &filename, &request.new_filename) {
+        println!("⚠️ Could not move the operation log of {}: {}", filename.as_str(), e);
+    }
+
+    let event_svc = event_service.read().await;
+    event_svc.on_book_renamed(&filename, &request.new_filename).await;
+    event_svc.on_book_saved(&request.new_filename).await;
+
+    Ok(Json(json!({
+        "success": true,
+        "filename": request.new_filename,
+        "previous_filename": filename.to_string()
+    })))
+}
+
+#[handler]
+pub async fn copy_book(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    filename: Path<String>,
+    request: Json<RenameBookRequest>,
+) -> Result<Json<serde_json::Value>> {
+    validate_names(&filename, &request.new_filename)?;
+
+    // Copies keep the original's permissions, so reading it is all this needs
+    let service = file_service.write().await;
+    service.copy_book(&filename, &request.new_filename).map_err(move_error)?;
+    println!("📄 Copied {} to {}", filename.as_str(), request.new_filename);
+
+    event_service.read().await.on_book_saved(&request.new_filename).await;
+
+    Ok(Json(json!({
+        "success": true,
+        "filename": request.new_filename,
+        "source": filename.to_string()
+    })))
+}
+
+#[handler]
+pub async fn set_permissions(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    filename: Path<String>,
+    request: Json<SetPermissionsRequest>,
+) -> Result<Json<serde_json::Value>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let service = file_service.write().await;
+    let mut book = service.load_book(&filename)
+        .map_err(|e| match e {
+            PixelError::FileNotFound { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+
+    // Only the owner may change permissions, even on a read-only book
+    permissions::check_owner(&filename, &book.metadata.permissions, owner_key(req))
+        .map_err(permission_error)?;
+    check_lock(&lock_service, &filename, req)?;
+
+    book.metadata.permissions.read_only = request.read_only;
+    book.metadata.permissions.owner_key_hash = request.owner_key.as_deref().map(permissions::hash_owner_key);
+
+    service.save_book(&book)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    println!("🔒 Updated permissions for {}: read_only={}, owner={}", filename.as_str(), request.read_only, request.owner_key.is_some());
+
+    event_service.read().await.on_book_saved(&filename).await;
+
+    Ok(Json(json!({
+        "success": true,
+        "filename": filename.to_string(),
+        "read_only": book.metadata.permissions.read_only,
+        "has_owner": book.metadata.permissions.owner_key_hash.is_some()
+    })))
+}
+
+#[handler]
+pub async fn get_details(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    filename: Path<String>,
+) -> Result<Json<BookDetails>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let metadata = file_service.read().await.load_metadata(&filename)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?
+        .ok_or_else(|| Error::from_string(
+            format!("File not found: {}", filename.as_str()),
+            poem::http::StatusCode::NOT_FOUND,
+        ))?;
+
+    Ok(Json(metadata.details))
+}
+
+#[handler]
+pub async fn update_details(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    filename: Path<String>,
+    request: Json<UpdateDetailsRequest>,
+) -> Result<Json<BookDetails>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let service = file_service.write().await;
+    let mut book = service.load_book(&filename)
+        .map_err(|e| match e {
+            PixelError::FileNotFound { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+
+    permissions::check_write_access(&filename, &book.metadata.permissions, owner_key(req))
+        .map_err(permission_error)?;
+    check_lock(&lock_service, &filename, req)?;
+
+    let details = request.apply(&book.metadata.details)
+        .map_err(|e| Error::from_string(e, poem::http::StatusCode::BAD_REQUEST))?;
+    details.check_frame_tags(book.frames.len())
+        .map_err(|e| Error::from_string(e, poem::http::StatusCode::BAD_REQUEST))?;
+    book.metadata.details = details;
+    service.save_book(&book)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    println!("🏷️ Updated details of {}", filename.as_str());
+
+    event_service.read().await.on_book_saved(&filename).await;
+
+    Ok(Json(book.metadata.details))
+}
+
+#[handler]
+pub async fn get_palette(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    filename: Path<String>,
+) -> Result<Json<Palette>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let metadata = file_service.read().await.load_metadata(&filename)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?
+        .ok_or_else(|| Error::from_string(
+            format!("File not found: {}", filename.as_str()),
+            poem::http::StatusCode::NOT_FOUND,
+        ))?;
+
+    metadata.palette.map(Json).ok_or_else(|| Error::from_string(
+        format!("{} has no palette", filename.as_str()),
+        poem::http::StatusCode::NOT_FOUND,
+    ))
+}
+
+#[handler]
+pub async fn set_palette(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    filename: Path<String>,
+    request: Json<Palette>,
+) -> Result<Json<Palette>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+    request.validate()
+        .map_err(|e| Error::from_string(e, poem::http::StatusCode::BAD_REQUEST))?;
+
+    let service = file_service.write().await;
+    let mut book = service.load_book(&filename)
+        .map_err(|e| match e {
+            PixelError::FileNotFound { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+
+    permissions::check_write_access(&filename, &book.metadata.permissions, owner_key(req))
+        .map_err(permission_error)?;
+    check_lock(&lock_service, &filename, req)?;
+
+    // Pixels keep their RGBA values; only operations sent from now on use the new colors
+    book.metadata.palette = Some(request.0.clone());
+    service.save_book(&book)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    println!("🎨 Set palette \"{}\" ({} colors) for {}", request.name, request.colors.len(), filename.as_str());
+
+    event_service.read().await.on_book_saved(&filename).await;
+
+    Ok(Json(request.0))
+}
+
+#[handler]
+pub async fn get_tileset(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    filename: Path<String>,
+) -> Result<Json<TilesetInfo>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let reader = file_service.read().await.open_book(&filename)
+        .map_err(|e| match e {
+            PixelError::FileNotFound { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+
+    let tileset = reader.metadata().tileset.ok_or_else(|| Error::from_string(
+        format!("{} is not a tileset", filename.as_str()),
+        poem::http::StatusCode::NOT_FOUND,
+    ))?;
+    Ok(Json(tileset.info(&filename, reader.width(), reader.height())))
+}
+
+#[handler]
+pub async fn set_tileset(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    filename: Path<String>,
+    request: Json<Tileset>,
+) -> Result<Json<TilesetInfo>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let service = file_service.write().await;
+    let mut book = service.load_book(&filename)
+        .map_err(|e| match e {
+            PixelError::FileNotFound { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+    request.validate(book.width, book.height)
+        .map_err(|e| Error::from_string(e, poem::http::StatusCode::BAD_REQUEST))?;
+
+    permissions::check_write_access(&filename, &book.metadata.permissions, owner_key(req))
+        .map_err(permission_error)?;
+    check_lock(&lock_service, &filename, req)?;
+
+    book.metadata.tileset = Some(request.0);
+    service.save_book(&book)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    println!("🧱 Set {}x{} tiles for {}", request.tile_width, request.tile_height, filename.as_str());
+
+    event_service.read().await.on_book_saved(&filename).await;
+
+    Ok(Json(request.info(&filename, book.width, book.height)))
+}
+
+#[handler]
+pub async fn set_frame_duration(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    Path((filename, index)): Path<(String, usize)>,
+    request: Json<FrameDuration>,
+) -> Result<Json<FrameDuration>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+    request.validate()
+        .map_err(|e| Error::from_string(e, poem::http::StatusCode::BAD_REQUEST))?;
+
+    let service = file_service.write().await;
+    let mut book = service.load_book(&filename)
+        .map_err(|e| match e {
+            PixelError::FileNotFound { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+
+    permissions::check_write_access(&filename, &book.metadata.permissions, owner_key(req))
+        .map_err(permission_error)?;
+    check_lock(&lock_service, &filename, req)?;
+
+    let frame_count = book.frames.len();
+    let frame = book.frames.get_mut(index)
+        .ok_or_else(|| Error::from_string(
+            format!("Frame {} does not exist; {} has {} frames", index, filename, frame_count),
+            poem::http::StatusCode::NOT_FOUND,
+        ))?;
+    frame.duration_ms = request.duration_ms;
+    service.save_book(&book)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    match request.duration_ms {
+        Some(ms) => println!("⏱️ Set frame {} of {} to {}ms", index, filename, ms),
+        None => println!("⏱️ Cleared the duration of frame {} of {}", index, filename),
+    }
+
+    event_service.read().await.on_book_saved(&filename).await;
+
+    Ok(Json(request.0))
+}
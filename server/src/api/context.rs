@@ -0,0 +1,130 @@
+use crate::models::{ActiveSelection, DrawingContext, PixelBook, PixelError, SetSymmetryRequest, Symmetry};
+use crate::services::{ContextService, DrawingService, FileService, DEFAULT_SESSION, MAX_SESSION_LEN, SESSION_HEADER};
+use crate::utils::{permissions, validation};
+use poem::{handler, web::{Json, Path}, Request, Result, Error};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// The session a request draws in, from its `X-Pixl-Session` header
+pub(crate) fn session(req: &Request) -> Result<&str> {
+    match req.header(SESSION_HEADER).map(str::trim) {
+        None | Some("") => Ok(DEFAULT_SESSION),
+        Some(session) if session.len() > MAX_SESSION_LEN => Err(Error::from_string(
+            format!("Session names are at most {} characters", MAX_SESSION_LEN),
+            poem::http::StatusCode::BAD_REQUEST,
+        )),
+        Some(session) => Ok(session),
+    }
+}
+
+/// The drawing context `req` draws on `filename` with
+pub(crate) fn load_context(service: &FileService, filename: &str, req: &Request) -> Result<DrawingContext> {
+    ContextService::load(service.get_path(), filename, session(req)?)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))
+}
+
+/// A drawing service for `book` set up from `context`, mirroring with
+/// `symmetry` when the request asks for it
+pub(crate) fn drawing_service(context: &DrawingContext, symmetry: Option<Symmetry>, book: &PixelBook) -> Result<DrawingService> {
+    DrawingService::with_symmetry(symmetry.unwrap_or(context.symmetry))
+        .with_selection(book, context.selection.as_ref())
+        .map_err(|e| Error::from_string(
+            format!("The selection no longer fits {}: {}; clear it to keep drawing", book.filename, e),
+            poem::http::StatusCode::CONFLICT,
+        ))
+}
+
+// Checks the book exists and, when `write` is set, that the caller may draw on it
+fn check_book(service: &FileService, filename: &str, req: &Request, write: bool) -> Result<()> {
+    if !validation::validate_filename(filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+    let metadata = service.load_metadata(filename)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?
+        .ok_or_else(|| Error::from_string(
+            format!("File not found: {}", filename),
+            poem::http::StatusCode::NOT_FOUND,
+        ))?;
+    if write {
+        permissions::check_write_access(filename, &metadata.permissions, req.header(permissions::OWNER_KEY_HEADER))
+            .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::FORBIDDEN))?;
+    }
+    Ok(())
+}
+
+// Stores `context` for the caller's session and echoes it back
+fn save_context(service: &FileService, filename: &str, req: &Request, context: DrawingContext) -> Result<Json<DrawingContext>> {
+    ContextService::save(service.get_path(), filename, session(req)?, &context)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    Ok(Json(context))
+}
+
+#[handler]
+pub async fn get_context(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    filename: Path<String>,
+) -> Result<Json<DrawingContext>> {
+    let service = file_service.read().await;
+    check_book(&service, &filename, req, false)?;
+    load_context(&service, &filename, req).map(Json)
+}
+
+#[handler]
+pub async fn set_symmetry(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    filename: Path<String>,
+    request: Json<SetSymmetryRequest>,
+) -> Result<Json<DrawingContext>> {
+    let service = file_service.write().await;
+    check_book(&service, &filename, req, true)?;
+
+    let mut context = load_context(&service, &filename, req)?;
+    context.symmetry = request.symmetry;
+    println!("🪞 Drawing on {} in session {} now uses {:?} symmetry", filename.as_str(), session(req)?, request.symmetry);
+    save_context(&service, &filename, req, context)
+}
+
+#[handler]
+pub async fn set_selection(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    filename: Path<String>,
+    request: Json<ActiveSelection>,
+) -> Result<Json<DrawingContext>> {
+    let service = file_service.write().await;
+    check_book(&service, &filename, req, true)?;
+
+    // Only keep a selection that can be picked from the book as it is
+    let book = service.load_book(&filename)
+        .map_err(|e| match e {
+            PixelError::FileNotFound { .. } => Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+    DrawingService::new().with_selection(&book, Some(&request))
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::BAD_REQUEST))?;
+
+    let mut context = load_context(&service, &filename, req)?;
+    context.selection = Some(request.0);
+    println!("⬚ Drawing on {} in session {} is now limited to a selection", filename.as_str(), session(req)?);
+    save_context(&service, &filename, req, context)
+}
+
+#[handler]
+pub async fn clear_selection(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    filename: Path<String>,
+) -> Result<Json<DrawingContext>> {
+    let service = file_service.write().await;
+    check_book(&service, &filename, req, true)?;
+
+    let mut context = load_context(&service, &filename, req)?;
+    context.selection = None;
+    println!("⬚ Cleared the selection on {} in session {}", filename.as_str(), session(req)?);
+    save_context(&service, &filename, req, context)
+}
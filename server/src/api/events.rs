@@ -1,18 +1,79 @@
-use poem::{handler, web::Path, web::sse::{SSE, Event}};
-use crate::services::EventService;
+use poem::{handler, web::{Json, Path, Query}, web::sse::{SSE, Event}};
+use crate::models::{EventType, PixelBookEvent};
+use crate::services::{EventHistoryService, EventService, FileService, DEFAULT_HISTORY_LIMIT, MAX_HISTORY_LIMIT};
+use serde::Deserialize;
+use serde_json::json;
 use poem::{Result, Error};
 use std::time::Duration;
-use tokio::time::interval;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::{interval_at, timeout, Instant, MissedTickBehavior};
+use tokio_stream::wrappers::ReceiverStream;
 use chrono::Utc;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use poem::{web::Data, Response};
-use futures::stream::Stream;
+
+pub const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+pub const DEFAULT_SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Messages buffered per connection before sends start waiting on the client
+const SSE_BUFFER: usize = 64;
+
+/// Timing of every SSE connection
+#[derive(Debug, Clone)]
+pub struct SseSettings {
+    /// Idle time after which a heartbeat is sent; any other message resets it
+    pub heartbeat_interval: Duration,
+    /// How long a message may wait for a slow client before the connection is dropped
+    pub send_timeout: Duration,
+}
+
+impl Default for SseSettings {
+    fn default() -> Self {
+        Self {
+            heartbeat_interval: DEFAULT_HEARTBEAT_INTERVAL,
+            send_timeout: DEFAULT_SEND_TIMEOUT,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct EventsQuery {
+    /// Comma-separated event types to receive, e.g. `drawing_operation,book_saved`
+    #[serde(default)]
+    pub types: Option<String>,
+}
+
+impl EventsQuery {
+    /// The requested event types; `None` means every type
+    fn type_filter(&self) -> Result<Option<Vec<String>>> {
+        let Some(types) = &self.types else { return Ok(None) };
+        let names: Vec<String> = types.split(',')
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty())
+            .map(|name| name.to_string())
+            .collect();
+        
+        if let Some(unknown) = names.iter().find(|name| !EventType::NAMES.contains(&name.as_str())) {
+            return Err(Error::from_string(
+                format!("Unknown event type: {}", unknown),
+                poem::http::StatusCode::BAD_REQUEST,
+            ));
+        }
+        Ok(Some(names))
+    }
+}
+
+// Queues a message for the client; false once the client is gone or too slow
+async fn deliver(tx: &mpsc::Sender<Event>, message: String, send_timeout: Duration) -> bool {
+    matches!(timeout(send_timeout, tx.send(Event::message(message))).await, Ok(Ok(())))
+}
 
 #[handler]
 pub async fn pixel_book_events(
     filename: Path<String>,
+    Query(query): Query<EventsQuery>,
     event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    settings: poem::web::Data<&Arc<SseSettings>>,
 ) -> Result<SSE> {
     if !crate::utils::validation::validate_filename(&filename) {
         return Err(Error::from_string(
@@ -21,58 +82,187 @@ pub async fn pixel_book_events(
         ));
     }
     
-    let filename = filename.to_string();
-    let event_service = event_service.clone();
+    let type_filter = query.type_filter()?;
+    Ok(event_stream(Some(filename.to_string()), type_filter, event_service.clone(), settings.as_ref().clone()))
+}
+
+/// Events of every book in the workspace, such as integrity problems found
+/// by a scan
+#[handler]
+pub async fn workspace_events(
+    Query(query): Query<EventsQuery>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    settings: poem::web::Data<&Arc<SseSettings>>,
+) -> Result<SSE> {
+    let type_filter = query.type_filter()?;
+    Ok(event_stream(None, type_filter, event_service.clone(), settings.as_ref().clone()))
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct EventHistoryQuery {
+    /// Only events sent after this RFC 3339 time
+    pub since: Option<chrono::DateTime<Utc>>,
+    /// Most events to return, 100 by default and at most 1000
+    pub limit: Option<usize>,
+}
+
+#[derive(serde::Serialize, schemars::JsonSchema)]
+pub struct EventHistoryResponse {
+    filename: String,
+    events: Vec<PixelBookEvent>,
+}
+
+/// Events already sent for a book, oldest first, read from its history
+#[handler]
+pub async fn event_history(
+    filename: Path<String>,
+    Query(query): Query<EventHistoryQuery>,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+) -> Result<Json<EventHistoryResponse>> {
+    if !crate::utils::validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_HISTORY_LIMIT);
+    if limit == 0 || limit > MAX_HISTORY_LIMIT {
+        return Err(Error::from_string(
+            format!("limit must be between 1 and {}", MAX_HISTORY_LIMIT),
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+    
+    let service = file_service.read().await;
+    let events = EventHistoryService::events(service.get_path(), &filename, query.since, limit)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    // Deleted books keep their history, so only a book with neither is unknown
+    if events.is_empty() && !service.get_path().join(filename.as_str()).exists() {
+        return Err(Error::from_string(
+            format!("File not found: {}", filename.as_str()),
+            poem::http::StatusCode::NOT_FOUND,
+        ));
+    }
+    
+    Ok(Json(EventHistoryResponse { filename: filename.to_string(), events }))
+}
+
+// Connection and heartbeat notices; `filename` is null on the workspace stream
+fn notice(kind: &str, filename: Option<&str>) -> String {
+    json!({
+        "type": kind,
+        "filename": filename,
+        "timestamp": Utc::now().to_rfc3339()
+    }).to_string()
+}
+
+/// Streams the events of `filename`, or of every book when `None`
+fn event_stream(
+    filename: Option<String>,
+    type_filter: Option<Vec<String>>,
+    event_service: Arc<RwLock<EventService>>,
+    settings: SseSettings,
+) -> SSE {
+    let (tx, rx) = mpsc::channel(SSE_BUFFER);
     
-    let stream = async_stream::stream! {
-        let mut interval = interval(Duration::from_millis(500)); // Check for updates every 500ms
-        let mut last_check = Utc::now();
+    // The producer owns the subscription and the heartbeat timer; it ends
+    // (closing the stream) when the client disconnects or stops reading for
+    // longer than the send timeout
+    tokio::spawn(async move {
+        let mut heartbeat = interval_at(Instant::now() + settings.heartbeat_interval, settings.heartbeat_interval);
+        heartbeat.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let source = filename.as_deref().unwrap_or("all books");
+        
+        // Subscribed and counted before the client hears it is connected, so
+        // no later event is missed and it shows up in `GET /status` right away
+        let (_client, mut events) = {
+            let service = event_service.read().await;
+            let events = match &filename {
+                Some(filename) => service.subscribe(filename),
+                None => service.subscribe_all(),
+            };
+            (service.track_client(), events)
+        };
         
         // Send initial connection event
-        yield Event::message(format!(
-            r#"{{"type":"connected","filename":"{}","timestamp":"{}"}}"#,
-            filename,
-            chrono::Utc::now().to_rfc3339()
-        ));
+        if !deliver(&tx, notice("connected", filename.as_deref()), settings.send_timeout).await {
+            return;
+        }
         
-        println!("📡 SSE client connected for book: {}", filename);
+        println!("📡 SSE client connected for: {}", source);
         
         loop {
-            interval.tick().await;
-            
-            // Get recent events from the event service
-            let service = event_service.read().await;
-            let recent_events = service.get_recent_events(&filename, last_check).await;
-            
-            if !recent_events.is_empty() {
-                println!("📨 Sending {} events for book: {}", recent_events.len(), filename);
-                
-                for event in recent_events {
+            tokio::select! {
+                received = events.recv() => {
+                    let event = match received {
+                        Ok(event) => event,
+                        // A client that missed events reconnects and reloads
+                        // rather than silently going stale
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            println!("⚠️ SSE client for {} fell {} events behind; disconnecting", source, skipped);
+                            break;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    };
+                    if let Some(types) = &type_filter
+                        && !types.iter().any(|name| name == event.event_type.name())
+                    {
+                        continue;
+                    }
+                    
                     // Convert PixelBookEvent to JSON and send via SSE
                     match serde_json::to_string(&event) {
                         Ok(json_event) => {
-                            println!("📤 Sending event: {}", json_event);
-                            yield Event::message(json_event);
-                        },
+                            if !deliver(&tx, json_event, settings.send_timeout).await {
+                                break;
+                            }
+                        }
                         Err(e) => {
                             println!("❌ Failed to serialize event: {}", e);
                         }
                     }
+                    heartbeat.reset();
                 }
-            }
-            
-            last_check = Utc::now();
-            
-            // Send periodic heartbeat every 10 seconds
-            if last_check.timestamp() % 10 == 0 {
-                yield Event::message(format!(
-                    r#"{{"type":"heartbeat","filename":"{}","timestamp":"{}"}}"#,
-                    filename,
-                    last_check.to_rfc3339()
-                ));
+                _ = heartbeat.tick() => {
+                    if !deliver(&tx, notice("heartbeat", filename.as_deref()), settings.send_timeout).await {
+                        break;
+                    }
+                }
+                _ = tx.closed() => break,
             }
         }
-    };
+        
+        println!("📴 SSE connection closed for: {}", source);
+    });
     
-    Ok(SSE::new(stream))
-} 
\ No newline at end of file
+    SSE::new(ReceiverStream::new(rx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_deliver_gives_up_on_stalled_clients() {
+        let (tx, mut rx) = mpsc::channel(1);
+        assert!(deliver(&tx, "first".to_string(), Duration::from_millis(10)).await);
+        
+        // Nobody reads, so the second message cannot be queued in time
+        assert!(!deliver(&tx, "second".to_string(), Duration::from_millis(10)).await);
+        
+        rx.recv().await.unwrap();
+        drop(rx);
+        assert!(!deliver(&tx, "third".to_string(), Duration::from_millis(10)).await);
+    }
+
+    #[test]
+    fn test_type_filter_parsing() {
+        let query = |types: Option<&str>| EventsQuery { types: types.map(|t| t.to_string()) };
+        assert_eq!(query(None).type_filter().unwrap(), None);
+        assert_eq!(
+            query(Some("drawing_operation, book_saved")).type_filter().unwrap(),
+            Some(vec!["drawing_operation".to_string(), "book_saved".to_string()])
+        );
+        assert!(query(Some("book_saved,pixel_party")).type_filter().is_err());
+    }
+}
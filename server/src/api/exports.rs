@@ -0,0 +1,421 @@
+use crate::models::{FrameRange, PixelBook, PixelError};
+use crate::services::{ExportService, FileService, PreviewBackground, ThumbnailService, DEFAULT_THUMBNAIL_SIZE, MAX_THUMBNAIL_SIZE};
+use crate::utils::validation;
+use pixl_format::convert;
+use pixl_format::embedded::{CodeLanguage, ColorFormat, EmbeddedOptions, EmbeddedOutput, RowOrder};
+use poem::{handler, http::header, web::{Path, Query}, Response, Result, Error};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+async fn load_for_export(file_service: &Arc<RwLock<FileService>>, filename: &str) -> Result<PixelBook> {
+    if !validation::validate_filename(filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    file_service.read().await.load_book(filename)
+        .map_err(|e| match e {
+            PixelError::FileNotFound { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })
+}
+
+fn attachment(filename: &str, extension: &str, content_type: &str, bytes: Vec<u8>) -> Response {
+    let stem = filename.trim_end_matches(".pxl");
+    Response::builder()
+        .content_type(content_type)
+        .header(header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.{}\"", stem, extension))
+        .body(bytes)
+}
+
+#[handler]
+pub async fn export_zip(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    filename: Path<String>,
+) -> Result<Response> {
+    let book = load_for_export(&file_service, &filename).await?;
+
+    let bytes = ExportService::frames_zip(&book)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    println!("📦 Exported {} frames of {} as ZIP", book.frames.len(), filename.as_str());
+
+    Ok(attachment(&filename, "zip", "application/zip", bytes))
+}
+
+#[handler]
+pub async fn export_embedded(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    filename: Path<String>,
+    Query(options): Query<EmbeddedOptions>,
+) -> Result<Response> {
+    let book = load_for_export(&file_service, &filename).await?;
+
+    let bytes = embedded_bytes(&book, &options)?;
+    println!("🔌 Exported {} for embedded targets ({:?}, {:?})", filename.as_str(), options.output, options.color);
+
+    Ok(embedded_attachment(&filename, options.output, bytes))
+}
+
+fn embedded_attachment(filename: &str, output: EmbeddedOutput, bytes: Vec<u8>) -> Response {
+    match output {
+        EmbeddedOutput::C => attachment(filename, "h", "text/x-c; charset=utf-8", bytes),
+        EmbeddedOutput::Rust => attachment(filename, "rs", "text/x-rust; charset=utf-8", bytes),
+        EmbeddedOutput::Binary => attachment(filename, "bin", "application/octet-stream", bytes),
+    }
+}
+
+// Unknown frames and too many colors for a palette are caller errors
+fn embedded_bytes(book: &PixelBook, options: &EmbeddedOptions) -> Result<Vec<u8>> {
+    ExportService::embedded(book, options)
+        .map_err(|e| match e {
+            PixelError::InvalidFormat { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::BAD_REQUEST),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })
+}
+
+/// Options of `export/raw`, the binary output of `export/embedded`
+#[derive(serde::Deserialize)]
+pub struct RawQuery {
+    /// Color format of each pixel
+    #[serde(default)]
+    format: ColorFormat,
+    #[serde(default)]
+    row_order: RowOrder,
+    #[serde(default)]
+    big_endian: bool,
+    /// A single frame instead of all of them
+    frame: Option<usize>,
+}
+
+/// Raw framebuffer bytes, ready to copy to a display's memory
+#[handler]
+pub async fn export_raw(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    filename: Path<String>,
+    Query(query): Query<RawQuery>,
+) -> Result<Response> {
+    let book = load_for_export(&file_service, &filename).await?;
+
+    let options = EmbeddedOptions {
+        output: EmbeddedOutput::Binary,
+        color: query.format,
+        row_order: query.row_order,
+        big_endian: query.big_endian,
+        frame: query.frame,
+    };
+    let bytes = embedded_bytes(&book, &options)?;
+    println!("🔌 Exported {} as raw {:?}", filename.as_str(), options.color);
+
+    Ok(attachment(&filename, "bin", "application/octet-stream", bytes))
+}
+
+/// Options of `export/code`, the source outputs of `export/embedded`
+#[derive(serde::Deserialize)]
+pub struct CodeQuery {
+    #[serde(default)]
+    lang: CodeLanguage,
+    /// Color format of each array element
+    #[serde(default)]
+    format: ColorFormat,
+    #[serde(default)]
+    row_order: RowOrder,
+    /// A single frame instead of all of them
+    frame: Option<usize>,
+}
+
+/// A C header or Rust module holding the frames as `const` arrays, with
+/// width, height and frame count constants
+#[handler]
+pub async fn export_code(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    filename: Path<String>,
+    Query(query): Query<CodeQuery>,
+) -> Result<Response> {
+    let book = load_for_export(&file_service, &filename).await?;
+
+    let options = EmbeddedOptions {
+        output: query.lang.into(),
+        color: query.format,
+        row_order: query.row_order,
+        big_endian: false,
+        frame: query.frame,
+    };
+    let bytes = embedded_bytes(&book, &options)?;
+    println!("🔌 Exported {} as {:?} source ({:?})", filename.as_str(), query.lang, options.color);
+
+    Ok(embedded_attachment(&filename, options.output, bytes))
+}
+
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+pub struct AsepriteQuery {
+    /// Frames per sheet row; a single row when omitted
+    columns: Option<u32>,
+}
+
+#[handler]
+pub async fn export_aseprite(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    filename: Path<String>,
+    Query(query): Query<AsepriteQuery>,
+) -> Result<Response> {
+    let book = load_for_export(&file_service, &filename).await?;
+
+    let bytes = ExportService::aseprite(&book, query.columns)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    println!("🗂️ Exported {} as an Aseprite sheet", filename.as_str());
+
+    Ok(attachment(&filename, "zip", "application/zip", bytes))
+}
+
+/// The Aseprite JSON of `export/aseprite` alone, for pipelines that fetch
+/// the sheet from `export/aseprite-png`
+#[handler]
+pub async fn export_aseprite_json(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    filename: Path<String>,
+    Query(query): Query<AsepriteQuery>,
+) -> Result<Response> {
+    let book = load_for_export(&file_service, &filename).await?;
+
+    let bytes = serde_json::to_vec_pretty(&ExportService::aseprite_json(&book, query.columns))
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    println!("🗂️ Exported {} as Aseprite JSON", filename.as_str());
+
+    Ok(attachment(&filename, "json", "application/json", bytes))
+}
+
+/// The sprite sheet of `export/aseprite` alone
+#[handler]
+pub async fn export_aseprite_png(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    filename: Path<String>,
+    Query(query): Query<AsepriteQuery>,
+) -> Result<Response> {
+    let book = load_for_export(&file_service, &filename).await?;
+
+    let bytes = ExportService::sprite_sheet(&book, query.columns)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    println!("🗂️ Exported {} as a sprite sheet", filename.as_str());
+
+    Ok(attachment(&filename, "png", "image/png", bytes))
+}
+
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+pub struct GifQuery {
+    /// Milliseconds each frame without its own duration shows for
+    delay_ms: Option<u32>,
+    /// Frames per second, as an alternative to `delay_ms`
+    fps: Option<u32>,
+}
+
+impl GifQuery {
+    fn frame_delay_ms(&self) -> Result<u32> {
+        let invalid = |message: &str| Err(Error::from_string(message.to_string(), poem::http::StatusCode::BAD_REQUEST));
+        match (self.delay_ms, self.fps) {
+            (Some(_), Some(_)) => invalid("Pass either delay_ms or fps, not both"),
+            (_, Some(0)) => invalid("fps must be at least 1"),
+            (_, Some(fps)) => Ok((1000 / fps).max(1)),
+            (delay_ms, None) => Ok(delay_ms.unwrap_or(convert::DEFAULT_FRAME_DELAY_MS)),
+        }
+    }
+}
+
+#[handler]
+pub async fn export_gif(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    filename: Path<String>,
+    Query(query): Query<GifQuery>,
+) -> Result<Response> {
+    let delay_ms = query.frame_delay_ms()?;
+    let book = load_for_export(&file_service, &filename).await?;
+
+    let bytes = ExportService::gif(&book, delay_ms)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    println!("🎞️ Exported {} frames of {} as GIF", book.frames.len(), filename.as_str());
+
+    Ok(attachment(&filename, "gif", "image/gif", bytes))
+}
+
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+pub struct ThumbnailQuery {
+    /// Longest side of the thumbnail in pixels, at most 512; smaller books keep their size
+    #[serde(default = "default_thumbnail_size")]
+    size: u16,
+}
+
+fn default_thumbnail_size() -> u16 {
+    DEFAULT_THUMBNAIL_SIZE
+}
+
+#[handler]
+pub async fn thumbnail(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    filename: Path<String>,
+    Query(query): Query<ThumbnailQuery>,
+) -> Result<Response> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+    if query.size == 0 || query.size > MAX_THUMBNAIL_SIZE {
+        return Err(Error::from_string(
+            format!("Thumbnail size must be between 1 and {}", MAX_THUMBNAIL_SIZE),
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let bytes = ThumbnailService::thumbnail(&*file_service.read().await, &filename, query.size)
+        .map_err(|e| match e {
+            PixelError::FileNotFound { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+
+    Ok(Response::builder()
+        .content_type("image/png")
+        .body(bytes))
+}
+
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+pub struct PngQuery {
+    /// 0-based frame to export
+    #[serde(default)]
+    frame: usize,
+    /// Draw each pixel as a `scale` x `scale` block
+    #[serde(default = "default_scale")]
+    scale: u32,
+}
+
+fn default_scale() -> u32 {
+    1
+}
+
+#[handler]
+pub async fn export_png(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    filename: Path<String>,
+    Query(query): Query<PngQuery>,
+) -> Result<Response> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let range = FrameRange::single(query.frame).ok_or_else(|| Error::from_string(
+        format!("Frame {} is out of range", query.frame),
+        poem::http::StatusCode::BAD_REQUEST,
+    ))?;
+    let (book, frame_count) = file_service.read().await.load_frames(&filename, &range)
+        .map_err(|e| match e {
+            PixelError::FileNotFound { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+    if book.frames.is_empty() {
+        return Err(Error::from_string(
+            format!("Frame {} does not exist; {} has {} frames", query.frame, filename.as_str(), frame_count),
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    // Only the requested frame was loaded
+    let bytes = ExportService::frame_png(&book, 0, query.scale)
+        .map_err(|e| match e {
+            PixelError::InvalidFormat { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::BAD_REQUEST),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+    println!("🖼️ Exported frame {} of {} as PNG", query.frame, filename.as_str());
+
+    let name = format!("{}_{:03}", filename.trim_end_matches(".pxl"), query.frame);
+    Ok(attachment(&name, "png", "image/png", bytes))
+}
+
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+pub struct PreviewQuery {
+    /// 0-based frame to preview
+    #[serde(default)]
+    frame: usize,
+    /// Draw each pixel as a `scale` x `scale` block
+    #[serde(default = "default_preview_scale")]
+    scale: u32,
+    /// `checker` (the default), `none`, or a `#rrggbb` color to show through transparency
+    #[serde(default)]
+    background: Option<String>,
+}
+
+fn default_preview_scale() -> u32 {
+    8
+}
+
+#[handler]
+pub async fn preview(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    filename: Path<String>,
+    Query(query): Query<PreviewQuery>,
+) -> Result<Response> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+    let background: PreviewBackground = match &query.background {
+        Some(background) => background.parse()
+            .map_err(|e: String| Error::from_string(e, poem::http::StatusCode::BAD_REQUEST))?,
+        None => PreviewBackground::default(),
+    };
+
+    let range = FrameRange::single(query.frame).ok_or_else(|| Error::from_string(
+        format!("Frame {} is out of range", query.frame),
+        poem::http::StatusCode::BAD_REQUEST,
+    ))?;
+    let (book, frame_count) = file_service.read().await.load_frames(&filename, &range)
+        .map_err(|e| match e {
+            PixelError::FileNotFound { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+    if book.frames.is_empty() {
+        return Err(Error::from_string(
+            format!("Frame {} does not exist; {} has {} frames", query.frame, filename.as_str(), frame_count),
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let bytes = ExportService::preview(&book, 0, query.scale, background)
+        .map_err(|e| match e {
+            PixelError::InvalidFormat { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::BAD_REQUEST),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+
+    // Served inline so chat clients and agents can show it as is
+    Ok(Response::builder()
+        .content_type("image/png")
+        .body(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gif_frame_delay() {
+        let query = |delay_ms, fps| GifQuery { delay_ms, fps };
+        assert_eq!(query(None, None).frame_delay_ms().unwrap(), convert::DEFAULT_FRAME_DELAY_MS);
+        assert_eq!(query(Some(40), None).frame_delay_ms().unwrap(), 40);
+        assert_eq!(query(None, Some(12)).frame_delay_ms().unwrap(), 83);
+        assert_eq!(query(None, Some(5000)).frame_delay_ms().unwrap(), 1);
+        assert!(query(None, Some(0)).frame_delay_ms().is_err());
+        assert!(query(Some(40), Some(12)).frame_delay_ms().is_err());
+    }
+}
@@ -0,0 +1,93 @@
+use crate::models::{LockRequest, PixelError};
+use crate::services::{FileService, LockService, DEFAULT_LOCK_TTL, LOCK_TOKEN_HEADER};
+use crate::utils::{permissions, validation};
+use poem::{handler, web::{Json, Path}, Request, Result, Error};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+fn lock_token(req: &Request) -> Option<&str> {
+    req.header(LOCK_TOKEN_HEADER)
+}
+
+fn lock_error(e: PixelError) -> Error {
+    let status = match e {
+        PixelError::BookLocked { .. } => poem::http::StatusCode::LOCKED,
+        _ => poem::http::StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    Error::from_string(e.to_string(), status)
+}
+
+/// Rejects a write with `423 Locked` when another client holds the book's lock
+pub(crate) fn check_lock(locks: &LockService, filename: &str, req: &Request) -> Result<()> {
+    locks.check(filename, lock_token(req)).map_err(|e| {
+        println!("🔐 Rejected write: {}", e);
+        lock_error(e)
+    })
+}
+
+#[handler]
+pub async fn lock_book(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    filename: Path<String>,
+    request: Json<LockRequest>,
+) -> Result<Json<serde_json::Value>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+    if request.holder.trim().is_empty() {
+        return Err(Error::from_string(
+            "A lock holder name is required",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+    
+    // Only clients that could write the book may lock others out of it
+    let service = file_service.read().await;
+    let metadata = service.load_metadata(&filename)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?
+        .ok_or_else(|| Error::from_string(
+            format!("File not found: {}", filename.as_str()),
+            poem::http::StatusCode::NOT_FOUND,
+        ))?;
+    permissions::check_write_access(&filename, &metadata.permissions, req.header(permissions::OWNER_KEY_HEADER))
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::FORBIDDEN))?;
+    
+    let ttl = request.ttl_seconds.map_or(DEFAULT_LOCK_TTL, |secs| Duration::from_secs(secs.max(1)));
+    let granted = lock_service.acquire(&filename, request.holder.trim(), ttl, lock_token(req))
+        .map_err(lock_error)?;
+    println!("🔐 {} locked by {} until {}", filename.as_str(), granted.lock.holder, granted.lock.expires_at);
+    
+    Ok(Json(json!(granted)))
+}
+
+#[handler]
+pub async fn unlock_book(
+    req: &Request,
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    filename: Path<String>,
+) -> Result<Json<serde_json::Value>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+    
+    let released = lock_service.release(&filename, lock_token(req)).map_err(lock_error)?;
+    if released {
+        println!("🔓 {} unlocked", filename.as_str());
+    }
+    
+    Ok(Json(json!({
+        "success": true,
+        "filename": filename.to_string(),
+        "released": released
+    })))
+}
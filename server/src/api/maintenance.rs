@@ -0,0 +1,27 @@
+use crate::services::{EventService, FileService, IntegrityService};
+use crate::tasks;
+use poem::{handler, web::Json, Result, Error};
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+#[handler]
+pub async fn scan_books(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    integrity_service: poem::web::Data<&Arc<IntegrityService>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+) -> Result<Json<serde_json::Value>> {
+    let report = tasks::run_integrity_scan(&file_service, &integrity_service, &event_service).await
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    
+    Ok(Json(json!(report)))
+}
+
+#[handler]
+pub async fn maintenance_status(
+    integrity_service: poem::web::Data<&Arc<IntegrityService>>,
+) -> Json<serde_json::Value> {
+    Json(json!({
+        "last_scan": integrity_service.last_report()
+    }))
+}
@@ -1,3 +1,13 @@
 pub mod path;
 pub mod books;
-pub mod events; 
\ No newline at end of file
+pub mod context;
+pub mod events;
+pub mod exports;
+pub mod locks;
+pub mod maintenance;
+pub mod openapi;
+pub mod snapshots;
+pub mod status;
+pub mod templates;
+pub mod tilemaps;
+pub mod trash;
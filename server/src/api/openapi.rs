@@ -0,0 +1,363 @@
+//! OpenAPI 3 description of the routes in [`crate::app::build_app`]. Request
+//! and response schemas are generated from the Rust types, so they follow
+//! the types; the route table below has to be kept in step with the router,
+//! which a test checks.
+
+use std::sync::LazyLock;
+
+use poem::{handler, web::{Html, Json}};
+use schemars::r#gen::{SchemaGenerator, SchemaSettings};
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde_json::{json, Map, Value};
+
+use crate::api::{books, events, exports, path, snapshots, templates, tilemaps, trash};
+use crate::models::*;
+
+static SPEC: LazyLock<Value> = LazyLock::new(spec);
+
+/// Swagger UI, loaded from a CDN, pointed at `/openapi.json`
+const SWAGGER_UI: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+  <title>PIXL API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });</script>
+</body>
+</html>
+"##;
+
+#[handler]
+pub fn openapi_json() -> Json<Value> {
+    Json(SPEC.clone())
+}
+
+#[handler]
+pub fn swagger_ui() -> Html<&'static str> {
+    Html(SWAGGER_UI)
+}
+
+/// Builds the document served at `/openapi.json`
+pub fn spec() -> Value {
+    let mut spec = Spec::new();
+
+    spec.get("/", "Health check").ok("Service name and health");
+    spec.get("/status", "Server version, uptime and book count").json::<ServerStatus>();
+    spec.get("/openapi.json", "This OpenAPI description").content("application/json", "OpenAPI 3 document");
+    spec.get("/docs", "Interactive API documentation").content("text/html", "Swagger UI page");
+    spec.get("/events", "Server-sent events for every book").query::<events::EventsQuery>().sse();
+    spec.get("/path", "Directory books are stored in").json::<path::PathResponse>();
+    spec.put("/path", "Change the directory books are stored in").body::<path::SetPathRequest>().json::<path::PathResponse>();
+
+    spec.get("/books", "List, filter and page through books").query::<BookFilter>().json::<BookList>();
+    spec.post("/books", "Create an empty book").body::<CreatePixelBookRequest>().ok("The new book's filename and path");
+    spec.post("/books/import-url", "Create a book from an image URL").body::<ImportUrlRequest>().ok("The new book's filename, path and size");
+    spec.post("/books/from-template", "Create a book from a template").body::<CreateFromTemplateRequest>().ok("The new book's filename, path and size");
+    spec.post("/batch", "Apply operations to several books, saving all or none").body::<BatchRequest>().json::<BatchResult>();
+    spec.get("/books/:filename", "Load a book, or a range of its frames").query::<books::BookQuery>().json::<PixelBook>();
+    spec.put("/books/:filename", "Apply drawing operations, or check them with dry_run").query::<books::UpdateQuery>().if_match().body::<UpdatePixelBookRequest>().ok("How many operations were applied, and what became of each");
+    spec.delete("/books/:filename", "Move a book to the trash").ok("The trash entry");
+    spec.get("/books/:filename/stream", "Stream a book's frames as newline-delimited JSON").query::<books::BookQuery>().content("application/x-ndjson", "A header line, then one line per frame");
+    spec.get("/books/:filename/frames/:index", "One frame as run-length text or base64 RGBA").query::<books::FrameQuery>().json::<EncodedFrame>();
+    spec.get("/books/:filename/frames/:index/grid", "One frame as a grid of hex colors").query::<books::GridQuery>().json::<ColorGrid>();
+    spec.get("/books/:filename/frames/:index/stats", "Color histogram and content bounds of one frame").query::<books::StatsQuery>().json::<FrameStats>();
+    spec.put("/books/:filename/frames/:index/duration", "Set or clear how long one frame shows").body::<FrameDuration>().json::<FrameDuration>();
+    spec.get("/books/:filename/frames/:index/pixels/:x/:y", "The color of one pixel").json::<PixelColor>();
+    spec.get("/books/:filename/diff", "Compare two frames, or a frame across snapshots").query::<DiffQuery>().json::<FrameComparison>();
+    spec.get("/books/:filename/events", "Server-sent events for one book").query::<events::EventsQuery>().sse();
+    spec.get("/books/:filename/context", "The drawing context of the caller's session").json::<DrawingContext>();
+    spec.put("/books/:filename/symmetry", "Set the symmetry later updates in the session use by default").body::<SetSymmetryRequest>().json::<DrawingContext>();
+    spec.put("/books/:filename/selection", "Limit later updates in the session to a selection").body::<ActiveSelection>().json::<DrawingContext>();
+    spec.delete("/books/:filename/selection", "Let later updates in the session draw anywhere again").json::<DrawingContext>();
+    spec.get("/books/:filename/events/history", "Events already sent for a book, oldest first").query::<events::EventHistoryQuery>().json::<events::EventHistoryResponse>();
+    spec.get("/books/:filename/export.zip", "Every frame as PNG in a zip archive").content("application/zip", "Zip of PNG frames");
+    spec.get("/books/:filename/export/embedded", "Frames as source code for embedded targets").content("text/plain", "Generated source");
+    spec.get("/books/:filename/export/code", "Frames as a C header or Rust module of const arrays").content("text/plain", "Generated source");
+    spec.get("/books/:filename/export/raw", "Raw framebuffer bytes in RGBA8888, RGB888, RGB565 or indexed color").content("application/octet-stream", "Pixel data with no header");
+    spec.get("/books/:filename/export/aseprite", "Sprite sheet PNG and Aseprite JSON in a zip archive").query::<exports::AsepriteQuery>().content("application/zip", "Sprite sheet and its JSON");
+    spec.get("/books/:filename/export/aseprite-json", "Aseprite json-array metadata for the sprite sheet").query::<exports::AsepriteQuery>().content("application/json", "Frame rectangles, durations and tags");
+    spec.get("/books/:filename/export/aseprite-png", "The sprite sheet described by /export/aseprite-json").query::<exports::AsepriteQuery>().content("image/png", "The sprite sheet");
+    spec.get("/books/:filename/export/spritesheet", "Alias of /export/aseprite").query::<exports::AsepriteQuery>().content("application/zip", "Sprite sheet and its JSON");
+    spec.get("/books/:filename/export/png", "One frame as PNG").query::<exports::PngQuery>().content("image/png", "The frame");
+    spec.get("/books/:filename/preview", "One frame enlarged over a checkerboard or solid background").query::<exports::PreviewQuery>().content("image/png", "The preview");
+    spec.get("/books/:filename/thumbnail", "Frame 0 shrunk for list views").query::<exports::ThumbnailQuery>().content("image/png", "The thumbnail");
+    spec.get("/books/:filename/export/gif", "Every frame as an animated GIF").query::<exports::GifQuery>().content("image/gif", "The animation");
+    spec.put("/books/:filename/permissions", "Set who may write a book").body::<SetPermissionsRequest>().ok("The new permissions");
+    spec.get("/books/:filename/palette", "The book's palette").json::<Palette>();
+    spec.put("/books/:filename/palette", "Replace the book's palette").body::<Palette>().json::<Palette>();
+    spec.get("/books/:filename/tileset", "The book's tile size and tile count").json::<TilesetInfo>();
+    spec.put("/books/:filename/tileset", "Declare the book a tileset of the given tile size").body::<Tileset>().json::<TilesetInfo>();
+    spec.get("/books/:filename/metadata", "Title, author, tags and description").json::<BookDetails>();
+    spec.patch("/books/:filename/metadata", "Change some of the title, author, tags and description").body::<UpdateDetailsRequest>().json::<BookDetails>();
+    spec.post("/books/:filename/lock", "Take or renew a book's edit lock").body::<LockRequest>().json::<LockResponse>();
+    spec.post("/books/:filename/unlock", "Release a book's edit lock").ok("Whether a lock was released");
+    spec.get("/books/:filename/operations", "Operation log, oldest first").query::<books::OperationLogQuery>().ok("The log entries");
+    spec.post("/books/:filename/undo", "Undo the latest batch of operations").ok("Whether a batch was undone");
+    spec.post("/books/:filename/redo", "Redo the latest undone batch").ok("Whether a batch was redone");
+    spec.post("/books/:filename/rename", "Rename a book").body::<RenameBookRequest>().ok("The old and new filenames");
+    spec.post("/books/:filename/copy", "Copy a book").body::<RenameBookRequest>().ok("The source and copy filenames");
+    spec.post("/books/:filename/quantize", "Reduce a book to a palette").body::<QuantizeRequest>().json::<QuantizeResult>();
+    spec.post("/books/:filename/resize", "Change the canvas size of every frame").body::<ResizeRequest>().ok("The book's new size and revision");
+    spec.post("/books/:filename/crop", "Crop every frame to a rectangle").body::<Rect>().ok("The book's new size and revision");
+    spec.post("/books/:filename/trim", "Crop every frame to the bounds of the book's visible pixels").ok("The book's new size and revision, and where the kept area started");
+    spec.post("/books/:filename/scale", "Scale every frame by a whole factor with nearest-neighbour sampling").query::<books::ScaleQuery>().ok("The book's new size and revision");
+    spec.post("/books/:filename/migrate", "Rewrite a book in another format version").query::<books::MigrateQuery>().body::<MigrateRequest>().json::<MigrateResult>();
+    spec.get("/books/:filename/snapshots", "Snapshots of a book, newest first").json::<snapshots::SnapshotsResponse>();
+    spec.post("/books/:filename/snapshots", "Take a named snapshot").body::<CreateSnapshotRequest>().json::<SnapshotInfo>();
+    spec.post("/books/:filename/snapshots/:id/restore", "Restore a snapshot").ok("The restored snapshot");
+
+    spec.post("/maintenance/scan", "Check every book for damage").json::<ScanReport>();
+    spec.get("/maintenance/status", "The latest integrity scan").ok("The latest scan report, if any");
+    spec.get("/templates", "Built-in and custom book templates").json::<templates::TemplatesResponse>();
+    spec.get("/tilemaps", "Every tilemap, sorted by name").json::<tilemaps::TilemapsResponse>();
+    spec.get("/tilemaps/:name", "A tilemap's grid of tile indices").json::<Tilemap>();
+    spec.put("/tilemaps/:name", "Create or replace a tilemap").body::<TilemapRequest>().json::<Tilemap>();
+    spec.delete("/tilemaps/:name", "Delete a tilemap").ok("The deleted tilemap's name");
+    spec.patch("/tilemaps/:name/tiles", "Place or clear some of a tilemap's tiles").body::<SetTilesRequest>().json::<Tilemap>();
+    spec.get("/tilemaps/:name/export/png", "The tilemap composited from its tileset").content("image/png", "The composited map");
+    spec.get("/tilemaps/:name/export/json", "The tilemap in Tiled's JSON map format").content("application/json", "A Tiled map with one tile layer");
+    spec.get("/trash", "Trashed books, newest first").json::<trash::TrashResponse>();
+    spec.post("/trash/:filename/restore", "Restore the latest trashed copy of a book").ok("The restored trash entry");
+
+    spec.finish()
+}
+
+struct Spec {
+    generator: SchemaGenerator,
+    paths: Map<String, Value>,
+}
+
+impl Spec {
+    fn new() -> Self {
+        Self { generator: SchemaSettings::openapi3().into_generator(), paths: Map::new() }
+    }
+
+    fn get(&mut self, route: &str, summary: &str) -> Operation<'_> {
+        self.operation("get", route, summary)
+    }
+
+    fn put(&mut self, route: &str, summary: &str) -> Operation<'_> {
+        self.operation("put", route, summary)
+    }
+
+    fn post(&mut self, route: &str, summary: &str) -> Operation<'_> {
+        self.operation("post", route, summary)
+    }
+
+    fn patch(&mut self, route: &str, summary: &str) -> Operation<'_> {
+        self.operation("patch", route, summary)
+    }
+
+    fn delete(&mut self, route: &str, summary: &str) -> Operation<'_> {
+        self.operation("delete", route, summary)
+    }
+
+    // Adds the operation with its path parameters, taking them from the
+    // poem route's `:name` segments
+    fn operation(&mut self, method: &str, route: &str, summary: &str) -> Operation<'_> {
+        let mut parameters = Vec::new();
+        let segments: Vec<String> = route.split('/')
+            .map(|segment| match segment.strip_prefix(':') {
+                Some(name) => {
+                    parameters.push(path_parameter(name));
+                    format!("{{{}}}", name)
+                }
+                None => segment.to_string(),
+            })
+            .collect();
+        let path = segments.join("/");
+
+        let operation = json!({
+            "summary": summary,
+            "parameters": parameters,
+            "responses": {
+                "default": { "description": "Error, with the message as plain text" },
+            },
+        });
+        self.paths.entry(path.clone())
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .expect("path item is an object")
+            .insert(method.to_string(), operation);
+        Operation { spec: self, path, method: method.to_string() }
+    }
+
+    fn finish(self) -> Value {
+        json!({
+            "openapi": "3.0.3",
+            "info": {
+                "title": "PIXL Server",
+                "version": env!("CARGO_PKG_VERSION"),
+                "description": "Pixel books and drawing operations over HTTP.",
+            },
+            "paths": self.paths,
+            "components": { "schemas": self.generator.definitions() },
+        })
+    }
+}
+
+fn path_parameter(name: &str) -> Value {
+    let (schema, description) = match name {
+        "filename" => (json!({ "type": "string" }), "Book filename, such as `hero.pxl`; percent-encode the `/` of books in sub-folders"),
+        "index" => (json!({ "type": "integer", "minimum": 0 }), "0-based frame index"),
+        "id" => (json!({ "type": "integer" }), "Snapshot id"),
+        "name" => (json!({ "type": "string" }), "Tilemap name: letters, digits, `-` and `_`"),
+        _ => (json!({ "type": "integer", "minimum": 0 }), "Pixel coordinate"),
+    };
+    json!({ "name": name, "in": "path", "required": true, "schema": schema, "description": description })
+}
+
+/// One route being described; each method fills in part of it
+struct Operation<'a> {
+    spec: &'a mut Spec,
+    path: String,
+    method: String,
+}
+
+impl Operation<'_> {
+    fn entry(&mut self) -> &mut Value {
+        &mut self.spec.paths[&self.path][&self.method]
+    }
+
+    fn schema<T: JsonSchema>(&mut self) -> Value {
+        serde_json::to_value(self.spec.generator.subschema_for::<T>()).expect("schemas serialize")
+    }
+
+    /// Lists the fields of `T` as query parameters
+    fn query<T: JsonSchema>(mut self) -> Self {
+        let schema = T::json_schema(&mut self.spec.generator).into_object();
+        let Some(object) = schema.object else { return self };
+
+        let parameters: Vec<Value> = object.properties.into_iter()
+            .map(|(name, property)| {
+                let required = object.required.contains(&name);
+                let mut property = match property {
+                    Schema::Object(property) => property,
+                    Schema::Bool(_) => Default::default(),
+                };
+                let description = property.metadata.as_mut().and_then(|metadata| metadata.description.take());
+                let mut parameter = json!({ "name": name, "in": "query", "required": required, "schema": property });
+                if let Some(description) = description {
+                    parameter["description"] = description.into();
+                }
+                parameter
+            })
+            .collect();
+        self.entry()["parameters"].as_array_mut().expect("parameters are a list").extend(parameters);
+        self
+    }
+
+    /// Accepts the book's ETag as `If-Match`
+    fn if_match(mut self) -> Self {
+        self.entry()["parameters"].as_array_mut().expect("parameters are a list").push(json!({
+            "name": "If-Match",
+            "in": "header",
+            "required": false,
+            "description": "ETag of the revision the update was made against; a stale one gives 412 Precondition Failed",
+            "schema": { "type": "string" },
+        }));
+        self
+    }
+
+    fn body<T: JsonSchema>(mut self) -> Self {
+        let schema = self.schema::<T>();
+        self.entry()["requestBody"] = json!({
+            "required": true,
+            "content": { "application/json": { "schema": schema } },
+        });
+        self
+    }
+
+    fn json<T: JsonSchema>(mut self) -> Self {
+        let schema = self.schema::<T>();
+        self.entry()["responses"]["200"] = json!({
+            "description": "OK",
+            "content": { "application/json": { "schema": schema } },
+        });
+        self
+    }
+
+    /// A JSON object response without a generated schema
+    fn ok(mut self, description: &str) -> Self {
+        self.entry()["responses"]["200"] = json!({
+            "description": description,
+            "content": { "application/json": { "schema": { "type": "object" } } },
+        });
+        self
+    }
+
+    fn content(mut self, mime: &str, description: &str) -> Self {
+        self.entry()["responses"]["200"] = json!({
+            "description": description,
+            "content": { mime: { "schema": { "type": "string", "format": "binary" } } },
+        });
+        self
+    }
+
+    fn sse(self) -> Self {
+        self.content("text/event-stream", "Server-sent events with JSON data")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_describes_update_book() {
+        let spec = spec();
+        let update = &spec["paths"]["/books/{filename}"]["put"];
+        assert_eq!(update["parameters"][0]["name"], "filename");
+        assert_eq!(update["requestBody"]["content"]["application/json"]["schema"]["$ref"], "#/components/schemas/UpdatePixelBookRequest");
+
+        let schemas = &spec["components"]["schemas"];
+        let operations = schemas["DrawingOperation"]["oneOf"].as_array().unwrap();
+        assert!(operations.iter().any(|op| op["properties"]["type"]["enum"][0] == "draw_pixel"));
+
+        let list = &spec["paths"]["/books"]["get"]["parameters"];
+        assert!(list.as_array().unwrap().iter().any(|p| p["name"] == "recursive" && p["in"] == "query"));
+    }
+
+    // Every `.at(path, method(..).method(..))` registered in `build_app`, as
+    // OpenAPI paths paired with lowercase methods. poem's router cannot be
+    // listed, so the routes are read from the source.
+    fn registered_routes() -> Vec<(String, &'static str)> {
+        const METHODS: [&str; 5] = ["get", "put", "post", "delete", "patch"];
+        let mut routes = Vec::new();
+        for line in include_str!("../app.rs").lines() {
+            let Some(rest) = line.trim().strip_prefix(".at(\"") else { continue };
+            let Some((path, handlers)) = rest.split_once('"') else { continue };
+            let path = path.split('/')
+                .map(|segment| match segment.strip_prefix(':') {
+                    Some(name) => format!("{{{}}}", name),
+                    None => segment.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+            for (start, _) in handlers.match_indices('(') {
+                let word = handlers[..start].rsplit(|c: char| !c.is_alphanumeric() && c != '_').next().unwrap_or_default();
+                if let Some(method) = METHODS.iter().find(|method| **method == word) {
+                    routes.push((path.clone(), *method));
+                }
+            }
+        }
+        routes
+    }
+
+    #[test]
+    fn test_spec_covers_every_route() {
+        let routes = registered_routes();
+        assert!(routes.contains(&("/books/{filename}".to_string(), "delete")));
+        assert!(routes.contains(&("/tilemaps/{name}/tiles".to_string(), "patch")));
+
+        let spec = spec();
+        let missing: Vec<_> = routes.iter()
+            .filter(|(path, method)| spec["paths"][path.as_str()][*method].is_null())
+            .collect();
+        assert!(missing.is_empty(), "routes missing from the OpenAPI spec: {:?}", missing);
+    }
+}
@@ -1,15 +1,15 @@
-use crate::services::FileService;
+use crate::services::{EventService, FileService, SettingsService};
 use poem::{handler, web::Json, Result, Error};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, schemars::JsonSchema)]
 pub struct SetPathRequest {
     pub path: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, schemars::JsonSchema)]
 pub struct PathResponse {
     pub path: String,
 }
@@ -27,13 +27,21 @@ pub async fn get_path(
 #[handler]
 pub async fn set_path(
     file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    settings_service: poem::web::Data<&Arc<SettingsService>>,
     request: Json<SetPathRequest>,
 ) -> Result<Json<PathResponse>> {
     let mut service = file_service.write().await;
     let new_path = std::path::PathBuf::from(&request.path);
     
-    service.set_path(new_path)
+    service.set_path(new_path.clone())
         .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::BAD_REQUEST))?;
+    event_service.read().await.move_history(service.get_path().to_path_buf());
+    
+    // The new path is already in use; failing to keep it only matters after a restart
+    if let Err(e) = settings_service.set_path(&new_path) {
+        println!("⚠️ Failed to save settings: {}", e);
+    }
     
     Ok(Json(PathResponse { 
         path: request.path.clone() 
@@ -0,0 +1,116 @@
+use crate::models::{CreateSnapshotRequest, PixelError, SnapshotInfo};
+use crate::api::locks::check_lock;
+use crate::services::{EventService, FileService, LockService, SnapshotService};
+use crate::utils::{permissions, validation};
+use poem::{handler, web::{Json, Path}, Request, Result, Error};
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+#[derive(serde::Serialize, schemars::JsonSchema)]
+pub struct SnapshotsResponse {
+    snapshots: Vec<SnapshotInfo>,
+}
+
+#[handler]
+pub async fn list_snapshots(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    snapshot_service: poem::web::Data<&Arc<SnapshotService>>,
+    filename: Path<String>,
+) -> Result<Json<SnapshotsResponse>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let service = file_service.read().await;
+    let snapshots = snapshot_service.list_snapshots(service.get_path(), &filename)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+
+    Ok(Json(SnapshotsResponse { snapshots }))
+}
+
+#[handler]
+pub async fn create_snapshot(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    snapshot_service: poem::web::Data<&Arc<SnapshotService>>,
+    filename: Path<String>,
+    request: Json<CreateSnapshotRequest>,
+) -> Result<Json<SnapshotInfo>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    // Hold the write lock so the copy never sees a half-written book
+    let service = file_service.write().await;
+    let metadata = service.load_metadata(&filename)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?
+        .ok_or_else(|| Error::from_string(
+            format!("File not found: {}", filename.as_str()),
+            poem::http::StatusCode::NOT_FOUND,
+        ))?;
+    permissions::check_write_access(&filename, &metadata.permissions, req.header(permissions::OWNER_KEY_HEADER))
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::FORBIDDEN))?;
+
+    let snapshot = snapshot_service.checkpoint_book(service.get_path(), &filename, &request.name)
+        .map_err(|e| match e {
+            PixelError::InvalidFormat { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::BAD_REQUEST),
+            PixelError::FileNotFound { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+    println!("📸 Saved snapshot \"{}\" of {}", request.name.trim(), filename.as_str());
+
+    Ok(Json(snapshot))
+}
+
+#[handler]
+pub async fn restore_snapshot(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    snapshot_service: poem::web::Data<&Arc<SnapshotService>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    Path((filename, id)): Path<(String, i64)>,
+) -> Result<Json<serde_json::Value>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let service = file_service.write().await;
+
+    // Restoring overwrites the live book, so it needs the same access as a write
+    let metadata = service.load_metadata(&filename)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    if let Some(metadata) = metadata {
+        permissions::check_write_access(&filename, &metadata.permissions, req.header(permissions::OWNER_KEY_HEADER))
+            .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::FORBIDDEN))?;
+    }
+    check_lock(&lock_service, &filename, req)?;
+
+    let snapshot = snapshot_service.restore_snapshot(service.get_path(), &filename, id)
+        .map_err(|e| match e {
+            PixelError::FileNotFound { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+    println!("⏪ Restored {} from snapshot {}", filename, snapshot.created_at);
+
+    event_service.read().await.on_book_restored(&filename).await;
+
+    Ok(Json(json!({
+        "success": true,
+        "filename": filename,
+        "snapshot": snapshot
+    })))
+}
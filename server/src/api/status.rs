@@ -0,0 +1,33 @@
+use crate::models::ServerStatus;
+use crate::services::{EventService, FileService};
+use chrono::{DateTime, Utc};
+use poem::{handler, web::Json, Result, Error};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// When the server was started, for the uptime in `GET /status`
+#[derive(Debug, Clone, Copy)]
+pub struct StartedAt(pub DateTime<Utc>);
+
+#[handler]
+pub async fn server_status(
+    started_at: poem::web::Data<&StartedAt>,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+) -> Result<Json<ServerStatus>> {
+    let service = file_service.read().await;
+    let books = service.list_books(true)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    let active_clients = event_service.read().await.active_clients();
+    let StartedAt(started_at) = **started_at;
+
+    Ok(Json(ServerStatus {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        started_at,
+        uptime_seconds: (Utc::now() - started_at).num_seconds().max(0) as u64,
+        path: service.get_path().to_string_lossy().to_string(),
+        book_count: books.len(),
+        disk_usage: books.iter().map(|book| book.size).sum(),
+        active_clients,
+    }))
+}
@@ -0,0 +1,73 @@
+use crate::models::{CreateFromTemplateRequest, PixelError, TemplateInfo};
+use crate::api::locks::check_lock;
+use crate::services::{FileService, LockService, TemplateService};
+use crate::utils::{permissions, validation};
+use poem::{handler, web::Json, Request, Result, Error};
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+#[derive(serde::Serialize, schemars::JsonSchema)]
+pub struct TemplatesResponse {
+    templates: Vec<TemplateInfo>,
+}
+
+#[handler]
+pub async fn list_templates(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+) -> Result<Json<TemplatesResponse>> {
+    let service = file_service.read().await;
+    let templates = TemplateService::list_templates(service.get_path())
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+
+    Ok(Json(TemplatesResponse { templates }))
+}
+
+#[handler]
+pub async fn create_from_template(
+    req: &Request,
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    lock_service: poem::web::Data<&Arc<LockService>>,
+    request: Json<CreateFromTemplateRequest>,
+) -> Result<Json<serde_json::Value>> {
+    if !validation::validate_filename(&request.filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let service = file_service.write().await;
+
+    // Creating over an existing book replaces it, so it needs write access
+    let existing = service.load_metadata(&request.filename)
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+    if let Some(metadata) = existing {
+        permissions::check_write_access(&request.filename, &metadata.permissions, req.header(permissions::OWNER_KEY_HEADER))
+            .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::FORBIDDEN))?;
+    }
+    check_lock(&lock_service, &request.filename, req)?;
+
+    let book = TemplateService::instantiate(service.get_path(), &request.template, &request.filename)
+        .and_then(|book| service.import_book(&book).map(|_| book))
+        .map_err(|e| match e {
+            PixelError::TemplateNotFound { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+            PixelError::InvalidFormat { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::BAD_REQUEST),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+    println!("🧩 Created {} from template {}", book.filename, request.template);
+
+    let full_path = service.get_path().join(&request.filename);
+
+    Ok(Json(json!({
+        "success": true,
+        "filename": book.filename,
+        "template": request.template,
+        "path": full_path.to_string_lossy(),
+        "width": book.width,
+        "height": book.height,
+        "frames": book.frames.len()
+    })))
+}
@@ -0,0 +1,121 @@
+use crate::models::{PixelError, SetTilesRequest, Tilemap, TilemapRequest, TilemapSummary};
+use crate::services::{FileService, TilemapService};
+use poem::{handler, http::header, web::{Json, Path}, Response, Result, Error};
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+#[derive(serde::Serialize, schemars::JsonSchema)]
+pub struct TilemapsResponse {
+    tilemaps: Vec<TilemapSummary>,
+}
+
+fn tilemap_error(e: PixelError) -> Error {
+    match e {
+        PixelError::TilemapNotFound { .. } | PixelError::FileNotFound { .. } =>
+            Error::from_string(e.to_string(), poem::http::StatusCode::NOT_FOUND),
+        PixelError::InvalidFormat { .. } =>
+            Error::from_string(e.to_string(), poem::http::StatusCode::BAD_REQUEST),
+        _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[handler]
+pub async fn list_tilemaps(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+) -> Result<Json<TilemapsResponse>> {
+    let service = file_service.read().await;
+    let tilemaps = TilemapService::list_tilemaps(service.get_path())
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+
+    Ok(Json(TilemapsResponse { tilemaps }))
+}
+
+#[handler]
+pub async fn get_tilemap(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    name: Path<String>,
+) -> Result<Json<Tilemap>> {
+    let service = file_service.read().await;
+    TilemapService::load_tilemap(service.get_path(), &name)
+        .map(Json)
+        .map_err(tilemap_error)
+}
+
+#[handler]
+pub async fn put_tilemap(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    name: Path<String>,
+    request: Json<TilemapRequest>,
+) -> Result<Json<Tilemap>> {
+    let tilemap = request.0.into_tilemap(&name);
+
+    let service = file_service.write().await;
+    TilemapService::validate(&service, &tilemap).map_err(tilemap_error)?;
+    let replaced = TilemapService::save_tilemap(service.get_path(), &tilemap).map_err(tilemap_error)?;
+    println!("🗺️ {} {}x{} tilemap {} from {}", if replaced { "Replaced" } else { "Created" },
+        tilemap.columns, tilemap.rows, tilemap.name, tilemap.tileset);
+
+    Ok(Json(tilemap))
+}
+
+#[handler]
+pub async fn set_tiles(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    name: Path<String>,
+    request: Json<SetTilesRequest>,
+) -> Result<Json<Tilemap>> {
+    let service = file_service.write().await;
+    let mut tilemap = TilemapService::load_tilemap(service.get_path(), &name).map_err(tilemap_error)?;
+    tilemap.set_tiles(&request.cells)
+        .map_err(|e| Error::from_string(e, poem::http::StatusCode::BAD_REQUEST))?;
+    TilemapService::validate(&service, &tilemap).map_err(tilemap_error)?;
+    TilemapService::save_tilemap(service.get_path(), &tilemap).map_err(tilemap_error)?;
+    println!("🗺️ Placed {} tiles in {}", request.cells.len(), tilemap.name);
+
+    Ok(Json(tilemap))
+}
+
+#[handler]
+pub async fn delete_tilemap(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    name: Path<String>,
+) -> Result<Json<serde_json::Value>> {
+    let service = file_service.write().await;
+    TilemapService::delete_tilemap(service.get_path(), &name).map_err(tilemap_error)?;
+    println!("🗑️ Deleted tilemap {}", name.as_str());
+
+    Ok(Json(json!({
+        "success": true,
+        "name": name.as_str()
+    })))
+}
+
+#[handler]
+pub async fn export_tilemap_png(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    name: Path<String>,
+) -> Result<Response> {
+    let service = file_service.read().await;
+    let tilemap = TilemapService::load_tilemap(service.get_path(), &name).map_err(tilemap_error)?;
+    let bytes = TilemapService::render_png(&service, &tilemap).map_err(tilemap_error)?;
+    println!("🗺️ Exported tilemap {} as PNG", tilemap.name);
+
+    Ok(Response::builder()
+        .content_type("image/png")
+        .header(header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.png\"", tilemap.name))
+        .body(bytes))
+}
+
+#[handler]
+pub async fn export_tilemap_json(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    name: Path<String>,
+) -> Result<Json<serde_json::Value>> {
+    let service = file_service.read().await;
+    let tilemap = TilemapService::load_tilemap(service.get_path(), &name).map_err(tilemap_error)?;
+    let map = TilemapService::tiled_json(&service, &tilemap).map_err(tilemap_error)?;
+    println!("🗺️ Exported tilemap {} as Tiled JSON", tilemap.name);
+
+    Ok(Json(map))
+}
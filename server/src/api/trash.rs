@@ -0,0 +1,56 @@
+use crate::models::{PixelError, TrashEntry};
+use crate::services::{EventService, FileService};
+use crate::utils::validation;
+use poem::{handler, web::{Json, Path}, Result, Error};
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+#[derive(serde::Serialize, schemars::JsonSchema)]
+pub struct TrashResponse {
+    books: Vec<TrashEntry>,
+}
+
+#[handler]
+pub async fn list_trash(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+) -> Result<Json<TrashResponse>> {
+    let service = file_service.read().await;
+    let books = service.list_trash()
+        .map_err(|e| Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR))?;
+
+    Ok(Json(TrashResponse { books }))
+}
+
+#[handler]
+pub async fn restore_book(
+    file_service: poem::web::Data<&Arc<RwLock<FileService>>>,
+    event_service: poem::web::Data<&Arc<RwLock<EventService>>>,
+    filename: Path<String>,
+) -> Result<Json<serde_json::Value>> {
+    if !validation::validate_filename(&filename) {
+        return Err(Error::from_string(
+            "Invalid filename",
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    let service = file_service.write().await;
+    let entry = service.restore_book(&filename)
+        .map_err(|e| match e {
+            PixelError::FileNotFound { .. } =>
+                Error::from_string(format!("No trashed copy of {}", filename.as_str()), poem::http::StatusCode::NOT_FOUND),
+            PixelError::AlreadyExists { .. } =>
+                Error::from_string(e.to_string(), poem::http::StatusCode::CONFLICT),
+            _ => Error::from_string(e.to_string(), poem::http::StatusCode::INTERNAL_SERVER_ERROR),
+        })?;
+    println!("♻️ Restored {} from trash", filename.as_str());
+
+    event_service.read().await.on_book_restored(&filename).await;
+
+    Ok(Json(json!({
+        "success": true,
+        "filename": entry.filename,
+        "deleted_at": entry.deleted_at
+    })))
+}
@@ -0,0 +1,454 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use poem::{
+    get, handler, patch, post, put,
+    listener::{Acceptor, Listener, TcpListener},
+    middleware::Cors,
+    web::Json,
+    Endpoint, EndpointExt, Route, Server,
+};
+use tokio::sync::{oneshot, RwLock};
+use tokio::task::JoinHandle;
+
+use crate::api::{books, context, events, exports, locks, maintenance, openapi, path, snapshots, status, templates, tilemaps, trash};
+use crate::api::books::RevisionSettings;
+use crate::api::events::SseSettings;
+use crate::services::{
+    EventService, FileService, ImportService, IntegrityService, LockService, SettingsService, SnapshotService, UndoService,
+    DEFAULT_INTEGRITY_SCAN_INTERVAL, DEFAULT_MAX_IMPORT_BYTES, DEFAULT_SNAPSHOT_INTERVAL, DEFAULT_SNAPSHOT_RETENTION, DEFAULT_TRASH_RETENTION, DEFAULT_UNDO_DEPTH,
+};
+use crate::tasks;
+
+/// Settings needed to start a server instance
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Address to listen on; use port 0 to pick an ephemeral port
+    pub bind: String,
+    /// Directory pixel books are read from and written to
+    pub base_path: PathBuf,
+    /// How long deleted books stay in the trash before being purged
+    pub trash_retention: Duration,
+    /// How often modified books are snapshotted; `None` disables autosave
+    pub snapshot_interval: Option<Duration>,
+    /// Snapshots kept per book
+    pub snapshot_retention: usize,
+    /// Hosts `POST /books/import-url` may fetch from (subdomains included); `None` allows any
+    pub import_allowed_hosts: Option<Vec<String>>,
+    /// Largest image `POST /books/import-url` will download
+    pub max_import_bytes: u64,
+    /// Save every book in the newest format version instead of the oldest one that fits
+    pub auto_upgrade: bool,
+    /// Save books as format v5 with run-length encoded frames
+    pub compress_frames: bool,
+    /// Window in which operation bursts become one `operations_applied` event;
+    /// `None`, the default, sends a `drawing_operation` event per operation so
+    /// viewers can preview each one
+    pub event_coalesce_window: Option<Duration>,
+    /// Write every book event to the book's history under `.events` so it can
+    /// be replayed later
+    pub event_history: bool,
+    /// Idle time after which SSE connections receive a heartbeat
+    pub sse_heartbeat_interval: Duration,
+    /// How long an SSE message may wait on a slow client before it is disconnected
+    pub sse_send_timeout: Duration,
+    /// How often every book is checked for damage; `None` disables the
+    /// background scan, though `POST /maintenance/scan` still works
+    pub integrity_scan_interval: Option<Duration>,
+    /// File that settings changed at runtime, such as the `PUT /path`
+    /// directory, are saved to and restored from at startup; `None` keeps
+    /// them in memory only
+    pub settings_file: Option<PathBuf>,
+    /// Batches each book can undo; 0 turns undo off
+    pub undo_depth: usize,
+    /// Directory `PUT /path` may not leave; `None` allows any directory
+    pub allowed_root: Option<PathBuf>,
+    /// Refuse `PUT /books/:filename` without an `If-Match` header naming the
+    /// revision the update was made against
+    pub require_if_match: bool,
+    /// Origins browsers may call the API from, such as `https://example.com`
+    /// or `https://*.example.com`; `*` allows any. Empty sends no CORS headers.
+    pub cors_origins: Vec<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: "0.0.0.0:3000".to_string(),
+            base_path: dirs::home_dir().unwrap_or_else(|| PathBuf::from(".")),
+            trash_retention: DEFAULT_TRASH_RETENTION,
+            snapshot_interval: Some(DEFAULT_SNAPSHOT_INTERVAL),
+            snapshot_retention: DEFAULT_SNAPSHOT_RETENTION,
+            import_allowed_hosts: None,
+            max_import_bytes: DEFAULT_MAX_IMPORT_BYTES,
+            auto_upgrade: false,
+            compress_frames: false,
+            event_coalesce_window: None,
+            event_history: true,
+            sse_heartbeat_interval: events::DEFAULT_HEARTBEAT_INTERVAL,
+            sse_send_timeout: events::DEFAULT_SEND_TIMEOUT,
+            integrity_scan_interval: Some(DEFAULT_INTEGRITY_SCAN_INTERVAL),
+            settings_file: None,
+            undo_depth: DEFAULT_UNDO_DEPTH,
+            allowed_root: None,
+            require_if_match: false,
+            cors_origins: Vec::new(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// The defaults with `PIXL_HOST`, `PIXL_PORT`, `PIXL_BOOKS_PATH`,
+    /// `PIXL_ALLOWED_ROOT`, `PIXL_REQUIRE_IF_MATCH` and `PIXL_COMPRESS_FRAMES` applied, saving runtime settings to `PIXL_SETTINGS_FILE` (the user's
+    /// config directory by default). A path saved there by an earlier run
+    /// takes precedence over `PIXL_BOOKS_PATH`.
+    pub fn from_env() -> Self {
+        Self::from_vars(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let mut config = Self::default();
+        config.apply_vars(var);
+        config
+    }
+
+    /// Overrides settings with the environment variables [`ServerConfig::from_env`] reads
+    pub(crate) fn apply_vars(&mut self, var: impl Fn(&str) -> Option<String>) {
+        self.set_address(var("PIXL_HOST"), var("PIXL_PORT"));
+        if let Some(path) = var("PIXL_BOOKS_PATH") {
+            self.base_path = PathBuf::from(path);
+        }
+        if let Some(root) = var("PIXL_ALLOWED_ROOT") {
+            self.allowed_root = Some(PathBuf::from(root));
+        }
+        if let Some(require) = var("PIXL_REQUIRE_IF_MATCH") {
+            self.require_if_match = matches!(require.as_str(), "1" | "true");
+        }
+        if let Some(compress) = var("PIXL_COMPRESS_FRAMES") {
+            self.compress_frames = matches!(compress.as_str(), "1" | "true");
+        }
+        self.settings_file = var("PIXL_SETTINGS_FILE")
+            .map(PathBuf::from)
+            .or(self.settings_file.take())
+            .or_else(|| dirs::config_dir().map(|dir| dir.join("pixl").join("server.json")));
+    }
+
+    /// Replaces the host and/or port of `bind`, keeping the other part
+    pub fn set_address(&mut self, host: Option<String>, port: Option<String>) {
+        let (current_host, current_port) = self.bind.rsplit_once(':')
+            .map(|(host, port)| (host.to_string(), port.to_string()))
+            .unwrap_or_default();
+        self.bind = format!("{}:{}", host.unwrap_or(current_host), port.unwrap_or(current_port));
+    }
+}
+
+#[handler]
+fn health_check() -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "status": "healthy",
+        "service": "pixl-server"
+    }))
+}
+
+/// All API routes, without any shared state attached
+pub fn build_app() -> Route {
+    Route::new()
+        .at("/", get(health_check))
+        .at("/status", get(status::server_status))
+        .at("/openapi.json", get(openapi::openapi_json))
+        .at("/docs", get(openapi::swagger_ui))
+        .at("/events", get(events::workspace_events))
+        .at("/path", get(path::get_path).put(path::set_path))
+        .at("/books", get(books::list_books).post(books::create_book))
+        .at("/books/import-url", post(books::import_url))
+        .at("/books/from-template", post(templates::create_from_template))
+        .at("/batch", post(books::batch_update))
+        .at("/books/:filename", get(books::get_book).put(books::update_book).delete(books::delete_book))
+        .at("/books/:filename/stream", get(books::stream_book))
+        .at("/books/:filename/frames/:index", get(books::get_frame))
+        .at("/books/:filename/frames/:index/grid", get(books::frame_grid))
+        .at("/books/:filename/frames/:index/stats", get(books::get_frame_stats))
+        .at("/books/:filename/frames/:index/duration", put(books::set_frame_duration))
+        .at("/books/:filename/frames/:index/pixels/:x/:y", get(books::get_pixel))
+        .at("/books/:filename/diff", get(books::diff_book))
+        .at("/books/:filename/events", get(events::pixel_book_events))
+        .at("/books/:filename/events/history", get(events::event_history))
+        .at("/books/:filename/context", get(context::get_context))
+        .at("/books/:filename/symmetry", put(context::set_symmetry))
+        .at("/books/:filename/selection", put(context::set_selection).delete(context::clear_selection))
+        .at("/books/:filename/export.zip", get(exports::export_zip))
+        .at("/books/:filename/export/embedded", get(exports::export_embedded))
+        .at("/books/:filename/export/raw", get(exports::export_raw))
+        .at("/books/:filename/export/code", get(exports::export_code))
+        .at("/books/:filename/export/aseprite", get(exports::export_aseprite))
+        .at("/books/:filename/export/aseprite-json", get(exports::export_aseprite_json))
+        .at("/books/:filename/export/aseprite-png", get(exports::export_aseprite_png))
+        .at("/books/:filename/export/spritesheet", get(exports::export_aseprite))
+        .at("/books/:filename/export/png", get(exports::export_png))
+        .at("/books/:filename/thumbnail", get(exports::thumbnail))
+        .at("/books/:filename/preview", get(exports::preview))
+        .at("/books/:filename/export/gif", get(exports::export_gif))
+        .at("/books/:filename/permissions", put(books::set_permissions))
+        .at("/books/:filename/palette", get(books::get_palette).put(books::set_palette))
+        .at("/books/:filename/tileset", get(books::get_tileset).put(books::set_tileset))
+        .at("/books/:filename/metadata", get(books::get_details).patch(books::update_details))
+        .at("/books/:filename/lock", post(locks::lock_book))
+        .at("/books/:filename/unlock", post(locks::unlock_book))
+        .at("/books/:filename/operations", get(books::operation_log))
+        .at("/books/:filename/undo", post(books::undo_book))
+        .at("/books/:filename/rename", post(books::rename_book))
+        .at("/books/:filename/copy", post(books::copy_book))
+        .at("/books/:filename/redo", post(books::redo_book))
+        .at("/books/:filename/quantize", post(books::quantize_book))
+        .at("/books/:filename/resize", post(books::resize_book))
+        .at("/books/:filename/crop", post(books::crop_book))
+        .at("/books/:filename/trim", post(books::trim_book))
+        .at("/books/:filename/scale", post(books::scale_book))
+        .at("/books/:filename/migrate", post(books::migrate_book))
+        .at("/books/:filename/snapshots", get(snapshots::list_snapshots).post(snapshots::create_snapshot))
+        .at("/books/:filename/snapshots/:id/restore", post(snapshots::restore_snapshot))
+        .at("/maintenance/scan", post(maintenance::scan_books))
+        .at("/maintenance/status", get(maintenance::maintenance_status))
+        .at("/templates", get(templates::list_templates))
+        .at("/tilemaps", get(tilemaps::list_tilemaps))
+        .at("/tilemaps/:name", get(tilemaps::get_tilemap).put(tilemaps::put_tilemap).delete(tilemaps::delete_tilemap))
+        .at("/tilemaps/:name/tiles", patch(tilemaps::set_tiles))
+        .at("/tilemaps/:name/export/png", get(tilemaps::export_tilemap_png))
+        .at("/tilemaps/:name/export/json", get(tilemaps::export_tilemap_json))
+        .at("/trash", get(trash::list_trash))
+        .at("/trash/:filename/restore", post(trash::restore_book))
+}
+
+/// Services shared by every handler and background task of one server
+#[derive(Clone)]
+pub struct AppState {
+    pub file_service: Arc<RwLock<FileService>>,
+    pub event_service: Arc<RwLock<EventService>>,
+    pub snapshot_service: Arc<SnapshotService>,
+    pub import_service: Arc<ImportService>,
+    pub lock_service: Arc<LockService>,
+    pub integrity_service: Arc<IntegrityService>,
+    pub sse_settings: Arc<SseSettings>,
+    pub revision_settings: Arc<RevisionSettings>,
+    pub settings_service: Arc<SettingsService>,
+    pub undo_service: Arc<UndoService>,
+    pub started_at: status::StartedAt,
+}
+
+impl AppState {
+    pub fn new(config: &ServerConfig) -> Self {
+        let settings_service = SettingsService::new(config.settings_file.clone());
+        let saved_path = match settings_service.load() {
+            Ok(settings) => settings.path,
+            Err(e) => {
+                println!("⚠️ Ignoring unreadable settings file: {}", e);
+                None
+            }
+        };
+        let mut file_service = FileService::new(config.base_path.clone())
+            .with_trash_retention(config.trash_retention)
+            .with_auto_upgrade(config.auto_upgrade)
+            .with_compression(config.compress_frames)
+            .with_allowed_root(config.allowed_root.clone());
+        // A saved directory that has since gone away, or is now outside the
+        // allowed root, falls back to the configured one
+        if let Some(path) = saved_path
+            && let Err(e) = file_service.set_path(path)
+        {
+            println!("⚠️ Ignoring saved path: {}", e);
+        }
+
+        let history_path = config.event_history.then(|| file_service.get_path().to_path_buf());
+
+        Self {
+            file_service: Arc::new(RwLock::new(file_service)),
+            event_service: Arc::new(RwLock::new(
+                EventService::new()
+                    .with_coalesce_window(config.event_coalesce_window)
+                    .with_history(history_path),
+            )),
+            snapshot_service: Arc::new(SnapshotService::new(config.snapshot_retention)),
+            import_service: Arc::new(ImportService::new(
+                config.import_allowed_hosts.clone(),
+                config.max_import_bytes,
+            )),
+            lock_service: Arc::new(LockService::new()),
+            integrity_service: Arc::new(IntegrityService::new()),
+            sse_settings: Arc::new(SseSettings {
+                heartbeat_interval: config.sse_heartbeat_interval,
+                send_timeout: config.sse_send_timeout,
+            }),
+            revision_settings: Arc::new(RevisionSettings { require_if_match: config.require_if_match }),
+            settings_service: Arc::new(settings_service),
+            undo_service: Arc::new(UndoService::new(config.undo_depth)),
+            started_at: status::StartedAt(chrono::Utc::now()),
+        }
+    }
+
+    /// The routes from [`build_app`] with these services attached
+    pub fn endpoint(&self) -> impl Endpoint + use<> {
+        build_app()
+            .data(self.file_service.clone())
+            .data(self.event_service.clone())
+            .data(self.snapshot_service.clone())
+            .data(self.import_service.clone())
+            .data(self.lock_service.clone())
+            .data(self.integrity_service.clone())
+            .data(self.sse_settings.clone())
+            .data(self.revision_settings.clone())
+            .data(self.settings_service.clone())
+            .data(self.undo_service.clone())
+            .data(self.started_at)
+    }
+
+    /// Starts the background jobs enabled in `config`
+    pub fn start_tasks(&self, config: &ServerConfig) -> Vec<JoinHandle<()>> {
+        let mut handles = Vec::new();
+        if let Some(interval) = config.snapshot_interval {
+            handles.push(tasks::spawn_autosave(
+                self.file_service.clone(),
+                self.snapshot_service.clone(),
+                interval,
+            ));
+        }
+        if let Some(interval) = config.integrity_scan_interval {
+            handles.push(tasks::spawn_integrity_scan(
+                self.file_service.clone(),
+                self.integrity_service.clone(),
+                self.event_service.clone(),
+                interval,
+            ));
+        }
+        handles
+    }
+}
+
+/// The routes from [`build_app`] with freshly created services for `config`.
+/// Background tasks are not started; use [`run`] or [`spawn`] for a full server.
+pub fn build_endpoint(config: &ServerConfig) -> impl Endpoint + 'static {
+    with_cors(AppState::new(config).endpoint(), &config.cors_origins)
+}
+
+// `endpoint` answering CORS requests from `origins`, when there are any
+fn with_cors<E: Endpoint>(endpoint: E, origins: &[String]) -> impl Endpoint + use<E> {
+    let cors = origins.iter()
+        .filter(|origin| *origin != "*")
+        .fold(Cors::new(), |cors, origin| cors.allow_origin_regex(origin));
+    endpoint.with_if(!origins.is_empty(), cors)
+}
+
+/// Runs the server until the process is stopped
+pub async fn run(config: ServerConfig) -> std::io::Result<()> {
+    let listener = TcpListener::bind(config.bind.clone());
+    println!("PIXL Server starting on http://{}", config.bind);
+
+    let state = AppState::new(&config);
+    let _tasks = state.start_tasks(&config);
+
+    Server::new(listener)
+        .run(with_cors(state.endpoint(), &config.cors_origins))
+        .await
+}
+
+/// How long [`RunningServer::shutdown`] lets open connections (such as SSE
+/// streams, which never end on their own) drain before dropping them
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+/// A server running on a background task, as returned by [`spawn`]
+pub struct RunningServer {
+    addr: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+    handle: JoinHandle<std::io::Result<()>>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl RunningServer {
+    /// The address actually bound, including the resolved port
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Stops accepting connections and waits for the server task to finish
+    pub async fn shutdown(self) -> std::io::Result<()> {
+        for task in &self.tasks {
+            task.abort();
+        }
+        let _ = self.shutdown.send(());
+        self.handle.await.map_err(std::io::Error::other)?
+    }
+}
+
+/// Binds and starts the server on a background task, returning once it is
+/// accepting connections. Intended for embedding and integration tests.
+pub async fn spawn(config: ServerConfig) -> std::io::Result<RunningServer> {
+    let acceptor = TcpListener::bind(config.bind.clone()).into_acceptor().await?;
+    let addr = acceptor.local_addr()
+        .iter()
+        .find_map(|addr| addr.as_socket_addr().copied())
+        .ok_or_else(|| std::io::Error::other("listener has no socket address"))?;
+
+    let state = AppState::new(&config);
+    let tasks = state.start_tasks(&config);
+    let endpoint = with_cors(state.endpoint(), &config.cors_origins);
+    let (shutdown, signal) = oneshot::channel();
+    let handle = tokio::spawn(async move {
+        Server::new_with_acceptor(acceptor)
+            .run_with_graceful_shutdown(endpoint, async { let _ = signal.await; }, Some(SHUTDOWN_GRACE_PERIOD))
+            .await
+    });
+
+    Ok(RunningServer { addr, shutdown, handle, tasks })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_spawn_on_ephemeral_port() {
+        let temp_dir = TempDir::new().unwrap();
+        let server = spawn(ServerConfig {
+            bind: "127.0.0.1:0".to_string(),
+            base_path: temp_dir.path().to_path_buf(),
+            ..ServerConfig::default()
+        }).await.unwrap();
+        assert_ne!(server.addr().port(), 0);
+
+        let health: serde_json::Value = reqwest::get(server.url()).await.unwrap()
+            .json().await.unwrap();
+        assert_eq!(health["status"], "healthy");
+
+        server.shutdown().await.unwrap();
+    }
+
+    #[test]
+    fn test_config_from_vars() {
+        let vars = |pairs: &'static [(&'static str, &'static str)]| {
+            move |name: &str| pairs.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string())
+        };
+
+        let config = ServerConfig::from_vars(vars(&[("PIXL_PORT", "4000")]));
+        assert_eq!(config.bind, "0.0.0.0:4000");
+        assert_eq!(config.base_path, ServerConfig::default().base_path);
+
+        let config = ServerConfig::from_vars(vars(&[
+            ("PIXL_HOST", "127.0.0.1"),
+            ("PIXL_BOOKS_PATH", "/srv/books"),
+            ("PIXL_SETTINGS_FILE", "/etc/pixl.json"),
+            ("PIXL_ALLOWED_ROOT", "/srv"),
+            ("PIXL_COMPRESS_FRAMES", "true"),
+        ]));
+        assert_eq!(config.bind, "127.0.0.1:3000");
+        assert_eq!(config.base_path, PathBuf::from("/srv/books"));
+        assert_eq!(config.settings_file, Some(PathBuf::from("/etc/pixl.json")));
+        assert_eq!(config.allowed_root, Some(PathBuf::from("/srv")));
+        assert!(config.compress_frames);
+    }
+}
@@ -0,0 +1,174 @@
+//! Server settings from command-line flags and an optional `pixl.toml`.
+//! Flags take precedence over environment variables, which take precedence
+//! over the config file.
+
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use serde::Deserialize;
+
+use crate::app::ServerConfig;
+
+/// Config file read from the working directory when `--config` is not given
+pub const CONFIG_FILE: &str = "pixl.toml";
+
+/// Log filter used when neither `--log-level`, `RUST_LOG` nor the config file sets one
+pub const DEFAULT_LOG_LEVEL: &str = "debug";
+
+/// Contents of `pixl.toml`. Every key is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    /// Address to listen on, without the port
+    pub bind: Option<String>,
+    pub port: Option<u16>,
+    pub books_path: Option<PathBuf>,
+    pub log_level: Option<String>,
+    #[serde(default)]
+    pub cors_origins: Vec<String>,
+    pub allowed_root: Option<PathBuf>,
+    pub settings_file: Option<PathBuf>,
+    #[serde(default)]
+    pub require_if_match: bool,
+    #[serde(default)]
+    pub compress_frames: bool,
+}
+
+impl ConfigFile {
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        toml::from_str(&text).map_err(|e| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e))
+        })
+    }
+}
+
+/// Command-line flags of `pixl-server`
+#[derive(Debug, Default, Parser)]
+#[command(name = "pixl-server", version, about = "Serves pixel books over HTTP")]
+pub struct ServerArgs {
+    /// Config file to read instead of `pixl.toml` in the working directory
+    #[arg(short, long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    /// Address to listen on [default: 0.0.0.0]
+    #[arg(long, value_name = "ADDRESS")]
+    pub bind: Option<String>,
+    /// Port to listen on [default: 3000]
+    #[arg(short, long)]
+    pub port: Option<u16>,
+    /// Books directory used until a client sets one with `PUT /path`
+    #[arg(long, value_name = "DIR")]
+    pub books_path: Option<PathBuf>,
+    /// Log filter, such as `info` or `pixl_server=trace` [default: debug]
+    #[arg(long, value_name = "FILTER")]
+    pub log_level: Option<String>,
+    /// Origin browsers may call the API from; repeat for several, or use `*` for any
+    #[arg(long = "cors-origin", value_name = "ORIGIN")]
+    pub cors_origins: Vec<String>,
+}
+
+/// Everything `pixl-server` needs to start
+#[derive(Debug)]
+pub struct Settings {
+    pub server: ServerConfig,
+    pub log_level: String,
+}
+
+impl ServerArgs {
+    /// Layers the config file, the environment and these flags. A missing
+    /// `pixl.toml` is fine; a missing `--config` file is an error.
+    pub fn settings(&self) -> std::io::Result<Settings> {
+        self.settings_with(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+    }
+
+    fn settings_with(&self, var: impl Fn(&str) -> Option<String>) -> std::io::Result<Settings> {
+        let file = match &self.config {
+            Some(path) => ConfigFile::load(path)?,
+            None if Path::new(CONFIG_FILE).is_file() => ConfigFile::load(Path::new(CONFIG_FILE))?,
+            None => ConfigFile::default(),
+        };
+
+        let mut server = ServerConfig::default();
+        server.set_address(file.bind, file.port.map(|port| port.to_string()));
+        if let Some(path) = file.books_path {
+            server.base_path = path;
+        }
+        server.allowed_root = file.allowed_root;
+        server.settings_file = file.settings_file;
+        server.cors_origins = file.cors_origins;
+        server.require_if_match = file.require_if_match;
+        server.compress_frames = file.compress_frames;
+
+        server.apply_vars(&var);
+
+        server.set_address(self.bind.clone(), self.port.map(|port| port.to_string()));
+        if let Some(path) = &self.books_path {
+            server.base_path = path.clone();
+        }
+        if !self.cors_origins.is_empty() {
+            server.cors_origins = self.cors_origins.clone();
+        }
+
+        let log_level = self.log_level.clone()
+            .or_else(|| var("RUST_LOG"))
+            .or(file.log_level)
+            .unwrap_or_else(|| DEFAULT_LOG_LEVEL.to_string());
+        Ok(Settings { server, log_level })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_flags_override_environment_and_config_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("pixl.toml");
+        std::fs::write(&path, r#"
+            bind = "127.0.0.1"
+            port = 4000
+            books_path = "/srv/books"
+            log_level = "info"
+            cors_origins = ["https://example.com"]
+        "#).unwrap();
+        let no_vars = |_: &str| None;
+
+        let args = ServerArgs { config: Some(path.clone()), ..Default::default() };
+        let settings = args.settings_with(no_vars).unwrap();
+        assert_eq!(settings.server.bind, "127.0.0.1:4000");
+        assert_eq!(settings.server.base_path, PathBuf::from("/srv/books"));
+        assert_eq!(settings.server.cors_origins, vec!["https://example.com"]);
+        assert_eq!(settings.log_level, "info");
+
+        let env = |name: &str| match name {
+            "PIXL_PORT" => Some("5000".to_string()),
+            "RUST_LOG" => Some("warn".to_string()),
+            _ => None,
+        };
+        let settings = args.settings_with(env).unwrap();
+        assert_eq!((settings.server.bind.as_str(), settings.log_level.as_str()), ("127.0.0.1:5000", "warn"));
+
+        let args = ServerArgs::try_parse_from([
+            "pixl-server", "--config", path.to_str().unwrap(), "-p", "6000",
+            "--log-level", "trace", "--cors-origin", "*",
+        ]).unwrap();
+        let settings = args.settings_with(env).unwrap();
+        assert_eq!(settings.server.bind, "127.0.0.1:6000");
+        assert_eq!(settings.server.cors_origins, vec!["*"]);
+        assert_eq!(settings.log_level, "trace");
+    }
+
+    #[test]
+    fn test_bad_config_files_are_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("pixl.toml");
+        std::fs::write(&path, "prot = 4000\n").unwrap();
+
+        let args = ServerArgs { config: Some(path), ..Default::default() };
+        assert!(args.settings_with(|_| None).is_err());
+        let missing = ServerArgs { config: Some(temp_dir.path().join("missing.toml")), ..Default::default() };
+        assert!(missing.settings_with(|_| None).is_err());
+    }
+}
@@ -1,4 +1,13 @@
+//! PIXL server as a library: build the poem routes with [`build_app`], run a
+//! configured instance with [`run`], or [`spawn`] one on a background task.
+
 pub mod api;
+pub mod app;
+pub mod config;
 pub mod models;
 pub mod services;
-pub mod utils; 
\ No newline at end of file
+pub mod tasks;
+pub mod utils;
+
+pub use app::{build_app, build_endpoint, run, spawn, AppState, RunningServer, ServerConfig};
+pub use config::{ConfigFile, ServerArgs, Settings};
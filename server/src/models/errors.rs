@@ -14,6 +14,42 @@ pub enum PixelError {
     #[error("Invalid color values: {details}")]
     InvalidColor { details: String },
     
+    #[error("Book already exists: {filename}")]
+    AlreadyExists { filename: String },
+    
+    #[error("Permission denied for {filename}: {reason}")]
+    PermissionDenied { filename: String, reason: String },
+    
+    #[error("{filename} is locked by {holder} until {expires_at}")]
+    BookLocked { filename: String, holder: String, expires_at: chrono::DateTime<chrono::Utc> },
+    
+    #[error("Invalid URL {url}: {reason}")]
+    InvalidUrl { url: String, reason: String },
+    
+    #[error("Host not allowed for import: {host}")]
+    HostNotAllowed { host: String },
+    
+    #[error("Download failed: {details}")]
+    DownloadFailed { details: String },
+    
+    #[error("Download too large: {size} bytes exceeds the {limit} byte limit")]
+    DownloadTooLarge { size: u64, limit: u64 },
+    
+    #[error("Export failed: {details}")]
+    ExportFailed { details: String },
+    
+    #[error("Template not found: {name}")]
+    TemplateNotFound { name: String },
+    
+    #[error("Tilemap not found: {name}")]
+    TilemapNotFound { name: String },
+    
+    #[error("Nothing to {action} for {filename}")]
+    NothingToUndo { filename: String, action: String },
+    
+    #[error("{filename} was changed outside its undo history")]
+    HistoryConflict { filename: String },
+    
     #[error("Invalid path: {path}")]
     InvalidPath { path: String },
     
@@ -24,4 +60,13 @@ pub enum PixelError {
     SerializationError(#[from] serde_json::Error),
 }
 
+impl From<pixl_format::FormatError> for PixelError {
+    fn from(error: pixl_format::FormatError) -> Self {
+        match error {
+            pixl_format::FormatError::Io(e) => PixelError::IoError(e),
+            other => PixelError::InvalidFormat { details: other.to_string() },
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, PixelError>; 
\ No newline at end of file
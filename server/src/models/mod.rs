@@ -1,7 +1,4 @@
-pub mod pixel_book;
-pub mod operations;
 pub mod errors;
 
-pub use pixel_book::*;
-pub use operations::*;
-pub use errors::*; 
\ No newline at end of file
+pub use pixl_core::*;
+pub use errors::*; 
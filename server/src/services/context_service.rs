@@ -0,0 +1,113 @@
+use crate::models::{DrawingContext, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory under the base path holding each book's drawing contexts as
+/// `<filename>.json`, keyed by session
+pub const CONTEXT_DIR: &str = ".contexts";
+
+/// Header naming the session whose drawing context a request uses
+pub const SESSION_HEADER: &str = "X-Pixl-Session";
+
+/// Session of requests that do not name one
+pub const DEFAULT_SESSION: &str = "default";
+
+/// Longest session name accepted in [`SESSION_HEADER`]
+pub const MAX_SESSION_LEN: usize = 128;
+
+/// Keeps the drawing context (symmetry and the like) of every book and
+/// session on disk, so it is shared by every client of a session and
+/// survives restarts. Contexts follow a book when it is renamed and go away
+/// with it when it is deleted.
+pub struct ContextService;
+
+impl ContextService {
+    fn context_path(base_path: &Path, filename: &str) -> PathBuf {
+        base_path.join(CONTEXT_DIR).join(format!("{}.json", filename))
+    }
+
+    fn load_sessions(base_path: &Path, filename: &str) -> Result<BTreeMap<String, DrawingContext>> {
+        let path = Self::context_path(base_path, filename);
+        if !path.is_file() {
+            return Ok(BTreeMap::new());
+        }
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    /// The context of `session` on `filename`, fresh when none was stored
+    pub fn load(base_path: &Path, filename: &str, session: &str) -> Result<DrawingContext> {
+        Ok(Self::load_sessions(base_path, filename)?.remove(session).unwrap_or_default())
+    }
+
+    /// Stores the context of `session` on `filename`; a fresh context is
+    /// dropped rather than written
+    pub fn save(base_path: &Path, filename: &str, session: &str, context: &DrawingContext) -> Result<()> {
+        let mut sessions = Self::load_sessions(base_path, filename)?;
+        if context.is_empty() {
+            sessions.remove(session);
+        } else {
+            sessions.insert(session.to_string(), context.clone());
+        }
+
+        let path = Self::context_path(base_path, filename);
+        if sessions.is_empty() {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_vec_pretty(&sessions)?)?;
+        Ok(())
+    }
+
+    /// Moves a renamed book's contexts along with it
+    pub fn rename(base_path: &Path, filename: &str, new_filename: &str) -> Result<()> {
+        let path = Self::context_path(base_path, filename);
+        if path.exists() {
+            let destination = Self::context_path(base_path, new_filename);
+            if let Some(dir) = destination.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::rename(path, destination)?;
+        }
+        Ok(())
+    }
+
+    /// Drops every context of `filename`
+    pub fn remove(base_path: &Path, filename: &str) {
+        let _ = fs::remove_file(Self::context_path(base_path, filename));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Symmetry;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_contexts_are_kept_per_session() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path();
+        assert_eq!(ContextService::load(base, "hero.pxl", DEFAULT_SESSION).unwrap(), DrawingContext::default());
+
+        let mirrored = DrawingContext { symmetry: Symmetry::Quad, ..Default::default() };
+        ContextService::save(base, "hero.pxl", DEFAULT_SESSION, &mirrored).unwrap();
+        ContextService::save(base, "hero.pxl", "agent-1", &DrawingContext { symmetry: Symmetry::Vertical, ..Default::default() }).unwrap();
+        assert_eq!(ContextService::load(base, "hero.pxl", DEFAULT_SESSION).unwrap(), mirrored);
+        assert_eq!(ContextService::load(base, "hero.pxl", "agent-1").unwrap().symmetry, Symmetry::Vertical);
+
+        ContextService::rename(base, "hero.pxl", "art/villain.pxl").unwrap();
+        assert_eq!(ContextService::load(base, "hero.pxl", DEFAULT_SESSION).unwrap(), DrawingContext::default());
+        assert_eq!(ContextService::load(base, "art/villain.pxl", DEFAULT_SESSION).unwrap(), mirrored);
+
+        // Fresh contexts are not stored, so resetting every session removes the file
+        ContextService::save(base, "art/villain.pxl", DEFAULT_SESSION, &DrawingContext::default()).unwrap();
+        ContextService::save(base, "art/villain.pxl", "agent-1", &DrawingContext::default()).unwrap();
+        assert!(!base.join(CONTEXT_DIR).join("art/villain.pxl.json").exists());
+    }
+}
@@ -1,10 +1,83 @@
-use crate::models::{PixelBook, DrawingOperation, ShapeType, LineType, Point, Size, PixelError};
+use base64::Engine;
+use std::cell::RefCell;
+use crate::models::{ActiveSelection, antialiased_path, bezier_points, blend, circle_pixels, flood_region, shape_pixels, noise_fill, BlendMode, line_pixels, path_pixels, pixel_perfect, polygon_interior, LineStyle, FillRule, dither_gradient, flip_pixels, region_pixels, rotate_pixels, shift_pixels, stamp_pixels, FlipAxis, Selection, StampSource, PixelBook, ColorRef, DitherPattern, DrawingOperation, GradientDirection, ShapeType, LineType, Point, Rect, Size, Symmetry, PixelError, OperationError, OperationResult, OperationStatus, ValidationReport};
 
-pub struct DrawingService;
+/// The color erased pixels are set to
+const TRANSPARENT: [u8; 4] = [0, 0, 0, 0];
+
+/// A seed for noise fills that did not give one
+pub fn random_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+#[derive(Default)]
+pub struct DrawingService {
+    symmetry: Symmetry,
+    /// The session's selection, which every batch of operations starts from
+    initial_selection: Option<SelectionMask>,
+    /// Set by `set_selection` and kept for the operations that follow it
+    selection: RefCell<Option<SelectionMask>>,
+    /// Set while an operation with a blend mode other than `Replace` draws
+    blending: RefCell<Option<Blending>>,
+}
+
+/// Pixels of the frame being drawn as they were before the operation.
+/// Colors blend with these rather than the current pixels, so spots an
+/// operation paints twice (outlines, mirrors) don't compound.
+struct Blending {
+    mode: BlendMode,
+    frame: usize,
+    before: Vec<u8>,
+}
+
+// Frames below `frame_count` whose pixels `operation` may change
+fn touched_frames(operation: &DrawingOperation, frame_count: usize) -> Vec<usize> {
+    let mut frames = match operation {
+        DrawingOperation::CutRegion { src_frame, dst_frame, .. } => vec![*src_frame, *dst_frame],
+        DrawingOperation::SwapColors { frame: None, .. } => (0..frame_count).collect(),
+        operation => operation.frame().into_iter().collect(),
+    };
+    frames.retain(|&frame| frame < frame_count);
+    frames.dedup();
+    frames
+}
+
+/// The pixels of one frame operations may change
+#[derive(Clone)]
+struct SelectionMask {
+    frame: usize,
+    selected: Vec<bool>,
+}
 
 impl DrawingService {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    pub fn with_symmetry(symmetry: Symmetry) -> Self {
+        Self { symmetry, ..Self::default() }
+    }
+
+    /// Limits every batch of operations to `selection` until one of them
+    /// sets or clears its own. The selection is picked from `book` as it
+    /// is now.
+    pub fn with_selection(mut self, book: &PixelBook, selection: Option<&ActiveSelection>) -> Result<Self, PixelError> {
+        self.initial_selection = match selection {
+            Some(active) => Some(SelectionMask {
+                frame: active.frame,
+                selected: self.selection_mask(book, active.frame, &active.selection)?,
+            }),
+            None => None,
+        };
+        Ok(self)
+    }
+
+    // Puts back the session's selection at the start of a batch
+    fn reset_selection(&self) {
+        self.selection.replace(self.initial_selection.clone());
     }
 
     pub fn apply_operations(
@@ -12,37 +85,269 @@ impl DrawingService {
         book: &mut PixelBook,
         operations: Vec<DrawingOperation>,
     ) -> Result<(), PixelError> {
+        self.reset_selection();
         for operation in operations {
             self.apply_operation(book, operation)?;
         }
         Ok(())
     }
 
+    /// Applies `operations` in order and reports what became of each. By
+    /// default the first failure marks every other operation skipped, and
+    /// the caller should discard `book`. With `continue_on_error` a failing
+    /// operation leaves no trace on `book` and the rest still apply.
+    pub fn apply_each(
+        &self,
+        book: &mut PixelBook,
+        operations: &[DrawingOperation],
+        continue_on_error: bool,
+    ) -> Vec<OperationResult> {
+        self.reset_selection();
+        let mut results = Vec::with_capacity(operations.len());
+        for (index, operation) in operations.iter().enumerate() {
+            let outcome = if continue_on_error {
+                // Keep the frames it draws on so an operation that fails part way through is not half drawn
+                let saved: Vec<_> = touched_frames(operation, book.frames.len()).into_iter()
+                    .map(|frame| (frame, book.frames[frame].pixels.clone()))
+                    .collect();
+                self.apply_operation(book, operation.clone()).inspect_err(|_| {
+                    for (frame, pixels) in saved {
+                        book.frames[frame].pixels = pixels;
+                    }
+                })
+            } else {
+                self.apply_operation(book, operation.clone())
+            };
+            match outcome {
+                Ok(()) => results.push(OperationResult { index, status: OperationStatus::Applied, error: None }),
+                Err(e) => {
+                    results.push(OperationResult { index, status: OperationStatus::Error, error: Some(e.to_string()) });
+                    if !continue_on_error {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if results.iter().any(|result| result.status == OperationStatus::Error) && !continue_on_error {
+            for result in &mut results {
+                if result.status == OperationStatus::Applied {
+                    result.status = OperationStatus::Skipped;
+                }
+            }
+            let attempted = results.len();
+            results.extend((attempted..operations.len())
+                .map(|index| OperationResult { index, status: OperationStatus::Skipped, error: None }));
+        }
+        results
+    }
+
+    /// Applies `operations` to a copy of `book`, reporting every one that
+    /// fails rather than stopping at the first. Later operations see the
+    /// pixels written by earlier ones, as they would when applied for real.
+    pub fn validate_operations(&self, book: &PixelBook, operations: &[DrawingOperation]) -> ValidationReport {
+        self.reset_selection();
+        let mut scratch = book.clone();
+        let errors: Vec<_> = operations.iter().enumerate()
+            .filter_map(|(index, operation)| {
+                self.apply_operation(&mut scratch, operation.clone()).err().map(|e| OperationError {
+                    index,
+                    operation: operation.summary(),
+                    error: e.to_string(),
+                })
+            })
+            .collect();
+        ValidationReport { valid: errors.is_empty(), operations: operations.len(), errors }
+    }
+
+    /// Applies one operation, keeping it inside the current selection
     pub fn apply_operation(
         &self,
         book: &mut PixelBook,
         operation: DrawingOperation,
     ) -> Result<(), PixelError> {
         match operation {
-            DrawingOperation::DrawPixel { frame, x, y, color } => {
-                self.draw_pixel(book, frame, x, y, color)
+            DrawingOperation::SetSelection { frame, selection } => {
+                let selected = self.selection_mask(book, frame, &selection)?;
+                self.selection.replace(Some(SelectionMask { frame, selected }));
+                return Ok(());
+            }
+            DrawingOperation::ClearSelection => {
+                self.selection.take();
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let mode = match &operation {
+            // Partly covered pixels are partly transparent, so anti-aliased lines always blend
+            DrawingOperation::DrawLine { style: LineStyle::AntiAliased, blend_mode: BlendMode::Replace, .. } => BlendMode::Alpha,
+            operation => operation.blend_mode(),
+        };
+        if let Some(frame) = operation.frame().filter(|&frame| mode != BlendMode::Replace && frame < book.frames.len()) {
+            let before = book.frames[frame].pixels.clone();
+            self.blending.replace(Some(Blending { mode, frame, before }));
+        }
+        let result = self.draw_selected(book, operation);
+        self.blending.take();
+        result
+    }
+
+    // Draws `operation`, then puts back whatever changed outside the selection
+    fn draw_selected(&self, book: &mut PixelBook, operation: DrawingOperation) -> Result<(), PixelError> {
+        let selection = self.selection.borrow();
+        let Some(mask) = selection.as_ref() else {
+            return self.draw(book, operation);
+        };
+        let saved = book.frames.get(mask.frame).map(|frame| frame.pixels.clone());
+        let result = self.draw(book, operation);
+        if let Some(saved) = saved {
+            let pixels = &mut book.frames[mask.frame].pixels;
+            for ((pixel, saved), &selected) in pixels.chunks_exact_mut(4).zip(saved.chunks_exact(4)).zip(&mask.selected) {
+                if !selected {
+                    pixel.copy_from_slice(saved);
+                }
+            }
+        }
+        result
+    }
+
+    // Pixels of `frame_idx` that `selection` covers, row by row
+    fn selection_mask(&self, book: &PixelBook, frame_idx: usize, selection: &Selection) -> Result<Vec<bool>, PixelError> {
+        if frame_idx >= book.frames.len() {
+            return Err(PixelError::InvalidCoordinates {
+                x: 0, y: 0, width: book.width, height: book.height
+            });
+        }
+
+        let (width, height) = (book.width as usize, book.height as usize);
+        let mut selected = vec![false; width * height];
+        match selection {
+            Selection::Rect { rect } => {
+                for y in (rect.y as usize..rect.y as usize + rect.height as usize).take_while(|&y| y < height) {
+                    for x in (rect.x as usize..rect.x as usize + rect.width as usize).take_while(|&x| x < width) {
+                        selected[y * width + x] = true;
+                    }
+                }
+            }
+            Selection::Polygon { points } => {
+                // Rasterize the polygon exactly as a filled draw_polygon would
+                let mut scratch = PixelBook::new(book.filename.clone(), book.width, book.height, 1);
+                DrawingService::new().draw_polygon(&mut scratch, 0, points.clone(), true, FillRule::EvenOdd, [0, 0, 0, 255])?;
+                for (selected, pixel) in selected.iter_mut().zip(scratch.frames[0].pixels.chunks_exact(4)) {
+                    *selected = pixel[3] != 0;
+                }
+            }
+            Selection::MagicWand { x, y, contiguous } => {
+                if *x >= book.width || *y >= book.height {
+                    return Err(PixelError::InvalidCoordinates {
+                        x: *x, y: *y, width: book.width, height: book.height
+                    });
+                }
+                // A new selection is picked from the whole frame, not the current one
+                let region = flood_region(&book.frames[frame_idx], book.width, book.height, *x, *y, *contiguous, None, None);
+                for (px, py) in region {
+                    selected[py as usize * width + px as usize] = true;
+                }
+            }
+        }
+        Ok(selected)
+    }
+
+    fn draw(
+        &self,
+        book: &mut PixelBook,
+        operation: DrawingOperation,
+    ) -> Result<(), PixelError> {
+        // Palette indices become RGBA against the palette the book has now
+        let palette = book.metadata.palette.clone();
+        let rgba = |color: ColorRef| color.resolve(palette.as_ref()).ok_or_else(|| PixelError::InvalidColor {
+            details: match (color, &palette) {
+                (ColorRef::Index(index), Some(palette)) => format!("Palette index {} is out of range for {} colors", index, palette.colors.len()),
+                _ => format!("{} has no palette to index into", book.filename),
+            },
+        });
+
+        match operation {
+            DrawingOperation::DrawPixel { frame, x, y, color, .. } => {
+                self.draw_pixel(book, frame, x, y, rgba(color)?)
             }
             DrawingOperation::SetColor { color: _ } => {
                 // SetColor doesn't directly modify the pixel book, it's for setting drawing color
                 Ok(())
             }
-            DrawingOperation::DrawLine { frame, start, end, line_type, color } => {
-                self.draw_line(book, frame, start, end, line_type, color)
+            DrawingOperation::DrawLine { frame, start, end, line_type, color, control_points, style, .. } => {
+                self.draw_line(book, frame, start, end, line_type, rgba(color)?, &control_points, style)
+            }
+            DrawingOperation::DrawShape { frame, shape, position, size, filled, color, .. } => {
+                self.draw_shape(book, frame, shape, position, size, filled, rgba(color)?)
+            }
+            DrawingOperation::DrawCircle { frame, center, radius, filled, color, .. } => {
+                self.draw_centered_circle(book, frame, center.x as i32, center.y as i32, radius as i32, filled, rgba(color)?)
+            }
+            DrawingOperation::DrawPolygon { frame, points, filled, color, fill_rule, .. } => {
+                self.draw_polygon(book, frame, points, filled, fill_rule, rgba(color)?)
+            }
+            DrawingOperation::FillArea { frame, x, y, color, contiguous, bounds, .. } => {
+                self.fill_area(book, frame, x, y, rgba(color)?, contiguous, bounds)
+            }
+            DrawingOperation::CopyRegion { src_frame, src_rect, dst_frame, dst_point } => {
+                self.copy_region(book, src_frame, src_rect, dst_frame, dst_point, false)
+            }
+            DrawingOperation::CutRegion { src_frame, src_rect, dst_frame, dst_point } => {
+                self.copy_region(book, src_frame, src_rect, dst_frame, dst_point, true)
+            }
+            DrawingOperation::DitherGradient { frame, rect, from, to, direction, .. } => {
+                self.draw_dither_gradient(book, frame, rect, rgba(from)?, rgba(to)?, direction)
+            }
+            DrawingOperation::GradientFill { frame, rect, seed, from, to, direction, dither, .. } => {
+                self.gradient_fill(book, frame, rect, seed, rgba(from)?, rgba(to)?, direction, dither)
+            }
+            DrawingOperation::NoiseFill { frame, rect, colors, density, seed, .. } => {
+                let colors = colors.into_iter().map(rgba).collect::<Result<Vec<_>, _>>()?;
+                self.noise_fill(book, frame, rect, &colors, density, seed.unwrap_or_else(random_seed))
             }
-            DrawingOperation::DrawShape { frame, shape, position, size, filled, color } => {
-                self.draw_shape(book, frame, shape, position, size, filled, color)
+            DrawingOperation::SwapColors { frame, a, b } => {
+                self.swap_colors(book, frame, rgba(a)?, rgba(b)?)
             }
-            DrawingOperation::DrawPolygon { frame, points, filled, color } => {
-                self.draw_polygon(book, frame, points, filled, color)
+            DrawingOperation::ErasePixel { frame, x, y } => {
+                self.draw_pixel(book, frame, x, y, TRANSPARENT)
             }
-            DrawingOperation::FillArea { frame, x, y, color } => {
-                self.fill_area(book, frame, x, y, color)
+            DrawingOperation::EraseArea { frame, rect } => {
+                self.erase_area(book, frame, rect)
             }
+            DrawingOperation::ClearFrame { frame } => {
+                self.clear_frame(book, frame)
+            }
+            DrawingOperation::FlipFrame { frame, axis } => {
+                self.transform_frame(book, frame, |pixels, width, height| Ok(flip_pixels(pixels, width, height, axis)))
+            }
+            DrawingOperation::RotateFrame { frame, degrees } => {
+                self.transform_frame(book, frame, |pixels, width, height| {
+                    let turns = match degrees {
+                        90 => 1,
+                        180 => 2,
+                        270 => 3,
+                        _ => return Err(PixelError::InvalidFormat {
+                            details: format!("Frames rotate by 90, 180, or 270 degrees, not {}", degrees),
+                        }),
+                    };
+                    if turns != 2 && width != height {
+                        return Err(PixelError::InvalidFormat {
+                            details: format!("Only square frames rotate by {} degrees, not {}x{}", degrees, width, height),
+                        });
+                    }
+                    Ok(rotate_pixels(pixels, width, height, turns))
+                })
+            }
+            DrawingOperation::ShiftFrame { frame, dx, dy, wrap } => {
+                self.transform_frame(book, frame, |pixels, width, height| Ok(shift_pixels(pixels, width, height, dx, dy, wrap)))
+            }
+            DrawingOperation::DrawStamp { frame, position, source, flip, skip_transparent, .. } => {
+                self.draw_stamp(book, frame, position, &source, flip, skip_transparent)
+            }
+            // Handled by apply_operation
+            DrawingOperation::SetSelection { .. } | DrawingOperation::ClearSelection => Ok(()),
         }
     }
 
@@ -66,13 +371,45 @@ impl DrawingService {
             });
         }
 
+        let blending = self.blending.borrow();
+        let blending = blending.as_ref().filter(|blending| blending.frame == frame_idx);
+        let width = book.width;
         let frame = &mut book.frames[frame_idx];
-        let pixel = crate::models::Pixel::new(color[0], color[1], color[2], color[3]);
-        frame.set_pixel(x, y, book.width, pixel);
+        let mut paint = |x: u16, y: u16| {
+            let color = match blending {
+                Some(Blending { mode, before, .. }) => {
+                    let i = (y as usize * width as usize + x as usize) * 4;
+                    blend(*mode, before[i..i + 4].try_into().unwrap(), color)
+                }
+                None => color,
+            };
+            frame.set_pixel(x, y, width, crate::models::Pixel::new(color[0], color[1], color[2], color[3]));
+        };
+
+        paint(x, y);
+        for (mx, my) in self.mirror_points(x, y, book.width, book.height) {
+            paint(mx, my);
+        }
 
         Ok(())
     }
 
+    /// Reflections of (x, y) required by the active symmetry mode, excluding the point itself
+    fn mirror_points(&self, x: u16, y: u16, width: u16, height: u16) -> Vec<(u16, u16)> {
+        let mx = width - 1 - x;
+        let my = height - 1 - y;
+
+        let mut points = match self.symmetry {
+            Symmetry::None => Vec::new(),
+            Symmetry::Horizontal => vec![(mx, y)],
+            Symmetry::Vertical => vec![(x, my)],
+            Symmetry::Quad => vec![(mx, y), (x, my), (mx, my)],
+        };
+        points.retain(|&point| point != (x, y));
+        points
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn draw_line(
         &self,
         book: &mut PixelBook,
@@ -81,60 +418,65 @@ impl DrawingService {
         end: Point,
         line_type: LineType,
         color: [u8; 4],
+        control_points: &[Point],
+        style: LineStyle,
     ) -> Result<(), PixelError> {
-        match line_type {
-            LineType::Straight => self.draw_straight_line(book, frame_idx, start, end, color),
+        let vertices = match line_type {
+            LineType::Straight => vec![start, end],
             LineType::Curved => {
-                // For now, treat curved lines as straight lines
-                // This can be enhanced later with proper curve algorithms
-                self.draw_straight_line(book, frame_idx, start, end, color)
+                if control_points.len() > 2 {
+                    return Err(PixelError::InvalidFormat {
+                        details: format!("Curved lines take at most 2 control points, got {}", control_points.len()),
+                    });
+                }
+                // Flatten the curve and join the vertices, so it stays connected
+                bezier_points(&start, control_points, &end)
+            }
+        };
+
+        let pixels = match style {
+            LineStyle::Aliased => path_pixels(&vertices),
+            LineStyle::PixelPerfect => pixel_perfect(&path_pixels(&vertices)),
+            LineStyle::AntiAliased => return self.draw_antialiased_path(book, frame_idx, &vertices, color),
+        };
+        for (x, y) in pixels {
+            if x >= 0 && y >= 0 && x < book.width as i32 && y < book.height as i32 {
+                self.draw_pixel(book, frame_idx, x as u16, y as u16, color)?;
             }
         }
+        Ok(())
     }
 
-    fn draw_straight_line(
+    /// Draws each pixel of the path with the color's alpha scaled by how
+    /// much of it the line covers
+    fn draw_antialiased_path(
         &self,
         book: &mut PixelBook,
         frame_idx: usize,
-        start: Point,
-        end: Point,
+        vertices: &[Point],
         color: [u8; 4],
     ) -> Result<(), PixelError> {
-        // Bresenham's line algorithm
-        let mut x0 = start.x as i32;
-        let mut y0 = start.y as i32;
-        let x1 = end.x as i32;
-        let y1 = end.y as i32;
-
-        let dx = (x1 - x0).abs();
-        let dy = (y1 - y0).abs();
-        let sx = if x0 < x1 { 1 } else { -1 };
-        let sy = if y0 < y1 { 1 } else { -1 };
-        let mut err = dx - dy;
-
-        loop {
-            if x0 >= 0 && y0 >= 0 && x0 < book.width as i32 && y0 < book.height as i32 {
-                self.draw_pixel(book, frame_idx, x0 as u16, y0 as u16, color)?;
-            }
-
-            if x0 == x1 && y0 == y1 {
-                break;
-            }
-
-            let e2 = 2 * err;
-            if e2 > -dy {
-                err -= dy;
-                x0 += sx;
-            }
-            if e2 < dx {
-                err += dx;
-                y0 += sy;
+        for (x, y, coverage) in antialiased_path(vertices) {
+            if x >= 0 && y >= 0 && x < book.width as i32 && y < book.height as i32 {
+                let alpha = (color[3] as f32 * coverage).round() as u8;
+                self.draw_pixel(book, frame_idx, x as u16, y as u16, [color[0], color[1], color[2], alpha])?;
             }
         }
-
         Ok(())
     }
 
+    fn draw_straight_line(
+        &self,
+        book: &mut PixelBook,
+        frame_idx: usize,
+        start: Point,
+        end: Point,
+        color: [u8; 4],
+    ) -> Result<(), PixelError> {
+        self.draw_clipped(book, frame_idx, line_pixels(&start, &end), color)
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn draw_shape(
         &self,
         book: &mut PixelBook,
@@ -145,348 +487,399 @@ impl DrawingService {
         filled: bool,
         color: [u8; 4],
     ) -> Result<(), PixelError> {
-        match shape {
-            ShapeType::Rectangle => self.draw_rectangle(book, frame_idx, position, size, filled, color),
-            ShapeType::Circle => self.draw_circle(book, frame_idx, position, size, filled, color),
-            ShapeType::Oval => self.draw_oval(book, frame_idx, position, size, filled, color),
-            ShapeType::Triangle => self.draw_triangle(book, frame_idx, position, size, filled, color),
-        }
+        self.draw_clipped(book, frame_idx, shape_pixels(&shape, &position, &size, filled, book.width, book.height), color)
     }
 
-    fn draw_rectangle(
+    #[allow(clippy::too_many_arguments)]
+    fn draw_centered_circle(
         &self,
         book: &mut PixelBook,
         frame_idx: usize,
-        position: Point,
-        size: Size,
+        cx: i32,
+        cy: i32,
+        radius: i32,
         filled: bool,
         color: [u8; 4],
     ) -> Result<(), PixelError> {
-        let x1 = position.x;
-        let y1 = position.y;
-        let x2 = position.x + size.width.saturating_sub(1);
-        let y2 = position.y + size.height.saturating_sub(1);
+        self.draw_clipped(book, frame_idx, circle_pixels(cx, cy, radius, filled, book.width, book.height), color)
+    }
 
-        if filled {
-            for y in y1..=y2.min(book.height - 1) {
-                for x in x1..=x2.min(book.width - 1) {
-                    self.draw_pixel(book, frame_idx, x, y, color)?;
-                }
-            }
-        } else {
-            // Draw outline
-            for x in x1..=x2.min(book.width - 1) {
-                if y1 < book.height {
-                    self.draw_pixel(book, frame_idx, x, y1, color)?;
-                }
-                if y2 < book.height && y2 != y1 {
-                    self.draw_pixel(book, frame_idx, x, y2, color)?;
-                }
-            }
-            for y in y1..=y2.min(book.height - 1) {
-                if x1 < book.width {
-                    self.draw_pixel(book, frame_idx, x1, y, color)?;
-                }
-                if x2 < book.width && x2 != x1 {
-                    self.draw_pixel(book, frame_idx, x2, y, color)?;
-                }
+    /// Paints those of `pixels` that fall on the canvas
+    fn draw_clipped(
+        &self,
+        book: &mut PixelBook,
+        frame_idx: usize,
+        pixels: Vec<(i32, i32)>,
+        color: [u8; 4],
+    ) -> Result<(), PixelError> {
+        for (x, y) in pixels {
+            if x >= 0 && y >= 0 && x < book.width as i32 && y < book.height as i32 {
+                self.draw_pixel(book, frame_idx, x as u16, y as u16, color)?;
             }
         }
-
         Ok(())
     }
 
-    fn draw_circle(
+    fn draw_polygon(
         &self,
         book: &mut PixelBook,
         frame_idx: usize,
-        position: Point,
-        size: Size,
+        points: Vec<Point>,
         filled: bool,
+        fill_rule: FillRule,
         color: [u8; 4],
     ) -> Result<(), PixelError> {
-        let cx = position.x as i32 + size.width as i32 / 2;
-        let cy = position.y as i32 + size.height as i32 / 2;
-        let radius = (size.width.min(size.height) / 2) as i32;
+        if points.len() < 3 {
+            return Ok(()); // Can't draw a polygon with less than 3 points
+        }
 
         if filled {
-            for y in (cy - radius).max(0)..(cy + radius + 1).min(book.height as i32) {
-                for x in (cx - radius).max(0)..(cx + radius + 1).min(book.width as i32) {
-                    let dx = x - cx;
-                    let dy = y - cy;
-                    if dx * dx + dy * dy <= radius * radius {
-                        self.draw_pixel(book, frame_idx, x as u16, y as u16, color)?;
-                    }
-                }
+            if frame_idx >= book.frames.len() {
+                return Err(PixelError::InvalidCoordinates {
+                    x: points[0].x, y: points[0].y, width: book.width, height: book.height
+                });
             }
-        } else {
-            // Midpoint circle algorithm for outline
-            let mut x = 0;
-            let mut y = radius;
-            let mut d = 1 - radius;
-
-            while x <= y {
-                // Draw 8 points of symmetry
-                self.draw_circle_points(book, frame_idx, cx, cy, x, y, color)?;
-                
-                if d < 0 {
-                    d += 2 * x + 3;
-                } else {
-                    d += 2 * (x - y) + 5;
-                    y -= 1;
-                }
-                x += 1;
+            for (x, y) in polygon_interior(&points, fill_rule, book.width, book.height) {
+                self.draw_pixel(book, frame_idx, x, y, color)?;
             }
         }
 
+        // The outline covers edge pixels whose centres fall just outside the interior
+        for i in 0..points.len() {
+            let start = points[i].clone();
+            let end = points[(i + 1) % points.len()].clone();
+            self.draw_straight_line(book, frame_idx, start, end, color)?;
+        }
+
         Ok(())
     }
 
-    fn draw_circle_points(
+    #[allow(clippy::too_many_arguments)]
+    fn fill_area(
         &self,
         book: &mut PixelBook,
         frame_idx: usize,
-        cx: i32,
-        cy: i32,
-        x: i32,
-        y: i32,
+        x: u16,
+        y: u16,
         color: [u8; 4],
+        contiguous: bool,
+        bounds: Option<Rect>,
     ) -> Result<(), PixelError> {
-        let points = [
-            (cx + x, cy + y), (cx + x, cy - y),
-            (cx - x, cy + y), (cx - x, cy - y),
-            (cx + y, cy + x), (cx + y, cy - x),
-            (cx - y, cy + x), (cx - y, cy - x),
-        ];
+        let region = self.fill_region(book, frame_idx, x, y, contiguous, bounds.as_ref())?;
+        if region.first().is_some_and(|&(px, py)| {
+            book.frames[frame_idx].get_pixel(px, py, book.width)
+                .is_some_and(|p| [p.r, p.g, p.b, p.a] == color)
+        }) {
+            return Ok(()); // Already the target color
+        }
 
-        for (px, py) in points {
-            if px >= 0 && py >= 0 && px < book.width as i32 && py < book.height as i32 {
-                self.draw_pixel(book, frame_idx, px as u16, py as u16, color)?;
-            }
+        for (px, py) in region {
+            self.draw_pixel(book, frame_idx, px, py, color)?;
         }
 
         Ok(())
     }
 
-    fn draw_oval(
+    /// Pixels a fill from (x, y) replaces: those of its color connected to it,
+    /// or anywhere in the frame when not `contiguous`, inside `bounds`. An
+    /// active selection on the frame walls the fill in. The region is
+    /// collected before painting so that mirrored writes can't cut a fill
+    /// short when symmetry is active.
+    fn fill_region(
+        &self,
+        book: &PixelBook,
+        frame_idx: usize,
+        x: u16,
+        y: u16,
+        contiguous: bool,
+        bounds: Option<&Rect>,
+    ) -> Result<Vec<(u16, u16)>, PixelError> {
+        if frame_idx >= book.frames.len() || x >= book.width || y >= book.height {
+            return Err(PixelError::InvalidCoordinates {
+                x, y, width: book.width, height: book.height
+            });
+        }
+
+        let selection = self.selection.borrow();
+        let mask = selection.as_ref().filter(|mask| mask.frame == frame_idx).map(|mask| &mask.selected[..]);
+        Ok(flood_region(&book.frames[frame_idx], book.width, book.height, x, y, contiguous, bounds, mask))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn gradient_fill(
         &self,
         book: &mut PixelBook,
         frame_idx: usize,
-        position: Point,
-        size: Size,
-        filled: bool,
-        color: [u8; 4],
+        rect: Option<Rect>,
+        seed: Option<Point>,
+        from: [u8; 4],
+        to: [u8; 4],
+        direction: GradientDirection,
+        dither: DitherPattern,
     ) -> Result<(), PixelError> {
-        let cx = position.x as i32 + size.width as i32 / 2;
-        let cy = position.y as i32 + size.height as i32 / 2;
-        let rx = (size.width / 2) as i32;
-        let ry = (size.height / 2) as i32;
-
-        if filled {
-            for y in (cy - ry).max(0)..(cy + ry + 1).min(book.height as i32) {
-                for x in (cx - rx).max(0)..(cx + rx + 1).min(book.width as i32) {
-                    let dx = x - cx;
-                    let dy = y - cy;
-                    if rx * rx * dy * dy + ry * ry * dx * dx <= rx * rx * ry * ry {
-                        self.draw_pixel(book, frame_idx, x as u16, y as u16, color)?;
-                    }
-                }
-            }
-        } else {
-            // Simple ellipse outline using parametric equations
-            let steps = ((rx + ry) * 2).max(20);
-            for i in 0..steps {
-                let angle = 2.0 * std::f64::consts::PI * i as f64 / steps as f64;
-                let x = cx + (rx as f64 * angle.cos()) as i32;
-                let y = cy + (ry as f64 * angle.sin()) as i32;
-                
-                if x >= 0 && y >= 0 && x < book.width as i32 && y < book.height as i32 {
-                    self.draw_pixel(book, frame_idx, x as u16, y as u16, color)?;
+        let region = match (seed, &rect) {
+            (Some(seed), _) => self.fill_region(book, frame_idx, seed.x, seed.y, true, rect.as_ref())?,
+            (None, Some(rect)) => {
+                if frame_idx >= book.frames.len() || rect.x >= book.width || rect.y >= book.height {
+                    return Err(PixelError::InvalidCoordinates {
+                        x: rect.x, y: rect.y, width: book.width, height: book.height
+                    });
                 }
+                rect.canvas_pixels(book.width, book.height)
             }
+            (None, None) => return Err(PixelError::InvalidFormat {
+                details: "gradient_fill needs a rect, a seed, or both".to_string(),
+            }),
+        };
+
+        for (x, y, color) in crate::models::gradient_fill(&region, from, to, direction, dither) {
+            self.draw_pixel(book, frame_idx, x, y, color)?;
         }
 
         Ok(())
     }
 
-    fn draw_triangle(
+    fn noise_fill(
         &self,
         book: &mut PixelBook,
         frame_idx: usize,
-        position: Point,
-        size: Size,
-        filled: bool,
-        color: [u8; 4],
+        rect: Rect,
+        colors: &[[u8; 4]],
+        density: f32,
+        seed: u64,
     ) -> Result<(), PixelError> {
-        // Simple triangle: top vertex at center-top, base at bottom
-        let x1 = position.x + size.width / 2;  // Top vertex
-        let y1 = position.y;
-        let x2 = position.x;  // Bottom left
-        let y2 = position.y + size.height.saturating_sub(1);
-        let x3 = position.x + size.width.saturating_sub(1);  // Bottom right
-        let y3 = position.y + size.height.saturating_sub(1);
+        if frame_idx >= book.frames.len() || rect.x >= book.width || rect.y >= book.height {
+            return Err(PixelError::InvalidCoordinates {
+                x: rect.x, y: rect.y, width: book.width, height: book.height
+            });
+        }
+        if colors.is_empty() {
+            return Err(PixelError::InvalidFormat { details: "noise_fill needs at least one color".to_string() });
+        }
+        if !(0.0..=1.0).contains(&density) {
+            return Err(PixelError::InvalidFormat {
+                details: format!("Noise density must be between 0 and 1, not {}", density),
+            });
+        }
 
-        if filled {
-            // Simple triangle fill using scanline approach
-            for y in y1..=y2.min(book.height - 1) {
-                let progress = if y2 == y1 { 0.0 } else { (y - y1) as f32 / (y2 - y1) as f32 };
-                let left_x = x1 as f32 + progress * (x2 as f32 - x1 as f32);
-                let right_x = x1 as f32 + progress * (x3 as f32 - x1 as f32);
-                
-                let start_x = (left_x as u16).min(right_x as u16);
-                let end_x = (left_x as u16).max(right_x as u16);
-                
-                for x in start_x..=end_x.min(book.width - 1) {
-                    self.draw_pixel(book, frame_idx, x, y, color)?;
-                }
+        let region = rect.canvas_pixels(book.width, book.height);
+        for (x, y, color) in noise_fill(&region, colors, density, seed) {
+            self.draw_pixel(book, frame_idx, x, y, color)?;
+        }
+
+        Ok(())
+    }
+
+    fn copy_region(
+        &self,
+        book: &mut PixelBook,
+        src_frame: usize,
+        src_rect: Rect,
+        dst_frame: usize,
+        dst_point: Point,
+        cut: bool,
+    ) -> Result<(), PixelError> {
+        if src_frame >= book.frames.len() || src_rect.x >= book.width || src_rect.y >= book.height {
+            return Err(PixelError::InvalidCoordinates {
+                x: src_rect.x, y: src_rect.y, width: book.width, height: book.height
+            });
+        }
+
+        if dst_frame >= book.frames.len() || dst_point.x >= book.width || dst_point.y >= book.height {
+            return Err(PixelError::InvalidCoordinates {
+                x: dst_point.x, y: dst_point.y, width: book.width, height: book.height
+            });
+        }
+
+        // Clip the region against both the source and destination bounds
+        let width = src_rect.width
+            .min(book.width - src_rect.x)
+            .min(book.width - dst_point.x);
+        let height = src_rect.height
+            .min(book.height - src_rect.y)
+            .min(book.height - dst_point.y);
+
+        // Buffer the source rows first so copies within the same frame can overlap safely
+        let row_bytes = width as usize * 4;
+        let mut region = Vec::with_capacity(row_bytes * height as usize);
+        let source = &book.frames[src_frame].pixels;
+        for row in 0..height {
+            let start = ((src_rect.y + row) as usize * book.width as usize + src_rect.x as usize) * 4;
+            region.extend_from_slice(&source[start..start + row_bytes]);
+        }
+
+        // A cut clears the whole source rectangle, including any part the
+        // destination clipped off, before pasting over it
+        if cut {
+            self.erase_area(book, src_frame, src_rect)?;
+        }
+
+        for (row, chunk) in region.chunks(row_bytes.max(1)).enumerate() {
+            for (col, rgba) in chunk.chunks(4).enumerate() {
+                let x = dst_point.x + col as u16;
+                let y = dst_point.y + row as u16;
+                self.draw_pixel(book, dst_frame, x, y, [rgba[0], rgba[1], rgba[2], rgba[3]])?;
             }
-        } else {
-            // Draw triangle outline
-            self.draw_straight_line(book, frame_idx, Point { x: x1, y: y1 }, Point { x: x2, y: y2 }, color)?;
-            self.draw_straight_line(book, frame_idx, Point { x: x2, y: y2 }, Point { x: x3, y: y3 }, color)?;
-            self.draw_straight_line(book, frame_idx, Point { x: x3, y: y3 }, Point { x: x1, y: y1 }, color)?;
         }
 
         Ok(())
     }
 
-    fn draw_polygon(
+    fn draw_dither_gradient(
         &self,
         book: &mut PixelBook,
         frame_idx: usize,
-        points: Vec<Point>,
-        filled: bool,
-        color: [u8; 4],
+        rect: Rect,
+        from: [u8; 4],
+        to: [u8; 4],
+        direction: GradientDirection,
     ) -> Result<(), PixelError> {
-        if points.len() < 3 {
-            return Ok(()); // Can't draw a polygon with less than 3 points
+        if frame_idx >= book.frames.len() || rect.x >= book.width || rect.y >= book.height {
+            return Err(PixelError::InvalidCoordinates {
+                x: rect.x, y: rect.y, width: book.width, height: book.height
+            });
         }
 
-        if filled {
-            // Simple polygon fill using scanline algorithm
-            let min_y = points.iter().map(|p| p.y).min().unwrap_or(0);
-            let max_y = points.iter().map(|p| p.y).max().unwrap_or(0);
-
-            for y in min_y..=max_y.min(book.height - 1) {
-                let mut intersections = Vec::new();
-                
-                // Find intersections with polygon edges
-                for i in 0..points.len() {
-                    let p1 = &points[i];
-                    let p2 = &points[(i + 1) % points.len()];
-                    
-                    if (p1.y <= y && p2.y > y) || (p2.y <= y && p1.y > y) {
-                        let x_intersect = p1.x as f32 + 
-                            (y as f32 - p1.y as f32) * (p2.x as f32 - p1.x as f32) / (p2.y as f32 - p1.y as f32);
-                        intersections.push(x_intersect as u16);
-                    }
-                }
-                
-                intersections.sort();
-                
-                // Fill between pairs of intersections
-                for chunk in intersections.chunks(2) {
-                    if chunk.len() == 2 {
-                        let start_x = chunk[0];
-                        let end_x = chunk[1];
-                        for x in start_x..=end_x.min(book.width - 1) {
-                            self.draw_pixel(book, frame_idx, x, y, color)?;
-                        }
-                    }
-                }
-            }
-        } else {
-            // Draw polygon outline
-            for i in 0..points.len() {
-                let start = points[i].clone();
-                let end = points[(i + 1) % points.len()].clone();
-                self.draw_straight_line(book, frame_idx, start, end, color)?;
-            }
+        // The gradient spans the whole rect; only the part on the canvas is drawn
+        for (x, y, color) in dither_gradient(&rect, from, to, direction, book.width, book.height) {
+            self.draw_pixel(book, frame_idx, x, y, color)?;
         }
 
         Ok(())
     }
 
-    fn fill_area(
+    fn draw_stamp(
         &self,
         book: &mut PixelBook,
         frame_idx: usize,
-        x: u16,
-        y: u16,
-        color: [u8; 4],
+        position: Point,
+        source: &StampSource,
+        flip: Option<FlipAxis>,
+        skip_transparent: bool,
     ) -> Result<(), PixelError> {
-        if frame_idx >= book.frames.len() || x >= book.width || y >= book.height {
+        if frame_idx >= book.frames.len() || position.x >= book.width || position.y >= book.height {
             return Err(PixelError::InvalidCoordinates {
-                x, y, width: book.width, height: book.height
+                x: position.x, y: position.y, width: book.width, height: book.height
             });
         }
 
-        // Get target color without borrowing book
-        let target_color = {
-            let frame = &book.frames[frame_idx];
-            match frame.get_pixel(x, y, book.width) {
-                Some(pixel) => [pixel.r, pixel.g, pixel.b, pixel.a],
-                None => return Ok(()),
+        let (width, height, pixels) = match source {
+            StampSource::Inline { width, height, data } => {
+                let pixels = base64::engine::general_purpose::STANDARD.decode(data)
+                    .map_err(|e| PixelError::InvalidFormat { details: format!("Stamp data is not base64: {}", e) })?;
+                if pixels.len() != *width as usize * *height as usize * 4 {
+                    return Err(PixelError::InvalidFormat {
+                        details: format!("A {}x{} stamp needs {} bytes, not {}", width, height, *width as usize * *height as usize * 4, pixels.len()),
+                    });
+                }
+                (*width, *height, pixels)
+            }
+            StampSource::Frame { book: None, frame, rect } => {
+                let source = book.frames.get(*frame).ok_or(PixelError::InvalidCoordinates {
+                    x: 0, y: 0, width: book.width, height: book.height
+                })?;
+                let whole = Rect { x: 0, y: 0, width: book.width, height: book.height };
+                region_pixels(&source.pixels, book.width, book.height, rect.as_ref().unwrap_or(&whole))
+            }
+            StampSource::Frame { book: Some(other), .. } => {
+                return Err(PixelError::InvalidFormat {
+                    details: format!("Stamps from {} must be resolved before drawing", other),
+                });
             }
         };
 
-        if target_color == color {
-            return Ok(()); // Already the target color
+        for (dx, dy, color) in stamp_pixels(&pixels, width, height, flip, skip_transparent) {
+            let (x, y) = (position.x as u32 + dx as u32, position.y as u32 + dy as u32);
+            if x < book.width as u32 && y < book.height as u32 {
+                self.draw_pixel(book, frame_idx, x as u16, y as u16, color)?;
+            }
         }
 
-        // Flood fill using a stack-based approach
-        let mut stack = vec![(x, y)];
-        let mut visited = std::collections::HashSet::new();
-
-        while let Some((cx, cy)) = stack.pop() {
-            if visited.contains(&(cx, cy)) {
-                continue;
-            }
-            visited.insert((cx, cy));
+        Ok(())
+    }
 
-            if cx >= book.width || cy >= book.height {
-                continue;
+    fn swap_colors(
+        &self,
+        book: &mut PixelBook,
+        frame_idx: Option<usize>,
+        a: [u8; 4],
+        b: [u8; 4],
+    ) -> Result<(), PixelError> {
+        let frames = match frame_idx {
+            Some(index) if index >= book.frames.len() => {
+                return Err(PixelError::InvalidCoordinates {
+                    x: 0, y: 0, width: book.width, height: book.height
+                });
             }
+            Some(index) => &mut book.frames[index..=index],
+            // With a selection active only its frame has pixels to swap
+            None => match self.selection.borrow().as_ref() {
+                Some(mask) if mask.frame < book.frames.len() => &mut book.frames[mask.frame..=mask.frame],
+                Some(_) => &mut [],
+                None => &mut book.frames[..],
+            },
+        };
 
-            // Check current pixel color without borrowing book mutably
-            let current_color = {
-                let frame = &book.frames[frame_idx];
-                match frame.get_pixel(cx, cy, book.width) {
-                    Some(pixel) => [pixel.r, pixel.g, pixel.b, pixel.a],
-                    None => continue,
+        for frame in frames {
+            for pixel in frame.pixels.chunks_exact_mut(4) {
+                if pixel == a {
+                    pixel.copy_from_slice(&b);
+                } else if pixel == b {
+                    pixel.copy_from_slice(&a);
                 }
-            };
-
-            if current_color != target_color {
-                continue;
             }
+        }
+
+        Ok(())
+    }
 
-            // Fill this pixel
-            self.draw_pixel(book, frame_idx, cx, cy, color)?;
+    fn erase_area(&self, book: &mut PixelBook, frame_idx: usize, rect: Rect) -> Result<(), PixelError> {
+        if frame_idx >= book.frames.len() || rect.x >= book.width || rect.y >= book.height {
+            return Err(PixelError::InvalidCoordinates {
+                x: rect.x, y: rect.y, width: book.width, height: book.height
+            });
+        }
 
-            // Add neighboring pixels to stack
-            if cx > 0 {
-                stack.push((cx - 1, cy));
-            }
-            if cx + 1 < book.width {
-                stack.push((cx + 1, cy));
-            }
-            if cy > 0 {
-                stack.push((cx, cy - 1));
-            }
-            if cy + 1 < book.height {
-                stack.push((cx, cy + 1));
+        // Clipped to the canvas, like the other rectangle operations
+        let right = rect.x.saturating_add(rect.width).min(book.width);
+        let bottom = rect.y.saturating_add(rect.height).min(book.height);
+        for y in rect.y..bottom {
+            for x in rect.x..right {
+                self.draw_pixel(book, frame_idx, x, y, TRANSPARENT)?;
             }
         }
 
         Ok(())
     }
+
+    fn clear_frame(&self, book: &mut PixelBook, frame_idx: usize) -> Result<(), PixelError> {
+        let frame = book.frames.get_mut(frame_idx).ok_or(PixelError::InvalidCoordinates {
+            x: 0, y: 0, width: book.width, height: book.height
+        })?;
+        frame.pixels.fill(0);
+        Ok(())
+    }
+
+    /// Replaces a frame's pixels with `transform(pixels, width, height)`
+    fn transform_frame(
+        &self,
+        book: &mut PixelBook,
+        frame_idx: usize,
+        transform: impl FnOnce(&[u8], u16, u16) -> Result<Vec<u8>, PixelError>,
+    ) -> Result<(), PixelError> {
+        let (width, height) = (book.width, book.height);
+        let frame = book.frames.get_mut(frame_idx).ok_or(PixelError::InvalidCoordinates {
+            x: 0, y: 0, width, height
+        })?;
+        frame.pixels = transform(&frame.pixels, width, height)?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::{PixelBook, Point, Size, LineType, ShapeType};
+    use crate::models::{DitherPattern, FlipAxis, PixelBook, Point, Rect, Size, LineType, ShapeType, Symmetry};
 
     fn create_test_book() -> PixelBook {
         PixelBook::new("test.pxl".to_string(), 10, 10, 1)
@@ -519,6 +912,64 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_validate_operations_reports_every_failure() {
+        let book = create_test_book();
+        let service = DrawingService::new();
+        let operations = vec![
+            DrawingOperation::DrawPixel { frame: 0, x: 15, y: 0, color: [255, 0, 0, 255].into(), blend_mode: BlendMode::Replace },
+            DrawingOperation::DrawPixel { frame: 0, x: 1, y: 1, color: [255, 0, 0, 255].into(), blend_mode: BlendMode::Replace },
+            DrawingOperation::DrawPixel { frame: 3, x: 1, y: 1, color: [255, 0, 0, 255].into(), blend_mode: BlendMode::Replace },
+        ];
+
+        let report = service.validate_operations(&book, &operations);
+        assert!(!report.valid);
+        assert_eq!(report.operations, 3);
+        assert_eq!(report.errors.iter().map(|e| e.index).collect::<Vec<_>>(), vec![0, 2]);
+        assert!(book.frames[0].get_pixel(1, 1, book.width).unwrap().a == 0);
+
+        assert!(service.validate_operations(&book, &operations[1..2]).valid);
+    }
+
+    #[test]
+    fn test_apply_each_skips_or_continues_past_failures() {
+        let service = DrawingService::new();
+        let operations = vec![
+            DrawingOperation::DrawPixel { frame: 0, x: 1, y: 1, color: [255, 0, 0, 255].into(), blend_mode: BlendMode::Replace },
+            DrawingOperation::DrawPixel { frame: 0, x: 15, y: 0, color: [255, 0, 0, 255].into(), blend_mode: BlendMode::Replace },
+            DrawingOperation::DrawPixel { frame: 0, x: 2, y: 2, color: [255, 0, 0, 255].into(), blend_mode: BlendMode::Replace },
+        ];
+        let statuses = |results: &[OperationResult]| results.iter().map(|r| r.status).collect::<Vec<_>>();
+
+        let mut book = create_test_book();
+        let results = service.apply_each(&mut book, &operations, false);
+        assert_eq!(statuses(&results), vec![OperationStatus::Skipped, OperationStatus::Error, OperationStatus::Skipped]);
+        assert!(results[1].error.is_some());
+
+        let mut book = create_test_book();
+        let results = service.apply_each(&mut book, &operations, true);
+        assert_eq!(statuses(&results), vec![OperationStatus::Applied, OperationStatus::Error, OperationStatus::Applied]);
+        assert_eq!(book.frames[0].get_pixel(2, 2, book.width).unwrap().r, 255);
+    }
+
+    #[test]
+    fn test_touched_frames_are_the_ones_an_operation_may_change() {
+        let red = [255, 0, 0, 255];
+        let pixel = DrawingOperation::DrawPixel { frame: 2, x: 0, y: 0, color: red.into(), blend_mode: BlendMode::Replace };
+        assert_eq!(touched_frames(&pixel, 4), vec![2]);
+        assert!(touched_frames(&pixel, 2).is_empty());
+
+        let rect = Rect { x: 0, y: 0, width: 2, height: 2 };
+        let cut = DrawingOperation::CutRegion { src_frame: 3, src_rect: rect.clone(), dst_frame: 1, dst_point: Point { x: 0, y: 0 } };
+        assert_eq!(touched_frames(&cut, 4), vec![3, 1]);
+        let cut = DrawingOperation::CutRegion { src_frame: 1, src_rect: rect, dst_frame: 1, dst_point: Point { x: 0, y: 0 } };
+        assert_eq!(touched_frames(&cut, 4), vec![1]);
+
+        let swap = DrawingOperation::SwapColors { frame: None, a: red.into(), b: [0, 0, 255, 255].into() };
+        assert_eq!(touched_frames(&swap, 3), vec![0, 1, 2]);
+        assert!(touched_frames(&DrawingOperation::ClearSelection, 3).is_empty());
+    }
+
     #[test]
     fn test_draw_pixel_invalid_frame() {
         let mut book = create_test_book();
@@ -552,7 +1003,7 @@ mod tests {
         
         let start = Point { x: 1, y: 1 };
         let end = Point { x: 8, y: 8 };
-        let result = service.draw_line(&mut book, 0, start, end, LineType::Straight, [0, 0, 255, 255]);
+        let result = service.draw_line(&mut book, 0, start, end, LineType::Straight, [0, 0, 255, 255], &[], LineStyle::Aliased);
         assert!(result.is_ok());
         
         // Check diagonal line pixels
@@ -562,6 +1013,103 @@ mod tests {
         assert_eq!(pixel.b, 255);
     }
 
+    #[test]
+    fn test_draw_curved_line() {
+        let mut book = create_test_book();
+        let service = DrawingService::new();
+        let (start, end) = (Point { x: 0, y: 8 }, Point { x: 8, y: 8 });
+        let control = Point { x: 4, y: 0 };
+
+        let result = service.draw_line(&mut book, 0, start.clone(), end.clone(), LineType::Curved, [255, 0, 0, 255], std::slice::from_ref(&control), LineStyle::Aliased);
+        assert!(result.is_ok());
+
+        // The curve bends up towards the control point, leaving the straight path
+        let red = |x: u16, y: u16| book.frames[0].get_pixel(x, y, book.width).unwrap().r == 255;
+        assert!(red(0, 8) && red(8, 8) && red(4, 4));
+        assert!(!red(4, 8));
+        // Every column between the ends is reached
+        assert!((0..=8).all(|x| (0..10).any(|y| red(x, y))));
+
+        let result = service.draw_line(&mut book, 0, start, end, LineType::Curved, [255, 0, 0, 255], &[control.clone(), control.clone(), control], LineStyle::Aliased);
+        assert!(matches!(result, Err(PixelError::InvalidFormat { .. })));
+    }
+
+    #[test]
+    fn test_line_styles() {
+        let service = DrawingService::new();
+        let (start, end, control) = (Point { x: 0, y: 0 }, Point { x: 9, y: 9 }, Point { x: 9, y: 0 });
+        let painted = |style| {
+            let mut book = create_test_book();
+            service.draw_line(&mut book, 0, start.clone(), end.clone(), LineType::Curved, [255, 0, 0, 255], std::slice::from_ref(&control), style).unwrap();
+            book.frames[0].pixels.chunks(4).map(|pixel| pixel[3]).collect::<Vec<_>>()
+        };
+
+        // Pixel-perfect strokes drop the corners of the aliased one and nothing else
+        let (aliased, perfect) = (painted(LineStyle::Aliased), painted(LineStyle::PixelPerfect));
+        assert!(perfect.iter().zip(&aliased).all(|(p, a)| *p == 0 || *a == 255));
+        assert!(perfect.iter().filter(|a| **a == 255).count() < aliased.iter().filter(|a| **a == 255).count());
+
+        // Anti-aliased edges blend into the pixels underneath
+        let mut book = create_test_book();
+        for frame_pixel in book.frames[0].pixels.chunks_mut(4) {
+            frame_pixel.copy_from_slice(&[0, 0, 255, 255]);
+        }
+        let operation = DrawingOperation::DrawLine {
+            frame: 0,
+            start: Point { x: 0, y: 0 },
+            end: Point { x: 9, y: 3 },
+            line_type: LineType::Straight,
+            color: [255, 0, 0, 255].into(),
+            control_points: Vec::new(),
+            style: LineStyle::AntiAliased,
+            blend_mode: BlendMode::Replace,
+        };
+        service.apply_operation(&mut book, operation).unwrap();
+        let pixel = |x, y| book.frames[0].get_pixel(x, y, book.width).unwrap();
+        assert_eq!((pixel(0, 0).r, pixel(0, 0).b), (255, 0));
+        assert!(book.frames[0].pixels.chunks(4).all(|p| p[3] == 255));
+        assert!(book.frames[0].pixels.chunks(4).any(|p| p[0] > 0 && p[2] > 0));
+    }
+
+    #[test]
+    fn test_blend_modes() {
+        let mut book = create_test_book();
+        for pixel in book.frames[0].pixels.chunks_mut(4) {
+            pixel.copy_from_slice(&[255, 0, 0, 255]);
+        }
+        let shade = |blend_mode| DrawingOperation::DrawShape {
+            frame: 0,
+            shape: ShapeType::Rectangle,
+            position: Point { x: 3, y: 3 },
+            size: Size { width: 4, height: 4 },
+            filled: true,
+            color: [0, 0, 255, 128].into(),
+            blend_mode,
+        };
+        let pixel = |book: &PixelBook, x, y| {
+            let pixel = book.frames[0].get_pixel(x, y, book.width).unwrap();
+            [pixel.r, pixel.g, pixel.b, pixel.a]
+        };
+
+        // Replace keeps the old behaviour of writing the color as is
+        let mut replaced = book.clone();
+        DrawingService::new().apply_operation(&mut replaced, shade(BlendMode::Replace)).unwrap();
+        assert_eq!(pixel(&replaced, 3, 3), [0, 0, 255, 128]);
+
+        // The centred rectangle is its own mirror image, yet each pixel is blended only once
+        DrawingService::with_symmetry(Symmetry::Quad).apply_operation(&mut book, shade(BlendMode::Alpha)).unwrap();
+        assert_eq!(pixel(&book, 3, 3), [127, 0, 128, 255]);
+        assert_eq!(pixel(&book, 5, 4), [127, 0, 128, 255]);
+        assert_eq!(pixel(&book, 2, 2), [255, 0, 0, 255]);
+
+        let operation = DrawingOperation::DrawPixel { frame: 0, x: 8, y: 8, color: [0, 128, 0, 255].into(), blend_mode: BlendMode::Add };
+        DrawingService::new().apply_operation(&mut book, operation).unwrap();
+        assert_eq!(pixel(&book, 8, 8), [255, 128, 0, 255]);
+        let operation = DrawingOperation::DrawPixel { frame: 0, x: 8, y: 8, color: [128, 255, 255, 255].into(), blend_mode: BlendMode::Multiply };
+        DrawingService::new().apply_operation(&mut book, operation).unwrap();
+        assert_eq!(pixel(&book, 8, 8), [128, 128, 0, 255]);
+    }
+
     #[test]
     fn test_draw_rectangle_outline() {
         let mut book = create_test_book();
@@ -569,7 +1117,7 @@ mod tests {
         
         let position = Point { x: 2, y: 2 };
         let size = Size { width: 4, height: 3 };
-        let result = service.draw_rectangle(&mut book, 0, position, size, false, [255, 255, 0, 255]);
+        let result = service.draw_shape(&mut book, 0, ShapeType::Rectangle, position, size, false, [255, 255, 0, 255]);
         assert!(result.is_ok());
         
         // Check corners
@@ -593,7 +1141,7 @@ mod tests {
         
         let position = Point { x: 1, y: 1 };
         let size = Size { width: 3, height: 3 };
-        let result = service.draw_rectangle(&mut book, 0, position, size, true, [128, 64, 192, 255]);
+        let result = service.draw_shape(&mut book, 0, ShapeType::Rectangle, position, size, true, [128, 64, 192, 255]);
         assert!(result.is_ok());
         
         // Check that center is filled
@@ -601,6 +1149,11 @@ mod tests {
         assert_eq!(pixel.r, 128);
         assert_eq!(pixel.g, 64);
         assert_eq!(pixel.b, 192);
+
+        // A shape far larger than the book only costs the book's pixels
+        let huge = Size { width: u16::MAX, height: u16::MAX };
+        service.draw_shape(&mut book, 0, ShapeType::Rectangle, Point { x: 0, y: 0 }, huge, true, [1, 2, 3, 255]).unwrap();
+        assert!(book.frames[0].pixels.chunks_exact(4).all(|pixel| pixel == [1, 2, 3, 255]));
     }
 
     #[test]
@@ -610,7 +1163,7 @@ mod tests {
         
         let position = Point { x: 5, y: 5 };
         let size = Size { width: 4, height: 4 };
-        let result = service.draw_circle(&mut book, 0, position, size, false, [255, 128, 64, 255]);
+        let result = service.draw_shape(&mut book, 0, ShapeType::Circle, position, size, false, [255, 128, 64, 255]);
         assert!(result.is_ok());
         
         // Check that center pixel exists (circle should draw something)
@@ -628,13 +1181,15 @@ mod tests {
                 frame: 0,
                 x: 1,
                 y: 1,
-                color: [255, 0, 0, 255],
+                color: [255, 0, 0, 255].into(),
+                blend_mode: BlendMode::Replace,
             },
             DrawingOperation::DrawPixel {
                 frame: 0,
                 x: 2,
                 y: 2,
-                color: [0, 255, 0, 255],
+                color: [0, 255, 0, 255].into(),
+                blend_mode: BlendMode::Replace,
             },
             DrawingOperation::DrawShape {
                 frame: 0,
@@ -642,7 +1197,8 @@ mod tests {
                 position: Point { x: 5, y: 5 },
                 size: Size { width: 2, height: 2 },
                 filled: true,
-                color: [0, 0, 255, 255],
+                color: [0, 0, 255, 255].into(),
+                blend_mode: BlendMode::Replace,
             },
         ];
         
@@ -666,7 +1222,7 @@ mod tests {
         let service = DrawingService::new();
         
         // Fill from origin should work
-        let result = service.fill_area(&mut book, 0, 0, 0, [200, 100, 50, 255]);
+        let result = service.fill_area(&mut book, 0, 0, 0, [200, 100, 50, 255], true, None);
         assert!(result.is_ok());
         
         // Check that origin pixel is filled
@@ -676,6 +1232,309 @@ mod tests {
         assert_eq!(pixel.b, 50);
     }
 
+    #[test]
+    fn test_swap_colors() {
+        let mut book = PixelBook::new("test.pxl".to_string(), 4, 4, 2);
+        let service = DrawingService::new();
+        let (red, blue) = ([255, 0, 0, 255], [0, 0, 255, 255]);
+        for frame in 0..2 {
+            service.draw_pixel(&mut book, frame, 0, 0, red).unwrap();
+            service.draw_pixel(&mut book, frame, 1, 0, blue).unwrap();
+        }
+
+        let swap = DrawingOperation::SwapColors { frame: Some(1), a: red.into(), b: blue.into() };
+        service.apply_operation(&mut book, swap).unwrap();
+        assert_eq!(book.frames[0].get_pixel(0, 0, 4).unwrap().r, 255);
+        assert_eq!(book.frames[1].get_pixel(0, 0, 4).unwrap().b, 255);
+        assert_eq!(book.frames[1].get_pixel(1, 0, 4).unwrap().r, 255);
+        assert_eq!(book.frames[1].get_pixel(2, 0, 4).unwrap().a, 0);
+
+        // Every frame when none is given
+        let swap = DrawingOperation::SwapColors { frame: None, a: red.into(), b: blue.into() };
+        service.apply_operation(&mut book, swap).unwrap();
+        assert_eq!(book.frames[0].get_pixel(0, 0, 4).unwrap().b, 255);
+        assert_eq!(book.frames[1].get_pixel(0, 0, 4).unwrap().r, 255);
+
+        let swap = DrawingOperation::SwapColors { frame: Some(2), a: red.into(), b: blue.into() };
+        assert!(service.apply_operation(&mut book, swap).is_err());
+
+        // A selection keeps the swap to its own pixels of its own frame
+        let selection = ActiveSelection { frame: 1, selection: Selection::Rect { rect: Rect { x: 0, y: 0, width: 1, height: 1 } } };
+        let service = DrawingService::new().with_selection(&book, Some(&selection)).unwrap();
+        let swap = DrawingOperation::SwapColors { frame: None, a: red.into(), b: blue.into() };
+        service.apply_operations(&mut book, vec![swap]).unwrap();
+        assert_eq!(book.frames[0].get_pixel(0, 0, 4).unwrap().b, 255);
+        assert_eq!(book.frames[1].get_pixel(0, 0, 4).unwrap().b, 255);
+        assert_eq!(book.frames[1].get_pixel(1, 0, 4).unwrap().b, 255);
+
+        let selection = ActiveSelection { frame: 2, selection: Selection::Rect { rect: Rect { x: 0, y: 0, width: 1, height: 1 } } };
+        assert!(DrawingService::new().with_selection(&book, Some(&selection)).is_err());
+    }
+
+    #[test]
+    fn test_erase_and_clear_make_pixels_transparent() {
+        let mut book = PixelBook::new("test.pxl".to_string(), 4, 4, 2);
+        let service = DrawingService::new();
+        let red = [255, 0, 0, 255];
+        for frame in 0..2 {
+            let fill = DrawingOperation::FillArea { frame, x: 0, y: 0, color: red.into(), contiguous: true, bounds: None, blend_mode: BlendMode::Replace };
+            service.apply_operation(&mut book, fill).unwrap();
+        }
+        let alpha = |book: &PixelBook, frame: usize, x: u16, y: u16| book.frames[frame].get_pixel(x, y, 4).unwrap().a;
+
+        service.apply_operation(&mut book, DrawingOperation::ErasePixel { frame: 0, x: 0, y: 0 }).unwrap();
+        assert_eq!(alpha(&book, 0, 0, 0), 0);
+        assert_eq!(alpha(&book, 0, 1, 0), 255);
+
+        // The area is clipped to the canvas
+        let erase = DrawingOperation::EraseArea { frame: 0, rect: Rect { x: 2, y: 2, width: 8, height: 8 } };
+        service.apply_operation(&mut book, erase).unwrap();
+        assert_eq!(alpha(&book, 0, 3, 3), 0);
+        assert_eq!(alpha(&book, 0, 2, 2), 0);
+        assert_eq!(alpha(&book, 0, 1, 2), 255);
+
+        service.apply_operation(&mut book, DrawingOperation::ClearFrame { frame: 1 }).unwrap();
+        assert!(book.frames[1].pixels.iter().all(|&channel| channel == 0));
+        assert_eq!(alpha(&book, 0, 1, 0), 255);
+        assert!(service.apply_operation(&mut book, DrawingOperation::ClearFrame { frame: 2 }).is_err());
+    }
+
+    #[test]
+    fn test_frame_transforms() {
+        let mut book = create_test_book();
+        let service = DrawingService::new();
+        let red = [255, 0, 0, 255];
+        service.draw_pixel(&mut book, 0, 1, 2, red).unwrap();
+        let red_at = |book: &PixelBook| -> Vec<(u16, u16)> {
+            (0..10).flat_map(|y| (0..10).map(move |x| (x, y)))
+                .filter(|&(x, y)| book.frames[0].get_pixel(x, y, 10).unwrap().r == 255)
+                .collect()
+        };
+
+        service.apply_operation(&mut book, DrawingOperation::FlipFrame { frame: 0, axis: FlipAxis::Horizontal }).unwrap();
+        assert_eq!(red_at(&book), [(8, 2)]);
+        service.apply_operation(&mut book, DrawingOperation::RotateFrame { frame: 0, degrees: 90 }).unwrap();
+        assert_eq!(red_at(&book), [(7, 8)]);
+        service.apply_operation(&mut book, DrawingOperation::ShiftFrame { frame: 0, dx: 3, dy: 0, wrap: true }).unwrap();
+        assert_eq!(red_at(&book), [(0, 8)]);
+        service.apply_operation(&mut book, DrawingOperation::ShiftFrame { frame: 0, dx: -1, dy: 0, wrap: false }).unwrap();
+        assert!(red_at(&book).is_empty());
+
+        let rotate = DrawingOperation::RotateFrame { frame: 0, degrees: 45 };
+        assert!(matches!(service.apply_operation(&mut book, rotate), Err(PixelError::InvalidFormat { .. })));
+        let mut wide = PixelBook::new("wide.pxl".to_string(), 4, 2, 1);
+        let rotate = DrawingOperation::RotateFrame { frame: 0, degrees: 270 };
+        assert!(service.apply_operation(&mut wide, rotate).is_err());
+        assert!(service.apply_operation(&mut wide, DrawingOperation::RotateFrame { frame: 0, degrees: 180 }).is_ok());
+    }
+
+    #[test]
+    fn test_draw_stamp() {
+        let mut book = create_test_book();
+        let service = DrawingService::new();
+        let blue = [0, 0, 255, 255];
+        // 2x1 stamp: red, then a transparent pixel
+        let data = base64::engine::general_purpose::STANDARD.encode([255, 0, 0, 255, 0, 0, 0, 0]);
+        let stamp = |x, flip, skip_transparent| DrawingOperation::DrawStamp {
+            frame: 0,
+            position: Point { x, y: 0 },
+            source: StampSource::Inline { width: 2, height: 1, data: data.clone() },
+            flip,
+            skip_transparent,
+            blend_mode: BlendMode::Replace,
+        };
+
+        service.draw_pixel(&mut book, 0, 1, 0, blue).unwrap();
+        service.apply_operation(&mut book, stamp(0, None, true)).unwrap();
+        assert_eq!(book.frames[0].get_pixel(0, 0, 10).unwrap().r, 255);
+        assert_eq!(book.frames[0].get_pixel(1, 0, 10).unwrap().b, 255);
+        service.apply_operation(&mut book, stamp(0, Some(FlipAxis::Horizontal), false)).unwrap();
+        assert_eq!(book.frames[0].get_pixel(0, 0, 10).unwrap().a, 0);
+        assert_eq!(book.frames[0].get_pixel(1, 0, 10).unwrap().r, 255);
+
+        // Hanging off the right edge is clipped; starting off the canvas is not allowed
+        service.apply_operation(&mut book, stamp(9, None, true)).unwrap();
+        assert!(service.apply_operation(&mut book, stamp(10, None, true)).is_err());
+
+        let copy = DrawingOperation::DrawStamp {
+            frame: 0,
+            position: Point { x: 4, y: 4 },
+            source: StampSource::Frame { book: None, frame: 0, rect: Some(Rect { x: 0, y: 0, width: 2, height: 1 }) },
+            flip: None,
+            skip_transparent: true,
+            blend_mode: BlendMode::Replace,
+        };
+        service.apply_operation(&mut book, copy).unwrap();
+        assert_eq!(book.frames[0].get_pixel(5, 4, 10).unwrap(), book.frames[0].get_pixel(1, 0, 10).unwrap());
+        // A source rect starting past the frame copies nothing
+        let before = book.frames[0].pixels.clone();
+        let offside = DrawingOperation::DrawStamp {
+            frame: 0,
+            position: Point { x: 0, y: 0 },
+            source: StampSource::Frame { book: None, frame: 0, rect: Some(Rect { x: 1000, y: 0, width: 2, height: 2 }) },
+            flip: None,
+            skip_transparent: false,
+            blend_mode: BlendMode::Replace,
+        };
+        service.apply_operation(&mut book, offside).unwrap();
+        assert_eq!(book.frames[0].pixels, before);
+        let bad = DrawingOperation::DrawStamp {
+            frame: 0,
+            position: Point { x: 0, y: 0 },
+            source: StampSource::Inline { width: 3, height: 1, data },
+            flip: None,
+            skip_transparent: true,
+            blend_mode: BlendMode::Replace,
+        };
+        assert!(matches!(service.apply_operation(&mut book, bad), Err(PixelError::InvalidFormat { .. })));
+    }
+
+    #[test]
+    fn test_filled_polygon_follows_fill_rule() {
+        let service = DrawingService::new();
+        let star = vec![Point { x: 5, y: 0 }, Point { x: 8, y: 9 }, Point { x: 0, y: 3 }, Point { x: 9, y: 3 }, Point { x: 1, y: 9 }];
+        let draw = |fill_rule| {
+            let mut book = create_test_book();
+            let polygon = DrawingOperation::DrawPolygon { frame: 0, points: star.clone(), filled: true, color: [255, 0, 0, 255].into(), fill_rule, blend_mode: BlendMode::Replace };
+            service.apply_operation(&mut book, polygon).unwrap();
+            book
+        };
+
+        let even_odd = draw(FillRule::EvenOdd);
+        assert_eq!(even_odd.frames[0].get_pixel(4, 5, 10).unwrap().a, 0);
+        assert_eq!(even_odd.frames[0].get_pixel(5, 1, 10).unwrap().a, 255);
+        assert_eq!(draw(FillRule::NonZero).frames[0].get_pixel(4, 5, 10).unwrap().a, 255);
+        // Vertices and edges are always drawn
+        assert!(star.iter().all(|p| even_odd.frames[0].get_pixel(p.x, p.y, 10).unwrap().a == 255));
+    }
+
+    #[test]
+    fn test_selection_limits_later_operations() {
+        let mut book = create_test_book();
+        let service = DrawingService::new();
+        let red = [255, 0, 0, 255];
+        let painted = |book: &PixelBook| (0..10u16).flat_map(|y| (0..10u16).map(move |x| (x, y)))
+            .filter(|&(x, y)| book.frames[0].get_pixel(x, y, 10).unwrap().a != 0)
+            .count();
+        let operations = vec![
+            DrawingOperation::SetSelection { frame: 0, selection: Selection::Rect { rect: Rect { x: 2, y: 2, width: 3, height: 3 } } },
+            DrawingOperation::FillArea { frame: 0, x: 3, y: 3, color: red.into(), contiguous: true, bounds: None, blend_mode: BlendMode::Replace },
+            DrawingOperation::ClearSelection,
+            DrawingOperation::DrawPixel { frame: 0, x: 9, y: 9, color: red.into(), blend_mode: BlendMode::Replace },
+        ];
+        service.apply_operations(&mut book, operations).unwrap();
+        assert_eq!(painted(&book), 10);
+        assert_eq!(book.frames[0].get_pixel(1, 1, 10).unwrap().a, 0);
+
+        // The magic wand picks the red square, so the fill turns only it blue
+        let operations = vec![
+            DrawingOperation::SetSelection { frame: 0, selection: Selection::MagicWand { x: 3, y: 3, contiguous: true } },
+            DrawingOperation::DrawShape { frame: 0, shape: ShapeType::Rectangle, position: Point { x: 0, y: 0 }, size: Size { width: 10, height: 10 }, filled: true, color: [0, 0, 255, 255].into(), blend_mode: BlendMode::Replace },
+        ];
+        service.apply_operations(&mut book, operations).unwrap();
+        assert_eq!(book.frames[0].get_pixel(3, 3, 10).unwrap().b, 255);
+        assert_eq!(book.frames[0].get_pixel(9, 9, 10).unwrap().r, 255);
+        assert_eq!(painted(&book), 10);
+
+        // A selection does not outlive its request
+        let mut book = create_test_book();
+        let points = vec![Point { x: 0, y: 0 }, Point { x: 4, y: 0 }, Point { x: 0, y: 4 }];
+        let mut outline = create_test_book();
+        service.draw_polygon(&mut outline, 0, points.clone(), true, FillRule::EvenOdd, red).unwrap();
+        let fill = DrawingOperation::FillArea { frame: 0, x: 0, y: 0, color: red.into(), contiguous: true, bounds: None, blend_mode: BlendMode::Replace };
+        let select = DrawingOperation::SetSelection { frame: 0, selection: Selection::Polygon { points } };
+        let cover = DrawingOperation::DrawShape { frame: 0, shape: ShapeType::Rectangle, position: Point { x: 0, y: 0 }, size: Size { width: 10, height: 10 }, filled: true, color: red.into(), blend_mode: BlendMode::Replace };
+        service.apply_operations(&mut book, vec![select, cover]).unwrap();
+        assert_eq!(book.frames[0].pixels, outline.frames[0].pixels);
+        let mut book = create_test_book();
+        service.apply_operations(&mut book, vec![fill]).unwrap();
+        assert_eq!(painted(&book), 100);
+    }
+
+    #[test]
+    fn test_fill_does_not_cross_unselected_pixels() {
+        let mut book = create_test_book();
+        let service = DrawingService::new();
+        let red = [255, 0, 0, 255];
+        // Two selected islands, columns 0-2 and 7-9, of the same transparent color
+        let selected = (0..100).map(|i| !(3..7).contains(&(i % 10))).collect();
+        service.selection.replace(Some(SelectionMask { frame: 0, selected }));
+
+        service.fill_area(&mut book, 0, 0, 0, red, true, None).unwrap();
+        let painted: Vec<u16> = (0..10).filter(|&x| book.frames[0].get_pixel(x, 5, 10).unwrap().a != 0).collect();
+        assert_eq!(painted, vec![0, 1, 2]);
+
+        // Outside the selection there is nothing to fill
+        service.fill_area(&mut book, 0, 5, 5, red, true, None).unwrap();
+        assert_eq!(book.frames[0].get_pixel(5, 5, 10).unwrap().a, 0);
+    }
+
+    #[test]
+    fn test_fill_stops_at_bounds() {
+        let mut book = create_test_book();
+        let service = DrawingService::new();
+        let red = [255, 0, 0, 255];
+        let bounds = Rect { x: 2, y: 2, width: 3, height: 3 };
+
+        service.fill_area(&mut book, 0, 3, 3, red, true, Some(bounds.clone())).unwrap();
+        let filled = (0..10).flat_map(|y| (0..10).map(move |x| (x, y)))
+            .filter(|&(x, y)| book.frames[0].get_pixel(x, y, book.width).unwrap().a != 0)
+            .count();
+        assert_eq!(filled, 9);
+        assert_eq!(book.frames[0].get_pixel(4, 4, book.width).unwrap().r, 255);
+        assert_eq!(book.frames[0].get_pixel(5, 4, book.width).unwrap().a, 0);
+
+        // A seed outside the bounds fills nothing
+        service.fill_area(&mut book, 0, 0, 0, red, false, Some(bounds)).unwrap();
+        assert_eq!(book.frames[0].get_pixel(0, 0, book.width).unwrap().a, 0);
+    }
+
+    #[test]
+    fn test_circle_by_center_and_radius() {
+        let mut book = create_test_book();
+        let service = DrawingService::new();
+
+        let operation = DrawingOperation::DrawCircle {
+            frame: 0,
+            center: Point { x: 4, y: 4 },
+            radius: 3,
+            filled: false,
+            color: [255, 0, 0, 255].into(),
+            blend_mode: BlendMode::Replace,
+        };
+        assert!(service.apply_operation(&mut book, operation).is_ok());
+
+        // Extremes sit exactly `radius` from the centre, which stays empty
+        for (x, y) in [(1, 4), (7, 4), (4, 1), (4, 7)] {
+            assert_eq!(book.frames[0].get_pixel(x, y, book.width).unwrap().r, 255);
+        }
+        assert_eq!(book.frames[0].get_pixel(0, 4, book.width).unwrap().a, 0);
+        assert_eq!(book.frames[0].get_pixel(4, 4, book.width).unwrap().a, 0);
+    }
+
+    #[test]
+    fn test_non_contiguous_fill_replaces_every_match() {
+        let mut book = create_test_book();
+        let service = DrawingService::new();
+        let (red, wall) = ([255, 0, 0, 255], [0, 0, 0, 255]);
+
+        // A wall down column 5 splits the frame in two
+        for y in 0..10 {
+            service.draw_pixel(&mut book, 0, 5, y, wall).unwrap();
+        }
+
+        service.fill_area(&mut book, 0, 0, 0, red, true, None).unwrap();
+        assert_eq!(book.frames[0].get_pixel(9, 9, book.width).unwrap().a, 0);
+
+        service.fill_area(&mut book, 0, 0, 0, [0, 255, 0, 255], false, None).unwrap();
+        assert_eq!(book.frames[0].get_pixel(9, 9, book.width).unwrap().a, 0);
+
+        // Transparent pixels on the far side of the wall are replaced too
+        service.fill_area(&mut book, 0, 9, 9, red, false, None).unwrap();
+        assert_eq!(book.frames[0].get_pixel(6, 0, book.width).unwrap().r, 255);
+        assert_eq!(book.frames[0].get_pixel(5, 0, book.width).unwrap().r, 0);
+    }
+
     #[test]
     fn test_set_color_operation() {
         let book = create_test_book();
@@ -683,11 +1542,255 @@ mod tests {
         
         // SetColor doesn't modify the book, just validates
         let operation = DrawingOperation::SetColor {
-            color: [255, 255, 255, 255],
+            color: [255, 255, 255, 255].into(),
         };
         
         let mut test_book = book;
         let result = service.apply_operation(&mut test_book, operation);
         assert!(result.is_ok());
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_dither_gradient_fills_rect() {
+        let mut book = create_test_book();
+        let service = DrawingService::new();
+        let (red, blue) = ([255, 0, 0, 255], [0, 0, 255, 255]);
+
+        let operation = DrawingOperation::DitherGradient {
+            frame: 0,
+            rect: Rect { x: 2, y: 2, width: 20, height: 4 },
+            from: red.into(),
+            to: blue.into(),
+            direction: GradientDirection::Horizontal,
+            blend_mode: BlendMode::Replace,
+        };
+        assert!(service.apply_operation(&mut book, operation).is_ok());
+
+        // Only red and blue inside the rect, nothing outside it
+        let colors: Vec<[u8; 4]> = (2..6).flat_map(|y| (2..10).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let p = book.frames[0].get_pixel(x, y, book.width).unwrap();
+                [p.r, p.g, p.b, p.a]
+            })
+            .collect();
+        assert!(colors.iter().all(|&c| c == red || c == blue));
+        assert!(colors.contains(&red) && colors.contains(&blue));
+        assert_eq!(book.frames[0].get_pixel(2, 6, book.width).unwrap().a, 0);
+
+        let outside = DrawingOperation::DitherGradient {
+            frame: 0,
+            rect: Rect { x: 10, y: 0, width: 2, height: 2 },
+            from: red.into(),
+            to: blue.into(),
+            direction: GradientDirection::Vertical,
+            blend_mode: BlendMode::Replace,
+        };
+        assert!(service.apply_operation(&mut book, outside).is_err());
+
+        // A rect far past the canvas only costs the canvas it covers
+        let huge = DrawingOperation::DitherGradient {
+            frame: 0,
+            rect: Rect { x: 0, y: 0, width: u16::MAX, height: u16::MAX },
+            from: red.into(),
+            to: blue.into(),
+            direction: GradientDirection::Radial,
+            blend_mode: BlendMode::Replace,
+        };
+        assert!(service.apply_operation(&mut book, huge).is_ok());
+        assert_eq!(book.frames[0].get_pixel(9, 9, book.width).unwrap().a, 255);
+    }
+
+    #[test]
+    fn test_gradient_fill_covers_only_the_seed_region() {
+        let mut book = create_test_book();
+        let service = DrawingService::new();
+        let (red, black, white) = ([255, 0, 0, 255], [0, 0, 0, 255], [255, 255, 255, 255]);
+        let block = Rect { x: 2, y: 2, width: 5, height: 5 };
+        let fill = DrawingOperation::FillArea { frame: 0, x: 2, y: 2, color: red.into(), contiguous: true, bounds: Some(block), blend_mode: BlendMode::Replace };
+        service.apply_operation(&mut book, fill).unwrap();
+
+        let gradient = DrawingOperation::GradientFill {
+            frame: 0,
+            rect: None,
+            seed: Some(Point { x: 4, y: 4 }),
+            from: black.into(),
+            to: white.into(),
+            direction: GradientDirection::Horizontal,
+            dither: DitherPattern::ErrorDiffusion,
+            blend_mode: BlendMode::Replace,
+        };
+        service.apply_operation(&mut book, gradient).unwrap();
+
+        let color = |x: u16, y: u16| {
+            let p = book.frames[0].get_pixel(x, y, book.width).unwrap();
+            [p.r, p.g, p.b, p.a]
+        };
+        assert!((2..7).all(|y| color(2, y) == black && color(6, y) == white));
+        assert!((2..7).all(|y| (2..7).all(|x| color(x, y) != red)));
+        assert_eq!(color(1, 1), [0, 0, 0, 0]);
+
+        let neither = DrawingOperation::GradientFill {
+            frame: 0,
+            rect: None,
+            seed: None,
+            from: black.into(),
+            to: white.into(),
+            direction: GradientDirection::Radial,
+            dither: DitherPattern::Ordered,
+            blend_mode: BlendMode::Replace,
+        };
+        assert!(matches!(service.apply_operation(&mut book, neither), Err(PixelError::InvalidFormat { .. })));
+    }
+
+    #[test]
+    fn test_noise_fill_speckles_rect_reproducibly() {
+        let service = DrawingService::new();
+        let (green, moss) = ([60, 140, 60, 255], [40, 110, 40, 255]);
+        let noise = |seed, density| DrawingOperation::NoiseFill {
+            frame: 0,
+            rect: Rect { x: 2, y: 2, width: 6, height: 6 },
+            colors: vec![green.into(), moss.into()],
+            density,
+            seed,
+            blend_mode: BlendMode::Replace,
+        };
+
+        let mut book = create_test_book();
+        service.apply_operation(&mut book, noise(Some(3), 0.5)).unwrap();
+        let mut again = create_test_book();
+        service.apply_operation(&mut again, noise(Some(3), 0.5)).unwrap();
+        assert_eq!(book.frames[0].pixels, again.frames[0].pixels);
+
+        let painted: Vec<[u8; 4]> = book.frames[0].pixels.chunks(4)
+            .map(|p| [p[0], p[1], p[2], p[3]])
+            .filter(|p| p[3] != 0)
+            .collect();
+        assert!(painted.len() > 5 && painted.len() < 31);
+        assert!(painted.iter().all(|p| *p == green || *p == moss));
+        // Nothing outside the rect is touched
+        assert!((0..10).all(|i| book.frames[0].get_pixel(i, 0, 10).unwrap().a == 0 && book.frames[0].get_pixel(9, i, 10).unwrap().a == 0));
+
+        assert!(matches!(service.apply_operation(&mut book, noise(None, 1.5)), Err(PixelError::InvalidFormat { .. })));
+    }
+
+    #[test]
+    fn test_copy_region_between_frames() {
+        let mut book = PixelBook::new("test.pxl".to_string(), 10, 10, 2);
+        let service = DrawingService::new();
+
+        service.draw_pixel(&mut book, 0, 1, 1, [255, 0, 0, 255]).unwrap();
+        service.draw_pixel(&mut book, 0, 2, 2, [0, 255, 0, 255]).unwrap();
+
+        let result = service.copy_region(
+            &mut book,
+            0,
+            Rect { x: 1, y: 1, width: 2, height: 2 },
+            1,
+            Point { x: 6, y: 7 },
+            false,
+        );
+        assert!(result.is_ok());
+
+        let pixel = book.frames[1].get_pixel(6, 7, book.width).unwrap();
+        assert_eq!(pixel.r, 255);
+        let pixel = book.frames[1].get_pixel(7, 8, book.width).unwrap();
+        assert_eq!(pixel.g, 255);
+
+        // Source frame is left untouched
+        let pixel = book.frames[0].get_pixel(6, 7, book.width).unwrap();
+        assert_eq!(pixel.a, 0);
+    }
+
+    #[test]
+    fn test_copy_region_clips_to_bounds() {
+        let mut book = create_test_book();
+        let service = DrawingService::new();
+
+        service.draw_pixel(&mut book, 0, 0, 0, [0, 0, 255, 255]).unwrap();
+
+        // Destination near the edge: only the part that fits is copied
+        let result = service.copy_region(
+            &mut book,
+            0,
+            Rect { x: 0, y: 0, width: 5, height: 5 },
+            0,
+            Point { x: 8, y: 8 },
+            false,
+        );
+        assert!(result.is_ok());
+
+        let pixel = book.frames[0].get_pixel(8, 8, book.width).unwrap();
+        assert_eq!(pixel.b, 255);
+
+        let result = service.copy_region(
+            &mut book,
+            3,
+            Rect { x: 0, y: 0, width: 5, height: 5 },
+            0,
+            Point { x: 0, y: 0 },
+            false,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_cut_region_clears_the_source() {
+        let mut book = create_test_book();
+        let service = DrawingService::new();
+        let red = [255, 0, 0, 255];
+        let bounds = Rect { x: 0, y: 0, width: 3, height: 3 };
+        let fill = DrawingOperation::FillArea { frame: 0, x: 0, y: 0, color: red.into(), contiguous: true, bounds: Some(bounds), blend_mode: BlendMode::Replace };
+        service.apply_operation(&mut book, fill).unwrap();
+
+        // Overlapping move within one frame: one pixel right and down
+        let cut = DrawingOperation::CutRegion {
+            src_frame: 0,
+            src_rect: Rect { x: 0, y: 0, width: 3, height: 3 },
+            dst_frame: 0,
+            dst_point: Point { x: 1, y: 1 },
+        };
+        service.apply_operation(&mut book, cut).unwrap();
+
+        let alpha = |x: u16, y: u16| book.frames[0].get_pixel(x, y, book.width).unwrap().a;
+        assert_eq!(alpha(0, 0), 0);
+        assert_eq!(alpha(2, 0), 0);
+        assert_eq!(alpha(0, 2), 0);
+        assert!((1..4).all(|y| (1..4).all(|x| alpha(x, y) == 255)));
+    }
+
+    #[test]
+    fn test_horizontal_symmetry_mirrors_pixels() {
+        let mut book = create_test_book();
+        let service = DrawingService::with_symmetry(Symmetry::Horizontal);
+
+        service.draw_pixel(&mut book, 0, 1, 3, [255, 0, 0, 255]).unwrap();
+
+        let pixel = book.frames[0].get_pixel(8, 3, book.width).unwrap();
+        assert_eq!(pixel.r, 255);
+        // No vertical reflection in horizontal mode
+        let pixel = book.frames[0].get_pixel(1, 6, book.width).unwrap();
+        assert_eq!(pixel.a, 0);
+    }
+
+    #[test]
+    fn test_quad_symmetry_mirrors_shapes() {
+        let mut book = create_test_book();
+        let service = DrawingService::with_symmetry(Symmetry::Quad);
+
+        let operation = DrawingOperation::DrawShape {
+            frame: 0,
+            shape: ShapeType::Rectangle,
+            position: Point { x: 0, y: 0 },
+            size: Size { width: 2, height: 2 },
+            filled: true,
+            color: [0, 0, 255, 255].into(),
+            blend_mode: BlendMode::Replace,
+        };
+        service.apply_operation(&mut book, operation).unwrap();
+
+        for (x, y) in [(0, 0), (9, 0), (0, 9), (9, 9), (8, 8)] {
+            let pixel = book.frames[0].get_pixel(x, y, book.width).unwrap();
+            assert_eq!(pixel.b, 255, "expected mirrored pixel at ({}, {})", x, y);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use crate::models::{PixelBookEvent, Result};
+use chrono::{DateTime, Utc};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// Directory under the base path holding `<filename>.jsonl` event histories
+pub const EVENT_HISTORY_DIR: &str = ".events";
+
+pub const DEFAULT_HISTORY_LIMIT: usize = 100;
+pub const MAX_HISTORY_LIMIT: usize = 1000;
+
+/// Append-only record of every event sent for each book, so the events can
+/// be replayed after the fact or across a restart. Histories are JSON lines,
+/// oldest first, and are kept when a book is deleted.
+pub struct EventHistoryService;
+
+impl EventHistoryService {
+    fn history_path(base_path: &Path, filename: &str) -> PathBuf {
+        base_path.join(EVENT_HISTORY_DIR).join(format!("{}.jsonl", filename))
+    }
+
+    pub fn record(base_path: &Path, event: &PixelBookEvent) -> Result<()> {
+        let path = Self::history_path(base_path, &event.filename);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+        OpenOptions::new().create(true).append(true).open(&path)?.write_all(&line)?;
+        Ok(())
+    }
+
+    /// Moves a renamed book's history along with it
+    pub fn rename(base_path: &Path, filename: &str, new_filename: &str) -> Result<()> {
+        let path = Self::history_path(base_path, filename);
+        if path.exists() {
+            let destination = Self::history_path(base_path, new_filename);
+            if let Some(dir) = destination.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::rename(path, destination)?;
+        }
+        Ok(())
+    }
+
+    /// The first `limit` events after `since`, or from the start, oldest
+    /// first. Lines that cannot be parsed, such as one cut short by a crash,
+    /// are skipped.
+    pub fn events(base_path: &Path, filename: &str, since: Option<DateTime<Utc>>, limit: usize) -> Result<Vec<PixelBookEvent>> {
+        let path = Self::history_path(base_path, filename);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut events = Vec::new();
+        for line in BufReader::new(fs::File::open(&path)?).lines() {
+            if events.len() == limit {
+                break;
+            }
+            let Ok(event) = serde_json::from_str::<PixelBookEvent>(&line?) else {
+                continue;
+            };
+            if since.is_none_or(|since| event.timestamp > since) {
+                events.push(event);
+            }
+        }
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EventType;
+    use chrono::Duration;
+    use tempfile::TempDir;
+
+    fn event(filename: &str, timestamp: DateTime<Utc>, event_type: EventType) -> PixelBookEvent {
+        PixelBookEvent { filename: filename.to_string(), timestamp, event_type, origin: None }
+    }
+
+    #[test]
+    fn test_events_since_and_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path();
+        let start = Utc::now();
+        EventHistoryService::record(base, &event("log.pxl", start, EventType::BookLoaded)).unwrap();
+        EventHistoryService::record(base, &event("log.pxl", start + Duration::seconds(1), EventType::BookSaved)).unwrap();
+        EventHistoryService::record(base, &event("log.pxl", start + Duration::seconds(2), EventType::BookDeleted)).unwrap();
+
+        let all = EventHistoryService::events(base, "log.pxl", None, DEFAULT_HISTORY_LIMIT).unwrap();
+        assert_eq!(all.len(), 3);
+        assert!(matches!(all[0].event_type, EventType::BookLoaded));
+
+        let later = EventHistoryService::events(base, "log.pxl", Some(start), 1).unwrap();
+        assert_eq!(later.len(), 1);
+        assert!(matches!(later[0].event_type, EventType::BookSaved));
+
+        EventHistoryService::rename(base, "log.pxl", "renamed.pxl").unwrap();
+        assert!(EventHistoryService::events(base, "log.pxl", None, DEFAULT_HISTORY_LIMIT).unwrap().is_empty());
+        assert_eq!(EventHistoryService::events(base, "renamed.pxl", None, DEFAULT_HISTORY_LIMIT).unwrap().len(), 3);
+    }
+}
@@ -1,91 +1,264 @@
-use crate::models::DrawingOperation;
-use serde::{Serialize, Deserialize};
+use crate::models::{DrawingOperation, EventType, PixelBookEvent, Rect};
+use crate::services::EventHistoryService;
 use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::RwLock;
-use chrono::{DateTime, Utc};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use chrono::Utc;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PixelBookEvent {
-    pub filename: String,
-    pub timestamp: DateTime<Utc>,
-    pub event_type: EventType,
-}
+/// A window for coalescing operation bursts that suits clients which only
+/// need to know when to reload
+pub const DEFAULT_COALESCE_WINDOW: Duration = Duration::from_millis(250);
+
+/// Events a subscriber may fall behind by before it starts missing some
+pub const EVENT_CHANNEL_CAPACITY: usize = 256;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type")]
-pub enum EventType {
-    #[serde(rename = "drawing_operation")]
-    DrawingOperation { operation: DrawingOperation },
-    #[serde(rename = "book_saved")]
-    BookSaved,
-    #[serde(rename = "book_loaded")]
-    BookLoaded,
-    #[serde(rename = "frame_changed")]
-    FrameChanged { frame_index: usize },
-    #[serde(rename = "heartbeat")]
-    Heartbeat,
+// Operations applied to one book since the coalescing window opened
+struct PendingOperations {
+    // Tells the window's timer whether this is still the burst it was started for
+    id: u64,
+    count: usize,
+    region: Option<Rect>,
+    saved: bool,
+    // `None` once operations from different clients are mixed in
+    origin: Option<String>,
 }
 
-pub struct EventService {
-    // In a real implementation, this would use a proper event store/database
-    events: Arc<RwLock<HashMap<String, Vec<PixelBookEvent>>>>,
+// Everything an event passes through, behind one lock so that events of a
+// book always go out in the order they happened
+#[derive(Default)]
+struct Channels {
+    books: HashMap<String, broadcast::Sender<PixelBookEvent>>,
+    pending: HashMap<String, PendingOperations>,
+    next_burst: u64,
+    // Books directory whose event histories record every event sent
+    history: Option<PathBuf>,
 }
 
-impl EventService {
-    pub fn new() -> Self {
-        Self {
-            events: Arc::new(RwLock::new(HashMap::new())),
-        }
+impl Channels {
+    fn lock(state: &Mutex<Channels>) -> MutexGuard<'_, Channels> {
+        state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
     }
-    
-    pub async fn emit_event(&self, filename: &str, event_type: EventType) {
+
+    fn send(&mut self, workspace: &broadcast::Sender<PixelBookEvent>, filename: &str, event_type: EventType, origin: Option<String>) {
         let event = PixelBookEvent {
             filename: filename.to_string(),
             timestamp: Utc::now(),
             event_type,
+            origin,
         };
         
         println!("📤 EventService: Emitting event for {}: {:?}", filename, event.event_type);
         
-        let mut events = self.events.write().await;
-        events.entry(filename.to_string())
-            .or_insert_with(Vec::new)
-            .push(event);
+        if let Some(base_path) = &self.history
+            && let Err(e) = EventHistoryService::record(base_path, &event)
+        {
+            println!("⚠️ Could not record the event history of {}: {}", filename, e);
+        }
         
-        println!("📊 EventService: Total events for {}: {}", filename, 
-            events.get(filename).map(|v| v.len()).unwrap_or(0));
+        // Sending only fails once every subscriber is gone; drop the channel then
+        if let Some(channel) = self.books.get(filename)
+            && channel.send(event.clone()).is_err()
+        {
+            self.books.remove(filename);
+        }
+        let _ = workspace.send(event);
     }
     
-    pub async fn get_recent_events(&self, filename: &str, since: DateTime<Utc>) -> Vec<PixelBookEvent> {
-        let events = self.events.read().await;
-        
-        if let Some(file_events) = events.get(filename) {
-            file_events
-                .iter()
-                .filter(|event| event.timestamp > since)
-                .cloned()
-                .collect()
-        } else {
-            Vec::new()
+    /// Turns the pending burst of `filename`, if any, into events
+    fn flush(&mut self, workspace: &broadcast::Sender<PixelBookEvent>, filename: &str) {
+        if let Some(burst) = self.pending.remove(filename) {
+            self.send(workspace, filename, EventType::OperationsApplied { count: burst.count, region: burst.region }, burst.origin);
+            if burst.saved {
+                self.send(workspace, filename, EventType::BookSaved, None);
+            }
+        }
+    }
+}
+
+/// Pushes book events to subscribers as they happen. Each book has its own
+/// broadcast channel, created when someone subscribes and dropped once
+/// nobody listens, and every event also goes to a workspace-wide channel.
+/// Nothing is buffered for clients that are not connected, but with a
+/// history directory set every event is also written to the book's history.
+pub struct EventService {
+    state: Arc<Mutex<Channels>>,
+    workspace: broadcast::Sender<PixelBookEvent>,
+    coalesce_window: Option<Duration>,
+    clients: Arc<AtomicUsize>,
+}
+
+/// Counts one connected event stream for as long as it is held
+pub struct ClientGuard {
+    clients: Arc<AtomicUsize>,
+}
+
+impl Drop for ClientGuard {
+    fn drop(&mut self) {
+        self.clients.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Default for EventService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventService {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(Channels::default())),
+            workspace: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            coalesce_window: None,
+            clients: Arc::new(AtomicUsize::new(0)),
         }
     }
     
-    pub async fn clear_old_events(&self, filename: &str, older_than: DateTime<Utc>) {
-        let mut events = self.events.write().await;
-        
-        if let Some(file_events) = events.get_mut(filename) {
-            file_events.retain(|event| event.timestamp > older_than);
+    /// Record every event in the event history of its book under `base_path`
+    pub fn with_history(self, base_path: Option<PathBuf>) -> Self {
+        Channels::lock(&self.state).history = base_path;
+        self
+    }
+    
+    /// Records later events under another books directory, e.g. after the
+    /// server's path changed. Does nothing when histories are off.
+    pub fn move_history(&self, base_path: PathBuf) {
+        let mut channels = Channels::lock(&self.state);
+        if channels.history.is_some() {
+            channels.history = Some(base_path);
         }
     }
     
+    /// Registers a connected event stream until the guard is dropped
+    pub fn track_client(&self) -> ClientGuard {
+        self.clients.fetch_add(1, Ordering::Relaxed);
+        ClientGuard { clients: self.clients.clone() }
+    }
+    
+    /// Event streams currently connected
+    pub fn active_clients(&self) -> usize {
+        self.clients.load(Ordering::Relaxed)
+    }
+    
+    /// Collect operations (and the saves that follow them) arriving within
+    /// `window` into a single `operations_applied` event plus one `book_saved`.
+    /// `None` emits one `drawing_operation` event per operation.
+    pub fn with_coalesce_window(mut self, window: Option<Duration>) -> Self {
+        self.coalesce_window = window.filter(|window| !window.is_zero());
+        self
+    }
+    
+    /// Events of `filename` from now on
+    pub fn subscribe(&self, filename: &str) -> broadcast::Receiver<PixelBookEvent> {
+        let mut channels = Channels::lock(&self.state);
+        channels.books.retain(|_, channel| channel.receiver_count() > 0);
+        channels.books.entry(filename.to_string())
+            .or_insert_with(|| broadcast::channel(EVENT_CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+    
+    /// Events of every book from now on
+    pub fn subscribe_all(&self) -> broadcast::Receiver<PixelBookEvent> {
+        self.workspace.subscribe()
+    }
+    
+    /// Books that currently have a channel of their own
+    pub fn channel_count(&self) -> usize {
+        Channels::lock(&self.state).books.len()
+    }
+    
+    pub async fn emit_event(&self, filename: &str, event_type: EventType) {
+        let mut channels = Channels::lock(&self.state);
+        // Anything else happening to the book must come after its pending burst
+        channels.flush(&self.workspace, filename);
+        channels.send(&self.workspace, filename, event_type, None);
+    }
+    
     // Global event handlers for integration
     pub async fn on_drawing_operation(&self, filename: &str, operation: DrawingOperation) {
         self.emit_event(filename, EventType::DrawingOperation { operation }).await;
     }
     
+    /// Records a batch of operations applied in one request. `region` bounds
+    /// the pixels they changed; `origin` identifies the client that sent them.
+    pub async fn on_operations_applied(&self, filename: &str, operations: &[DrawingOperation], region: Option<Rect>, origin: Option<&str>) {
+        let mut channels = Channels::lock(&self.state);
+        let Some(window) = self.coalesce_window else {
+            channels.flush(&self.workspace, filename);
+            for operation in operations {
+                let event_type = EventType::DrawingOperation { operation: operation.clone() };
+                channels.send(&self.workspace, filename, event_type, origin.map(str::to_string));
+            }
+            return;
+        };
+        
+        if !channels.pending.contains_key(filename) {
+            let id = channels.next_burst;
+            channels.next_burst += 1;
+            channels.pending.insert(filename.to_string(), PendingOperations {
+                id,
+                count: 0,
+                region: None,
+                saved: false,
+                origin: origin.map(str::to_string),
+            });
+            
+            // Close the window unless something else flushed this burst first
+            let state = self.state.clone();
+            let workspace = self.workspace.clone();
+            let filename = filename.to_string();
+            tokio::spawn(async move {
+                tokio::time::sleep(window).await;
+                let mut channels = Channels::lock(&state);
+                if channels.pending.get(&filename).is_some_and(|burst| burst.id == id) {
+                    channels.flush(&workspace, &filename);
+                }
+            });
+        }
+        
+        let Some(burst) = channels.pending.get_mut(filename) else { return };
+        if burst.origin.as_deref() != origin {
+            burst.origin = None;
+        }
+        burst.count += operations.len();
+        burst.region = match (burst.region.take(), region) {
+            (Some(a), Some(b)) => Some(a.union(&b)),
+            (a, b) => a.or(b),
+        };
+    }
+    
     pub async fn on_book_saved(&self, filename: &str) {
-        self.emit_event(filename, EventType::BookSaved).await;
+        let mut channels = Channels::lock(&self.state);
+        // A save closing a pending burst is reported together with it
+        if let Some(burst) = channels.pending.get_mut(filename) {
+            burst.saved = true;
+            return;
+        }
+        channels.send(&self.workspace, filename, EventType::BookSaved, None);
+    }
+    
+    pub async fn on_book_deleted(&self, filename: &str) {
+        self.emit_event(filename, EventType::BookDeleted).await;
+    }
+    
+    /// Reports the rename under the old name, then moves the book's history
+    /// to the new one
+    pub async fn on_book_renamed(&self, filename: &str, new_filename: &str) {
+        let mut channels = Channels::lock(&self.state);
+        channels.flush(&self.workspace, filename);
+        channels.send(&self.workspace, filename, EventType::BookRenamed { new_filename: new_filename.to_string() }, None);
+        if let Some(base_path) = &channels.history
+            && let Err(e) = EventHistoryService::rename(base_path, filename, new_filename)
+        {
+            println!("⚠️ Could not move the event history of {}: {}", filename, e);
+        }
+    }
+    
+    pub async fn on_book_restored(&self, filename: &str) {
+        self.emit_event(filename, EventType::BookRestored).await;
     }
     
     pub async fn on_book_loaded(&self, filename: &str) {
@@ -95,44 +268,52 @@ impl EventService {
     pub async fn on_frame_changed(&self, filename: &str, frame_index: usize) {
         self.emit_event(filename, EventType::FrameChanged { frame_index }).await;
     }
+    
+    pub async fn on_integrity_problem(&self, filename: &str, problem: &str) {
+        self.emit_event(filename, EventType::IntegrityProblem { problem: problem.to_string() }).await;
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::{DrawingOperation, Point, ShapeType, Size};
+    use crate::models::{BlendMode, DrawingOperation, Point, ShapeType, Size};
+
+    // Every event already delivered to `receiver`
+    fn drain(receiver: &mut broadcast::Receiver<PixelBookEvent>) -> Vec<PixelBookEvent> {
+        std::iter::from_fn(|| receiver.try_recv().ok()).collect()
+    }
 
     #[tokio::test]
-    async fn test_emit_and_get_events() {
+    async fn test_emit_and_receive_events() {
         let service = EventService::new();
         let filename = "test.pxl";
+        let mut events = service.subscribe(filename);
         
         // Emit a drawing operation event
         let operation = DrawingOperation::DrawPixel {
             frame: 0,
             x: 5,
             y: 5,
-            color: [255, 0, 0, 255],
+            color: [255, 0, 0, 255].into(),
+            blend_mode: BlendMode::Replace,
         };
         service.on_drawing_operation(filename, operation.clone()).await;
         
         // Emit a book saved event
         service.on_book_saved(filename).await;
         
-        // Get recent events
-        let start_time = Utc::now() - chrono::Duration::milliseconds(1000);
-        let events = service.get_recent_events(filename, start_time).await;
-        
+        let events = drain(&mut events);
         assert_eq!(events.len(), 2);
         
         // Check the drawing operation event
         if let EventType::DrawingOperation { operation: op } = &events[0].event_type {
             match op {
-                DrawingOperation::DrawPixel { frame, x, y, color } => {
+                DrawingOperation::DrawPixel { frame, x, y, color, .. } => {
                     assert_eq!(*frame, 0);
                     assert_eq!(*x, 5);
                     assert_eq!(*y, 5);
-                    assert_eq!(*color, [255, 0, 0, 255]);
+                    assert_eq!(*color, [255, 0, 0, 255].into());
                 }
                 _ => panic!("Expected DrawPixel operation"),
             }
@@ -148,53 +329,51 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_get_events_for_different_files() {
+    async fn test_events_for_different_files() {
         let service = EventService::new();
+        let mut file1 = service.subscribe("file1.pxl");
+        let mut file2 = service.subscribe("file2.pxl");
+        let mut nonexistent = service.subscribe("nonexistent.pxl");
+        let mut all = service.subscribe_all();
         
         // Emit events for different files
         service.on_book_saved("file1.pxl").await;
         service.on_book_saved("file2.pxl").await;
         
-        let start_time = Utc::now() - chrono::Duration::milliseconds(1000);
-        
-        // Get events for file1
-        let events1 = service.get_recent_events("file1.pxl", start_time).await;
+        let events1 = drain(&mut file1);
         assert_eq!(events1.len(), 1);
         assert_eq!(events1[0].filename, "file1.pxl");
         
-        // Get events for file2
-        let events2 = service.get_recent_events("file2.pxl", start_time).await;
+        let events2 = drain(&mut file2);
         assert_eq!(events2.len(), 1);
         assert_eq!(events2[0].filename, "file2.pxl");
         
-        // Get events for non-existent file
-        let events3 = service.get_recent_events("nonexistent.pxl", start_time).await;
-        assert_eq!(events3.len(), 0);
+        assert!(drain(&mut nonexistent).is_empty());
+        
+        // The workspace-wide channel sees both, oldest first
+        let all = drain(&mut all);
+        assert_eq!(all.iter().map(|e| e.filename.as_str()).collect::<Vec<_>>(), vec!["file1.pxl", "file2.pxl"]);
     }
 
     #[tokio::test]
-    async fn test_time_filtering() {
+    async fn test_only_later_events_are_received() {
         let service = EventService::new();
         let filename = "test.pxl";
         
-        // Emit an event
+        // Nothing is kept for subscribers that are not there yet
         service.on_book_saved(filename).await;
+        let mut events = service.subscribe(filename);
+        assert!(drain(&mut events).is_empty());
         
-        // Get events from the future (should be empty)
-        let future_time = Utc::now() + chrono::Duration::seconds(1);
-        let events = service.get_recent_events(filename, future_time).await;
-        assert_eq!(events.len(), 0);
-        
-        // Get events from the past (should include the event)
-        let past_time = Utc::now() - chrono::Duration::seconds(1);
-        let events = service.get_recent_events(filename, past_time).await;
-        assert_eq!(events.len(), 1);
+        service.on_book_saved(filename).await;
+        assert_eq!(drain(&mut events).len(), 1);
     }
 
     #[tokio::test]
     async fn test_all_event_types() {
         let service = EventService::new();
         let filename = "test.pxl";
+        let mut events = service.subscribe(filename);
         
         // Test all event type handlers
         let operation = DrawingOperation::DrawShape {
@@ -203,7 +382,8 @@ mod tests {
             position: Point { x: 10, y: 10 },
             size: Size { width: 5, height: 5 },
             filled: true,
-            color: [0, 255, 0, 255],
+            color: [0, 255, 0, 255].into(),
+            blend_mode: BlendMode::Replace,
         };
         
         service.on_drawing_operation(filename, operation).await;
@@ -211,9 +391,7 @@ mod tests {
         service.on_book_loaded(filename).await;
         service.on_frame_changed(filename, 2).await;
         
-        let start_time = Utc::now() - chrono::Duration::milliseconds(1000);
-        let events = service.get_recent_events(filename, start_time).await;
-        
+        let events = drain(&mut events);
         assert_eq!(events.len(), 4);
         
         // Verify event types
@@ -225,40 +403,34 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_clear_old_events() {
+    async fn test_channels_without_subscribers_are_dropped() {
         let service = EventService::new();
-        let filename = "test.pxl";
-        
-        // Emit some events
-        service.on_book_saved(filename).await;
-        service.on_book_loaded(filename).await;
-        
-        // Clear events older than now (should clear all)
-        let now = Utc::now();
-        service.clear_old_events(filename, now).await;
+        let events = service.subscribe("a.pxl");
+        let _other = service.subscribe("b.pxl");
+        assert_eq!(service.channel_count(), 2);
         
-        // Should have no events now
-        let past_time = Utc::now() - chrono::Duration::seconds(1);
-        let events = service.get_recent_events(filename, past_time).await;
-        assert_eq!(events.len(), 0);
+        drop(events);
+        service.on_book_saved("a.pxl").await;
+        assert_eq!(service.channel_count(), 1);
     }
 
     #[tokio::test]
     async fn test_event_serialization() {
         let service = EventService::new();
         let filename = "test.pxl";
+        let mut events = service.subscribe(filename);
         
         let operation = DrawingOperation::DrawPixel {
             frame: 1,
             x: 3,
             y: 7,
-            color: [128, 64, 192, 255],
+            color: [128, 64, 192, 255].into(),
+            blend_mode: BlendMode::Replace,
         };
         
         service.on_drawing_operation(filename, operation).await;
         
-        let start_time = Utc::now() - chrono::Duration::milliseconds(1000);
-        let events = service.get_recent_events(filename, start_time).await;
+        let events = drain(&mut events);
         
         // Test that the event can be serialized to JSON
         let json_result = serde_json::to_string(&events[0]);
@@ -269,4 +441,87 @@ mod tests {
         assert!(json.contains("\"x\":3"));
         assert!(json.contains("\"y\":7"));
     }
-} 
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_operations_are_coalesced_within_window() {
+        let service = EventService::new().with_coalesce_window(Some(Duration::from_millis(50)));
+        let filename = "burst.pxl";
+        let mut receiver = service.subscribe(filename);
+        
+        let pixel = |x| DrawingOperation::DrawPixel { frame: 0, x, y: 0, color: [255, 0, 0, 255].into(), blend_mode: BlendMode::Replace };
+        for x in 0..3 {
+            let operations: Vec<_> = (0..100).map(|_| pixel(x)).collect();
+            let region = Rect { x: x * 2, y: 1, width: 1, height: 1 };
+            service.on_operations_applied(filename, &operations, Some(region), Some("agent")).await;
+            service.on_book_saved(filename).await;
+        }
+        
+        // Nothing is sent until the window closes
+        assert!(drain(&mut receiver).is_empty());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        
+        let events = drain(&mut receiver);
+        assert_eq!(events.len(), 2);
+        match &events[0].event_type {
+            EventType::OperationsApplied { count, region: Some(region) } => {
+                assert_eq!(*count, 300);
+                assert_eq!((region.x, region.y, region.width, region.height), (0, 1, 5, 1));
+            }
+            other => panic!("Expected OperationsApplied, got {:?}", other),
+        }
+        assert_eq!(events[0].origin.as_deref(), Some("agent"));
+        assert!(matches!(events[1].event_type, EventType::BookSaved));
+        
+        // Other events flush a pending burst first, keeping the order intact
+        service.on_operations_applied(filename, &[pixel(0)], None, None).await;
+        service.on_book_deleted(filename).await;
+        let events = drain(&mut receiver);
+        assert!(matches!(events[0].event_type, EventType::OperationsApplied { count: 1, region: None }));
+        assert!(matches!(events[1].event_type, EventType::BookDeleted));
+        
+        // The window's timer leaves a burst it did not start alone
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(drain(&mut receiver).is_empty());
+    }
+
+    #[test]
+    fn test_client_guards_count_connections() {
+        let service = EventService::new();
+        let first = service.track_client();
+        let second = service.track_client();
+        assert_eq!(service.active_clients(), 2);
+        
+        drop(first);
+        assert_eq!(service.active_clients(), 1);
+        drop(second);
+        assert_eq!(service.active_clients(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_events_are_recorded_in_the_history() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let service = EventService::new().with_history(Some(temp_dir.path().to_path_buf()));
+        
+        // Recorded whether or not anyone is listening
+        service.on_book_loaded("a.pxl").await;
+        service.on_book_saved("a.pxl").await;
+        service.on_book_renamed("a.pxl", "b.pxl").await;
+        service.on_book_saved("b.pxl").await;
+        
+        let events = EventHistoryService::events(temp_dir.path(), "b.pxl", None, 10).unwrap();
+        let types: Vec<_> = events.iter().map(|event| &event.event_type).collect();
+        assert!(matches!(types[..], [EventType::BookLoaded, EventType::BookSaved, EventType::BookRenamed { .. }, EventType::BookSaved]));
+        assert!(EventHistoryService::events(temp_dir.path(), "a.pxl", None, 10).unwrap().is_empty());
+        
+        let other_dir = tempfile::TempDir::new().unwrap();
+        service.move_history(other_dir.path().to_path_buf());
+        service.on_book_saved("b.pxl").await;
+        assert_eq!(EventHistoryService::events(temp_dir.path(), "b.pxl", None, 10).unwrap().len(), 4);
+        assert_eq!(EventHistoryService::events(other_dir.path(), "b.pxl", None, 10).unwrap().len(), 1);
+        
+        let service = EventService::new();
+        service.move_history(other_dir.path().to_path_buf());
+        service.on_book_saved("b.pxl").await;
+        assert_eq!(EventHistoryService::events(other_dir.path(), "b.pxl", None, 10).unwrap().len(), 1);
+    }
+}
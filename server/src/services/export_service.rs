@@ -0,0 +1,281 @@
+use crate::models::{blend, BlendMode, Frame, PixelBook, PixelError, Result};
+use pixl_format::{aseprite, convert};
+use pixl_format::embedded::{self, EmbeddedOptions};
+use std::io::{Cursor, Write};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Largest factor a PNG export may enlarge a frame by
+pub const MAX_PNG_SCALE: u32 = 32;
+
+/// Widest and tallest a preview may be, in pixels
+pub const MAX_PREVIEW_SIZE: u32 = 4096;
+
+/// Side of one checkerboard square behind a preview, in output pixels
+const CHECKER_SIZE: u16 = 8;
+const CHECKER_LIGHT: [u8; 4] = [255, 255, 255, 255];
+const CHECKER_DARK: [u8; 4] = [204, 204, 204, 255];
+
+/// What shows through transparent pixels of a preview: `checker`, `none`,
+/// or a solid `#rrggbb` color
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PreviewBackground {
+    #[default]
+    Checker,
+    None,
+    Color([u8; 3]),
+}
+
+impl std::str::FromStr for PreviewBackground {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "checker" => Ok(Self::Checker),
+            "none" => Ok(Self::None),
+            _ => {
+                let hex = s.strip_prefix('#').unwrap_or(s);
+                let channel = |i: usize| hex.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok());
+                match (hex.len(), channel(0), channel(2), channel(4)) {
+                    (6, Some(r), Some(g), Some(b)) => Ok(Self::Color([r, g, b])),
+                    _ => Err(format!("invalid background: {}; use checker, none or #rrggbb", s)),
+                }
+            }
+        }
+    }
+}
+
+/// Encodes books into formats consumed by other tools
+pub struct ExportService;
+
+impl ExportService {
+    /// Name of frame `index` inside a frames archive
+    pub fn frame_filename(index: usize) -> String {
+        format!("frame_{:03}.png", index)
+    }
+
+    /// One frame as a PNG, each pixel drawn as a `scale` x `scale` block
+    pub fn frame_png(book: &PixelBook, index: usize, scale: u32) -> Result<Vec<u8>> {
+        let (scaled, width, height) = scaled_frame(book, index, scale, u16::MAX as u32)?;
+        let mut png = Vec::new();
+        convert::write_png(&scaled, width, height, &mut png)?;
+        Ok(png)
+    }
+
+    /// One frame enlarged like [`ExportService::frame_png`] and composited
+    /// over `background`, for showing directly in chats and agent tools
+    pub fn preview(book: &PixelBook, index: usize, scale: u32, background: PreviewBackground) -> Result<Vec<u8>> {
+        let (mut scaled, width, height) = scaled_frame(book, index, scale, MAX_PREVIEW_SIZE)?;
+        if background != PreviewBackground::None {
+            for (i, pixel) in scaled.pixels.chunks_exact_mut(4).enumerate() {
+                let (x, y) = ((i % width as usize) as u16, (i / width as usize) as u16);
+                let behind = match background {
+                    PreviewBackground::Color([r, g, b]) => [r, g, b, 255],
+                    _ if (x / CHECKER_SIZE + y / CHECKER_SIZE).is_multiple_of(2) => CHECKER_LIGHT,
+                    _ => CHECKER_DARK,
+                };
+                let src = [pixel[0], pixel[1], pixel[2], pixel[3]];
+                pixel.copy_from_slice(&blend(BlendMode::Alpha, behind, src));
+            }
+        }
+
+        let mut png = Vec::new();
+        convert::write_png(&scaled, width, height, &mut png)?;
+        Ok(png)
+    }
+
+    /// Every frame as a looping animated GIF, `delay_ms` apart
+    pub fn gif(book: &PixelBook, delay_ms: u32) -> Result<Vec<u8>> {
+        let mut gif = Vec::new();
+        convert::write_gif(book, &mut gif, delay_ms)?;
+        Ok(gif)
+    }
+
+    /// Every frame as a numbered PNG in a ZIP archive
+    pub fn frames_zip(book: &PixelBook) -> Result<Vec<u8>> {
+        let mut entries = Vec::with_capacity(book.frames.len());
+        for (index, frame) in book.frames.iter().enumerate() {
+            let mut png = Vec::new();
+            convert::write_png(frame, book.width, book.height, &mut png)?;
+            entries.push((Self::frame_filename(index), png));
+        }
+        zip_entries(&entries)
+    }
+
+    /// A ZIP with `<stem>.png`, a sprite sheet `columns` frames wide (one row
+    /// when `None`), and `<stem>.json` describing it in Aseprite's format
+    pub fn aseprite(book: &PixelBook, columns: Option<u32>) -> Result<Vec<u8>> {
+        let stem = book.filename.trim_end_matches(".pxl");
+        let json = serde_json::to_vec_pretty(&Self::aseprite_json(book, columns))?;
+        zip_entries(&[(format!("{}.png", stem), Self::sprite_sheet(book, columns)?), (format!("{}.json", stem), json)])
+    }
+
+    /// The sprite sheet of [`ExportService::aseprite`] on its own, as a PNG
+    pub fn sprite_sheet(book: &PixelBook, columns: Option<u32>) -> Result<Vec<u8>> {
+        let columns = columns.unwrap_or_else(|| aseprite::default_columns(book));
+        let mut png = Vec::new();
+        convert::write_image_png(&aseprite::sprite_sheet(book, columns), &mut png)?;
+        Ok(png)
+    }
+
+    /// The Aseprite JSON of [`ExportService::aseprite`] on its own, naming
+    /// `<stem>.png` as its image
+    pub fn aseprite_json(book: &PixelBook, columns: Option<u32>) -> serde_json::Value {
+        let columns = columns.unwrap_or_else(|| aseprite::default_columns(book));
+        let image_name = format!("{}.png", book.filename.trim_end_matches(".pxl"));
+        aseprite::aseprite_json(book, &image_name, columns)
+    }
+
+    /// Source arrays or raw framebuffer bytes for embedded displays
+    pub fn embedded(book: &PixelBook, options: &EmbeddedOptions) -> Result<Vec<u8>> {
+        Ok(embedded::export_embedded(book, options)?)
+    }
+}
+
+// Frame `index` with each pixel drawn as a `scale` x `scale` block, refusing
+// results wider or taller than `max_size`
+fn scaled_frame(book: &PixelBook, index: usize, scale: u32, max_size: u32) -> Result<(Frame, u16, u16)> {
+    let frame = book.frames.get(index).ok_or_else(|| PixelError::InvalidFormat {
+        details: format!("Frame {} does not exist", index),
+    })?;
+    // The scale is checked before multiplying, so the sizes cannot overflow
+    let size = (1..=MAX_PNG_SCALE).contains(&scale)
+        .then(|| (book.width as u32 * scale, book.height as u32 * scale))
+        .filter(|&(width, height)| width <= max_size && height <= max_size);
+    let Some((width, height)) = size else {
+        return Err(PixelError::InvalidFormat {
+            details: format!("Cannot scale a {}x{} frame by {}; use 1-{} and stay within {} pixels", book.width, book.height, scale, MAX_PNG_SCALE, max_size),
+        });
+    };
+    if scale == 1 {
+        return Ok((frame.clone(), book.width, book.height));
+    }
+
+    let (width, height) = (width as u16, height as u16);
+    let mut scaled = Frame::new(frame.index, width, height);
+    for y in 0..height {
+        for x in 0..width {
+            if let Some(pixel) = frame.get_pixel(x / scale as u16, y / scale as u16, book.width) {
+                scaled.set_pixel(x, y, width, pixel);
+            }
+        }
+    }
+    Ok((scaled, width, height))
+}
+
+// PNG data is already compressed, so entries are stored rather than deflated
+fn zip_entries(entries: &[(String, Vec<u8>)]) -> Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+    for (name, bytes) in entries {
+        zip.start_file(name.as_str(), options).map_err(export_error)?;
+        zip.write_all(bytes)?;
+    }
+
+    Ok(zip.finish().map_err(export_error)?.into_inner())
+}
+
+fn export_error(e: zip::result::ZipError) -> PixelError {
+    PixelError::ExportFailed { details: e.to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pixl_format::Limits;
+    use crate::models::Pixel;
+    use std::io::Read;
+    use zip::ZipArchive;
+
+    #[test]
+    fn test_frames_zip_contains_numbered_pngs() {
+        let mut book = PixelBook::new("walk.pxl".to_string(), 3, 2, 3);
+        book.frames[2].set_pixel(1, 0, 3, Pixel::new(0, 255, 0, 255));
+
+        let bytes = ExportService::frames_zip(&book).unwrap();
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let names: Vec<&str> = archive.file_names().collect();
+        assert_eq!(names.len(), 3);
+        assert!(names.contains(&"frame_000.png") && names.contains(&"frame_002.png"));
+
+        let mut png = Vec::new();
+        archive.by_name("frame_002.png").unwrap().read_to_end(&mut png).unwrap();
+        let image = convert::read_image(Cursor::new(png), &Limits::default()).unwrap();
+        assert_eq!(convert::image_to_frame(2, &image).pixels, book.frames[2].pixels);
+    }
+
+    #[test]
+    fn test_frame_png_scales_pixels_into_blocks() {
+        let mut book = PixelBook::new("dot.pxl".to_string(), 2, 1, 1);
+        book.frames[0].set_pixel(1, 0, 2, Pixel::new(255, 0, 0, 255));
+
+        let png = ExportService::frame_png(&book, 0, 3).unwrap();
+        let image = convert::read_image(Cursor::new(png), &Limits::default()).unwrap();
+        assert_eq!((image.width(), image.height()), (6, 3));
+        assert_eq!(image.get_pixel(3, 2).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(2, 0).0[3], 0);
+
+        for scale in [0, MAX_PNG_SCALE + 1, 4294967295] {
+            assert!(matches!(ExportService::frame_png(&book, 0, scale), Err(PixelError::InvalidFormat { .. })));
+        }
+    }
+
+    #[test]
+    fn test_preview_fills_transparency_with_the_background() {
+        let mut book = PixelBook::new("dot.pxl".to_string(), 4, 1, 1);
+        book.frames[0].set_pixel(0, 0, 4, Pixel::new(255, 0, 0, 255));
+        book.frames[0].set_pixel(3, 0, 4, Pixel::new(0, 0, 255, 128));
+
+        let png = ExportService::preview(&book, 0, 4, PreviewBackground::Checker).unwrap();
+        let image = convert::read_image(Cursor::new(png), &Limits::default()).unwrap();
+        assert_eq!((image.width(), image.height()), (16, 4));
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(4, 0).0, CHECKER_LIGHT);
+        assert_eq!(image.get_pixel(8, 0).0, CHECKER_DARK);
+        assert_eq!(image.get_pixel(12, 0).0, [102, 102, 230, 255]);
+
+        let png = ExportService::preview(&book, 0, 1, "#102030".parse().unwrap()).unwrap();
+        assert_eq!(convert::read_image(Cursor::new(png), &Limits::default()).unwrap().get_pixel(1, 0).0, [16, 32, 48, 255]);
+        let png = ExportService::preview(&book, 0, 1, PreviewBackground::None).unwrap();
+        assert_eq!(convert::read_image(Cursor::new(png), &Limits::default()).unwrap().get_pixel(1, 0).0, [0, 0, 0, 0]);
+
+        let large = PixelBook::new("large.pxl".to_string(), 1024, 8, 1);
+        assert!(matches!(ExportService::preview(&large, 0, 8, PreviewBackground::Checker), Err(PixelError::InvalidFormat { .. })));
+        assert!("#12345".parse::<PreviewBackground>().is_err());
+        assert_eq!("none".parse(), Ok(PreviewBackground::None));
+    }
+
+    #[test]
+    fn test_gif_has_every_frame() {
+        let mut book = PixelBook::new("blink.pxl".to_string(), 2, 2, 3);
+        book.frames[1].set_pixel(0, 1, 2, Pixel::new(255, 0, 0, 255));
+
+        let bytes = ExportService::gif(&book, 80).unwrap();
+        assert!(bytes.starts_with(b"GIF8"));
+        let images = convert::read_gif(Cursor::new(bytes), &Limits::default()).unwrap();
+        assert_eq!(images.len(), 3);
+        assert_eq!(convert::image_to_frame(1, &images[1]).pixels, book.frames[1].pixels);
+    }
+
+    #[test]
+    fn test_aseprite_zip_has_sheet_and_json() {
+        let book = PixelBook::new("walk.pxl".to_string(), 4, 4, 5);
+
+        let bytes = ExportService::aseprite(&book, Some(2)).unwrap();
+        let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+
+        let mut json = String::new();
+        archive.by_name("walk.json").unwrap().read_to_string(&mut json).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["frames"].as_array().unwrap().len(), 5);
+
+        let mut png = Vec::new();
+        archive.by_name("walk.png").unwrap().read_to_end(&mut png).unwrap();
+        assert_eq!(convert::read_image(Cursor::new(png.as_slice()), &Limits::default()).unwrap().dimensions(), (8, 12));
+
+        // The standalone parts match the archive
+        assert_eq!(ExportService::sprite_sheet(&book, Some(2)).unwrap(), png);
+        assert_eq!(ExportService::aseprite_json(&book, Some(2)), json);
+    }
+}
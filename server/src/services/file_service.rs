@@ -1,23 +1,78 @@
-use crate::models::{PixelBook, Frame, PixelBookInfo, Result, PixelError};
-use std::fs::{File, OpenOptions, read_dir};
-use std::path::{Path, PathBuf};
-use std::io::{Read, Write, Seek, SeekFrom, BufWriter};
+use crate::models::{BookMetadata, Frame, FrameRange, MigrateResult, PixelBook, PixelBookInfo, TrashEntry, Result, PixelError};
+use crate::services::{ContextService, ThumbnailService};
+use pixl_format::{Limits, PxlHeader, PxlReader, PxlWriter, FORMAT_VERSION, FORMAT_VERSION_V5, SUPPORTED_VERSIONS};
+use std::fs::{self, File, read_dir};
+use std::path::{Component, Path, PathBuf};
+use std::io::{BufReader, BufWriter, Cursor, Read, Seek, Write};
+use std::time::Duration;
 use chrono::{DateTime, Utc};
 
-const MAGIC_NUMBER: u32 = 0x504958; // "PIX"
-const FORMAT_VERSION: u16 = 1;
+/// Directory under the base path where deleted books are kept
+pub const TRASH_DIR: &str = ".trash";
+
+/// How long deleted books stay restorable unless configured otherwise
+pub const DEFAULT_TRASH_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Directory under the base path holding each book's revision
+pub const REVISION_DIR: &str = ".revisions";
+
+/// Books at least this large are memory-mapped when loaded
+pub const MMAP_THRESHOLD: u64 = 1024 * 1024;
+
+/// A book's file as it was at one revision, taken by [`FileService::backup_book`]
+pub struct BookBackup {
+    filename: String,
+    bytes: Vec<u8>,
+    revision: u64,
+}
 
 pub struct FileService {
     base_path: PathBuf,
+    trash_retention: Duration,
+    auto_upgrade: bool,
+    compress_frames: bool,
+    allowed_root: Option<PathBuf>,
 }
 
 impl FileService {
     pub fn new(base_path: PathBuf) -> Self {
-        Self { base_path }
+        Self { base_path, trash_retention: DEFAULT_TRASH_RETENTION, auto_upgrade: false, compress_frames: false, allowed_root: None }
+    }
+    
+    pub fn with_trash_retention(mut self, retention: Duration) -> Self {
+        self.trash_retention = retention;
+        self
+    }
+    
+    /// Write every saved book in the newest format version. Off by default,
+    /// so books stay readable by older tools until explicitly migrated.
+    pub fn with_auto_upgrade(mut self, auto_upgrade: bool) -> Self {
+        self.auto_upgrade = auto_upgrade;
+        self
+    }
+    
+    /// Save books as format v5, run-length encoding frames where that makes
+    /// them smaller. Off by default, like auto-upgrade, since older tools
+    /// cannot read v5.
+    pub fn with_compression(mut self, compress_frames: bool) -> Self {
+        self.compress_frames = compress_frames;
+        self
+    }
+    
+    /// Only let [`FileService::set_path`] move to `root` or a directory
+    /// inside it, so a server on a shared machine cannot be pointed elsewhere
+    pub fn with_allowed_root(mut self, root: Option<PathBuf>) -> Self {
+        self.allowed_root = root;
+        self
+    }
+    
+    /// Whether `path` is an existing directory the base path may be set to
+    pub fn allows_path(&self, path: &Path) -> bool {
+        path.is_dir() && self.allowed_root.as_deref().is_none_or(|root| within(root, path))
     }
     
     pub fn set_path(&mut self, path: PathBuf) -> Result<()> {
-        if !path.exists() || !path.is_dir() {
+        if !self.allows_path(&path) {
             return Err(PixelError::InvalidPath { 
                 path: path.to_string_lossy().to_string() 
             });
@@ -30,194 +85,567 @@ impl FileService {
         &self.base_path
     }
     
-    pub fn list_books(&self) -> Result<Vec<PixelBookInfo>> {
+    /// Books in the base directory, and in its sub-folders when `recursive`.
+    /// Books in sub-folders are named by their relative path, e.g. `sprites/hero.pxl`.
+    pub fn list_books(&self, recursive: bool) -> Result<Vec<PixelBookInfo>> {
         let mut books = Vec::new();
         
-        for entry in read_dir(&self.base_path)? {
-            let entry = entry?;
-            let path = entry.path();
+        for (filename, path) in book_files(&self.base_path, recursive)? {
+            let metadata = fs::metadata(&path)?;
+            let size = metadata.len();
             
-            if path.extension().and_then(|s| s.to_str()) == Some("pxl") {
-                if let Some(filename) = path.file_name().and_then(|s| s.to_str()) {
-                    let metadata = entry.metadata()?;
-                    let size = metadata.len();
-                    
-                    // Get creation and modification times
-                    let created = metadata.created()
-                        .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
-                    let modified = metadata.modified()
-                        .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
-                    
-                    let created: DateTime<Utc> = created.into();
-                    let modified: DateTime<Utc> = modified.into();
-                    
-                    // Try to read frame count from file header
-                    let frames = self.get_frame_count(&path).unwrap_or(1);
-                    
-                    books.push(PixelBookInfo {
-                        filename: filename.to_string(),
-                        size,
-                        created,
-                        modified,
-                        frames,
-                    });
-                }
-            }
+            // Get creation and modification times
+            let created = metadata.created()
+                .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+            let modified = metadata.modified()
+                .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+            
+            let created: DateTime<Utc> = created.into();
+            let modified: DateTime<Utc> = modified.into();
+            
+            // Try to read frame count and version from file header
+            let header = pixl_format::read_header(&path).ok();
+            // Only v2 and later books carry details; skip opening the rest
+            let details = header
+                .filter(|h| h.version >= pixl_format::FORMAT_VERSION_V2)
+                .and_then(|_| PxlReader::open(&path).ok())
+                .map(|reader| reader.metadata().details.clone())
+                .unwrap_or_default();
+            
+            books.push(PixelBookInfo {
+                filename,
+                size,
+                created,
+                modified,
+                frames: header.map_or(1, |h| h.frame_count as usize),
+                width: header.map_or(0, |h| h.width),
+                height: header.map_or(0, |h| h.height),
+                version: header.map_or(0, |h| h.version),
+                lock: None,
+                details,
+            });
         }
         
         Ok(books)
     }
     
-    fn get_frame_count(&self, path: &Path) -> Result<usize> {
-        let mut file = File::open(path)?;
-        let mut header = [0u8; 16];
-        file.read_exact(&mut header)?;
-        
-        // Validate magic number
-        let magic = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
-        if magic != MAGIC_NUMBER {
-            return Err(PixelError::InvalidFormat { 
-                details: "Invalid magic number".to_string() 
-            });
+    /// Format version of a stored book, read from its header alone
+    pub fn book_version(&self, filename: &str) -> Result<u16> {
+        let path = self.resolve(filename)?;
+        if !path.exists() {
+            return Err(PixelError::FileNotFound { filename: filename.to_string() });
         }
         
-        // Read frame count
-        let frame_count = u16::from_le_bytes([header[10], header[11]]);
-        Ok(frame_count as usize)
+        Ok(pixl_format::read_header(&path)?.version)
     }
     
+    /// Loads every frame. Books of [`MMAP_THRESHOLD`] bytes or more are
+    /// memory-mapped, and their frames are copied straight out of the
+    /// mapping. Saves replace a book's file rather than truncate it, so a
+    /// mapping never loses the pages under it.
     pub fn load_book(&self, filename: &str) -> Result<PixelBook> {
-        let path = self.base_path.join(filename);
-        let mut file = File::open(&path)?;
+        let path = self.book_path(filename)?;
+        let mut book = if fs::metadata(&path)?.len() >= MMAP_THRESHOLD {
+            mapped_book(PxlReader::open_mmap(&path)?, filename, &FrameRange { start: 0, end: None })?.0
+        } else {
+            PxlReader::open(&path)?.read_book(filename)?
+        };
+        book.revision = self.revision(filename)?;
+        Ok(book)
+    }
+    
+    /// Opens a book so frames can be read one at a time. The file is read
+    /// through a buffer, so the reader may outlive the service's lock.
+    pub fn open_book(&self, filename: &str) -> Result<PxlReader<BufReader<File>>> {
+        Ok(PxlReader::open(self.book_path(filename)?)?)
+    }
+    
+    /// Loads only the frames in `range`, which keep their indices, along with
+    /// the book's total frame count. A range past the last frame loads none.
+    /// Large books are memory-mapped as in [`FileService::load_book`].
+    pub fn load_frames(&self, filename: &str, range: &FrameRange) -> Result<(PixelBook, usize)> {
+        let path = self.book_path(filename)?;
+        let (mut book, frame_count) = if fs::metadata(&path)?.len() >= MMAP_THRESHOLD {
+            mapped_book(PxlReader::open_mmap(&path)?, filename, range)?
+        } else {
+            partial_book(PxlReader::open(&path)?, filename, range)?
+        };
+        book.revision = self.revision(filename)?;
+        Ok((book, frame_count))
+    }
+    
+    /// Current revision of an existing book. A book written other than by
+    /// [`FileService::save_book`], such as by a snapshot restore or another
+    /// program, moves on to a new revision the first time it is looked at.
+    pub fn revision(&self, filename: &str) -> Result<u64> {
+        let stamp = file_stamp(&self.book_path(filename)?)?;
+        match self.recorded_revision(filename) {
+            Some((revision, recorded)) if recorded == stamp => Ok(revision),
+            recorded => {
+                let revision = recorded.map_or(0, |(revision, _)| revision) + 1;
+                self.record_revision(filename, revision, stamp)?;
+                Ok(revision)
+            }
+        }
+    }
+    
+    // Revisions are kept as `<revision> <modified nanos> <size>`, the book's
+    // file as it was when that revision was recorded
+    fn revision_path(&self, filename: &str) -> PathBuf {
+        self.base_path.join(REVISION_DIR).join(filename)
+    }
+    
+    fn recorded_revision(&self, filename: &str) -> Option<(u64, FileStamp)> {
+        let text = fs::read_to_string(self.revision_path(filename)).ok()?;
+        let mut fields = text.split_whitespace().map(str::parse::<u128>);
+        match (fields.next(), fields.next(), fields.next()) {
+            (Some(Ok(revision)), Some(Ok(modified)), Some(Ok(size))) => Some((revision as u64, (modified, size as u64))),
+            _ => None,
+        }
+    }
+    
+    fn record_revision(&self, filename: &str, revision: u64, (modified, size): FileStamp) -> Result<()> {
+        let path = self.revision_path(filename);
+        create_parent(&path)?;
+        fs::write(path, format!("{} {} {}\n", revision, modified, size))?;
+        Ok(())
+    }
+    
+    // Path of an existing book
+    fn book_path(&self, filename: &str) -> Result<PathBuf> {
+        let path = self.resolve(filename)?;
+        if !path.exists() {
+            return Err(PixelError::FileNotFound { filename: filename.to_string() });
+        }
+        Ok(path)
+    }
+    
+    /// Where `filename` lives under the base path. Names that are absolute or
+    /// climb out with `..` are refused, as are names that would reach outside
+    /// the base path through a symlink, whether or not the book exists yet.
+    pub fn resolve(&self, filename: &str) -> Result<PathBuf> {
+        let relative = Path::new(filename);
+        let plain = relative.components().all(|component| matches!(component, Component::Normal(_)));
+        let path = self.base_path.join(relative);
+        if !plain || !within(&self.base_path, &path) {
+            return Err(PixelError::InvalidPath { path: filename.to_string() });
+        }
+        Ok(path)
+    }
+    
+    /// Reads the whole book, checking its header, metadata and frame table
+    /// and that every frame's data is present
+    pub fn verify_book(&self, filename: &str) -> Result<()> {
+        let mut reader = self.open_book(filename)?;
+        for frame in reader.frames() {
+            frame?;
+        }
+        Ok(())
+    }
+    
+    /// Reads only the header and metadata block, without any pixel data.
+    /// Returns `None` when the book does not exist.
+    pub fn load_metadata(&self, filename: &str) -> Result<Option<BookMetadata>> {
+        let path = self.resolve(filename)?;
+        if !path.exists() {
+            return Ok(None);
+        }
         
-        // Read and validate header
-        let mut header = [0u8; 16];
-        file.read_exact(&mut header)?;
+        let reader = PxlReader::open(&path)?;
+        Ok(Some(reader.metadata().clone()))
+    }
+    
+    /// Writes a book and returns its new revision. The book is written
+    /// beside the old file and renamed over it, so readers never see half a
+    /// book and memory-mapped readers keep the file they opened.
+    pub fn save_book(&self, book: &PixelBook) -> Result<u64> {
+        let path = self.resolve(&book.filename)?;
+        create_parent(&path)?;
+        replace_file(&path, |file| {
+            let file = BufWriter::new(file);
+            let file = if self.auto_upgrade {
+                PxlWriter::write_book_as(file, book, FORMAT_VERSION)?
+            } else if self.compress_frames {
+                PxlWriter::write_book_as(file, book, FORMAT_VERSION_V5)?
+            } else {
+                PxlWriter::write_book(file, book)?
+            };
+            file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+            Ok(())
+        })?;
         
-        let magic = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
-        if magic != MAGIC_NUMBER {
-            return Err(PixelError::InvalidFormat { 
-                details: "Invalid magic number".to_string() 
+        let revision = self.recorded_revision(&book.filename).map_or(0, |(revision, _)| revision) + 1;
+        self.record_revision(&book.filename, revision, file_stamp(&path)?)?;
+        ThumbnailService::invalidate(&self.base_path, &book.filename);
+        Ok(revision)
+    }
+    
+    /// Copies an existing book's file along with its current revision
+    pub fn backup_book(&self, filename: &str) -> Result<BookBackup> {
+        let revision = self.revision(filename)?;
+        let bytes = fs::read(self.book_path(filename)?)?;
+        Ok(BookBackup { filename: filename.to_string(), bytes, revision })
+    }
+    
+    /// Puts a backed-up book back exactly as it was. It keeps its old revision
+    /// rather than moving on to a new one, so ETags issued for it stay valid.
+    pub fn restore_backup(&self, backup: &BookBackup) -> Result<()> {
+        let path = self.resolve(&backup.filename)?;
+        replace_file(&path, |mut file| {
+            file.write_all(&backup.bytes)?;
+            file.sync_all()?;
+            Ok(())
+        })?;
+        self.record_revision(&backup.filename, backup.revision, file_stamp(&path)?)?;
+        ThumbnailService::invalidate(&self.base_path, &backup.filename);
+        Ok(())
+    }
+    
+    /// Rewrites a book in format `version` (the newest when `None`). Books
+    /// already at that version are left untouched.
+    pub fn migrate_book(&self, filename: &str, version: Option<u16>) -> Result<MigrateResult> {
+        let to_version = version.unwrap_or(FORMAT_VERSION);
+        if !SUPPORTED_VERSIONS.contains(&to_version) {
+            return Err(PixelError::InvalidFormat {
+                details: format!("Unsupported target version {}", to_version),
             });
         }
         
-        let version = u16::from_le_bytes([header[4], header[5]]);
-        if version != FORMAT_VERSION {
-            return Err(PixelError::InvalidFormat { 
-                details: format!("Unsupported version: {}", version) 
-            });
+        let from_version = self.book_version(filename)?;
+        if from_version != to_version {
+            // Encode fully before touching the file; a downgrade can fail on metadata
+            let book = self.load_book(filename)?;
+            let bytes = PxlWriter::write_book_as(Vec::new(), &book, to_version)?;
+            replace_file(&self.resolve(filename)?, |mut file| Ok(file.write_all(&bytes)?))?;
         }
         
-        let width = u16::from_le_bytes([header[6], header[7]]);
-        let height = u16::from_le_bytes([header[8], header[9]]);
-        let frame_count = u16::from_le_bytes([header[10], header[11]]);
-        
-        if width == 0 || height == 0 || frame_count == 0 {
+        Ok(MigrateResult { filename: filename.to_string(), from_version, to_version })
+    }
+    
+    pub fn create_book(&self, filename: &str, width: u16, height: u16, frames: usize) -> Result<PixelBook> {
+        if width == 0 || height == 0 || frames == 0 {
             return Err(PixelError::InvalidFormat { 
-                details: "Invalid dimensions or frame count".to_string() 
+                details: "Width, height, and frame count must be greater than 0".to_string() 
             });
         }
         
-        // Read frame metadata
-        let mut frame_offsets = Vec::new();
-        let mut frame_sizes = Vec::new();
+        Self::check_limits(width, height, frames)?;
         
-        for _ in 0..frame_count {
-            let mut metadata = [0u8; 8];
-            file.read_exact(&mut metadata)?;
-            
-            let offset = u32::from_le_bytes([metadata[0], metadata[1], metadata[2], metadata[3]]);
-            let size = u32::from_le_bytes([metadata[4], metadata[5], metadata[6], metadata[7]]);
-            
-            frame_offsets.push(offset);
-            frame_sizes.push(size);
+        let mut book = PixelBook::new(filename.to_string(), width, height, frames);
+        book.revision = self.save_book(&book)?;
+        Ok(book)
+    }
+    
+    /// Saves a book built elsewhere (for example decoded from an image),
+    /// applying the same limits as [`FileService::create_book`]
+    pub fn import_book(&self, book: &PixelBook) -> Result<()> {
+        Self::check_limits(book.width, book.height, book.frames.len())?;
+        self.save_book(book)?;
+        Ok(())
+    }
+    
+    /// Refuses sizes the reader would not load again, so no such book is ever written
+    pub fn check_limits(width: u16, height: u16, frames: usize) -> Result<()> {
+        let frame_count = u16::try_from(frames).unwrap_or(u16::MAX);
+        Limits::default().check_header(&PxlHeader::new(width, height, frame_count))?;
+        Ok(())
+    }
+    
+    /// Moves a book to `new_filename`, which must not exist yet
+    pub fn rename_book(&self, filename: &str, new_filename: &str) -> Result<()> {
+        let path = self.book_path(filename)?;
+        let destination = self.resolve(new_filename)?;
+        if destination.exists() {
+            return Err(PixelError::AlreadyExists { filename: new_filename.to_string() });
         }
+        create_parent(&destination)?;
+        fs::rename(path, destination)?;
+        ThumbnailService::invalidate(&self.base_path, filename);
+        ThumbnailService::invalidate(&self.base_path, new_filename);
+        ContextService::rename(&self.base_path, filename, new_filename)?;
         
-        // Read frame data
-        let mut frames = Vec::new();
-        let expected_frame_size = (width as u32 * height as u32 * 4) as usize;
-        
-        for (i, (&offset, &size)) in frame_offsets.iter().zip(frame_sizes.iter()).enumerate() {
-            if size as usize != expected_frame_size {
-                return Err(PixelError::InvalidFormat { 
-                    details: format!("Invalid frame size for frame {}", i) 
-                });
-            }
-            
-            file.seek(SeekFrom::Start(offset as u64))?;
-            
-            let mut pixel_data = vec![0u8; size as usize];
-            file.read_exact(&mut pixel_data)?;
-            
-            // Store raw pixel data directly
-            frames.push(Frame { index: i, pixels: pixel_data });
+        // The book keeps counting its revisions under the new name
+        let revisions = self.revision_path(filename);
+        if revisions.exists() {
+            let new_revisions = self.revision_path(new_filename);
+            create_parent(&new_revisions)?;
+            fs::rename(revisions, new_revisions)?;
         }
+        Ok(())
+    }
+    
+    /// Duplicates a book as `new_filename`, which must not exist yet. The
+    /// copy is written under a temporary name first, so it only ever appears complete.
+    pub fn copy_book(&self, filename: &str, new_filename: &str) -> Result<()> {
+        let path = self.book_path(filename)?;
+        let destination = self.resolve(new_filename)?;
+        if destination.exists() {
+            return Err(PixelError::AlreadyExists { filename: new_filename.to_string() });
+        }
+        create_parent(&destination)?;
+        let temp = destination.with_file_name(format!(".{}.tmp", file_name(new_filename)));
+        fs::copy(path, &temp)?;
+        fs::rename(&temp, destination)?;
+        Ok(())
+    }
+    
+    fn trash_path(&self) -> PathBuf {
+        self.base_path.join(TRASH_DIR)
+    }
+    
+    /// Moves a book into the trash. Trashed copies are named
+    /// `<deleted millis>_<filename>` so repeated deletes never collide, and
+    /// keep the sub-folder the book was in.
+    pub fn delete_book(&self, filename: &str) -> Result<TrashEntry> {
+        let path = self.book_path(filename)?;
         
-        Ok(PixelBook {
-            filename: filename.to_string(),
-            width,
-            height,
-            frames,
-        })
-    }
-    
-    pub fn save_book(&self, book: &PixelBook) -> Result<()> {
-        let path = self.base_path.join(&book.filename);
-        let mut file = BufWriter::new(OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&path)?);
-        
-        let frame_count = book.frames.len() as u16;
-        let frame_size = (book.width as u32 * book.height as u32 * 4) as u32;
-        
-        // Calculate frame offsets
-        let header_size = 16u32;
-        let metadata_size = frame_count as u32 * 8;
-        let mut current_offset = header_size + metadata_size;
-        
-        // Write header
-        file.write_all(&MAGIC_NUMBER.to_le_bytes())?;
-        file.write_all(&FORMAT_VERSION.to_le_bytes())?;
-        file.write_all(&book.width.to_le_bytes())?;
-        file.write_all(&book.height.to_le_bytes())?;
-        file.write_all(&frame_count.to_le_bytes())?;
-        file.write_all(&[0u8; 4])?; // Reserved
-        
-        // Write frame metadata
-        for _ in 0..frame_count {
-            file.write_all(&current_offset.to_le_bytes())?;
-            file.write_all(&frame_size.to_le_bytes())?;
-            current_offset += frame_size;
+        self.purge_expired_trash()?;
+        let trash_dir = self.trash_path().join(filename).parent().map(Path::to_path_buf).unwrap_or_else(|| self.trash_path());
+        fs::create_dir_all(&trash_dir)?;
+        
+        // Bump the timestamp in the unlikely case of two deletes in the same millisecond
+        let mut deleted_at = Utc::now();
+        let trash_name = |deleted_at: DateTime<Utc>| trash_dir.join(format!("{}_{}", deleted_at.timestamp_millis(), file_name(filename)));
+        let mut trash_file = trash_name(deleted_at);
+        while trash_file.exists() {
+            deleted_at += chrono::Duration::milliseconds(1);
+            trash_file = trash_name(deleted_at);
         }
         
-        // Write frame data
-        for frame in &book.frames {
-            file.write_all(&frame.pixels)?;
+        let size = fs::metadata(&path)?.len();
+        fs::rename(&path, trash_file)?;
+        ThumbnailService::invalidate(&self.base_path, filename);
+        ContextService::remove(&self.base_path, filename);
+        
+        Ok(self.trash_entry(filename, size, deleted_at))
+    }
+    
+    /// Trashed books, newest first, after dropping any past their retention
+    pub fn list_trash(&self) -> Result<Vec<TrashEntry>> {
+        self.purge_expired_trash()?;
+        let mut entries: Vec<TrashEntry> = self.trashed_files()?
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.deleted_at));
+        Ok(entries)
+    }
+    
+    /// Restores the most recently deleted copy of `filename`
+    pub fn restore_book(&self, filename: &str) -> Result<TrashEntry> {
+        let destination = self.resolve(filename)?;
+        if destination.exists() {
+            return Err(PixelError::AlreadyExists { filename: filename.to_string() });
         }
         
-        file.flush()?;
-        Ok(())
+        self.purge_expired_trash()?;
+        let (path, entry) = self.trashed_files()?
+            .into_iter()
+            .filter(|(_, entry)| entry.filename == filename)
+            .max_by_key(|(_, entry)| entry.deleted_at)
+            .ok_or_else(|| PixelError::FileNotFound { filename: filename.to_string() })?;
+        
+        create_parent(&destination)?;
+        fs::rename(path, destination)?;
+        Ok(entry)
     }
     
-    pub fn create_book(&self, filename: &str, width: u16, height: u16, frames: usize) -> Result<PixelBook> {
-        if width == 0 || height == 0 || frames == 0 {
-            return Err(PixelError::InvalidFormat { 
-                details: "Width, height, and frame count must be greater than 0".to_string() 
-            });
+    /// Permanently removes trashed books older than the retention period
+    pub fn purge_expired_trash(&self) -> Result<usize> {
+        let now = Utc::now();
+        let mut purged = 0;
+        for (path, entry) in self.trashed_files()? {
+            if entry.expires_at <= now {
+                fs::remove_file(path)?;
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+    
+    fn trashed_files(&self) -> Result<Vec<(PathBuf, TrashEntry)>> {
+        let trash = self.trash_path();
+        if !trash.is_dir() {
+            return Ok(Vec::new());
         }
         
-        let book = PixelBook::new(filename.to_string(), width, height, frames);
-        self.save_book(&book)?;
-        Ok(book)
+        let mut files = Vec::new();
+        let mut dirs = vec![trash];
+        while let Some(dir) = dirs.pop() {
+            for entry in read_dir(&dir)? {
+                let entry = entry?;
+                let path = entry.path();
+                if entry.file_type()?.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+                let name = entry.file_name().to_string_lossy().to_string();
+                
+                // Skip anything that doesn't follow the `<millis>_<filename>` scheme
+                let Some((millis, name)) = name.split_once('_') else { continue };
+                let Some(deleted_at) = millis.parse().ok().and_then(DateTime::from_timestamp_millis) else { continue };
+                let folder = relative_name(&self.trash_path(), &dir);
+                let filename = if folder.is_empty() { name.to_string() } else { format!("{}/{}", folder, name) };
+                
+                let size = entry.metadata()?.len();
+                files.push((path, self.trash_entry(&filename, size, deleted_at)));
+            }
+        }
+        Ok(files)
+    }
+    
+    fn trash_entry(&self, filename: &str, size: u64, deleted_at: DateTime<Utc>) -> TrashEntry {
+        let retention = chrono::Duration::from_std(self.trash_retention)
+            .unwrap_or(chrono::Duration::MAX);
+        TrashEntry {
+            filename: filename.to_string(),
+            size,
+            deleted_at,
+            expires_at: deleted_at.checked_add_signed(retention).unwrap_or(DateTime::<Utc>::MAX_UTC),
+        }
+    }
+}
+
+/// Every `.pxl` file under `base_path`, named by its path relative to it with
+/// `/` separators. Hidden entries, such as the trash and snapshot folders,
+/// are skipped, as are symlinks leading outside `base_path`; sub-folders are
+/// only entered when `recursive`.
+pub fn book_files(base_path: &Path, recursive: bool) -> Result<Vec<(String, PathBuf)>> {
+    let mut books = Vec::new();
+    let mut dirs = vec![base_path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let file_type = entry.file_type()?;
+            if file_type.is_symlink() && !within(base_path, &path) {
+                continue;
+            }
+            if file_type.is_dir() {
+                if recursive {
+                    dirs.push(path);
+                }
+            } else if path.extension().and_then(|s| s.to_str()) == Some("pxl") {
+                books.push((relative_name(base_path, &path), path));
+            }
+        }
+    }
+    Ok(books)
+}
+
+// `path` relative to `base`, with `/` separators
+fn relative_name(base: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(base).unwrap_or(path);
+    relative.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// Last segment of a book name that may include sub-folders
+fn file_name(filename: &str) -> &str {
+    filename.rsplit('/').next().unwrap_or(filename)
+}
+
+// Whether `path` stays inside `base` once symlinks in the part of it that
+// already exists are resolved. A dangling symlink never counts as inside.
+fn within(base: &Path, path: &Path) -> bool {
+    let Ok(base) = base.canonicalize() else { return false };
+    let mut existing = path;
+    while existing.symlink_metadata().is_err() {
+        match existing.parent() {
+            Some(parent) => existing = parent,
+            None => return false,
+        }
+    }
+    existing.canonicalize().is_ok_and(|existing| existing.starts_with(base))
+}
+
+// A file's modification time in nanoseconds and its size
+type FileStamp = (u128, u64);
+
+fn file_stamp(path: &Path) -> Result<FileStamp> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata.modified()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |since| since.as_nanos());
+    Ok((modified, metadata.len()))
+}
+
+// Creates the sub-folders a book at `path` lives in
+fn create_parent(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(())
+}
+
+// Writes `path` through a temporary file beside it, renamed over it once
+// `write` succeeds. Files already open, or mapped, keep the old contents.
+fn replace_file(path: &Path, write: impl FnOnce(File) -> Result<()>) -> Result<()> {
+    let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let temp = path.with_file_name(format!(".{}.tmp", name));
+    let written = File::create(&temp).map_err(PixelError::from).and_then(write);
+    if let Err(e) = written.and_then(|()| Ok(fs::rename(&temp, path)?)) {
+        let _ = fs::remove_file(&temp);
+        return Err(e);
     }
+    Ok(())
+}
+
+// The frames of a memory-mapped or in-memory `reader` selected by `range`,
+// copied from the source without staging them in a read buffer
+fn mapped_book<T: AsRef<[u8]>>(reader: PxlReader<Cursor<T>>, filename: &str, range: &FrameRange) -> Result<(PixelBook, usize)> {
+    let frame_count = reader.frame_count();
+    let frames = match range.resolve(frame_count) {
+        Some(indices) => indices
+            .map(|index| Ok(Frame {
+                index,
+                pixels: reader.frame_bytes(index)?.into_owned(),
+                duration_ms: reader.frame_entries()[index].duration(),
+            }))
+            .collect::<Result<Vec<_>>>()?,
+        None => Vec::new(),
+    };
+    
+    let book = PixelBook {
+        filename: filename.to_string(),
+        width: reader.width(),
+        height: reader.height(),
+        frames,
+        metadata: reader.metadata().clone(),
+        revision: 0,
+    };
+    Ok((book, frame_count))
+}
+
+// The frames of `reader` selected by `range`, with the total frame count
+fn partial_book<R: Read + Seek>(mut reader: PxlReader<R>, filename: &str, range: &FrameRange) -> Result<(PixelBook, usize)> {
+    let frame_count = reader.frame_count();
+    let frames = match range.resolve(frame_count) {
+        Some(indices) => indices.map(|index| reader.read_frame(index)).collect::<std::result::Result<Vec<_>, _>>()?,
+        None => Vec::new(),
+    };
+    
+    let book = PixelBook {
+        filename: filename.to_string(),
+        width: reader.width(),
+        height: reader.height(),
+        frames,
+        metadata: reader.metadata().clone(),
+        revision: 0,
+    };
+    Ok((book, frame_count))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use tempfile::TempDir;
+    use crate::models::Pixel;
     
     #[test]
     fn test_create_and_load_pixel_book() {
@@ -236,6 +664,61 @@ mod tests {
         assert_eq!(loaded_book.height, 4);
         assert_eq!(loaded_book.frames.len(), 2);
         assert_eq!(loaded_book.filename, "test.pxl");
+        
+        assert!(file_service.load_metadata("test.pxl").unwrap().unwrap().is_empty());
+        assert!(file_service.load_metadata("missing.pxl").unwrap().is_none());
+        
+        // Missing books are reported as such rather than as IO errors
+        assert!(matches!(
+            file_service.load_book("missing.pxl"),
+            Err(PixelError::FileNotFound { .. })
+        ));
+    }
+    
+    #[test]
+    #[cfg(unix)]
+    fn test_paths_outside_the_base_are_refused() {
+        let temp_dir = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        let file_service = FileService::new(temp_dir.path().to_path_buf());
+        FileService::new(outside.path().to_path_buf()).create_book("secret.pxl", 2, 2, 1).unwrap();
+        
+        for filename in ["../escape.pxl", "sprites/../../escape.pxl", "/tmp/escape.pxl"] {
+            assert!(matches!(file_service.create_book(filename, 2, 2, 1), Err(PixelError::InvalidPath { .. })), "{filename}");
+            assert!(matches!(file_service.load_book(filename), Err(PixelError::InvalidPath { .. })), "{filename}");
+        }
+        
+        // Symlinks out of the base path, to a book or a folder, are neither read nor written through
+        std::os::unix::fs::symlink(outside.path().join("secret.pxl"), temp_dir.path().join("link.pxl")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), temp_dir.path().join("linked")).unwrap();
+        std::os::unix::fs::symlink(outside.path().join("new.pxl"), temp_dir.path().join("dangling.pxl")).unwrap();
+        assert!(matches!(file_service.load_book("link.pxl"), Err(PixelError::InvalidPath { .. })));
+        assert!(matches!(file_service.create_book("linked/new.pxl", 2, 2, 1), Err(PixelError::InvalidPath { .. })));
+        assert!(matches!(file_service.create_book("dangling.pxl", 2, 2, 1), Err(PixelError::InvalidPath { .. })));
+        assert!(matches!(file_service.delete_book("link.pxl"), Err(PixelError::InvalidPath { .. })));
+        assert!(!outside.path().join("new.pxl").exists());
+        assert!(file_service.list_books(true).unwrap().is_empty());
+        
+        // Symlinks that stay inside are fine
+        file_service.create_book("real.pxl", 2, 2, 1).unwrap();
+        std::os::unix::fs::symlink(temp_dir.path().join("real.pxl"), temp_dir.path().join("alias.pxl")).unwrap();
+        assert_eq!(file_service.load_book("alias.pxl").unwrap().width, 2);
+    }
+    
+    #[test]
+    fn test_set_path_stays_inside_allowed_root() {
+        let root = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        fs::create_dir(root.path().join("art")).unwrap();
+        let mut file_service = FileService::new(root.path().to_path_buf())
+            .with_allowed_root(Some(root.path().to_path_buf()));
+        
+        file_service.set_path(root.path().join("art")).unwrap();
+        assert_eq!(file_service.get_path(), root.path().join("art"));
+        for path in [outside.path().to_path_buf(), root.path().join("art/../.."), root.path().join("missing")] {
+            assert!(matches!(file_service.set_path(path), Err(PixelError::InvalidPath { .. })));
+        }
+        assert_eq!(file_service.get_path(), root.path().join("art"));
     }
     
     #[test]
@@ -247,7 +730,7 @@ mod tests {
         file_service.create_book("book1.pxl", 8, 8, 1).unwrap();
         file_service.create_book("book2.pxl", 16, 16, 3).unwrap();
         
-        let books = file_service.list_books().unwrap();
+        let books = file_service.list_books(false).unwrap();
         assert_eq!(books.len(), 2);
         
         let book1 = books.iter().find(|b| b.filename == "book1.pxl").unwrap();
@@ -256,4 +739,195 @@ mod tests {
         let book2 = books.iter().find(|b| b.filename == "book2.pxl").unwrap();
         assert_eq!(book2.frames, 3);
     }
-} 
\ No newline at end of file
+    
+    #[test]
+    fn test_delete_and_restore_through_trash() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_service = FileService::new(temp_dir.path().to_path_buf());
+        file_service.create_book("hero.pxl", 4, 4, 1).unwrap();
+        
+        let entry = file_service.delete_book("hero.pxl").unwrap();
+        assert_eq!(entry.filename, "hero.pxl");
+        assert!(entry.expires_at > entry.deleted_at);
+        assert!(file_service.list_books(false).unwrap().is_empty());
+        assert_eq!(file_service.list_trash().unwrap().len(), 1);
+        
+        // A new book with the same name blocks the restore
+        file_service.create_book("hero.pxl", 2, 2, 1).unwrap();
+        assert!(matches!(file_service.restore_book("hero.pxl"), Err(PixelError::AlreadyExists { .. })));
+        
+        file_service.delete_book("hero.pxl").unwrap();
+        assert_eq!(file_service.list_trash().unwrap().len(), 2);
+        
+        // Restore brings back the most recent deletion
+        file_service.restore_book("hero.pxl").unwrap();
+        assert_eq!(file_service.load_book("hero.pxl").unwrap().width, 2);
+        assert_eq!(file_service.list_trash().unwrap().len(), 1);
+        assert!(matches!(file_service.restore_book("missing.pxl"), Err(PixelError::FileNotFound { .. })));
+    }
+    
+    #[test]
+    fn test_rename_and_copy_books() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_service = FileService::new(temp_dir.path().to_path_buf());
+        let mut book = file_service.create_book("draft.pxl", 2, 2, 1).unwrap();
+        assert_eq!(book.revision, 1);
+        book.frames[0].set_pixel(1, 1, 2, Pixel::new(255, 0, 0, 255));
+        assert_eq!(file_service.save_book(&book).unwrap(), 2);
+        file_service.create_book("taken.pxl", 2, 2, 1).unwrap();
+        
+        file_service.copy_book("draft.pxl", "draft-v1.pxl").unwrap();
+        assert_eq!(file_service.load_book("draft-v1.pxl").unwrap().frames[0].pixels, book.frames[0].pixels);
+        assert!(!temp_dir.path().join(".draft-v1.pxl.tmp").exists());
+        
+        file_service.rename_book("draft.pxl", "final.pxl").unwrap();
+        assert!(!temp_dir.path().join("draft.pxl").exists());
+        let renamed = file_service.load_book("final.pxl").unwrap();
+        assert_eq!((renamed.frames[0].pixels.as_slice(), renamed.revision), (book.frames[0].pixels.as_slice(), 2));
+        
+        assert!(matches!(file_service.rename_book("final.pxl", "taken.pxl"), Err(PixelError::AlreadyExists { .. })));
+        assert!(matches!(file_service.copy_book("final.pxl", "taken.pxl"), Err(PixelError::AlreadyExists { .. })));
+        assert!(matches!(file_service.copy_book("draft.pxl", "other.pxl"), Err(PixelError::FileNotFound { .. })));
+    }
+    
+    #[test]
+    fn test_expired_trash_is_purged() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_service = FileService::new(temp_dir.path().to_path_buf())
+            .with_trash_retention(Duration::ZERO);
+        file_service.create_book("old.pxl", 4, 4, 1).unwrap();
+        file_service.delete_book("old.pxl").unwrap();
+        
+        assert!(file_service.list_trash().unwrap().is_empty());
+        assert!(matches!(file_service.restore_book("old.pxl"), Err(PixelError::FileNotFound { .. })));
+    }
+    
+    #[test]
+    fn test_create_book_respects_format_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_service = FileService::new(temp_dir.path().to_path_buf());
+        
+        // 4096x4096 RGBA is 64 MiB per frame, well past the total size limit at 100 frames
+        let result = file_service.create_book("huge.pxl", 4096, 4096, 100);
+        assert!(matches!(result, Err(PixelError::InvalidFormat { .. })));
+        assert!(!temp_dir.path().join("huge.pxl").exists());
+    }
+    
+    #[test]
+    fn test_migrate_and_auto_upgrade() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_service = FileService::new(temp_dir.path().to_path_buf());
+        file_service.create_book("old.pxl", 4, 4, 1).unwrap();
+        assert_eq!(file_service.book_version("old.pxl").unwrap(), pixl_format::FORMAT_VERSION_V1);
+        
+        let result = file_service.migrate_book("old.pxl", None).unwrap();
+        assert!(result.changed());
+        assert_eq!(file_service.book_version("old.pxl").unwrap(), pixl_format::FORMAT_VERSION_V5);
+        assert_eq!(file_service.load_book("old.pxl").unwrap().width, 4);
+        assert!(!file_service.migrate_book("old.pxl", None).unwrap().changed());
+        assert!(matches!(file_service.migrate_book("old.pxl", Some(7)), Err(PixelError::InvalidFormat { .. })));
+        
+        let upgrading = FileService::new(temp_dir.path().to_path_buf()).with_auto_upgrade(true);
+        upgrading.create_book("new.pxl", 4, 4, 1).unwrap();
+        assert_eq!(upgrading.book_version("new.pxl").unwrap(), pixl_format::FORMAT_VERSION_V5);
+        let listed = upgrading.list_books(false).unwrap();
+        assert_eq!(listed.iter().find(|b| b.filename == "new.pxl").unwrap().version, pixl_format::FORMAT_VERSION_V5);
+    }
+    
+    #[test]
+    fn test_migrate_round_trips_between_versions() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_service = FileService::new(temp_dir.path().to_path_buf());
+        let mut book = file_service.create_book("walk.pxl", 8, 8, 3).unwrap();
+        book.frames[1].pixels[12..16].copy_from_slice(&[10, 20, 30, 255]);
+        file_service.save_book(&book).unwrap();
+        let path = temp_dir.path().join("walk.pxl");
+        let original = fs::read(&path).unwrap();
+        
+        // v1 -> v2 -> v1 gives back the same file
+        file_service.migrate_book("walk.pxl", Some(pixl_format::FORMAT_VERSION_V2)).unwrap();
+        assert_eq!(file_service.book_version("walk.pxl").unwrap(), pixl_format::FORMAT_VERSION_V2);
+        assert_ne!(fs::read(&path).unwrap(), original);
+        let result = file_service.migrate_book("walk.pxl", Some(pixl_format::FORMAT_VERSION_V1)).unwrap();
+        assert_eq!((result.from_version, result.to_version), (pixl_format::FORMAT_VERSION_V2, pixl_format::FORMAT_VERSION_V1));
+        assert_eq!(fs::read(&path).unwrap(), original);
+        
+        // Durations and metadata survive a trip through every version that holds them
+        book.frames[2].duration_ms = Some(40);
+        book.metadata.details.title = Some("Walk".to_string());
+        file_service.save_book(&book).unwrap();
+        let original = fs::read(&path).unwrap();
+        for version in [pixl_format::FORMAT_VERSION_V5, pixl_format::FORMAT_VERSION_V4] {
+            file_service.migrate_book("walk.pxl", Some(version)).unwrap();
+            let loaded = file_service.load_book("walk.pxl").unwrap();
+            assert_eq!(loaded.metadata, book.metadata);
+            assert_eq!(loaded.frames.iter().map(|f| (&f.pixels, f.duration_ms)).collect::<Vec<_>>(),
+                book.frames.iter().map(|f| (&f.pixels, f.duration_ms)).collect::<Vec<_>>());
+        }
+        assert_eq!(fs::read(&path).unwrap(), original);
+        
+        // Versions that cannot hold the book refuse to drop data
+        assert!(file_service.migrate_book("walk.pxl", Some(pixl_format::FORMAT_VERSION_V3)).is_err());
+        assert_eq!(fs::read(&path).unwrap(), original);
+    }
+    
+    #[test]
+    fn test_compressed_saves_shrink_and_load_back() {
+        let temp_dir = TempDir::new().unwrap();
+        let plain = FileService::new(temp_dir.path().to_path_buf());
+        let mut book = plain.create_book("sprite.pxl", 64, 64, 8).unwrap();
+        book.frames[3].pixels[100..104].copy_from_slice(&[255, 0, 0, 255]);
+        plain.save_book(&book).unwrap();
+        let plain_size = fs::metadata(temp_dir.path().join("sprite.pxl")).unwrap().len();
+        
+        let compressing = FileService::new(temp_dir.path().to_path_buf()).with_compression(true);
+        compressing.save_book(&book).unwrap();
+        assert_eq!(compressing.book_version("sprite.pxl").unwrap(), FORMAT_VERSION_V5);
+        assert!(fs::metadata(temp_dir.path().join("sprite.pxl")).unwrap().len() * 20 < plain_size);
+        
+        // Either service reads it back, and v1 files still load as before
+        let loaded = plain.load_book("sprite.pxl").unwrap();
+        assert_eq!(loaded.frames[3].pixels, book.frames[3].pixels);
+        plain.migrate_book("sprite.pxl", Some(pixl_format::FORMAT_VERSION_V1)).unwrap();
+        assert_eq!(compressing.load_book("sprite.pxl").unwrap().frames[3].pixels, book.frames[3].pixels);
+    }
+    
+    #[test]
+    fn test_load_frame_range() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_service = FileService::new(temp_dir.path().to_path_buf());
+        file_service.create_book("anim.pxl", 2, 2, 5).unwrap();
+        
+        let (book, frame_count) = file_service.load_frames("anim.pxl", &"1..3".parse().unwrap()).unwrap();
+        assert_eq!(frame_count, 5);
+        assert_eq!(book.frames.iter().map(|f| f.index).collect::<Vec<_>>(), vec![1, 2]);
+        
+        let (past_end, _) = file_service.load_frames("anim.pxl", &"9..".parse().unwrap()).unwrap();
+        assert!(past_end.frames.is_empty());
+        assert!(matches!(file_service.load_frames("missing.pxl", &"0..".parse().unwrap()), Err(PixelError::FileNotFound { .. })));
+    }
+    
+    #[test]
+    fn test_large_books_load_through_mmap() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_service = FileService::new(temp_dir.path().to_path_buf());
+        let mut book = file_service.create_book("big.pxl", 512, 512, 2).unwrap();
+        book.frames[1].set_pixel(511, 511, 512, Pixel::new(1, 2, 3, 255));
+        file_service.save_book(&book).unwrap();
+        assert!(fs::metadata(temp_dir.path().join("big.pxl")).unwrap().len() >= MMAP_THRESHOLD);
+        
+        let loaded = file_service.load_book("big.pxl").unwrap();
+        assert_eq!(loaded.frames[1].get_pixel(511, 511, 512), Some(Pixel::new(1, 2, 3, 255)));
+        let (partial, frame_count) = file_service.load_frames("big.pxl", &"1".parse().unwrap()).unwrap();
+        assert_eq!((partial.frames.len(), frame_count), (1, 2));
+        assert_eq!(partial.frames[0].pixels, loaded.frames[1].pixels);
+        
+        // Saving a smaller book replaces the file, so a mapping of the old one stays readable
+        let mapped = PxlReader::open_mmap(temp_dir.path().join("big.pxl")).unwrap();
+        book.frames.truncate(1);
+        file_service.save_book(&book).unwrap();
+        assert_eq!(mapped.frame_bytes(1).unwrap()[..], loaded.frames[1].pixels[..]);
+        assert_eq!(file_service.load_book("big.pxl").unwrap().frames.len(), 1);
+        assert!(!temp_dir.path().join(".big.pxl.tmp").exists());
+    }
+}
@@ -0,0 +1,204 @@
+use crate::models::{PixelBook, PixelError, Result};
+use futures_util::StreamExt;
+use pixl_format::{convert, Limits};
+use reqwest::{redirect, Client, Url};
+use std::io::Cursor;
+use std::time::Duration;
+
+/// Largest download accepted unless configured otherwise
+pub const DEFAULT_MAX_IMPORT_BYTES: u64 = 10 * 1024 * 1024;
+
+const IMPORT_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_REDIRECTS: usize = 5;
+
+/// Downloads PNG/GIF images from remote URLs and turns them into books.
+///
+/// With an allowlist, only those hosts (and their subdomains) can be fetched,
+/// including every hop of a redirect.
+pub struct ImportService {
+    client: Client,
+    allowed_hosts: Option<Vec<String>>,
+    max_bytes: u64,
+}
+
+impl Default for ImportService {
+    fn default() -> Self {
+        Self::new(None, DEFAULT_MAX_IMPORT_BYTES)
+    }
+}
+
+impl ImportService {
+    /// `allowed_hosts` of `None` permits any host
+    pub fn new(allowed_hosts: Option<Vec<String>>, max_bytes: u64) -> Self {
+        let allowed_hosts = allowed_hosts
+            .map(|hosts| hosts.into_iter().map(|host| host.to_ascii_lowercase()).collect::<Vec<_>>());
+
+        let redirect_hosts = allowed_hosts.clone();
+        let policy = redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                return attempt.error("too many redirects");
+            }
+            match attempt.url().host_str() {
+                Some(host) if host_allowed(redirect_hosts.as_deref(), host) => attempt.follow(),
+                _ => attempt.error("redirect to a host that is not allowed"),
+            }
+        });
+
+        let client = Client::builder()
+            .redirect(policy)
+            .timeout(IMPORT_TIMEOUT)
+            .build()
+            .expect("HTTP client configuration is valid");
+
+        Self { client, allowed_hosts, max_bytes }
+    }
+
+    /// Parses `url` and checks its scheme and host against the allowlist
+    pub fn check_url(&self, url: &str) -> Result<Url> {
+        let parsed = Url::parse(url).map_err(|e| PixelError::InvalidUrl {
+            url: url.to_string(),
+            reason: e.to_string(),
+        })?;
+
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(PixelError::InvalidUrl {
+                url: url.to_string(),
+                reason: "only http and https URLs can be imported".to_string(),
+            });
+        }
+
+        let host = parsed.host_str().ok_or_else(|| PixelError::InvalidUrl {
+            url: url.to_string(),
+            reason: "URL has no host".to_string(),
+        })?;
+        if !host_allowed(self.allowed_hosts.as_deref(), host) {
+            return Err(PixelError::HostNotAllowed { host: host.to_string() });
+        }
+
+        Ok(parsed)
+    }
+
+    /// Fetches `url`, giving up as soon as the body passes the size limit
+    pub async fn download(&self, url: &str) -> Result<Vec<u8>> {
+        let url = self.check_url(url)?;
+        let response = self.client.get(url).send().await
+            .map_err(|e| PixelError::DownloadFailed { details: e.to_string() })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(PixelError::DownloadFailed { details: format!("server responded with {}", status) });
+        }
+        if let Some(size) = response.content_length()
+            && size > self.max_bytes
+        {
+            return Err(PixelError::DownloadTooLarge { size, limit: self.max_bytes });
+        }
+
+        // Content-Length can be missing or wrong, so count while streaming too
+        let mut bytes = Vec::new();
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| PixelError::DownloadFailed { details: e.to_string() })?;
+            let size = (bytes.len() + chunk.len()) as u64;
+            if size > self.max_bytes {
+                return Err(PixelError::DownloadTooLarge { size, limit: self.max_bytes });
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        Ok(bytes)
+    }
+
+    /// Decodes downloaded bytes: every frame of a GIF, or a single still image.
+    /// Images beyond the default format limits are refused while decoding,
+    /// before their frames are allocated.
+    pub fn decode(filename: &str, bytes: &[u8]) -> Result<PixelBook> {
+        let limits = Limits::default();
+        let images = if bytes.starts_with(b"GIF8") {
+            convert::read_gif(Cursor::new(bytes), &limits)?
+        } else {
+            vec![convert::read_image(Cursor::new(bytes), &limits)?]
+        };
+        Ok(convert::book_from_images(filename, &images)?)
+    }
+}
+
+/// Book filename for an import: the last path segment with a `.pxl` extension,
+/// with anything but letters, digits, `-` and `_` replaced by `_`
+pub fn filename_from_url(url: &Url) -> Option<String> {
+    let segment = url.path_segments()?.rev().find(|segment| !segment.is_empty())?;
+    let stem = segment.rsplit_once('.').map_or(segment, |(stem, _)| stem);
+    let stem: String = stem.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    (!stem.is_empty()).then(|| format!("{}.pxl", stem))
+}
+
+fn host_allowed(allowed_hosts: Option<&[String]>, host: &str) -> bool {
+    let Some(allowed_hosts) = allowed_hosts else { return true };
+    let host = host.to_ascii_lowercase();
+    allowed_hosts.iter().any(|allowed| {
+        host == *allowed || host.strip_suffix(allowed.as_str()).is_some_and(|prefix| prefix.ends_with('.'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Pixel;
+    use poem::listener::{Acceptor, Listener, TcpListener};
+    use poem::{get, handler, Route, Server};
+
+    fn png_bytes() -> Vec<u8> {
+        let mut book = PixelBook::new("ref.pxl".to_string(), 3, 2, 1);
+        book.frames[0].set_pixel(1, 1, 3, Pixel::new(255, 0, 0, 255));
+        let mut bytes = Vec::new();
+        convert::write_png(&book.frames[0], 3, 2, &mut bytes).unwrap();
+        bytes
+    }
+
+    #[handler]
+    fn serve_png() -> Vec<u8> {
+        png_bytes()
+    }
+
+    async fn serve() -> String {
+        let acceptor = TcpListener::bind("127.0.0.1:0").into_acceptor().await.unwrap();
+        let addr = acceptor.local_addr()[0].as_socket_addr().copied().unwrap();
+        tokio::spawn(Server::new_with_acceptor(acceptor).run(Route::new().at("/ref.png", get(serve_png))));
+        format!("http://{}/ref.png", addr)
+    }
+
+    #[test]
+    fn test_check_url_enforces_scheme_and_allowlist() {
+        let service = ImportService::new(Some(vec!["Example.com".to_string()]), DEFAULT_MAX_IMPORT_BYTES);
+        assert!(service.check_url("https://example.com/a.png").is_ok());
+        assert!(service.check_url("https://cdn.example.com/a.png").is_ok());
+        assert!(matches!(service.check_url("https://badexample.com/a.png"), Err(PixelError::HostNotAllowed { .. })));
+        assert!(matches!(service.check_url("file:///etc/passwd"), Err(PixelError::InvalidUrl { .. })));
+        assert!(matches!(service.check_url("not a url"), Err(PixelError::InvalidUrl { .. })));
+
+        let url = Url::parse("https://example.com/art/hero%20idle.gif?size=2").unwrap();
+        assert_eq!(filename_from_url(&url).as_deref(), Some("hero_20idle.pxl"));
+        assert_eq!(filename_from_url(&Url::parse("https://example.com/").unwrap()), None);
+    }
+
+    #[tokio::test]
+    async fn test_download_and_decode() {
+        let url = serve().await;
+
+        let bytes = ImportService::default().download(&url).await.unwrap();
+        let book = ImportService::decode("ref.pxl", &bytes).unwrap();
+        assert_eq!((book.width, book.height, book.frames.len()), (3, 2, 1));
+        assert_eq!(book.frames[0].get_pixel(1, 1, 3), Some(Pixel::new(255, 0, 0, 255)));
+
+        let small = ImportService::new(None, 16);
+        assert!(matches!(small.download(&url).await, Err(PixelError::DownloadTooLarge { .. })));
+        assert!(matches!(ImportService::decode("bad.pxl", b"not an image"), Err(PixelError::InvalidFormat { .. })));
+
+        // A GIF whose logical screen is beyond the limits is refused from its header alone
+        let mut huge = b"GIF89a".to_vec();
+        huge.extend_from_slice(&[0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x3b]);
+        assert!(matches!(ImportService::decode("huge.pxl", &huge), Err(PixelError::InvalidFormat { .. })));
+    }
+}
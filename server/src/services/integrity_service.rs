@@ -0,0 +1,88 @@
+use crate::models::{IntegrityIssue, ScanReport};
+use crate::services::FileService;
+use chrono::Utc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub const DEFAULT_INTEGRITY_SCAN_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Checks every book in the workspace for damage such as bit rot or a
+/// partial write. `.pxl` files carry no checksums, so a book passes when its
+/// header, metadata and frame table are valid and all frame data is present.
+#[derive(Default)]
+pub struct IntegrityService {
+    last_report: Mutex<Option<ScanReport>>,
+}
+
+impl IntegrityService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Scans the workspace and keeps the report as the latest one. Returns
+    /// it with the issues that the previous scan had not already found.
+    pub fn scan(&self, file_service: &FileService) -> crate::models::Result<(ScanReport, Vec<IntegrityIssue>)> {
+        let started_at = Utc::now();
+        let books = file_service.list_books(true)?;
+        let issues: Vec<IntegrityIssue> = books.iter()
+            .filter_map(|book| file_service.verify_book(&book.filename).err().map(|e| IntegrityIssue {
+                filename: book.filename.clone(),
+                problem: e.to_string(),
+            }))
+            .collect();
+
+        let report = ScanReport {
+            started_at,
+            finished_at: Utc::now(),
+            books_scanned: books.len(),
+            issues,
+        };
+
+        let mut last_report = self.last_report.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let known = last_report.as_ref().map(|last| last.issues.as_slice()).unwrap_or_default();
+        let new_issues = report.issues.iter()
+            .filter(|issue| !known.contains(issue))
+            .cloned()
+            .collect();
+        *last_report = Some(report.clone());
+        Ok((report, new_issues))
+    }
+
+    /// The most recent scan, if one has run
+    pub fn last_report(&self) -> Option<ScanReport> {
+        self.last_report.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_scan_reports_damaged_books_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_service = FileService::new(temp_dir.path().to_path_buf());
+        file_service.create_book("good.pxl", 4, 4, 2).unwrap();
+        file_service.create_book("cut.pxl", 4, 4, 2).unwrap();
+
+        // Simulate a partial write
+        let path = temp_dir.path().join("cut.pxl");
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 10]).unwrap();
+
+        let service = IntegrityService::new();
+        assert!(service.last_report().is_none());
+
+        let (report, new_issues) = service.scan(&file_service).unwrap();
+        assert_eq!(report.books_scanned, 2);
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].filename, "cut.pxl");
+        assert_eq!(new_issues, report.issues);
+
+        let (again, new_issues) = service.scan(&file_service).unwrap();
+        assert_eq!(again.issues.len(), 1);
+        assert!(new_issues.is_empty());
+        assert_eq!(service.last_report().unwrap().finished_at, again.finished_at);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::models::{BookLock, LockResponse, PixelError, Result};
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Request header carrying the token of a held lock
+pub const LOCK_TOKEN_HEADER: &str = "X-Pixl-Lock-Token";
+
+pub const DEFAULT_LOCK_TTL: Duration = Duration::from_secs(5 * 60);
+/// Longest lease a single lock or renewal may ask for
+pub const MAX_LOCK_TTL: Duration = Duration::from_secs(60 * 60);
+
+struct HeldLock {
+    token: String,
+    lock: BookLock,
+}
+
+/// In-memory advisory locks, so a person editing a book can keep other
+/// clients from overwriting it. Locks are leases: a holder that disappears
+/// stops blocking writes once its lock expires.
+#[derive(Default)]
+pub struct LockService {
+    locks: Mutex<HashMap<String, HeldLock>>,
+}
+
+impl LockService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Runs `f` on the lock table with expired locks removed
+    fn with_locks<T>(&self, f: impl FnOnce(&mut HashMap<String, HeldLock>) -> T) -> T {
+        let mut locks = self.locks.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Utc::now();
+        locks.retain(|_, held| held.lock.expires_at > now);
+        f(&mut locks)
+    }
+
+    fn locked_error(filename: &str, lock: &BookLock) -> PixelError {
+        PixelError::BookLocked {
+            filename: filename.to_string(),
+            holder: lock.holder.clone(),
+            expires_at: lock.expires_at,
+        }
+    }
+
+    /// Takes the lock on `filename`, or renews it when `token` is the current one
+    pub fn acquire(&self, filename: &str, holder: &str, ttl: Duration, token: Option<&str>) -> Result<LockResponse> {
+        let now = Utc::now();
+        let expires_at = now + chrono::Duration::from_std(ttl.min(MAX_LOCK_TTL)).unwrap_or_default();
+
+        self.with_locks(|locks| {
+            let held = match locks.get_mut(filename) {
+                Some(held) if Some(held.token.as_str()) == token => {
+                    held.lock.holder = holder.to_string();
+                    held.lock.expires_at = expires_at;
+                    held
+                }
+                Some(held) => return Err(Self::locked_error(filename, &held.lock)),
+                None => locks.entry(filename.to_string()).or_insert(HeldLock {
+                    token: uuid::Uuid::new_v4().to_string(),
+                    lock: BookLock { holder: holder.to_string(), acquired_at: now, expires_at },
+                }),
+            };
+
+            Ok(LockResponse {
+                filename: filename.to_string(),
+                token: held.token.clone(),
+                lock: held.lock.clone(),
+            })
+        })
+    }
+
+    /// Releases the lock on `filename`; false when none was held
+    pub fn release(&self, filename: &str, token: Option<&str>) -> Result<bool> {
+        self.with_locks(|locks| match locks.get(filename) {
+            Some(held) if Some(held.token.as_str()) != token => Err(Self::locked_error(filename, &held.lock)),
+            Some(_) => Ok(locks.remove(filename).is_some()),
+            None => Ok(false),
+        })
+    }
+
+    /// The unexpired lock on `filename`, if any
+    pub fn current(&self, filename: &str) -> Option<BookLock> {
+        self.with_locks(|locks| locks.get(filename).map(|held| held.lock.clone()))
+    }
+
+    /// Passes when `filename` is unlocked or `token` matches its lock
+    pub fn check(&self, filename: &str, token: Option<&str>) -> Result<()> {
+        self.with_locks(|locks| match locks.get(filename) {
+            Some(held) if Some(held.token.as_str()) != token => Err(Self::locked_error(filename, &held.lock)),
+            _ => Ok(()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_blocks_other_writers_until_released() {
+        let service = LockService::new();
+        let granted = service.acquire("a.pxl", "viewer", DEFAULT_LOCK_TTL, None).unwrap();
+        assert_eq!(service.current("a.pxl").unwrap().holder, "viewer");
+
+        assert!(service.check("a.pxl", Some(&granted.token)).is_ok());
+        assert!(matches!(service.check("a.pxl", None), Err(PixelError::BookLocked { .. })));
+        assert!(service.check("b.pxl", None).is_ok());
+        assert!(matches!(service.acquire("a.pxl", "agent", DEFAULT_LOCK_TTL, None), Err(PixelError::BookLocked { .. })));
+
+        // Renewing keeps the token
+        let renewed = service.acquire("a.pxl", "viewer", DEFAULT_LOCK_TTL, Some(&granted.token)).unwrap();
+        assert_eq!(renewed.token, granted.token);
+        assert!(renewed.lock.expires_at >= granted.lock.expires_at);
+
+        assert!(service.release("a.pxl", Some("wrong")).is_err());
+        assert!(service.release("a.pxl", Some(&granted.token)).unwrap());
+        assert!(!service.release("a.pxl", None).unwrap());
+        assert!(service.check("a.pxl", None).is_ok());
+    }
+
+    #[test]
+    fn test_expired_locks_stop_blocking() {
+        let service = LockService::new();
+        service.acquire("a.pxl", "viewer", Duration::from_millis(10), None).unwrap();
+        std::thread::sleep(Duration::from_millis(30));
+
+        assert!(service.current("a.pxl").is_none());
+        assert!(service.check("a.pxl", None).is_ok());
+        assert!(service.acquire("a.pxl", "agent", DEFAULT_LOCK_TTL, None).is_ok());
+    }
+}
@@ -1,7 +1,33 @@
 pub mod file_service;
 pub mod drawing_service;
 pub mod event_service;
+pub mod snapshot_service;
+pub mod import_service;
+pub mod export_service;
+pub mod lock_service;
+pub mod integrity_service;
+pub mod template_service;
+pub mod operation_log_service;
+pub mod settings_service;
+pub mod undo_service;
+pub mod tilemap_service;
+pub mod thumbnail_service;
+pub mod event_history_service;
+pub mod context_service;
 
 pub use file_service::*;
 pub use drawing_service::*;
-pub use event_service::*; 
\ No newline at end of file
+pub use event_service::*;
+pub use snapshot_service::*;
+pub use import_service::*;
+pub use export_service::*;
+pub use lock_service::*;
+pub use integrity_service::*;
+pub use template_service::*;
+pub use operation_log_service::*;
+pub use settings_service::*;
+pub use undo_service::*;
+pub use tilemap_service::*;
+pub use thumbnail_service::*;
+pub use event_history_service::*;
+pub use context_service::*;
@@ -0,0 +1,101 @@
+use crate::models::{OperationLogEntry, Result};
+use chrono::{DateTime, Utc};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// Directory under the base path holding `<filename>.jsonl` operation logs
+pub const OPERATION_LOG_DIR: &str = ".operations";
+
+/// Header a client may send to tie a batch to its own request id
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// Append-only record of every operation batch applied to each book, so
+/// "who drew that and when" can be answered after the fact. Logs are JSON
+/// lines, oldest first, and are kept when a book is deleted.
+pub struct OperationLogService;
+
+impl OperationLogService {
+    fn log_path(base_path: &Path, filename: &str) -> PathBuf {
+        base_path.join(OPERATION_LOG_DIR).join(format!("{}.jsonl", filename))
+    }
+
+    pub fn record(base_path: &Path, filename: &str, entry: &OperationLogEntry) -> Result<()> {
+        let path = Self::log_path(base_path, filename);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        OpenOptions::new().create(true).append(true).open(&path)?.write_all(&line)?;
+        Ok(())
+    }
+
+    /// Moves a renamed book's log along with it
+    pub fn rename(base_path: &Path, filename: &str, new_filename: &str) -> Result<()> {
+        let path = Self::log_path(base_path, filename);
+        if path.exists() {
+            let destination = Self::log_path(base_path, new_filename);
+            if let Some(dir) = destination.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::rename(path, destination)?;
+        }
+        Ok(())
+    }
+
+    /// Entries recorded after `since`, or all of them, oldest first. Lines
+    /// that cannot be parsed, such as one cut short by a crash, are skipped.
+    pub fn entries(base_path: &Path, filename: &str, since: Option<DateTime<Utc>>) -> Result<Vec<OperationLogEntry>> {
+        let path = Self::log_path(base_path, filename);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut entries = Vec::new();
+        for line in BufReader::new(fs::File::open(&path)?).lines() {
+            let Ok(entry) = serde_json::from_str::<OperationLogEntry>(&line?) else {
+                continue;
+            };
+            if since.is_none_or(|since| entry.timestamp > since) {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use tempfile::TempDir;
+
+    fn entry(timestamp: DateTime<Utc>, operation: &str) -> OperationLogEntry {
+        OperationLogEntry {
+            timestamp,
+            origin: Some("agent/1.0".to_string()),
+            request_id: None,
+            operations: vec![operation.to_string()],
+        }
+    }
+
+    #[test]
+    fn test_entries_since() {
+        let temp_dir = TempDir::new().unwrap();
+        let start = Utc::now();
+        OperationLogService::record(temp_dir.path(), "log.pxl", &entry(start, "draw_pixel f0")).unwrap();
+        OperationLogService::record(temp_dir.path(), "log.pxl", &entry(start + Duration::seconds(1), "fill_area f1")).unwrap();
+
+        let all = OperationLogService::entries(temp_dir.path(), "log.pxl", None).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].operations, vec!["draw_pixel f0"]);
+
+        let later = OperationLogService::entries(temp_dir.path(), "log.pxl", Some(start)).unwrap();
+        assert_eq!(later.len(), 1);
+        assert_eq!(later[0].operations, vec!["fill_area f1"]);
+
+        assert!(OperationLogService::entries(temp_dir.path(), "other.pxl", None).unwrap().is_empty());
+    }
+}
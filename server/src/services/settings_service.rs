@@ -0,0 +1,87 @@
+use crate::models::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Settings clients can change while the server runs, kept across restarts
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerSettings {
+    /// Books directory last set with `PUT /path`
+    pub path: Option<PathBuf>,
+}
+
+/// Reads and writes [`ServerSettings`] as JSON. Without a file, changes only
+/// last until the server stops.
+pub struct SettingsService {
+    file: Option<PathBuf>,
+}
+
+impl SettingsService {
+    pub fn new(file: Option<PathBuf>) -> Self {
+        Self { file }
+    }
+
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// The saved settings; defaults when there is no file yet
+    pub fn load(&self) -> Result<ServerSettings> {
+        let Some(file) = &self.file else {
+            return Ok(ServerSettings::default());
+        };
+        if !file.exists() {
+            return Ok(ServerSettings::default());
+        }
+        Ok(serde_json::from_slice(&fs::read(file)?)?)
+    }
+
+    pub fn save(&self, settings: &ServerSettings) -> Result<()> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        // Write beside the file and rename, so a crash never leaves half of it
+        let temp = file.with_extension("tmp");
+        fs::write(&temp, serde_json::to_vec_pretty(settings)?)?;
+        fs::rename(&temp, file)?;
+        Ok(())
+    }
+
+    /// Records `path` as the books directory to use after a restart
+    pub fn set_path(&self, path: &Path) -> Result<()> {
+        let mut settings = self.load().unwrap_or_default();
+        settings.path = Some(path.to_path_buf());
+        self.save(&settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_settings_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let service = SettingsService::new(Some(temp_dir.path().join("pixl").join("server.json")));
+        assert_eq!(service.load().unwrap(), ServerSettings::default());
+
+        service.set_path(Path::new("/srv/books")).unwrap();
+        assert_eq!(service.load().unwrap().path, Some(PathBuf::from("/srv/books")));
+
+        std::fs::write(service.file().unwrap(), "{ not json").unwrap();
+        assert!(service.load().is_err());
+        // A damaged file is replaced rather than blocking the change
+        service.set_path(Path::new("/srv/other")).unwrap();
+        assert_eq!(service.load().unwrap().path, Some(PathBuf::from("/srv/other")));
+
+        let in_memory = SettingsService::new(None);
+        in_memory.set_path(Path::new("/srv/books")).unwrap();
+        assert_eq!(in_memory.load().unwrap(), ServerSettings::default());
+    }
+}
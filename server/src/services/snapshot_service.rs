@@ -0,0 +1,274 @@
+use crate::models::{PixelBook, PixelError, Result, SnapshotInfo};
+use pixl_format::PxlReader;
+use crate::services::file_service::book_files;
+use chrono::{DateTime, Utc};
+use std::fs::{self, read_dir};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Directory under the base path holding `<filename>/<millis>.pxl` snapshots.
+/// Named snapshots also have a `<millis>.name` file holding their name.
+pub const SNAPSHOT_DIR: &str = ".snapshots";
+
+/// Longest snapshot name, in characters
+pub const MAX_SNAPSHOT_NAME_LEN: usize = 100;
+
+pub const DEFAULT_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(5 * 60);
+pub const DEFAULT_SNAPSHOT_RETENTION: usize = 10;
+
+/// Keeps rolling copies of books so earlier states can be restored after a
+/// crash or a bad editing session, plus named checkpoints taken on request.
+/// Snapshots are plain `.pxl` copies.
+pub struct SnapshotService {
+    retention: usize,
+}
+
+impl Default for SnapshotService {
+    fn default() -> Self {
+        Self::new(DEFAULT_SNAPSHOT_RETENTION)
+    }
+}
+
+impl SnapshotService {
+    /// `retention` is the number of snapshots kept per book (at least one)
+    pub fn new(retention: usize) -> Self {
+        Self { retention: retention.max(1) }
+    }
+
+    fn book_dir(base_path: &Path, filename: &str) -> PathBuf {
+        base_path.join(SNAPSHOT_DIR).join(filename)
+    }
+
+    fn snapshot_path(&self, base_path: &Path, snapshot: &SnapshotInfo) -> PathBuf {
+        Self::book_dir(base_path, &snapshot.filename).join(format!("{}.pxl", snapshot.id))
+    }
+
+    fn name_path(&self, base_path: &Path, filename: &str, id: i64) -> PathBuf {
+        Self::book_dir(base_path, filename).join(format!("{}.name", id))
+    }
+
+    fn read_name(&self, base_path: &Path, filename: &str, id: i64) -> Option<String> {
+        fs::read_to_string(self.name_path(base_path, filename, id)).ok()
+    }
+
+    /// Copies the current file into a new snapshot and prunes old ones
+    pub fn snapshot_book(&self, base_path: &Path, filename: &str) -> Result<SnapshotInfo> {
+        self.take_snapshot(base_path, filename, None)
+    }
+
+    /// Copies the current file into a named snapshot, which retention never prunes
+    pub fn checkpoint_book(&self, base_path: &Path, filename: &str, name: &str) -> Result<SnapshotInfo> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_SNAPSHOT_NAME_LEN || name.chars().any(char::is_control) {
+            return Err(PixelError::InvalidFormat {
+                details: format!("Snapshot names must be 1-{} printable characters", MAX_SNAPSHOT_NAME_LEN),
+            });
+        }
+        self.take_snapshot(base_path, filename, Some(name))
+    }
+
+    fn take_snapshot(&self, base_path: &Path, filename: &str, name: Option<&str>) -> Result<SnapshotInfo> {
+        let source = base_path.join(filename);
+        if !source.exists() {
+            return Err(PixelError::FileNotFound { filename: filename.to_string() });
+        }
+
+        let dir = Self::book_dir(base_path, filename);
+        fs::create_dir_all(&dir)?;
+
+        // Ids are creation millis; step past any snapshot taken in the same millisecond
+        let latest = self.list_snapshots(base_path, filename)?.first().map(|s| s.id);
+        let id = Utc::now().timestamp_millis().max(latest.map_or(0, |id| id + 1));
+
+        // Write the name first so the snapshot is never listed without it
+        if let Some(name) = name {
+            fs::write(self.name_path(base_path, filename, id), name)?;
+        }
+        let size = fs::copy(&source, dir.join(format!("{}.pxl", id)))?;
+        self.prune(base_path, filename)?;
+
+        Ok(SnapshotInfo {
+            filename: filename.to_string(),
+            id,
+            created_at: DateTime::from_timestamp_millis(id).unwrap_or_default(),
+            size,
+            name: name.map(str::to_string),
+        })
+    }
+
+    /// Snapshots every book modified since its latest snapshot
+    pub fn snapshot_modified(&self, base_path: &Path) -> Result<Vec<SnapshotInfo>> {
+        let mut taken = Vec::new();
+
+        for (filename, path) in book_files(base_path, true)? {
+            // Compare against the snapshot file's own mtime; ids are truncated to millis
+            let modified = path.metadata()?.modified()?;
+            let latest = match self.list_snapshots(base_path, &filename)?.first() {
+                Some(snapshot) => Some(self.snapshot_path(base_path, snapshot).metadata()?.modified()?),
+                None => None,
+            };
+            if latest.is_none_or(|latest| modified > latest) {
+                taken.push(self.snapshot_book(base_path, &filename)?);
+            }
+        }
+
+        Ok(taken)
+    }
+
+    /// Snapshots of one book, newest first
+    pub fn list_snapshots(&self, base_path: &Path, filename: &str) -> Result<Vec<SnapshotInfo>> {
+        let dir = Self::book_dir(base_path, filename);
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut snapshots = Vec::new();
+        for entry in read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Some(id) = name.strip_suffix(".pxl").and_then(|id| id.parse::<i64>().ok()) else { continue };
+            let Some(created_at) = DateTime::from_timestamp_millis(id) else { continue };
+
+            snapshots.push(SnapshotInfo {
+                filename: filename.to_string(),
+                id,
+                created_at,
+                size: entry.metadata()?.len(),
+                name: self.read_name(base_path, filename, id),
+            });
+        }
+
+        snapshots.sort_by_key(|snapshot| std::cmp::Reverse(snapshot.id));
+        Ok(snapshots)
+    }
+
+    /// Reads snapshot `id` of a book
+    pub fn load_snapshot(&self, base_path: &Path, filename: &str, id: i64) -> Result<PixelBook> {
+        let snapshot = Self::book_dir(base_path, filename).join(format!("{}.pxl", id));
+        if !snapshot.exists() {
+            return Err(PixelError::FileNotFound { filename: format!("{} snapshot {}", filename, id) });
+        }
+        Ok(PxlReader::open(&snapshot)?.read_book(filename)?)
+    }
+
+    /// Replaces the live book with snapshot `id`. The current state is
+    /// snapshotted first, so a restore can itself be undone.
+    pub fn restore_snapshot(&self, base_path: &Path, filename: &str, id: i64) -> Result<SnapshotInfo> {
+        let snapshot = Self::book_dir(base_path, filename).join(format!("{}.pxl", id));
+        if !snapshot.exists() {
+            return Err(PixelError::FileNotFound { filename: format!("{} snapshot {}", filename, id) });
+        }
+
+        // Read it before snapshotting the live book, which may prune this very snapshot
+        let bytes = fs::read(&snapshot)?;
+        if base_path.join(filename).exists() {
+            self.snapshot_book(base_path, filename)?;
+        }
+        // Renamed over the live book so readers that mapped it keep their pages
+        let path = base_path.join(filename);
+        let temp = path.with_file_name(format!(".{}.tmp", id));
+        fs::write(&temp, &bytes)?;
+        fs::rename(&temp, &path)?;
+
+        Ok(SnapshotInfo {
+            filename: filename.to_string(),
+            id,
+            created_at: DateTime::from_timestamp_millis(id).unwrap_or_default(),
+            size: bytes.len() as u64,
+            name: self.read_name(base_path, filename, id),
+        })
+    }
+
+    /// Drops autosave snapshots past the retention count; named ones stay
+    fn prune(&self, base_path: &Path, filename: &str) -> Result<()> {
+        let automatic = self.list_snapshots(base_path, filename)?.into_iter().filter(|s| s.name.is_none());
+        for snapshot in automatic.skip(self.retention) {
+            fs::remove_file(self.snapshot_path(base_path, &snapshot))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::FileService;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_snapshot_retention() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_service = FileService::new(temp_dir.path().to_path_buf());
+        file_service.create_book("hero.pxl", 4, 4, 1).unwrap();
+
+        let service = SnapshotService::new(3);
+        let ids: Vec<i64> = (0..5)
+            .map(|_| service.snapshot_book(temp_dir.path(), "hero.pxl").unwrap().id)
+            .collect();
+
+        let kept: Vec<i64> = service.list_snapshots(temp_dir.path(), "hero.pxl").unwrap()
+            .iter().map(|s| s.id).collect();
+        assert_eq!(kept, vec![ids[4], ids[3], ids[2]]);
+    }
+
+    #[test]
+    fn test_named_snapshots_outlive_retention() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_service = FileService::new(temp_dir.path().to_path_buf());
+        file_service.create_book("hero.pxl", 4, 4, 1).unwrap();
+
+        let service = SnapshotService::new(2);
+        let checkpoint = service.checkpoint_book(temp_dir.path(), "hero.pxl", " before shading ").unwrap();
+        assert_eq!(checkpoint.name.as_deref(), Some("before shading"));
+        for _ in 0..3 {
+            service.snapshot_book(temp_dir.path(), "hero.pxl").unwrap();
+        }
+
+        let snapshots = service.list_snapshots(temp_dir.path(), "hero.pxl").unwrap();
+        assert_eq!(snapshots.len(), 3);
+        assert_eq!(snapshots.last().unwrap().id, checkpoint.id);
+        assert_eq!(snapshots.last().unwrap().name.as_deref(), Some("before shading"));
+
+        for name in ["", "   ", "line\nbreak"] {
+            assert!(matches!(
+                service.checkpoint_book(temp_dir.path(), "hero.pxl", name),
+                Err(PixelError::InvalidFormat { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_only_modified_books_are_snapshotted() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_service = FileService::new(temp_dir.path().to_path_buf());
+        file_service.create_book("a.pxl", 4, 4, 1).unwrap();
+        file_service.create_book("b.pxl", 4, 4, 1).unwrap();
+
+        let service = SnapshotService::default();
+        assert_eq!(service.snapshot_modified(temp_dir.path()).unwrap().len(), 2);
+        assert!(service.snapshot_modified(temp_dir.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_restore_snapshot_keeps_current_state() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_service = FileService::new(temp_dir.path().to_path_buf());
+        file_service.create_book("hero.pxl", 4, 4, 1).unwrap();
+
+        let service = SnapshotService::default();
+        let original = service.snapshot_book(temp_dir.path(), "hero.pxl").unwrap();
+        file_service.create_book("hero.pxl", 8, 8, 2).unwrap();
+
+        assert_eq!(service.load_snapshot(temp_dir.path(), "hero.pxl", original.id).unwrap().width, 4);
+        service.restore_snapshot(temp_dir.path(), "hero.pxl", original.id).unwrap();
+        assert_eq!(file_service.load_book("hero.pxl").unwrap().width, 4);
+
+        // The 8x8 version was saved as a snapshot before being replaced
+        let snapshots = service.list_snapshots(temp_dir.path(), "hero.pxl").unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert!(matches!(
+            service.restore_snapshot(temp_dir.path(), "hero.pxl", 42),
+            Err(PixelError::FileNotFound { .. })
+        ));
+    }
+}
@@ -0,0 +1,222 @@
+use crate::models::{BlendMode, BookMetadata, DrawingOperation, LineStyle, LineType, PixelBook, PixelError, Point, Result, ShapeType, Size, TemplateInfo};
+use crate::services::DrawingService;
+use pixl_format::{read_header, PxlReader};
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+
+/// Directory under the base path holding user templates as `<name>.pxl`
+pub const TEMPLATE_DIR: &str = ".templates";
+
+/// Faint gray used for the construction lines of built-in templates
+const GUIDE_COLOR: [u8; 4] = [128, 128, 128, 96];
+
+struct Builtin {
+    name: &'static str,
+    description: &'static str,
+    width: u16,
+    height: u16,
+    frames: usize,
+    draw: fn(usize) -> Vec<DrawingOperation>,
+}
+
+const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "character-skeleton",
+        description: "32x32 stick figure guide over a four frame walk cycle",
+        width: 32,
+        height: 32,
+        frames: 4,
+        draw: character_skeleton,
+    },
+    Builtin {
+        name: "tileset-grid",
+        description: "64x64 sheet divided into sixteen 16x16 tiles",
+        width: 64,
+        height: 64,
+        frames: 1,
+        draw: tileset_grid,
+    },
+];
+
+/// Starting points for new books: a few built-in templates drawn on demand,
+/// plus any `.pxl` file dropped into [`TEMPLATE_DIR`]. A user template with
+/// the same name as a built-in one replaces it.
+pub struct TemplateService;
+
+impl TemplateService {
+    fn user_template_path(base_path: &Path, name: &str) -> PathBuf {
+        base_path.join(TEMPLATE_DIR).join(format!("{}.pxl", name))
+    }
+
+    /// Every template, sorted by name
+    pub fn list_templates(base_path: &Path) -> Result<Vec<TemplateInfo>> {
+        let mut templates: Vec<TemplateInfo> = Vec::new();
+
+        let dir = base_path.join(TEMPLATE_DIR);
+        if dir.is_dir() {
+            for entry in read_dir(&dir)? {
+                let path = entry?.path();
+                if path.extension().and_then(|s| s.to_str()) != Some("pxl") || !path.is_file() {
+                    continue;
+                }
+                let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+                    continue;
+                };
+                // Skip anything the reader would reject when instantiating
+                let Ok(header) = read_header(&path) else {
+                    continue;
+                };
+                templates.push(TemplateInfo {
+                    name: name.to_string(),
+                    description: format!("User template from {}", TEMPLATE_DIR),
+                    width: header.width,
+                    height: header.height,
+                    frames: header.frame_count as usize,
+                    builtin: false,
+                });
+            }
+        }
+
+        for builtin in BUILTINS {
+            if templates.iter().any(|t| t.name == builtin.name) {
+                continue;
+            }
+            templates.push(TemplateInfo {
+                name: builtin.name.to_string(),
+                description: builtin.description.to_string(),
+                width: builtin.width,
+                height: builtin.height,
+                frames: builtin.frames,
+                builtin: true,
+            });
+        }
+
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(templates)
+    }
+
+    /// A new, unsaved book named `filename` with the contents of `name`.
+    /// Permissions stored in a user template are not carried over.
+    pub fn instantiate(base_path: &Path, name: &str, filename: &str) -> Result<PixelBook> {
+        // Template names become file stems, so hold them to the same rules
+        if name.is_empty() || name.contains(['/', '\\', '.']) {
+            return Err(PixelError::TemplateNotFound { name: name.to_string() });
+        }
+
+        let path = Self::user_template_path(base_path, name);
+        if path.is_file() {
+            let mut book = PxlReader::open(&path)?.read_book(filename)?;
+            book.metadata = BookMetadata::default();
+            return Ok(book);
+        }
+
+        let builtin = BUILTINS.iter().find(|b| b.name == name)
+            .ok_or_else(|| PixelError::TemplateNotFound { name: name.to_string() })?;
+        let mut book = PixelBook::new(filename.to_string(), builtin.width, builtin.height, builtin.frames);
+        let drawing = DrawingService::new();
+        for frame in 0..builtin.frames {
+            drawing.apply_operations(&mut book, (builtin.draw)(frame))?;
+        }
+        Ok(book)
+    }
+}
+
+fn line(frame: usize, start: (u16, u16), end: (u16, u16)) -> DrawingOperation {
+    DrawingOperation::DrawLine {
+        frame,
+        start: Point { x: start.0, y: start.1 },
+        end: Point { x: end.0, y: end.1 },
+        line_type: LineType::Straight,
+        color: GUIDE_COLOR.into(),
+        control_points: Vec::new(),
+        style: LineStyle::Aliased,
+        blend_mode: BlendMode::Replace,
+    }
+}
+
+// Head, spine and arms on every frame; the legs alternate between a stride
+// and a passing pose
+fn character_skeleton(frame: usize) -> Vec<DrawingOperation> {
+    let stride = if frame.is_multiple_of(2) { 4 } else { 1 };
+    vec![
+        DrawingOperation::DrawShape {
+            frame,
+            shape: ShapeType::Circle,
+            position: Point { x: 12, y: 2 },
+            size: Size { width: 8, height: 8 },
+            filled: false,
+            color: GUIDE_COLOR.into(),
+            blend_mode: BlendMode::Replace,
+        },
+        line(frame, (16, 11), (16, 20)),
+        line(frame, (16, 13), (11, 18)),
+        line(frame, (16, 13), (21, 18)),
+        line(frame, (16, 20), (16 - stride, 30)),
+        line(frame, (16, 20), (16 + stride, 30)),
+    ]
+}
+
+// Outlines each 16x16 tile
+fn tileset_grid(frame: usize) -> Vec<DrawingOperation> {
+    (0..4u16)
+        .flat_map(|row| (0..4u16).map(move |col| (col * 16, row * 16)))
+        .map(|(x, y)| DrawingOperation::DrawShape {
+            frame,
+            shape: ShapeType::Rectangle,
+            position: Point { x, y },
+            size: Size { width: 16, height: 16 },
+            filled: false,
+            color: GUIDE_COLOR.into(),
+            blend_mode: BlendMode::Replace,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::FileService;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_builtin_templates() {
+        let temp_dir = TempDir::new().unwrap();
+        let templates = TemplateService::list_templates(temp_dir.path()).unwrap();
+        let names: Vec<&str> = templates.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["character-skeleton", "tileset-grid"]);
+
+        let book = TemplateService::instantiate(temp_dir.path(), "tileset-grid", "tiles.pxl").unwrap();
+        assert_eq!(book.filename, "tiles.pxl");
+        assert_eq!((book.width, book.height, book.frames.len()), (64, 64, 1));
+        assert!(book.frames[0].get_pixel(16, 5, 64).unwrap().a > 0);
+        assert_eq!(book.frames[0].get_pixel(5, 5, 64).unwrap().a, 0);
+
+        assert!(matches!(
+            TemplateService::instantiate(temp_dir.path(), "../tileset-grid", "x.pxl"),
+            Err(PixelError::TemplateNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_user_templates_replace_builtins() {
+        let temp_dir = TempDir::new().unwrap();
+        let template_dir = temp_dir.path().join(TEMPLATE_DIR);
+        std::fs::create_dir_all(&template_dir).unwrap();
+
+        // Save a protected 8x8 book as the "tileset-grid" template
+        let service = FileService::new(template_dir);
+        let mut book = PixelBook::new("tileset-grid.pxl".to_string(), 8, 8, 2);
+        book.metadata.permissions.read_only = true;
+        service.save_book(&book).unwrap();
+
+        let templates = TemplateService::list_templates(temp_dir.path()).unwrap();
+        let grid = templates.iter().find(|t| t.name == "tileset-grid").unwrap();
+        assert!(!grid.builtin);
+        assert_eq!((grid.width, grid.height, grid.frames), (8, 8, 2));
+        assert_eq!(templates.len(), 2);
+
+        let book = TemplateService::instantiate(temp_dir.path(), "tileset-grid", "mine.pxl").unwrap();
+        assert_eq!((book.width, book.frames.len()), (8, 2));
+        assert!(!book.metadata.permissions.read_only);
+    }
+}
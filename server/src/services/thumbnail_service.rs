@@ -0,0 +1,112 @@
+use crate::models::{thumbnail, FrameRange, Result};
+use crate::services::FileService;
+use pixl_format::convert;
+use std::fs::{self, read_dir};
+use std::path::{Path, PathBuf};
+
+/// Directory under the base path holding cached thumbnails as
+/// `<filename>/<revision>-<size>.png`
+pub const THUMBNAIL_DIR: &str = ".thumbnails";
+
+pub const DEFAULT_THUMBNAIL_SIZE: u16 = 64;
+pub const MAX_THUMBNAIL_SIZE: u16 = 512;
+
+/// Small PNGs of each book's first frame for list views. Thumbnails are
+/// cached on disk per book revision, so a book changed by any means gets a
+/// fresh one; saving a book also drops its cached thumbnails right away.
+pub struct ThumbnailService;
+
+impl ThumbnailService {
+    fn book_dir(base_path: &Path, filename: &str) -> PathBuf {
+        base_path.join(THUMBNAIL_DIR).join(filename)
+    }
+
+    /// Frame 0 of the book shrunk to fit in `size` x `size`, as a PNG. Books
+    /// smaller than that keep their size.
+    pub fn thumbnail(files: &FileService, filename: &str, size: u16) -> Result<Vec<u8>> {
+        let revision = files.revision(filename)?;
+        let dir = Self::book_dir(files.get_path(), filename);
+        let path = dir.join(format!("{}-{}.png", revision, size));
+        if let Ok(png) = fs::read(&path) {
+            return Ok(png);
+        }
+
+        let (book, _) = files.load_frames(filename, &FrameRange { start: 0, end: Some(1) })?;
+        let (thumb, width, height) = thumbnail(&book.frames[0], book.width, book.height, size);
+        let mut png = Vec::new();
+        convert::write_png(&thumb, width, height, &mut png)?;
+
+        // Thumbnails of earlier revisions will never be served again
+        let current = format!("{}-", revision);
+        if dir.is_dir() {
+            for entry in read_dir(&dir)? {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if !name.starts_with(&current) && !name.starts_with('.') {
+                    let _ = fs::remove_file(entry.path());
+                }
+            }
+        }
+        // Written aside and renamed so concurrent requests never read half a file
+        fs::create_dir_all(&dir)?;
+        let temp = dir.join(format!(".{}.tmp", uuid::Uuid::new_v4()));
+        fs::write(&temp, &png)?;
+        fs::rename(temp, &path)?;
+        Ok(png)
+    }
+
+    /// Drops every cached thumbnail of `filename`
+    pub fn invalidate(base_path: &Path, filename: &str) {
+        let _ = fs::remove_dir_all(Self::book_dir(base_path, filename));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pixl_format::Limits;
+    use crate::models::{PixelBook, Pixel};
+    use tempfile::TempDir;
+
+    fn cached(base: &Path) -> Vec<String> {
+        let mut names: Vec<String> = read_dir(base.join(THUMBNAIL_DIR).join("hero.pxl")).map_or_else(
+            |_| Vec::new(),
+            |entries| entries.map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned()).collect(),
+        );
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_thumbnails_are_cached_until_the_book_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let files = FileService::new(temp_dir.path().to_path_buf());
+        let mut book = PixelBook::new("hero.pxl".to_string(), 128, 64, 2);
+        // Sampled for the bottom right pixel of a 32x16 thumbnail
+        book.frames[0].set_pixel(124, 60, 128, Pixel::new(255, 0, 0, 255));
+        files.save_book(&book).unwrap();
+
+        let png = ThumbnailService::thumbnail(&files, "hero.pxl", 32).unwrap();
+        let image = convert::read_image(std::io::Cursor::new(png.clone()), &Limits::default()).unwrap();
+        assert_eq!(image.dimensions(), (32, 16));
+        assert_eq!(image.get_pixel(31, 15).0, [255, 0, 0, 255]);
+        assert_eq!(ThumbnailService::thumbnail(&files, "hero.pxl", 32).unwrap(), png);
+        ThumbnailService::thumbnail(&files, "hero.pxl", 64).unwrap();
+        assert_eq!(cached(temp_dir.path()), vec!["1-32.png", "1-64.png"]);
+
+        files.save_book(&book).unwrap();
+        assert!(cached(temp_dir.path()).is_empty());
+        ThumbnailService::thumbnail(&files, "hero.pxl", 32).unwrap();
+        assert_eq!(cached(temp_dir.path()), vec!["2-32.png"]);
+    }
+
+    #[test]
+    fn test_small_books_keep_their_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let files = FileService::new(temp_dir.path().to_path_buf());
+        files.create_book("hero.pxl", 16, 8, 1).unwrap();
+
+        let png = ThumbnailService::thumbnail(&files, "hero.pxl", DEFAULT_THUMBNAIL_SIZE).unwrap();
+        assert_eq!(convert::read_image(std::io::Cursor::new(png), &Limits::default()).unwrap().dimensions(), (16, 8));
+    }
+}
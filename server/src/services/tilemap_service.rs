@@ -0,0 +1,203 @@
+use crate::models::{PixelBook, PixelError, Result, Tilemap, TilemapSummary, Tileset, valid_tilemap_name};
+use crate::services::FileService;
+use pixl_format::convert;
+use serde_json::json;
+use std::fs::{self, read_dir};
+use std::path::{Path, PathBuf};
+
+/// Directory under the base path holding tilemaps as `<name>.json`
+pub const TILEMAP_DIR: &str = ".tilemaps";
+
+/// Stores tilemaps: grids of tile indices into a tileset book, which are
+/// composited from the book's current pixels whenever they are exported.
+pub struct TilemapService;
+
+impl TilemapService {
+    fn tilemap_path(base_path: &Path, name: &str) -> Result<PathBuf> {
+        // Names become file stems, so anything else cannot exist
+        if !valid_tilemap_name(name) {
+            return Err(PixelError::TilemapNotFound { name: name.to_string() });
+        }
+        Ok(base_path.join(TILEMAP_DIR).join(format!("{}.json", name)))
+    }
+
+    /// Every readable tilemap, sorted by name
+    pub fn list_tilemaps(base_path: &Path) -> Result<Vec<TilemapSummary>> {
+        let dir = base_path.join(TILEMAP_DIR);
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut tilemaps = Vec::new();
+        for entry in read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            let Ok(tilemap) = fs::read(&path).map_err(PixelError::from)
+                .and_then(|bytes| serde_json::from_slice::<Tilemap>(&bytes).map_err(PixelError::from)) else {
+                continue;
+            };
+            tilemaps.push(tilemap.summary());
+        }
+        tilemaps.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(tilemaps)
+    }
+
+    pub fn load_tilemap(base_path: &Path, name: &str) -> Result<Tilemap> {
+        let path = Self::tilemap_path(base_path, name)?;
+        if !path.is_file() {
+            return Err(PixelError::TilemapNotFound { name: name.to_string() });
+        }
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    /// Writes `tilemap`, returning true when it replaced an existing one
+    pub fn save_tilemap(base_path: &Path, tilemap: &Tilemap) -> Result<bool> {
+        let path = Self::tilemap_path(base_path, &tilemap.name)?;
+        let existed = path.is_file();
+        fs::create_dir_all(base_path.join(TILEMAP_DIR))?;
+        fs::write(path, serde_json::to_vec_pretty(tilemap)?)?;
+        Ok(existed)
+    }
+
+    pub fn delete_tilemap(base_path: &Path, name: &str) -> Result<()> {
+        let path = Self::tilemap_path(base_path, name)?;
+        if !path.is_file() {
+            return Err(PixelError::TilemapNotFound { name: name.to_string() });
+        }
+        Ok(fs::remove_file(path)?)
+    }
+
+    /// Loads a tileset book with its tile size, failing when the book has
+    /// not been declared a tileset
+    pub fn load_tileset(files: &FileService, filename: &str) -> Result<(Tileset, PixelBook)> {
+        let book = files.load_book(filename)?;
+        let tileset = book.metadata.tileset.ok_or_else(|| PixelError::InvalidFormat {
+            details: format!("{} is not a tileset", filename),
+        })?;
+        Ok((tileset, book))
+    }
+
+    /// Checks `tilemap` against its tileset as it is now
+    pub fn validate(files: &FileService, tilemap: &Tilemap) -> Result<()> {
+        let (tileset, book) = Self::load_tileset(files, &tilemap.tileset)?;
+        tilemap.validate(&tileset, &book).map_err(|details| PixelError::InvalidFormat { details })
+    }
+
+    /// The composited map as a PNG
+    pub fn render_png(files: &FileService, tilemap: &Tilemap) -> Result<Vec<u8>> {
+        let (tileset, book) = Self::load_tileset(files, &tilemap.tileset)?;
+        let (width, height) = tilemap.pixel_size(&tileset);
+        let mut png = Vec::new();
+        convert::write_png(&tilemap.compose(&tileset, &book), width as u16, height as u16, &mut png)?;
+        Ok(png)
+    }
+
+    /// The map in Tiled's JSON map format. Cells hold global tile ids, which
+    /// are tile indices plus one, with 0 for an empty cell. The tileset image
+    /// is named `<stem>.png` after the tileset book.
+    pub fn tiled_json(files: &FileService, tilemap: &Tilemap) -> Result<serde_json::Value> {
+        let (tileset, book) = Self::load_tileset(files, &tilemap.tileset)?;
+        let stem = book.filename.trim_end_matches(".pxl");
+        let data: Vec<u32> = tilemap.tiles.iter().map(|tile| tile.map_or(0, |tile| tile + 1)).collect();
+
+        Ok(json!({
+            "type": "map",
+            "version": "1.10",
+            "orientation": "orthogonal",
+            "renderorder": "right-down",
+            "infinite": false,
+            "width": tilemap.columns,
+            "height": tilemap.rows,
+            "tilewidth": tileset.tile_width,
+            "tileheight": tileset.tile_height,
+            "nextlayerid": 2,
+            "nextobjectid": 1,
+            "layers": [{
+                "id": 1,
+                "type": "tilelayer",
+                "name": tilemap.name,
+                "width": tilemap.columns,
+                "height": tilemap.rows,
+                "x": 0,
+                "y": 0,
+                "opacity": 1,
+                "visible": true,
+                "data": data,
+            }],
+            "tilesets": [{
+                "firstgid": 1,
+                "name": stem,
+                "image": format!("{}.png", stem),
+                "imagewidth": book.width,
+                "imageheight": book.height,
+                "tilewidth": tileset.tile_width,
+                "tileheight": tileset.tile_height,
+                "tilecount": tileset.tile_count(book.width, book.height),
+                "columns": tileset.columns(book.width),
+                "margin": 0,
+                "spacing": 0,
+            }],
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pixl_format::Limits;
+    use crate::models::Pixel;
+    use tempfile::TempDir;
+
+    fn tilemap(name: &str, tiles: Vec<Option<u32>>) -> Tilemap {
+        Tilemap { name: name.to_string(), tileset: "tiles.pxl".to_string(), frame: 0, columns: 2, rows: 1, tiles }
+    }
+
+    #[test]
+    fn test_tilemaps_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path();
+        assert!(TilemapService::list_tilemaps(base).unwrap().is_empty());
+
+        let map = tilemap("level-1", vec![Some(1), None]);
+        assert!(!TilemapService::save_tilemap(base, &map).unwrap());
+        assert!(TilemapService::save_tilemap(base, &map).unwrap());
+        TilemapService::save_tilemap(base, &tilemap("intro", vec![None, None])).unwrap();
+        fs::write(base.join(TILEMAP_DIR).join("broken.json"), "{").unwrap();
+
+        assert_eq!(TilemapService::load_tilemap(base, "level-1").unwrap(), map);
+        let names: Vec<String> = TilemapService::list_tilemaps(base).unwrap().into_iter().map(|m| m.name).collect();
+        assert_eq!(names, vec!["intro", "level-1"]);
+
+        TilemapService::delete_tilemap(base, "level-1").unwrap();
+        assert!(matches!(TilemapService::load_tilemap(base, "level-1"), Err(PixelError::TilemapNotFound { .. })));
+        assert!(matches!(TilemapService::delete_tilemap(base, "../tiles"), Err(PixelError::TilemapNotFound { .. })));
+    }
+
+    #[test]
+    fn test_tilemaps_need_a_tileset() {
+        let temp_dir = TempDir::new().unwrap();
+        let files = FileService::new(temp_dir.path().to_path_buf());
+        let mut book = PixelBook::new("tiles.pxl".to_string(), 4, 2, 1);
+        book.frames[0].set_pixel(2, 0, 4, Pixel::new(0, 255, 0, 255));
+        files.import_book(&book).unwrap();
+
+        let map = tilemap("level-1", vec![Some(1), None]);
+        assert!(matches!(TilemapService::validate(&files, &map), Err(PixelError::InvalidFormat { .. })));
+
+        book.metadata.tileset = Some(Tileset { tile_width: 2, tile_height: 2 });
+        files.save_book(&book).unwrap();
+        TilemapService::validate(&files, &map).unwrap();
+
+        let png = TilemapService::render_png(&files, &map).unwrap();
+        let image = convert::read_image(std::io::Cursor::new(png), &Limits::default()).unwrap();
+        assert_eq!(image.dimensions(), (4, 2));
+        assert_eq!(image.get_pixel(0, 0).0, [0, 255, 0, 255]);
+
+        let tiled = TilemapService::tiled_json(&files, &map).unwrap();
+        assert_eq!(tiled["layers"][0]["data"], json!([2, 0]));
+        assert_eq!(tiled["tilesets"][0]["image"], "tiles.png");
+        assert_eq!(tiled["tilesets"][0]["tilecount"], 2);
+    }
+}
@@ -0,0 +1,214 @@
+use crate::models::{FrameDiff, PixelBook, PixelError, Result};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Steps kept per book before the oldest is forgotten
+pub const DEFAULT_UNDO_DEPTH: usize = 100;
+
+/// The pixels one applied batch of operations changed
+#[derive(Debug, Clone)]
+pub struct UndoStep {
+    width: u16,
+    height: u16,
+    frames: Vec<(usize, FrameDiff)>,
+}
+
+impl UndoStep {
+    /// The changes between `before` and `after`, frames matched by index;
+    /// `None` when nothing changed
+    pub fn new(width: u16, height: u16, frames: Vec<(usize, FrameDiff)>) -> Option<Self> {
+        let frames: Vec<_> = frames.into_iter().filter(|(_, diff)| !diff.is_empty()).collect();
+        (!frames.is_empty()).then_some(Self { width, height, frames })
+    }
+
+    /// Indices of the frames this step touches
+    pub fn frames(&self) -> Vec<usize> {
+        self.frames.iter().map(|(index, _)| *index).collect()
+    }
+
+    pub fn pixel_count(&self) -> usize {
+        self.frames.iter().map(|(_, diff)| diff.changed_count()).sum()
+    }
+
+    // Moves `book` to the before (`forward == false`) or after side of this
+    // step, provided it is exactly on the other side now
+    fn apply(&self, book: &mut PixelBook, forward: bool) -> Result<()> {
+        let conflict = || PixelError::HistoryConflict { filename: book.filename.clone() };
+        if (book.width, book.height) != (self.width, self.height) {
+            return Err(conflict());
+        }
+
+        for (index, diff) in &self.frames {
+            let frame = book.frames.get(*index).ok_or_else(conflict)?;
+            let unchanged = diff.changes.iter().all(|change| {
+                let expected = if forward { change.before } else { change.after };
+                frame.get_pixel(change.x, change.y, self.width) == Some(expected)
+            });
+            if !unchanged {
+                return Err(conflict());
+            }
+        }
+
+        for (index, diff) in &self.frames {
+            let frame = &mut book.frames[*index];
+            for change in &diff.changes {
+                frame.set_pixel(change.x, change.y, self.width, if forward { change.after } else { change.before });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct History {
+    undo: Vec<UndoStep>,
+    redo: Vec<UndoStep>,
+}
+
+/// In-memory undo and redo stacks per book, filled by every applied batch
+/// of operations. A step is only reverted while the book still holds exactly
+/// the pixels it produced; after any other change, such as a snapshot
+/// restore, the book's history is dropped instead.
+pub struct UndoService {
+    histories: Mutex<HashMap<String, History>>,
+    depth: usize,
+}
+
+impl Default for UndoService {
+    fn default() -> Self {
+        Self::new(DEFAULT_UNDO_DEPTH)
+    }
+}
+
+impl UndoService {
+    pub fn new(depth: usize) -> Self {
+        Self { histories: Mutex::new(HashMap::new()), depth }
+    }
+
+    fn with_histories<T>(&self, f: impl FnOnce(&mut HashMap<String, History>) -> T) -> T {
+        let mut histories = self.histories.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        f(&mut histories)
+    }
+
+    /// Adds a newly applied batch; anything that could be redone is discarded
+    pub fn record(&self, filename: &str, step: UndoStep) {
+        if self.depth == 0 {
+            return;
+        }
+        self.with_histories(|histories| {
+            let history = histories.entry(filename.to_string()).or_default();
+            history.redo.clear();
+            history.undo.push(step);
+            if history.undo.len() > self.depth {
+                history.undo.remove(0);
+            }
+        })
+    }
+
+    /// Reverts the most recent batch applied to `book`
+    pub fn undo(&self, book: &mut PixelBook) -> Result<UndoStep> {
+        self.step(book, false)
+    }
+
+    /// Reapplies the most recently undone batch
+    pub fn redo(&self, book: &mut PixelBook) -> Result<UndoStep> {
+        self.step(book, true)
+    }
+
+    fn step(&self, book: &mut PixelBook, forward: bool) -> Result<UndoStep> {
+        self.with_histories(|histories| {
+            let action = if forward { "redo" } else { "undo" };
+            let nothing = || PixelError::NothingToUndo { filename: book.filename.clone(), action: action.to_string() };
+            let history = histories.get_mut(&book.filename).ok_or_else(nothing)?;
+            let (from, to) = if forward {
+                (&mut history.redo, &mut history.undo)
+            } else {
+                (&mut history.undo, &mut history.redo)
+            };
+            let step = from.pop().ok_or_else(nothing)?;
+
+            if let Err(e) = step.apply(book, forward) {
+                histories.remove(&book.filename);
+                return Err(e);
+            }
+            to.push(step.clone());
+            Ok(step)
+        })
+    }
+
+    /// Steps available to undo and to redo
+    pub fn depths(&self, filename: &str) -> (usize, usize) {
+        self.with_histories(|histories| {
+            histories.get(filename).map_or((0, 0), |history| (history.undo.len(), history.redo.len()))
+        })
+    }
+
+    /// Keeps a renamed book's history under its new name
+    pub fn rename(&self, filename: &str, new_filename: &str) {
+        self.with_histories(|histories| {
+            if let Some(history) = histories.remove(filename) {
+                histories.insert(new_filename.to_string(), history);
+            }
+        })
+    }
+
+    /// Drops the history of a book that no longer exists
+    pub fn forget(&self, filename: &str) {
+        self.with_histories(|histories| {
+            histories.remove(filename);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{diff_frames, Pixel};
+
+    const RED: Pixel = Pixel { r: 255, g: 0, b: 0, a: 255 };
+
+    // Paints (x, 0) red and records it as one step
+    fn paint(service: &UndoService, book: &mut PixelBook, x: u16) {
+        let before = book.frames[0].clone();
+        book.frames[0].set_pixel(x, 0, book.width, RED);
+        let diff = diff_frames(&before, &book.frames[0], book.width, book.height);
+        service.record(&book.filename, UndoStep::new(book.width, book.height, vec![(0, diff)]).unwrap());
+    }
+
+    #[test]
+    fn test_undo_and_redo() {
+        let service = UndoService::new(DEFAULT_UNDO_DEPTH);
+        let mut book = PixelBook::new("u.pxl".to_string(), 4, 4, 1);
+        paint(&service, &mut book, 0);
+        paint(&service, &mut book, 1);
+        assert_eq!(service.depths("u.pxl"), (2, 0));
+
+        let step = service.undo(&mut book).unwrap();
+        assert_eq!((step.frames(), step.pixel_count()), (vec![0], 1));
+        assert_eq!(book.frames[0].get_pixel(1, 0, 4), Some(Pixel::transparent()));
+        assert_eq!(book.frames[0].get_pixel(0, 0, 4), Some(RED));
+
+        service.redo(&mut book).unwrap();
+        assert_eq!(book.frames[0].get_pixel(1, 0, 4), Some(RED));
+        assert!(matches!(service.redo(&mut book), Err(PixelError::NothingToUndo { .. })));
+
+        // A new edit after an undo discards the redo stack
+        service.undo(&mut book).unwrap();
+        paint(&service, &mut book, 2);
+        assert_eq!(service.depths("u.pxl"), (2, 0));
+    }
+
+    #[test]
+    fn test_outside_changes_drop_the_history() {
+        let service = UndoService::new(1);
+        let mut book = PixelBook::new("u.pxl".to_string(), 4, 4, 1);
+        paint(&service, &mut book, 0);
+        paint(&service, &mut book, 1);
+        assert_eq!(service.depths("u.pxl"), (1, 0));
+
+        book.frames[0].set_pixel(1, 0, 4, Pixel::new(0, 0, 255, 255));
+        assert!(matches!(service.undo(&mut book), Err(PixelError::HistoryConflict { .. })));
+        assert_eq!(service.depths("u.pxl"), (0, 0));
+        assert_eq!(book.frames[0].get_pixel(0, 0, 4), Some(RED));
+    }
+}
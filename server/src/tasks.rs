@@ -0,0 +1,77 @@
+//! Long-running background jobs started alongside the HTTP server.
+
+use crate::models::{Result, ScanReport};
+use crate::services::{EventService, FileService, IntegrityService, SnapshotService};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+/// Periodically snapshots every book modified since its last snapshot
+pub fn spawn_autosave(
+    file_service: Arc<RwLock<FileService>>,
+    snapshot_service: Arc<SnapshotService>,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // The first tick completes immediately; nothing has been modified yet
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+
+            let service = file_service.read().await;
+            match snapshot_service.snapshot_modified(service.get_path()) {
+                Ok(taken) if !taken.is_empty() => {
+                    println!("📸 Autosave snapshotted {} book(s)", taken.len());
+                }
+                Ok(_) => {}
+                Err(e) => println!("❌ Autosave failed: {}", e),
+            }
+        }
+    })
+}
+
+/// Scans every book and reports newly found damage on the affected books'
+/// event streams
+pub async fn run_integrity_scan(
+    file_service: &RwLock<FileService>,
+    integrity_service: &IntegrityService,
+    event_service: &RwLock<EventService>,
+) -> Result<ScanReport> {
+    // Holding the read lock keeps writes from showing up as torn books
+    let (report, new_issues) = integrity_service.scan(&*file_service.read().await)?;
+    
+    let events = event_service.read().await;
+    for issue in &new_issues {
+        println!("🩺 Integrity problem in {}: {}", issue.filename, issue.problem);
+        events.on_integrity_problem(&issue.filename, &issue.problem).await;
+    }
+    Ok(report)
+}
+
+/// Periodically runs [`run_integrity_scan`]
+pub fn spawn_integrity_scan(
+    file_service: Arc<RwLock<FileService>>,
+    integrity_service: Arc<IntegrityService>,
+    event_service: Arc<RwLock<EventService>>,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            match run_integrity_scan(&file_service, &integrity_service, &event_service).await {
+                Ok(report) => println!(
+                    "🩺 Integrity scan checked {} book(s), {} with problems",
+                    report.books_scanned,
+                    report.issues.len()
+                ),
+                Err(e) => println!("❌ Integrity scan failed: {}", e),
+            }
+        }
+    })
+}
@@ -1 +1,2 @@
-pub mod validation; 
\ No newline at end of file
+pub mod validation;
+pub mod permissions;
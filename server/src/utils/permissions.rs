@@ -0,0 +1,63 @@
+use crate::models::{Permissions, PixelError, Result};
+use sha2::{Digest, Sha256};
+
+/// Request header carrying the owner key for protected books
+pub const OWNER_KEY_HEADER: &str = "X-Pixl-Owner-Key";
+
+pub fn hash_owner_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+/// Passes when the book has no owner or `key` matches it
+pub fn check_owner(filename: &str, permissions: &Permissions, key: Option<&str>) -> Result<()> {
+    match &permissions.owner_key_hash {
+        Some(expected) if key.map(hash_owner_key).as_ref() != Some(expected) => {
+            Err(PixelError::PermissionDenied {
+                filename: filename.to_string(),
+                reason: format!("a valid {} header is required", OWNER_KEY_HEADER),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Checks that pixel data may be modified: the book must not be read-only
+/// and the owner key, if one is set, must match
+pub fn check_write_access(filename: &str, permissions: &Permissions, key: Option<&str>) -> Result<()> {
+    if permissions.read_only {
+        return Err(PixelError::PermissionDenied {
+            filename: filename.to_string(),
+            reason: "book is read-only".to_string(),
+        });
+    }
+    check_owner(filename, permissions, key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_access() {
+        let open = Permissions::default();
+        assert!(check_write_access("a.pxl", &open, None).is_ok());
+
+        let read_only = Permissions { read_only: true, owner_key_hash: None };
+        assert!(matches!(
+            check_write_access("a.pxl", &read_only, None),
+            Err(PixelError::PermissionDenied { .. })
+        ));
+
+        let owned = Permissions { read_only: false, owner_key_hash: Some(hash_owner_key("secret")) };
+        assert!(check_write_access("a.pxl", &owned, Some("secret")).is_ok());
+        assert!(check_write_access("a.pxl", &owned, Some("wrong")).is_err());
+        assert!(check_write_access("a.pxl", &owned, None).is_err());
+    }
+
+    #[test]
+    fn test_owner_can_manage_read_only_book() {
+        let locked = Permissions { read_only: true, owner_key_hash: Some(hash_owner_key("secret")) };
+        assert!(check_owner("a.pxl", &locked, Some("secret")).is_ok());
+        assert!(check_write_access("a.pxl", &locked, Some("secret")).is_err());
+    }
+}
@@ -1,6 +1,12 @@
 // Validation utilities will be expanded as needed
+
+/// Book filenames may include sub-folders (`sprites/hero.pxl`), but every
+/// segment must be a plain name: no `.` or `..`, no hidden names that could
+/// reach the trash or snapshot folders, and no backslashes or NULs.
 pub fn validate_filename(filename: &str) -> bool {
-    !filename.is_empty() && filename.ends_with(".pxl")
+    filename.ends_with(".pxl")
+        && !filename.contains(['\\', '\0'])
+        && filename.split('/').all(|segment| !segment.is_empty() && !segment.starts_with('.'))
 }
 
 pub fn validate_dimensions(width: u16, height: u16) -> bool {
@@ -11,4 +17,20 @@ pub fn validate_color(_color: &[u8; 4]) -> bool {
     // Color validation logic would go here
     // For now, all colors are valid
     true
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_filename_allows_sub_folders() {
+        assert!(validate_filename("hero.pxl"));
+        assert!(validate_filename("sprites/enemies/slime.pxl"));
+
+        for filename in ["", "hero.png", "../hero.pxl", "sprites/../../hero.pxl", "/etc/hero.pxl",
+            "sprites//hero.pxl", ".trash/hero.pxl", "./hero.pxl", "sprites\\hero.pxl", "hero\0.pxl"] {
+            assert!(!validate_filename(filename), "{filename:?} should be rejected");
+        }
+    }
+}
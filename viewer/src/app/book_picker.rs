@@ -0,0 +1,94 @@
+use crate::models::PixelBookInfo;
+
+/// The books on the server, one of them selected, shown as a list to open from
+#[derive(Debug)]
+pub struct BookPicker {
+    books: Vec<PixelBookInfo>,
+    selected: usize,
+    // First book shown when the list is taller than the window
+    first_visible: usize,
+}
+
+impl BookPicker {
+    pub fn new(books: Vec<PixelBookInfo>) -> Self {
+        Self { books, selected: 0, first_visible: 0 }
+    }
+    
+    pub fn len(&self) -> usize {
+        self.books.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.books.is_empty()
+    }
+    
+    /// Moves the selection by `delta` books, stopping at either end
+    pub fn move_selection(&mut self, delta: isize) {
+        let last = self.books.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+    
+    pub fn selected(&self) -> Option<&PixelBookInfo> {
+        self.books.get(self.selected)
+    }
+    
+    /// One line per book that fits in `rows`, scrolled so the selection is
+    /// among them, and the selected line's position in the result
+    pub fn visible_lines(&mut self, rows: usize) -> (Vec<String>, usize) {
+        let rows = rows.max(1);
+        if self.selected < self.first_visible {
+            self.first_visible = self.selected;
+        } else if self.selected >= self.first_visible + rows {
+            self.first_visible = self.selected + 1 - rows;
+        }
+        
+        let lines = self.books.iter()
+            .skip(self.first_visible)
+            .take(rows)
+            .map(|book| format!("{}  {}x{}  {} frames", book.filename, book.width, book.height, book.frames))
+            .collect();
+        (lines, self.selected - self.first_visible)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn books(count: usize) -> Vec<PixelBookInfo> {
+        (0..count).map(|i| PixelBookInfo {
+            filename: format!("book{}.pxl", i),
+            size: 0,
+            created: chrono::DateTime::UNIX_EPOCH,
+            modified: chrono::DateTime::UNIX_EPOCH,
+            frames: 1,
+            width: 8,
+            height: 8,
+            version: 1,
+            lock: None,
+            details: Default::default(),
+        }).collect()
+    }
+    
+    #[test]
+    fn test_selection_scrolls_the_list() {
+        let mut picker = BookPicker::new(books(10));
+        let (lines, selected) = picker.visible_lines(4);
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "book0.pxl  8x8  1 frames");
+        assert_eq!(selected, 0);
+        
+        picker.move_selection(5);
+        let (lines, selected) = picker.visible_lines(4);
+        assert_eq!((lines[0].as_str(), selected), ("book2.pxl  8x8  1 frames", 3));
+        
+        picker.move_selection(-4);
+        let (lines, selected) = picker.visible_lines(4);
+        assert_eq!((lines[0].as_str(), selected), ("book1.pxl  8x8  1 frames", 0));
+        
+        picker.move_selection(100);
+        assert_eq!(picker.selected().unwrap().filename, "book9.pxl");
+        picker.move_selection(-100);
+        assert_eq!(picker.selected().unwrap().filename, "book0.pxl");
+    }
+}
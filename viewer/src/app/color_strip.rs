@@ -0,0 +1,78 @@
+use crate::models::PixelBook;
+use std::collections::VecDeque;
+
+/// Height of the color strip along the bottom of the window
+pub const STRIP_HEIGHT: usize = 20;
+/// Widest a swatch in the color strip gets
+pub const STRIP_SWATCH_WIDTH: usize = 24;
+/// Recently picked colors kept for books without a palette
+pub const RECENT_COLORS: usize = 10;
+
+/// Swatches along the bottom edge: the book's palette, or the colors picked
+/// most recently when it has none
+#[derive(Debug, Default)]
+pub struct ColorStrip {
+    recent: VecDeque<[u8; 4]>,
+}
+
+impl ColorStrip {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Moves `color` to the front of the recent colors
+    pub fn remember(&mut self, color: [u8; 4]) {
+        self.recent.retain(|&recent| recent != color);
+        self.recent.push_front(color);
+        self.recent.truncate(RECENT_COLORS);
+    }
+    
+    /// The swatches shown for `book`, left to right
+    pub fn colors(&self, book: &PixelBook) -> Vec<[u8; 4]> {
+        match &book.metadata.palette {
+            Some(palette) if !palette.colors.is_empty() => palette.colors.clone(),
+            _ => self.recent.iter().copied().collect(),
+        }
+    }
+    
+    /// Index of the swatch under window position (x, y) when `count` swatches
+    /// are laid out over a strip `width` pixels wide at the bottom of a window
+    /// `height` pixels tall
+    pub fn swatch_at(x: usize, y: usize, count: usize, width: usize, height: usize) -> Option<usize> {
+        if y < height.saturating_sub(STRIP_HEIGHT) || y >= height || x >= width {
+            return None;
+        }
+        let index = x / swatch_width(count, width);
+        (index < count).then_some(index)
+    }
+}
+
+/// Screen width of each of `count` swatches sharing a strip `width` pixels wide
+pub fn swatch_width(count: usize, width: usize) -> usize {
+    (width / count.max(1)).clamp(1, STRIP_SWATCH_WIDTH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Palette;
+    
+    #[test]
+    fn test_strip_colors_and_hit_testing() {
+        let mut book = PixelBook::new("p.pxl".to_string(), 4, 4, 1);
+        let mut strip = ColorStrip::new();
+        strip.remember([255, 0, 0, 255]);
+        strip.remember([0, 0, 255, 255]);
+        strip.remember([255, 0, 0, 255]);
+        assert_eq!(strip.colors(&book), vec![[255, 0, 0, 255], [0, 0, 255, 255]]);
+        
+        // A palette replaces the recent colors
+        book.metadata.palette = Some(Palette { name: "mono".to_string(), colors: vec![[0, 0, 0, 255]] });
+        assert_eq!(strip.colors(&book), vec![[0, 0, 0, 255]]);
+        
+        assert_eq!(ColorStrip::swatch_at(30, 500, 3, 512, 512), Some(1));
+        assert_eq!(ColorStrip::swatch_at(80, 500, 3, 512, 512), None);
+        assert_eq!(ColorStrip::swatch_at(30, 480, 3, 512, 512), None);
+        assert_eq!(swatch_width(256, 512), 2);
+    }
+}
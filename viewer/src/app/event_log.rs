@@ -0,0 +1,92 @@
+use crate::models::{EventType, PixelBookEvent};
+use std::collections::VecDeque;
+
+/// Events kept for the event log panel
+pub const EVENT_LOG_CAPACITY: usize = 12;
+
+/// The most recent events of the open book, oldest first
+#[derive(Debug, Default)]
+pub struct EventLog {
+    pub visible: bool,
+    entries: VecDeque<PixelBookEvent>,
+}
+
+impl EventLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Records `event`, dropping the oldest beyond [`EVENT_LOG_CAPACITY`].
+    /// Heartbeats and connection notices are not logged.
+    pub fn push(&mut self, event: &PixelBookEvent) {
+        if matches!(event.event_type, EventType::Heartbeat | EventType::Connected) {
+            return;
+        }
+        if self.entries.len() == EVENT_LOG_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(event.clone());
+    }
+
+    /// One line per logged event, oldest first
+    pub fn lines(&self) -> Vec<String> {
+        self.entries.iter().map(describe).collect()
+    }
+}
+
+/// `time what [frame] [origin]`, e.g. `14:02:11 draw_line f2 pixl-client/0.1.0`
+pub fn describe(event: &PixelBookEvent) -> String {
+    let time = event.timestamp.with_timezone(&chrono::Local).format("%H:%M:%S");
+    let mut line = match &event.event_type {
+        EventType::DrawingOperation { operation } => format!("{} {}", time, operation.summary()),
+        EventType::OperationsApplied { count, .. } => format!("{} {} operations", time, count),
+        EventType::FrameChanged { frame_index } => format!("{} frame_changed f{}", time, frame_index),
+        other => format!("{} {}", time, other.name()),
+    };
+    if let Some(origin) = &event.origin {
+        line.push(' ');
+        line.push_str(origin);
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{BlendMode, DrawingOperation};
+    use chrono::Utc;
+
+    fn event(event_type: EventType) -> PixelBookEvent {
+        PixelBookEvent {
+            filename: "log.pxl".to_string(),
+            timestamp: Utc::now(),
+            event_type,
+            origin: Some("agent/1.0".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_log_keeps_recent_events() {
+        let mut log = EventLog::new();
+        log.push(&event(EventType::Heartbeat));
+        for x in 0..EVENT_LOG_CAPACITY as u16 + 2 {
+            log.push(&event(EventType::DrawingOperation {
+                operation: DrawingOperation::DrawPixel { frame: 3, x, y: 0, color: [0, 0, 0, 255].into(), blend_mode: BlendMode::Replace },
+            }));
+        }
+        log.push(&event(EventType::BookSaved));
+
+        let lines = log.lines();
+        assert_eq!(lines.len(), EVENT_LOG_CAPACITY);
+        assert!(lines[0].ends_with(" draw_pixel f3 agent/1.0"));
+        assert!(lines[EVENT_LOG_CAPACITY - 1].ends_with(" book_saved agent/1.0"));
+    }
+}
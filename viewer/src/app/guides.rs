@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+/// Thickness of the rulers along the top and left window edges
+pub const RULER_SIZE: usize = 16;
+/// How close, in screen pixels, a click must be to a guide to pick it up
+pub const GUIDE_GRAB_DISTANCE: i32 = 3;
+
+/// A guide line on a pixel boundary: the y of a horizontal guide or the x of
+/// a vertical one, from 0 to the book's height or width
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Guide {
+    Horizontal(u16),
+    Vertical(u16),
+}
+
+impl Guide {
+    /// The same guide moved to `boundary`
+    pub fn moved_to(self, boundary: u16) -> Self {
+        match self {
+            Guide::Horizontal(_) => Guide::Horizontal(boundary),
+            Guide::Vertical(_) => Guide::Vertical(boundary),
+        }
+    }
+}
+
+/// Guides of every book opened this session, keyed by filename
+#[derive(Debug, Default)]
+pub struct GuideStore {
+    guides: HashMap<String, Vec<Guide>>,
+}
+
+impl GuideStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, filename: &str) -> &[Guide] {
+        self.guides.get(filename).map_or(&[], Vec::as_slice)
+    }
+
+    /// Adds a guide, returning its index
+    pub fn add(&mut self, filename: &str, guide: Guide) -> usize {
+        let guides = self.guides.entry(filename.to_string()).or_default();
+        guides.push(guide);
+        guides.len() - 1
+    }
+
+    pub fn set(&mut self, filename: &str, index: usize, guide: Guide) {
+        if let Some(slot) = self.guides.get_mut(filename).and_then(|guides| guides.get_mut(index)) {
+            *slot = guide;
+        }
+    }
+
+    pub fn remove(&mut self, filename: &str, index: usize) {
+        if let Some(guides) = self.guides.get_mut(filename) {
+            if index < guides.len() {
+                guides.remove(index);
+            }
+        }
+    }
+
+    /// Index of the guide within [`GUIDE_GRAB_DISTANCE`] of screen point
+    /// (x, y), given where each guide is drawn
+    pub fn find_near(&self, filename: &str, x: i32, y: i32, screen_position: impl Fn(Guide) -> i32) -> Option<usize> {
+        self.get(filename).iter().position(|&guide| {
+            let along = match guide {
+                Guide::Horizontal(_) => y,
+                Guide::Vertical(_) => x,
+            };
+            (along - screen_position(guide)).abs() <= GUIDE_GRAB_DISTANCE
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guides_are_kept_per_book() {
+        let mut store = GuideStore::new();
+        let index = store.add("a.pxl", Guide::Vertical(4));
+        store.add("b.pxl", Guide::Horizontal(2));
+
+        store.set("a.pxl", index, Guide::Vertical(4).moved_to(6));
+        assert_eq!(store.get("a.pxl"), &[Guide::Vertical(6)]);
+        assert_eq!(store.get("b.pxl"), &[Guide::Horizontal(2)]);
+
+        // Guides are drawn at 10 screen pixels per boundary
+        let position = |guide| match guide {
+            Guide::Horizontal(y) | Guide::Vertical(y) => y as i32 * 10,
+        };
+        assert_eq!(store.find_near("a.pxl", 62, 0, position), Some(0));
+        assert_eq!(store.find_near("a.pxl", 0, 60, position), None);
+
+        store.remove("a.pxl", 0);
+        assert!(store.get("a.pxl").is_empty());
+        assert!(store.get("c.pxl").is_empty());
+    }
+}
@@ -0,0 +1,103 @@
+use crate::services::ConnectionState;
+use std::time::{Duration, Instant};
+
+/// Key bindings listed by the help overlay
+pub const HELP_LINES: &[&str] = &[
+    "CTRL+O      OPEN A BOOK",
+    "UP/DOWN     CHOOSE A BOOK, ENTER OPENS",
+    "LEFT/RIGHT  PREVIOUS/NEXT FRAME",
+    "SPACE       PLAY/PAUSE",
+    "P           PALETTE PANEL",
+    "1-9, 0      PICK FROM THE COLOR STRIP",
+    "ALT+CLICK   PICK THE COLOR UNDER THE CURSOR",
+    "L           EVENT LOG",
+    "R           RULERS",
+    "E           SAVE FRAME AS PNG",
+    "SHIFT+DRAG  SELECT",
+    "CTRL+C      COPY SELECTION",
+    "C           CLEAR ERROR",
+    "H, F1       THIS HELP",
+    "ESC         QUIT",
+];
+
+/// Status line label and indicator color for the live update stream
+pub fn connection_status(state: ConnectionState) -> (String, u32) {
+    match state {
+        ConnectionState::Connected => ("LIVE".to_string(), 0x40C040),
+        ConnectionState::Connecting => ("CONNECTING".to_string(), 0xE0C040),
+        ConnectionState::Reconnecting { attempt } => (format!("RECONNECTING ({})", attempt), 0xE0C040),
+        ConnectionState::Disconnected => ("OFFLINE".to_string(), 0xE04040),
+    }
+}
+
+/// Frames presented per second, counted over one-second windows
+#[derive(Debug, Default)]
+pub struct FpsCounter {
+    frames: u32,
+    window_start: Option<Instant>,
+    fps: u32,
+}
+
+impl FpsCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts a presented frame
+    pub fn tick(&mut self, now: Instant) {
+        let start = *self.window_start.get_or_insert(now);
+        self.frames += 1;
+        if now.duration_since(start) >= Duration::from_secs(1) {
+            self.fps = self.frames;
+            self.frames = 0;
+            self.window_start = Some(now);
+        }
+    }
+
+    /// Frames counted in the last full window
+    pub fn fps(&self) -> u32 {
+        self.fps
+    }
+}
+
+/// Text drawn over the window: a status line, the help overlay, and messages
+#[derive(Debug, Default)]
+pub struct Hud {
+    pub help_visible: bool,
+    pub fps: FpsCounter,
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn toggle_help(&mut self) {
+        self.help_visible = !self.help_visible;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fps_counter() {
+        let mut counter = FpsCounter::new();
+        let start = Instant::now();
+        for frame in 0..30 {
+            counter.tick(start + Duration::from_millis(frame * 20));
+        }
+        assert_eq!(counter.fps(), 0);
+
+        counter.tick(start + Duration::from_secs(1));
+        assert_eq!(counter.fps(), 31);
+    }
+
+    #[test]
+    fn test_connection_status() {
+        assert_eq!(connection_status(ConnectionState::Connected).0, "LIVE");
+        assert_eq!(connection_status(ConnectionState::Reconnecting { attempt: 3 }).0, "RECONNECTING (3)");
+        assert_ne!(connection_status(ConnectionState::Connected).1, connection_status(ConnectionState::Disconnected).1);
+    }
+}
@@ -1,4 +1,4 @@
-use minifb::{Key, Window};
+use minifb::{Key, MouseButton, MouseMode, Window};
 
 pub struct InputHandler;
 
@@ -31,6 +31,83 @@ impl InputHandler {
         window.is_key_pressed(Key::I, minifb::KeyRepeat::No)
     }
     
+    pub fn is_export_frame_pressed(window: &Window) -> bool {
+        window.is_key_pressed(Key::E, minifb::KeyRepeat::No)
+    }
+    
+    pub fn is_event_log_toggle_pressed(window: &Window) -> bool {
+        window.is_key_pressed(Key::L, minifb::KeyRepeat::No)
+    }
+    
+    pub fn is_palette_toggle_pressed(window: &Window) -> bool {
+        window.is_key_pressed(Key::P, minifb::KeyRepeat::No)
+    }
+    
+    pub fn is_playback_toggle_pressed(window: &Window) -> bool {
+        window.is_key_pressed(Key::Space, minifb::KeyRepeat::No)
+    }
+    
+    pub fn is_up_pressed(window: &Window) -> bool {
+        window.is_key_pressed(Key::Up, minifb::KeyRepeat::Yes)
+    }
+    
+    pub fn is_down_pressed(window: &Window) -> bool {
+        window.is_key_pressed(Key::Down, minifb::KeyRepeat::Yes)
+    }
+    
+    pub fn is_page_up_pressed(window: &Window) -> bool {
+        window.is_key_pressed(Key::PageUp, minifb::KeyRepeat::Yes)
+    }
+    
+    pub fn is_page_down_pressed(window: &Window) -> bool {
+        window.is_key_pressed(Key::PageDown, minifb::KeyRepeat::Yes)
+    }
+    
+    pub fn is_enter_pressed(window: &Window) -> bool {
+        window.is_key_pressed(Key::Enter, minifb::KeyRepeat::No)
+            || window.is_key_pressed(Key::NumPadEnter, minifb::KeyRepeat::No)
+    }
+    
+    /// Swatch chosen with the number keys: `1` to `9` pick the first nine, `0` the tenth
+    pub fn number_key_pressed(window: &Window) -> Option<usize> {
+        const KEYS: [Key; 10] = [
+            Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5,
+            Key::Key6, Key::Key7, Key::Key8, Key::Key9, Key::Key0,
+        ];
+        KEYS.iter().position(|&key| window.is_key_pressed(key, minifb::KeyRepeat::No))
+    }
+    
+    pub fn is_alt_down(window: &Window) -> bool {
+        window.is_key_down(Key::LeftAlt) || window.is_key_down(Key::RightAlt)
+    }
+    
+    pub fn is_shift_down(window: &Window) -> bool {
+        window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift)
+    }
+    
+    pub fn is_copy_pressed(window: &Window) -> bool {
+        window.is_key_pressed(Key::C, minifb::KeyRepeat::No)
+            && (window.is_key_down(Key::LeftCtrl) || window.is_key_down(Key::RightCtrl)
+                || window.is_key_down(Key::LeftSuper) || window.is_key_down(Key::RightSuper))
+    }
+    
+    pub fn is_rulers_toggle_pressed(window: &Window) -> bool {
+        window.is_key_pressed(Key::R, minifb::KeyRepeat::No)
+    }
+    
+    /// Window position of the cursor, even outside the window
+    pub fn mouse_position(window: &Window) -> Option<(i32, i32)> {
+        window.get_mouse_pos(MouseMode::Pass).map(|(x, y)| (x as i32, y as i32))
+    }
+    
+    /// Window position of the cursor while the left button is down
+    pub fn left_mouse_position(window: &Window) -> Option<(usize, usize)> {
+        if !window.get_mouse_down(MouseButton::Left) {
+            return None;
+        }
+        window.get_mouse_pos(MouseMode::Discard).map(|(x, y)| (x as usize, y as usize))
+    }
+    
     pub fn is_escape_pressed(window: &Window) -> bool {
         window.is_key_pressed(Key::Escape, minifb::KeyRepeat::No)
     }
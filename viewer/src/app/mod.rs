@@ -1,7 +1,21 @@
 pub mod viewer;
 pub mod input;
 pub mod state;
+pub mod palette;
+pub mod event_log;
+pub mod guides;
+pub mod selection;
+pub mod hud;
+pub mod book_picker;
+pub mod color_strip;
 
 pub use viewer::*;
 pub use input::*;
-pub use state::*; 
\ No newline at end of file
+pub use state::*;
+pub use palette::*;
+pub use event_log::*;
+pub use guides::*;
+pub use selection::*;
+pub use hud::*;
+pub use book_picker::*;
+pub use color_strip::*; 
\ No newline at end of file
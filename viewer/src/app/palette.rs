@@ -0,0 +1,94 @@
+use crate::models::Frame;
+use std::collections::HashMap;
+
+/// Width of the palette panel along the right edge of the window
+pub const PALETTE_PANEL_WIDTH: usize = 96;
+/// Height of one swatch row in the palette panel
+pub const SWATCH_SIZE: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaletteEntry {
+    pub color: [u8; 4],
+    pub count: usize,
+}
+
+/// Every distinct visible color of the current frame, most used first
+#[derive(Debug, Default)]
+pub struct PalettePanel {
+    pub visible: bool,
+    pub entries: Vec<PaletteEntry>,
+}
+
+impl PalettePanel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Recounts the colors of `frame`; fully transparent pixels are left out
+    pub fn refresh(&mut self, frame: &Frame) {
+        let mut counts: HashMap<[u8; 4], usize> = HashMap::new();
+        for pixel in frame.pixels.chunks_exact(4).filter(|p| p[3] != 0) {
+            *counts.entry([pixel[0], pixel[1], pixel[2], pixel[3]]).or_default() += 1;
+        }
+
+        self.entries = counts.into_iter()
+            .map(|(color, count)| PaletteEntry { color, count })
+            .collect();
+        self.entries.sort_unstable_by(|a, b| b.count.cmp(&a.count).then(a.color.cmp(&b.color)));
+    }
+
+    /// The entry whose row is under window position (x, y), if the panel is shown there
+    pub fn entry_at(&self, x: usize, y: usize, window_width: usize) -> Option<&PaletteEntry> {
+        if !self.visible || x < window_width.saturating_sub(PALETTE_PANEL_WIDTH) || x >= window_width {
+            return None;
+        }
+        self.entries.get(y / SWATCH_SIZE)
+    }
+
+    /// One line per entry, for the console
+    pub fn describe(&self) -> String {
+        self.entries.iter()
+            .map(|entry| format!("{}  {} px", hex(entry.color), entry.count))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// `#rrggbbaa`
+pub fn hex(color: [u8; 4]) -> String {
+    format!("#{:02x}{:02x}{:02x}{:02x}", color[0], color[1], color[2], color[3])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{PixelBook, Pixel};
+
+    #[test]
+    fn test_palette_counts_and_hit_testing() {
+        let mut book = PixelBook::new("p.pxl".to_string(), 4, 1, 1);
+        let frame = &mut book.frames[0];
+        frame.set_pixel(0, 0, 4, Pixel::new(255, 0, 0, 255));
+        frame.set_pixel(1, 0, 4, Pixel::new(0, 0, 255, 255));
+        frame.set_pixel(2, 0, 4, Pixel::new(0, 0, 255, 255));
+
+        let mut panel = PalettePanel::new();
+        panel.refresh(&book.frames[0]);
+        assert_eq!(panel.entries, vec![
+            PaletteEntry { color: [0, 0, 255, 255], count: 2 },
+            PaletteEntry { color: [255, 0, 0, 255], count: 1 },
+        ]);
+
+        // Hidden panels never hit
+        assert_eq!(panel.entry_at(500, 20, 512), None);
+        panel.toggle();
+        assert_eq!(panel.entry_at(500, 20, 512).map(|e| e.color), Some([255, 0, 0, 255]));
+        assert_eq!(panel.entry_at(10, 20, 512), None);
+        assert_eq!(panel.entry_at(500, 40, 512), None);
+        assert_eq!(hex([255, 0, 16, 255]), "#ff0010ff");
+    }
+}
@@ -0,0 +1,41 @@
+use crate::models::{Frame, Pixel, Rect};
+
+/// The rectangle of pixels between two pixel boundaries, in any order;
+/// `None` when it would be empty
+pub fn rect_between(a: (u16, u16), b: (u16, u16)) -> Option<Rect> {
+    let (x, y) = (a.0.min(b.0), a.1.min(b.1));
+    let (width, height) = (a.0.abs_diff(b.0), a.1.abs_diff(b.1));
+    if width == 0 || height == 0 {
+        return None;
+    }
+    Some(Rect { x, y, width, height })
+}
+
+/// RGBA bytes of `rect` in `frame`, row by row
+pub fn region_rgba(frame: &Frame, frame_width: u16, rect: &Rect) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(rect.width as usize * rect.height as usize * 4);
+    for y in rect.y..rect.y + rect.height {
+        for x in rect.x..rect.x + rect.width {
+            let pixel = frame.get_pixel(x, y, frame_width).unwrap_or(Pixel::new(0, 0, 0, 0));
+            bytes.extend_from_slice(&[pixel.r, pixel.g, pixel.b, pixel.a]);
+        }
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PixelBook;
+
+    #[test]
+    fn test_selection_region() {
+        assert!(rect_between((2, 1), (2, 4)).is_none());
+        let rect = rect_between((3, 2), (1, 1)).unwrap();
+        assert_eq!((rect.x, rect.y, rect.width, rect.height), (1, 1, 2, 1));
+
+        let mut book = PixelBook::new("s.pxl".to_string(), 4, 4, 1);
+        book.frames[0].set_pixel(2, 1, 4, Pixel::new(9, 8, 7, 255));
+        assert_eq!(region_rgba(&book.frames[0], 4, &rect), vec![0, 0, 0, 0, 9, 8, 7, 255]);
+    }
+}
@@ -1,21 +1,35 @@
-use crate::models::PixelBook;
+use crate::app::GuideStore;
+use crate::models::{Frame, PixelBook};
+use crate::services::ConnectionState;
+use std::time::{Duration, Instant};
 
-#[derive(Debug)]
+/// How long a toast stays on screen
+pub const TOAST_DURATION: Duration = Duration::from_secs(3);
+
+/// How long playback shows a frame that has no duration of its own
+pub const DEFAULT_FRAME_DURATION: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Default)]
 pub struct AppState {
     pub current_book: Option<PixelBook>,
     pub current_frame: usize,
     pub is_connected: bool,
+    /// State of the live update stream of the open book
+    pub connection: ConnectionState,
     pub last_error: Option<String>,
+    /// Drawing color picked from the palette panel, the color strip, or the frame
+    pub current_color: Option<[u8; 4]>,
+    /// Short-lived confirmation message and when it was shown
+    pub toast: Option<(String, Instant)>,
+    /// Guide lines of each book, kept while the viewer runs
+    pub guides: GuideStore,
+    /// When playback last moved to the current frame; `None` while paused
+    pub playing_since: Option<Instant>,
 }
 
 impl AppState {
     pub fn new() -> Self {
-        Self {
-            current_book: None,
-            current_frame: 0,
-            is_connected: false,
-            last_error: None,
-        }
+        Self::default()
     }
     
     pub fn set_book(&mut self, book: PixelBook) {
@@ -24,9 +38,17 @@ impl AppState {
         self.last_error = None;
     }
     
+    /// Adds frames that arrived after the book was first shown
+    pub fn append_frames(&mut self, frames: Vec<Frame>) {
+        if let Some(book) = &mut self.current_book {
+            book.frames.extend(frames);
+        }
+    }
+    
     pub fn clear_book(&mut self) {
         self.current_book = None;
         self.current_frame = 0;
+        self.playing_since = None;
     }
     
     pub fn set_frame(&mut self, frame: usize) {
@@ -51,11 +73,99 @@ impl AppState {
         }
     }
     
+    pub fn is_playing(&self) -> bool {
+        self.playing_since.is_some()
+    }
+    
+    pub fn toggle_playback(&mut self, now: Instant) {
+        self.playing_since = match self.playing_since {
+            Some(_) => None,
+            None => Some(now),
+        };
+    }
+    
+    /// How long playback shows the current frame
+    pub fn current_frame_duration(&self) -> Duration {
+        self.current_book.as_ref()
+            .and_then(|book| book.frames.get(self.current_frame))
+            .and_then(|frame| frame.duration_ms)
+            .map(|ms| Duration::from_millis(ms as u64))
+            .unwrap_or(DEFAULT_FRAME_DURATION)
+    }
+    
+    /// Moves playback on to the next frame, looping at the end, once the
+    /// current frame has been shown for its duration
+    pub fn advance_playback(&mut self, now: Instant) {
+        let Some(since) = self.playing_since else { return };
+        let Some(frame_count) = self.current_book.as_ref().map(|book| book.frames.len()) else { return };
+        if frame_count < 2 {
+            return;
+        }
+        
+        let duration = self.current_frame_duration();
+        if now.duration_since(since) >= duration {
+            self.current_frame = (self.current_frame + 1) % frame_count;
+            // Keep to the schedule when a render runs a little late, but don't
+            // race through frames to catch up after a long stall
+            let next = since + duration;
+            self.playing_since = Some(if now.duration_since(next) >= duration { now } else { next });
+        }
+    }
+    
+    pub fn show_toast(&mut self, message: String) {
+        self.toast = Some((message, Instant::now()));
+    }
+    
+    /// The toast, until [`TOAST_DURATION`] has passed
+    pub fn active_toast(&self) -> Option<&str> {
+        self.toast.as_ref()
+            .filter(|(_, shown)| shown.elapsed() < TOAST_DURATION)
+            .map(|(message, _)| message.as_str())
+    }
+    
+    /// Shows `error` in the window, printing it once rather than every frame
     pub fn set_error(&mut self, error: String) {
+        if self.last_error.as_ref() != Some(&error) {
+            println!("Error: {}", error);
+        }
         self.last_error = Some(error);
     }
     
     pub fn clear_error(&mut self) {
         self.last_error = None;
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn book(durations: &[Option<u32>]) -> PixelBook {
+        let mut book = PixelBook::new("walk.pxl".to_string(), 2, 2, 0);
+        book.frames = durations.iter().enumerate()
+            .map(|(index, &duration_ms)| Frame { duration_ms, ..Frame::new(index, 2, 2) })
+            .collect();
+        book
+    }
+    
+    #[test]
+    fn test_playback_uses_frame_durations() {
+        let mut state = AppState::new();
+        state.set_book(book(&[Some(500), None]));
+        let start = Instant::now();
+        state.toggle_playback(start);
+        
+        state.advance_playback(start + Duration::from_millis(400));
+        assert_eq!(state.current_frame, 0);
+        state.advance_playback(start + Duration::from_millis(500));
+        assert_eq!(state.current_frame, 1);
+        
+        // The second frame falls back to the default and then loops around
+        state.advance_playback(start + Duration::from_millis(500) + DEFAULT_FRAME_DURATION);
+        assert_eq!(state.current_frame, 0);
+        
+        state.toggle_playback(start);
+        state.advance_playback(start + Duration::from_secs(10));
+        assert_eq!(state.current_frame, 0);
+    }
+}
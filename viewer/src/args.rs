@@ -0,0 +1,47 @@
+//! Command-line flags of the viewer. Flags take precedence over environment
+//! variables.
+
+use std::path::PathBuf;
+
+use clap::Parser;
+
+/// Server the viewer talks to when neither `--server` nor `PIXL_SERVER_URL` is set
+pub const DEFAULT_SERVER_URL: &str = "http://localhost:3000";
+
+/// Command-line flags of `viewer`
+#[derive(Debug, Parser)]
+#[command(name = "viewer", version, about = "Shows pixel books from a PIXL server as they are drawn")]
+pub struct ViewerArgs {
+    /// Base URL of the PIXL server
+    #[arg(long, value_name = "URL", env = "PIXL_SERVER_URL", default_value = DEFAULT_SERVER_URL)]
+    pub server: String,
+    /// Book to open instead of listing the server's books
+    #[arg(long, value_name = "NAME")]
+    pub book: Option<String>,
+    /// Frame of `--book` to show first (0-based)
+    #[arg(long, value_name = "N", requires = "book")]
+    pub frame: Option<usize>,
+    /// Cover the screen with a borderless window
+    #[arg(long)]
+    pub fullscreen: bool,
+    /// Directory the `E` key saves frame PNGs into [default: the working directory]
+    #[arg(long, value_name = "DIR", env = "PIXL_EXPORT_DIR")]
+    pub export_dir: Option<PathBuf>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_args() {
+        let args = ViewerArgs::try_parse_from(["viewer", "--server", "http://pixl.local:8080", "--book", "walk.pxl", "--frame", "3", "--fullscreen"]).unwrap();
+        assert_eq!(args.server, "http://pixl.local:8080");
+        assert_eq!(args.book.as_deref(), Some("walk.pxl"));
+        assert_eq!(args.frame, Some(3));
+        assert!(args.fullscreen);
+
+        // A frame means nothing without a book to show it from
+        assert!(ViewerArgs::try_parse_from(["viewer", "--frame", "3"]).is_err());
+    }
+}
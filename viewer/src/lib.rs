@@ -1,5 +1,8 @@
 pub mod app;
+pub mod args;
 pub mod rendering;
 pub mod models;
 pub mod services;
-pub mod utils; 
\ No newline at end of file
+pub mod utils;
+
+pub use args::ViewerArgs;
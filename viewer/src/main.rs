@@ -1,15 +1,13 @@
 use std::error::Error;
 
-mod app;
-mod rendering;
-mod models;
-mod services;
-mod utils;
-
-use app::Viewer;
+use clap::Parser;
+use viewer::app::Viewer;
+use viewer::ViewerArgs;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let args = ViewerArgs::parse();
+    
     // Initialize logging
     if std::env::var_os("RUST_LOG").is_none() {
         std::env::set_var("RUST_LOG", "info");
@@ -18,11 +16,12 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
 
     println!("Starting PIXL Viewer...");
 
-    let mut viewer = Viewer::new()?;
-    
-    // For demo purposes in Phase 1, try to load a demo book if available
-    if let Err(e) = viewer.load_demo_book().await {
-        println!("Could not load demo book: {}", e);
+    let mut viewer = Viewer::new(&args.server, args.fullscreen)?;
+    if let Some(dir) = args.export_dir {
+        viewer = viewer.with_export_dir(dir);
+    }
+    if let Some(book) = args.book {
+        viewer = viewer.with_book(book, args.frame.unwrap_or(0));
     }
     
     viewer.run().await?;
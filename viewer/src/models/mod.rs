@@ -1,5 +1 @@
-pub mod pixel_book;
-pub mod events;
-
-pub use pixel_book::*;
-pub use events::*; 
\ No newline at end of file
+pub use pixl_core::*;
@@ -18,7 +18,7 @@ impl CheckerboardPattern {
         let checker_x = x / checker_size;
         let checker_y = y / checker_size;
         
-        if (checker_x + checker_y) % 2 == 0 {
+        if (checker_x + checker_y).is_multiple_of(2) {
             self.light_color
         } else {
             self.dark_color
@@ -26,6 +26,12 @@ impl CheckerboardPattern {
     }
 }
 
+impl Default for CheckerboardPattern {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
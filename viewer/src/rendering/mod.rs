@@ -1,7 +1,11 @@
 pub mod renderer;
 pub mod scaling;
 pub mod checkerboard;
+pub mod preview;
+pub mod font;
 
 pub use renderer::*;
 pub use scaling::*;
-pub use checkerboard::*; 
\ No newline at end of file
+pub use checkerboard::*;
+pub use preview::*;
+pub use font::*; 
\ No newline at end of file
@@ -0,0 +1,300 @@
+use crate::models::{antialiased_path, bezier_points, circle_pixels, flood_region, shape_pixels, noise_fill, line_pixels, path_pixels, pixel_perfect, polygon_interior, LineStyle, dither_gradient, ColorRef, flip_pixels, gradient_fill, region_pixels, rotate_pixels, shift_pixels, stamp_pixels, DrawingOperation, LineType, PixelBook, Rect, StampSource};
+use base64::Engine;
+use std::collections::HashMap;
+
+/// Opacity of previewed pixels over the last saved frame
+pub const PREVIEW_ALPHA: u8 = 160;
+
+/// Pixels that operations reported over the event stream are expected to
+/// change, shown until the saved frame data arrives. The geometry comes from
+/// the same rasterizer the server draws with; symmetry and selections are
+/// not previewed.
+#[derive(Debug, Default)]
+pub struct PreviewOverlay {
+    // (frame, x, y) -> color
+    pixels: HashMap<(usize, u16, u16), [u8; 4]>,
+}
+
+impl PreviewOverlay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.pixels.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pixels.is_empty()
+    }
+
+    /// Previewed pixels of one frame
+    pub fn pixels_for_frame(&self, frame: usize) -> impl Iterator<Item = (u16, u16, [u8; 4])> + '_ {
+        self.pixels.iter()
+            .filter(move |((f, _, _), _)| *f == frame)
+            .map(|(&(_, x, y), &color)| (x, y, color))
+    }
+
+    /// Adds the pixels `operation` will draw; `book` is the last saved state,
+    /// used by fills and copies
+    pub fn add_operation(&mut self, operation: &DrawingOperation, book: &PixelBook) {
+        let (width, height) = (book.width, book.height);
+        let mut plot = |frame: usize, x: i32, y: i32, color: [u8; 4]| {
+            if x >= 0 && y >= 0 && x < width as i32 && y < height as i32 {
+                self.pixels.insert((frame, x as u16, y as u16), color);
+            }
+        };
+        // Operations using palette indices the saved book can't resolve are not previewed
+        let palette = book.metadata.palette.as_ref();
+        let rgba = |color: &ColorRef| color.resolve(palette);
+
+        match operation {
+            DrawingOperation::DrawPixel { frame, x, y, color, .. } => {
+                let Some(color) = rgba(color) else { return };
+                plot(*frame, *x as i32, *y as i32, color);
+            }
+            DrawingOperation::SetColor { .. }
+            | DrawingOperation::SetSelection { .. }
+            | DrawingOperation::ClearSelection => {}
+            DrawingOperation::DrawLine { frame, start, end, line_type, color, control_points, style, .. } => {
+                let Some(color) = rgba(color) else { return };
+                let vertices = match line_type {
+                    LineType::Straight => vec![start.clone(), end.clone()],
+                    LineType::Curved => bezier_points(start, control_points, end),
+                };
+                match style {
+                    LineStyle::Aliased => {
+                        for (x, y) in path_pixels(&vertices) {
+                            plot(*frame, x, y, color);
+                        }
+                    }
+                    LineStyle::PixelPerfect => {
+                        for (x, y) in pixel_perfect(&path_pixels(&vertices)) {
+                            plot(*frame, x, y, color);
+                        }
+                    }
+                    // Coverage becomes opacity; blending with the saved pixel is left to the save
+                    LineStyle::AntiAliased => {
+                        for (x, y, coverage) in antialiased_path(&vertices) {
+                            let alpha = (color[3] as f32 * coverage).round() as u8;
+                            plot(*frame, x, y, [color[0], color[1], color[2], alpha]);
+                        }
+                    }
+                }
+            }
+            DrawingOperation::DrawShape { frame, shape, position, size, filled, color, .. } => {
+                let Some(color) = rgba(color) else { return };
+                for (x, y) in shape_pixels(shape, position, size, *filled, width, height) {
+                    plot(*frame, x, y, color);
+                }
+            }
+            DrawingOperation::DrawCircle { frame, center, radius, filled, color, .. } => {
+                let Some(color) = rgba(color) else { return };
+                for (x, y) in circle_pixels(center.x as i32, center.y as i32, *radius as i32, *filled, width, height) {
+                    plot(*frame, x, y, color);
+                }
+            }
+            DrawingOperation::DrawPolygon { frame, points, filled, color, fill_rule, .. } => {
+                let Some(color) = rgba(color) else { return };
+                if *filled {
+                    for (x, y) in polygon_interior(points, *fill_rule, width, height) {
+                        plot(*frame, x as i32, y as i32, color);
+                    }
+                }
+                for (start, end) in points.iter().zip(points.iter().cycle().skip(1)) {
+                    for (x, y) in line_pixels(start, end) {
+                        plot(*frame, x, y, color);
+                    }
+                }
+            }
+            DrawingOperation::FillArea { frame, x, y, color, contiguous, bounds, .. } => {
+                let Some(color) = rgba(color) else { return };
+                if let Some(source) = book.frames.get(*frame) {
+                    for (x, y) in flood_region(source, width, height, *x, *y, *contiguous, bounds.as_ref(), None) {
+                        plot(*frame, x as i32, y as i32, color);
+                    }
+                }
+            }
+            DrawingOperation::CopyRegion { src_frame, src_rect, dst_frame, dst_point }
+            | DrawingOperation::CutRegion { src_frame, src_rect, dst_frame, dst_point } => {
+                if matches!(operation, DrawingOperation::CutRegion { .. }) {
+                    for dy in 0..src_rect.height as i32 {
+                        for dx in 0..src_rect.width as i32 {
+                            plot(*src_frame, src_rect.x as i32 + dx, src_rect.y as i32 + dy, [0, 0, 0, 0]);
+                        }
+                    }
+                }
+                if let Some(source) = book.frames.get(*src_frame) {
+                    for dy in 0..src_rect.height {
+                        for dx in 0..src_rect.width {
+                            if let Some(pixel) = source.get_pixel(src_rect.x + dx, src_rect.y + dy, width) {
+                                let (x, y) = (dst_point.x as i32 + dx as i32, dst_point.y as i32 + dy as i32);
+                                plot(*dst_frame, x, y, [pixel.r, pixel.g, pixel.b, pixel.a]);
+                            }
+                        }
+                    }
+                }
+            }
+            DrawingOperation::SwapColors { frame, a, b } => {
+                let (Some(a), Some(b)) = (rgba(a), rgba(b)) else { return };
+                for (index, source) in book.frames.iter().enumerate() {
+                    if frame.is_some_and(|frame| frame != index) {
+                        continue;
+                    }
+                    for (i, pixel) in source.pixels.chunks_exact(4).enumerate() {
+                        let (x, y) = ((i % width as usize) as i32, (i / width as usize) as i32);
+                        if pixel == a {
+                            plot(index, x, y, b);
+                        } else if pixel == b {
+                            plot(index, x, y, a);
+                        }
+                    }
+                }
+            }
+            DrawingOperation::ErasePixel { frame, x, y } => plot(*frame, *x as i32, *y as i32, [0, 0, 0, 0]),
+            DrawingOperation::EraseArea { frame, rect } => {
+                for dy in 0..rect.height as i32 {
+                    for dx in 0..rect.width as i32 {
+                        plot(*frame, rect.x as i32 + dx, rect.y as i32 + dy, [0, 0, 0, 0]);
+                    }
+                }
+            }
+            DrawingOperation::ClearFrame { frame } => {
+                for y in 0..height as i32 {
+                    for x in 0..width as i32 {
+                        plot(*frame, x, y, [0, 0, 0, 0]);
+                    }
+                }
+            }
+            DrawingOperation::FlipFrame { frame, .. }
+            | DrawingOperation::RotateFrame { frame, .. }
+            | DrawingOperation::ShiftFrame { frame, .. } => {
+                let Some(source) = book.frames.get(*frame) else { return };
+                let pixels = match *operation {
+                    DrawingOperation::FlipFrame { axis, .. } => flip_pixels(&source.pixels, width, height, axis),
+                    DrawingOperation::RotateFrame { degrees: 180, .. } => rotate_pixels(&source.pixels, width, height, 2),
+                    DrawingOperation::RotateFrame { degrees, .. } if width == height && (degrees == 90 || degrees == 270) => {
+                        rotate_pixels(&source.pixels, width, height, (degrees / 90) as u8)
+                    }
+                    DrawingOperation::ShiftFrame { dx, dy, wrap, .. } => shift_pixels(&source.pixels, width, height, dx, dy, wrap),
+                    _ => return,
+                };
+                for (i, pixel) in pixels.chunks_exact(4).enumerate() {
+                    let (x, y) = ((i % width as usize) as i32, (i / width as usize) as i32);
+                    plot(*frame, x, y, [pixel[0], pixel[1], pixel[2], pixel[3]]);
+                }
+            }
+            DrawingOperation::GradientFill { frame, rect, seed, from, to, direction, dither, .. } => {
+                let (Some(from), Some(to)) = (rgba(from), rgba(to)) else { return };
+                let region: Vec<(u16, u16)> = match (seed, rect) {
+                    (Some(seed), _) => match book.frames.get(*frame) {
+                        Some(source) => flood_region(source, width, height, seed.x, seed.y, true, rect.as_ref(), None),
+                        None => return,
+                    },
+                    (None, Some(rect)) => rect.canvas_pixels(width, height),
+                    (None, None) => return,
+                };
+                for (x, y, color) in gradient_fill(&region, from, to, *direction, *dither) {
+                    plot(*frame, x as i32, y as i32, color);
+                }
+            }
+            // The server fills in a seed before announcing the operation
+            DrawingOperation::NoiseFill { frame, rect, colors, density, seed: Some(seed), .. } => {
+                let Some(colors) = colors.iter().map(rgba).collect::<Option<Vec<_>>>() else { return };
+                for (x, y, color) in noise_fill(&rect.canvas_pixels(width, height), &colors, *density, *seed) {
+                    plot(*frame, x as i32, y as i32, color);
+                }
+            }
+            DrawingOperation::NoiseFill { seed: None, .. } => {}
+            DrawingOperation::DitherGradient { frame, rect, from, to, direction, .. } => {
+                let (Some(from), Some(to)) = (rgba(from), rgba(to)) else { return };
+                for (x, y, color) in dither_gradient(rect, from, to, *direction, width, height) {
+                    plot(*frame, x as i32, y as i32, color);
+                }
+            }
+            DrawingOperation::DrawStamp { frame, position, source, flip, skip_transparent, .. } => {
+                let (stamp_width, stamp_height, pixels) = match source {
+                    StampSource::Inline { width, height, data } => {
+                        let Ok(pixels) = base64::engine::general_purpose::STANDARD.decode(data) else { return };
+                        if pixels.len() != *width as usize * *height as usize * 4 {
+                            return;
+                        }
+                        (*width, *height, pixels)
+                    }
+                    StampSource::Frame { book: None, frame, rect } => {
+                        let Some(source) = book.frames.get(*frame) else { return };
+                        let whole = Rect { x: 0, y: 0, width, height };
+                        region_pixels(&source.pixels, width, height, rect.as_ref().unwrap_or(&whole))
+                    }
+                    // The server sends stamps from other books inline
+                    StampSource::Frame { book: Some(_), .. } => return,
+                };
+                for (dx, dy, color) in stamp_pixels(&pixels, stamp_width, stamp_height, *flip, *skip_transparent) {
+                    plot(*frame, position.x as i32 + dx as i32, position.y as i32 + dy as i32, color);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{BlendMode, Pixel, Point, ShapeType, Size};
+
+    #[test]
+    fn test_preview_geometry() {
+        let mut book = PixelBook::new("p.pxl".to_string(), 8, 8, 2);
+        book.frames[0].set_pixel(3, 0, 8, Pixel::new(0, 0, 0, 255));
+        let mut overlay = PreviewOverlay::new();
+
+        overlay.add_operation(&DrawingOperation::DrawLine {
+            frame: 0,
+            start: Point { x: 0, y: 0 },
+            end: Point { x: 3, y: 3 },
+            line_type: crate::models::LineType::Straight,
+            color: [255, 0, 0, 255].into(),
+            control_points: Vec::new(),
+            style: LineStyle::Aliased,
+            blend_mode: BlendMode::Replace,
+        }, &book);
+        assert_eq!(overlay.pixels_for_frame(0).count(), 4);
+
+        overlay.add_operation(&DrawingOperation::DrawShape {
+            frame: 1,
+            shape: ShapeType::Rectangle,
+            position: Point { x: 1, y: 1 },
+            size: Size { width: 3, height: 3 },
+            filled: false,
+            color: [0, 255, 0, 255].into(),
+            blend_mode: BlendMode::Replace,
+        }, &book);
+        // Outline only: the centre stays untouched
+        assert_eq!(overlay.pixels_for_frame(1).count(), 8);
+        assert!(overlay.pixels_for_frame(1).all(|(x, y, _)| (x, y) != (2, 2)));
+
+        overlay.clear();
+        assert!(overlay.is_empty());
+        // The fill stops at the opaque pixel, which is alone in its row segment
+        overlay.add_operation(&DrawingOperation::FillArea { frame: 0, x: 0, y: 0, color: [0, 0, 255, 255].into(), contiguous: true, bounds: None, blend_mode: BlendMode::Replace }, &book);
+        assert_eq!(overlay.pixels_for_frame(0).count(), 63);
+    }
+
+    #[test]
+    fn test_preview_of_a_huge_shape_stays_on_the_canvas() {
+        let book = PixelBook::new("tiny.pxl".to_string(), 16, 16, 1);
+        let mut overlay = PreviewOverlay::new();
+        for shape in [ShapeType::Rectangle, ShapeType::Oval] {
+            overlay.add_operation(&DrawingOperation::DrawShape {
+                frame: 0,
+                shape,
+                position: Point { x: 0, y: 0 },
+                size: Size { width: u16::MAX, height: u16::MAX },
+                filled: true,
+                color: [0, 255, 0, 255].into(),
+                blend_mode: BlendMode::Replace,
+            }, &book);
+        }
+        assert_eq!(overlay.pixels_for_frame(0).count(), 256);
+    }
+}
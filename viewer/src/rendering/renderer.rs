@@ -1,10 +1,31 @@
-use crate::models::{Frame, Pixel};
-use crate::rendering::{ScalingCalculator, CheckerboardPattern};
+use crate::app::{swatch_width, Guide, PaletteEntry, SWATCH_SIZE};
+use crate::models::{Frame, Pixel, Rect};
+use crate::rendering::{glyph, text_width, wrap_text, ScalingCalculator, CheckerboardPattern, GLYPH_HEIGHT, GLYPH_WIDTH};
+
+/// Font scale of HUD text
+const HUD_SCALE: usize = 2;
+/// Space between HUD text and the edge of its panel
+const HUD_PADDING: usize = 4;
+/// Space between HUD panels and the edge of the frame area
+const HUD_MARGIN: usize = 4;
+
+// Screen size of a panel holding `lines` of HUD text
+fn text_panel_size(lines: &[String]) -> (usize, usize) {
+    let width = lines.iter().map(|line| text_width(line, HUD_SCALE)).max().unwrap_or(0);
+    let height = lines.len() * (GLYPH_HEIGHT + 2) * HUD_SCALE;
+    (width + 2 * HUD_PADDING, height + 2 * HUD_PADDING)
+}
 
 pub struct Renderer {
     buffer: Vec<u32>,
     width: usize,
     height: usize,
+    // Columns on the right kept free for side panels
+    panel_width: usize,
+    // Rows on top and columns on the left kept free for rulers
+    ruler_size: usize,
+    // Rows at the bottom kept free for the color strip
+    strip_height: usize,
     checkerboard: CheckerboardPattern,
 }
 
@@ -14,6 +35,9 @@ impl Renderer {
             buffer: vec![0; width * height],
             width,
             height,
+            panel_width: 0,
+            ruler_size: 0,
+            strip_height: 0,
             checkerboard: CheckerboardPattern::new(),
         }
     }
@@ -26,6 +50,48 @@ impl Renderer {
         }
     }
     
+    /// Keeps `width` columns on the right free; frames are fitted into the rest
+    pub fn set_panel_width(&mut self, width: usize) {
+        self.panel_width = width.min(self.width);
+    }
+    
+    /// Keeps `size` rows on top and columns on the left free for rulers
+    pub fn set_ruler_size(&mut self, size: usize) {
+        self.ruler_size = size;
+    }
+    
+    /// Keeps `height` rows at the bottom free for the color strip
+    pub fn set_strip_height(&mut self, height: usize) {
+        self.strip_height = height;
+    }
+    
+    // Width frames are fitted into, before the rulers
+    fn viewport_width(&self) -> usize {
+        self.width - self.panel_width.min(self.width)
+    }
+    
+    // Height frames are fitted into, before the rulers
+    fn viewport_height(&self) -> usize {
+        self.height - self.strip_height.min(self.height)
+    }
+    
+    /// Scale and screen offset of frames of the given size, as used by
+    /// [`Renderer::render_frame`]
+    pub fn frame_layout(&self, image_width: u16, image_height: u16) -> (u32, i32, i32) {
+        let (scale, offset_x, offset_y) = ScalingCalculator::calculate_scale_and_offset(
+            image_width,
+            image_height,
+            self.viewport_width().saturating_sub(self.ruler_size),
+            self.viewport_height().saturating_sub(self.ruler_size),
+        );
+        (scale, offset_x + self.ruler_size as i32, offset_y + self.ruler_size as i32)
+    }
+    
+    /// Width and height of the buffer, in screen pixels
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+    
     pub fn get_buffer(&self) -> &[u32] {
         &self.buffer
     }
@@ -37,12 +103,7 @@ impl Renderer {
     pub fn render_frame(&mut self, frame: &Frame, image_width: u16, image_height: u16) {
         self.clear();
         
-        let (scale, offset_x, offset_y) = ScalingCalculator::calculate_scale_and_offset(
-            image_width,
-            image_height,
-            self.width,
-            self.height,
-        );
+        let (scale, offset_x, offset_y) = self.frame_layout(image_width, image_height);
         
         // Iterate through each pixel in the image
         for y in 0..image_height {
@@ -54,6 +115,329 @@ impl Renderer {
         }
     }
     
+    /// Blends `pixels` over the frame drawn by the last [`Renderer::render_frame`],
+    /// scaling each pixel's alpha by `opacity`
+    pub fn render_overlay(
+        &mut self,
+        pixels: impl Iterator<Item = (u16, u16, [u8; 4])>,
+        image_width: u16,
+        image_height: u16,
+        opacity: u8,
+    ) {
+        let (scale, offset_x, offset_y) = self.frame_layout(image_width, image_height);
+        
+        for (x, y, [r, g, b, a]) in pixels {
+            let (screen_x, screen_y) = ScalingCalculator::pixel_to_screen_coords(x, y, scale, offset_x, offset_y);
+            if screen_x < 0 || screen_y < 0 {
+                continue;
+            }
+            
+            let color = Pixel::new(r, g, b, 255).to_rgba32();
+            let alpha = (a as u32 * opacity as u32 / 255) as u8;
+            for dy in 0..scale as usize {
+                for dx in 0..scale as usize {
+                    let px = screen_x as usize + dx;
+                    let py = screen_y as usize + dy;
+                    if px < self.viewport_width() && py < self.viewport_height() {
+                        let index = py * self.width + px;
+                        self.buffer[index] = self.blend_colors(self.buffer[index], color, alpha);
+                    }
+                }
+            }
+        }
+    }
+    
+    /// Draws one row per entry down the right-hand panel: a swatch, then a
+    /// bar whose length shows the color's share of the most used one
+    pub fn render_palette(&mut self, entries: &[PaletteEntry]) {
+        let left = self.viewport_width();
+        for px in left..self.width {
+            for py in 0..self.height {
+                self.buffer[py * self.width + px] = 0x202020;
+            }
+        }
+        
+        let most_used = entries.first().map_or(1, |entry| entry.count.max(1));
+        let bar_space = self.panel_width.saturating_sub(SWATCH_SIZE + 4);
+        for (row, entry) in entries.iter().enumerate() {
+            let top = row * SWATCH_SIZE;
+            if top + SWATCH_SIZE > self.height {
+                break;
+            }
+            
+            let [r, g, b, a] = entry.color;
+            let color = Pixel::new(r, g, b, 255).to_rgba32();
+            let bar = (bar_space * entry.count).div_ceil(most_used);
+            for dy in 1..SWATCH_SIZE - 1 {
+                let py = top + dy;
+                for dx in 1..SWATCH_SIZE - 1 {
+                    let px = left + dx;
+                    let bg_color = self.checkerboard.get_color_at(px as u32, py as u32, 1);
+                    self.buffer[py * self.width + px] = self.blend_colors(bg_color, color, a);
+                }
+                if (SWATCH_SIZE / 2 - 2..SWATCH_SIZE / 2 + 2).contains(&dy) {
+                    for dx in 0..bar {
+                        self.buffer[py * self.width + left + SWATCH_SIZE + 2 + dx] = 0x808080;
+                    }
+                }
+            }
+        }
+    }
+    
+    /// Draws `colors` as swatches along the bottom of the frame area,
+    /// outlining the one matching `current`
+    pub fn render_color_strip(&mut self, colors: &[[u8; 4]], current: Option<[u8; 4]>) {
+        let top = self.viewport_height();
+        let right = self.viewport_width();
+        for py in top..self.height {
+            for px in 0..right {
+                self.buffer[py * self.width + px] = 0x202020;
+            }
+        }
+        
+        let width = swatch_width(colors.len(), right);
+        for (index, &[r, g, b, a]) in colors.iter().enumerate() {
+            let left = index * width;
+            if left + width > right {
+                break;
+            }
+            let color = Pixel::new(r, g, b, 255).to_rgba32();
+            let outlined = current == Some([r, g, b, a]);
+            for py in top + 1..self.height.saturating_sub(1) {
+                for px in left..left + width {
+                    let edge = px == left || px + 1 == left + width || py == top + 1 || py + 2 == self.height;
+                    self.buffer[py * self.width + px] = if outlined && edge {
+                        0xFFFFFF
+                    } else {
+                        let bg_color = self.checkerboard.get_color_at(px as u32, py as u32, 1);
+                        self.blend_colors(bg_color, color, a)
+                    };
+                }
+            }
+        }
+    }
+    
+    /// Draws rulers along the top and left edges with a tick on every pixel
+    /// boundary that has room for one, labelled every 8 pixels
+    pub fn render_rulers(&mut self, image_width: u16, image_height: u16) {
+        let (scale, offset_x, offset_y) = self.frame_layout(image_width, image_height);
+        let size = self.ruler_size.min(self.height);
+        let right = self.viewport_width();
+        
+        for py in 0..self.viewport_height() {
+            for px in 0..right {
+                if py < size || px < size {
+                    self.buffer[py * self.width + px] = 0x303030;
+                }
+            }
+        }
+        
+        for (count, offset, horizontal) in [(image_width, offset_x, true), (image_height, offset_y, false)] {
+            for boundary in 0..=count {
+                let labelled = boundary % 8 == 0;
+                if !labelled && scale < 4 {
+                    continue;
+                }
+                let position = offset + (boundary as u32 * scale) as i32;
+                let length = if labelled { size } else { size / 3 };
+                for step in size - length..size {
+                    let (px, py) = if horizontal { (position, step as i32) } else { (step as i32, position) };
+                    if px >= 0 && py >= 0 && (px as usize) < right && (py as usize) < self.viewport_height() {
+                        self.buffer[py as usize * self.width + px as usize] = 0xA0A0A0;
+                    }
+                }
+                if labelled && position >= 0 {
+                    let (x, y) = if horizontal { (position as usize + 2, 1) } else { (1, position as usize + 2) };
+                    if x + text_width(&boundary.to_string(), 1) <= right {
+                        self.draw_text(x, y, &boundary.to_string(), 0xE0E0E0, 1);
+                    }
+                }
+            }
+        }
+    }
+    
+    /// Draws each guide as a line across the frame area
+    pub fn render_guides(&mut self, guides: &[Guide], image_width: u16, image_height: u16) {
+        let (scale, offset_x, offset_y) = self.frame_layout(image_width, image_height);
+        let right = self.viewport_width();
+        
+        for &guide in guides {
+            match guide {
+                Guide::Horizontal(y) => {
+                    let py = offset_y + (y as u32 * scale) as i32;
+                    if py >= self.ruler_size as i32 && (py as usize) < self.viewport_height() {
+                        for px in self.ruler_size..right {
+                            self.buffer[py as usize * self.width + px] = 0x00C0FF;
+                        }
+                    }
+                }
+                Guide::Vertical(x) => {
+                    let px = offset_x + (x as u32 * scale) as i32;
+                    if px >= self.ruler_size as i32 && (px as usize) < right {
+                        for py in self.ruler_size..self.viewport_height() {
+                            self.buffer[py * self.width + px as usize] = 0x00C0FF;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    
+    /// Outlines the selected pixels with a dashed black and white border
+    pub fn render_selection(&mut self, rect: &Rect, image_width: u16, image_height: u16) {
+        let (scale, offset_x, offset_y) = self.frame_layout(image_width, image_height);
+        let left = offset_x + (rect.x as u32 * scale) as i32;
+        let top = offset_y + (rect.y as u32 * scale) as i32;
+        let right = left + (rect.width as u32 * scale) as i32 - 1;
+        let bottom = top + (rect.height as u32 * scale) as i32 - 1;
+        
+        let plot = |renderer: &mut Self, x: i32, y: i32, step: i32| {
+            if x >= 0 && y >= 0 && (x as usize) < renderer.viewport_width() && (y as usize) < renderer.height {
+                let color = if (step / 4) % 2 == 0 { 0xFFFFFF } else { 0x000000 };
+                renderer.buffer[y as usize * renderer.width + x as usize] = color;
+            }
+        };
+        for x in left..=right {
+            plot(self, x, top, x - left);
+            plot(self, x, bottom, x - left);
+        }
+        for y in top..=bottom {
+            plot(self, left, y, y - top);
+            plot(self, right, y, y - top);
+        }
+    }
+    
+    /// Draws `text` with its top-left corner at (x, y), each font pixel
+    /// `scale` screen pixels wide; anything past the window edge is cut off
+    pub fn draw_text(&mut self, x: usize, y: usize, text: &str, color: u32, scale: usize) {
+        for (index, c) in text.chars().enumerate() {
+            let left = x + index * (GLYPH_WIDTH + 1) * scale;
+            for (row, bits) in glyph(c).iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    if bits & (0b100 >> column) == 0 {
+                        continue;
+                    }
+                    for dy in 0..scale {
+                        for dx in 0..scale {
+                            let px = left + column * scale + dx;
+                            let py = y + row * scale + dy;
+                            if px < self.width && py < self.height {
+                                self.buffer[py * self.width + px] = color;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    
+    /// Lists `lines` over the bottom of the frame area on a darkened backdrop
+    pub fn render_event_log(&mut self, lines: &[String]) {
+        const SCALE: usize = 2;
+        const PADDING: usize = 4;
+        let line_height = (GLYPH_HEIGHT + 2) * SCALE;
+        let height = (lines.len() * line_height + 2 * PADDING).min(self.viewport_height());
+        let top = self.viewport_height() - height;
+        
+        for py in top..self.viewport_height() {
+            for px in 0..self.viewport_width() {
+                let index = py * self.width + px;
+                self.buffer[index] = self.blend_colors(self.buffer[index], 0x000000, 192);
+            }
+        }
+        
+        let max_chars = self.viewport_width().saturating_sub(2 * PADDING) / ((GLYPH_WIDTH + 1) * SCALE);
+        for (row, line) in lines.iter().enumerate() {
+            let text: String = line.chars().take(max_chars).collect();
+            self.draw_text(PADDING, top + PADDING + row * line_height, &text, 0xE0E0E0, SCALE);
+        }
+    }
+    
+    /// Draws a status line in the top-left corner of the frame area
+    pub fn render_status(&mut self, text: &str) {
+        let left = self.ruler_size + HUD_MARGIN;
+        let max_chars = self.viewport_width().saturating_sub(left + HUD_MARGIN + 2 * HUD_PADDING) / ((GLYPH_WIDTH + 1) * HUD_SCALE);
+        let line: String = text.chars().take(max_chars).collect();
+        self.render_text_panel(left, self.ruler_size + HUD_MARGIN, &[line], 0xE0E0E0, None);
+    }
+    
+    /// Draws a small square of `color` in the top-right corner of the frame area
+    pub fn render_indicator(&mut self, color: u32) {
+        const SIZE: usize = 8;
+        let right = self.viewport_width().saturating_sub(HUD_MARGIN);
+        let top = self.ruler_size + HUD_MARGIN;
+        for py in top..(top + SIZE).min(self.viewport_height()) {
+            for px in right.saturating_sub(SIZE)..right {
+                self.buffer[py * self.width + px] = color;
+            }
+        }
+    }
+    
+    /// Draws `message` wrapped across the bottom of the frame area, above
+    /// anything else drawn there
+    pub fn render_message(&mut self, message: &str, color: u32) {
+        let max_chars = self.viewport_width().saturating_sub(2 * HUD_MARGIN + 2 * HUD_PADDING) / ((GLYPH_WIDTH + 1) * HUD_SCALE);
+        let lines = wrap_text(message, max_chars);
+        let (_, height) = text_panel_size(&lines);
+        let top = self.viewport_height().saturating_sub(height + HUD_MARGIN);
+        self.render_text_panel(HUD_MARGIN, top, &lines, color, None);
+    }
+    
+    /// Draws `lines` on a panel centred in the frame area
+    pub fn render_help(&mut self, lines: &[&str]) {
+        let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        self.render_centered_panel(&lines, None);
+    }
+    
+    /// Lines of HUD text that fit in the window above one another,
+    /// leaving room for a title line
+    pub fn list_rows(&self) -> usize {
+        let line_height = (GLYPH_HEIGHT + 2) * HUD_SCALE;
+        (self.viewport_height().saturating_sub(2 * HUD_MARGIN + 2 * HUD_PADDING) / line_height).saturating_sub(1).max(1)
+    }
+    
+    /// Draws `title` over `items` on a panel centred in the frame area,
+    /// with the item at `selected` highlighted
+    pub fn render_list(&mut self, title: &str, items: &[String], selected: usize) {
+        let max_chars = self.viewport_width().saturating_sub(2 * HUD_MARGIN + 2 * HUD_PADDING) / ((GLYPH_WIDTH + 1) * HUD_SCALE);
+        let lines: Vec<String> = std::iter::once(title)
+            .chain(items.iter().map(String::as_str))
+            .map(|line| line.chars().take(max_chars).collect())
+            .collect();
+        self.render_centered_panel(&lines, Some(selected + 1));
+    }
+    
+    fn render_centered_panel(&mut self, lines: &[String], highlight: Option<usize>) {
+        let (width, height) = text_panel_size(lines);
+        let left = self.viewport_width().saturating_sub(width) / 2;
+        let top = self.viewport_height().saturating_sub(height) / 2;
+        self.render_text_panel(left, top, lines, 0xE0E0E0, highlight);
+    }
+    
+    // Darkens a box fitted around `lines` and draws them inside it, the line
+    // at `highlight` on a lighter bar
+    fn render_text_panel(&mut self, left: usize, top: usize, lines: &[String], color: u32, highlight: Option<usize>) {
+        let (width, height) = text_panel_size(lines);
+        let right = (left + width).min(self.viewport_width());
+        let line_height = (GLYPH_HEIGHT + 2) * HUD_SCALE;
+        for py in top..(top + height).min(self.viewport_height()) {
+            let row = py.checked_sub(top + HUD_PADDING).map(|offset| offset / line_height);
+            let highlighted = row.is_some() && row == highlight && py < top + height - HUD_PADDING;
+            for px in left..right {
+                let index = py * self.width + px;
+                self.buffer[index] = if highlighted {
+                    0x305080
+                } else {
+                    self.blend_colors(self.buffer[index], 0x000000, 192)
+                };
+            }
+        }
+        
+        for (row, line) in lines.iter().enumerate() {
+            self.draw_text(left + HUD_PADDING, top + HUD_PADDING + row * line_height, line, color, HUD_SCALE);
+        }
+    }
+    
     fn render_pixel(&mut self, x: u16, y: u16, pixel: &Pixel, scale: u32, offset_x: i32, offset_y: i32) {
         let (screen_x, screen_y) = ScalingCalculator::pixel_to_screen_coords(x, y, scale, offset_x, offset_y);
         
@@ -65,7 +449,7 @@ impl Renderer {
         let screen_x = screen_x as usize;
         let screen_y = screen_y as usize;
         
-        if screen_x + scale as usize > self.width || screen_y + scale as usize > self.height {
+        if screen_x + scale as usize > self.viewport_width() || screen_y + scale as usize > self.viewport_height() {
             return;
         }
         
@@ -31,6 +31,21 @@ impl ScalingCalculator {
         let screen_y = offset_y + (pixel_y as u32 * scale) as i32;
         (screen_x, screen_y)
     }
+    
+    /// The pixel (0 to `max` exclusive) covering a screen coordinate along one
+    /// axis, if any does
+    pub fn screen_to_pixel(screen: i32, offset: i32, scale: u32, max: u16) -> Option<u16> {
+        let pixel = (screen - offset).div_euclid(scale.max(1) as i32);
+        (0..max as i32).contains(&pixel).then_some(pixel as u16)
+    }
+    
+    /// The pixel boundary (0 to `max` inclusive) nearest to a screen coordinate
+    /// along one axis
+    pub fn screen_to_pixel_boundary(screen: i32, offset: i32, scale: u32, max: u16) -> u16 {
+        let scale = scale.max(1) as i32;
+        let boundary = (screen - offset + scale / 2).div_euclid(scale);
+        boundary.clamp(0, max as i32) as u16
+    }
 }
 
 #[cfg(test)]
@@ -56,4 +71,22 @@ mod tests {
         assert_eq!(offset_x, 2);
         assert_eq!(offset_y, 2);
     }
-} 
\ No newline at end of file
+    
+    #[test]
+    fn test_screen_to_pixel_boundary() {
+        // 8x scale starting at x=20: boundary 2 sits at x=36
+        assert_eq!(ScalingCalculator::screen_to_pixel_boundary(35, 20, 8, 16), 2);
+        assert_eq!(ScalingCalculator::screen_to_pixel_boundary(39, 20, 8, 16), 2);
+        assert_eq!(ScalingCalculator::screen_to_pixel_boundary(41, 20, 8, 16), 3);
+        assert_eq!(ScalingCalculator::screen_to_pixel_boundary(0, 20, 8, 16), 0);
+        assert_eq!(ScalingCalculator::screen_to_pixel_boundary(500, 20, 8, 16), 16);
+    }
+    
+    #[test]
+    fn test_screen_to_pixel() {
+        assert_eq!(ScalingCalculator::screen_to_pixel(20, 20, 8, 16), Some(0));
+        assert_eq!(ScalingCalculator::screen_to_pixel(43, 20, 8, 16), Some(2));
+        assert_eq!(ScalingCalculator::screen_to_pixel(19, 20, 8, 16), None);
+        assert_eq!(ScalingCalculator::screen_to_pixel(148, 20, 8, 16), None);
+    }
+}
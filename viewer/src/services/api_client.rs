@@ -1,4 +1,4 @@
-use crate::models::{PixelBook, PixelBookInfo};
+use crate::models::{filename_segment, FrameRange, PixelBook, PixelBookInfo};
 use reqwest::Client;
 use std::error::Error;
 
@@ -39,7 +39,7 @@ impl ApiClient {
     }
     
     pub async fn get_book(&self, filename: &str) -> Result<PixelBook, Box<dyn Error + Send + Sync>> {
-        let url = format!("{}/books/{}", self.base_url, filename);
+        let url = format!("{}/books/{}", self.base_url, filename_segment(filename));
         let response = self.client.get(&url).send().await?;
         
         if !response.status().is_success() {
@@ -50,6 +50,35 @@ impl ApiClient {
         Ok(book)
     }
     
+    /// Only the frames in `range`, plus the book's total frame count
+    pub async fn get_book_frames(&self, filename: &str, range: &FrameRange) -> Result<(PixelBook, usize), Box<dyn Error + Send + Sync>> {
+        let url = format!("{}/books/{}", self.base_url, filename_segment(filename));
+        let response = self.client.get(&url).query(&[("frames", range.to_string())]).send().await?;
+        
+        if !response.status().is_success() {
+            return Err(format!("Server error: {}", response.status()).into());
+        }
+        
+        let frame_count = response.headers().get("X-Pixl-Frame-Count")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok());
+        let book: PixelBook = response.json().await?;
+        let frame_count = frame_count.unwrap_or(book.frames.len());
+        Ok((book, frame_count))
+    }
+    
+    /// Frame `frame` encoded as a PNG by the server
+    pub async fn export_frame_png(&self, filename: &str, frame: usize) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let url = format!("{}/books/{}/export/png", self.base_url, filename_segment(filename));
+        let response = self.client.get(&url).query(&[("frame", frame)]).send().await?;
+        
+        if !response.status().is_success() {
+            return Err(format!("Server error: {}", response.status()).into());
+        }
+        
+        Ok(response.bytes().await?.to_vec())
+    }
+    
     pub async fn get_path(&self) -> Result<String, Box<dyn Error + Send + Sync>> {
         let url = format!("{}/path", self.base_url);
         let response = self.client.get(&url).send().await?;
@@ -1,17 +1,49 @@
-use crate::models::events::PixelBookEvent;
+use crate::models::{filename_segment, PixelBookEvent};
 use reqwest::Client;
 use std::error::Error;
 use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
+use tokio::task::AbortHandle;
 use futures_util::StreamExt;
 
+/// Wait before the first reconnection attempt; it doubles with every failure
+pub const RECONNECT_BASE_DELAY: Duration = Duration::from_millis(500);
+/// Longest wait between reconnection attempts
+pub const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// How the event stream of the followed book is doing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// No book is being followed
+    #[default]
+    Disconnected,
+    /// Opening the stream for the first time
+    Connecting,
+    /// Receiving live updates
+    Connected,
+    /// The stream dropped; waiting before attempt `attempt` to reopen it
+    Reconnecting { attempt: u32 },
+}
+
+/// Wait before reconnection attempt `attempt` (counting from 1)
+pub fn reconnect_delay(attempt: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+    RECONNECT_BASE_DELAY.saturating_mul(factor).min(RECONNECT_MAX_DELAY)
+}
+
 #[derive(Clone)]
 pub struct EventClient {
     base_url: String,
     client: Client,
     current_filename: Option<String>,
     event_buffer: Arc<Mutex<VecDeque<PixelBookEvent>>>,
+    state: Arc<std::sync::Mutex<ConnectionState>>,
+    // Set when the stream comes back after dropping, as events may have been missed
+    reconnected: Arc<AtomicBool>,
+    listener: Option<AbortHandle>,
 }
 
 impl EventClient {
@@ -21,40 +53,70 @@ impl EventClient {
             client: Client::new(),
             current_filename: None,
             event_buffer: Arc::new(Mutex::new(VecDeque::new())),
+            state: Arc::new(std::sync::Mutex::new(ConnectionState::Disconnected)),
+            reconnected: Arc::new(AtomicBool::new(false)),
+            listener: None,
         }
     }
     
+    /// Follows the events of `filename` in the background, reopening the
+    /// stream with exponential backoff whenever it drops
     pub async fn connect(&mut self, filename: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.stop_listener();
+        self.event_buffer.lock().await.clear();
         self.current_filename = Some(filename.to_string());
+        self.set_state(ConnectionState::Connecting);
         
         // Start SSE connection in background
-        let url = format!("{}/books/{}/events", self.base_url, filename);
+        let url = format!("{}/books/{}/events", self.base_url, filename_segment(filename));
         let client = self.client.clone();
         let event_buffer = self.event_buffer.clone();
+        let state = self.state.clone();
+        let reconnected = self.reconnected.clone();
         let filename_clone = filename.to_string();
         
         println!("🔌 Connecting to SSE endpoint: {}", url);
         
-        tokio::spawn(async move {
-            match Self::sse_listener(client, url, event_buffer, filename_clone).await {
-                Ok(_) => println!("📡 SSE connection closed"),
-                Err(e) => println!("❌ SSE connection error: {}", e),
+        let task = tokio::spawn(async move {
+            let mut attempt = 0;
+            let mut resumed = false;
+            loop {
+                let resumed_flag = resumed.then_some(reconnected.as_ref());
+                match Self::sse_listener(&client, &url, &event_buffer, &state, resumed_flag, &filename_clone).await {
+                    Ok(_) => println!("📡 SSE connection closed"),
+                    Err(e) => println!("❌ SSE connection error: {}", e),
+                }
+                
+                // A stream that got going starts the backoff over
+                if *state.lock().unwrap() == ConnectionState::Connected {
+                    attempt = 0;
+                    resumed = true;
+                }
+                attempt += 1;
+                *state.lock().unwrap() = ConnectionState::Reconnecting { attempt };
+                let delay = reconnect_delay(attempt);
+                println!("🔁 Reconnecting to {} in {:?} (attempt {})", filename_clone, delay, attempt);
+                tokio::time::sleep(delay).await;
             }
         });
+        self.listener = Some(task.abort_handle());
         
         Ok(())
     }
     
     async fn sse_listener(
-        client: Client,
-        url: String,
-        event_buffer: Arc<Mutex<VecDeque<PixelBookEvent>>>,
-        filename: String,
+        client: &Client,
+        url: &str,
+        event_buffer: &Mutex<VecDeque<PixelBookEvent>>,
+        state: &std::sync::Mutex<ConnectionState>,
+        // Raised once the stream is open again, when this reopens a dropped one
+        reconnected: Option<&AtomicBool>,
+        filename: &str,
     ) -> Result<(), Box<dyn Error + Send + Sync>> {
         println!("🎯 Starting SSE listener for: {}", filename);
         
         let response = client
-            .get(&url)
+            .get(url)
             .header("Accept", "text/event-stream")
             .header("Cache-Control", "no-cache")
             .send()
@@ -65,6 +127,10 @@ impl EventClient {
         if !response.status().is_success() {
             return Err(format!("SSE connection failed: {}", response.status()).into());
         }
+        *state.lock().unwrap() = ConnectionState::Connected;
+        if let Some(reconnected) = reconnected {
+            reconnected.store(true, Ordering::SeqCst);
+        }
         
         let mut stream = response.bytes_stream();
         let mut buffer = String::new();
@@ -121,10 +187,33 @@ impl EventClient {
     }
     
     pub async fn disconnect(&mut self) {
+        self.stop_listener();
         self.current_filename = None;
+        self.set_state(ConnectionState::Disconnected);
         println!("🔌 Disconnected from real-time updates");
     }
     
+    fn stop_listener(&mut self) {
+        if let Some(listener) = self.listener.take() {
+            listener.abort();
+        }
+        self.reconnected.store(false, Ordering::SeqCst);
+    }
+    
+    fn set_state(&self, state: ConnectionState) {
+        *self.state.lock().unwrap() = state;
+    }
+    
+    pub fn connection_state(&self) -> ConnectionState {
+        *self.state.lock().unwrap()
+    }
+    
+    /// Whether the stream came back after dropping since the last call, in
+    /// which case the book should be reloaded to catch up on missed changes
+    pub fn take_reconnected(&self) -> bool {
+        self.reconnected.swap(false, Ordering::SeqCst)
+    }
+    
     pub async fn poll_events(&self) -> Result<Option<Vec<PixelBookEvent>>, Box<dyn Error + Send + Sync>> {
         let mut events = self.event_buffer.lock().await;
         if events.is_empty() {
@@ -136,10 +225,24 @@ impl EventClient {
     }
     
     pub fn is_connected(&self) -> bool {
-        self.current_filename.is_some()
+        self.connection_state() == ConnectionState::Connected
     }
     
     pub fn current_filename(&self) -> Option<&str> {
         self.current_filename.as_deref()
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_reconnect_delay_backs_off() {
+        assert_eq!(reconnect_delay(1), Duration::from_millis(500));
+        assert_eq!(reconnect_delay(2), Duration::from_secs(1));
+        assert_eq!(reconnect_delay(4), Duration::from_secs(4));
+        assert_eq!(reconnect_delay(7), RECONNECT_MAX_DELAY);
+        assert_eq!(reconnect_delay(100), RECONNECT_MAX_DELAY);
+    }
+}